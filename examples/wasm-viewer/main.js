@@ -0,0 +1,106 @@
+import init, { WasmGrid } from './pkg/petra_grid_wasm_viewer.js';
+
+const drop = document.getElementById('drop');
+const hint = document.getElementById('hint');
+const canvas = document.getElementById('canvas');
+const probe = document.getElementById('probe');
+const colormap = document.getElementById('colormap');
+const hillshade = document.getElementById('hillshade');
+const error = document.getElementById('error');
+const metadata = document.getElementById('metadata');
+
+let grid = null;
+
+function render() {
+  if (!grid) {
+    return;
+  }
+  canvas.width = grid.width;
+  canvas.height = grid.height;
+  const pixels = grid.render(colormap.value, hillshade.checked);
+  const image = new ImageData(new Uint8ClampedArray(pixels.buffer),
+    grid.width, grid.height);
+  canvas.getContext('2d').putImageData(image, 0, 0);
+}
+
+function showMetadata(name) {
+  const rows = [
+    ['file', name],
+    ['name', grid.name],
+    ['type', grid.triangular ? 'triangular' : 'rectangular'],
+    ['rows x columns', `${grid.rows} x ${grid.columns}`],
+    ['triangles', grid.nTriangles],
+    ['x', `${grid.xmin} to ${grid.xmax} by ${grid.xstep}`],
+    ['y', `${grid.ymin} to ${grid.ymax} by ${grid.ystep}`],
+    ['z', `${grid.zmin} to ${grid.zmax}`],
+    ['xy units', grid.xyUnits],
+    ['z units', grid.zUnits],
+    ['created', grid.createdDate],
+    ['source data', grid.sourceData],
+    ['projection', grid.projection],
+    ['datum', grid.datum],
+  ];
+  metadata.replaceChildren(...rows.map(([k, v]) => {
+    const tr = document.createElement('tr');
+    for (const text of [k, v]) {
+      const td = document.createElement('td');
+      td.textContent = text;
+      tr.appendChild(td);
+    }
+    return tr;
+  }));
+}
+
+async function load(file) {
+  try {
+    const bytes = new Uint8Array(await file.arrayBuffer());
+    if (grid) {
+      grid.free();
+    }
+    grid = new WasmGrid(bytes);
+    error.textContent = '';
+    hint.hidden = true;
+    canvas.hidden = false;
+    showMetadata(file.name);
+    render();
+  } catch (e) {
+    error.textContent = `Error reading ${file.name}: ${e}`;
+  }
+}
+
+drop.addEventListener('dragover', e => {
+  e.preventDefault();
+  drop.classList.add('hover');
+});
+
+drop.addEventListener('dragleave', () => drop.classList.remove('hover'));
+
+drop.addEventListener('drop', e => {
+  e.preventDefault();
+  drop.classList.remove('hover');
+  if (e.dataTransfer.files.length > 0) {
+    load(e.dataTransfer.files[0]);
+  }
+});
+
+canvas.addEventListener('mousemove', e => {
+  if (!grid) {
+    return;
+  }
+  const rect = canvas.getBoundingClientRect();
+  const u = (e.clientX - rect.left) / rect.width * grid.width;
+  const v = (e.clientY - rect.top) / rect.height * grid.height;
+  const x = grid.xmin + (u - 0.5) * grid.xstep;
+  const y = grid.ymin + (grid.height - v - 0.5) * grid.ystep;
+  const z = grid.sample(x, y);
+  const zText = z === undefined ? '(outside grid)'
+    : Number.isNaN(z) ? '(missing)' : z.toFixed(3);
+  probe.textContent = `x = ${x.toFixed(2)}, y = ${y.toFixed(2)}, z = ${zText}`;
+  probe.hidden = false;
+});
+
+canvas.addEventListener('mouseleave', () => probe.hidden = true);
+colormap.addEventListener('change', render);
+hillshade.addEventListener('change', render);
+
+await init();
@@ -0,0 +1,141 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! node-by-node arithmetic on grids, keeping track of the quantities their
+//! *z* values measure (see [Quantity])
+
+use ndarray::s;
+
+use crate::{Error, Grid, GridData, NullPolicy, Quantity, invariants};
+
+impl Grid {
+    /// combine this grid with another on the same lattice (see
+    /// [Grid::is_coregistered]), node by node, using `f`, with missing
+    /// values treated according to a policy (see [NullPolicy::combine])
+    ///
+    /// the result keeps this grid's descriptive metadata, but has no
+    /// quantity, since `f` could mean anything (see [Grid::with_quantity]);
+    /// fails with [Error::NotRectangular] for triangular grids, or
+    /// [Error::InvalidArgument] if the grids aren't coregistered
+    pub fn zip_with<F: Fn(f64, f64) -> f64>(&self, other: &Grid,
+      policy: &NullPolicy, f: F) -> Result<Grid, Error> {
+        let (a, b) = (self.rectangular()?, other.rectangular()?);
+        let tolerance = invariants::SPEC_REL_TOLERANCE
+          * self.xstep.abs().min(self.ystep.abs());
+        if !self.is_coregistered(other, tolerance) {
+            return Err(Error::InvalidArgument(format!(
+              "can't combine grids on different lattices ({} x {} from \
+               ({}, {}) and {} x {} from ({}, {}))", self.columns, self.rows,
+               self.xmin, self.ymin, other.columns, other.rows, other.xmin,
+               other.ymin)));
+        }
+
+        let mut data = a.to_owned();
+        data.zip_mut_with(b, |a, &b| *a = policy.combine(*a, b, &f));
+        let mut grid = self.clone();
+        grid.data = GridData::Rectangular(data.into_shared());
+        let stats = grid.data.stats();
+        grid.zmin = stats.min;
+        grid.zmax = stats.max;
+        grid.quantity = None;
+        Ok(grid)
+    }
+
+    /// add another grid on the same lattice to this one (see
+    /// [Grid::zip_with]); the result measures the sum of the grids'
+    /// quantities (see [Quantity::sum]), so fails if both are known and
+    /// have different units
+    pub fn add(&self, other: &Grid, policy: &NullPolicy)
+      -> Result<Grid, Error> {
+        let quantity = self.derived_quantity(other, Quantity::sum)?;
+        let mut grid = self.zip_with(other, policy, |a, b| a + b)?;
+        grid.quantity = quantity;
+        Ok(grid)
+    }
+
+    /// subtract another grid on the same lattice from this one (see
+    /// [Grid::zip_with]), e.g. a base from a top for a thickness; the result
+    /// measures the difference of the grids' quantities (see
+    /// [Quantity::difference]), so fails if both are known and have
+    /// different units
+    pub fn subtract(&self, other: &Grid, policy: &NullPolicy)
+      -> Result<Grid, Error> {
+        let quantity = self.derived_quantity(other, Quantity::difference)?;
+        let mut grid = self.zip_with(other, policy, |a, b| a - b)?;
+        grid.quantity = quantity;
+        Ok(grid)
+    }
+
+    /// multiply this grid by another on the same lattice (see
+    /// [Grid::zip_with]); the result measures the product of the grids'
+    /// quantities (see [Quantity::product])
+    pub fn multiply(&self, other: &Grid, policy: &NullPolicy)
+      -> Result<Grid, Error> {
+        let quantity = self.derived_quantity(other,
+          |a, b| Ok(a.product(b)))?;
+        let mut grid = self.zip_with(other, policy, |a, b| a * b)?;
+        grid.quantity = quantity;
+        Ok(grid)
+    }
+
+    /// divide this grid by another on the same lattice (see
+    /// [Grid::zip_with]); the result measures the quotient of the grids'
+    /// quantities (see [Quantity::quotient]). division by zero gives
+    /// infinite (or, for 0 / 0, missing) values
+    pub fn divide(&self, other: &Grid, policy: &NullPolicy)
+      -> Result<Grid, Error> {
+        let quantity = self.derived_quantity(other,
+          |a, b| Ok(a.quotient(b)))?;
+        let mut grid = self.zip_with(other, policy, |a, b| a / b)?;
+        grid.quantity = quantity;
+        Ok(grid)
+    }
+
+    /// multiply every *z* value by a constant (e.g. a cell area, or a unit
+    /// conversion factor); the result measures this grid's quantity in
+    /// scaled units (see [Quantity::scaled]), which can be renamed with
+    /// [Grid::with_quantity]
+    pub fn scale(&self, factor: f64) -> Grid {
+        let mut grid = self.clone();
+        match &mut grid.data {
+            GridData::Rectangular(arr) => arr.mapv_inplace(|z| z * factor),
+            GridData::Triangular(arr) => arr.slice_mut(s![.., .., 2])
+              .mapv_inplace(|z| z * factor),
+        }
+        let stats = grid.data.stats();
+        grid.zmin = stats.min;
+        grid.zmax = stats.max;
+        grid.quantity = self.z_quantity().map(|q| q.scaled(factor));
+        grid
+    }
+
+    // the quantity measured by combining this grid with another, if both
+    // are known
+    fn derived_quantity<F>(&self, other: &Grid, combine: F)
+      -> Result<Option<Quantity>, Error>
+      where F: Fn(&Quantity, &Quantity) -> Result<Quantity, Error>,
+    {
+        match (self.z_quantity(), other.z_quantity()) {
+            (Some(a), Some(b)) => combine(&a, &b).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! conversion of grids to Apache Arrow record batches, for Arrow-native
+//! pipelines and IPC streams
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow_array::{
+    ArrayRef,
+    Float64Array,
+    Int32Array,
+    RecordBatch,
+    StringArray,
+    TimestampMicrosecondArray,
+    UInt32Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+use crate::{Grid, GridData, export::crs_description};
+
+/// a grid as Arrow record batches; see [Grid::to_arrow]
+#[derive(Clone, Debug)]
+pub struct ArrowGrid {
+    /// one row per point: each node of a rectangular grid (south to north,
+    /// then west to east) or each vertex of each triangle of a triangular
+    /// grid, with float64 `x`, `y`, and (nullable, null where missing) `z`
+    /// columns
+    pub points: RecordBatch,
+
+    /// a single row of the grid's metadata: its name, source data,
+    /// projection, datum, units, EPSG code (null if unknown; see
+    /// [Grid::epsg]), creation date, shape, and bounds
+    pub metadata: RecordBatch,
+}
+
+impl Grid {
+    /// convert this grid to Arrow record batches of its points and metadata
+    ///
+    /// both batches' schemas carry the grid's name and a description of its
+    /// coordinate system as schema metadata (under `name` and `crs`)
+    pub fn to_arrow(&self) -> ArrowGrid {
+        let schema_metadata = [
+            ("name".to_string(), self.name.clone()),
+            ("crs".to_string(), crs_description(self)),
+        ].into_iter().collect::<HashMap<_, _>>();
+
+        let n = match &self.data {
+            GridData::Rectangular(arr) => arr.len(),
+            GridData::Triangular(arr) => arr.dim().0 * 3,
+        };
+        let mut xs = Vec::with_capacity(n);
+        let mut ys = Vec::with_capacity(n);
+        let mut zs = Vec::with_capacity(n);
+        for (x, y, z) in self.points() {
+            xs.push(x);
+            ys.push(y);
+            zs.push(if z.is_nan() { None } else { Some(z) });
+        }
+        let points_schema = Schema::new(vec![
+            Field::new("x", DataType::Float64, false),
+            Field::new("y", DataType::Float64, false),
+            Field::new("z", DataType::Float64, true),
+        ]).with_metadata(schema_metadata.clone());
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Float64Array::from(xs)),
+            Arc::new(Float64Array::from(ys)),
+            Arc::new(Float64Array::from(zs)),
+        ];
+        /* safety: the columns match the schema, and are all n long */
+        let points = RecordBatch::try_new(Arc::new(points_schema), columns)
+          .unwrap();
+
+        let string = |name: &str, value: &str| -> (Field, ArrayRef) {
+            (Field::new(name, DataType::Utf8, false),
+              Arc::new(StringArray::from(vec![value])))
+        };
+        let count = |name: &str, value: u32| -> (Field, ArrayRef) {
+            (Field::new(name, DataType::UInt32, false),
+              Arc::new(UInt32Array::from(vec![value])))
+        };
+        let float = |name: &str, value: f64| -> (Field, ArrayRef) {
+            (Field::new(name, DataType::Float64, false),
+              Arc::new(Float64Array::from(vec![value])))
+        };
+        let created = self.created_date.assume_utc();
+        let created_micros = created.unix_timestamp() * 1_000_000
+          + created.microsecond() as i64;
+        let epsg = self.epsg().and_then(|code| i32::try_from(code).ok());
+        let (fields, columns): (Vec<_>, Vec<_>) = [
+            string("name", &self.name),
+            string("source_data", &self.source_data),
+            string("projection", &self.projection),
+            string("datum", &self.datum),
+            string("xy_units", &format!("{:?}", self.xyunits)),
+            string("z_units", &format!("{:?}", self.zunits)),
+            (Field::new("epsg", DataType::Int32, true),
+              Arc::new(Int32Array::from(vec![epsg])) as ArrayRef),
+            (Field::new("created_date",
+              DataType::Timestamp(TimeUnit::Microsecond, None), false),
+              Arc::new(TimestampMicrosecondArray::from(vec![created_micros]))
+                as ArrayRef),
+            count("rows", self.rows),
+            count("columns", self.columns),
+            count("n_triangles", self.n_triangles),
+            float("xmin", self.xmin),
+            float("xmax", self.xmax),
+            float("ymin", self.ymin),
+            float("ymax", self.ymax),
+            float("xstep", self.xstep),
+            float("ystep", self.ystep),
+            float("zmin", self.zmin),
+            float("zmax", self.zmax),
+        ].into_iter().unzip();
+        let metadata_schema = Schema::new(fields)
+          .with_metadata(schema_metadata);
+        /* safety: as above, with one row in each column */
+        let metadata = RecordBatch::try_new(Arc::new(metadata_schema), columns)
+          .unwrap();
+
+        ArrowGrid { points, metadata }
+    }
+}
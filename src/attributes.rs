@@ -0,0 +1,352 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! derived surface attributes (slope, aspect, hillshade, curvature) and
+//! smoothing for rectangular grids, and per-triangle attributes (area, dip,
+//! and dip azimuth) for triangular grids
+//!
+//! gradients are estimated with Horn's method over each node's 3 × 3
+//! neighborhood; nodes on the edge of the grid reuse their own values for
+//! missing neighbors, and any missing (NaN) value in a neighborhood yields a
+//! missing result
+//!
+//! with the `rayon` feature enabled, rows are computed in parallel
+
+use ndarray::{ArcArray2, Array2};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{Error, Grid, GridData, UnitOfMeasure};
+
+const FEET_PER_METER: f64 = 1.0 / 0.3048;
+
+/// the geometry of one triangle of a triangular grid, treated as a planar
+/// facet; see [Grid::triangle_attributes]
+///
+/// attributes of triangles with missing vertices are NaN, as are the dip and
+/// dip azimuth of degenerate (zero-area) triangles and the dip azimuth of
+/// horizontal ones
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TriangleAttributes {
+    /// the index of the triangle, as stored in the grid's data
+    pub index: usize,
+
+    /// the centroid of the triangle (*x*, *y*, and *z*)
+    pub centroid: (f64, f64, f64),
+
+    /// the true (not map-view) area of the triangle, in square *x*/*y*
+    /// units
+    pub area: f64,
+
+    /// the dip, in degrees from horizontal
+    pub dip: f64,
+
+    /// the dip azimuth (the compass direction of steepest descent), in
+    /// degrees clockwise from north
+    pub dip_azimuth: f64,
+}
+
+/// a derived attribute of a rectangular grid's surface, for computing
+/// attributes chosen at run time; see [Grid::attribute]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SurfaceAttribute {
+    /// see [Grid::slope]
+    Slope,
+
+    /// see [Grid::aspect]
+    Aspect,
+
+    /// see [Grid::hillshade]
+    Hillshade {
+        /// the compass azimuth of the light source, in degrees
+        azimuth: f64,
+
+        /// the altitude of the light source above the horizon, in degrees
+        altitude: f64,
+    },
+
+    /// see [Grid::curvature]
+    Curvature,
+}
+
+impl SurfaceAttribute {
+    /// a short lowercase name for the attribute (e.g. for band or variable
+    /// names)
+    pub fn name(&self) -> &'static str {
+        match self {
+            SurfaceAttribute::Slope => "slope",
+            SurfaceAttribute::Aspect => "aspect",
+            SurfaceAttribute::Hillshade { .. } => "hillshade",
+            SurfaceAttribute::Curvature => "curvature",
+        }
+    }
+}
+
+impl Grid {
+    /// the factor by which *z* values must be multiplied to express them in
+    /// the *x*/*y* units of measure (assumed to be 1 if either is
+    /// [UnitOfMeasure::Other])
+    pub fn z_factor(&self) -> f64 {
+        match (self.xyunits, self.zunits) {
+            (UnitOfMeasure::Feet, UnitOfMeasure::Meters) => FEET_PER_METER,
+            (UnitOfMeasure::Meters, UnitOfMeasure::Feet) => 1.0 / FEET_PER_METER,
+            _ => 1.0,
+        }
+    }
+
+    /// slope, in degrees from horizontal, at each node of a rectangular grid
+    pub fn slope(&self) -> Result<Array2<f64>, Error> {
+        let window = self.window()?;
+        Ok(window.map(|j, i| {
+            let (dx, dy) = window.gradient(j, i);
+            dx.hypot(dy).atan().to_degrees()
+        }))
+    }
+
+    /// aspect (the compass direction of steepest descent), in degrees
+    /// clockwise from north, at each node of a rectangular grid; flat nodes
+    /// have no aspect and yield NaN
+    pub fn aspect(&self) -> Result<Array2<f64>, Error> {
+        let window = self.window()?;
+        Ok(window.map(|j, i| {
+            let (dx, dy) = window.gradient(j, i);
+            aspect_degrees(dx, dy)
+        }))
+    }
+
+    /// shaded relief at each node of a rectangular grid, from 0 (fully
+    /// shadowed) to 1 (fully lit), for a light source at the given compass
+    /// `azimuth` and `altitude` above the horizon (both in degrees)
+    pub fn hillshade(&self, azimuth: f64, altitude: f64)
+      -> Result<Array2<f64>, Error> {
+        let window = self.window()?;
+        let zenith = (90.0 - altitude).to_radians();
+        let azimuth = azimuth.to_radians();
+        Ok(window.map(|j, i| {
+            let (dx, dy) = window.gradient(j, i);
+            let slope = dx.hypot(dy).atan();
+            let aspect = if dx == 0.0 && dy == 0.0 {
+                0.0
+            } else {
+                aspect_degrees(dx, dy).to_radians()
+            };
+            let shade = zenith.cos() * slope.cos()
+              + zenith.sin() * slope.sin() * (azimuth - aspect).cos();
+            if shade.is_nan() { shade } else { shade.max(0.0) }
+        }))
+    }
+
+    /// total curvature (the negated Laplacian of the surface, after
+    /// Zevenbergen & Thorne) at each node of a rectangular grid, in units of
+    /// 1 / (*x*/*y* units); positive values are convex upward (crests),
+    /// negative values concave (troughs)
+    pub fn curvature(&self) -> Result<Array2<f64>, Error> {
+        let window = self.window()?;
+        let (x2, y2) = (self.xstep * self.xstep, self.ystep * self.ystep);
+        Ok(window.map(|j, i| {
+            let z = window.at(j, i, 0, 0);
+            let d = ((window.at(j, i, 0, -1) + window.at(j, i, 0, 1)) / 2.0
+              - z) / x2;
+            let e = ((window.at(j, i, -1, 0) + window.at(j, i, 1, 0)) / 2.0
+              - z) / y2;
+            -2.0 * (d + e)
+        }))
+    }
+
+    /// the given attribute at each node of a rectangular grid
+    pub fn attribute(&self, attribute: SurfaceAttribute)
+      -> Result<Array2<f64>, Error> {
+        match attribute {
+            SurfaceAttribute::Slope => self.slope(),
+            SurfaceAttribute::Aspect => self.aspect(),
+            SurfaceAttribute::Hillshade { azimuth, altitude } =>
+                self.hillshade(azimuth, altitude),
+            SurfaceAttribute::Curvature => self.curvature(),
+        }
+    }
+
+    /// a smoothed copy of a rectangular grid, replacing each node with the
+    /// mean of the non-missing values in its 3 × 3 neighborhood, repeated
+    /// `passes` times; missing nodes stay missing
+    pub fn smooth(&self, passes: usize) -> Result<Grid, Error> {
+        let mut arr = match &self.data {
+            GridData::Rectangular(arr) => arr.to_owned(),
+            _ => return Err(Error::NotRectangular),
+        };
+        for _ in 0..passes {
+            let (rows, cols) = arr.dim();
+            let current = &arr;
+            arr = map_nodes(rows, cols, |j, i| {
+                if current[(j, i)].is_nan() {
+                    return f64::NAN;
+                }
+                let (mut sum, mut n) = (0.0, 0);
+                for jj in j.saturating_sub(1)..(j + 2).min(rows) {
+                    for ii in i.saturating_sub(1)..(i + 2).min(cols) {
+                        let z = current[(jj, ii)];
+                        if !z.is_nan() {
+                            sum += z;
+                            n += 1;
+                        }
+                    }
+                }
+                sum / n as f64
+            });
+        }
+
+        let mut header = self.header();
+        let data = GridData::Rectangular(arr.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Ok(Grid::from_parts(header, data))
+    }
+
+    /// the area, dip, and dip azimuth of each triangle of a triangular grid
+    /// (with *z* converted to *x*/*y* units; see [Grid::z_factor]), e.g. for
+    /// structural analysis of a faulted or folded surface
+    pub fn triangle_attributes(&self)
+      -> Result<Vec<TriangleAttributes>, Error> {
+        let GridData::Triangular(arr) = &self.data else {
+            return Err(Error::NotTriangular);
+        };
+        let zf = self.z_factor();
+        Ok(arr.outer_iter().enumerate().map(|(index, tri)| {
+            let vertex = |v: usize| (tri[(v, 0)], tri[(v, 1)], tri[(v, 2)]);
+            let (a, b, c) = (vertex(0), vertex(1), vertex(2));
+            let centroid = ((a.0 + b.0 + c.0) / 3.0, (a.1 + b.1 + c.1) / 3.0,
+              (a.2 + b.2 + c.2) / 3.0);
+
+            let u = (b.0 - a.0, b.1 - a.1, (b.2 - a.2) * zf);
+            let v = (c.0 - a.0, c.1 - a.1, (c.2 - a.2) * zf);
+            let mut n = (u.1 * v.2 - u.2 * v.1, u.2 * v.0 - u.0 * v.2,
+              u.0 * v.1 - u.1 * v.0);
+            // the upward-facing normal, whatever the winding
+            if n.2 < 0.0 {
+                n = (-n.0, -n.1, -n.2);
+            }
+            let horizontal = n.0.hypot(n.1);
+            let norm = horizontal.hypot(n.2);
+
+            let (dip, dip_azimuth) = if norm == 0.0 {
+                (f64::NAN, f64::NAN)
+            } else if horizontal == 0.0 {
+                (0.0, f64::NAN)
+            } else {
+                // the normal leans toward the down-dip direction
+                (horizontal.atan2(n.2).to_degrees(),
+                 n.0.atan2(n.1).to_degrees().rem_euclid(360.0))
+            };
+
+            TriangleAttributes {
+                index,
+                centroid,
+                area: norm / 2.0,
+                dip,
+                dip_azimuth,
+            }
+        }).collect())
+    }
+
+    fn window(&self) -> Result<Window<'_>, Error> {
+        match &self.data {
+            GridData::Rectangular(arr) => Ok(Window {
+                arr,
+                zf: self.z_factor(),
+                xstep: self.xstep,
+                ystep: self.ystep,
+            }),
+            _ => Err(Error::NotRectangular),
+        }
+    }
+}
+
+// the neighborhoods of a rectangular grid's nodes, with z scaled to xy units
+struct Window<'a> {
+    arr: &'a ArcArray2<f64>,
+    zf: f64,
+    xstep: f64,
+    ystep: f64,
+}
+
+impl Window<'_> {
+    // the value at an offset (in rows and columns) from a node, clamped to
+    // the edges of the grid
+    #[inline]
+    fn at(&self, j: usize, i: usize, dj: isize, di: isize) -> f64 {
+        let (rows, cols) = self.arr.dim();
+        let jj = (j as isize + dj).clamp(0, rows as isize - 1) as usize;
+        let ii = (i as isize + di).clamp(0, cols as isize - 1) as usize;
+        self.arr[(jj, ii)] * self.zf
+    }
+
+    /* the (dz/dx, dz/dy) gradient at a node; remember that row indices
+     * increase northward */
+    #[inline]
+    fn gradient(&self, j: usize, i: usize) -> (f64, f64) {
+        let at = |dj, di| self.at(j, i, dj, di);
+        let dzdx = ((at(1, 1) + 2.0 * at(0, 1) + at(-1, 1))
+          - (at(1, -1) + 2.0 * at(0, -1) + at(-1, -1))) / (8.0 * self.xstep);
+        let dzdy = ((at(1, -1) + 2.0 * at(1, 0) + at(1, 1))
+          - (at(-1, -1) + 2.0 * at(-1, 0) + at(-1, 1))) / (8.0 * self.ystep);
+        (dzdx, dzdy)
+    }
+
+    fn map<F>(&self, f: F) -> Array2<f64>
+      where F: Fn(usize, usize) -> f64 + Sync
+    {
+        let (rows, cols) = self.arr.dim();
+        map_nodes(rows, cols, f)
+    }
+}
+
+// evaluate a function at every node, by rows in parallel with `rayon`
+pub(crate) fn map_nodes<F>(rows: usize, cols: usize, f: F) -> Array2<f64>
+  where F: Fn(usize, usize) -> f64 + Sync
+{
+    let mut out = Array2::zeros((rows, cols));
+    if cols == 0 {
+        return out;
+    }
+    let values = out.as_slice_mut().expect("standard layout");
+    let fill_row = |(j, row): (usize, &mut [f64])| {
+        for (i, v) in row.iter_mut().enumerate() {
+            *v = f(j, i);
+        }
+    };
+
+    #[cfg(feature = "rayon")]
+    values.par_chunks_mut(cols).enumerate().for_each(fill_row);
+
+    #[cfg(not(feature = "rayon"))]
+    values.chunks_mut(cols).enumerate().for_each(fill_row);
+
+    out
+}
+
+// the compass direction of the downslope vector (-dz/dx, -dz/dy)
+fn aspect_degrees(dzdx: f64, dzdy: f64) -> f64 {
+    if dzdx == 0.0 && dzdy == 0.0 {
+        return f64::NAN;
+    }
+    (-dzdx).atan2(-dzdy).to_degrees().rem_euclid(360.0)
+}
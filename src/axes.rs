@@ -0,0 +1,170 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! the order of rows and columns in grid data
+//!
+//! Petra stores rectangular grids with the southernmost row first and each
+//! row from west to east, as do the grids in this library; other formats and
+//! arrays (rasters and images, notably) often run north to south instead.
+//! an [AxisOrder] describes such an order, so data can be converted to and
+//! from Petra's without hand-rolled (and easily mistaken) index flips
+
+use ndarray::{Array2, ArrayView2, Axis};
+
+use crate::{Error, Grid, GridData};
+
+/// the order of rows
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RowOrder {
+    /// southernmost row first ("south-up"), as in Petra and Surfer grids
+    #[default]
+    SouthToNorth,
+
+    /// northernmost row first ("north-up"), as in ESRI grids and images
+    NorthToSouth,
+}
+
+/// the order of columns within each row
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColumnOrder {
+    /// westernmost column first, as in nearly every format
+    #[default]
+    WestToEast,
+
+    /// easternmost column first
+    EastToWest,
+}
+
+/// the order of a grid's rows and columns in some file or array
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AxisOrder {
+    /// the order of rows
+    pub rows: RowOrder,
+
+    /// the order of columns within each row
+    pub columns: ColumnOrder,
+}
+
+impl AxisOrder {
+    /// south to north, then west to east: Petra's order (and the default)
+    pub const SOUTH_UP: AxisOrder = AxisOrder {
+        rows: RowOrder::SouthToNorth,
+        columns: ColumnOrder::WestToEast,
+    };
+
+    /// north to south, then west to east: the usual order for rasters
+    pub const NORTH_UP: AxisOrder = AxisOrder {
+        rows: RowOrder::NorthToSouth,
+        columns: ColumnOrder::WestToEast,
+    };
+
+    /// an order from its row and column orders
+    pub fn new(rows: RowOrder, columns: ColumnOrder) -> AxisOrder {
+        AxisOrder { rows, columns }
+    }
+
+    /// convert rows × columns data between Petra's order and this one
+    ///
+    /// reversing an axis undoes itself, so this works in either direction:
+    /// from a grid's data to this order, or from data in this order to a
+    /// grid's
+    pub fn reorder<T: Clone>(&self, data: ArrayView2<T>) -> Array2<T> {
+        let mut data = data;
+        if self.rows == RowOrder::NorthToSouth {
+            data.invert_axis(Axis(0));
+        }
+        if self.columns == ColumnOrder::EastToWest {
+            data.invert_axis(Axis(1));
+        }
+        data.to_owned()
+    }
+
+    /* the reordering which takes data read as if in `assumed` order to data
+     * read in this order */
+    fn relative_to(&self, assumed: AxisOrder) -> AxisOrder {
+        AxisOrder {
+            rows: if self.rows == assumed.rows {
+                RowOrder::SouthToNorth
+            } else {
+                RowOrder::NorthToSouth
+            },
+            columns: if self.columns == assumed.columns {
+                ColumnOrder::WestToEast
+            } else {
+                ColumnOrder::EastToWest
+            },
+        }
+    }
+}
+
+impl Grid {
+    /// a new rectangular grid, as [Grid::new_rectangular], from data in the
+    /// given order
+    pub fn new_rectangular_in(xmin: f64, ymin: f64, xstep: f64, ystep: f64,
+      data: ArrayView2<f64>, order: AxisOrder) -> Grid {
+        Grid::new_rectangular(xmin, ymin, xstep, ystep, order.reorder(data))
+    }
+
+    /// a copy of the rectangular grid data in the given order, or
+    /// [Error::NotRectangular]
+    pub fn rectangular_in(&self, order: AxisOrder)
+      -> Result<Array2<f64>, Error> {
+        Ok(order.reorder(self.rectangular()?.view()))
+    }
+
+    /// every (*x*, *y*, *z*) point in the grid, as [Grid::points], with the
+    /// nodes of a rectangular grid in the given order (by rows, then
+    /// columns); triangular grids are unaffected
+    pub fn points_in(&self, order: AxisOrder)
+      -> Box<dyn Iterator<Item = (f64, f64, f64)> + '_> {
+        let arr = match &self.data {
+            GridData::Rectangular(arr) => arr,
+            GridData::Triangular(_) => return self.points(),
+        };
+        let (rows, cols) = arr.dim();
+        let row = move |j: usize| match order.rows {
+            RowOrder::SouthToNorth => j,
+            RowOrder::NorthToSouth => rows - 1 - j,
+        };
+        let col = move |i: usize| match order.columns {
+            ColumnOrder::WestToEast => i,
+            ColumnOrder::EastToWest => cols - 1 - i,
+        };
+        Box::new((0..rows).flat_map(move |j| (0..cols).map(move |i| {
+            let (j, i) = (row(j), col(i));
+            let (x, y) = self.node_xy(j, i);
+            (x, y, arr[(j, i)])
+        })))
+    }
+
+    /* for data which was read as if in one order, but was really stored in
+     * another */
+    pub(crate) fn reinterpret_axes(&mut self, actual: AxisOrder,
+      assumed: AxisOrder) {
+        let flip = actual.relative_to(assumed);
+        if let GridData::Rectangular(arr) = &mut self.data {
+            *arr = flip.reorder(arr.view()).into_shared();
+        }
+    }
+}
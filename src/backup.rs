@@ -0,0 +1,67 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! timestamped backups of files about to be overwritten, so that destructive
+//! edits can be undone
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use time::OffsetDateTime;
+
+use crate::Error;
+
+/// copy a file, if it exists, to a timestamped backup beside it (e.g.
+/// `top.grd` to `top.grd.20230415T093000Z.bak`), returning the backup's path;
+/// earlier backups are never overwritten (a numeric suffix is added instead)
+///
+/// if there's no file at `path`, there's nothing to back up, and `None` is
+/// returned
+pub fn backup_file<P: AsRef<Path>>(path: P) -> Result<Option<PathBuf>, Error> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let mut stem = path.as_os_str().to_owned();
+    stem.push(format!(".{:04}{:02}{:02}T{:02}{:02}{:02}Z", now.year(),
+      u8::from(now.month()), now.day(), now.hour(), now.minute(),
+      now.second()));
+
+    let mut backup = with_suffix(&stem, ".bak");
+    let mut n = 1;
+    while backup.exists() {
+        backup = with_suffix(&stem, &format!("-{}.bak", n));
+        n += 1;
+    }
+    fs::copy(path, &backup)?;
+    Ok(Some(backup))
+}
+
+fn with_suffix(stem: &OsString, suffix: &str) -> PathBuf {
+    let mut name = stem.clone();
+    name.push(suffix);
+    PathBuf::from(name)
+}
@@ -0,0 +1,403 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! a lightweight native viewer for Petra grids
+//!
+//! usage: `petra-grid-view [grd-file]`; more files can be opened by dragging
+//! and dropping them onto the window
+//!
+//! when zoomed out, grids are drawn from their overviews (see
+//! [Grid::build_overviews]), which are quicker to render and don't alias
+
+use std::{
+    env,
+    fs::File,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use eframe::egui::{
+    self,
+    Color32,
+    ColorImage,
+    Pos2,
+    Rect,
+    Sense,
+    TextureHandle,
+    TextureOptions,
+    Vec2,
+};
+
+use ndarray::Array2;
+
+use petra_grid::{Colormap, Grid, GridData};
+
+const HILLSHADE_AZIMUTH: f64 = 315.0;
+const HILLSHADE_ALTITUDE: f64 = 45.0;
+const ZOOM_SPEED: f32 = 0.002;
+const MAX_OVERVIEWS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ColormapChoice {
+    Greyscale,
+    Viridis,
+    Terrain,
+}
+
+impl ColormapChoice {
+    const ALL: [ColormapChoice; 3] = [
+        ColormapChoice::Greyscale,
+        ColormapChoice::Viridis,
+        ColormapChoice::Terrain,
+    ];
+
+    fn colormap(self) -> Colormap {
+        match self {
+            ColormapChoice::Greyscale => Colormap::greyscale(),
+            ColormapChoice::Viridis => Colormap::viridis(),
+            ColormapChoice::Terrain => Colormap::terrain(),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ColormapChoice::Greyscale => "greyscale",
+            ColormapChoice::Viridis => "viridis",
+            ColormapChoice::Terrain => "terrain",
+        }
+    }
+}
+
+// a raster we render from, and its hillshade
+struct Level {
+    raster: Array2<f64>,
+    shade: Option<Array2<f64>>,
+}
+
+impl Level {
+    fn new(rasterized: Grid) -> Level {
+        let shade = rasterized.hillshade(HILLSHADE_AZIMUTH, HILLSHADE_ALTITUDE)
+          .ok();
+        let raster = match rasterized.data {
+            GridData::Rectangular(arr) => arr.into_owned(),
+            GridData::Triangular(_) => unreachable!("rasterized grid"),
+        };
+        Level { raster, shade }
+    }
+}
+
+// a loaded grid, plus the full-resolution raster and its overviews
+struct LoadedGrid {
+    path: PathBuf,
+    grid: Grid,
+    levels: Vec<Level>,
+}
+
+impl LoadedGrid {
+    fn load(path: &Path) -> Result<LoadedGrid, petra_grid::Error> {
+        let mut f = File::open(path)?;
+        let grid = Grid::read(&mut f)?;
+        let rasterized = grid.rasterize();
+        let overviews = rasterized.build_overviews(MAX_OVERVIEWS);
+        let levels = std::iter::once(rasterized)
+          .chain(overviews)
+          .map(Level::new)
+          .collect();
+        Ok(LoadedGrid { path: path.to_owned(), grid, levels })
+    }
+}
+
+struct Viewer {
+    loaded: Option<LoadedGrid>,
+    error: Option<String>,
+    // the texture, and the level it was rendered from
+    texture: Option<(usize, TextureHandle)>,
+    colormap: ColormapChoice,
+    stretch: (f64, f64),
+    hillshade: bool,
+    zoom: f32,
+    offset: Vec2,
+}
+
+impl Viewer {
+    fn new(path: Option<PathBuf>) -> Viewer {
+        let mut viewer = Viewer {
+            loaded: None,
+            error: None,
+            texture: None,
+            colormap: ColormapChoice::Viridis,
+            stretch: (0.0, 1.0),
+            hillshade: false,
+            zoom: 1.0,
+            offset: Vec2::ZERO,
+        };
+        if let Some(path) = path {
+            viewer.open(&path);
+        }
+        viewer
+    }
+
+    fn open(&mut self, path: &Path) {
+        match LoadedGrid::load(path) {
+            Ok(loaded) => {
+                self.stretch = (loaded.grid.zmin, loaded.grid.zmax);
+                self.loaded = Some(loaded);
+                self.error = None;
+                self.texture = None;
+                self.zoom = 1.0;
+                self.offset = Vec2::ZERO;
+            },
+            Err(e) => {
+                self.error = Some(format!("Error reading {}: {}",
+                  path.display(), e));
+            },
+        }
+    }
+
+    fn render(&self, level: &Level) -> ColorImage {
+        let (rows, cols) = level.raster.dim();
+        let colormap = self.colormap.colormap();
+        let (lo, hi) = self.stretch;
+        let mut pixels = Vec::with_capacity(rows * cols);
+        // north-up: the last row of the grid is the first row of the image
+        for j in (0..rows).rev() {
+            for i in 0..cols {
+                let z = level.raster[(j, i)];
+                if z.is_nan() {
+                    pixels.push(Color32::TRANSPARENT);
+                    continue;
+                }
+                let [mut r, mut g, mut b] = colormap.color_for(z, lo, hi);
+                if let (true, Some(shade)) = (self.hillshade, &level.shade) {
+                    let s = shade[(j, i)];
+                    if !s.is_nan() {
+                        let k = 0.35 + 0.65 * s;
+                        r = (r as f64 * k) as u8;
+                        g = (g as f64 * k) as u8;
+                        b = (b as f64 * k) as u8;
+                    }
+                }
+                pixels.push(Color32::from_rgb(r, g, b));
+            }
+        }
+        ColorImage::new([cols, rows], pixels)
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("colormap:");
+            egui::ComboBox::from_id_salt("colormap")
+              .selected_text(self.colormap.label())
+              .show_ui(ui, |ui| {
+                  for choice in ColormapChoice::ALL {
+                      changed |= ui.selectable_value(
+                        &mut self.colormap, choice, choice.label()).changed();
+                  }
+              });
+
+            changed |= ui.checkbox(&mut self.hillshade, "hillshade").changed();
+
+            if let Some(loaded) = &self.loaded {
+                let (zmin, zmax) = (loaded.grid.zmin, loaded.grid.zmax);
+                ui.label("stretch:");
+                changed |= ui.add(egui::Slider::new(&mut self.stretch.0,
+                  zmin..=zmax).text("low")).changed();
+                changed |= ui.add(egui::Slider::new(&mut self.stretch.1,
+                  zmin..=zmax).text("high")).changed();
+            }
+
+            if ui.button("reset view").clicked() {
+                self.zoom = 1.0;
+                self.offset = Vec2::ZERO;
+            }
+        });
+        if changed {
+            self.texture = None;
+        }
+    }
+
+    fn metadata(ui: &mut egui::Ui, grid: &Grid) {
+        egui::Grid::new("metadata").striped(true).show(ui, |ui| {
+            let mut row = |label: &str, value: String| {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("name", grid.name.clone());
+            row("version", grid.version.to_string());
+            row("type", match grid.data {
+                GridData::Rectangular(_) => "rectangular".to_string(),
+                GridData::Triangular(_) => "triangular".to_string(),
+            });
+            row("rows × columns",
+              format!("{} × {} ({})", grid.rows, grid.columns, grid.size));
+            row("triangles", grid.n_triangles.to_string());
+            row("x", format!("{} to {} by {}",
+              grid.xmin, grid.xmax, grid.xstep));
+            row("y", format!("{} to {} by {}",
+              grid.ymin, grid.ymax, grid.ystep));
+            row("z", format!("{} to {}", grid.zmin, grid.zmax));
+            row("xy units", format!("{:?}", grid.xyunits));
+            row("z units", format!("{:?}", grid.zunits));
+            row("created", grid.created_date.to_string());
+            row("source data", grid.source_data.clone());
+            row("projection", format!("{} ({})",
+              grid.projection, grid.projection_code));
+            row("datum", grid.datum.clone());
+            row("grid method", format!("{} ({})", grid.method(),
+              grid.grid_method));
+            row("CM", grid.cm.to_string());
+            row("RLAT", grid.rlat.to_string());
+            row("vertical datum", grid.vertical_datum.to_string());
+        });
+    }
+
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        let Some(loaded) = &self.loaded else {
+            ui.centered_and_justified(|ui| {
+                ui.label("drop a GRD file here to view it");
+            });
+            return;
+        };
+
+        let (rows, cols) = loaded.levels[0].raster.dim();
+        let (response, painter) = ui.allocate_painter(
+          ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+        if rows == 0 || cols == 0 {
+            return;
+        }
+
+        let fit = (rect.width() / cols as f32).min(rect.height() / rows as f32);
+        let scale = fit * self.zoom;
+        let size = Vec2::new(cols as f32, rows as f32) * scale;
+        let origin = rect.center() - size / 2.0 + self.offset;
+
+        if response.dragged() {
+            self.offset += response.drag_delta();
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let scroll = ui.ctx().input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                // zoom about the cursor
+                let u = (pos - origin) / scale;
+                self.zoom *= (scroll * ZOOM_SPEED).exp();
+                let new_scale = fit * self.zoom;
+                let new_size = Vec2::new(cols as f32, rows as f32) * new_scale;
+                let new_origin = pos - u * new_scale;
+                self.offset = new_origin - rect.center() + new_size / 2.0;
+            }
+        }
+
+        /* draw from the coarsest overview with at least a pixel per node;
+         * overview nodes are centered on every 2^k-th node of the grid */
+        let level = if scale < 1.0 {
+            ((1.0 / scale).log2().floor() as usize)
+              .min(loaded.levels.len() - 1)
+        } else {
+            0
+        };
+        if self.texture.as_ref().is_none_or(|(l, _)| *l != level) {
+            let image = self.render(&loaded.levels[level]);
+            self.texture = Some((level, ui.ctx().load_texture(
+              "grid", image, TextureOptions::NEAREST)));
+        }
+        if let Some((_, texture)) = &self.texture {
+            let (level_rows, level_cols) = loaded.levels[level].raster.dim();
+            let f = (1 << level) as f32;
+            let min = origin + Vec2::new(0.5 - f / 2.0,
+              rows as f32 - f * (level_rows as f32 - 1.0) - 0.5 - f / 2.0)
+              * scale;
+            let level_size = Vec2::new(level_cols as f32, level_rows as f32)
+              * f * scale;
+            painter.image(texture.id(), Rect::from_min_size(min, level_size),
+              Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+              Color32::WHITE);
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let u = (pos - origin) / scale;
+            let grid = &loaded.grid;
+            let x = grid.xmin + (u.x as f64 - 0.5) * grid.xstep;
+            let y = grid.ymin + ((rows as f64 - u.y as f64) - 0.5) * grid.ystep;
+            let z = match grid.sample(x, y) {
+                Some(z) if z.is_nan() => "(missing)".to_string(),
+                Some(z) => format!("{:.3}", z),
+                None => "(outside grid)".to_string(),
+            };
+            response.on_hover_text_at_pointer(
+              format!("x = {:.2}\ny = {:.2}\nz = {}", x, y, z));
+        }
+    }
+}
+
+impl eframe::App for Viewer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if let Some(path) = dropped.into_iter().find_map(|f| f.path) {
+            self.open(&path);
+        }
+
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| {
+            self.controls(ui);
+            if let Some(e) = &self.error {
+                ui.colored_label(Color32::RED, e);
+            }
+        });
+
+        if let Some(loaded) = &self.loaded {
+            egui::SidePanel::right("metadata").show(ctx, |ui| {
+                ui.heading(loaded.path.display().to_string());
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    Viewer::metadata(ui, &loaded.grid);
+                });
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| self.canvas(ui));
+    }
+}
+
+fn main() -> ExitCode {
+    let args = env::args().collect::<Vec<_>>();
+    let path = match &args[..] {
+        [] | [_] => None,
+        [_, path] => Some(PathBuf::from(path)),
+        [prog, ..] => {
+            eprintln!("Usage: {} [grd-file]", prog);
+            return ExitCode::from(2);
+        },
+    };
+
+    let options = eframe::NativeOptions::default();
+    let result = eframe::run_native("petra-grid-view", options,
+      Box::new(|_cc| Ok(Box::new(Viewer::new(path)))));
+
+    match result {
+        Ok(()) => ExitCode::from(0),
+        Err(e) => {
+            eprintln!("Error running viewer: {}", e);
+            ExitCode::from(1)
+        },
+    }
+}
@@ -0,0 +1,62 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid checksum`: write or verify SHA-256 sidecar files
+
+use std::process::ExitCode;
+
+use petra_grid::checksum::{verify_sidecar, write_sidecar, Verification};
+
+use crate::report;
+
+pub fn run(args: &[String]) -> ExitCode {
+    let (verify, paths) = match args {
+        [flag, paths @ ..] if flag == "--verify" => (true, paths),
+        paths => (false, paths),
+    };
+
+    if paths.is_empty() {
+        return report::usage("Usage: petra-grid checksum [--verify] <files>");
+    }
+
+    for path in paths {
+        if verify {
+            match verify_sidecar(path) {
+                Ok(Verification::Ok) => println!("{}: OK", path),
+                Ok(Verification::Mismatch { expected, actual }) => {
+                    println!("{}: FAILED (expected {}, got {})",
+                      path, expected, actual);
+                    report::failure("verifying", path, "checksum_mismatch",
+                      &format!("expected {}, got {}", expected, actual),
+                      report::EXIT_CHECK);
+                },
+                Err(e) => report::error("verifying", path, &e),
+            }
+        } else {
+            match write_sidecar(path) {
+                Ok(sidecar) => println!("{}", sidecar.display()),
+                Err(e) => report::error("checksumming", path, &e),
+            }
+        }
+    }
+
+    report::exit_code()
+}
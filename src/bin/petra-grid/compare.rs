@@ -0,0 +1,88 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid compare`: check whether two grids share a lattice
+
+use std::{
+    fs::File,
+    process::ExitCode,
+};
+
+use petra_grid::{Grid, qc::Tolerance};
+
+use crate::report;
+
+const USAGE: &str =
+  "Usage: petra-grid compare [--tolerance <xy-units> | --profile <name>] \
+   <grd-file> <grd-file>\n\n\
+   Profiles (tolerances relative to the first grid's extent): exact, \
+   display, volumetrics";
+
+// an absolute tolerance, or one relative to the first grid
+enum XyTolerance {
+    Absolute(f64),
+    Profile(Tolerance),
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    let (tolerance, paths) = match args {
+        [flag, tol, paths @ ..] if flag == "--tolerance" => match tol.parse() {
+            Ok(tol) => (XyTolerance::Absolute(tol), paths),
+            Err(_) => return report::usage(USAGE),
+        },
+        [flag, name, paths @ ..] if flag == "--profile" =>
+            match Tolerance::named(name) {
+                Some(profile) => (XyTolerance::Profile(profile), paths),
+                None => return report::usage(USAGE),
+            },
+        paths => (XyTolerance::Absolute(0.0), paths),
+    };
+
+    let [first, second] = paths else {
+        return report::usage(USAGE);
+    };
+
+    let mut grids = Vec::new();
+    for path in [first, second] {
+        match File::open(path).map_err(Into::into)
+          .and_then(|mut f| Grid::read(&mut f)) {
+            Ok(grid) => grids.push(grid),
+            Err(e) => report::error("reading", path, &e),
+        }
+    }
+    let [a, b] = &grids[..] else {
+        return report::exit_code();
+    };
+
+    let tolerance = match tolerance {
+        XyTolerance::Absolute(tol) => tol,
+        XyTolerance::Profile(profile) => profile.xy_tolerance(a),
+    };
+    let comparison = a.compare_geometry(b);
+    println!("{}", comparison);
+    if comparison.is_coregistered(tolerance) {
+        println!("coregistered");
+    } else {
+        report::failure("comparing", second, "not_coregistered",
+          &format!("not coregistered with {}", first), report::EXIT_CHECK);
+    }
+    report::exit_code()
+}
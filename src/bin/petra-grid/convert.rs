@@ -0,0 +1,101 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid convert`: convert grids in any supported format, detected
+//! automatically, to one or more other formats
+
+use std::{path::Path, process::ExitCode};
+
+use petra_grid::{
+    MetadataTemplate,
+    TemplateContext,
+    import::read_file_with,
+};
+
+use crate::{
+    export::{column_order, row_order, run_with},
+    report,
+};
+
+const INPUT_USAGE: &str = "\n\n\
+  Input options (for files written in the wrong order for their format):\n  \
+    --input-rows <order>\n                      \
+    read rows as stored south-up or north-up\n  \
+    --input-columns <order>\n                      \
+    read columns as stored west-east or east-west\n\n\
+  Metadata options:\n  \
+    --metadata <file>   fill in each grid's name, source data,\n                      \
+    projection, and datum from a template (see the README)";
+
+pub fn run(args: &[String]) -> ExitCode {
+    // pick out our own options, and leave the rest to `run_with`
+    let mut input_rows = None;
+    let mut input_columns = None;
+    let mut template = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input-rows" => match iter.next().and_then(|o| row_order(o)) {
+                Some(rows) => input_rows = Some(rows),
+                None => return report::usage(&format!(
+                  "Error: --input-rows must be south-up or north-up{}",
+                  INPUT_USAGE)),
+            },
+            "--input-columns" =>
+                match iter.next().and_then(|o| column_order(o)) {
+                    Some(columns) => input_columns = Some(columns),
+                    None => return report::usage(&format!(
+                      "Error: --input-columns must be west-east or \
+                       east-west{}", INPUT_USAGE)),
+                },
+            "--metadata" => {
+                let Some(path) = iter.next() else {
+                    return report::usage(&format!(
+                      "Error: --metadata needs a template file{}",
+                      INPUT_USAGE));
+                };
+                match MetadataTemplate::read_file(path) {
+                    Ok(t) => template = Some(t),
+                    Err(e) => {
+                        report::error("reading metadata template", path, &e);
+                        return report::exit_code();
+                    },
+                }
+            },
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    run_with(&rest, "convert", INPUT_USAGE, |path| {
+        let (mut grid, format) =
+          read_file_with(path, input_rows, input_columns)?;
+        eprintln!("{}: read as {}", path, format);
+        if let Some(template) = &template {
+            let format = format.to_string();
+            template.apply(&mut grid, &TemplateContext {
+                path: Some(Path::new(path)),
+                format: Some(&format),
+            })?;
+        }
+        Ok(grid)
+    })
+}
@@ -0,0 +1,58 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid dates`: list plausible timestamps in grid headers, to help
+//! identify Petra's (suspected) modification-time field
+
+use std::{
+    fs::File,
+    io::Read,
+    process::ExitCode,
+};
+
+use petra_grid::{Error, probe::{HEADER_LEN, find_header_dates}};
+
+use crate::report;
+
+fn process_grid_file(path: &String) -> Result<(), Error> {
+    let mut bytes = Vec::new();
+    File::open(path)?.take(HEADER_LEN).read_to_end(&mut bytes)?;
+    println!("{}:", path);
+    for d in find_header_dates(&bytes) {
+        println!("  {:#06x}: {}{}", d.offset, d.date,
+          if d.known { " (created_date)" } else { "" });
+    }
+    Ok(())
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    if args.is_empty() {
+        return report::usage("Usage: petra-grid dates <grd-files>");
+    }
+
+    for path in args {
+        if let Err(e) = process_grid_file(path) {
+            report::error("reading", path, &e);
+        }
+    }
+
+    report::exit_code()
+}
@@ -0,0 +1,69 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid duplicates`: find grids with identical data
+
+use std::{
+    path::Path,
+    process::ExitCode,
+};
+
+use petra_grid::GridSet;
+
+use crate::report;
+
+pub fn run(args: &[String]) -> ExitCode {
+    if args.is_empty() {
+        return report::usage(
+          "Usage: petra-grid duplicates <grd-files-or-directories>");
+    }
+
+    let mut set = GridSet::new();
+    for arg in args {
+        let path = Path::new(arg);
+        let (found, errors) = if path.is_dir() {
+            match GridSet::read_dir(path) {
+                Ok(result) => result,
+                Err(e) => {
+                    report::error("reading directory", arg, &e.into());
+                    continue;
+                },
+            }
+        } else {
+            GridSet::read_paths([path])
+        };
+        for (path, e) in errors {
+            report::error("reading", &path.display().to_string(), &e);
+        }
+        for (path, grid) in found {
+            set.push(path, grid);
+        }
+    }
+
+    for (i, group) in set.find_duplicates().iter().enumerate() {
+        println!("duplicate group {}:", i + 1);
+        for path in group {
+            println!("  {}", path.display());
+        }
+    }
+
+    report::exit_code()
+}
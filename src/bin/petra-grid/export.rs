@@ -0,0 +1,395 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid export`: write grids in one or more other formats
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Instant,
+};
+
+use petra_grid::{
+    AxisOrder,
+    ColumnOrder,
+    Error,
+    Grid,
+    NullPolicy,
+    RowOrder,
+    backup_file,
+    export::{
+        FORMATS,
+        Exporter,
+        Pipeline,
+        dxf::DxfContours,
+        exporter,
+        xyz::{Text, TextOptions},
+    },
+};
+
+use crate::{
+    report,
+    timing::{FileTiming, Timings, file_size},
+};
+
+pub fn run(args: &[String]) -> ExitCode {
+    run_with(args, "export", "",
+      |path| Grid::read(&mut File::open(path)?))
+}
+
+// parse the name of a row order, as accepted by --rows
+pub fn row_order(name: &str) -> Option<RowOrder> {
+    match name {
+        "south-up" => Some(RowOrder::SouthToNorth),
+        "north-up" => Some(RowOrder::NorthToSouth),
+        _ => None,
+    }
+}
+
+// parse the name of a column order, as accepted by --columns
+pub fn column_order(name: &str) -> Option<ColumnOrder> {
+    match name {
+        "west-east" => Some(ColumnOrder::WestToEast),
+        "east-west" => Some(ColumnOrder::EastToWest),
+        _ => None,
+    }
+}
+
+/* open an output file for writing; unless we may overwrite, this fails if
+ * the file exists, even if it was created since we last checked */
+pub fn create_output(path: &Path, overwrite: bool) -> io::Result<File> {
+    File::options()
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .create_new(!overwrite)
+      .open(path)
+}
+
+// overrides for the formatting of csv and xyz output
+#[derive(Default)]
+struct TextFlags {
+    delimiter: Option<String>,
+    precision: Option<usize>,
+    scientific: bool,
+    null_token: Option<String>,
+    header: Option<bool>,
+    reproducible: bool,
+    axis_order: AxisOrder,
+}
+
+impl TextFlags {
+    fn apply(&self, mut options: TextOptions) -> TextOptions {
+        if let Some(delimiter) = &self.delimiter {
+            options = options.delimiter(delimiter.as_str());
+        }
+        if let Some(header) = self.header {
+            options = options.header(header);
+        }
+        options.precision(self.precision)
+          .scientific(self.scientific)
+          .null_token(self.null_token.clone())
+          .reproducible(self.reproducible)
+          .axis_order(self.axis_order)
+    }
+}
+
+/* the guts of `export` and `convert`, which differ only in how they read
+ * their input (and in any options for doing so, described by
+ * `input_usage`) */
+pub fn run_with<F>(args: &[String], command: &str, input_usage: &str, read: F)
+  -> ExitCode
+  where F: Fn(&str) -> Result<Grid, Error>
+{
+    let usage = format!(
+      "Usage: petra-grid {} --to <formats> [--out-dir <dir>] [--dry-run] \
+       [--force | --backup] [--timings] [--timings-json <file>] \
+       [--null-value <z>]... [--contour-interval <z>] [<text-options>] \
+       {}<files>\n\n\
+       Formats: {}\n\n\
+       Existing files are never overwritten unless --force is given; with\n\
+       --backup, they're copied to timestamped .bak files and then\n\
+       overwritten. With --dry-run, the files which would be written are\n\
+       listed, but nothing is written.\n\n\
+       --timings prints each file's read and write throughput, and the\n\
+       total wall time, at the end; --timings-json writes the same as JSON.\n\n\
+       --null-value marks <z> (as well as 1e30) as missing in the input,\n\
+       for grids written by software using another null value.\n\n\
+       --contour-interval sets the interval between contours for\n\
+       dxf-contours; by default, a round interval giving about ten levels\n\
+       is chosen for each grid.\n\n\
+       Text options (for csv and xyz):\n  \
+         --delimiter <text>  field delimiter (\"tab\" for a tab)\n  \
+         --precision <n>     digits after the decimal point\n  \
+         --scientific        use scientific notation\n  \
+         --null <token>      write missing values as <token>, rather than\n                      \
+         omitting them\n  \
+         --header, --no-header\n                      \
+         write (or omit) a header row\n  \
+         --reproducible      write values in a canonical form, identical\n                      \
+         across platforms (overrides --precision and\n                      \
+         --scientific)\n  \
+         --rows <order>      write rows south-up (the default) or north-up\n  \
+         --columns <order>   write columns west-east (the default) or\n                      \
+         east-west{}",
+      command, if input_usage.is_empty() { "" } else { "[<input-options>] " },
+      FORMATS.join(", "), input_usage);
+
+    let mut formats = None;
+    let mut out_dir = None;
+    let mut dry_run = false;
+    let mut force = false;
+    let mut backup = false;
+    let mut print_timings = false;
+    let mut timings_json = None;
+    let mut text = TextFlags::default();
+    let mut nulls = NullPolicy::new();
+    let mut contour_interval = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => formats = args.next(),
+            "--out-dir" => match args.next() {
+                Some(dir) => out_dir = Some(PathBuf::from(dir)),
+                None => return report::usage(&usage),
+            },
+            "--dry-run" => dry_run = true,
+            "--force" => force = true,
+            "--backup" => backup = true,
+            "--timings" => print_timings = true,
+            "--timings-json" => match args.next() {
+                Some(path) => timings_json = Some(PathBuf::from(path)),
+                None => return report::usage(&usage),
+            },
+            "--delimiter" => match args.next().map(String::as_str) {
+                Some("tab") => text.delimiter = Some("\t".to_string()),
+                Some(delim) => text.delimiter = Some(delim.to_string()),
+                None => return report::usage(&usage),
+            },
+            "--precision" => match args.next().map(|n| n.parse()) {
+                Some(Ok(n)) => text.precision = Some(n),
+                _ => return report::usage(&usage),
+            },
+            "--scientific" => text.scientific = true,
+            "--reproducible" => text.reproducible = true,
+            "--null-value" => match args.next().map(|z| z.parse()) {
+                Some(Ok(z)) => nulls = nulls.sentinel(z),
+                _ => return report::usage(&usage),
+            },
+            "--contour-interval" => match args.next().map(|z| z.parse()) {
+                Some(Ok(z)) => contour_interval = Some(z),
+                _ => return report::usage(&usage),
+            },
+            "--null" => match args.next() {
+                Some(token) => text.null_token = Some(token.clone()),
+                None => return report::usage(&usage),
+            },
+            "--rows" => match args.next().and_then(|o| row_order(o)) {
+                Some(order) => text.axis_order.rows = order,
+                None => return report::usage(&usage),
+            },
+            "--columns" => match args.next().and_then(|o| column_order(o)) {
+                Some(order) => text.axis_order.columns = order,
+                None => return report::usage(&usage),
+            },
+            "--header" => text.header = Some(true),
+            "--no-header" => text.header = Some(false),
+            _ => paths.push(arg),
+        }
+    }
+
+    let formats = match formats {
+        Some(formats) if !paths.is_empty() => formats,
+        _ => return report::usage(&usage),
+    };
+
+    let overwrite = force || backup;
+    let mut pipeline = Pipeline::new().no_clobber(!overwrite);
+    for name in formats.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let name = name.to_ascii_lowercase();
+        let exporter: Box<dyn Exporter> = match name.as_str() {
+            "csv" => Box::new(Text::new("csv", "csv",
+              text.apply(TextOptions::csv()))),
+            "xyz" => Box::new(Text::new("xyz", "xyz",
+              text.apply(TextOptions::xyz()))),
+            "dxf-contours" =>
+                Box::new(DxfContours { interval: contour_interval }),
+            _ => match exporter(&name) {
+                Some(exporter) => exporter,
+                None => return report::usage(&format!(
+                  "Error: unknown export format {} (available: {})",
+                  name, FORMATS.join(", "))),
+            },
+        };
+        pipeline.push(exporter);
+    }
+
+    if let Some(json) = &timings_json {
+        if !overwrite && json.exists() {
+            report::failure("writing", &json.display().to_string(),
+              "would_overwrite", "refusing to overwrite existing file \
+               (use --force or --backup to overwrite)", report::EXIT_USAGE);
+            return report::exit_code();
+        }
+    }
+
+    let mut timings = Timings::start();
+    for path in paths {
+        let base = match &out_dir {
+            Some(dir) =>
+                dir.join(Path::new(path).file_name().unwrap_or_default()),
+            None => PathBuf::from(path),
+        };
+        let outputs = pipeline.output_paths(&base);
+        if outputs.iter().any(|p| same_file(p, Path::new(path))) {
+            report::failure("writing", path, "would_overwrite_input",
+              "refusing to overwrite input", report::EXIT_USAGE);
+            continue;
+        }
+        if !overwrite {
+            let existing = outputs.iter()
+              .filter(|p| p.exists())
+              .collect::<Vec<_>>();
+            for out in &existing {
+                report::failure("writing", &out.display().to_string(),
+                  "would_overwrite", "refusing to overwrite existing file \
+                   (use --force or --backup to overwrite)", report::EXIT_USAGE);
+            }
+            if !existing.is_empty() {
+                continue;
+            }
+        }
+
+        let started = Instant::now();
+        let grid = match read(path) {
+            Ok(mut grid) => {
+                grid.data.recode_nulls(&nulls);
+                grid
+            },
+            Err(e) => {
+                report::error("reading", path, &e);
+                continue;
+            },
+        };
+        let mut timing = FileTiming {
+            path: path.clone(),
+            read_bytes: file_size(path),
+            read_time: started.elapsed(),
+            ..Default::default()
+        };
+
+        if dry_run {
+            for out in outputs {
+                if backup && out.exists() {
+                    println!("would back up {}", out.display());
+                }
+                println!("would write {}", out.display());
+            }
+            timings.record(timing);
+            continue;
+        }
+
+        if backup {
+            let mut failed = false;
+            for out in &outputs {
+                match backup_file(out) {
+                    Ok(Some(bak)) => println!("{} -> {}", out.display(),
+                      bak.display()),
+                    Ok(None) => { },
+                    Err(e) => {
+                        report::error("backing up",
+                          &out.display().to_string(), &e);
+                        failed = true;
+                    },
+                }
+            }
+            /* never overwrite a file we couldn't back up */
+            if failed {
+                continue;
+            }
+        }
+
+        let started = Instant::now();
+        let results = pipeline.run(&grid, base);
+        timing.write_time = started.elapsed();
+        for (out, result) in results {
+            match result {
+                Ok(()) => {
+                    println!("{}", out.display());
+                    timing.written_bytes += file_size(&out);
+                },
+                Err(e) =>
+                    report::error("writing", &out.display().to_string(), &e),
+            }
+        }
+        timings.record(timing);
+    }
+
+    if print_timings {
+        timings.print();
+    }
+    if let Some(json) = timings_json {
+        if let Err(e) = write_timings(&json, &timings, force, backup) {
+            report::error("writing", &json.display().to_string(), &e);
+        }
+    }
+
+    report::exit_code()
+}
+
+// write timings as JSON, following the same rules as other outputs
+fn write_timings(path: &Path, timings: &Timings, force: bool, backup: bool)
+  -> Result<(), Error> {
+    if backup {
+        if let Some(bak) = backup_file(path)? {
+            println!("{} -> {}", path.display(), bak.display());
+        }
+    }
+    create_output(path, force || backup)?
+      .write_all(timings.json().as_bytes())?;
+    Ok(())
+}
+
+/* do two paths name the same file? besides comparing them as given, we
+ * compare their canonical forms (resolving `.`, `..`, and symlinks) and, on
+ * Unix, their devices and inodes (catching hard links), so long as both
+ * exist; a path which doesn't exist yet can't name an existing input */
+fn same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    if let (Ok(a), Ok(b)) = (fs::canonicalize(a), fs::canonicalize(b)) {
+        if a == b {
+            return true;
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(a), Ok(b)) = (fs::metadata(a), fs::metadata(b)) {
+            return a.dev() == b.dev() && a.ino() == b.ino();
+        }
+    }
+    false
+}
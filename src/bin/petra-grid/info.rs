@@ -0,0 +1,120 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid info`: dump grid metadata
+
+use std::{
+    fs::File,
+    process::ExitCode,
+};
+
+use petra_grid::{Error, Grid, GridData, ReadOptions};
+
+use crate::report;
+
+// the slack (in radians) allowed for nearly-cocircular vertices
+const DELAUNAY_TOLERANCE: f64 = 1e-6;
+
+fn process_grid_file(path: &String, options: &ReadOptions)
+  -> Result<(), Error> {
+    let mut f = File::open(path)?;
+    let (grid, warnings) = Grid::read_with_options(&mut f, options)?;
+    for w in warnings {
+        report::warning("reading", path, &w);
+    }
+    let stats = grid.data.stats();
+
+    println!("{}:", path);
+    println!("  name: {}", grid.name);
+    println!("  version: {}", grid.version);
+    match grid.data {
+        GridData::Rectangular(_) => println!("  type: rectangular"),
+        GridData::Triangular(_) => println!("  type: triangular"),
+    };
+    println!("  size: {} ({} rows x {} columns)",
+      grid.size, grid.rows, grid.columns);
+    println!("  triangles: {}", grid.n_triangles);
+    println!("  x: {} to {} by {}", grid.xmin, grid.xmax, grid.xstep);
+    println!("  y: {} to {} by {}", grid.ymin, grid.ymax, grid.ystep);
+    println!("  z: {} to {}", grid.zmin, grid.zmax);
+    println!("  xy units: {:?}", grid.xyunits);
+    println!("  z units: {:?}", grid.zunits);
+    println!("  created: {}", grid.created_date);
+    println!("  source data: {}", grid.source_data);
+    println!("  projection: {} ({})", grid.projection, grid.projection_code);
+    println!("  datum: {}", grid.datum);
+    if let Some(code) = grid.epsg() {
+        println!("  EPSG: {}", code);
+    }
+    println!("  grid method: {} (code {})", grid.method(), grid.grid_method);
+    println!("  CM: {}", grid.cm);
+    println!("  RLAT: {}", grid.rlat);
+    println!("  vertical datum: {}", grid.vertical_datum);
+    println!("  values: {} ({} missing)", stats.count, stats.null_count);
+    println!("  mean: {} (std. dev. {})", stats.mean, stats.std_dev);
+    if let Some(topo) = grid.mesh_topology() {
+        println!("  vertices: {} stored, {} unique (reuse {:.2}, max valence {})",
+          topo.stored_vertices, topo.unique_vertices, topo.vertex_reuse(),
+          topo.max_vertex_valence);
+        println!("  edges: {} ({} shared, {} boundary, {} non-manifold)",
+          topo.edges, topo.shared_edges, topo.boundary_edges,
+          topo.non_manifold_edges);
+        println!("  degenerate triangles: {}", topo.degenerate_triangles);
+        println!("  network: {}",
+          if topo.is_network() { "yes" } else { "no (triangle soup)" });
+    }
+    if let Some(check) = grid.check_delaunay(DELAUNAY_TOLERANCE) {
+        match check.violations.first() {
+            None => println!("  delaunay: yes"),
+            Some(worst) => println!(
+              "  delaunay: no ({} of {} shared edges; worst {:.3}° at \
+              triangles {} and {})",
+              check.violations.len(), check.interior_edges,
+              worst.excess.to_degrees(), worst.triangles.0, worst.triangles.1),
+        }
+    }
+    Ok(())
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    let mut options = ReadOptions::new();
+    let mut paths = args;
+    while let [flag, rest @ ..] = paths {
+        match flag.as_str() {
+            "--salvage" => options = options.salvage(true),
+            "--lenient" => options = options.lenient(true),
+            _ => break,
+        }
+        paths = rest;
+    }
+
+    if paths.is_empty() {
+        return report::usage(
+          "Usage: petra-grid info [--salvage] [--lenient] <grd-files>");
+    }
+    for path in paths {
+        if let Err(e) = process_grid_file(path, &options) {
+            report::error("reading", path, &e);
+        }
+    }
+
+    report::exit_code()
+}
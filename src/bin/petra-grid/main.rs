@@ -0,0 +1,184 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid`: command-line tools for Petra grids
+
+use std::{
+    env,
+    process::ExitCode,
+};
+
+#[cfg(feature = "checksum")]
+mod checksum;
+mod compare;
+mod convert;
+mod dates;
+mod duplicates;
+mod export;
+mod info;
+#[cfg(feature = "geotiff")]
+mod publish;
+mod report;
+mod scan;
+#[cfg(feature = "serve")]
+mod serve;
+mod timing;
+#[cfg(feature = "tui")]
+mod tui;
+mod validate;
+mod zonal;
+
+const USAGE: &str = "\
+Usage: petra-grid [--error-format text|json] <command> <args>
+
+Commands:
+  checksum [--verify] <files>
+                      write SHA-256 sidecar files (<file>.sha256), or
+                      verify files against them (requires the `checksum`
+                      feature)
+  compare [--tolerance <xy-units> | --profile <name>] <grd-file> <grd-file>
+                      compare two grids' lattices (origin, spacing,
+                      extent, and shape), failing unless they match
+                      (to within a tolerance, or a named profile: exact,
+                      display, or volumetrics)
+  convert --to <formats> [--out-dir <dir>] [--dry-run]
+          [--force | --backup] <files>
+                      convert Petra, Surfer, ZMAP+, ESRI ASCII, or XYZ
+                      grids (detected automatically) to one or more
+                      comma-separated formats (e.g. grd,csv); see
+                      `petra-grid convert` for options overriding the
+                      order of rows and columns in the input
+  dates <grd-files>   list every plausible timestamp in each grid's header
+                      (to help identify an undecoded modification time)
+  duplicates <grd-files-or-directories>
+                      list groups of grids with identical data
+  export --to <formats> [--out-dir <dir>] [--dry-run]
+         [--force | --backup] <grd-files>
+                      write each grid in one or more comma-separated
+                      formats (e.g. csv,xyz), reading it only once;
+                      see `petra-grid export` for options controlling
+                      the formatting of csv and xyz output and for
+                      reporting throughput (--timings)
+  info [--salvage] [--lenient] <grd-files>
+                      dump the metadata of each grid, optionally
+                      recovering what data is present in truncated files,
+                      or reading despite inconsistent size or x/y specs
+  publish --out-dir <dir> [--crs <epsg-code>] [--cs2cs <program>]
+          [--dry-run] [--force] <grd-files-or-directories>
+                      reproject grids to one coordinate system (with
+                      PROJ's cs2cs), mosaic them by horizon, and write
+                      each horizon as a cloud-optimized GeoTIFF, with a
+                      JSON catalog (requires the `geotiff` feature)
+  scan <directory>    list the metadata of every grid in a directory tree,
+                      as tab-separated values
+  serve [--bind <address:port>] <directory>
+                      serve the grids in a directory over HTTP: metadata,
+                      PNG renders, contours, and point samples (requires
+                      the `serve` feature)
+  tui <grd-file>      inspect a grid interactively in the terminal
+                      (requires the `tui` feature)
+  validate [--strict] [--lenient] [--summary <json-file>] [--timings]
+           <grd-files-or-directories>
+                      check that grids (including every grid in a
+                      directory tree) read cleanly, reporting truncated
+                      or corrupt data (and, with --lenient, inconsistent
+                      size or x/y specs) as warnings; with --strict, any
+                      warning fails the run, --summary writes an
+                      aggregate JSON report (with read times), and
+                      --timings prints each file's read throughput
+  zonal [--base <z>] <grid-file> <polygon-file>
+                      write a CSV table of the grid's statistics (or,
+                      with --base, its volume above the base) within
+                      each polygon of a GeoJSON or Shapefile layer
+
+Errors are written to standard error, as text or (with --error-format json)
+as one JSON object per line, with fields level, action, path, kind, offset,
+message, and exit_code.
+
+Exit codes:
+  0  success
+  1  a file couldn't be read or written
+  2  invalid command line
+  3  a file was read but its contents were invalid
+  4  a check failed (e.g. a checksum mismatch)
+When several files fail for different reasons, the highest code applies.";
+
+fn main() -> ExitCode {
+    let args_os = env::args_os().collect::<Vec<_>>();
+    let mut args = args_os.iter()
+      .map(|arg| arg.to_string_lossy().into_owned())
+      .collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("--error-format") {
+        match args.get(2).map(String::as_str) {
+            Some("text") => report::set_json(false),
+            Some("json") => report::set_json(true),
+            _ => return report::usage(USAGE),
+        }
+        args.drain(1..3);
+    }
+
+    /* file names which aren't valid Unicode can't be passed on as strings;
+     * but directories of them can still be scanned, since paths found there
+     * are never converted */
+    if let Some(arg) = args_os.iter().find(|arg| arg.to_str().is_none()) {
+        report::failure("parsing arguments", &arg.to_string_lossy(),
+          "invalid_unicode", "argument is not valid Unicode (pass its \
+          directory instead)", report::EXIT_USAGE);
+        return report::exit_code();
+    }
+
+    match args.get(1).map(String::as_str) {
+        #[cfg(feature = "checksum")]
+        Some("checksum") => checksum::run(&args[2..]),
+
+        Some("compare") => compare::run(&args[2..]),
+
+        Some("convert") => convert::run(&args[2..]),
+
+        Some("dates") => dates::run(&args[2..]),
+
+        Some("duplicates") => duplicates::run(&args[2..]),
+
+        Some("export") => export::run(&args[2..]),
+
+        Some("info") => info::run(&args[2..]),
+
+        #[cfg(feature = "geotiff")]
+        Some("publish") => publish::run(&args[2..]),
+
+        Some("scan") => scan::run(&args[2..]),
+
+        #[cfg(feature = "serve")]
+        Some("serve") => serve::run(&args[2..]),
+
+        #[cfg(feature = "tui")]
+        Some("tui") => tui::run(&args[2..]),
+
+        Some("validate") => validate::run(&args[2..]),
+
+        Some("zonal") => zonal::run(&args[2..]),
+
+        Some(cmd) =>
+            report::usage(&format!("Unknown command: {}\n{}", cmd, USAGE)),
+
+        None => report::usage(USAGE),
+    }
+}
@@ -0,0 +1,383 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid publish`: mosaic a collection of grids by horizon and write
+//! them as cloud-optimized GeoTIFFs, with a catalog
+//!
+//! each grid's coordinate system is resolved to an EPSG code (see
+//! [Grid::epsg]); grids are published in a single target system, and since
+//! we don't implement projections, grids in any other system are reprojected
+//! by PROJ's `cs2cs` (see [Grid::transform_xy_batched]), while grids in a
+//! system we can't identify are reported and left out. grids are grouped
+//! into horizons by their names, and the grids of each horizon are combined
+//! with [Grid::mosaic], in the order given (so list the most trusted first)
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+    thread,
+};
+
+use petra_grid::{
+    Error,
+    Grid,
+    epsg,
+    export::geotiff::write_cog,
+    find_grd_files,
+};
+
+use crate::{
+    export::create_output,
+    report::{self, json_string},
+};
+
+const USAGE: &str = "\
+Usage: petra-grid publish --out-dir <dir> [--crs <epsg-code>]
+       [--cs2cs <program>] [--dry-run] [--force] <grd-files-or-directories>
+
+Groups grids (including every grid in a directory tree) into horizons by
+their names, mosaics each horizon's grids (earlier grids take precedence
+where they overlap), and writes each horizon as a cloud-optimized GeoTIFF
+(<horizon>.tif) in the output directory, along with a JSON catalog of the
+horizons (catalog.json).
+
+Grids are published in the target coordinate system: the one given by
+--crs, or else the one all the grids share. Grids in other systems are
+reprojected with PROJ's cs2cs (or the program given by --cs2cs, which must
+take the same arguments), so the target must be a state plane system which
+petra-grid can identify. Grids in unknown systems are reported and left
+out.
+
+Existing files are never overwritten unless --force is given. With
+--dry-run, the files which would be written are listed, but nothing is
+written.";
+
+const CATALOG: &str = "catalog.json";
+
+// the program used to reproject grids, unless --cs2cs says otherwise
+const CS2CS: &str = "cs2cs";
+
+// a horizon's grids, and the paths they were read from
+#[derive(Default)]
+struct Horizon {
+    sources: Vec<String>,
+    grids: Vec<Grid>,
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    let mut out_dir = None;
+    let mut target = None;
+    let mut cs2cs = CS2CS.to_string();
+    let mut dry_run = false;
+    let mut force = false;
+    let mut inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out-dir" => match args.next() {
+                Some(dir) => out_dir = Some(PathBuf::from(dir)),
+                None => return report::usage(USAGE),
+            },
+            "--crs" => match args.next().map(|c| epsg_code(c)) {
+                Some(Some(code)) => target = Some(code),
+                _ => return report::usage(USAGE),
+            },
+            "--cs2cs" => match args.next() {
+                Some(program) => cs2cs = program.clone(),
+                None => return report::usage(USAGE),
+            },
+            "--dry-run" => dry_run = true,
+            "--force" => force = true,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    let Some(out_dir) = out_dir.filter(|_| !inputs.is_empty()) else {
+        return report::usage(USAGE);
+    };
+
+    // read every grid, resolving its coordinate system
+    let mut paths = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let (found, errors) = find_grd_files(&input);
+            for (path, e) in errors {
+                report::error("reading directory",
+                  &path.display().to_string(), &e);
+            }
+            paths.extend(found);
+        } else {
+            paths.push(input);
+        }
+    }
+    let mut resolved = Vec::new();
+    for path in paths {
+        let name = path.display().to_string();
+        let grid = match File::open(&path).map_err(Error::from)
+          .and_then(|mut f| Grid::read(&mut f)) {
+            Ok(grid) => grid,
+            Err(e) => {
+                report::error("reading", &name, &e);
+                continue;
+            },
+        };
+        match grid.epsg() {
+            Some(code) => resolved.push((name, code, grid)),
+            None => report::failure("resolving CRS", &name, "unknown_crs",
+              &format!("can't identify coordinate system (projection {:?}, \
+                datum {:?})", grid.projection, grid.datum),
+              report::EXIT_INVALID),
+        }
+    }
+
+    let target = match target {
+        Some(code) => code,
+        None => {
+            let codes = resolved.iter()
+              .map(|(_, code, _)| *code)
+              .collect::<HashSet<_>>();
+            match codes.into_iter().collect::<Vec<_>>()[..] {
+                [code] => code,
+                [] => return report::exit_code(),
+                ref codes => return report::usage(&format!(
+                  "Error: grids are in several coordinate systems (EPSG {}); \
+                   choose one with --crs", codes.iter()
+                     .map(u32::to_string).collect::<Vec<_>>().join(", "))),
+            }
+        },
+    };
+
+    let mut horizons: BTreeMap<String, Horizon> = BTreeMap::new();
+    for (name, code, grid) in resolved {
+        let grid = if code == target {
+            grid
+        } else {
+            match reproject(&grid, &cs2cs, code, target) {
+                Ok(grid) => grid,
+                Err(e) => {
+                    report::error("reprojecting", &name, &e);
+                    continue;
+                },
+            }
+        };
+        let horizon = horizon_name(&grid, Path::new(&name));
+        let entry = horizons.entry(horizon).or_default();
+        entry.sources.push(name);
+        entry.grids.push(grid);
+    }
+
+    // output files for each horizon, refusing to clobber anything
+    let mut outputs = Vec::new();
+    let mut used = HashSet::new();
+    for (horizon, entry) in horizons {
+        let file = format!("{}.tif", file_stem(&horizon));
+        if !used.insert(file.to_ascii_lowercase()) {
+            report::failure("publishing", &horizon, "duplicate_horizon",
+              &format!("another horizon is already published as {}", file),
+              report::EXIT_INVALID);
+            continue;
+        }
+        outputs.push((horizon, file, entry));
+    }
+    if outputs.is_empty() {
+        return report::exit_code();
+    }
+    let catalog = out_dir.join(CATALOG);
+    if !force {
+        let existing = outputs.iter()
+          .map(|(_, file, _)| out_dir.join(file))
+          .chain([catalog.clone()])
+          .filter(|p| p.exists())
+          .collect::<Vec<_>>();
+        for out in &existing {
+            report::failure("writing", &out.display().to_string(),
+              "would_overwrite", "refusing to overwrite existing file \
+               (use --force to overwrite)", report::EXIT_USAGE);
+        }
+        if !existing.is_empty() {
+            return report::exit_code();
+        }
+    }
+
+    if dry_run {
+        for (horizon, file, entry) in &outputs {
+            println!("would write {} (horizon {}, from {} grid{})",
+              out_dir.join(file).display(), horizon, entry.grids.len(),
+              if entry.grids.len() == 1 { "" } else { "s" });
+        }
+        println!("would write {}", catalog.display());
+        return report::exit_code();
+    }
+
+    if let Err(e) = fs::create_dir_all(&out_dir) {
+        report::error("creating directory", &out_dir.display().to_string(),
+          &e.into());
+        return report::exit_code();
+    }
+    let mut items = Vec::new();
+    for (horizon, file, entry) in outputs {
+        let out = out_dir.join(&file);
+        let grids = entry.grids.iter().collect::<Vec<_>>();
+        let result = Grid::mosaic(&grids).and_then(|mosaic| {
+            write_cog(&mosaic, &mut create_output(&out, force)?)?;
+            Ok(mosaic)
+        });
+        match result {
+            Ok(mosaic) => {
+                println!("{}", out.display());
+                items.push(catalog_item(&horizon, &file, &entry.sources,
+                  &mosaic));
+            },
+            Err(e) => report::error("publishing", &out.display().to_string(),
+              &e),
+        }
+    }
+
+    let json = format!("{{\"crs\":{},\"horizons\":[{}]}}\n",
+      json_string(&format!("EPSG:{}", target)), items.join(","));
+    let written = create_output(&catalog, force)
+      .and_then(|mut f| f.write_all(json.as_bytes()));
+    match written {
+        Ok(()) => println!("{}", catalog.display()),
+        Err(e) => report::error("writing", &catalog.display().to_string(),
+          &e.into()),
+    }
+    report::exit_code()
+}
+
+// reproject a grid between EPSG coordinate systems with cs2cs, and label
+// it with the target system
+fn reproject(grid: &Grid, cs2cs: &str, from: u32, to: u32)
+  -> Result<Grid, Error> {
+    let (projection, datum, xyunits) = epsg::coordinate_system(to)
+      .ok_or_else(|| Error::InvalidArgument(format!(
+        "can't reproject to EPSG:{}, which isn't a state plane system \
+         petra-grid can identify", to)))?;
+    let mut grid = grid.transform_xy_batched(
+      |points| run_cs2cs(cs2cs, from, to, points),
+      |points| run_cs2cs(cs2cs, to, from, points))?;
+    grid.projection = projection;
+    grid.datum = datum.to_string();
+    grid.xyunits = xyunits;
+    Ok(grid)
+}
+
+// map points between EPSG coordinate systems with one run of cs2cs, which
+// marks the points it can't map with "*"
+fn run_cs2cs(program: &str, from: u32, to: u32, points: &[(f64, f64)])
+  -> Result<Vec<Option<(f64, f64)>>, Error> {
+    let mut child = Command::new(program)
+      .args(["-f", "%.9f", &format!("EPSG:{}", from), &format!("EPSG:{}", to)])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()?;
+
+    /* the input is written from another thread, so that cs2cs can't stall
+     * writing its output while we're still writing its input */
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let input = points.iter()
+      .map(|(x, y)| format!("{} {}\n", x, y))
+      .collect::<String>();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    let written = writer.join().expect("cs2cs input thread panicked");
+    if !output.status.success() {
+        return Err(Error::IOError(io::Error::other(format!(
+          "{} failed ({}): {}", program, output.status,
+          String::from_utf8_lossy(&output.stderr).trim()))));
+    }
+    written?;
+
+    let mapped = String::from_utf8_lossy(&output.stdout).lines()
+      .map(|line| {
+          let mut fields = line.split_ascii_whitespace()
+            .map(|f| f.parse::<f64>().ok());
+          match (fields.next(), fields.next()) {
+              (Some(x), Some(y)) => x.zip(y),
+              _ => None,
+          }
+      })
+      .collect::<Vec<_>>();
+    if mapped.len() != points.len() {
+        return Err(Error::IOError(io::Error::other(format!(
+          "{} mapped {} points, expected {}", program, mapped.len(),
+          points.len()))));
+    }
+    Ok(mapped)
+}
+
+// an EPSG code, as "32039" or "EPSG:32039"
+fn epsg_code(s: &str) -> Option<u32> {
+    let s = s.trim();
+    let code = match s.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("epsg:") => &s[5..],
+        _ => s,
+    };
+    code.parse().ok()
+}
+
+// a grid's horizon: its name, or (for unnamed grids) its file's name
+fn horizon_name(grid: &Grid, path: &Path) -> String {
+    match grid.name.trim() {
+        "" => path.file_stem()
+          .map(|s| s.to_string_lossy().into_owned())
+          .unwrap_or_default(),
+        name => name.to_string(),
+    }
+}
+
+// a horizon name made safe for use as a file name
+fn file_stem(horizon: &str) -> String {
+    horizon.chars()
+      .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+          c
+      } else {
+          '_'
+      })
+      .collect()
+}
+
+fn catalog_item(horizon: &str, file: &str, sources: &[String], grid: &Grid)
+  -> String {
+    let number = |v: f64| if v.is_finite() {
+        v.to_string()
+    } else {
+        "null".to_string()
+    };
+    let sources = sources.iter()
+      .map(|s| json_string(s))
+      .collect::<Vec<_>>();
+    let mut item = String::new();
+    write!(item, "{{\"horizon\":{},\"file\":{},\"sources\":[{}],\
+      \"rows\":{},\"columns\":{},\"bbox\":[{},{},{},{}],\"xstep\":{},\
+      \"ystep\":{},\"zmin\":{},\"zmax\":{},\"xyunits\":{},\"zunits\":{}}}",
+      json_string(horizon), json_string(file), sources.join(","), grid.rows,
+      grid.columns, number(grid.xmin), number(grid.ymin), number(grid.xmax),
+      number(grid.ymax), number(grid.xstep), number(grid.ystep),
+      number(grid.zmin), number(grid.zmax),
+      json_string(&format!("{:?}", grid.xyunits)),
+      json_string(&format!("{:?}", grid.zunits))).expect("write to String");
+    item
+}
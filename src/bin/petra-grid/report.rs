@@ -0,0 +1,153 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! error reporting, as human-readable text or (with `--error-format json`)
+//! as one JSON record per line, plus the exit codes which go with them
+
+use std::{
+    fmt::Write,
+    process::ExitCode,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use petra_grid::{Error, ErrorCategory, Warning};
+
+/// success
+pub const EXIT_OK: u8 = 0;
+/// a file couldn't be read or written
+pub const EXIT_IO: u8 = 1;
+/// the command line was invalid
+pub const EXIT_USAGE: u8 = 2;
+/// a file was read but its contents were invalid
+pub const EXIT_INVALID: u8 = 3;
+/// a check (e.g. a checksum, or strict validation) failed
+pub const EXIT_CHECK: u8 = 4;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static STATUS: AtomicU8 = AtomicU8::new(EXIT_OK);
+
+/// switch between text (the default) and JSON error records
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// the exit code for the failures reported so far; when several kinds of
+/// failure occur, the highest code wins
+pub fn exit_code() -> ExitCode {
+    ExitCode::from(status())
+}
+
+/// the numeric exit code for the failures reported so far
+pub fn status() -> u8 {
+    STATUS.load(Ordering::Relaxed)
+}
+
+/// report a failure to read, write, or otherwise process (the `action`) a
+/// file
+pub fn error(action: &str, path: &str, e: &Error) {
+    let code = match e.category() {
+        ErrorCategory::Io
+          | ErrorCategory::Environment
+          | ErrorCategory::Other => EXIT_IO,
+        ErrorCategory::InvalidArgument => EXIT_USAGE,
+        _ => EXIT_INVALID,
+    };
+    emit("error", action, Some(path), (e.kind(), Some(e.category())),
+      e.offset(), &e.to_string(), code);
+}
+
+/// report a failure which isn't a library error
+pub fn failure(action: &str, path: &str, kind: &str, message: &str,
+  code: u8) {
+    emit("error", action, Some(path), (kind, None), None, message, code);
+}
+
+/// report a problem which doesn't cause the command to fail
+pub fn warning(action: &str, path: &str, w: &Warning) {
+    emit("warning", action, Some(path), (w.kind(), Some(w.category())),
+      Some(w.offset()), &w.to_string(), EXIT_OK);
+}
+
+/// report a usage error, returning the corresponding exit code
+pub fn usage(message: &str) -> ExitCode {
+    emit("error", "parsing arguments", None, ("usage", None), None, message,
+      EXIT_USAGE);
+    exit_code()
+}
+
+// `kind` is the kind of problem, and its category (if it has one)
+fn emit(level: &str, action: &str, path: Option<&str>,
+  (kind, category): (&str, Option<ErrorCategory>), offset: Option<u64>,
+  message: &str, code: u8) {
+    STATUS.fetch_max(code, Ordering::Relaxed);
+
+    if !JSON.load(Ordering::Relaxed) {
+        match (level, path) {
+            (_, None) => eprintln!("{}", message),
+            ("warning", Some(path)) =>
+                eprintln!("Warning {} {}: {}", action, path, message),
+            (_, Some(path)) =>
+                eprintln!("Error {} {}: {}", action, path, message),
+        }
+        return;
+    }
+
+    let mut record = format!("{{\"level\":{},\"action\":{}",
+      json_string(level), json_string(action));
+    match path {
+        Some(path) => write!(record, ",\"path\":{}", json_string(path)),
+        None => write!(record, ",\"path\":null"),
+    }.expect("write to String");
+    write!(record, ",\"kind\":{}", json_string(kind)).expect("write to String");
+    match category {
+        Some(category) =>
+            write!(record, ",\"category\":{}", json_string(category.name())),
+        None => write!(record, ",\"category\":null"),
+    }.expect("write to String");
+    match offset {
+        Some(offset) => write!(record, ",\"offset\":{}", offset),
+        None => write!(record, ",\"offset\":null"),
+    }.expect("write to String");
+    write!(record, ",\"message\":{},\"exit_code\":{}}}",
+      json_string(message), code).expect("write to String");
+    eprintln!("{}", record);
+}
+
+/// quote and escape a string for JSON
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(quoted, "\\u{:04x}", c as u32).expect("write to String");
+            },
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
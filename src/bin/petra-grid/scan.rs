@@ -0,0 +1,52 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid scan`: index the grids in a directory tree
+
+use std::process::ExitCode;
+
+use petra_grid::scan_headers;
+
+use crate::report;
+
+pub fn run(args: &[String]) -> ExitCode {
+    let dir = match args {
+        [dir] => dir,
+        _ => return report::usage("Usage: petra-grid scan <directory>"),
+    };
+
+    println!("path\tname\trows\tcolumns\ttriangles\txmin\txmax\tymin\tymax\t\
+      xyunits\tzunits\tprojection\tdatum\tcreated");
+
+    for (path, header) in scan_headers(dir) {
+        match header {
+            Ok(h) => println!(
+              "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:?}\t{:?}\t{}\t{}\t{}",
+              path.display(), h.name, h.rows, h.columns, h.n_triangles,
+              h.xmin, h.xmax, h.ymin, h.ymax, h.xyunits, h.zunits,
+              h.projection, h.datum, h.created_date),
+            Err(e) =>
+                report::error("reading", &path.display().to_string(), &e),
+        }
+    }
+
+    report::exit_code()
+}
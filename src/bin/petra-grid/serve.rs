@@ -0,0 +1,456 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid serve`: a read-only HTTP server for a directory of grids
+//!
+//! every GRD file directly within the directory is read at startup, and
+//! served under `/grids/<name>` (the file's name, without its extension):
+//!
+//! - `/grids`: a JSON array of each grid's metadata
+//! - `/grids/<name>`: the grid's metadata, as JSON
+//! - `/grids/<name>/grd`: the GRD file itself (honoring `Range` requests)
+//! - `/grids/<name>/render.png?colormap=<name>&hillshade=<bool>`: a north-up
+//!   render, one pixel per node (triangular grids are rasterized first)
+//! - `/grids/<name>/contours.geojson?interval=<z>`: contour lines
+//! - `/grids/<name>/sample?x=<x>&y=<y>`: the *z* value at a point
+//! - `/grids/<name>/tiles/<z>/<x>/<y>.png`: XYZ tiles (see [tiles]), also
+//!   offered to WMTS clients through `/wmts`
+//!
+//! the same grids are offered as OGC API Coverages collections, starting
+//! from the landing page at `/` (see [coverages])
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Arc, OnceLock},
+};
+
+use ndarray::{ArcArray2, Array2};
+
+use axum::{
+    Router,
+    extract::{Path as UrlPath, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use petra_grid::{
+    Colormap,
+    Error,
+    Grid,
+    GridData,
+    GridSet,
+    export::geojson::write_contours_geojson,
+};
+
+use crate::report::{self, json_string};
+
+mod coverages;
+mod tiles;
+
+const SERVE_USAGE: &str = "\
+Usage: petra-grid serve [--bind <address:port>] <directory>
+
+Serves every GRD file in the directory (by default, on 127.0.0.1:8080):
+  /grids                          metadata of every grid, as JSON
+  /grids/<name>                   metadata of one grid, as JSON
+  /grids/<name>/grd               the GRD file (with Range support)
+  /grids/<name>/render.png        a north-up render; query parameters
+                                  colormap (greyscale, terrain, or
+                                  viridis) and hillshade (true or false)
+  /grids/<name>/contours.geojson  contour lines; query parameter interval
+                                  (default: a tenth of the z range)
+  /grids/<name>/sample            the z value at a point; query parameters
+                                  x and y
+  /grids/<name>/tiles/<z>/<x>/<y>.png
+                                  XYZ tiles, in the grid's own coordinate
+                                  system (with the same query parameters
+                                  as render.png)
+  /wmts                           WMTS (KVP GetCapabilities and GetTile)
+  /                               OGC API Coverages landing page, leading
+                                  to /conformance and /collections";
+
+const DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+const HILLSHADE_AZIMUTH: f64 = 315.0;
+const HILLSHADE_ALTITUDE: f64 = 45.0;
+const DEFAULT_CONTOURS: f64 = 10.0;
+
+struct Served {
+    path: PathBuf,
+    grid: Grid,
+    // the (rasterized) grid and its hillshade, computed on first use
+    raster: OnceLock<ArcArray2<f64>>,
+    shade: OnceLock<Option<Array2<f64>>>,
+}
+
+impl Served {
+    fn new(path: PathBuf, grid: Grid) -> Served {
+        Served { path, grid, raster: OnceLock::new(), shade: OnceLock::new() }
+    }
+
+    fn raster(&self) -> &ArcArray2<f64> {
+        self.raster.get_or_init(|| match self.grid.rasterize().data {
+            GridData::Rectangular(arr) => arr,
+            GridData::Triangular(_) => unreachable!("rasterized grid"),
+        })
+    }
+
+    fn shade(&self) -> Option<&Array2<f64>> {
+        self.shade.get_or_init(|| self.grid.rasterize()
+          .hillshade(HILLSHADE_AZIMUTH, HILLSHADE_ALTITUDE)
+          .ok()).as_ref()
+    }
+}
+
+// how to color a grid, from the `colormap` and `hillshade` query parameters
+struct Style {
+    colormap: Colormap,
+    hillshade: bool,
+}
+
+impl Style {
+    fn from_query(query: &HashMap<String, String>) -> Result<Style, String> {
+        let colormap = match query.get("colormap").map(String::as_str) {
+            None | Some("viridis") => Colormap::viridis(),
+            Some("greyscale") => Colormap::greyscale(),
+            Some("terrain") => Colormap::terrain(),
+            Some(other) => return Err(format!("unknown colormap {}", other)),
+        };
+        let hillshade = match query.get("hillshade").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => return Err(format!("invalid hillshade {}", other)),
+        };
+        Ok(Style { colormap, hillshade })
+    }
+
+    // the RGBA pixel for the node at (row, column); missing values are
+    // transparent
+    fn pixel(&self, served: &Served, j: usize, i: usize) -> [u8; 4] {
+        let z = served.raster()[(j, i)];
+        if z.is_nan() {
+            return [0, 0, 0, 0];
+        }
+        let grid = &served.grid;
+        let mut rgb = self.colormap.color_for(z, grid.zmin, grid.zmax);
+        if self.hillshade {
+            let s = served.shade().map_or(f64::NAN, |shade| shade[(j, i)]);
+            if !s.is_nan() {
+                for c in &mut rgb {
+                    *c = (*c as f64 * (0.35 + 0.65 * s)) as u8;
+                }
+            }
+        }
+        [rgb[0], rgb[1], rgb[2], 255]
+    }
+}
+
+type Grids = Arc<BTreeMap<String, Served>>;
+
+pub fn run(args: &[String]) -> ExitCode {
+    let (bind, dir) = match args {
+        [dir] => (DEFAULT_BIND, dir),
+        [flag, bind, dir] if flag == "--bind" => (bind.as_str(), dir),
+        _ => return report::usage(SERVE_USAGE),
+    };
+    let addr: SocketAddr = match bind.parse() {
+        Ok(addr) => addr,
+        Err(_) => return report::usage(&format!(
+          "Invalid address: {}\n{}", bind, SERVE_USAGE)),
+    };
+
+    let (set, errors) = match GridSet::read_dir(dir) {
+        Ok(result) => result,
+        Err(e) => {
+            report::error("reading directory", dir, &e.into());
+            return report::exit_code();
+        },
+    };
+    for (path, e) in errors {
+        report::error("reading", &path.display().to_string(), &e);
+    }
+
+    let mut grids = BTreeMap::new();
+    for (path, grid) in set {
+        let name = grid_name(&path);
+        if grids.contains_key(&name) {
+            report::failure("serving", &path.display().to_string(), "io",
+              &format!("another grid is already served as {}", name), 1);
+            continue;
+        }
+        grids.insert(name, Served::new(path, grid));
+    }
+
+    let app = Router::new()
+      .route("/grids", get(list))
+      .route("/grids/:name", get(metadata))
+      .route("/grids/:name/grd", get(grd))
+      .route("/grids/:name/render.png", get(render))
+      .route("/grids/:name/contours.geojson", get(contours))
+      .route("/grids/:name/sample", get(sample))
+      .route("/grids/:name/tiles/:z/:x/:y", get(tiles::xyz))
+      .route("/wmts", get(tiles::wmts))
+      .route("/", get(coverages::landing))
+      .route("/conformance", get(coverages::conformance))
+      .route("/collections", get(coverages::collections))
+      .route("/collections/:name", get(coverages::collection))
+      .route("/collections/:name/coverage", get(coverages::coverage))
+      .with_state(Arc::new(grids));
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            report::error("starting server", bind, &e.into());
+            return report::exit_code();
+        },
+    };
+    let result = runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!("serving {} on http://{}", dir, addr);
+        axum::serve(listener, app).await
+    });
+    if let Err(e) = result {
+        report::error("serving", bind, &e.into());
+    }
+    report::exit_code()
+}
+
+// the name a grid is served under: its file name, without the extension
+fn grid_name(path: &Path) -> String {
+    path.file_stem()
+      .map(|s| s.to_string_lossy().into_owned())
+      .unwrap_or_default()
+}
+
+async fn list(State(grids): State<Grids>) -> Response {
+    let items = grids.iter()
+      .map(|(name, served)| metadata_json(name, &served.grid))
+      .collect::<Vec<_>>();
+    json(format!("[{}]", items.join(",")))
+}
+
+async fn metadata(State(grids): State<Grids>, UrlPath(name): UrlPath<String>)
+  -> Response {
+    match grids.get(&name) {
+        Some(served) => json(metadata_json(&name, &served.grid)),
+        None => not_found(&name),
+    }
+}
+
+async fn grd(State(grids): State<Grids>, UrlPath(name): UrlPath<String>,
+  headers: HeaderMap) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+
+    let octets = [(header::CONTENT_TYPE, "application/octet-stream"),
+      (header::ACCEPT_RANGES, "bytes")];
+    let Some(range) = headers.get(header::RANGE) else {
+        return match fs::read(&served.path) {
+            Ok(bytes) => (octets, bytes).into_response(),
+            Err(e) => internal_error(&e.into()),
+        };
+    };
+
+    // read only the requested bytes
+    let mut file = match File::open(&served.path) {
+        Ok(file) => file,
+        Err(e) => return internal_error(&e.into()),
+    };
+    let len = match file.metadata() {
+        Ok(metadata) => metadata.len() as usize,
+        Err(e) => return internal_error(&e.into()),
+    };
+    match range.to_str().ok().and_then(|r| byte_range(r, len)) {
+        Some((start, end)) => {
+            let mut bytes = vec![0u8; end - start];
+            let read = file.seek(SeekFrom::Start(start as u64))
+              .and_then(|_| file.read_exact(&mut bytes));
+            if let Err(e) = read {
+                return internal_error(&e.into());
+            }
+            let content_range = format!("bytes {}-{}/{}", start, end - 1,
+              len);
+            (StatusCode::PARTIAL_CONTENT, octets,
+              [(header::CONTENT_RANGE, content_range)], bytes)
+              .into_response()
+        },
+        None => (StatusCode::RANGE_NOT_SATISFIABLE,
+          [(header::CONTENT_RANGE, format!("bytes */{}", len))])
+          .into_response(),
+    }
+}
+
+async fn render(State(grids): State<Grids>, UrlPath(name): UrlPath<String>,
+  Query(query): Query<HashMap<String, String>>) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+    let style = match Style::from_query(&query) {
+        Ok(style) => style,
+        Err(message) => return bad_request(&message),
+    };
+
+    // north up, one pixel per node
+    let (rows, cols) = served.raster().dim();
+    let mut pixels = Vec::with_capacity(rows * cols * 4);
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            pixels.extend_from_slice(&style.pixel(served, j, i));
+        }
+    }
+    png_response(cols, rows, &pixels)
+}
+
+async fn contours(State(grids): State<Grids>, UrlPath(name): UrlPath<String>,
+  Query(query): Query<HashMap<String, String>>) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+    let grid = &served.grid;
+    let interval = match query.get("interval") {
+        Some(interval) => match interval.parse::<f64>() {
+            Ok(v) if v > 0.0 && v.is_finite() => v,
+            _ => return bad_request(&format!(
+              "invalid interval {}", interval)),
+        },
+        None => (grid.zmax - grid.zmin) / DEFAULT_CONTOURS,
+    };
+
+    let levels = match grid.contour_levels(interval) {
+        Ok(levels) => levels,
+        Err(e) => return bad_request(&e.to_string()),
+    };
+
+    let mut body = Vec::new();
+    match write_contours_geojson(grid, &levels, &mut body) {
+        Ok(()) => ([(header::CONTENT_TYPE, "application/geo+json")], body)
+          .into_response(),
+        Err(e) => internal_error(&e),
+    }
+}
+
+async fn sample(State(grids): State<Grids>, UrlPath(name): UrlPath<String>,
+  Query(query): Query<HashMap<String, String>>) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+    let coordinate = |key: &str| query.get(key)
+      .and_then(|v| v.parse::<f64>().ok())
+      .filter(|v| v.is_finite());
+    let (Some(x), Some(y)) = (coordinate("x"), coordinate("y")) else {
+        return bad_request("x and y must be given as numbers");
+    };
+    let z = match served.grid.sample(x, y) {
+        Some(z) => z.to_string(),
+        None => "null".to_string(),
+    };
+    json(format!("{{\"x\":{},\"y\":{},\"z\":{}}}", x, y, z))
+}
+
+fn metadata_json(name: &str, grid: &Grid) -> String {
+    let mut s = String::new();
+    let kind = match grid.data {
+        GridData::Rectangular(_) => "rectangular",
+        GridData::Triangular(_) => "triangular",
+    };
+    write!(s, "{{\"name\":{},\"title\":{},\"kind\":\"{}\",\"rows\":{},\
+      \"columns\":{},\"n_triangles\":{},\"xmin\":{},\"xmax\":{},\
+      \"ymin\":{},\"ymax\":{},\"xstep\":{},\"ystep\":{},\"zmin\":{},\
+      \"zmax\":{},\"xyunits\":{},\"zunits\":{},\"projection\":{},\
+      \"datum\":{},\"source_data\":{},\"created_date\":{}}}",
+      json_string(name), json_string(&grid.name), kind, grid.rows,
+      grid.columns, grid.n_triangles, number(grid.xmin), number(grid.xmax),
+      number(grid.ymin), number(grid.ymax), number(grid.xstep),
+      number(grid.ystep), number(grid.zmin), number(grid.zmax),
+      json_string(&format!("{:?}", grid.xyunits)),
+      json_string(&format!("{:?}", grid.zunits)),
+      json_string(&grid.projection), json_string(&grid.datum),
+      json_string(&grid.source_data),
+      json_string(&grid.created_date.to_string()))
+      .expect("write to String");
+    s
+}
+
+// JSON has no NaN or infinities
+fn number(v: f64) -> String {
+    if v.is_finite() { v.to_string() } else { "null".to_string() }
+}
+
+// a single HTTP byte range ("bytes=a-b", "bytes=a-", or "bytes=-n"), as a
+// half-open range of offsets, if it's satisfiable
+fn byte_range(header: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = header.strip_prefix("bytes=")?.trim().split_once('-')?;
+    let (start, end) = match (start, end) {
+        ("", suffix) => {
+            let n = suffix.parse::<usize>().ok()?;
+            (len.saturating_sub(n), len)
+        },
+        (start, "") => (start.parse().ok()?, len),
+        (start, end) => (start.parse().ok()?,
+          end.parse::<usize>().ok()?.saturating_add(1).min(len)),
+    };
+    (start < end).then_some((start, end))
+}
+
+// encode RGBA pixels as a PNG
+fn png_response(width: usize, height: usize, pixels: &[u8]) -> Response {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32,
+      height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let result = encoder.write_header()
+      .and_then(|mut writer| writer.write_image_data(pixels));
+    match result {
+        Ok(()) => ([(header::CONTENT_TYPE, "image/png")], png)
+          .into_response(),
+        Err(e) => internal_error(&Error::InvalidInput(e.to_string())),
+    }
+}
+
+fn json(body: String) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+fn error_json(status: StatusCode, message: &str) -> Response {
+    (status, json(format!("{{\"error\":{}}}", json_string(message))))
+      .into_response()
+}
+
+fn not_found(name: &str) -> Response {
+    error_json(StatusCode::NOT_FOUND, &format!("no grid named {}", name))
+}
+
+fn bad_request(message: &str) -> Response {
+    error_json(StatusCode::BAD_REQUEST, message)
+}
+
+fn internal_error(e: &Error) -> Response {
+    error_json(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
@@ -0,0 +1,167 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! a basic OGC API Coverages interface to served grids
+//!
+//! each grid is a collection, whose coverage is delivered as CoverageJSON
+//! (triangular grids are rasterized first), in the grid's own coordinate
+//! system; subsetting and other encodings aren't supported, but the
+//! collection links to the PNG render and to the XYZ tiles
+
+use std::fmt::Write as _;
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+};
+
+use petra_grid::Grid;
+
+use crate::report::json_string;
+
+use super::{Grids, Served, json, not_found, number, tiles::base_url};
+
+const CONFORMANCE: &[&str] = &[
+    "http://www.opengis.net/spec/ogcapi-common-1/1.0/conf/core",
+    "http://www.opengis.net/spec/ogcapi-common-1/1.0/conf/json",
+    "http://www.opengis.net/spec/ogcapi-common-2/1.0/conf/collections",
+    "http://www.opengis.net/spec/ogcapi-coverages-1/1.0/conf/core",
+    "http://www.opengis.net/spec/ogcapi-coverages-1/1.0/conf/coveragejson",
+];
+
+const COVERAGE_JSON: &str = "application/prs.coverage+json";
+
+/// `/`: the landing page
+pub(super) async fn landing(headers: HeaderMap) -> Response {
+    let base = base_url(&headers);
+    json(format!("{{\"title\":\"petra-grid\",\"description\":\
+      \"Petra grids as OGC API coverages\",\"links\":[{},{},{}]}}",
+      link(&format!("{}/", base), "self", "application/json"),
+      link(&format!("{}/conformance", base),
+        "http://www.opengis.net/def/rel/ogc/1.0/conformance",
+        "application/json"),
+      link(&format!("{}/collections", base),
+        "http://www.opengis.net/def/rel/ogc/1.0/data", "application/json")))
+}
+
+/// `/conformance`: the conformance classes we (basically) implement
+pub(super) async fn conformance() -> Response {
+    let classes = CONFORMANCE.iter()
+      .map(|c| json_string(c))
+      .collect::<Vec<_>>();
+    json(format!("{{\"conformsTo\":[{}]}}", classes.join(",")))
+}
+
+/// `/collections`: every grid
+pub(super) async fn collections(State(grids): State<Grids>,
+  headers: HeaderMap) -> Response {
+    let base = base_url(&headers);
+    let items = grids.iter()
+      .map(|(name, served)| collection_json(&base, name, served))
+      .collect::<Vec<_>>();
+    json(format!("{{\"links\":[{}],\"collections\":[{}]}}",
+      link(&format!("{}/collections", base), "self", "application/json"),
+      items.join(",")))
+}
+
+/// `/collections/<name>`: one grid
+pub(super) async fn collection(State(grids): State<Grids>,
+  UrlPath(name): UrlPath<String>, headers: HeaderMap) -> Response {
+    match grids.get(&name) {
+        Some(served) => json(collection_json(&base_url(&headers), &name,
+          served)),
+        None => not_found(&name),
+    }
+}
+
+/// `/collections/<name>/coverage`: the grid as CoverageJSON
+pub(super) async fn coverage(State(grids): State<Grids>,
+  UrlPath(name): UrlPath<String>) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+    let grid = &served.grid;
+    let raster = served.raster();
+    let (rows, cols) = raster.dim();
+
+    let mut system = String::from("{\"type\":\"ProjectedCRS\"");
+    if let Some(uri) = crs_uri(grid) {
+        write!(system, ",\"id\":{}", json_string(&uri))
+          .expect("write to String");
+    }
+    system.push('}');
+
+    // rows run south to north, as the y axis does
+    let values = raster.iter()
+      .map(|&z| number(z))
+      .collect::<Vec<_>>();
+    let body = format!("{{\"type\":\"Coverage\",\"domain\":{{\"type\":\
+      \"Domain\",\"domainType\":\"Grid\",\"axes\":{{\
+      \"x\":{{\"start\":{},\"stop\":{},\"num\":{}}},\
+      \"y\":{{\"start\":{},\"stop\":{},\"num\":{}}}}},\
+      \"referencing\":[{{\"coordinates\":[\"x\",\"y\"],\"system\":{}}}]}},\
+      \"parameters\":{{\"z\":{{\"type\":\"Parameter\",\"description\":\
+      {{\"en\":{}}},\"unit\":{{\"label\":{{\"en\":{}}}}},\
+      \"observedProperty\":{{\"label\":{{\"en\":\"z\"}}}}}}}},\
+      \"ranges\":{{\"z\":{{\"type\":\"NdArray\",\"dataType\":\"float\",\
+      \"axisNames\":[\"y\",\"x\"],\"shape\":[{},{}],\"values\":[{}]}}}}}}",
+      number(grid.xmin), number(grid.xmax), cols, number(grid.ymin),
+      number(grid.ymax), rows, system, json_string(&grid.name),
+      json_string(&format!("{:?}", grid.zunits)), rows, cols,
+      values.join(","));
+    ([(header::CONTENT_TYPE, COVERAGE_JSON)], body).into_response()
+}
+
+fn collection_json(base: &str, name: &str, served: &Served) -> String {
+    let grid = &served.grid;
+    let url = format!("{}/collections/{}", base, name);
+    let grid_url = format!("{}/grids/{}", base, name);
+    let crs = match crs_uri(grid) {
+        Some(uri) => json_string(&uri),
+        None => "null".to_string(),
+    };
+    // XYZ tile URLs are templates
+    let tiles_link = format!("{{\"href\":{},\"rel\":\"tiles\",\
+      \"type\":\"image/png\",\"templated\":true}}", json_string(&format!(
+        "{}/tiles/{{z}}/{{x}}/{{y}}.png", grid_url)));
+    format!("{{\"id\":{},\"title\":{},\"extent\":{{\"spatial\":{{\
+      \"bbox\":[[{},{},{},{}]],\"crs\":{}}}}},\"storageCrs\":{},\
+      \"links\":[{},{},{},{}]}}",
+      json_string(name), json_string(&grid.name), number(grid.xmin),
+      number(grid.ymin), number(grid.xmax), number(grid.ymax), crs, crs,
+      link(&url, "self", "application/json"),
+      link(&format!("{}/coverage", url),
+        "http://www.opengis.net/def/rel/ogc/1.0/coverage", COVERAGE_JSON),
+      link(&format!("{}/render.png", grid_url), "preview", "image/png"),
+      tiles_link)
+}
+
+// the OGC URI for a grid's coordinate system, if we know its EPSG code
+fn crs_uri(grid: &Grid) -> Option<String> {
+    grid.epsg()
+      .map(|code| format!("http://www.opengis.net/def/crs/EPSG/0/{}", code))
+}
+
+fn link(href: &str, rel: &str, media_type: &str) -> String {
+    format!("{{\"href\":{},\"rel\":{},\"type\":{}}}", json_string(href),
+      json_string(rel), json_string(media_type))
+}
@@ -0,0 +1,267 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! XYZ and WMTS tiles of served grids
+//!
+//! we don't implement projections, so rather than the usual web mercator
+//! tiles, each grid gets its own tile matrix set in its own coordinate
+//! system: at zoom level 0, a single 256-pixel tile covers a square sharing
+//! the grid's lower-left corner and its longer side, and each further level
+//! halves the tiles' size.
+//! columns count east and rows count south from the top-left corner, as
+//! in both XYZ and WMTS; clients need to be told the grid's coordinate
+//! system (as WMTS capabilities do, when we know its EPSG code)
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+};
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+};
+
+use petra_grid::{Grid, UnitOfMeasure};
+
+use super::{
+    Grids,
+    Served,
+    Style,
+    bad_request,
+    not_found,
+    png_response,
+};
+
+/// the width and height of tiles, in pixels
+pub const TILE_SIZE: usize = 256;
+
+// the finest level is the first at which a pixel is this fraction of a node
+const MAX_OVERSAMPLING: f64 = 4.0;
+
+// the pixel size assumed by WMTS scale denominators, in meters
+const WMTS_PIXEL_SIZE: f64 = 0.28e-3;
+
+const US_SURVEY_FOOT: f64 = 1200.0 / 3937.0;
+
+// the side of the (square) area covered by the level 0 tile
+fn extent(grid: &Grid) -> f64 {
+    (grid.xmax - grid.xmin).max(grid.ymax - grid.ymin)
+}
+
+// the finest zoom level offered for a grid
+fn max_zoom(grid: &Grid) -> u32 {
+    let node = grid.xstep.abs().min(grid.ystep.abs());
+    let pixel = extent(grid) / TILE_SIZE as f64;
+    if !(node > 0.0 && pixel > 0.0) {
+        return 0;
+    }
+    (pixel * MAX_OVERSAMPLING / node).log2().ceil().clamp(0.0, 24.0) as u32
+}
+
+// render the tile at (column, row) of a zoom level, or `None` if there's no
+// such tile
+fn render_tile(served: &Served, style: &Style, zoom: u32, column: u64,
+  row: u64) -> Option<Response> {
+    let grid = &served.grid;
+    if zoom > max_zoom(grid) || column >= 1 << zoom || row >= 1 << zoom {
+        return None;
+    }
+
+    let size = extent(grid) / (1u64 << zoom) as f64;
+    let pixel = size / TILE_SIZE as f64;
+    let left = grid.xmin + column as f64 * size;
+    let top = grid.ymin + extent(grid) - row as f64 * size;
+    let (rows, cols) = served.raster().dim();
+
+    // nearest node to the center of each pixel, if any
+    let node = |v: f64, min: f64, step: f64, n: usize| {
+        let k = ((v - min) / step).round();
+        (step != 0.0 && k >= 0.0 && k < n as f64).then_some(k as usize)
+    };
+    let mut pixels = Vec::with_capacity(TILE_SIZE * TILE_SIZE * 4);
+    for py in 0..TILE_SIZE {
+        let y = top - (py as f64 + 0.5) * pixel;
+        let j = node(y, grid.ymin, grid.ystep, rows);
+        for px in 0..TILE_SIZE {
+            let x = left + (px as f64 + 0.5) * pixel;
+            let i = node(x, grid.xmin, grid.xstep, cols);
+            match (j, i) {
+                (Some(j), Some(i)) =>
+                    pixels.extend_from_slice(&style.pixel(served, j, i)),
+                _ => pixels.extend_from_slice(&[0, 0, 0, 0]),
+            }
+        }
+    }
+    Some(png_response(TILE_SIZE, TILE_SIZE, &pixels))
+}
+
+/// `/grids/<name>/tiles/<z>/<x>/<y>.png`
+pub(super) async fn xyz(State(grids): State<Grids>,
+  UrlPath((name, zoom, column, row)): UrlPath<(String, u32, u64, String)>,
+  Query(query): Query<HashMap<String, String>>) -> Response {
+    let Some(served) = grids.get(&name) else {
+        return not_found(&name);
+    };
+    let Ok(row) = row.strip_suffix(".png").unwrap_or(&row).parse::<u64>()
+      else {
+        return bad_request(&format!("invalid tile row {}", row));
+    };
+    let style = match Style::from_query(&query) {
+        Ok(style) => style,
+        Err(message) => return bad_request(&message),
+    };
+    render_tile(served, &style, zoom, column, row)
+      .unwrap_or_else(|| not_found(&format!("{}/{}/{}/{}", name, zoom, column,
+        row)))
+}
+
+/// `/wmts`: key-value-pair `GetCapabilities` and `GetTile` requests
+pub(super) async fn wmts(State(grids): State<Grids>, headers: HeaderMap,
+  Query(query): Query<HashMap<String, String>>) -> Response {
+    // KVP parameter names are case-insensitive
+    let query = query.into_iter()
+      .map(|(k, v)| (k.to_ascii_lowercase(), v))
+      .collect::<HashMap<_, _>>();
+    let param = |key: &str| query.get(key).map(String::as_str);
+
+    if param("service").is_some_and(|s| !s.eq_ignore_ascii_case("wmts")) {
+        return bad_request("only the WMTS service is offered");
+    }
+    match param("request") {
+        None => capabilities(&grids, &headers),
+        Some(r) if r.eq_ignore_ascii_case("getcapabilities") =>
+            capabilities(&grids, &headers),
+        Some(r) if r.eq_ignore_ascii_case("gettile") => {
+            let (Some(layer), Some(zoom), Some(row), Some(column)) =
+              (param("layer"), param("tilematrix"), param("tilerow"),
+                param("tilecol")) else {
+                return bad_request(
+                  "GetTile needs LAYER, TILEMATRIX, TILEROW, and TILECOL");
+            };
+            let Some(served) = grids.get(layer) else {
+                return not_found(layer);
+            };
+            let (Ok(zoom), Ok(row), Ok(column)) = (zoom.parse::<u32>(),
+              row.parse::<u64>(), column.parse::<u64>()) else {
+                return bad_request("invalid tile address");
+            };
+            let style = match Style::from_query(&query) {
+                Ok(style) => style,
+                Err(message) => return bad_request(&message),
+            };
+            render_tile(served, &style, zoom, column, row)
+              .unwrap_or_else(|| bad_request("tile out of range"))
+        },
+        Some(other) => bad_request(&format!("unknown request {}", other)),
+    }
+}
+
+// the base URL of the server, as the client sees it
+pub(super) fn base_url(headers: &HeaderMap) -> String {
+    let host = headers.get(header::HOST)
+      .and_then(|h| h.to_str().ok())
+      .unwrap_or("localhost");
+    format!("http://{}", host)
+}
+
+// the OGC URN for a grid's coordinate system; grids in unknown systems are
+// offered in a local engineering system
+fn crs_urn(grid: &Grid) -> String {
+    match grid.epsg() {
+        Some(code) => format!("urn:ogc:def:crs:EPSG::{}", code),
+        None => "urn:ogc:def:crs:OGC:1.3:CRS1".to_string(),
+    }
+}
+
+fn capabilities(grids: &Grids, headers: &HeaderMap) -> Response {
+    let base = base_url(headers);
+    let mut layers = String::new();
+    let mut matrix_sets = String::new();
+    for (name, served) in grids.iter() {
+        let grid = &served.grid;
+        let name = xml_escape(name);
+        let crs = crs_urn(grid);
+        writeln!(layers, "<Layer><ows:Title>{}</ows:Title>\
+          <ows:Identifier>{}</ows:Identifier><ows:BoundingBox crs=\"{}\">\
+          <ows:LowerCorner>{} {}</ows:LowerCorner>\
+          <ows:UpperCorner>{} {}</ows:UpperCorner></ows:BoundingBox>\
+          <Style isDefault=\"true\"><ows:Identifier>default</ows:Identifier>\
+          </Style><Format>image/png</Format>\
+          <TileMatrixSetLink><TileMatrixSet>{}</TileMatrixSet>\
+          </TileMatrixSetLink><ResourceURL format=\"image/png\" \
+          resourceType=\"tile\" template=\"{}/grids/{}/tiles/{{TileMatrix}}/\
+          {{TileCol}}/{{TileRow}}.png\"/></Layer>",
+          xml_escape(&grid.name), name, crs, grid.xmin, grid.ymin, grid.xmax,
+          grid.ymax, name, base, name).expect("write to String");
+
+        let meters = match grid.xyunits {
+            UnitOfMeasure::Feet => US_SURVEY_FOOT,
+            _ => 1.0,
+        };
+        write!(matrix_sets, "<TileMatrixSet><ows:Identifier>{}\
+          </ows:Identifier><ows:SupportedCRS>{}</ows:SupportedCRS>",
+          name, crs).expect("write to String");
+        for zoom in 0..=max_zoom(grid) {
+            let n = 1u64 << zoom;
+            let pixel = extent(grid) / n as f64 / TILE_SIZE as f64;
+            write!(matrix_sets, "<TileMatrix><ows:Identifier>{}\
+              </ows:Identifier><ScaleDenominator>{}</ScaleDenominator>\
+              <TopLeftCorner>{} {}</TopLeftCorner><TileWidth>{}</TileWidth>\
+              <TileHeight>{}</TileHeight><MatrixWidth>{}</MatrixWidth>\
+              <MatrixHeight>{}</MatrixHeight></TileMatrix>", zoom,
+              pixel * meters / WMTS_PIXEL_SIZE, grid.xmin,
+              grid.ymin + extent(grid), TILE_SIZE, TILE_SIZE, n, n)
+              .expect("write to String");
+        }
+        matrix_sets.push_str("</TileMatrixSet>\n");
+    }
+
+    let xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+      <Capabilities xmlns=\"http://www.opengis.net/wmts/1.0\" \
+      xmlns:ows=\"http://www.opengis.net/ows/1.1\" \
+      xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.0.0\">\n\
+      <ows:ServiceIdentification><ows:Title>petra-grid</ows:Title>\
+      <ows:ServiceType>OGC WMTS</ows:ServiceType>\
+      <ows:ServiceTypeVersion>1.0.0</ows:ServiceTypeVersion>\
+      </ows:ServiceIdentification>\n\
+      <ows:OperationsMetadata>{}{}</ows:OperationsMetadata>\n\
+      <Contents>\n{}{}</Contents>\n</Capabilities>\n",
+      operation("GetCapabilities", &base), operation("GetTile", &base),
+      layers, matrix_sets);
+    ([(header::CONTENT_TYPE, "application/xml")], xml).into_response()
+}
+
+fn operation(name: &str, base: &str) -> String {
+    format!("<ows:Operation name=\"{}\"><ows:DCP><ows:HTTP>\
+      <ows:Get xlink:href=\"{}/wmts?\"><ows:Constraint name=\"GetEncoding\">\
+      <ows:AllowedValues><ows:Value>KVP</ows:Value></ows:AllowedValues>\
+      </ows:Constraint></ows:Get></ows:HTTP></ows:DCP></ows:Operation>",
+      name, base)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;")
+      .replace('"', "&quot;")
+}
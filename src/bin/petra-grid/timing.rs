@@ -0,0 +1,139 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! per-file throughput and total wall time for batch commands, so that
+//! nightly jobs can watch for performance regressions
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::report::json_string;
+
+// the time spent reading and writing one file
+#[derive(Default)]
+pub struct FileTiming {
+    pub path: String,
+    pub read_bytes: u64,
+    pub read_time: Duration,
+    pub written_bytes: u64,
+    pub write_time: Duration,
+}
+
+pub struct Timings {
+    started: Instant,
+    files: Vec<FileTiming>,
+}
+
+impl Timings {
+    // start the wall clock
+    pub fn start() -> Timings {
+        Timings { started: Instant::now(), files: Vec::new() }
+    }
+
+    pub fn record(&mut self, timing: FileTiming) {
+        self.files.push(timing);
+    }
+
+    // one line per file, then the totals
+    pub fn print(&self) {
+        for f in &self.files {
+            print!("{}: read {} in {}", f.path, megabytes(f.read_bytes),
+              rate_text(f.read_bytes, f.read_time));
+            if f.written_bytes > 0 {
+                print!("; wrote {} in {}", megabytes(f.written_bytes),
+                  rate_text(f.written_bytes, f.write_time));
+            }
+            println!();
+        }
+        let (read, written) = self.totals();
+        print!("total: {} file(s), read {}", self.files.len(),
+          megabytes(read));
+        if written > 0 {
+            print!(", wrote {}", megabytes(written));
+        }
+        println!(", wall time {:.3} s", self.elapsed().as_secs_f64());
+    }
+
+    // a JSON object with the totals and per-file details
+    pub fn json(&self) -> String {
+        let files = self.files.iter().map(|f| format!(
+          "{{\"path\":{},\"read_bytes\":{},\"read_seconds\":{},\
+           \"read_mb_per_s\":{},\"written_bytes\":{},\"write_seconds\":{},\
+           \"write_mb_per_s\":{}}}",
+          json_string(&f.path), f.read_bytes, f.read_time.as_secs_f64(),
+          rate_json(f.read_bytes, f.read_time), f.written_bytes,
+          f.write_time.as_secs_f64(),
+          rate_json(f.written_bytes, f.write_time)))
+          .collect::<Vec<_>>();
+        let (read, written) = self.totals();
+        format!("{{\n  \"files\": {},\n  \"read_bytes\": {},\n  \
+          \"written_bytes\": {},\n  \"wall_seconds\": {},\n  \
+          \"results\": [\n    {}\n  ]\n}}\n",
+          self.files.len(), read, written,
+          self.elapsed().as_secs_f64(), files.join(",\n    "))
+    }
+
+    // the wall time since the clock was started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn totals(&self) -> (u64, u64) {
+        self.files.iter()
+          .fold((0, 0), |(r, w), f| (r + f.read_bytes, w + f.written_bytes))
+    }
+}
+
+// the size of a file, or zero if it can't be determined
+pub fn file_size<P: AsRef<Path>>(path: P) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.2} MB", bytes as f64 / 1e6)
+}
+
+fn rate(bytes: u64, time: Duration) -> Option<f64> {
+    let secs = time.as_secs_f64();
+    if secs > 0.0 {
+        Some(bytes as f64 / 1e6 / secs)
+    } else {
+        None
+    }
+}
+
+fn rate_text(bytes: u64, time: Duration) -> String {
+    match rate(bytes, time) {
+        Some(rate) => format!("{:.3} s ({:.1} MB/s)", time.as_secs_f64(),
+          rate),
+        None => format!("{:.3} s", time.as_secs_f64()),
+    }
+}
+
+fn rate_json(bytes: u64, time: Duration) -> String {
+    match rate(bytes, time) {
+        Some(rate) => rate.to_string(),
+        None => "null".to_string(),
+    }
+}
@@ -0,0 +1,347 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid tui`: interactive terminal inspection of a grid
+//!
+//! the surface is drawn as a pseudo-heatmap, either in color (two grid
+//! "pixels" per character cell using half-block glyphs) or as an ASCII
+//! intensity ramp for terminals without color
+
+use std::{
+    fs::File,
+    io,
+    process::ExitCode,
+};
+
+use ndarray::ArcArray2;
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::Color,
+    text::Line,
+    widgets::{Block, Paragraph, Widget},
+    DefaultTerminal,
+    Frame,
+};
+
+use petra_grid::{Colormap, Error, Grid, GridData, Stats};
+
+use crate::report;
+
+const ASCII_RAMP: &[u8] = b".:-=+*#%@";
+const PAN_FRACTION: f64 = 0.1;
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Blocks,
+    Ascii,
+}
+
+struct App {
+    path: String,
+    grid: Grid,
+    raster: ArcArray2<f64>,
+    stats: Stats,
+    colormaps: Vec<(&'static str, Colormap)>,
+    colormap: usize,
+    mode: Mode,
+    // the view center, in (fractional) column and row indices
+    center: (f64, f64),
+    zoom: f64,
+}
+
+// the part of the raster visible in a heatmap, and how to draw it
+struct Heatmap<'a> {
+    raster: &'a ArcArray2<f64>,
+    colormap: &'a Colormap,
+    zmin: f64,
+    zmax: f64,
+    mode: Mode,
+    center: (f64, f64),
+    zoom: f64,
+}
+
+impl Heatmap<'_> {
+    // the value at "pixel" (px, py) of a (width x height) pixel view
+    fn value_at(&self, px: u16, py: u16, width: u16, height: u16,
+      nodes_per_px: (f64, f64)) -> Option<f64> {
+        let (rows, cols) = self.raster.dim();
+        let fi = self.center.0
+          + (px as f64 - width as f64 / 2.0) * nodes_per_px.0;
+        let fj = self.center.1
+          - (py as f64 - height as f64 / 2.0) * nodes_per_px.1;
+        if fi < 0.0 || fj < 0.0 {
+            return None;
+        }
+        let (i, j) = (fi as usize, fj as usize);
+        if i >= cols || j >= rows {
+            return None;
+        }
+        let z = self.raster[(j, i)];
+        if z.is_nan() { None } else { Some(z) }
+    }
+
+    fn frac(&self, z: f64) -> f64 {
+        let span = self.zmax - self.zmin;
+        if span == 0.0 { 0.0 } else { ((z - self.zmin) / span).clamp(0.0, 1.0) }
+    }
+
+    fn color(&self, z: Option<f64>) -> Color {
+        match z {
+            Some(z) => {
+                let [r, g, b] = self.colormap.color(self.frac(z));
+                Color::Rgb(r, g, b)
+            },
+            None => Color::Reset,
+        }
+    }
+}
+
+impl Widget for Heatmap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (rows, cols) = self.raster.dim();
+        if rows == 0 || cols == 0 || area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        /* character cells are roughly twice as tall as they are wide; in
+         * block mode each cell holds two square-ish pixels, and in ASCII mode
+         * each cell is one tall pixel */
+        let (width, height) = match self.mode {
+            Mode::Blocks => (area.width, area.height * 2),
+            Mode::Ascii => (area.width, area.height),
+        };
+        let fit = (cols as f64 / width as f64)
+          .max(rows as f64 / height as f64 / self.aspect());
+        let npp = fit / self.zoom;
+        let nodes_per_px = (npp, npp * self.aspect());
+
+        for cy in 0..area.height {
+            for cx in 0..area.width {
+                let Some(cell) = buf.cell_mut((area.x + cx, area.y + cy)) else {
+                    continue;
+                };
+                match self.mode {
+                    Mode::Blocks => {
+                        let top = self.value_at(cx, cy * 2, width, height,
+                          nodes_per_px);
+                        let bottom = self.value_at(cx, cy * 2 + 1, width,
+                          height, nodes_per_px);
+                        cell.set_char('▀')
+                          .set_fg(self.color(top))
+                          .set_bg(self.color(bottom));
+                    },
+                    Mode::Ascii => {
+                        let z = self.value_at(cx, cy, width, height,
+                          nodes_per_px);
+                        let c = match z {
+                            Some(z) => {
+                                let k = (self.frac(z)
+                                  * (ASCII_RAMP.len() - 1) as f64).round();
+                                ASCII_RAMP[k as usize] as char
+                            },
+                            None => ' ',
+                        };
+                        cell.set_char(c);
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl Heatmap<'_> {
+    // vertical nodes per horizontal node, per pixel
+    fn aspect(&self) -> f64 {
+        match self.mode {
+            Mode::Blocks => 1.0,
+            Mode::Ascii => 2.0,
+        }
+    }
+}
+
+impl App {
+    fn load(path: &str) -> Result<App, Error> {
+        let mut f = File::open(path)?;
+        let grid = Grid::read(&mut f)?;
+        let stats = grid.data.stats();
+        let raster = match grid.rasterize().data {
+            GridData::Rectangular(arr) => arr,
+            GridData::Triangular(_) => unreachable!("rasterized grid"),
+        };
+        let (rows, cols) = raster.dim();
+        Ok(App {
+            path: path.to_string(),
+            grid,
+            raster,
+            stats,
+            colormaps: vec![
+                ("viridis", Colormap::viridis()),
+                ("terrain", Colormap::terrain()),
+                ("greyscale", Colormap::greyscale()),
+            ],
+            colormap: 0,
+            mode: Mode::Blocks,
+            center: (cols as f64 / 2.0, rows as f64 / 2.0),
+            zoom: 1.0,
+        })
+    }
+
+    fn reset_view(&mut self) {
+        let (rows, cols) = self.raster.dim();
+        self.center = (cols as f64 / 2.0, rows as f64 / 2.0);
+        self.zoom = 1.0;
+    }
+
+    fn pan(&mut self, di: f64, dj: f64) {
+        let (rows, cols) = self.raster.dim();
+        let step = rows.max(cols) as f64 * PAN_FRACTION / self.zoom;
+        self.center.0 += di * step;
+        self.center.1 += dj * step;
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('m') => self.mode = match self.mode {
+                    Mode::Blocks => Mode::Ascii,
+                    Mode::Ascii => Mode::Blocks,
+                },
+                KeyCode::Char('c') =>
+                    self.colormap = (self.colormap + 1) % self.colormaps.len(),
+                KeyCode::Char('+') | KeyCode::Char('=') =>
+                    self.zoom *= ZOOM_STEP,
+                KeyCode::Char('-') => self.zoom /= ZOOM_STEP,
+                KeyCode::Char('r') => self.reset_view(),
+                KeyCode::Left | KeyCode::Char('h') => self.pan(-1.0, 0.0),
+                KeyCode::Right | KeyCode::Char('l') => self.pan(1.0, 0.0),
+                KeyCode::Up | KeyCode::Char('k') => self.pan(0.0, 1.0),
+                KeyCode::Down | KeyCode::Char('j') => self.pan(0.0, -1.0),
+                _ => { },
+            }
+        }
+    }
+
+    fn metadata_lines(&self) -> Vec<Line<'_>> {
+        let g = &self.grid;
+        let kind = match g.data {
+            GridData::Rectangular(_) => "rectangular",
+            GridData::Triangular(_) => "triangular",
+        };
+        vec![
+            Line::from(format!("name: {}", g.name)),
+            Line::from(format!("type: {}", kind)),
+            Line::from(format!("rows x cols: {} x {}", g.rows, g.columns)),
+            Line::from(format!("triangles: {}", g.n_triangles)),
+            Line::from(format!("x: {} to {}", g.xmin, g.xmax)),
+            Line::from(format!("y: {} to {}", g.ymin, g.ymax)),
+            Line::from(format!("step: {} x {}", g.xstep, g.ystep)),
+            Line::from(format!("units: {:?} (xy), {:?} (z)",
+              g.xyunits, g.zunits)),
+            Line::from(format!("created: {}", g.created_date)),
+            Line::from(format!("source: {}", g.source_data)),
+            Line::from(format!("projection: {}", g.projection)),
+            Line::from(format!("datum: {}", g.datum)),
+        ]
+    }
+
+    fn stats_lines(&self) -> Vec<Line<'_>> {
+        let s = &self.stats;
+        vec![
+            Line::from(format!("values: {}", s.count)),
+            Line::from(format!("missing: {}", s.null_count)),
+            Line::from(format!("min: {:.3}", s.min)),
+            Line::from(format!("max: {:.3}", s.max)),
+            Line::from(format!("mean: {:.3}", s.mean)),
+            Line::from(format!("std. dev.: {:.3}", s.std_dev)),
+        ]
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [map_area, side] = Layout::horizontal(
+          [Constraint::Fill(1), Constraint::Length(40)])
+          .areas(frame.area());
+        let [meta_area, stats_area, help_area] = Layout::vertical(
+          [Constraint::Fill(1), Constraint::Length(8), Constraint::Length(4)])
+          .areas(side);
+
+        let (name, colormap) = &self.colormaps[self.colormap];
+        let map_block = Block::bordered()
+          .title(format!(" {} [{}] ", self.path, name));
+        let heatmap = Heatmap {
+            raster: &self.raster,
+            colormap,
+            zmin: self.stats.min,
+            zmax: self.stats.max,
+            mode: self.mode,
+            center: self.center,
+            zoom: self.zoom,
+        };
+        frame.render_widget(heatmap, map_block.inner(map_area));
+        frame.render_widget(map_block, map_area);
+
+        frame.render_widget(Paragraph::new(self.metadata_lines())
+          .block(Block::bordered().title(" metadata ")), meta_area);
+        frame.render_widget(Paragraph::new(self.stats_lines())
+          .block(Block::bordered().title(" statistics ")), stats_area);
+        frame.render_widget(Paragraph::new(vec![
+            Line::from("arrows/hjkl pan, +/- zoom, r reset"),
+            Line::from("m mode, c colormap, q quit"),
+        ]).block(Block::bordered()), help_area);
+    }
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    let path = match args {
+        [path] => path,
+        _ => return report::usage("Usage: petra-grid tui <grd-file>"),
+    };
+
+    let mut app = match App::load(path) {
+        Ok(app) => app,
+        Err(e) => {
+            report::error("reading", path, &e);
+            return report::exit_code();
+        },
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    match result {
+        Ok(()) => ExitCode::from(0),
+        Err(e) => {
+            report::failure("displaying", path, "terminal", &e.to_string(),
+              report::EXIT_IO);
+            report::exit_code()
+        },
+    }
+}
@@ -0,0 +1,234 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid validate`: check that grids read cleanly, for data-quality
+//! gates over shared grid repositories
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use petra_grid::{
+    Error,
+    ErrorCategory,
+    Grid,
+    ReadOptions,
+    Warning,
+    find_grd_files,
+};
+
+use crate::{
+    report::{self, json_string},
+    timing::{FileTiming, Timings, file_size},
+};
+
+const USAGE: &str = "\
+Usage: petra-grid validate [--strict] [--lenient] [--summary <json-file>] \
+[--timings] <grd-files-or-directories>";
+
+// the outcome of validating one file
+struct Outcome {
+    path: String,
+    result: Result<Vec<Warning>, Error>,
+    bytes: u64,
+    read_time: Duration,
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    let mut strict = false;
+    let mut options = ReadOptions::new().salvage(true);
+    let mut summary = None;
+    let mut print_timings = false;
+    let mut inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            "--lenient" => options = options.lenient(true),
+            "--timings" => print_timings = true,
+            "--summary" => match args.next() {
+                Some(path) => summary = Some(PathBuf::from(path)),
+                None => return report::usage(USAGE),
+            },
+            _ => inputs.push(arg),
+        }
+    }
+
+    if inputs.is_empty() {
+        return report::usage(USAGE);
+    }
+
+    let mut timings = Timings::start();
+    let mut outcomes = Vec::new();
+    for input in inputs {
+        let input = Path::new(input);
+        if input.is_dir() {
+            let (paths, errors) = find_grd_files(input);
+            for (path, e) in errors {
+                outcomes.push(Outcome {
+                    path: path.display().to_string(),
+                    result: Err(e),
+                    bytes: 0,
+                    read_time: Duration::ZERO,
+                });
+            }
+            for path in paths {
+                outcomes.push(validate(&path, &options));
+            }
+        } else {
+            outcomes.push(validate(input, &options));
+        }
+    }
+
+    for outcome in &outcomes {
+        timings.record(FileTiming {
+            path: outcome.path.clone(),
+            read_bytes: outcome.bytes,
+            read_time: outcome.read_time,
+            ..Default::default()
+        });
+        match &outcome.result {
+            Ok(warnings) if warnings.is_empty() =>
+                println!("{}: OK", outcome.path),
+            Ok(warnings) => {
+                println!("{}: {} warning(s)", outcome.path, warnings.len());
+                for w in warnings {
+                    report::warning("reading", &outcome.path, w);
+                }
+                if strict {
+                    report::failure("validating", &outcome.path, "strict",
+                      "warnings are errors in strict mode",
+                      report::EXIT_CHECK);
+                }
+            },
+            Err(e) => {
+                println!("{}: FAILED", outcome.path);
+                report::error("reading", &outcome.path, e);
+            },
+        }
+    }
+
+    if print_timings {
+        timings.print();
+    }
+
+    if let Some(summary) = summary {
+        let json = summary_json(&outcomes, strict, report::status(),
+          timings.elapsed());
+        if let Err(e) = fs::write(&summary, json) {
+            report::error("writing", &summary.display().to_string(),
+              &e.into());
+        }
+    }
+
+    report::exit_code()
+}
+
+fn validate(path: &Path, options: &ReadOptions) -> Outcome {
+    let started = Instant::now();
+    let result = File::open(path)
+      .map_err(Error::from)
+      .and_then(|mut f| Grid::read_with_options(&mut f, options))
+      .map(|(_, warnings)| warnings);
+    Outcome {
+        path: path.display().to_string(),
+        result,
+        bytes: file_size(path),
+        read_time: started.elapsed(),
+    }
+}
+
+/* the aggregate summary, as a JSON object: counts of files by status and of
+ * problems by kind, then the per-file details */
+fn summary_json(outcomes: &[Outcome], strict: bool, exit_code: u8,
+  wall_time: Duration) -> String {
+    let mut ok = 0;
+    let mut with_warnings = 0;
+    let mut failed = 0;
+    let mut warning_kinds = BTreeMap::new();
+    let mut error_kinds = BTreeMap::new();
+    let mut results = Vec::new();
+
+    for outcome in outcomes {
+        let path = json_string(&outcome.path);
+        match &outcome.result {
+            Ok(warnings) => {
+                if warnings.is_empty() {
+                    ok += 1;
+                } else {
+                    with_warnings += 1;
+                }
+                let details = warnings.iter()
+                  .map(|w| {
+                      *warning_kinds.entry(w.kind()).or_insert(0) += 1;
+                      problem_json(w.kind(), w.category(), Some(w.offset()),
+                        &w.to_string())
+                  })
+                  .collect::<Vec<_>>();
+                results.push(format!(
+                  "{{\"path\":{},\"status\":{},\"warnings\":[{}],\"error\":null,{}}}",
+                  path,
+                  json_string(if warnings.is_empty() { "ok" } else { "warnings" }),
+                  details.join(","), timing_json(outcome)));
+            },
+            Err(e) => {
+                failed += 1;
+                *error_kinds.entry(e.kind()).or_insert(0) += 1;
+                results.push(format!(
+                  "{{\"path\":{},\"status\":\"failed\",\"warnings\":[],\"error\":{},{}}}",
+                  path, problem_json(e.kind(), e.category(), e.offset(),
+                    &e.to_string()),
+                  timing_json(outcome)));
+            },
+        }
+    }
+
+    let counts = |kinds: &BTreeMap<&str, usize>| kinds.iter()
+      .map(|(kind, n)| format!("{}:{}", json_string(kind), n))
+      .collect::<Vec<_>>()
+      .join(",");
+
+    format!("{{\n  \"strict\": {},\n  \"files\": {},\n  \"ok\": {},\n  \
+      \"with_warnings\": {},\n  \"failed\": {},\n  \"warnings\": {{{}}},\n  \
+      \"errors\": {{{}}},\n  \"exit_code\": {},\n  \"wall_seconds\": {},\n  \
+      \"results\": [\n    {}\n  ]\n}}\n",
+      strict, outcomes.len(), ok, with_warnings, failed,
+      counts(&warning_kinds), counts(&error_kinds), exit_code,
+      wall_time.as_secs_f64(), results.join(",\n    "))
+}
+
+// the size of a file and the time taken to read it, as JSON object members
+fn timing_json(outcome: &Outcome) -> String {
+    format!("\"bytes\":{},\"read_seconds\":{}", outcome.bytes,
+      outcome.read_time.as_secs_f64())
+}
+
+fn problem_json(kind: &str, category: ErrorCategory, offset: Option<u64>,
+  message: &str) -> String {
+    format!("{{\"kind\":{},\"category\":{},\"offset\":{},\"message\":{}}}",
+      json_string(kind), json_string(category.name()),
+      offset.map(|o| o.to_string()).unwrap_or_else(|| "null".to_string()),
+      json_string(message))
+}
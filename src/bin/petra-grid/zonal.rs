@@ -0,0 +1,83 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `petra-grid zonal`: summarize a grid within each polygon of a layer
+
+use std::{
+    io,
+    process::ExitCode,
+};
+
+use petra_grid::{
+    import::{self, polygons::read_polygons_file},
+    zonal::{self, zonal_stats},
+};
+
+use crate::report;
+
+const USAGE: &str = "\
+Usage: petra-grid zonal [--base <z>] <grid-file> <polygon-file>
+
+Writes a CSV attribute table of each polygon's attributes and the grid's
+statistics (node count, min, max, mean, sum, and area) within it; with
+--base, writes the gross volume above the base in place of the sum.
+Polygons are read from GeoJSON or Shapefiles (.shp, with attributes from
+the matching .dbf), and must be in the grid's coordinate system.";
+
+pub fn run(args: &[String]) -> ExitCode {
+    let (base, paths) = match args {
+        [flag, base, paths @ ..] if flag == "--base" => match base.parse() {
+            Ok(base) => (Some(base), paths),
+            Err(_) => return report::usage(USAGE),
+        },
+        paths => (None, paths),
+    };
+    let [grid_path, polygon_path] = paths else {
+        return report::usage(USAGE);
+    };
+
+    let grid = match import::read_file(grid_path) {
+        Ok((grid, _)) => grid,
+        Err(e) => {
+            report::error("reading", grid_path, &e);
+            return report::exit_code();
+        },
+    };
+    let polygons = match read_polygons_file(polygon_path) {
+        Ok(polygons) => polygons,
+        Err(e) => {
+            report::error("reading", polygon_path, &e);
+            return report::exit_code();
+        },
+    };
+
+    let mut stdout = io::stdout().lock();
+    let result = match base {
+        Some(base) => zonal::write_volume_csv(&polygons,
+          &grid.polygon_volumes(&polygons, base), &mut stdout),
+        None => zonal::write_zonal_csv(&polygons,
+          &zonal_stats(&grid, &polygons), &mut stdout),
+    };
+    if let Err(e) = result {
+        report::error("writing", "<stdout>", &e);
+    }
+    report::exit_code()
+}
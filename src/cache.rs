@@ -0,0 +1,302 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! a fast binary serialization of parsed grids, for caching
+//!
+//! the cache format is private to this library: it's versioned, and caches
+//! written by one release may be rejected (with an error, never silently
+//! misread) by another, so always keep the original GRD files around
+
+use std::io::{Cursor, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use ndarray::{Array, ShapeBuilder};
+
+use time::{Date, Duration, PrimitiveDateTime, Time};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    GridHeader,
+    Quantity,
+    UnitOfMeasure,
+    VerticalDatum,
+    invariants,
+};
+
+const CACHE_MAGIC: &[u8; 8] = b"PGRDCACH";
+const CACHE_VERSION: u32 = 4;
+
+impl Grid {
+    /// serialize the grid in a compact binary format which can be read back
+    /// (with [Grid::from_cache_bytes]) much faster than re-parsing a GRD file
+    pub fn to_cache_bytes(&self) -> Vec<u8> {
+        let n_values = match &self.data {
+            GridData::Rectangular(arr) => arr.len(),
+            GridData::Triangular(arr) => arr.len(),
+        };
+        let mut buf = Vec::with_capacity(512 + n_values * 8);
+        // writes to a Vec can't fail
+        write_cache(self, &mut buf).expect("in-memory write failed");
+        buf
+    }
+
+    /// deserialize a grid produced by [Grid::to_cache_bytes]
+    ///
+    /// caches from an incompatible release of this library are rejected
+    /// with [Error::InvalidInput]
+    pub fn from_cache_bytes(bytes: &[u8]) -> Result<Grid, Error> {
+        let mut source = Cursor::new(bytes);
+
+        let mut magic = [0u8; 8];
+        source.read_exact(&mut magic)
+          .map_err(|_| not_a_cache())?;
+        if &magic != CACHE_MAGIC {
+            return Err(not_a_cache());
+        }
+        let version = source.read_u32::<LittleEndian>()?;
+        if version != CACHE_VERSION {
+            return Err(Error::InvalidInput(format!(
+              "grid cache version {} is not supported (expected {})",
+              version, CACHE_VERSION)));
+        }
+
+        let header = GridHeader {
+            version: source.read_u32::<LittleEndian>()?,
+            name: read_string(&mut source)?,
+            size: source.read_u32::<LittleEndian>()?,
+            rows: source.read_u32::<LittleEndian>()?,
+            columns: source.read_u32::<LittleEndian>()?,
+            n_triangles: source.read_u32::<LittleEndian>()?,
+            xmin: source.read_f64::<LittleEndian>()?,
+            xmax: source.read_f64::<LittleEndian>()?,
+            ymin: source.read_f64::<LittleEndian>()?,
+            ymax: source.read_f64::<LittleEndian>()?,
+            xstep: source.read_f64::<LittleEndian>()?,
+            ystep: source.read_f64::<LittleEndian>()?,
+            zmin: source.read_f64::<LittleEndian>()?,
+            zmax: source.read_f64::<LittleEndian>()?,
+            xyunits: read_unit(&mut source)?,
+            zunits: read_unit(&mut source)?,
+            created_date: read_datetime(&mut source)?,
+            source_data: read_string(&mut source)?,
+            unknown_metadata: read_string(&mut source)?,
+            projection: read_string(&mut source)?,
+            datum: read_string(&mut source)?,
+            grid_method: source.read_u32::<LittleEndian>()?,
+            projection_code: source.read_u32::<LittleEndian>()?,
+            cm: source.read_f64::<LittleEndian>()?,
+            rlat: source.read_f64::<LittleEndian>()?,
+            vertical_datum: read_vertical_datum(&mut source)?,
+            quantity: read_quantity(&mut source)?,
+        };
+
+        let kind = source.read_u8()?;
+        let dim0 = source.read_u64::<LittleEndian>()? as usize;
+        let dim1 = source.read_u64::<LittleEndian>()? as usize;
+        let n_values = match kind {
+            b'R' => dim0.checked_mul(dim1),
+            b'T' => dim0.checked_mul(9),
+            _ => None,
+        }.ok_or_else(corrupt_cache)?;
+
+        let remaining = (bytes.len() as u64).saturating_sub(source.position());
+        if Some(remaining) != (n_values as u64).checked_mul(8) {
+            return Err(corrupt_cache());
+        }
+        let mut buf = vec![0.0; n_values];
+        source.read_f64_into::<LittleEndian>(&mut buf)?;
+
+        /* safety: we checked above that we read exactly the right number of
+         * values for the shape */
+        let data = if kind == b'R' {
+            GridData::Rectangular(
+              Array::from_shape_vec((dim0, dim1), buf).unwrap().into_shared())
+        } else {
+            // laid out as in GRD files; see read_data
+            GridData::Triangular(
+              Array::from_shape_vec(
+                (dim0, 3, 3).strides(invariants::TRIANGLE_STRIDES), buf)
+                .unwrap().into_shared())
+        };
+
+        Ok(Grid::from_parts(header, data))
+    }
+}
+
+fn write_cache(grid: &Grid, dest: &mut Vec<u8>) -> Result<(), Error> {
+    dest.write_all(CACHE_MAGIC)?;
+    dest.write_u32::<LittleEndian>(CACHE_VERSION)?;
+
+    dest.write_u32::<LittleEndian>(grid.version)?;
+    write_string(dest, &grid.name)?;
+    dest.write_u32::<LittleEndian>(grid.size)?;
+    dest.write_u32::<LittleEndian>(grid.rows)?;
+    dest.write_u32::<LittleEndian>(grid.columns)?;
+    dest.write_u32::<LittleEndian>(grid.n_triangles)?;
+    for v in [grid.xmin, grid.xmax, grid.ymin, grid.ymax, grid.xstep,
+      grid.ystep, grid.zmin, grid.zmax] {
+        dest.write_f64::<LittleEndian>(v)?;
+    }
+    dest.write_u32::<LittleEndian>(grid.xyunits.code())?;
+    dest.write_u32::<LittleEndian>(grid.zunits.code())?;
+    write_datetime(dest, grid.created_date)?;
+    write_string(dest, &grid.source_data)?;
+    write_string(dest, &grid.unknown_metadata)?;
+    write_string(dest, &grid.projection)?;
+    write_string(dest, &grid.datum)?;
+    dest.write_u32::<LittleEndian>(grid.grid_method)?;
+    dest.write_u32::<LittleEndian>(grid.projection_code)?;
+    dest.write_f64::<LittleEndian>(grid.cm)?;
+    dest.write_f64::<LittleEndian>(grid.rlat)?;
+    write_vertical_datum(dest, grid.vertical_datum)?;
+    write_quantity(dest, grid.quantity.as_ref())?;
+
+    match &grid.data {
+        GridData::Rectangular(arr) => {
+            let (rows, cols) = arr.dim();
+            dest.write_u8(b'R')?;
+            dest.write_u64::<LittleEndian>(rows as u64)?;
+            dest.write_u64::<LittleEndian>(cols as u64)?;
+            for &z in arr.iter() {
+                dest.extend_from_slice(&z.to_le_bytes());
+            }
+        },
+        GridData::Triangular(arr) => {
+            dest.write_u8(b'T')?;
+            dest.write_u64::<LittleEndian>(arr.dim().0 as u64)?;
+            dest.write_u64::<LittleEndian>(0)?;
+            for tri in arr.outer_iter() {
+                for component in 0..3 {
+                    for vertex in 0..3 {
+                        dest.extend_from_slice(
+                          &tri[(vertex, component)].to_le_bytes());
+                    }
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn write_string(dest: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+    dest.write_u32::<LittleEndian>(s.len() as u32)?;
+    dest.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn read_string(source: &mut Cursor<&[u8]>) -> Result<String, Error> {
+    let len = source.read_u32::<LittleEndian>()? as usize;
+    let start = source.position() as usize;
+    let end = start.checked_add(len).ok_or_else(corrupt_cache)?;
+    let bytes = source.get_ref().get(start..end)
+      .ok_or_else(corrupt_cache)?;
+    source.set_position(end as u64);
+    String::from_utf8(bytes.to_vec()).map_err(|_| corrupt_cache())
+}
+
+fn read_unit(source: &mut Cursor<&[u8]>) -> Result<UnitOfMeasure, Error> {
+    Ok(UnitOfMeasure::from_code(source.read_u32::<LittleEndian>()?))
+}
+
+// as a tag and (for local datums) the datum elevation
+fn write_vertical_datum(dest: &mut Vec<u8>, datum: VerticalDatum)
+  -> Result<(), Error> {
+    let (tag, elevation) = match datum {
+        VerticalDatum::Unknown => (0, 0.0),
+        VerticalDatum::MeanSeaLevel => (1, 0.0),
+        VerticalDatum::Subsea => (2, 0.0),
+        VerticalDatum::Local(elevation) => (3, elevation),
+    };
+    dest.write_u8(tag)?;
+    dest.write_f64::<LittleEndian>(elevation)?;
+    Ok(())
+}
+
+fn read_vertical_datum(source: &mut Cursor<&[u8]>)
+  -> Result<VerticalDatum, Error> {
+    let tag = source.read_u8()?;
+    let elevation = source.read_f64::<LittleEndian>()?;
+    match tag {
+        0 => Ok(VerticalDatum::Unknown),
+        1 => Ok(VerticalDatum::MeanSeaLevel),
+        2 => Ok(VerticalDatum::Subsea),
+        3 => Ok(VerticalDatum::Local(elevation)),
+        _ => Err(corrupt_cache()),
+    }
+}
+
+// as a flag and (if present) the name and units
+fn write_quantity(dest: &mut Vec<u8>, quantity: Option<&Quantity>)
+  -> Result<(), Error> {
+    match quantity {
+        Some(quantity) => {
+            dest.write_u8(1)?;
+            write_string(dest, &quantity.name)?;
+            write_string(dest, &quantity.units)
+        },
+        None => Ok(dest.write_u8(0)?),
+    }
+}
+
+fn read_quantity(source: &mut Cursor<&[u8]>)
+  -> Result<Option<Quantity>, Error> {
+    match source.read_u8()? {
+        0 => Ok(None),
+        1 => {
+            let name = read_string(source)?;
+            let units = read_string(source)?;
+            Ok(Some(Quantity::new(name, units)))
+        },
+        _ => Err(corrupt_cache()),
+    }
+}
+
+// as the Julian day number and nanoseconds since midnight
+fn write_datetime(dest: &mut Vec<u8>, dt: PrimitiveDateTime)
+  -> Result<(), Error> {
+    let midnight = dt.replace_time(Time::MIDNIGHT);
+    dest.write_i32::<LittleEndian>(dt.to_julian_day())?;
+    dest.write_i64::<LittleEndian>(
+      (dt - midnight).whole_nanoseconds() as i64)?;
+    Ok(())
+}
+
+fn read_datetime(source: &mut Cursor<&[u8]>)
+  -> Result<PrimitiveDateTime, Error> {
+    let day = source.read_i32::<LittleEndian>()?;
+    let nanos = source.read_i64::<LittleEndian>()?;
+    let date = Date::from_julian_day(day).map_err(|_| corrupt_cache())?;
+    PrimitiveDateTime::new(date, Time::MIDNIGHT)
+      .checked_add(Duration::nanoseconds(nanos))
+      .ok_or_else(corrupt_cache)
+}
+
+fn not_a_cache() -> Error {
+    Error::InvalidInput("not a grid cache".to_string())
+}
+
+fn corrupt_cache() -> Error {
+    Error::InvalidInput("corrupt grid cache".to_string())
+}
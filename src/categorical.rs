@@ -0,0 +1,166 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! categorical grids (e.g. facies maps or classified grids), whose nodes hold
+//! integer codes for labeled categories rather than continuous values
+
+use std::collections::HashSet;
+
+use ndarray::Array2;
+
+use crate::{Classification, Colormap, Error, Grid, GridData, GridHeader};
+
+/* codes which aren't within this distance of an integer aren't codes at
+ * all (and suggest a continuous grid) */
+const CODE_TOLERANCE: f64 = 1e-6;
+
+/// one category of a [CategoricalGrid]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Category {
+    /// the category's code in the grid
+    pub code: i32,
+
+    /// the category's name (e.g. a facies name, or a class's range)
+    pub label: String,
+
+    /// the category's color, used by paletted exports
+    pub color: [u8; 3],
+}
+
+/// a rectangular grid of integer category codes, with the categories'
+/// labels and colors
+#[derive(Clone, Debug)]
+pub struct CategoricalGrid {
+    header: GridHeader,
+    codes: Array2<i32>,
+    categories: Vec<Category>,
+}
+
+impl CategoricalGrid {
+    /// the code used for missing nodes
+    pub const MISSING: i32 = i32::MIN;
+
+    /// build a categorical grid from a header and a (`rows` × `columns`)
+    /// array of codes; each category's code must be unique, but codes
+    /// without a category are allowed
+    pub fn new(header: GridHeader, codes: Array2<i32>,
+      categories: Vec<Category>) -> Result<CategoricalGrid, Error> {
+        let expected = (header.rows as usize, header.columns as usize);
+        if codes.dim() != expected {
+            return Err(Error::InvalidArgument(format!(
+              "expected {} × {} codes, got {} × {}", expected.0, expected.1,
+              codes.dim().0, codes.dim().1)));
+        }
+        let mut seen = HashSet::new();
+        for category in &categories {
+            if category.code == Self::MISSING {
+                return Err(Error::InvalidArgument(format!(
+                  "category \"{}\" uses the missing code", category.label)));
+            }
+            if !seen.insert(category.code) {
+                return Err(Error::InvalidArgument(format!(
+                  "duplicate category code {}", category.code)));
+            }
+        }
+        Ok(CategoricalGrid { header, codes, categories })
+    }
+
+    /// build a categorical grid from a rectangular grid whose values are
+    /// integer codes (such as a facies grid); missing nodes stay missing
+    pub fn from_grid(grid: &Grid, categories: Vec<Category>)
+      -> Result<CategoricalGrid, Error> {
+        let arr = grid.rectangular()?;
+        let mut codes = Array2::from_elem(arr.dim(), Self::MISSING);
+        for (code, &z) in codes.iter_mut().zip(arr.iter()) {
+            if z.is_nan() {
+                continue;
+            }
+            let rounded = z.round();
+            if (z - rounded).abs() > CODE_TOLERANCE
+              || rounded <= Self::MISSING as f64
+              || rounded > i32::MAX as f64 {
+                return Err(Error::InvalidArgument(format!(
+                  "{} isn't an integer category code", z)));
+            }
+            *code = rounded as i32;
+        }
+        CategoricalGrid::new(grid.header(), codes, categories)
+    }
+
+    /// the grid's header (geometry and metadata)
+    pub fn header(&self) -> &GridHeader {
+        &self.header
+    }
+
+    /// the code at each node ([CategoricalGrid::MISSING] for missing
+    /// nodes)
+    pub fn codes(&self) -> &Array2<i32> {
+        &self.codes
+    }
+
+    /// the categories, in the order given
+    pub fn categories(&self) -> &[Category] {
+        &self.categories
+    }
+
+    /// the category with a given code, if any
+    pub fn category(&self, code: i32) -> Option<&Category> {
+        self.categories.iter().find(|c| c.code == code)
+    }
+
+    /// the category at a node, if it isn't missing and its code has a
+    /// category
+    pub fn category_at(&self, row: usize, column: usize)
+      -> Option<&Category> {
+        self.codes.get((row, column)).and_then(|&code| self.category(code))
+    }
+
+    /// a continuous grid holding each node's code (as a *z* value), with the
+    /// same geometry and metadata
+    pub fn to_grid(&self) -> Grid {
+        let data = self.codes.mapv(|code| {
+            if code == Self::MISSING { f64::NAN } else { code as f64 }
+        });
+        let data = GridData::Rectangular(data.into_shared());
+        let stats = data.stats();
+        let mut header = self.header.clone();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Grid::from_parts(header, data)
+    }
+}
+
+impl Classification {
+    /// the classified grid as a [CategoricalGrid], with one category per
+    /// class, labeled by its range of values and colored along `colormap`
+    pub fn categorical(&self, colormap: &Colormap)
+      -> Result<CategoricalGrid, Error> {
+        let last = self.legend.len().saturating_sub(1).max(1) as f64;
+        let categories = self.legend.iter().enumerate()
+          .map(|(k, class)| Category {
+              code: class.code as i32,
+              label: format!("{} to {}", class.lower, class.upper),
+              color: colormap.color(k as f64 / last),
+          })
+          .collect();
+        CategoricalGrid::from_grid(&self.grid, categories)
+    }
+}
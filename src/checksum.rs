@@ -0,0 +1,107 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! SHA-256 checksum sidecar files, for validating grid deliverables
+//!
+//! sidecars are written alongside each file, with `.sha256` appended to its
+//! name, in the format used by `sha256sum` (so `sha256sum -c` can check them
+//! too): the hex digest, two spaces, and the file's name
+
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::Error;
+
+/// the outcome of checking a file against its sidecar
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// the file's digest matches its sidecar
+    Ok,
+
+    /// the file's digest does not match its sidecar
+    Mismatch {
+        /** the digest recorded in the sidecar */ expected: String,
+        /** the digest of the file's contents */ actual: String,
+    },
+}
+
+/// the path of the sidecar for a file: the file's path with `.sha256`
+/// appended
+pub fn sidecar_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut sidecar = OsString::from(path.as_ref().as_os_str());
+    sidecar.push(".sha256");
+    PathBuf::from(sidecar)
+}
+
+/// the SHA-256 digest of a file's contents, as lowercase hex
+pub fn sha256_file<P: AsRef<Path>>(path: P) -> Result<String, Error> {
+    let mut f = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = f.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// compute the digest of a file and write it to the file's sidecar,
+/// returning the sidecar's path
+pub fn write_sidecar<P: AsRef<Path>>(path: P) -> Result<PathBuf, Error> {
+    let path = path.as_ref();
+    let digest = sha256_file(path)?;
+    let name = path.file_name()
+      .ok_or_else(|| Error::InvalidArgument(
+        "path does not name a file".to_string()))?
+      .to_string_lossy();
+    let sidecar = sidecar_path(path);
+    fs::write(&sidecar, format!("{}  {}\n", digest, name))?;
+    Ok(sidecar)
+}
+
+/// check a file against the digest recorded in its sidecar
+pub fn verify_sidecar<P: AsRef<Path>>(path: P)
+  -> Result<Verification, Error> {
+    let path = path.as_ref();
+    let sidecar = sidecar_path(path);
+    let contents = fs::read_to_string(&sidecar)?;
+    let expected = contents.split_whitespace().next()
+      .filter(|d| d.len() == 64 && d.bytes().all(|b| b.is_ascii_hexdigit()))
+      .ok_or_else(|| Error::InvalidInput(
+        format!("malformed checksum file {}", sidecar.display())))?
+      .to_ascii_lowercase();
+
+    let actual = sha256_file(path)?;
+    if actual == expected {
+        Ok(Verification::Ok)
+    } else {
+        Ok(Verification::Mismatch { expected, actual })
+    }
+}
@@ -0,0 +1,325 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! chunked processing of rectangular grids, a block of rows at a time, for
+//! working on grids larger than memory (or on many grids at once) within a
+//! memory budget
+//!
+//! in-memory grids are split into chunks with [Grid::chunks]; GRD files are
+//! read a chunk at a time with a [ChunkReader], and written a chunk at a
+//! time with a [ChunkWriter]. chunks can be summarized (see
+//! [GridChunk::stats] and [Stats::merge]) and combined node by node (see
+//! [GridChunk::map] and [GridChunk::zip_with]) along the way
+
+use std::{
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use ndarray::{ArcArray2, Array2, ArrayView2, CowArray, Ix2, s};
+
+use crate::{
+    Error,
+    GRID_OFFSET,
+    Grid,
+    GridData,
+    GridHeader,
+    NullPolicy,
+    Stats,
+    read_f64_blocks,
+    write::{header_bytes, nan_to_petra_null},
+};
+
+/// a block of consecutive rows (south to north) of a rectangular grid,
+/// either borrowed from an in-memory grid or read from a file
+#[derive(Clone, Debug)]
+pub struct GridChunk<'a> {
+    first_row: usize,
+    data: CowArray<'a, f64, Ix2>,
+}
+
+impl GridChunk<'static> {
+    /// a chunk holding the given rows (rows × columns), starting at
+    /// `first_row` of its grid
+    pub fn new(first_row: usize, data: Array2<f64>) -> GridChunk<'static> {
+        GridChunk { first_row, data: data.into() }
+    }
+}
+
+impl<'a> GridChunk<'a> {
+    /// the index, in the whole grid, of the chunk's first row
+    pub fn first_row(&self) -> usize {
+        self.first_row
+    }
+
+    /// the indices, in the whole grid, of the chunk's rows
+    pub fn rows(&self) -> Range<usize> {
+        self.first_row..self.first_row + self.data.nrows()
+    }
+
+    /// the chunk's *z* values (rows × columns)
+    pub fn data(&self) -> ArrayView2<'_, f64> {
+        self.data.view()
+    }
+
+    /// summary statistics of the chunk's *z* values; combine those of
+    /// several chunks with [Stats::merge]
+    pub fn stats(&self) -> Stats {
+        Stats::from_values(self.data.iter().copied())
+    }
+
+    /// a new chunk of the same rows, with `f` applied to each *z* value
+    pub fn map<F: Fn(f64) -> f64>(&self, f: F) -> GridChunk<'static> {
+        GridChunk::new(self.first_row, self.data.mapv(f))
+    }
+
+    /// a new chunk of the same rows, combining each *z* value with that of
+    /// the same node in another chunk (of the same rows of a coregistered
+    /// grid) using `f`
+    pub fn zip_with<F: Fn(f64, f64) -> f64>(&self, other: &GridChunk<'_>,
+      f: F) -> Result<GridChunk<'static>, Error> {
+        if self.rows() != other.rows() || self.data.dim() != other.data.dim() {
+            return Err(Error::InvalidArgument(format!(
+              "can't combine chunks of rows {:?} ({} columns) and {:?} \
+               ({} columns)", self.rows(), self.data.ncols(), other.rows(),
+               other.data.ncols())));
+        }
+        let mut data = self.data.to_owned();
+        data.zip_mut_with(&other.data, |a, &b| *a = f(*a, b));
+        Ok(GridChunk::new(self.first_row, data))
+    }
+
+    /// as [GridChunk::zip_with], but with missing values treated according
+    /// to a policy (see [NullPolicy::combine])
+    pub fn zip_with_nulls<F: Fn(f64, f64) -> f64>(&self,
+      other: &GridChunk<'_>, policy: &NullPolicy, f: F)
+      -> Result<GridChunk<'static>, Error> {
+        self.zip_with(other, |a, b| policy.combine(a, b, &f))
+    }
+
+    /// take ownership of the chunk's data
+    pub fn into_owned(self) -> GridChunk<'static> {
+        GridChunk::new(self.first_row, self.data.into_owned())
+    }
+}
+
+/// an iterator over chunks of an in-memory grid; see [Grid::chunks]
+#[derive(Clone, Debug)]
+pub struct Chunks<'a> {
+    arr: &'a ArcArray2<f64>,
+    rows_per_chunk: usize,
+    next_row: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = GridChunk<'a>;
+
+    fn next(&mut self) -> Option<GridChunk<'a>> {
+        let rows = self.arr.nrows();
+        if self.next_row >= rows {
+            return None;
+        }
+        let first_row = self.next_row;
+        self.next_row = (first_row + self.rows_per_chunk).min(rows);
+        Some(GridChunk {
+            first_row,
+            data: self.arr.slice(s![first_row..self.next_row, ..]).into(),
+        })
+    }
+}
+
+impl Grid {
+    /// split a rectangular grid into chunks of (at most) `rows_per_chunk`
+    /// rows each, from south to north, without copying
+    pub fn chunks(&self, rows_per_chunk: usize) -> Result<Chunks<'_>, Error> {
+        let arr = self.rectangular()?;
+        check_rows_per_chunk(rows_per_chunk)?;
+        Ok(Chunks { arr, rows_per_chunk, next_row: 0 })
+    }
+}
+
+/// reads a rectangular grid in the Petra GRD format from a seekable source
+/// a chunk at a time, as an iterator of chunks from south to north; only
+/// one chunk's data is in memory at once
+///
+/// Petra's null values are converted to NaN, as by [Grid::read]
+#[derive(Debug)]
+pub struct ChunkReader<R> {
+    source: R,
+    header: GridHeader,
+    rows_per_chunk: usize,
+    next_row: usize,
+}
+
+impl<R: Read + Seek> ChunkReader<R> {
+    /// read the header of a grid, preparing to read its data in chunks of
+    /// (at most) `rows_per_chunk` rows; fails with [Error::NotRectangular]
+    /// for triangular grids
+    pub fn new(mut source: R, rows_per_chunk: usize)
+      -> Result<ChunkReader<R>, Error> {
+        check_rows_per_chunk(rows_per_chunk)?;
+        let header = GridHeader::read(&mut source)?;
+        if header.n_triangles != 0 {
+            return Err(Error::NotRectangular);
+        }
+        if header.rows as u64 * header.columns as u64 != header.size as u64 {
+            return Err(Error::SizeMismatch(header.size, header.rows,
+              header.columns));
+        }
+        let data_size = source.seek(SeekFrom::End(0))?
+          .saturating_sub(GRID_OFFSET);
+        if data_size != header.size as u64 * 8 {
+            return Err(Error::InvalidRectangularSize(header.size, data_size));
+        }
+        Ok(ChunkReader { source, header, rows_per_chunk, next_row: 0 })
+    }
+
+    /// the grid's header
+    pub fn header(&self) -> &GridHeader {
+        &self.header
+    }
+
+    /// give back the source
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    fn read_chunk(&mut self) -> Result<GridChunk<'static>, Error> {
+        let rows = self.header.rows as usize;
+        let cols = self.header.columns as usize;
+        let first_row = self.next_row;
+        let n = self.rows_per_chunk.min(rows - first_row);
+        self.next_row += n;
+
+        self.source.seek(SeekFrom::Start(
+          GRID_OFFSET + (first_row * cols * 8) as u64))?;
+        let mut buf = vec![0.0; n * cols];
+        read_f64_blocks(&mut self.source, &mut buf,
+          Some(&NullPolicy::default()))?;
+        /* safety: the buffer holds exactly n rows of cols values */
+        let data = Array2::from_shape_vec((n, cols), buf).unwrap();
+        Ok(GridChunk::new(first_row, data))
+    }
+}
+
+impl<R: Read + Seek> Iterator for ChunkReader<R> {
+    type Item = Result<GridChunk<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_row >= self.header.rows as usize {
+            return None;
+        }
+        let result = self.read_chunk();
+        if result.is_err() {
+            // don't keep reading after a failure
+            self.next_row = self.header.rows as usize;
+        }
+        Some(result)
+    }
+}
+
+/// writes a rectangular grid in the Petra GRD format to a seekable
+/// destination a chunk at a time; chunks must arrive in order, from south
+/// to north
+///
+/// the header's *z* range is filled in from the data when the grid is
+/// finished (see [ChunkWriter::finish])
+#[derive(Debug)]
+pub struct ChunkWriter<W: Write + Seek> {
+    out: BufWriter<W>,
+    header: GridHeader,
+    stats: Stats,
+    next_row: usize,
+}
+
+impl<W: Write + Seek> ChunkWriter<W> {
+    /// start writing a grid with the given header (e.g. from a
+    /// [ChunkReader]), whose size must agree with its rows and columns
+    pub fn new(out: W, header: GridHeader) -> Result<ChunkWriter<W>, Error> {
+        if header.rows as u64 * header.columns as u64 != header.size as u64 {
+            return Err(Error::SizeMismatch(header.size, header.rows,
+              header.columns));
+        }
+        let header = GridHeader { n_triangles: 0, ..header };
+        let mut out = BufWriter::new(out);
+        out.write_all(&header_bytes(&empty_grid(header.clone()))?)?;
+        Ok(ChunkWriter {
+            out,
+            header,
+            stats: Stats::from_values([]),
+            next_row: 0,
+        })
+    }
+
+    /// write the next chunk of rows
+    pub fn write(&mut self, chunk: &GridChunk<'_>) -> Result<(), Error> {
+        let rows = chunk.rows();
+        if rows.start != self.next_row || rows.end > self.header.rows as usize
+          || chunk.data.ncols() != self.header.columns as usize {
+            return Err(Error::InvalidArgument(format!(
+              "expected a chunk starting at row {} with {} columns, got \
+               rows {:?} with {} columns", self.next_row, self.header.columns,
+               rows, chunk.data.ncols())));
+        }
+        for &z in chunk.data.iter() {
+            self.out.write_f64::<LittleEndian>(nan_to_petra_null(z))?;
+        }
+        self.stats = self.stats.merge(&chunk.stats());
+        self.next_row = rows.end;
+        Ok(())
+    }
+
+    /// finish the grid, once every row has been written, filling in the
+    /// header's *z* range; gives back the destination
+    pub fn finish(mut self) -> Result<W, Error> {
+        if self.next_row != self.header.rows as usize {
+            return Err(Error::InvalidArgument(format!(
+              "only {} of {} rows written", self.next_row,
+              self.header.rows)));
+        }
+        self.header.zmin = self.stats.min;
+        self.header.zmax = self.stats.max;
+        let header = header_bytes(&empty_grid(self.header))?;
+        let mut out = self.out.into_inner().map_err(io::Error::from)?;
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&header)?;
+        out.seek(SeekFrom::End(0))?;
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+fn check_rows_per_chunk(rows_per_chunk: usize) -> Result<(), Error> {
+    if rows_per_chunk == 0 {
+        Err(Error::InvalidArgument("chunks must hold at least one row"
+          .to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+// a grid with just a header, for encoding that header
+fn empty_grid(header: GridHeader) -> Grid {
+    Grid::from_parts(header, GridData::Rectangular(
+      Array2::zeros((0, 0)).into_shared()))
+}
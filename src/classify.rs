@@ -0,0 +1,209 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! classification of a continuous grid into a few classes (e.g. for
+//! play-fairway and risk maps); see [Grid::classify]
+
+use crate::{Error, Grid, GridData};
+
+/* natural breaks are found by dynamic programming, which takes time
+ * quadratic in the number of values; past this many, we work from evenly
+ * spaced order statistics instead */
+const MAX_NATURAL_BREAKS_VALUES: usize = 1000;
+
+/// how class boundaries are chosen
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClassificationMethod {
+    /// classes of equal width, spanning the range of values
+    EqualInterval,
+
+    /// classes holding (as nearly as possible) equal numbers of nodes
+    Quantile,
+
+    /// Jenks natural breaks: classes which minimize the variance within
+    /// each class
+    NaturalBreaks,
+}
+
+/// one class of a [Classification]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClassInterval {
+    /// the class's code in the classified grid (starting from 1)
+    pub code: u32,
+
+    /// the smallest value in the class (exclusive, except for the first
+    /// class)
+    pub lower: f64,
+
+    /// the largest value in the class (inclusive)
+    pub upper: f64,
+
+    /// the number of nodes in the class
+    pub count: usize,
+}
+
+/// a classified grid, and its legend
+#[derive(Clone, Debug)]
+pub struct Classification {
+    /// a copy of the original grid, with each node's value replaced by its
+    /// class code (missing nodes stay missing)
+    pub grid: Grid,
+
+    /// the classes, in increasing order of value
+    pub legend: Vec<ClassInterval>,
+}
+
+impl Grid {
+    /// classify a rectangular grid's values into (at most) `classes`
+    /// classes; fewer are produced if there are too few distinct values
+    pub fn classify(&self, method: ClassificationMethod, classes: usize)
+      -> Result<Classification, Error> {
+        let arr = self.rectangular()?;
+        if classes == 0 {
+            return Err(Error::InvalidArgument(
+              "can't classify into zero classes".to_string()));
+        }
+        let mut values = arr.iter().copied()
+          .filter(|z| !z.is_nan())
+          .collect::<Vec<_>>();
+        if values.is_empty() {
+            return Err(Error::InvalidArgument(
+              "no values to classify".to_string()));
+        }
+        values.sort_by(f64::total_cmp);
+
+        let mut uppers = match method {
+            ClassificationMethod::EqualInterval =>
+                equal_interval(&values, classes),
+            ClassificationMethod::Quantile => quantile(&values, classes),
+            ClassificationMethod::NaturalBreaks =>
+                natural_breaks(&values, classes),
+        };
+        // the last class always takes the maximum
+        uppers.push(values[values.len() - 1]);
+        uppers.dedup();
+
+        let mut legend = Vec::with_capacity(uppers.len());
+        let mut lower = values[0];
+        for (k, &upper) in uppers.iter().enumerate() {
+            legend.push(ClassInterval {
+                code: k as u32 + 1,
+                lower,
+                upper,
+                count: 0,
+            });
+            lower = upper;
+        }
+
+        let codes = arr.mapv(|z| {
+            if z.is_nan() {
+                return f64::NAN;
+            }
+            let k = uppers.partition_point(|&upper| upper < z)
+              .min(uppers.len() - 1);
+            k as f64 + 1.0
+        });
+        for &code in codes.iter().filter(|c| !c.is_nan()) {
+            legend[code as usize - 1].count += 1;
+        }
+
+        let mut header = self.header();
+        header.zmin = 1.0;
+        header.zmax = legend.len() as f64;
+        Ok(Classification {
+            grid: Grid::from_parts(header, GridData::Rectangular(codes.into_shared())),
+            legend,
+        })
+    }
+}
+
+// the upper bounds of all but the last class, for each method, from sorted
+// values
+
+fn equal_interval(values: &[f64], classes: usize) -> Vec<f64> {
+    let (lo, hi) = (values[0], values[values.len() - 1]);
+    let width = (hi - lo) / classes as f64;
+    (1..classes).map(|k| lo + k as f64 * width).collect()
+}
+
+fn quantile(values: &[f64], classes: usize) -> Vec<f64> {
+    let n = values.len();
+    (1..classes)
+      .map(|k| values[(k * n / classes).saturating_sub(1).min(n - 1)])
+      .collect()
+}
+
+fn natural_breaks(values: &[f64], classes: usize) -> Vec<f64> {
+    let sample;
+    let values = if values.len() > MAX_NATURAL_BREAKS_VALUES {
+        let n = values.len();
+        let m = MAX_NATURAL_BREAKS_VALUES;
+        sample = (0..m).map(|i| values[i * (n - 1) / (m - 1)])
+          .collect::<Vec<_>>();
+        &sample[..]
+    } else {
+        values
+    };
+    let n = values.len();
+    let classes = classes.min(n);
+
+    /* Fisher's exact method: cost[k][i] is the least total within-class
+     * sum of squared deviations for the first i + 1 values in k + 1
+     * classes, and start[k][i] is where the last of those classes starts */
+    let mut prefix = vec![(0.0, 0.0); n + 1];
+    for (i, &v) in values.iter().enumerate() {
+        prefix[i + 1] = (prefix[i].0 + v, prefix[i].1 + v * v);
+    }
+    // sum of squared deviations of values[a..=b]
+    let ssd = |a: usize, b: usize| {
+        let count = (b - a + 1) as f64;
+        let sum = prefix[b + 1].0 - prefix[a].0;
+        let sq = prefix[b + 1].1 - prefix[a].1;
+        (sq - sum * sum / count).max(0.0)
+    };
+
+    let mut cost = vec![vec![f64::INFINITY; n]; classes];
+    let mut start = vec![vec![0; n]; classes];
+    for (i, c) in cost[0].iter_mut().enumerate() {
+        *c = ssd(0, i);
+    }
+    for k in 1..classes {
+        for i in k..n {
+            for s in k..=i {
+                let c = cost[k - 1][s - 1] + ssd(s, i);
+                if c < cost[k][i] {
+                    cost[k][i] = c;
+                    start[k][i] = s;
+                }
+            }
+        }
+    }
+
+    let mut uppers = Vec::with_capacity(classes - 1);
+    let mut end = n - 1;
+    for k in (1..classes).rev() {
+        let s = start[k][end];
+        uppers.push(values[s - 1]);
+        end = s - 1;
+    }
+    uppers.reverse();
+    uppers
+}
@@ -0,0 +1,136 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! colormaps for rendering *z* values as colors
+
+/// a colormap: a sequence of evenly-spaced RGB color stops, linearly
+/// interpolated between
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Colormap {
+    stops: Vec<[u8; 3]>,
+}
+
+// a coarse sampling of matplotlib's "viridis"; close enough for display
+const VIRIDIS_STOPS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+// a rough "terrain" map: deep blue lows through green and tan to white highs
+const TERRAIN_STOPS: [[u8; 3]; 6] = [
+    [51, 51, 153],
+    [0, 153, 255],
+    [0, 204, 102],
+    [255, 255, 153],
+    [128, 92, 84],
+    [255, 255, 255],
+];
+
+impl Colormap {
+    /// construct a colormap from two or more color stops; returns `None` if
+    /// fewer than two stops are provided
+    pub fn from_stops(stops: Vec<[u8; 3]>) -> Option<Colormap> {
+        if stops.len() < 2 {
+            None
+        } else {
+            Some(Colormap { stops })
+        }
+    }
+
+    /// black (low) to white (high)
+    pub fn greyscale() -> Colormap {
+        Colormap { stops: vec![[0, 0, 0], [255, 255, 255]] }
+    }
+
+    /// a perceptually-uniform purple-green-yellow map
+    pub fn viridis() -> Colormap {
+        Colormap { stops: VIRIDIS_STOPS.to_vec() }
+    }
+
+    /// a topographic-style blue-green-brown-white map
+    pub fn terrain() -> Colormap {
+        Colormap { stops: TERRAIN_STOPS.to_vec() }
+    }
+
+    /// the color stops of this colormap
+    pub fn stops(&self) -> &[[u8; 3]] {
+        &self.stops
+    }
+
+    /// the color for a fraction of the way (from 0 to 1) along the colormap;
+    /// values outside that range are clamped
+    pub fn color(&self, frac: f64) -> [u8; 3] {
+        let frac = if frac.is_nan() { 0.0 } else { frac.clamp(0.0, 1.0) };
+        let pos = frac * (self.stops.len() - 1) as f64;
+        let i = (pos.floor() as usize).min(self.stops.len() - 2);
+        let t = pos - i as f64;
+        let (lo, hi) = (self.stops[i], self.stops[i + 1]);
+        let mut rgb = [0u8; 3];
+        for c in 0..3 {
+            rgb[c] = (lo[c] as f64 + t * (hi[c] as f64 - lo[c] as f64))
+              .round() as u8;
+        }
+        rgb
+    }
+
+    /// the color for a value within a range from `min` to `max`
+    pub fn color_for(&self, val: f64, min: f64, max: f64) -> [u8; 3] {
+        let span = max - min;
+        if span == 0.0 {
+            self.color(0.0)
+        } else {
+            self.color((val - min) / span)
+        }
+    }
+
+    /// the fraction (from 0 to 1) along the colormap whose color most closely
+    /// matches the provided color; this is only approximate, and is
+    /// meaningless for colors far from the colormap
+    pub fn invert(&self, rgb: [u8; 3]) -> f64 {
+        const SAMPLES: usize = 1024;
+        let mut best = (u32::MAX, 0.0);
+        for i in 0..=SAMPLES {
+            let frac = i as f64 / SAMPLES as f64;
+            let c = self.color(frac);
+            let dist = (0..3).map(|k| {
+                let d = c[k] as i32 - rgb[k] as i32;
+                (d * d) as u32
+            }).sum::<u32>();
+            if dist < best.0 {
+                best = (dist, frac);
+            }
+        }
+        best.1
+    }
+}
+
+impl Default for Colormap {
+    fn default() -> Self {
+        Colormap::viridis()
+    }
+}
@@ -0,0 +1,114 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! construction of new grids from scratch
+
+use ndarray::Array2;
+
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::{
+    AxisOrder,
+    ColumnOrder,
+    Grid,
+    GridHeader,
+    GridData,
+    RowOrder,
+    UnitOfMeasure,
+    VerticalDatum,
+};
+
+impl Grid {
+    /// a new rectangular grid with its lower-left node at (`xmin`, `ymin`),
+    /// the given node spacing, and the given (rows × columns, south to north
+    /// and west to east) data
+    ///
+    /// the extents, size, and *z* range are derived from the data; other
+    /// metadata takes default values (no name, feet for all units, created
+    /// now), and may be filled in afterward
+    ///
+    /// a negative step means the data runs the other way along that axis
+    /// (e.g. north to south, as declared by some formats with reversed
+    /// extents), with (`xmin`, `ymin`) the location of its first node; the
+    /// grid is reordered to the usual positive steps
+    pub fn new_rectangular(xmin: f64, ymin: f64, xstep: f64, ystep: f64,
+      data: Array2<f64>) -> Grid {
+        let (rows, columns) = data.dim();
+        let mut order = AxisOrder::SOUTH_UP;
+        if ystep < 0.0 {
+            order.rows = RowOrder::NorthToSouth;
+        }
+        if xstep < 0.0 {
+            order.columns = ColumnOrder::EastToWest;
+        }
+        let data = if order == AxisOrder::SOUTH_UP {
+            data
+        } else {
+            order.reorder(data.view())
+        };
+        let (xmin, xstep) = if xstep < 0.0 {
+            (xmin + columns.saturating_sub(1) as f64 * xstep, -xstep)
+        } else {
+            (xmin, xstep)
+        };
+        let (ymin, ystep) = if ystep < 0.0 {
+            (ymin + rows.saturating_sub(1) as f64 * ystep, -ystep)
+        } else {
+            (ymin, ystep)
+        };
+        let data = GridData::Rectangular(data.into_shared());
+        let (zmin, zmax) = {
+            let s = data.stats();
+            (s.min, s.max)
+        };
+        let now = OffsetDateTime::now_utc();
+        let header = GridHeader {
+            version: 2,
+            name: String::new(),
+            size: (rows * columns) as u32,
+            rows: rows as u32,
+            columns: columns as u32,
+            n_triangles: 0,
+            xmin,
+            xmax: xmin + columns.saturating_sub(1) as f64 * xstep,
+            ymin,
+            ymax: ymin + rows.saturating_sub(1) as f64 * ystep,
+            xstep,
+            ystep,
+            zmin,
+            zmax,
+            xyunits: UnitOfMeasure::Feet,
+            zunits: UnitOfMeasure::Feet,
+            created_date: PrimitiveDateTime::new(now.date(), now.time()),
+            source_data: String::new(),
+            unknown_metadata: String::from("C66"),
+            projection: String::new(),
+            datum: String::new(),
+            grid_method: 0,
+            projection_code: 0,
+            cm: 0.0,
+            rlat: 0.0,
+            vertical_datum: VerticalDatum::Unknown,
+            quantity: None,
+        };
+        Grid::from_parts(header, data)
+    }
+}
@@ -0,0 +1,174 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! contour lines of grids, for maps and CAD exports
+
+use std::collections::HashMap;
+
+use crate::{Error, Grid, export::mesh::Mesh};
+
+// the most levels contour_levels will produce; a tiny interval could
+// otherwise ask for more levels than there's memory to hold
+const MAX_LEVELS: f64 = 100_000.0;
+
+/// a contour line at some level: a polyline in (*x*, *y*), which may be
+/// closed (when its last point joins its first)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contour {
+    /// the *z* value traced by the contour
+    pub level: f64,
+
+    /// the points of the polyline
+    pub points: Vec<(f64, f64)>,
+
+    /// does the contour close on itself? (the first point isn't repeated at
+    /// the end)
+    pub closed: bool,
+}
+
+// a mesh edge, by its vertices (lower index first)
+type Edge = (usize, usize);
+
+impl Grid {
+    /// contour lines at each of the given levels, in that order
+    ///
+    /// contours are traced through the grid's triangles (for rectangular
+    /// grids, each cell is split into two triangles, which resolves the
+    /// ambiguous "saddle" cells of marching squares), and stop where the
+    /// grid has missing values; a node exactly at a contour's level counts
+    /// as above it
+    pub fn contours(&self, levels: &[f64]) -> Vec<Contour> {
+        let mesh = Mesh::from_grid(self);
+        let mut contours = Vec::new();
+        for &level in levels.iter().filter(|l| l.is_finite()) {
+            trace(&mesh, level, &mut contours);
+        }
+        contours
+    }
+
+    /// a round contour interval (1, 2, or 5 times a power of ten) giving
+    /// about ten levels across the range of the grid's *z* values; `None`
+    /// if the grid has no values, or they're all the same
+    pub fn contour_interval(&self) -> Option<f64> {
+        let stats = self.data.stats();
+        let range = stats.max - stats.min;
+        if stats.count == 0 || !(range > 0.0 && range.is_finite()) {
+            return None;
+        }
+        let rough = range / 10.0;
+        let magnitude = 10f64.powf(rough.log10().floor());
+        let mantissa = match rough / magnitude {
+            m if m < 1.5 => 1.0,
+            m if m < 3.5 => 2.0,
+            m if m < 7.5 => 5.0,
+            _ => 10.0,
+        };
+        Some(mantissa * magnitude)
+    }
+
+    /// the levels at every multiple of `interval` within the range of the
+    /// grid's *z* values, from lowest to highest; empty unless the interval
+    /// is positive (and finite)
+    ///
+    /// fails with [Error::InvalidArgument] if the interval is so small that
+    /// there would be more than 100,000 levels
+    pub fn contour_levels(&self, interval: f64) -> Result<Vec<f64>, Error> {
+        let stats = self.data.stats();
+        if !(interval > 0.0 && interval.is_finite()) || stats.count == 0 {
+            return Ok(Vec::new());
+        }
+        let first = (stats.min / interval).ceil();
+        let last = (stats.max / interval).floor();
+        let n = last - first;
+        if n.is_nan() || n >= MAX_LEVELS {
+            return Err(Error::InvalidArgument(format!(
+              "contour interval {} gives too many levels between {} and {}",
+              interval, stats.min, stats.max)));
+        }
+        let first = first as i64;
+        Ok((first..=last as i64).map(|k| k as f64 * interval).collect())
+    }
+}
+
+// trace the contours at one level, joining the segments crossing each face
+fn trace(mesh: &Mesh, level: f64, contours: &mut Vec<Contour>) {
+    let above = |v: usize| mesh.vertices[v][2] >= level;
+
+    // each segment crosses two edges of a face; each edge joins two segments
+    // (or one, on the boundary of the mesh)
+    let mut segments: Vec<[Edge; 2]> = Vec::new();
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for &[a, b, c] in &mesh.faces {
+        let crossing = [(a, b), (b, c), (c, a)].into_iter()
+          .filter(|&(u, v)| above(u) != above(v))
+          .map(|(u, v)| (u.min(v), u.max(v)))
+          .collect::<Vec<_>>();
+        if let [e1, e2] = crossing[..] {
+            let n = segments.len();
+            segments.push([e1, e2]);
+            by_edge.entry(e1).or_default().push(n);
+            by_edge.entry(e2).or_default().push(n);
+        }
+    }
+
+    let point = |(u, v): Edge| {
+        let [xu, yu, zu] = mesh.vertices[u];
+        let [xv, yv, zv] = mesh.vertices[v];
+        let t = (level - zu) / (zv - zu);
+        (xu + t * (xv - xu), yu + t * (yv - yu))
+    };
+
+    /* walk from each unused segment in turn, starting with those which end
+     * on the boundary so that open contours are traced from end to end */
+    let mut used = vec![false; segments.len()];
+    let mut starts = (0..segments.len())
+      .filter(|&n| segments[n].iter().any(|e| by_edge[e].len() == 1))
+      .collect::<Vec<_>>();
+    starts.extend(0..segments.len());
+    for start in starts {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let [first, second] = segments[start];
+        // begin at the boundary end, if there is one
+        let (first, mut edge) = if by_edge[&second].len() == 1 {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        let mut points = vec![point(first), point(edge)];
+        let closed = loop {
+            let next = by_edge[&edge].iter().copied().find(|&n| !used[n]);
+            let Some(next) = next else {
+                break false;
+            };
+            used[next] = true;
+            let [e1, e2] = segments[next];
+            edge = if e1 == edge { e2 } else { e1 };
+            if edge == first {
+                break true;
+            }
+            points.push(point(edge));
+        };
+        contours.push(Contour { level, points, closed });
+    }
+}
@@ -0,0 +1,225 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! horizontal datum shifts (e.g. NAD27 to NAD83) via NADCON grid-shift files
+//!
+//! only NADCON's static shifts are supported; HTDP's time-dependent
+//! transformations (between NAD83 realizations, or to ITRF at an epoch)
+//! need its crustal velocity models, which we don't implement
+//!
+//! grid shifts operate on geographic (longitude, latitude) coordinates;
+//! Petra grids are usually in projected (e.g. state plane) coordinates, so
+//! shifting a grid means unprojecting, shifting, and reprojecting each point.
+//! we don't implement projections, so supply your own (e.g. via PROJ) and
+//! combine them with a [GridShift] using [Grid::transform_xy]:
+//!
+//! ```no_run
+//! # use petra_grid::{Grid, datum::GridShift};
+//! # fn unproject(x: f64, y: f64) -> (f64, f64) { (x, y) }
+//! # fn project(lon: f64, lat: f64) -> (f64, f64) { (lon, lat) }
+//! # let grid: Grid = unimplemented!();
+//! let shift = GridShift::read_nadcon_files("conus")?; // conus.las, conus.los
+//! let nad83 = grid.transform_xy(
+//!   |x, y| {
+//!       let (lon, lat) = unproject(x, y); // NAD27 state plane
+//!       shift.forward(lon, lat).map(|(lon, lat)| project(lon, lat))
+//!   },
+//!   |x, y| {
+//!       let (lon, lat) = unproject(x, y); // NAD83 state plane
+//!       shift.inverse(lon, lat).map(|(lon, lat)| project(lon, lat))
+//!   });
+//! # Ok::<(), petra_grid::Error>(())
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::Error;
+
+// the fixed-size start of a NADCON header: identifier and program name
+const NADCON_ID_LEN: usize = 64;
+
+// iterations for inverting a shift; shifts are smooth and tiny, so this is
+// far more than enough
+const INVERSE_ITERATIONS: usize = 10;
+
+/// a grid of horizontal datum shifts, as published by NADCON (a pair of
+/// `.las` latitude-shift and `.los` longitude-shift files)
+#[derive(Clone, Debug)]
+pub struct GridShift {
+    lon_min: f64,
+    lat_min: f64,
+    lon_step: f64,
+    lat_step: f64,
+    columns: usize,
+    rows: usize,
+    // both in arc-seconds, south to north; longitude shifts are positive west
+    lat_shifts: Vec<f32>,
+    lon_shifts: Vec<f32>,
+}
+
+impl GridShift {
+    /// read a shift grid from a NADCON `.las`/`.los` pair in the original
+    /// binary format
+    pub fn read_nadcon<R: Read, S: Read>(las: &mut R, los: &mut S)
+      -> Result<GridShift, Error> {
+        let (lat_header, lat_shifts) = read_nadcon_grid(las)?;
+        let (lon_header, lon_shifts) = read_nadcon_grid(los)?;
+        if lat_header != lon_header {
+            return Err(Error::InvalidInput(
+              "NADCON .las and .los files cover different areas".to_string()));
+        }
+        let NadconHeader { columns, rows, lon_min, lon_step, lat_min,
+          lat_step } = lat_header;
+        Ok(GridShift {
+            lon_min: lon_min as f64,
+            lat_min: lat_min as f64,
+            lon_step: lon_step as f64,
+            lat_step: lat_step as f64,
+            columns,
+            rows,
+            lat_shifts,
+            lon_shifts,
+        })
+    }
+
+    /// read a shift grid from NADCON files named by a common base path,
+    /// with `.las` and `.los` extensions
+    pub fn read_nadcon_files<P: AsRef<Path>>(base: P)
+      -> Result<GridShift, Error> {
+        let base = base.as_ref();
+        let mut las = BufReader::new(File::open(base.with_extension("las"))?);
+        let mut los = BufReader::new(File::open(base.with_extension("los"))?);
+        GridShift::read_nadcon(&mut las, &mut los)
+    }
+
+    /// shift a (longitude, latitude) position, in degrees (east-positive),
+    /// from the source datum (e.g. NAD27) to the target (e.g. NAD83);
+    /// `None` outside the area covered by the shift grid
+    pub fn forward(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let (dlon, dlat) = self.shift_at(lon, lat)?;
+        Some((lon + dlon, lat + dlat))
+    }
+
+    /// shift a (longitude, latitude) position from the target datum back to
+    /// the source; `None` outside the area covered by the shift grid
+    pub fn inverse(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        // the shift is evaluated at the source position, so iterate
+        let (mut src_lon, mut src_lat) = (lon, lat);
+        for _ in 0..INVERSE_ITERATIONS {
+            let (dlon, dlat) = self.shift_at(src_lon, src_lat)?;
+            src_lon = lon - dlon;
+            src_lat = lat - dlat;
+        }
+        Some((src_lon, src_lat))
+    }
+
+    // the (east-positive) shift in degrees at a source position
+    fn shift_at(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let fi = (lon - self.lon_min) / self.lon_step;
+        let fj = (lat - self.lat_min) / self.lat_step;
+        if !(0.0..=(self.columns - 1) as f64).contains(&fi)
+          || !(0.0..=(self.rows - 1) as f64).contains(&fj) {
+            return None;
+        }
+
+        let i0 = (fi.floor() as usize).min(self.columns.saturating_sub(2));
+        let j0 = (fj.floor() as usize).min(self.rows.saturating_sub(2));
+        let i1 = (i0 + 1).min(self.columns - 1);
+        let j1 = (j0 + 1).min(self.rows - 1);
+        let (ti, tj) = (fi - i0 as f64, fj - j0 as f64);
+        let bilinear = |v: &[f32]| {
+            let at = |j: usize, i: usize| v[j * self.columns + i] as f64;
+            let south = at(j0, i0) * (1.0 - ti) + at(j0, i1) * ti;
+            let north = at(j1, i0) * (1.0 - ti) + at(j1, i1) * ti;
+            south * (1.0 - tj) + north * tj
+        };
+
+        Some((-bilinear(&self.lon_shifts) / 3600.0,
+          bilinear(&self.lat_shifts) / 3600.0))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct NadconHeader {
+    columns: usize,
+    rows: usize,
+    lon_min: f32,
+    lon_step: f32,
+    lat_min: f32,
+    lat_step: f32,
+}
+
+/* NADCON grids are stored in fixed-length records of (columns + 1) 4-byte
+ * words: the header occupies the first record, and each row of shifts
+ * (south to north) a following record, preceded by a word we ignore */
+fn read_nadcon_grid<R: Read>(source: &mut R)
+  -> Result<(NadconHeader, Vec<f32>), Error> {
+    let mut id = [0u8; NADCON_ID_LEN];
+    source.read_exact(&mut id)?;
+    let columns = source.read_i32::<LittleEndian>()?;
+    let rows = source.read_i32::<LittleEndian>()?;
+    let _planes = source.read_i32::<LittleEndian>()?;
+    let lon_min = source.read_f32::<LittleEndian>()?;
+    let lon_step = source.read_f32::<LittleEndian>()?;
+    let lat_min = source.read_f32::<LittleEndian>()?;
+    let lat_step = source.read_f32::<LittleEndian>()?;
+    let _angle = source.read_f32::<LittleEndian>()?;
+
+    if columns < 2 || rows < 2 || lon_step <= 0.0 || lat_step <= 0.0 {
+        return Err(Error::InvalidInput(
+          "malformed NADCON grid header".to_string()));
+    }
+    let (columns, rows) = (columns as usize, rows as usize);
+
+    let header_len = NADCON_ID_LEN + 8 * 4;
+    let record_len = columns.checked_add(1).and_then(|n| n.checked_mul(4))
+      .filter(|&len| len >= header_len)
+      .ok_or_else(|| Error::InvalidInput(
+        "NADCON grid too narrow for its header".to_string()))?;
+
+    /* read what's there before allocating, so that a corrupt header can't
+     * provoke a huge allocation */
+    let mut records = Vec::new();
+    source.read_to_end(&mut records)?;
+    let needed = rows.checked_add(1).and_then(|n| n.checked_mul(record_len))
+      .and_then(|len| len.checked_sub(header_len));
+    if needed.filter(|&needed| needed <= records.len()).is_none() {
+        return Err(Error::InvalidInput(format!(
+          "NADCON grid declares {} rows x {} columns, more than the file holds",
+          rows, columns)));
+    }
+
+    let mut shifts = vec![0.0; rows * columns];
+    let mut data = &records[record_len - header_len..];
+    for row in shifts.chunks_exact_mut(columns) {
+        let _ = data.read_u32::<LittleEndian>()?;
+        data.read_f32_into::<LittleEndian>(row)?;
+    }
+
+    Ok((NadconHeader { columns, rows, lon_min, lon_step, lat_min, lat_step },
+      shifts))
+}
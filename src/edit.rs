@@ -0,0 +1,197 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! local edits to a grid's surface, within a radius of a point or inside a
+//! polygon, for scripted fixes of "busts" (spurious highs and lows) without
+//! regridding; see [Grid::edit_region] and [Grid::apply_edit]
+//!
+//! an edit changes the nodes of a rectangular grid, or the triangle
+//! vertices of a triangular grid, which lie within the region (including on
+//! its boundary); missing values are left missing. the grid's *z* range
+//! (`zmin` and `zmax`) is updated after each edit
+
+use crate::{Error, Grid, GridData, import::polygons::Polygon};
+
+/// a region of a grid to edit
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditRegion {
+    /// the disc within `radius` (in *x*/*y* units) of `center`
+    Circle {
+        /// the (*x*, *y*) center of the disc
+        center: (f64, f64),
+        /// the radius of the disc
+        radius: f64,
+    },
+    /// the inside of a polygon, in the grid's coordinate system
+    Polygon(Polygon),
+}
+
+impl EditRegion {
+    /// does the region contain the point (*x*, *y*)?
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            EditRegion::Circle { center: (cx, cy), radius } =>
+                (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius,
+            EditRegion::Polygon(polygon) => polygon.contains(x, y),
+        }
+    }
+}
+
+/// an edit to the *z* values within a region
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RegionEdit {
+    /// replace each value with the given one
+    Set(f64),
+    /// add the given amount (negative to subtract) to each value
+    Add(f64),
+    /// replace each node with the mean of the non-missing values in its
+    /// 3 × 3 neighborhood (as [Grid::smooth] does), `passes` times; nodes
+    /// just outside the region contribute to the means, but aren't changed,
+    /// so the edit blends into its surroundings. rectangular grids only
+    Smooth {
+        /// the number of smoothing passes
+        passes: usize,
+    },
+}
+
+impl Grid {
+    /// apply a function to each non-missing *z* value within `radius` (in
+    /// *x*/*y* units) of `center`, updating the grid's *z* range; returns
+    /// the number of values edited
+    ///
+    /// ```
+    /// use petra_grid::testing::{Lattice, Surface};
+    ///
+    /// let flat = Surface::Plane { z0: 100.0, dzdx: 0.0, dzdy: 0.0 };
+    /// let mut grid = flat.rectangular(&Lattice::default());
+    /// let (x, y) = grid.node_xy(5, 5);
+    /// grid.edit_region((x, y), 1.0, |z| z - 30.0);
+    /// assert_eq!(grid.rectangular().unwrap()[(5, 5)], 70.0);
+    /// assert_eq!(grid.zmin, 70.0);
+    /// ```
+    pub fn edit_region<F: FnMut(f64) -> f64>(&mut self, center: (f64, f64),
+      radius: f64, f: F) -> usize {
+        let region = EditRegion::Circle { center, radius };
+        self.edit_where(|x, y| region.contains(x, y), f)
+    }
+
+    /// apply a function to each non-missing *z* value inside a polygon,
+    /// updating the grid's *z* range; returns the number of values edited
+    pub fn edit_polygon<F: FnMut(f64) -> f64>(&mut self, polygon: &Polygon,
+      f: F) -> usize {
+        self.edit_where(|x, y| polygon.contains(x, y), f)
+    }
+
+    /// apply an edit to the *z* values within a region, updating the grid's
+    /// *z* range; returns the number of values edited
+    ///
+    /// smoothing a triangular grid is an [Error::NotRectangular]
+    pub fn apply_edit(&mut self, region: &EditRegion, edit: RegionEdit)
+      -> Result<usize, Error> {
+        let contains = |x, y| region.contains(x, y);
+        match edit {
+            RegionEdit::Set(value) => Ok(self.edit_where(contains, |_| value)),
+            RegionEdit::Add(delta) =>
+                Ok(self.edit_where(contains, |z| z + delta)),
+            RegionEdit::Smooth { passes } =>
+                self.smooth_where(contains, passes),
+        }
+    }
+
+    fn edit_where<C, F>(&mut self, contains: C, mut f: F) -> usize
+      where C: Fn(f64, f64) -> bool,
+            F: FnMut(f64) -> f64,
+    {
+        let (xmin, ymin, xstep, ystep) =
+          (self.xmin, self.ymin, self.xstep, self.ystep);
+        let mut edited = 0;
+        match &mut self.data {
+            GridData::Rectangular(arr) => {
+                for ((j, i), z) in arr.indexed_iter_mut() {
+                    let (x, y) = (xmin + i as f64 * xstep,
+                      ymin + j as f64 * ystep);
+                    if !z.is_nan() && contains(x, y) {
+                        *z = f(*z);
+                        edited += 1;
+                    }
+                }
+            },
+            GridData::Triangular(arr) => {
+                for mut tri in arr.outer_iter_mut() {
+                    for v in 0..3 {
+                        let (x, y) = (tri[(v, 0)], tri[(v, 1)]);
+                        let z = &mut tri[(v, 2)];
+                        if !z.is_nan() && contains(x, y) {
+                            *z = f(*z);
+                            edited += 1;
+                        }
+                    }
+                }
+            },
+        }
+        self.update_z_range();
+        edited
+    }
+
+    fn smooth_where<C: Fn(f64, f64) -> bool>(&mut self, contains: C,
+      passes: usize) -> Result<usize, Error> {
+        let nodes = {
+            let arr = self.rectangular()?;
+            arr.indexed_iter()
+              .filter(|&((j, i), z)| {
+                  let (x, y) = self.node_xy(j, i);
+                  !z.is_nan() && contains(x, y)
+              })
+              .map(|(ji, _)| ji)
+              .collect::<Vec<_>>()
+        };
+        let GridData::Rectangular(arr) = &mut self.data else {
+            unreachable!("checked rectangular above");
+        };
+        let (rows, cols) = arr.dim();
+        for _ in 0..passes {
+            let means = nodes.iter().map(|&(j, i)| {
+                let (mut sum, mut n) = (0.0, 0);
+                for jj in j.saturating_sub(1)..(j + 2).min(rows) {
+                    for ii in i.saturating_sub(1)..(i + 2).min(cols) {
+                        let z = arr[(jj, ii)];
+                        if !z.is_nan() {
+                            sum += z;
+                            n += 1;
+                        }
+                    }
+                }
+                sum / n as f64
+            }).collect::<Vec<_>>();
+            for (&ji, mean) in nodes.iter().zip(means) {
+                arr[ji] = mean;
+            }
+        }
+        self.update_z_range();
+        Ok(nodes.len())
+    }
+
+    fn update_z_range(&mut self) {
+        let stats = self.data.stats();
+        self.zmin = stats.min;
+        self.zmax = stats.max;
+    }
+}
@@ -0,0 +1,203 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! EPSG codes for the coordinate systems Petra grids are (usually) in
+//!
+//! Petra records projections by name (e.g. "TX-27C"), which, as far as we
+//! can tell, give a US state's postal abbreviation, the datum's year (27 for
+//! NAD27, 83 for NAD83), and the state plane zone (e.g. "C" for central; "N",
+//! "NC", "SC", "S", "E", "EC", "WC", and "W" are the others, and California's
+//! zones are numbered). we map these to EPSG codes for the common state plane
+//! zones of oil- and gas-producing states; the table is necessarily partial,
+//! so please open an issue if yours is missing
+//!
+//! NAD27 state plane systems are always in US survey feet; NAD83 systems
+//! come in meters and (for most zones) US survey feet, and are chosen by the
+//! grid's *x*/*y* units
+
+use crate::{Grid, GridHeader, UnitOfMeasure};
+
+// a state plane zone's FIPS zone code, and its EPSG codes, for NAD27 and
+// for NAD83 in meters and in US survey feet
+struct Zone {
+    state: &'static str,
+    zone: &'static str,
+    fips: u16,
+    nad27: Option<u32>,
+    nad83_m: Option<u32>,
+    nad83_ftus: Option<u32>,
+}
+
+// 0 marks a system which doesn't exist
+const fn code(c: u32) -> Option<u32> {
+    if c == 0 { None } else { Some(c) }
+}
+
+const fn zone(state: &'static str, zone: &'static str, fips: u16,
+  nad27: u32, nad83_m: u32, nad83_ftus: u32) -> Zone {
+    Zone {
+        state,
+        zone,
+        fips,
+        nad27: code(nad27),
+        nad83_m: code(nad83_m),
+        nad83_ftus: code(nad83_ftus),
+    }
+}
+
+static ZONES: &[Zone] = &[
+    zone("AR", "N", 301, 26751, 26951, 3433),
+    zone("AR", "S", 302, 26752, 26952, 3434),
+    zone("CA", "I", 401, 26741, 26941, 2225),
+    zone("CA", "II", 402, 26742, 26942, 2226),
+    zone("CA", "III", 403, 26743, 26943, 2227),
+    zone("CA", "IV", 404, 26744, 26944, 2228),
+    zone("CA", "V", 405, 26745, 26945, 2229),
+    zone("CA", "VI", 406, 26746, 26946, 2230),
+    zone("CA", "VII", 407, 26799, 0, 0),
+    zone("CO", "N", 501, 26753, 26953, 2231),
+    zone("CO", "C", 502, 26754, 26954, 2232),
+    zone("CO", "S", 503, 26755, 26955, 2233),
+    zone("IL", "E", 1201, 26771, 26971, 3435),
+    zone("IL", "W", 1202, 26772, 26972, 3436),
+    zone("IN", "E", 1301, 26773, 26973, 2965),
+    zone("IN", "W", 1302, 26774, 26974, 2966),
+    zone("KS", "N", 1501, 26777, 26977, 3419),
+    zone("KS", "S", 1502, 26778, 26978, 3420),
+    zone("KY", "N", 1601, 26779, 0, 0),
+    zone("KY", "S", 1602, 26780, 0, 0),
+    zone("LA", "N", 1701, 26781, 26981, 3451),
+    zone("LA", "S", 1702, 26782, 26982, 3452),
+    zone("MS", "E", 2301, 26794, 26994, 2254),
+    zone("MS", "W", 2302, 26795, 26995, 2255),
+    zone("MT", "N", 2501, 32001, 0, 0),
+    zone("MT", "C", 2502, 32002, 0, 0),
+    zone("MT", "S", 2503, 32003, 0, 0),
+    zone("ND", "N", 3301, 32020, 32120, 0),
+    zone("ND", "S", 3302, 32021, 32121, 0),
+    zone("NM", "E", 3001, 32012, 32112, 2257),
+    zone("NM", "C", 3002, 32013, 32113, 2258),
+    zone("NM", "W", 3003, 32014, 32114, 2259),
+    zone("OH", "N", 3401, 32022, 32122, 3734),
+    zone("OH", "S", 3402, 32023, 32123, 3735),
+    zone("OK", "N", 3501, 32024, 32124, 2267),
+    zone("OK", "S", 3502, 32025, 32125, 2268),
+    zone("PA", "N", 3701, 32028, 32128, 2271),
+    zone("PA", "S", 3702, 32029, 32129, 2272),
+    zone("TX", "N", 4201, 32037, 32137, 2275),
+    zone("TX", "NC", 4202, 32038, 32138, 2276),
+    zone("TX", "C", 4203, 32039, 32139, 2277),
+    zone("TX", "SC", 4204, 32040, 32140, 2278),
+    zone("TX", "S", 4205, 32041, 32141, 2279),
+    zone("UT", "N", 4301, 32042, 32142, 3560),
+    zone("UT", "C", 4302, 32043, 32143, 3566),
+    zone("UT", "S", 4303, 32044, 32144, 3567),
+    zone("WV", "N", 4701, 32050, 32150, 0),
+    zone("WV", "S", 4702, 32051, 32151, 0),
+    zone("WY", "E", 4901, 32005, 32155, 3736),
+    zone("WY", "EC", 4902, 32006, 32156, 3737),
+    zone("WY", "WC", 4903, 32007, 32157, 3738),
+    zone("WY", "W", 4904, 32008, 32158, 3739),
+];
+
+/// the EPSG code for a Petra projection name (e.g. "TX-27C"), if we know
+/// it; if the name doesn't give the datum's year, the datum name (e.g.
+/// "NAD83") is used instead
+pub fn lookup(projection: &str, datum: &str, xyunits: UnitOfMeasure)
+  -> Option<u32> {
+    let (zone, year) = find_zone(projection, datum)?;
+    match (year, xyunits) {
+        ("27", UnitOfMeasure::Feet) => zone.nad27,
+        ("83", UnitOfMeasure::Feet) => zone.nad83_ftus,
+        ("83", UnitOfMeasure::Meters) => zone.nad83_m,
+        _ => None,
+    }
+}
+
+/// the state plane zone of a Petra projection name, as its FIPS zone code
+/// (e.g. 4203 for Texas central), and the year of its datum (27 or 83), if
+/// we know it; see [lookup]
+pub fn state_plane_zone(projection: &str, datum: &str) -> Option<(u16, u16)> {
+    let (zone, year) = find_zone(projection, datum)?;
+    Some((zone.fips, if year == "27" { 27 } else { 83 }))
+}
+
+/// the Petra projection name (e.g. "TX-27C"), datum name, and *x*/*y* units
+/// of the state plane system with an EPSG code, if we know it; the inverse
+/// of [lookup]
+pub fn coordinate_system(code: u32)
+  -> Option<(String, &'static str, UnitOfMeasure)> {
+    ZONES.iter().find_map(|z| {
+        let (year, datum, units) = if z.nad27 == Some(code) {
+            ("27", "NAD27", UnitOfMeasure::Feet)
+        } else if z.nad83_m == Some(code) {
+            ("83", "NAD83", UnitOfMeasure::Meters)
+        } else if z.nad83_ftus == Some(code) {
+            ("83", "NAD83", UnitOfMeasure::Feet)
+        } else {
+            return None;
+        };
+        Some((format!("{}-{}{}", z.state, year, z.zone), datum, units))
+    })
+}
+
+// the zone, and the datum's year ("27" or "83")
+fn find_zone(projection: &str, datum: &str)
+  -> Option<(&'static Zone, &'static str)> {
+    let projection = projection.trim().to_ascii_uppercase();
+    let (state, rest) = projection.split_once('-')?;
+    let (year, zone_name) = match rest.get(..2) {
+        Some("27") => ("27", &rest[2..]),
+        Some("83") => ("83", &rest[2..]),
+        _ => (datum_year(datum)?, rest),
+    };
+    let zone = ZONES.iter()
+      .find(|z| z.state == state && z.zone == zone_name.trim())?;
+    Some((zone, year))
+}
+
+// "27" or "83", from a datum name like "NAD27" or "NAD 1983"
+fn datum_year(datum: &str) -> Option<&'static str> {
+    let datum = datum.to_ascii_uppercase().replace(' ', "");
+    if datum.contains("NAD27") || datum.contains("NAD1927") {
+        Some("27")
+    } else if datum.contains("NAD83") || datum.contains("NAD1983") {
+        Some("83")
+    } else {
+        None
+    }
+}
+
+impl Grid {
+    /// the EPSG code of the grid's coordinate system, if we can work it out
+    /// (see [lookup])
+    pub fn epsg(&self) -> Option<u32> {
+        lookup(&self.projection, &self.datum, self.xyunits)
+    }
+}
+
+impl GridHeader {
+    /// the EPSG code of the grid's coordinate system, if we can work it out
+    /// (see [lookup])
+    pub fn epsg(&self) -> Option<u32> {
+        lookup(&self.projection, &self.datum, self.xyunits)
+    }
+}
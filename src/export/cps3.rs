@@ -0,0 +1,107 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! CPS-3 ASCII grids
+//!
+//! `FSASCI`, `FSATTR`, `FSLIMI` (*x*, *y*, and *z* limits), `FSNROW` (rows
+//! and columns), and `FSXINC` (*x* and *y* steps) header records and a
+//! `->`-prefixed grid name are followed by the nodes row by row from north to
+//! south, each row from west to east, [VALUES_PER_LINE] to a line. missing
+//! values are written as [NULL_VALUE]. triangular grids are rasterized first
+//! (see [Grid::rasterize])
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+/// the sentinel written for missing values
+pub const NULL_VALUE: f64 = 1e30;
+
+/// the number of values written to a line
+pub const VALUES_PER_LINE: usize = 5;
+
+/// write a grid as a CPS-3 ASCII grid
+pub fn write_cps3<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+
+    let xmax = grid.xmin + cols.saturating_sub(1) as f64 * grid.xstep;
+    let ymax = grid.ymin + rows.saturating_sub(1) as f64 * grid.ystep;
+    let stats = grid.data.stats();
+    let (zmin, zmax) = if stats.count > 0 {
+        (stats.min, stats.max)
+    } else {
+        (0.0, 0.0)
+    };
+
+    let mut out = BufWriter::new(out);
+    writeln!(out, "FSASCI 0 1 COMPUTED 0 {:E}", NULL_VALUE)?;
+    writeln!(out, "FSATTR 0 0")?;
+    writeln!(out, "FSLIMI {} {} {} {} {} {}", grid.xmin, xmax, grid.ymin, ymax,
+      zmin, zmax)?;
+    writeln!(out, "FSNROW {} {}", rows, cols)?;
+    writeln!(out, "FSXINC {} {}", grid.xstep, grid.ystep)?;
+    writeln!(out, "->{}", grid.name.trim())?;
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            let z = arr[(j, i)];
+            if z.is_nan() {
+                write!(out, "{:E}", NULL_VALUE)?;
+            } else {
+                write!(out, "{}", z)?;
+            }
+            if (i + 1) % VALUES_PER_LINE == 0 || i + 1 == cols {
+                writeln!(out)?;
+            } else {
+                write!(out, " ")?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// CPS-3 ASCII grid, as an [Exporter]; see [write_cps3]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Cps3;
+
+impl Exporter for Cps3 {
+    fn name(&self) -> &str {
+        "cps3"
+    }
+
+    fn extension(&self) -> &str {
+        "cps"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_cps3(grid, out)
+    }
+}
@@ -0,0 +1,199 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! AutoCAD DXF drawings, for land and lease mapping in CAD
+//!
+//! a grid can be written either as its surface, one `3DFACE` per triangle
+//! (rectangular cells are split in two, and faces with missing vertices are
+//! omitted), or as `POLYLINE` contour lines at a regular interval, each at
+//! the elevation of its level. drawings are minimal ASCII DXF in the R12
+//! (`AC1009`) dialect, which needs no handles, tables, or objects, and which
+//! every CAD package still reads: a header giving the version, drawing
+//! units, and extents, then the entities, on the `SURFACE` or `CONTOURS`
+//! layer
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    UnitOfMeasure,
+    export::{Exporter, crs_description, mesh::Mesh},
+};
+
+/// the layer for surface faces
+pub const SURFACE_LAYER: &str = "SURFACE";
+
+/// the layer for contour lines
+pub const CONTOUR_LAYER: &str = "CONTOURS";
+
+/// write a grid's surface as `3DFACE` entities
+pub fn write_dxf<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mesh = Mesh::from_grid(grid);
+    let mut out = BufWriter::new(out);
+    write_header(grid, &mut out)?;
+    for face in &mesh.faces {
+        pair(&mut out, 0, "3DFACE")?;
+        pair(&mut out, 8, SURFACE_LAYER)?;
+        /* 3DFACE always has four corners; a triangle repeats its third */
+        let corners = [face[0], face[1], face[2], face[2]];
+        for (k, &v) in corners.iter().enumerate() {
+            let [x, y, z] = mesh.vertices[v];
+            pair(&mut out, 10 + k, x)?;
+            pair(&mut out, 20 + k, y)?;
+            pair(&mut out, 30 + k, z)?;
+        }
+    }
+    write_trailer(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// write contour lines of a grid, at every multiple of `interval`, as
+/// `POLYLINE` entities at the elevation of their levels; see
+/// [Grid::contours]
+///
+/// fails with [Error::InvalidArgument] unless the interval is positive
+pub fn write_dxf_contours<W: Write + ?Sized>(grid: &Grid, interval: f64,
+  out: &mut W) -> Result<(), Error> {
+    if !(interval > 0.0 && interval.is_finite()) {
+        return Err(Error::InvalidArgument(format!(
+          "invalid contour interval {}", interval)));
+    }
+
+    let contours = grid.contours(&grid.contour_levels(interval)?);
+    let mut out = BufWriter::new(out);
+    write_header(grid, &mut out)?;
+    for contour in &contours {
+        /* an R12 polyline is a POLYLINE, whose "point" carries only its
+         * elevation, then a VERTEX per point, then a SEQEND */
+        pair(&mut out, 0, "POLYLINE")?;
+        pair(&mut out, 8, CONTOUR_LAYER)?;
+        pair(&mut out, 66, 1)?;
+        pair(&mut out, 10, 0.0)?;
+        pair(&mut out, 20, 0.0)?;
+        pair(&mut out, 30, contour.level)?;
+        pair(&mut out, 70, if contour.closed { 1 } else { 0 })?;
+        for (x, y) in &contour.points {
+            pair(&mut out, 0, "VERTEX")?;
+            pair(&mut out, 8, CONTOUR_LAYER)?;
+            pair(&mut out, 10, x)?;
+            pair(&mut out, 20, y)?;
+            pair(&mut out, 30, contour.level)?;
+        }
+        pair(&mut out, 0, "SEQEND")?;
+        pair(&mut out, 8, CONTOUR_LAYER)?;
+    }
+    write_trailer(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// DXF surfaces, as an [Exporter]; see [write_dxf]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Dxf;
+
+impl Exporter for Dxf {
+    fn name(&self) -> &str {
+        "dxf"
+    }
+
+    fn extension(&self) -> &str {
+        "dxf"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_dxf(grid, out)
+    }
+}
+
+/// DXF contours at a fixed interval, as an [Exporter]; see
+/// [write_dxf_contours]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DxfContours {
+    /// the contour interval, or `None` for a round interval suited to each
+    /// grid (see [Grid::contour_interval])
+    pub interval: Option<f64>,
+}
+
+impl Exporter for DxfContours {
+    fn name(&self) -> &str {
+        "dxf-contours"
+    }
+
+    fn extension(&self) -> &str {
+        "contours.dxf"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        /* a grid without a range of values has at most one level, at any
+         * interval */
+        let interval = self.interval.or_else(|| grid.contour_interval())
+          .unwrap_or(1.0);
+        write_dxf_contours(grid, interval, out)
+    }
+}
+
+// a DXF group: its code, then its value, each on their own line
+fn pair<W: Write, T: std::fmt::Display>(out: &mut W, code: usize, value: T)
+  -> Result<(), Error> {
+    writeln!(out, "{:>3}", code)?;
+    writeln!(out, "{}", value)?;
+    Ok(())
+}
+
+fn write_header<W: Write>(grid: &Grid, out: &mut W) -> Result<(), Error> {
+    // comments can't span lines
+    let comment = format!("{}: {}", grid.name, crs_description(grid))
+      .replace(['\r', '\n'], " ");
+    pair(out, 999, comment)?;
+
+    pair(out, 0, "SECTION")?;
+    pair(out, 2, "HEADER")?;
+    pair(out, 9, "$ACADVER")?;
+    pair(out, 1, "AC1009")?;
+    pair(out, 9, "$INSUNITS")?;
+    pair(out, 70, match grid.xyunits {
+        UnitOfMeasure::Feet => 2,
+        UnitOfMeasure::Meters => 6,
+        UnitOfMeasure::Other(_) => 0,
+    })?;
+    pair(out, 9, "$EXTMIN")?;
+    pair(out, 10, grid.xmin)?;
+    pair(out, 20, grid.ymin)?;
+    pair(out, 30, grid.zmin)?;
+    pair(out, 9, "$EXTMAX")?;
+    pair(out, 10, grid.xmax)?;
+    pair(out, 20, grid.ymax)?;
+    pair(out, 30, grid.zmax)?;
+    pair(out, 0, "ENDSEC")?;
+
+    pair(out, 0, "SECTION")?;
+    pair(out, 2, "ENTITIES")?;
+    Ok(())
+}
+
+fn write_trailer<W: Write>(out: &mut W) -> Result<(), Error> {
+    pair(out, 0, "ENDSEC")?;
+    pair(out, 0, "EOF")?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! EarthVision 2-D grid ASCII files
+//!
+//! a `#`-prefixed header (field names, units, and the lattice's size and
+//! extents) is followed by one "x y z column row" line per node, row by row
+//! from south to north, each row from west to east, with 1-based column and
+//! row numbers; missing nodes are omitted. triangular grids are rasterized
+//! first (see [Grid::rasterize])
+//!
+//! EarthVision's projection names don't match Petra's, so the projection is
+//! given as "Local Rectangular", with the grid's own coordinate system
+//! recorded in the description
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    UnitOfMeasure,
+    export::{Exporter, crs_description},
+};
+
+/// write a grid as an EarthVision 2-D grid ASCII file
+pub fn write_earthvision<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let xmax = grid.xmin + cols.saturating_sub(1) as f64 * grid.xstep;
+    let ymax = grid.ymin + rows.saturating_sub(1) as f64 * grid.ystep;
+    let zunits = unit_name(grid.zunits);
+
+    let mut out = BufWriter::new(out);
+    let description = format!("{} | {}", grid.name, crs_description(grid))
+      .replace(['\r', '\n'], " ");
+    writeln!(out, "# Type: scattered data")?;
+    writeln!(out, "# Version: 6")?;
+    writeln!(out, "# Description: {}", description)?;
+    writeln!(out, "# Format: free")?;
+    writeln!(out, "# Field: 1 x")?;
+    writeln!(out, "# Field: 2 y")?;
+    writeln!(out, "# Field: 3 z {}", zunits)?;
+    writeln!(out, "# Field: 4 column")?;
+    writeln!(out, "# Field: 5 row")?;
+    writeln!(out, "# Projection: Local Rectangular")?;
+    writeln!(out, "# Units: {}", unit_name(grid.xyunits))?;
+    writeln!(out, "# End:")?;
+    writeln!(out, "# Information from grid:")?;
+    writeln!(out, "# Grid_size: {} x {}", cols, rows)?;
+    writeln!(out, "# Grid_space: {},{},{},{}", grid.xmin, xmax, grid.ymin,
+      ymax)?;
+    writeln!(out, "# Z_field: z")?;
+    writeln!(out, "# History: exported from Petra")?;
+    writeln!(out, "# Z_units: {}", zunits)?;
+
+    for ((j, i), &z) in arr.indexed_iter() {
+        if z.is_nan() {
+            continue;
+        }
+        let (x, y) = grid.node_xy(j, i);
+        writeln!(out, "{} {} {} {} {}", x, y, z, i + 1, j + 1)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// EarthVision's name for a unit; "unknown" when we don't know
+fn unit_name(unit: UnitOfMeasure) -> &'static str {
+    match unit {
+        UnitOfMeasure::Feet => "feet",
+        UnitOfMeasure::Meters => "meters",
+        UnitOfMeasure::Other(_) => "unknown",
+    }
+}
+
+/// EarthVision 2-D grid ASCII, as an [Exporter]; see [write_earthvision]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EarthVision;
+
+impl Exporter for EarthVision {
+    fn name(&self) -> &str {
+        "earthvision"
+    }
+
+    fn extension(&self) -> &str {
+        "dat"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_earthvision(grid, out)
+    }
+}
@@ -0,0 +1,146 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ESRI ASCII (Arc/Info ASCII grid) rasters
+//!
+//! a short header (`ncols`, `nrows`, `xllcorner`, `yllcorner`, `cellsize`,
+//! and `NODATA_value`) is followed by one line of values per row, from north
+//! to south. each grid node becomes the center of one cell, so the lower-left
+//! corner lies half a cell south-west of the lower-left node; missing values
+//! are written as [NODATA]. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+//!
+//! the format only allows square cells, so grids with different *x* and *y*
+//! steps can't be written (resample them first; see [Grid::resample])
+//!
+//! categorical grids (see [CategoricalGrid]) are written as integer codes;
+//! the format has no place for their labels or colors
+
+use std::io::{BufWriter, Write};
+
+use crate::{CategoricalGrid, Error, Grid, GridData, export::Exporter};
+
+/// the sentinel written for missing values
+pub const NODATA: f64 = -9999.0;
+
+/// write a grid as an ESRI ASCII raster
+pub fn write_esri_ascii<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+
+    let mut out = BufWriter::new(out);
+    write_header(&mut out, grid.xmin, grid.ymin, grid.xstep, grid.ystep,
+      rows, cols)?;
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            let z = arr[(j, i)];
+            let z = if z.is_nan() { NODATA } else { z };
+            if i > 0 {
+                write!(out, " ")?;
+            }
+            write!(out, "{}", z)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// write a categorical grid as an ESRI ASCII raster of integer codes
+///
+/// missing nodes are written as [NODATA], so no category may use that code
+pub fn write_esri_ascii_categorical<W: Write + ?Sized>(
+  grid: &CategoricalGrid, out: &mut W) -> Result<(), Error> {
+    let nodata = NODATA as i32;
+    if grid.codes().iter().any(|&code| code == nodata) {
+        return Err(Error::InvalidArgument(format!(
+          "category code {} is reserved for missing values", nodata)));
+    }
+    let codes = grid.codes();
+    let (rows, cols) = codes.dim();
+    let header = grid.header();
+
+    let mut out = BufWriter::new(out);
+    write_header(&mut out, header.xmin, header.ymin, header.xstep,
+      header.ystep, rows, cols)?;
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            let code = codes[(j, i)];
+            let code = if code == CategoricalGrid::MISSING {
+                nodata
+            } else {
+                code
+            };
+            if i > 0 {
+                write!(out, " ")?;
+            }
+            write!(out, "{}", code)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_header<W: Write>(out: &mut W, xmin: f64, ymin: f64, xstep: f64,
+  ystep: f64, rows: usize, cols: usize) -> Result<(), Error> {
+    let cellsize = xstep;
+    if (ystep - cellsize).abs() > 1e-9 * cellsize.abs() {
+        return Err(Error::InvalidArgument(format!(
+          "ESRI ASCII rasters need square cells, but x step is {} and \
+           y step is {}", xstep, ystep)));
+    }
+
+    writeln!(out, "ncols {}", cols)?;
+    writeln!(out, "nrows {}", rows)?;
+    writeln!(out, "xllcorner {}", xmin - cellsize / 2.0)?;
+    writeln!(out, "yllcorner {}", ymin - cellsize / 2.0)?;
+    writeln!(out, "cellsize {}", cellsize)?;
+    writeln!(out, "NODATA_value {}", NODATA)?;
+    Ok(())
+}
+
+/// ESRI ASCII raster, as an [Exporter]; see [write_esri_ascii]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EsriAscii;
+
+impl Exporter for EsriAscii {
+    fn name(&self) -> &str {
+        "esri-ascii"
+    }
+
+    fn extension(&self) -> &str {
+        "asc"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_esri_ascii(grid, out)
+    }
+}
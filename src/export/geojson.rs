@@ -0,0 +1,168 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! GeoJSON feature collections, for quick QC on web maps
+//!
+//! rectangular grids become one point feature per (non-missing) node, with
+//! `row`, `column`, and `z` properties; triangular grids become one polygon
+//! feature per triangle (omitting those with missing vertices), with `index`
+//! and `z` (the mean of its vertices') properties
+//!
+//! by default, coordinates are written in the grid's own coordinate system,
+//! which is recorded (along with the grid's name) in the feature
+//! collection's `name` and `description` members. web maps expect WGS 84
+//! longitude and latitude, but we don't implement projections; when the
+//! grid's coordinate system is known, supply your own conversion (e.g. via
+//! PROJ) to [write_geojson_wgs84]
+//!
+//! [write_contours_geojson] writes contour lines instead, as line string
+//! features with a `level` property
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    export::{Exporter, crs_description, triangles::json_string},
+};
+
+/// write a grid as a GeoJSON feature collection, in the grid's own
+/// coordinate system
+pub fn write_geojson<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_features(grid, &crs_description(grid), |x, y| Some((x, y)), out)
+}
+
+/// write a grid as a GeoJSON feature collection in WGS 84, converting each
+/// point from the grid's coordinate system to (longitude, latitude) with
+/// `to_wgs84`; features with any point which can't be converted are omitted
+pub fn write_geojson_wgs84<W, F>(grid: &Grid, to_wgs84: F, out: &mut W)
+  -> Result<(), Error>
+  where W: Write + ?Sized,
+        F: Fn(f64, f64) -> Option<(f64, f64)>,
+{
+    write_features(grid, "WGS 84 (longitude, latitude)", to_wgs84, out)
+}
+
+/// write contour lines of a grid at the given levels as a GeoJSON feature
+/// collection of line strings, in the grid's own coordinate system; see
+/// [Grid::contours]
+pub fn write_contours_geojson<W: Write + ?Sized>(grid: &Grid, levels: &[f64],
+  out: &mut W) -> Result<(), Error> {
+    let mut out = BufWriter::new(out);
+    write!(out, "{{\"type\":\"FeatureCollection\",\"name\":{},\
+      \"description\":{},\"features\":[", json_string(&grid.name),
+      json_string(&crs_description(grid)))?;
+    for (n, contour) in grid.contours(levels).iter().enumerate() {
+        let mut points = contour.points.iter()
+          .map(|(x, y)| format!("[{},{}]", x, y))
+          .collect::<Vec<_>>();
+        // GeoJSON rings repeat their first point
+        if contour.closed {
+            points.push(points[0].clone());
+        }
+        let sep = if n == 0 { "\n" } else { ",\n" };
+        write!(out, "{}{{\"type\":\"Feature\",\"geometry\":{{\"type\":\
+          \"LineString\",\"coordinates\":[{}]}},\"properties\":{{\
+          \"level\":{}}}}}", sep, points.join(","), contour.level)?;
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn write_features<W, F>(grid: &Grid, description: &str, xy: F, out: &mut W)
+  -> Result<(), Error>
+  where W: Write + ?Sized,
+        F: Fn(f64, f64) -> Option<(f64, f64)>,
+{
+    let mut out = BufWriter::new(out);
+    write!(out, "{{\"type\":\"FeatureCollection\",\"name\":{},\
+      \"description\":{},\"features\":[", json_string(&grid.name),
+      json_string(description))?;
+    let mut first = true;
+    let mut separate = |out: &mut BufWriter<&mut W>| {
+        let sep = if first { "\n" } else { ",\n" };
+        first = false;
+        out.write_all(sep.as_bytes())
+    };
+
+    match &grid.data {
+        GridData::Rectangular(arr) => {
+            for ((j, i), &z) in arr.indexed_iter() {
+                if z.is_nan() {
+                    continue;
+                }
+                let Some((x, y)) = xy(grid.xmin + i as f64 * grid.xstep,
+                  grid.ymin + j as f64 * grid.ystep) else {
+                    continue;
+                };
+                separate(&mut out)?;
+                write!(out, "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\
+                  \"Point\",\"coordinates\":[{},{}]}},\"properties\":{{\
+                  \"row\":{},\"column\":{},\"z\":{}}}}}", x, y, j, i, z)?;
+            }
+        },
+
+        GridData::Triangular(arr) => {
+            for (index, tri) in arr.outer_iter().enumerate() {
+                if tri.iter().any(|v| v.is_nan()) {
+                    continue;
+                }
+                let Some(points) = (0..3)
+                  .map(|v| xy(tri[(v, 0)], tri[(v, 1)]))
+                  .collect::<Option<Vec<_>>>() else {
+                    continue;
+                };
+                let z = (tri[(0, 2)] + tri[(1, 2)] + tri[(2, 2)]) / 3.0;
+                let point = |v: usize| format!("[{},{}]",
+                  points[v].0, points[v].1);
+                separate(&mut out)?;
+                write!(out, "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\
+                  \"Polygon\",\"coordinates\":[[{},{},{},{}]]}},\
+                  \"properties\":{{\"index\":{},\"z\":{}}}}}",
+                  point(0), point(1), point(2), point(0), index, z)?;
+            }
+        },
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// GeoJSON, as an [Exporter]; see [write_geojson]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GeoJson;
+
+impl Exporter for GeoJson {
+    fn name(&self) -> &str {
+        "geojson"
+    }
+
+    fn extension(&self) -> &str {
+        "geojson"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_geojson(grid, out)
+    }
+}
@@ -0,0 +1,478 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! GeoPackage tiled gridded coverages, a single SQLite file which QGIS and
+//! GDAL open directly
+//!
+//! the grid is written as an OGC "2D gridded coverage": a tile pyramid with
+//! one zoom level of 256 × 256 single-band, single-precision TIFF tiles,
+//! tile row 0 at the north edge. each node becomes one cell, centered on the
+//! node ("grid-value-is-center"), and tiles along the east and south edges
+//! are padded with missing values. missing nodes are written as
+//! [GPKG_NODATA], which is declared as the coverage's null value.
+//! triangular grids are rasterized first (see [Grid::rasterize])
+//!
+//! where the projection maps to an EPSG code (see [Grid::epsg]), the
+//! coverage is tagged with that code, which readers resolve from their own
+//! EPSG databases; otherwise it's tagged as GeoPackage's "undefined
+//! Cartesian" coordinate system. what the coverage measures (see
+//! [Grid::quantity]) is recorded as its quantity definition and unit of
+//! measure, falling back on the grid's name and *z* units
+//!
+//! the database is built in memory, so needs room for a second copy of the
+//! grid; this format needs the `geopackage` feature, which builds a bundled
+//! SQLite
+
+use std::io::{self, BufWriter, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use ndarray::{ArcArray2, s};
+
+use rusqlite::{Connection, DatabaseName, params};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+/// the value written for missing nodes, and declared as the coverage's null
+pub const GPKG_NODATA: f32 = 1e30;
+
+// the width and height of each tile, in cells
+const TILE_SIZE: usize = 256;
+
+const COVERAGE_EXTENSION: &str = "gpkg_2d_gridded_coverage";
+const COVERAGE_DEFINITION: &str =
+  "http://docs.opengeospatial.org/is/17-066r1/17-066r1.html";
+
+// "GPKG", and version 1.2.1 of the standard (the gridded coverage
+// extension's first)
+const APPLICATION_ID: i32 = 0x47504b47;
+const USER_VERSION: i32 = 10201;
+
+const UNDEFINED_CARTESIAN: i64 = -1;
+
+// TIFF field types
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+
+// the tables every GeoPackage has, and those of the coverage extension
+const SCHEMA: &str = "
+CREATE TABLE gpkg_spatial_ref_sys (
+  srs_name TEXT NOT NULL,
+  srs_id INTEGER PRIMARY KEY,
+  organization TEXT NOT NULL,
+  organization_coordsys_id INTEGER NOT NULL,
+  definition TEXT NOT NULL,
+  description TEXT
+);
+CREATE TABLE gpkg_contents (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  data_type TEXT NOT NULL,
+  identifier TEXT UNIQUE,
+  description TEXT DEFAULT '',
+  last_change DATETIME NOT NULL
+    DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+  min_x DOUBLE,
+  min_y DOUBLE,
+  max_x DOUBLE,
+  max_y DOUBLE,
+  srs_id INTEGER,
+  CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id)
+    REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE gpkg_tile_matrix_set (
+  table_name TEXT NOT NULL PRIMARY KEY,
+  srs_id INTEGER NOT NULL,
+  min_x DOUBLE NOT NULL,
+  min_y DOUBLE NOT NULL,
+  max_x DOUBLE NOT NULL,
+  max_y DOUBLE NOT NULL,
+  CONSTRAINT fk_gtms_table_name FOREIGN KEY (table_name)
+    REFERENCES gpkg_contents(table_name),
+  CONSTRAINT fk_gtms_srs FOREIGN KEY (srs_id)
+    REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+CREATE TABLE gpkg_tile_matrix (
+  table_name TEXT NOT NULL,
+  zoom_level INTEGER NOT NULL,
+  matrix_width INTEGER NOT NULL,
+  matrix_height INTEGER NOT NULL,
+  tile_width INTEGER NOT NULL,
+  tile_height INTEGER NOT NULL,
+  pixel_x_size DOUBLE NOT NULL,
+  pixel_y_size DOUBLE NOT NULL,
+  CONSTRAINT pk_ttm PRIMARY KEY (table_name, zoom_level),
+  CONSTRAINT fk_tmm_table_name FOREIGN KEY (table_name)
+    REFERENCES gpkg_contents(table_name)
+);
+CREATE TABLE gpkg_extensions (
+  table_name TEXT,
+  column_name TEXT,
+  extension_name TEXT NOT NULL,
+  definition TEXT NOT NULL,
+  scope TEXT NOT NULL,
+  CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+);
+CREATE TABLE gpkg_2d_gridded_coverage_ancillary (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  tile_matrix_set_name TEXT NOT NULL UNIQUE,
+  datatype TEXT NOT NULL DEFAULT 'integer',
+  scale REAL NOT NULL DEFAULT 1.0,
+  offset REAL NOT NULL DEFAULT 0.0,
+  precision REAL DEFAULT 1.0,
+  data_null REAL,
+  grid_cell_encoding TEXT DEFAULT 'grid-value-is-center',
+  uom TEXT,
+  field_name TEXT DEFAULT 'Height',
+  quantity_definition TEXT DEFAULT 'Height',
+  CONSTRAINT fk_g2dgtct_name FOREIGN KEY (tile_matrix_set_name)
+    REFERENCES gpkg_tile_matrix_set (table_name),
+  CHECK (datatype IN ('integer', 'float'))
+);
+CREATE TABLE gpkg_2d_gridded_tile_ancillary (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  tpudt_name TEXT NOT NULL,
+  tpudt_id INTEGER NOT NULL,
+  scale REAL NOT NULL DEFAULT 1.0,
+  offset REAL NOT NULL DEFAULT 0.0,
+  min REAL DEFAULT NULL,
+  max REAL DEFAULT NULL,
+  mean REAL DEFAULT NULL,
+  std_dev REAL DEFAULT NULL,
+  CONSTRAINT fk_g2dgtat_name FOREIGN KEY (tpudt_name)
+    REFERENCES gpkg_contents(table_name),
+  UNIQUE (tpudt_name, tpudt_id)
+);
+INSERT INTO gpkg_spatial_ref_sys VALUES
+  ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined',
+    'undefined cartesian coordinate reference system'),
+  ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined',
+    'undefined geographic coordinate reference system'),
+  ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS[\"WGS 84\",\
+DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,\
+AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],\
+PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],\
+UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],\
+AXIS[\"Latitude\",NORTH],AXIS[\"Longitude\",EAST],\
+AUTHORITY[\"EPSG\",\"4326\"]]',
+    'longitude/latitude coordinates in decimal degrees on the WGS 84 \
+spheroid');
+";
+
+/// write a grid as a GeoPackage 2D gridded coverage
+pub fn write_geopackage<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let bytes = build(grid, arr).map_err(io::Error::other)?;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(&bytes)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// the GeoPackage exporter
+pub struct GeoPackage;
+
+impl Exporter for GeoPackage {
+    fn name(&self) -> &str {
+        "geopackage"
+    }
+
+    fn extension(&self) -> &str {
+        "gpkg"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_geopackage(grid, out)
+    }
+}
+
+// build the database in memory, returning its bytes
+fn build(grid: &Grid, arr: &ArcArray2<f64>) -> rusqlite::Result<Vec<u8>> {
+    let (rows, cols) = arr.dim();
+    let matrix_width = cols.div_ceil(TILE_SIZE);
+    let matrix_height = rows.div_ceil(TILE_SIZE);
+    let table = table_name(&grid.name);
+    let identifier = if grid.name.trim().is_empty() {
+        table.clone()
+    } else {
+        grid.name.clone()
+    };
+
+    // cells are centered on nodes, and the tile matrix covers whole tiles
+    let west = grid.xmin - grid.xstep / 2.0;
+    let north = grid.ymin + (rows as f64 - 0.5) * grid.ystep;
+    let east = west + cols as f64 * grid.xstep;
+    let south = north - rows as f64 * grid.ystep;
+    let matrix_east = west + (matrix_width * TILE_SIZE) as f64 * grid.xstep;
+    let matrix_south = north
+      - (matrix_height * TILE_SIZE) as f64 * grid.ystep;
+
+    let (quantity, units) = match &grid.quantity {
+        Some(q) => (q.name.clone(), Some(q.units.clone())),
+        None => (identifier.clone(), grid.zunits.udunits().map(str::to_string)),
+    };
+
+    let mut db = Connection::open_in_memory()?;
+    db.pragma_update(None, "application_id", APPLICATION_ID)?;
+    db.pragma_update(None, "user_version", USER_VERSION)?;
+    let tx = db.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+
+    let srs_id = match grid.epsg() {
+        Some(code) if code != 4326 => {
+            let name = if grid.projection.is_empty() {
+                format!("EPSG:{}", code)
+            } else {
+                grid.projection.clone()
+            };
+            tx.execute("INSERT INTO gpkg_spatial_ref_sys VALUES \
+              (?1, ?2, 'EPSG', ?2, 'undefined', ?3)",
+              params![name, code, grid.datum])?;
+            code as i64
+        },
+        Some(code) => code as i64,
+        None => UNDEFINED_CARTESIAN,
+    };
+
+    tx.execute("INSERT INTO gpkg_contents (table_name, data_type, \
+      identifier, description, min_x, min_y, max_x, max_y, srs_id) \
+      VALUES (?1, '2d-gridded-coverage', ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+      params![table, identifier, grid.source_data, west, south, east, north,
+        srs_id])?;
+    tx.execute("INSERT INTO gpkg_tile_matrix_set VALUES \
+      (?1, ?2, ?3, ?4, ?5, ?6)",
+      params![table, srs_id, west, matrix_south, matrix_east, north])?;
+    tx.execute("INSERT INTO gpkg_tile_matrix VALUES \
+      (?1, 0, ?2, ?3, ?4, ?4, ?5, ?6)",
+      params![table, matrix_width as i64, matrix_height as i64,
+        TILE_SIZE as i64, grid.xstep, grid.ystep])?;
+    tx.execute(&format!("CREATE TABLE {} (\
+      id INTEGER PRIMARY KEY AUTOINCREMENT, \
+      zoom_level INTEGER NOT NULL, \
+      tile_column INTEGER NOT NULL, \
+      tile_row INTEGER NOT NULL, \
+      tile_data BLOB NOT NULL, \
+      UNIQUE (zoom_level, tile_column, tile_row))", quoted(&table)), [])?;
+
+    tx.execute("INSERT INTO gpkg_extensions VALUES \
+      ('gpkg_2d_gridded_coverage_ancillary', NULL, ?1, ?2, 'read-write'), \
+      ('gpkg_2d_gridded_tile_ancillary', NULL, ?1, ?2, 'read-write'), \
+      (?3, 'tile_data', ?1, ?2, 'read-write')",
+      params![COVERAGE_EXTENSION, COVERAGE_DEFINITION, table])?;
+    tx.execute("INSERT INTO gpkg_2d_gridded_coverage_ancillary \
+      (tile_matrix_set_name, datatype, scale, offset, precision, data_null, \
+      grid_cell_encoding, uom, field_name, quantity_definition) \
+      VALUES (?1, 'float', 1.0, 0.0, 1.0, ?2, 'grid-value-is-center', ?3, \
+      'z', ?4)",
+      params![table, GPKG_NODATA as f64, units, quantity])?;
+
+    {
+        let mut insert_tile = tx.prepare(&format!("INSERT INTO {} \
+          (zoom_level, tile_column, tile_row, tile_data) \
+          VALUES (0, ?1, ?2, ?3)", quoted(&table)))?;
+        let mut insert_stats = tx.prepare("INSERT INTO \
+          gpkg_2d_gridded_tile_ancillary \
+          (tpudt_name, tpudt_id, min, max, mean, std_dev) \
+          VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        for tile_row in 0..matrix_height {
+            for tile_col in 0..matrix_width {
+                let (data, stats) = tile(arr, tile_row, tile_col);
+                let id = insert_tile.insert(
+                  params![tile_col as i64, tile_row as i64, data])?;
+                insert_stats.execute(params![table, id,
+                  stats.map(|s| s.min), stats.map(|s| s.max),
+                  stats.map(|s| s.mean), stats.map(|s| s.std_dev)])?;
+            }
+        }
+    }
+    tx.commit()?;
+
+    Ok(db.serialize(DatabaseName::Main)?.to_vec())
+}
+
+// summary statistics of the values in one tile
+#[derive(Copy, Clone)]
+struct TileStats {
+    min: f64,
+    max: f64,
+    mean: f64,
+    std_dev: f64,
+}
+
+// one tile (counting rows from the north) as a TIFF, with statistics of its
+// values (if it has any)
+fn tile(arr: &ArcArray2<f64>, tile_row: usize, tile_col: usize)
+  -> (Vec<u8>, Option<TileStats>) {
+    let (rows, cols) = arr.dim();
+    let top = rows - tile_row * TILE_SIZE;
+    let bottom = top.saturating_sub(TILE_SIZE);
+    let left = tile_col * TILE_SIZE;
+    let right = (left + TILE_SIZE).min(cols);
+    let view = arr.slice(s![bottom..top, left..right]);
+
+    let mut cells = vec![GPKG_NODATA; TILE_SIZE * TILE_SIZE];
+    let (mut n, mut min, mut max) = (0usize, f64::INFINITY, f64::NEG_INFINITY);
+    let (mut sum, mut sum_sq) = (0.0, 0.0);
+    for (k, row) in view.outer_iter().rev().enumerate() {
+        for (i, &z) in row.iter().enumerate() {
+            if z.is_nan() {
+                continue;
+            }
+            cells[k * TILE_SIZE + i] = z as f32;
+            n += 1;
+            min = min.min(z);
+            max = max.max(z);
+            sum += z;
+            sum_sq += z * z;
+        }
+    }
+
+    let stats = if n == 0 {
+        None
+    } else {
+        let mean = sum / n as f64;
+        let var = (sum_sq / n as f64 - mean * mean).max(0.0);
+        Some(TileStats { min, max, mean, std_dev: var.sqrt() })
+    };
+    (tiff(&cells), stats)
+}
+
+// a single-strip, uncompressed, single-band float TIFF of one tile; every
+// tag's value fits in its directory entry, so the pixels follow directly
+fn tiff(cells: &[f32]) -> Vec<u8> {
+    let size = TILE_SIZE as u32;
+    let data_len = cells.len() as u32 * 4;
+    let entries: [(u16, u16, u32); 10] = [
+        (256, LONG, size),
+        (257, LONG, size),
+        (258, SHORT, 32),
+        (259, SHORT, 1), // no compression
+        (262, SHORT, 1), // black is zero
+        (273, LONG, 0), // the strip offset, filled in below
+        (277, SHORT, 1),
+        (278, LONG, size),
+        (279, LONG, data_len),
+        (339, SHORT, 3), // IEEE floating point
+    ];
+    let data_offset = 8 + 2 + 12 * entries.len() as u32 + 4;
+
+    let mut out = Vec::with_capacity((data_offset + data_len) as usize);
+    /* safety: writes to a Vec can't fail */
+    out.write_all(b"II").unwrap();
+    out.write_u16::<LittleEndian>(42).unwrap();
+    out.write_u32::<LittleEndian>(8).unwrap();
+    out.write_u16::<LittleEndian>(entries.len() as u16).unwrap();
+    for (tag, ty, value) in entries {
+        let value = if tag == 273 { data_offset } else { value };
+        out.write_u16::<LittleEndian>(tag).unwrap();
+        out.write_u16::<LittleEndian>(ty).unwrap();
+        out.write_u32::<LittleEndian>(1).unwrap();
+        match ty {
+            SHORT => {
+                out.write_u16::<LittleEndian>(value as u16).unwrap();
+                out.write_u16::<LittleEndian>(0).unwrap();
+            },
+            _ => out.write_u32::<LittleEndian>(value).unwrap(),
+        }
+    }
+    out.write_u32::<LittleEndian>(0).unwrap();
+    for &z in cells {
+        out.write_f32::<LittleEndian>(z).unwrap();
+    }
+    out
+}
+
+// a table name for the coverage, from the grid's name: lowercase letters,
+// digits, and underscores, starting with a letter
+fn table_name(name: &str) -> String {
+    let mut table: String = name.trim().chars()
+      .map(|c| if c.is_ascii_alphanumeric() {
+          c.to_ascii_lowercase()
+      } else {
+          '_'
+      })
+      .collect();
+    if !table.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        table.insert_str(0, "grid_");
+    }
+    if table.starts_with("gpkg_") || table.starts_with("sqlite_") {
+        table.insert_str(0, "grid_");
+    }
+    table
+}
+
+// an SQL identifier, quoted
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_and_tables_read_back() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_geopackage(&grid, &mut buf).expect("write GeoPackage");
+
+        assert_eq!(&buf[..16], b"SQLite format 3\0");
+        assert_eq!(i32::from_be_bytes(buf[60..64].try_into().unwrap()),
+          USER_VERSION);
+        assert_eq!(i32::from_be_bytes(buf[68..72].try_into().unwrap()),
+          APPLICATION_ID);
+
+        let path = std::env::temp_dir()
+          .join(format!("petra_grid-gpkg-{}.gpkg", std::process::id()));
+        fs::write(&path, &buf).expect("write scratch GeoPackage");
+        let db = Connection::open(&path).expect("open GeoPackage");
+        let table = table_name(&grid.name);
+        let bounds: [f64; 4] = db.query_row("SELECT min_x, min_y, max_x, \
+          max_y FROM gpkg_contents WHERE table_name = ?1", [&table],
+          |row| Ok([row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?]))
+          .expect("read coverage bounds");
+        assert_eq!(bounds, [987.5, 1975.0, 1087.5, 2125.0]);
+        let matrix: (i64, i64, f64, f64) = db.query_row("SELECT \
+          matrix_width, matrix_height, pixel_x_size, pixel_y_size \
+          FROM gpkg_tile_matrix WHERE table_name = ?1", [&table],
+          |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+          .expect("read tile matrix");
+        assert_eq!(matrix, (1, 1, 25.0, 50.0));
+        let tiles: i64 = db.query_row(&format!("SELECT count(*) FROM {}",
+          quoted(&table)), [], |row| row.get(0))
+          .expect("count tiles");
+        assert_eq!(tiles, 1);
+        drop(db);
+        let _ = fs::remove_file(&path);
+    }
+}
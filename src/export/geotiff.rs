@@ -0,0 +1,887 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! single-band floating-point GeoTIFF rasters
+//!
+//! each grid node becomes one pixel (the GeoTIFF is marked "pixel is point",
+//! so pixel centers fall exactly on nodes), rows running north to south as
+//! TIFF expects; missing values are written as NaN, which is also declared
+//! as the no-data value. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+//!
+//! the grid's projection and datum names are recorded as citations; where
+//! the projection maps to an EPSG code (see [Grid::epsg]), that's recorded
+//! as the projected coordinate system, which is otherwise "user-defined"
+//!
+//! grids paired with their variance (see [GridWithUncertainty]) are written
+//! as two-band rasters, with the estimate in the first band and the variance
+//! in the second; grids can also be bundled with derived attributes (see
+//! [SurfaceAttribute]) or with other coregistered layers (such as
+//! confidence grids; see [Grid::confidence]) as multi-band rasters. bands of
+//! multi-band rasters are named in GDAL's metadata tag, as is what the
+//! first band measures, for derived grids which say (see [Grid::quantity]):
+//! its name is the band's description (unless the band is already named)
+//! and its units the band's unit type
+//!
+//! grids on rotated lattices (see [RotatedGrid]) are written with a model
+//! transformation in place of the usual tie point and pixel scale, so that
+//! GIS software places them correctly without resampling
+//!
+//! files are classic TIFFs, with 32-bit offsets, so rasters of 4 GiB or
+//! more (e.g. 24,000 x 24,000 nodes of one band) can't be written; every
+//! writer fails with [Error::InvalidArgument] for them, rather than writing a
+//! corrupt file
+//!
+//! [write_cog] writes cloud-optimized GeoTIFFs: tiled, with overviews, and
+//! with all the directories ahead of the pixel data, so that clients can
+//! read any part of the raster at any resolution with a few HTTP range
+//! requests. their tiles are Deflate-compressed, with the floating-point
+//! predictor, unless asked otherwise (see [CogOptions])
+//!
+//! categorical grids (see [CategoricalGrid]) are written as 8-bit paletted
+//! rasters, colored by category; codes must lie between 0 and 254, with 255
+//! marking missing nodes. the format has no place for category labels
+
+use std::io::{BufWriter, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use flate2::{Compression, write::ZlibEncoder};
+
+use ndarray::{ArcArray2, Array2, ArrayView2};
+
+use crate::{
+    CategoricalGrid,
+    Error,
+    Grid,
+    GridData,
+    GridWithUncertainty,
+    Quantity,
+    RotatedGrid,
+    SurfaceAttribute,
+    UnitOfMeasure,
+    export::{Exporter, crs_description},
+};
+
+// TIFF field types
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const ASCII: u16 = 2;
+const DOUBLE: u16 = 12;
+
+// GeoTIFF tags
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const MODEL_TRANSFORMATION: u16 = 34264;
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GEO_ASCII_PARAMS: u16 = 34737;
+const GDAL_METADATA: u16 = 42112;
+const GDAL_NODATA: u16 = 42113;
+
+// GeoKeys and their values
+const GT_MODEL_TYPE: u16 = 1024;
+const GT_RASTER_TYPE: u16 = 1025;
+const GT_CITATION: u16 = 1026;
+const PROJECTED_CS_TYPE: u16 = 3072;
+const PCS_CITATION: u16 = 3073;
+const PROJ_LINEAR_UNITS: u16 = 3076;
+const VERTICAL_UNITS: u16 = 4099;
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+const USER_DEFINED: u16 = 32767;
+const LINEAR_METER: u16 = 9001;
+/* Petra's "feet" are, in every project we've seen, US survey feet (as used
+ * by the US state plane systems) */
+const LINEAR_FOOT_US_SURVEY: u16 = 9003;
+
+// the distance (in x/y units) within which layers' nodes must align
+const COREGISTRATION_TOLERANCE: f64 = 1e-6;
+
+// the palette index written for missing nodes in paletted rasters
+const PALETTE_NODATA: u8 = 255;
+
+// the width and height of the tiles of cloud-optimized GeoTIFFs
+const COG_TILE_SIZE: usize = 256;
+
+// TIFF compression schemes and predictors
+const COMPRESSION_NONE: u16 = 1;
+const COMPRESSION_DEFLATE: u16 = 8;
+const PREDICTOR_FLOATING_POINT: u16 = 3;
+
+/// how the tiles of cloud-optimized GeoTIFFs are compressed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CogCompression {
+    /// not at all
+    None,
+
+    /// with Deflate (zlib), which every GeoTIFF reader supports
+    #[default]
+    Deflate,
+}
+
+/// options for cloud-optimized GeoTIFFs; see [write_cog_with]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CogOptions {
+    compression: CogCompression,
+    predictor: bool,
+}
+
+impl CogOptions {
+    /// the default options: Deflate compression, with the floating-point
+    /// predictor
+    pub fn new() -> Self {
+        CogOptions::default()
+    }
+
+    /// set the compression of the tiles
+    pub fn compression(self, compression: CogCompression) -> Self {
+        CogOptions { compression, ..self }
+    }
+
+    /// use (or don't) the floating-point predictor when compressing,
+    /// which rearranges each row's bytes so that smooth surfaces compress
+    /// much better, at the cost of readers having to undo it
+    pub fn predictor(self, predictor: bool) -> Self {
+        CogOptions { predictor, ..self }
+    }
+
+    // the TIFF compression scheme and predictor (if any) for these options
+    fn tags(&self) -> (u16, Option<u16>) {
+        match (self.compression, self.predictor) {
+            (CogCompression::None, _) => (COMPRESSION_NONE, None),
+            (CogCompression::Deflate, false) => (COMPRESSION_DEFLATE, None),
+            (CogCompression::Deflate, true) =>
+                (COMPRESSION_DEFLATE, Some(PREDICTOR_FLOATING_POINT)),
+        }
+    }
+}
+
+impl Default for CogOptions {
+    fn default() -> Self {
+        CogOptions {
+            compression: CogCompression::default(),
+            predictor: true,
+        }
+    }
+}
+
+/// write a grid as a single-band 64-bit floating-point GeoTIFF
+pub fn write_geotiff<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_raster(grid, 0.0, out)
+}
+
+/// write a grid on a rotated lattice as a single-band 64-bit floating-point
+/// GeoTIFF, georeferenced by a model transformation which includes the
+/// rotation
+pub fn write_rotated_geotiff<W: Write + ?Sized>(rotated: &RotatedGrid,
+  out: &mut W) -> Result<(), Error> {
+    write_raster(&rotated.grid, rotated.azimuth, out)
+}
+
+/// write a grid and its variance as a two-band 64-bit floating-point
+/// GeoTIFF, with the variance in the second band
+pub fn write_geotiff_with_uncertainty<W: Write + ?Sized>(
+  paired: &GridWithUncertainty, out: &mut W) -> Result<(), Error> {
+    let bands = [paired.grid().rectangular()?.view(),
+      paired.variance().rectangular()?.view()];
+    write_bands(paired.grid(), &bands, &["estimate", "variance"], 0.0, out)
+}
+
+/// write a grid as a single-band 64-bit floating-point cloud-optimized
+/// GeoTIFF, in 256-pixel square tiles, with overviews (see
+/// [Grid::build_overviews]) down to the first which fits in a single tile;
+/// triangular grids are rasterized first
+///
+/// the file starts with the directories of the full-resolution raster and
+/// then each overview, followed by the tiles of the coarsest overview
+/// through to those of the full-resolution raster, as the COG layout
+/// requires; fails with [Error::InvalidArgument] if the file would be too
+/// large for a (classic, 32-bit) TIFF
+///
+/// tiles are compressed with the default options (see [CogOptions]); use
+/// [write_cog_with] for others
+pub fn write_cog<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_cog_with(grid, out, &CogOptions::default())
+}
+
+/// write a grid as a cloud-optimized GeoTIFF (see [write_cog]), with its
+/// tiles compressed according to `options`
+pub fn write_cog_with<W: Write + ?Sized>(grid: &Grid, out: &mut W,
+  options: &CogOptions) -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+
+    let (mut rows, mut cols) = grid.rectangular()?.dim();
+    let mut n_overviews = 0;
+    while rows.max(cols) > COG_TILE_SIZE {
+        rows = rows.div_ceil(2);
+        cols = cols.div_ceil(2);
+        n_overviews += 1;
+    }
+    let overviews = grid.build_overviews(n_overviews);
+    let mut levels = vec![grid.rectangular()?];
+    for overview in &overviews {
+        levels.push(overview.rectangular()?);
+    }
+
+    /* compressed tiles vary in size, so we encode them all before we can
+     * lay out the file */
+    let tiles = levels.iter()
+      .map(|level| encode_tiles(level, options))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    // the directories' sizes don't depend on the offsets they hold
+    let ifd_lens = levels.iter().zip(&tiles).enumerate()
+      .map(|(k, (level, tiles))| {
+          let zeros = vec![0; tiles.len()];
+          cog_ifd(grid, level, k > 0, 0, &zeros, &zeros, options).len()
+      })
+      .collect::<Vec<_>>();
+
+    // pixel data follows the directories, coarsest level first
+    let ifds_end = 8 + ifd_lens.iter().map(|&len| len as u64).sum::<u64>();
+    let total = ifds_end + tiles.iter().flatten()
+      .map(|tile| tile.len() as u64)
+      .sum::<u64>();
+    if total > u32::MAX as u64 {
+        return Err(too_large(grid.rows as usize, grid.columns as usize));
+    }
+    let mut tile_offsets = vec![Vec::new(); levels.len()];
+    let mut offset = ifds_end;
+    for k in (0..levels.len()).rev() {
+        for tile in &tiles[k] {
+            tile_offsets[k].push(offset as u32);
+            offset += tile.len() as u64;
+        }
+    }
+
+    let mut out = BufWriter::new(out);
+    out.write_all(b"II")?;
+    out.write_u16::<LittleEndian>(42)?;
+    out.write_u32::<LittleEndian>(8)?;
+    let mut ifd_offset = 8;
+    for (k, level) in levels.iter().enumerate() {
+        let tile_lens = tiles[k].iter()
+          .map(|tile| tile.len() as u32)
+          .collect::<Vec<_>>();
+        let mut ifd = cog_ifd(grid, level, k > 0, ifd_offset,
+          &tile_offsets[k], &tile_lens, options);
+        ifd_offset += ifd_lens[k];
+        if k + 1 < levels.len() {
+            ifd.next = ifd_offset;
+        }
+        ifd.write(&mut out)?;
+    }
+    for level in tiles.iter().rev() {
+        for tile in level {
+            out.write_all(tile)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// the directory of one level of a cloud-optimized GeoTIFF
+fn cog_ifd(grid: &Grid, level: &ArcArray2<f64>, overview: bool, offset: u32,
+  tile_offsets: &[u32], tile_lens: &[u32], options: &CogOptions) -> Ifd {
+    let (rows, cols) = level.dim();
+    let (compression, predictor) = options.tags();
+    let mut ifd = Ifd::new(offset);
+    if overview {
+        ifd.longs(254, &[1]); // reduced-resolution image
+    }
+    ifd.longs(256, &[cols as u32]);
+    ifd.longs(257, &[rows as u32]);
+    ifd.shorts(258, &[64]);
+    ifd.shorts(259, &[compression]);
+    ifd.shorts(262, &[1]); // black is zero
+    ifd.shorts(277, &[1]);
+    ifd.shorts(284, &[1]); // contiguous
+    if let Some(predictor) = predictor {
+        ifd.shorts(317, &[predictor]);
+    }
+    ifd.shorts(322, &[COG_TILE_SIZE as u16]);
+    ifd.shorts(323, &[COG_TILE_SIZE as u16]);
+    ifd.longs(324, tile_offsets);
+    ifd.longs(325, tile_lens);
+    ifd.shorts(339, &[3]); // IEEE floating point
+    if !overview {
+        georeference(&mut ifd, grid, rows, 0.0);
+        if let Some(xml) = band_metadata(&[], grid.quantity.as_ref()) {
+            ifd.ascii(GDAL_METADATA, &xml);
+        }
+    }
+    ifd.ascii(GDAL_NODATA, "nan");
+    ifd
+}
+
+/* encode a raster's tiles, left to right and top (north) to bottom,
+ * padding the tiles at the right and bottom edges with missing values */
+fn encode_tiles(level: &ArcArray2<f64>, options: &CogOptions)
+  -> Result<Vec<Vec<u8>>, Error> {
+    let (rows, cols) = level.dim();
+    let (_, predictor) = options.tags();
+    let row_len = COG_TILE_SIZE * 8;
+    let mut tiles = Vec::new();
+    for tile_row in 0..rows.div_ceil(COG_TILE_SIZE) {
+        for tile_col in 0..cols.div_ceil(COG_TILE_SIZE) {
+            let mut tile = Vec::with_capacity(COG_TILE_SIZE * row_len);
+            for r in 0..COG_TILE_SIZE {
+                let r = tile_row * COG_TILE_SIZE + r;
+                for c in 0..COG_TILE_SIZE {
+                    let c = tile_col * COG_TILE_SIZE + c;
+                    let z = if r < rows && c < cols {
+                        level[(rows - 1 - r, c)]
+                    } else {
+                        f64::NAN
+                    };
+                    tile.extend_from_slice(&z.to_le_bytes());
+                }
+            }
+            if predictor.is_some() {
+                for row in tile.chunks_exact_mut(row_len) {
+                    floating_point_predictor(row);
+                }
+            }
+            tiles.push(match options.compression {
+                CogCompression::None => tile,
+                CogCompression::Deflate => {
+                    let mut encoder = ZlibEncoder::new(Vec::new(),
+                      Compression::default());
+                    encoder.write_all(&tile)?;
+                    encoder.finish()?
+                },
+            });
+        }
+    }
+    Ok(tiles)
+}
+
+/* apply TIFF's floating-point predictor to a row of little-endian doubles,
+ * in place: the values' bytes are regrouped by significance (all the most
+ * significant bytes first), and each byte then replaced by its difference
+ * from the one before, as libtiff does */
+fn floating_point_predictor(row: &mut [u8]) {
+    let n = row.len() / 8;
+    let original = row.to_vec();
+    for (i, value) in original.chunks_exact(8).enumerate() {
+        for (byte, &b) in value.iter().enumerate() {
+            row[(7 - byte) * n + i] = b;
+        }
+    }
+    for i in (1..row.len()).rev() {
+        row[i] = row[i].wrapping_sub(row[i - 1]);
+    }
+}
+
+/// write a grid and the given derived attributes of its surface as a
+/// multi-band 64-bit floating-point GeoTIFF, with *z* in the first band and
+/// each attribute (in order) in the following bands; triangular grids are
+/// rasterized first
+pub fn write_attribute_stack<W: Write + ?Sized>(grid: &Grid,
+  attributes: &[SurfaceAttribute], out: &mut W) -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let derived = attributes.iter()
+      .map(|&attribute| grid.attribute(attribute))
+      .collect::<Result<Vec<_>, _>>()?;
+    let mut bands = vec![grid.rectangular()?.view()];
+    bands.extend(derived.iter().map(Array2::view));
+    let mut names = vec!["z"];
+    names.extend(attributes.iter().map(SurfaceAttribute::name));
+    write_bands(grid, &bands, &names, 0.0, out)
+}
+
+// write a grid, rotated clockwise about its lower-left node by azimuth
+fn write_raster<W: Write + ?Sized>(grid: &Grid, azimuth: f64, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    write_bands(grid, &[grid.rectangular()?.view()], &[], azimuth, out)
+}
+
+/// write a grid and other named layers on the same lattice (such as a
+/// confidence grid; see [Grid::confidence]) as a multi-band 64-bit
+/// floating-point GeoTIFF, with *z* in the first band and each layer (in
+/// order) in the following bands; triangular grids are rasterized first
+pub fn write_geotiff_with_layers<W: Write + ?Sized>(grid: &Grid,
+  layers: &[(&str, &Grid)], out: &mut W) -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let mut bands = vec![grid.rectangular()?.view()];
+    let mut names = vec!["z"];
+    for &(name, layer) in layers {
+        let comparison = grid.compare_geometry(layer);
+        if !comparison.is_coregistered(COREGISTRATION_TOLERANCE) {
+            return Err(Error::InvalidArgument(format!(
+              "layer {} not coregistered with grid: {}", name, comparison)));
+        }
+        bands.push(layer.rectangular()?.view());
+        names.push(name);
+    }
+    write_bands(grid, &bands, &names, 0.0, out)
+}
+
+/* write one or more bands on a rectangular grid's lattice (taking
+ * georeferencing from the grid), with samples interleaved pixel by pixel;
+ * bands are named if any names are given */
+fn write_bands<W: Write + ?Sized>(grid: &Grid, bands: &[ArrayView2<f64>],
+  names: &[&str], azimuth: f64, out: &mut W) -> Result<(), Error> {
+    let (rows, cols) = bands[0].dim();
+    let n_bands = bands.len();
+
+    // the pixel data goes first, right after the 8-byte header
+    let data_len = classic_data_len(rows, cols, n_bands * 8)?;
+    let mut ifd = Ifd::new(8 + data_len);
+    ifd.longs(256, &[cols as u32]);
+    ifd.longs(257, &[rows as u32]);
+    ifd.shorts(258, &vec![64; n_bands]);
+    ifd.shorts(259, &[1]); // no compression
+    ifd.shorts(262, &[1]); // black is zero
+    ifd.longs(273, &[8]);
+    ifd.shorts(277, &[n_bands as u16]);
+    ifd.longs(278, &[rows as u32]);
+    ifd.longs(279, &[data_len]);
+    ifd.shorts(284, &[1]); // contiguous
+    if n_bands > 1 {
+        ifd.shorts(338, &vec![0; n_bands - 1]); // unspecified extra samples
+    }
+    ifd.shorts(339, &vec![3; n_bands]); // IEEE floating point
+    georeference(&mut ifd, grid, rows, azimuth);
+    if let Some(xml) = band_metadata(names, grid.quantity.as_ref()) {
+        ifd.ascii(GDAL_METADATA, &xml);
+    }
+    ifd.ascii(GDAL_NODATA, "nan");
+    check_classic_len(&ifd, rows, cols)?;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(b"II")?;
+    out.write_u16::<LittleEndian>(42)?;
+    out.write_u32::<LittleEndian>(8 + data_len)?;
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            for band in bands {
+                out.write_f64::<LittleEndian>(band[(j, i)])?;
+            }
+        }
+    }
+    ifd.write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// write a categorical grid as an 8-bit paletted GeoTIFF, colored by
+/// category (codes without a category are black)
+pub fn write_categorical_geotiff<W: Write + ?Sized>(grid: &CategoricalGrid,
+  out: &mut W) -> Result<(), Error> {
+    let codes = grid.codes();
+    let (rows, cols) = codes.dim();
+    let mut pixels = Vec::with_capacity(rows * cols);
+    for j in (0..rows).rev() {
+        for i in 0..cols {
+            let code = codes[(j, i)];
+            if code == CategoricalGrid::MISSING {
+                pixels.push(PALETTE_NODATA);
+                continue;
+            }
+            match u8::try_from(code) {
+                Ok(index) if index != PALETTE_NODATA => pixels.push(index),
+                _ => return Err(Error::InvalidArgument(format!(
+                  "category code {} won't fit in a paletted GeoTIFF \
+                   (codes must lie between 0 and {})", code,
+                   PALETTE_NODATA - 1))),
+            }
+        }
+    }
+
+    // the colormap holds all the reds, then the greens, then the blues
+    let mut colormap = vec![0u16; 3 * 256];
+    for category in grid.categories() {
+        if let Ok(index) = u8::try_from(category.code) {
+            for (c, &v) in category.color.iter().enumerate() {
+                colormap[c * 256 + index as usize] = v as u16 * 257;
+            }
+        }
+    }
+
+    let data_len = classic_data_len(rows, cols, 1)?;
+    let mut ifd = Ifd::new(8 + data_len);
+    ifd.longs(256, &[cols as u32]);
+    ifd.longs(257, &[rows as u32]);
+    ifd.shorts(258, &[8]);
+    ifd.shorts(259, &[1]); // no compression
+    ifd.shorts(262, &[3]); // palette color
+    ifd.longs(273, &[8]);
+    ifd.shorts(277, &[1]);
+    ifd.longs(278, &[rows as u32]);
+    ifd.longs(279, &[data_len]);
+    ifd.shorts(284, &[1]); // contiguous
+    ifd.shorts(320, &colormap);
+    ifd.shorts(339, &[1]); // unsigned integer
+    georeference(&mut ifd, &grid.to_grid(), rows, 0.0);
+    ifd.ascii(GDAL_NODATA, &PALETTE_NODATA.to_string());
+    check_classic_len(&ifd, rows, cols)?;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(b"II")?;
+    out.write_u16::<LittleEndian>(42)?;
+    out.write_u32::<LittleEndian>(8 + data_len)?;
+    out.write_all(&pixels)?;
+    ifd.write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/* the length of a (rows × columns) raster's pixel data, of the given size
+ * per pixel, which must leave the file's (32-bit) offsets room to reach the
+ * directory after it; larger rasters would need BigTIFF */
+fn classic_data_len(rows: usize, cols: usize, pixel_len: usize)
+  -> Result<u32, Error> {
+    rows.checked_mul(cols)
+      .and_then(|n| n.checked_mul(pixel_len))
+      .and_then(|len| u32::try_from(len).ok())
+      .filter(|len| len.checked_add(8).is_some())
+      .ok_or_else(|| too_large(rows, cols))
+}
+
+// check that a directory (placed after the pixel data) ends within reach of
+// a classic TIFF's 32-bit offsets
+fn check_classic_len(ifd: &Ifd, rows: usize, cols: usize)
+  -> Result<(), Error> {
+    if ifd.offset as u64 + ifd.len() as u64 > u32::MAX as u64 {
+        return Err(too_large(rows, cols));
+    }
+    Ok(())
+}
+
+fn too_large(rows: usize, cols: usize) -> Error {
+    Error::InvalidArgument(format!(
+      "a {} x {} raster is too large for a classic TIFF", cols, rows))
+}
+
+/* add the tags placing a raster of `rows` rows on a grid's lattice (rotated
+ * clockwise about its lower-left node by azimuth), and describing its
+ * coordinate system */
+fn georeference(ifd: &mut Ifd, grid: &Grid, rows: usize, azimuth: f64) {
+    let citation = format!("{}|", crs_description(grid));
+    let mut geo_keys = vec![
+        [GT_MODEL_TYPE, 0, 1, MODEL_TYPE_PROJECTED],
+        [GT_RASTER_TYPE, 0, 1, RASTER_PIXEL_IS_POINT],
+        [GT_CITATION, GEO_ASCII_PARAMS, citation.len() as u16, 0],
+        [PROJECTED_CS_TYPE, 0, 1, projected_cs_type(grid)],
+        [PCS_CITATION, GEO_ASCII_PARAMS, citation.len() as u16, 0],
+        [PROJ_LINEAR_UNITS, 0, 1, linear_units(grid.xyunits)],
+        [VERTICAL_UNITS, 0, 1, linear_units(grid.zunits)],
+    ];
+    let mut directory = vec![1, 1, 0, geo_keys.len() as u16];
+    directory.extend(geo_keys.drain(..).flatten());
+
+    if azimuth == 0.0 {
+        ifd.doubles(MODEL_PIXEL_SCALE, &[grid.xstep, grid.ystep, 0.0]);
+        ifd.doubles(MODEL_TIEPOINT,
+          &[0.0, 0.0, 0.0, grid.xmin,
+            grid.ymin + (rows - 1) as f64 * grid.ystep, 0.0]);
+    } else {
+        /* pixel (I, J) is node (rows - 1 - J, I) of the unrotated lattice,
+         * which we then rotate clockwise about the lower-left node */
+        let (sin, cos) = azimuth.to_radians().sin_cos();
+        let top = (rows - 1) as f64 * grid.ystep;
+        ifd.doubles(MODEL_TRANSFORMATION, &[
+            grid.xstep * cos, -grid.ystep * sin, 0.0, grid.xmin + top * sin,
+            -grid.xstep * sin, -grid.ystep * cos, 0.0, grid.ymin + top * cos,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+    }
+    ifd.shorts(GEO_KEY_DIRECTORY, &directory);
+    ifd.ascii(GEO_ASCII_PARAMS, &citation);
+}
+
+/// GeoTIFF, as an [Exporter]; see [write_geotiff]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GeoTiff;
+
+impl Exporter for GeoTiff {
+    fn name(&self) -> &str {
+        "geotiff"
+    }
+
+    fn extension(&self) -> &str {
+        "tif"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_geotiff(grid, out)
+    }
+}
+
+/// cloud-optimized GeoTIFF, as an [Exporter]; see [write_cog]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Cog;
+
+impl Exporter for Cog {
+    fn name(&self) -> &str {
+        "cog"
+    }
+
+    fn extension(&self) -> &str {
+        "cog.tif"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_cog(grid, out)
+    }
+}
+
+// GDAL's metadata XML, describing each band by name, and the first by the
+// quantity it measures (if known); `None` if there's nothing to describe
+fn band_metadata(names: &[&str], quantity: Option<&Quantity>)
+  -> Option<String> {
+    let mut names = names.to_vec();
+    if let (true, Some(quantity)) = (names.is_empty(), quantity) {
+        names.push(&quantity.name);
+    }
+    if names.is_empty() {
+        return None;
+    }
+
+    let mut xml = String::from("<GDALMetadata>");
+    for (i, name) in names.iter().enumerate() {
+        xml.push_str(&format!(
+          "<Item name=\"DESCRIPTION\" sample=\"{}\" role=\"description\">\
+           {}</Item>", i, xml_escape(name)));
+    }
+    if let Some(quantity) = quantity {
+        xml.push_str(&format!(
+          "<Item name=\"UNITTYPE\" sample=\"0\" role=\"unittype\">{}</Item>",
+          xml_escape(&quantity.units)));
+    }
+    xml.push_str("</GDALMetadata>");
+    Some(xml)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;")
+      .replace('"', "&quot;")
+}
+
+fn projected_cs_type(grid: &Grid) -> u16 {
+    grid.epsg()
+      .and_then(|code| u16::try_from(code).ok())
+      .unwrap_or(USER_DEFINED)
+}
+
+fn linear_units(uom: UnitOfMeasure) -> u16 {
+    match uom {
+        UnitOfMeasure::Feet => LINEAR_FOOT_US_SURVEY,
+        UnitOfMeasure::Meters => LINEAR_METER,
+        UnitOfMeasure::Other(_) => USER_DEFINED,
+    }
+}
+
+/* a TIFF image file directory, built up one (tag-ordered) entry at a time;
+ * values too large to fit in an entry are stored just after the directory */
+struct Ifd {
+    offset: u32,
+    entries: Vec<(u16, u16, u32, Vec<u8>)>,
+    // the offset of the next directory, if any
+    next: u32,
+}
+
+impl Ifd {
+    fn new(offset: u32) -> Ifd {
+        Ifd { offset, entries: Vec::new(), next: 0 }
+    }
+
+    // the length of the directory and its out-of-line values, in bytes
+    fn len(&self) -> u32 {
+        let extra = self.entries.iter()
+          .map(|(_, _, _, bytes)| match bytes.len() {
+              n if n <= 4 => 0,
+              n => n + n % 2,
+          })
+          .sum::<usize>() as u32;
+        2 + 12 * self.entries.len() as u32 + 4 + extra
+    }
+
+    fn shorts(&mut self, tag: u16, values: &[u16]) {
+        let bytes = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.entries.push((tag, SHORT, values.len() as u32, bytes));
+    }
+
+    fn longs(&mut self, tag: u16, values: &[u32]) {
+        let bytes = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.entries.push((tag, LONG, values.len() as u32, bytes));
+    }
+
+    fn doubles(&mut self, tag: u16, values: &[f64]) {
+        let bytes = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.entries.push((tag, DOUBLE, values.len() as u32, bytes));
+    }
+
+    fn ascii(&mut self, tag: u16, value: &str) {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.push(0);
+        self.entries.push((tag, ASCII, bytes.len() as u32, bytes));
+    }
+
+    fn write<W: Write>(mut self, out: &mut W) -> Result<(), Error> {
+        self.entries.sort_by_key(|e| e.0);
+        let ifd_len = 2 + 12 * self.entries.len() as u32 + 4;
+        let mut extra_offset = self.offset + ifd_len;
+        let mut extra = Vec::new();
+
+        out.write_u16::<LittleEndian>(self.entries.len() as u16)?;
+        for (tag, ty, count, bytes) in &self.entries {
+            out.write_u16::<LittleEndian>(*tag)?;
+            out.write_u16::<LittleEndian>(*ty)?;
+            out.write_u32::<LittleEndian>(*count)?;
+            if bytes.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..bytes.len()].copy_from_slice(bytes);
+                out.write_all(&inline)?;
+            } else {
+                out.write_u32::<LittleEndian>(extra_offset)?;
+                extra.extend_from_slice(bytes);
+                // values must start on a word boundary
+                if extra.len() % 2 == 1 {
+                    extra.push(0);
+                }
+                extra_offset = self.offset + ifd_len + extra.len() as u32;
+            }
+        }
+        out.write_u32::<LittleEndian>(self.next)?;
+        out.write_all(&extra)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    const IMAGE_WIDTH: u16 = 256;
+    const IMAGE_LENGTH: u16 = 257;
+    const TILE_WIDTH: u16 = 322;
+
+    // the (tag, value bytes) entries of the first directory
+    fn first_ifd(buf: &[u8]) -> Vec<(u16, &[u8])> {
+        assert_eq!(&buf[..4], b"II*\0");
+        let offset = (&buf[4..]).read_u32::<LittleEndian>().unwrap();
+        let mut ifd = &buf[offset as usize..];
+        let n = ifd.read_u16::<LittleEndian>().unwrap();
+        (0..n).map(|_| {
+            let tag = ifd.read_u16::<LittleEndian>().unwrap();
+            let size = match ifd.read_u16::<LittleEndian>().unwrap() {
+                ASCII => 1,
+                SHORT => 2,
+                LONG => 4,
+                DOUBLE => 8,
+                other => panic!("unexpected field type {}", other),
+            };
+            let len = size * ifd.read_u32::<LittleEndian>().unwrap() as usize;
+            let value = if len <= 4 {
+                &ifd[..len]
+            } else {
+                let at = (&ifd[..4]).read_u32::<LittleEndian>().unwrap();
+                &buf[at as usize..at as usize + len]
+            };
+            ifd = &ifd[4..];
+            (tag, value)
+        }).collect()
+    }
+
+    fn field<'a>(ifd: &[(u16, &'a [u8])], tag: u16) -> &'a [u8] {
+        ifd.iter().find(|e| e.0 == tag)
+          .unwrap_or_else(|| panic!("no tag {}", tag)).1
+    }
+
+    fn doubles(bytes: &[u8]) -> Vec<f64> {
+        bytes.chunks(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+          .collect()
+    }
+
+    fn dimension(bytes: &[u8]) -> u32 {
+        match bytes.len() {
+            2 => u16::from_le_bytes(bytes.try_into().unwrap()) as u32,
+            _ => u32::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    #[test]
+    fn geotiff_directory_has_size_and_georeferencing() {
+        let mut buf = Vec::new();
+        write_geotiff(&small_plane(), &mut buf).expect("write GeoTIFF");
+        let ifd = first_ifd(&buf);
+        assert_eq!(dimension(field(&ifd, IMAGE_WIDTH)), 4);
+        assert_eq!(dimension(field(&ifd, IMAGE_LENGTH)), 3);
+        assert_eq!(doubles(field(&ifd, MODEL_PIXEL_SCALE)), [25.0, 50.0, 0.0]);
+        assert_eq!(doubles(field(&ifd, MODEL_TIEPOINT)),
+          [0.0, 0.0, 0.0, 1000.0, 2100.0, 0.0]);
+        let keys = field(&ifd, GEO_KEY_DIRECTORY);
+        assert_eq!(&keys[8..16], &[GT_MODEL_TYPE, 0, 1, MODEL_TYPE_PROJECTED]
+          .map(u16::to_le_bytes).concat()[..]);
+    }
+
+    #[test]
+    fn cog_directory_has_size_tiles_and_georeferencing() {
+        let mut buf = Vec::new();
+        write_cog(&small_plane(), &mut buf).expect("write COG");
+        let ifd = first_ifd(&buf);
+        assert_eq!(dimension(field(&ifd, IMAGE_WIDTH)), 4);
+        assert_eq!(dimension(field(&ifd, IMAGE_LENGTH)), 3);
+        assert_eq!(dimension(field(&ifd, TILE_WIDTH)), COG_TILE_SIZE as u32);
+        assert_eq!(doubles(field(&ifd, MODEL_PIXEL_SCALE)), [25.0, 50.0, 0.0]);
+        assert_eq!(doubles(field(&ifd, MODEL_TIEPOINT)),
+          [0.0, 0.0, 0.0, 1000.0, 2100.0, 0.0]);
+    }
+}
@@ -0,0 +1,251 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! binary glTF 2.0 (GLB) meshes, for web 3D viewers such as three.js,
+//! Babylon.js, and `<model-viewer>`
+//!
+//! grids are written as a single indexed triangle mesh: triangular grids
+//! as-is (with shared vertices merged), and rectangular grids with two
+//! triangles per cell; triangles with missing vertices are omitted. vertices
+//! may be colored by *z* with a [Colormap] (see [GltfOptions])
+//!
+//! glTF is *y*-up, so grid (*x*, *y*, *z*) becomes glTF (*x*, *z*, -*y*).
+//! glTF positions are single-precision, so *x* and *y* are written relative
+//! to the center of the grid's extent; that center, and the grid's
+//! coordinate system, are recorded in the node's `extras`
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Colormap,
+    Error,
+    Grid,
+    export::{Exporter, crs_description, mesh::Mesh, triangles::json_string},
+};
+
+// GLB chunk types, and the magic number of the file header
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: &[u8; 4] = b"JSON";
+const CHUNK_BIN: &[u8; 4] = b"BIN\0";
+
+// accessor component types and buffer view targets
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// options for glTF exports; see [write_glb_with]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GltfOptions {
+    colormap: Option<Colormap>,
+}
+
+impl GltfOptions {
+    /// color vertices by *z* over the grid's range with a colormap (or, with
+    /// `None`, the default, leave them uncolored)
+    pub fn colormap(mut self, colormap: Option<Colormap>) -> Self {
+        self.colormap = colormap;
+        self
+    }
+}
+
+/// write a grid as a binary glTF (GLB) triangle mesh, without vertex colors
+pub fn write_glb<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_glb_with(grid, &GltfOptions::default(), out)
+}
+
+/// write a grid as a binary glTF (GLB) triangle mesh, with the given options
+pub fn write_glb_with<W: Write + ?Sized>(grid: &Grid, options: &GltfOptions,
+  out: &mut W) -> Result<(), Error> {
+    let mesh = Mesh::from_grid(grid);
+    if mesh.faces.is_empty() {
+        return Err(Error::InvalidArgument(
+          "grid has no complete triangles to export".to_string()));
+    }
+
+    let cx = (grid.xmin + grid.xmax) / 2.0;
+    let cy = (grid.ymin + grid.ymax) / 2.0;
+    let positions = mesh.vertices.iter()
+      .map(|&[x, y, z]| [(x - cx) as f32, z as f32, (cy - y) as f32])
+      .collect::<Vec<_>>();
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for p in &positions {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+
+    let mut bin = Vec::new();
+    for p in &positions {
+        for c in p {
+            bin.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+    let positions_len = bin.len();
+
+    for face in &mesh.faces {
+        /* glTF front faces are counterclockwise; triangular grids may wind
+         * either way */
+        let [a, b, c] = face.map(|v| mesh.vertices[v]);
+        let area = (b[0] - a[0]) * (c[1] - a[1])
+          - (c[0] - a[0]) * (b[1] - a[1]);
+        let face = if area < 0.0 {
+            [face[0], face[2], face[1]]
+        } else {
+            *face
+        };
+        for v in face {
+            bin.extend_from_slice(&(v as u32).to_le_bytes());
+        }
+    }
+    let indices_len = bin.len() - positions_len;
+
+    let colors_len = match &options.colormap {
+        Some(colormap) => {
+            let (zmin, zmax) = (min[1] as f64, max[1] as f64);
+            for &[_, _, z] in &mesh.vertices {
+                let [r, g, b] = colormap.color_for(z, zmin, zmax);
+                bin.extend_from_slice(&[r, g, b, 255]);
+            }
+            4 * mesh.vertices.len()
+        },
+        None => 0,
+    };
+
+    let n_vertices = mesh.vertices.len();
+    let (color_attribute, color_view, color_accessor) = if colors_len > 0 {
+        (",\"COLOR_0\":2".to_string(),
+         format!(",{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\
+           \"target\":{}}}", positions_len + indices_len, colors_len,
+           ARRAY_BUFFER),
+         format!(",{{\"bufferView\":2,\"componentType\":{},\
+           \"normalized\":true,\"count\":{},\"type\":\"VEC4\"}}",
+           UNSIGNED_BYTE, n_vertices))
+    } else {
+        (String::new(), String::new(), String::new())
+    };
+    let name = json_string(&grid.name);
+    let json = format!(
+      "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"petra_grid {}\"}},\
+       \"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\
+       \"nodes\":[{{\"name\":{},\"mesh\":0,\"extras\":{{\
+       \"origin\":[{},{}],\"crs\":{}}}}}],\
+       \"meshes\":[{{\"name\":{},\"primitives\":[{{\
+       \"attributes\":{{\"POSITION\":0{}}},\"indices\":1,\"material\":0,\
+       \"mode\":4}}]}}],\
+       \"materials\":[{{\"doubleSided\":true,\"pbrMetallicRoughness\":{{\
+       \"metallicFactor\":0,\"roughnessFactor\":1}}}}],\
+       \"buffers\":[{{\"byteLength\":{}}}],\
+       \"bufferViews\":[\
+       {{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":{}}},\
+       {{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":{}}}{}],\
+       \"accessors\":[\
+       {{\"bufferView\":0,\"componentType\":{},\"count\":{},\"type\":\"VEC3\",\
+       \"min\":[{},{},{}],\"max\":[{},{},{}]}},\
+       {{\"bufferView\":1,\"componentType\":{},\"count\":{},\
+       \"type\":\"SCALAR\"}}{}]}}",
+      env!("CARGO_PKG_VERSION"), name, cx, cy,
+      json_string(&crs_description(grid)), name, color_attribute, bin.len(),
+      positions_len, ARRAY_BUFFER, positions_len, indices_len,
+      ELEMENT_ARRAY_BUFFER, color_view, FLOAT, n_vertices,
+      min[0], min[1], min[2], max[0], max[1], max[2], UNSIGNED_INT,
+      3 * mesh.faces.len(), color_accessor);
+
+    // chunks are padded to 4 bytes: JSON with spaces, binary with zeros
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let total = 12 + 8 + json.len() + 8 + bin.len();
+    let total = u32::try_from(total).map_err(|_| Error::InvalidArgument(
+      "mesh is too large for a GLB file".to_string()))?;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(GLB_MAGIC)?;
+    out.write_all(&2u32.to_le_bytes())?;
+    out.write_all(&total.to_le_bytes())?;
+    out.write_all(&(json.len() as u32).to_le_bytes())?;
+    out.write_all(CHUNK_JSON)?;
+    out.write_all(&json)?;
+    out.write_all(&(bin.len() as u32).to_le_bytes())?;
+    out.write_all(CHUNK_BIN)?;
+    out.write_all(&bin)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// binary glTF, as an [Exporter], with vertices colored by the "terrain"
+/// colormap; see [write_glb_with]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Glb;
+
+impl Exporter for Glb {
+    fn name(&self) -> &str {
+        "glb"
+    }
+
+    fn extension(&self) -> &str {
+        "glb"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        let options = GltfOptions::default()
+          .colormap(Some(Colormap::terrain()));
+        write_glb_with(grid, &options, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_and_chunks_read_back() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_glb(&grid, &mut buf).expect("write GLB file");
+
+        assert_eq!(&buf[..4], GLB_MAGIC);
+        let mut header = &buf[4..];
+        assert_eq!(header.read_u32::<LittleEndian>().unwrap(), 2);
+        assert_eq!(header.read_u32::<LittleEndian>().unwrap() as usize,
+          buf.len());
+
+        let json_len = header.read_u32::<LittleEndian>().unwrap() as usize;
+        assert_eq!(&header[..4], CHUNK_JSON);
+        let json = std::str::from_utf8(&header[4..4 + json_len]).unwrap();
+        assert!(json.contains("\"count\":12,\"type\":\"VEC3\",\
+          \"min\":[-37.5,1260,-50],\"max\":[37.5,1328.75,50]"));
+        assert!(json.contains("\"count\":36,\"type\":\"SCALAR\""));
+
+        let mut bin = &header[4 + json_len..];
+        // 12 vertices, then 6 cells of 2 triangles
+        assert_eq!(bin.read_u32::<LittleEndian>().unwrap(), 12 * 12 + 36 * 4);
+        assert_eq!(&bin[..4], CHUNK_BIN);
+        assert_eq!(bin.len(), 4 + 12 * 12 + 36 * 4);
+    }
+}
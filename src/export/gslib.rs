@@ -0,0 +1,93 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! GSLIB (GeoEAS) grid files, for SGeMS and other geostatistics tools
+//!
+//! a title line, the number of variables, and the variable's name are
+//! followed by one *z* value per line, in GSLIB's grid order: *x* fastest
+//! (west to east), then *y* (south to north). GSLIB files don't record their
+//! lattice, so the number of nodes, origin, and spacing along each axis
+//! (including a single layer along *z*) are given in the title, ready to be
+//! entered as the grid definition. missing values are written as
+//! [MISSING_VALUE]. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+/// the value written for missing nodes, as GSLIB programs conventionally
+/// expect
+pub const MISSING_VALUE: f64 = -999.0;
+
+/// write a grid as a GSLIB grid file
+pub fn write_gslib<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+
+    let mut out = BufWriter::new(out);
+    let name = match grid.name.replace(['\r', '\n'], " ").trim() {
+        "" => "Petra grid".to_string(),
+        name => name.to_string(),
+    };
+    writeln!(out, "{} (nx ny nz {} {} 1; xmn ymn zmn {} {} 0; \
+      xsiz ysiz zsiz {} {} 1)", name, cols, rows, grid.xmin, grid.ymin,
+      grid.xstep, grid.ystep)?;
+    writeln!(out, "1")?;
+    writeln!(out, "z")?;
+    // rows are already south to north, each west to east
+    for &z in arr.iter() {
+        if z.is_nan() {
+            writeln!(out, "{}", MISSING_VALUE)?;
+        } else {
+            writeln!(out, "{}", z)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// GSLIB grid files, as an [Exporter]; see [write_gslib]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Gslib;
+
+impl Exporter for Gslib {
+    fn name(&self) -> &str {
+        "gslib"
+    }
+
+    fn extension(&self) -> &str {
+        "gslib"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_gslib(grid, out)
+    }
+}
@@ -0,0 +1,515 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! HDF5 files, for reading grids directly from h5py, MATLAB, and friends
+//!
+//! files are written by hand (so need no external library) using only the
+//! simplest parts of the format: a version 2 superblock, "compact" groups,
+//! and contiguous, uncompressed datasets of little-endian doubles. a
+//! rectangular grid's *z* values become a 2D dataset `z` (rows south to
+//! north, columns west to east) with 1D coordinate datasets `x` and `y`; a
+//! triangular grid's triangles become a 3D dataset `triangles`, indexed by
+//! triangle, vertex, and (*x*, *y*, *z*). missing values are NaN, which is
+//! also the datasets' fill value
+//!
+//! the grid's header fields (name, source data, projection, datum, units,
+//! bounds, and so on) are attached to the `z` or `triangles` dataset as
+//! attributes, along with a description of its coordinate system and its
+//! EPSG code (if we know it; see [Grid::epsg])
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, GridData, export::{Exporter, crs_description}};
+
+const SIGNATURE: &[u8] = b"\x89HDF\r\n\x1a\n";
+const UNDEFINED_ADDRESS: u64 = u64::MAX;
+const SUPERBLOCK_SIZE: u64 = 48;
+
+// object header message types
+const MSG_DATASPACE: u8 = 0x01;
+const MSG_LINK_INFO: u8 = 0x02;
+const MSG_DATATYPE: u8 = 0x03;
+const MSG_FILL_VALUE: u8 = 0x05;
+const MSG_LINK: u8 = 0x06;
+const MSG_LAYOUT: u8 = 0x08;
+const MSG_GROUP_INFO: u8 = 0x0a;
+const MSG_ATTRIBUTE: u8 = 0x0c;
+
+// object header message flags
+const MSG_FLAG_CONSTANT: u8 = 0x01;
+
+/// write a grid as an HDF5 file
+pub fn write_hdf5<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mut attrs = vec![
+        ("name", Attr::Text(grid.name.clone())),
+        ("source_data", Attr::Text(grid.source_data.clone())),
+        ("projection", Attr::Text(grid.projection.clone())),
+        ("datum", Attr::Text(grid.datum.clone())),
+        ("crs", Attr::Text(crs_description(grid))),
+    ];
+    if let Some(code) = grid.epsg() {
+        attrs.push(("epsg", Attr::UInt(code)));
+    }
+    attrs.extend([
+        ("created_date", Attr::Text(grid.created_date.to_string())),
+        ("xy_units", Attr::Text(format!("{:?}", grid.xyunits))),
+        ("z_units", Attr::Text(format!("{:?}", grid.zunits))),
+        ("version", Attr::UInt(grid.version)),
+        ("projection_code", Attr::UInt(grid.projection_code)),
+        ("rows", Attr::UInt(grid.rows)),
+        ("columns", Attr::UInt(grid.columns)),
+        ("n_triangles", Attr::UInt(grid.n_triangles)),
+        ("xmin", Attr::Double(grid.xmin)),
+        ("xmax", Attr::Double(grid.xmax)),
+        ("ymin", Attr::Double(grid.ymin)),
+        ("ymax", Attr::Double(grid.ymax)),
+        ("xstep", Attr::Double(grid.xstep)),
+        ("ystep", Attr::Double(grid.ystep)),
+        ("zmin", Attr::Double(grid.zmin)),
+        ("zmax", Attr::Double(grid.zmax)),
+    ]);
+
+    let datasets = match &grid.data {
+        GridData::Rectangular(arr) => {
+            let (rows, cols) = arr.dim();
+            let xy_units = || vec![
+                ("units", Attr::Text(format!("{:?}", grid.xyunits))),
+            ];
+            vec![
+                Dataset {
+                    name: "x",
+                    dims: vec![cols as u64],
+                    attrs: xy_units(),
+                    data: (0..cols)
+                      .map(|i| grid.xmin + i as f64 * grid.xstep)
+                      .collect(),
+                },
+                Dataset {
+                    name: "y",
+                    dims: vec![rows as u64],
+                    attrs: xy_units(),
+                    data: (0..rows)
+                      .map(|j| grid.ymin + j as f64 * grid.ystep)
+                      .collect(),
+                },
+                Dataset {
+                    name: "z",
+                    dims: vec![rows as u64, cols as u64],
+                    attrs,
+                    data: arr.iter().copied().collect(),
+                },
+            ]
+        },
+        GridData::Triangular(arr) => {
+            let (n, vertices, coords) = arr.dim();
+            vec![
+                Dataset {
+                    name: "triangles",
+                    dims: vec![n as u64, vertices as u64, coords as u64],
+                    attrs,
+                    data: arr.iter().copied().collect(),
+                },
+            ]
+        },
+    };
+
+    /* every object header records the addresses of what it points to, but
+     * none of their lengths depend on those addresses; so we build the
+     * headers once to learn where everything will go, then again for
+     * real */
+    let root_size = root_header(&datasets, &vec![0; datasets.len()]).len();
+    let mut header_addrs = Vec::with_capacity(datasets.len());
+    let mut offset = SUPERBLOCK_SIZE + root_size as u64;
+    for ds in &datasets {
+        header_addrs.push(offset);
+        offset += dataset_header(ds, 0).len() as u64;
+    }
+    let mut headers = Vec::with_capacity(datasets.len());
+    for ds in &datasets {
+        headers.push(dataset_header(ds, offset));
+        offset += ds.data.len() as u64 * 8;
+    }
+    let eof = offset;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(&superblock(SUPERBLOCK_SIZE, eof))?;
+    out.write_all(&root_header(&datasets, &header_addrs))?;
+    for header in &headers {
+        out.write_all(header)?;
+    }
+    for ds in &datasets {
+        for v in &ds.data {
+            out.write_all(&v.to_le_bytes())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// HDF5, as an [Exporter]; see [write_hdf5]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Hdf5;
+
+impl Exporter for Hdf5 {
+    fn name(&self) -> &str {
+        "hdf5"
+    }
+
+    fn extension(&self) -> &str {
+        "h5"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_hdf5(grid, out)
+    }
+}
+
+enum Attr {
+    Text(String),
+    UInt(u32),
+    Double(f64),
+}
+
+impl Attr {
+    // the encoded datatype of the attribute's value
+    fn datatype(&self) -> Vec<u8> {
+        match self {
+            /* null-terminated UTF-8; GRD text fields are decoded lossily,
+             * so they're always valid */
+            Attr::Text(s) => datatype(0x13, [0x10, 0, 0], s.len() as u32 + 1,
+              &[]),
+            // unsigned, little-endian, 32 bits starting at bit 0
+            Attr::UInt(_) => datatype(0x10, [0, 0, 0], 4, &[0, 0, 32, 0]),
+            Attr::Double(_) => f64_datatype(),
+        }
+    }
+
+    fn value(&self) -> Vec<u8> {
+        match self {
+            Attr::Text(s) => {
+                let mut v = s.as_bytes().to_vec();
+                v.push(0);
+                v
+            },
+            Attr::UInt(u) => u.to_le_bytes().to_vec(),
+            Attr::Double(d) => d.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+struct Dataset {
+    name: &'static str,
+    dims: Vec<u64>,
+    attrs: Vec<(&'static str, Attr)>,
+    data: Vec<f64>,
+}
+
+fn superblock(root_addr: u64, eof: u64) -> Vec<u8> {
+    let mut h = SIGNATURE.to_vec();
+    // version 2, 8-byte offsets and lengths, no consistency flags
+    h.extend_from_slice(&[2, 8, 8, 0]);
+    put_u64(&mut h, 0); // base address
+    put_u64(&mut h, UNDEFINED_ADDRESS); // no superblock extension
+    put_u64(&mut h, eof);
+    put_u64(&mut h, root_addr);
+    let checksum = lookup3(&h);
+    put_u32(&mut h, checksum);
+    h
+}
+
+// the root group: compact storage, with a hard link to each dataset
+fn root_header(datasets: &[Dataset], addrs: &[u64]) -> Vec<u8> {
+    let mut link_info = vec![0, 0]; // version 0, no creation order
+    put_u64(&mut link_info, UNDEFINED_ADDRESS); // no fractal heap
+    put_u64(&mut link_info, UNDEFINED_ADDRESS); // no name index
+    let mut messages = vec![
+        (MSG_LINK_INFO, 0, link_info),
+        (MSG_GROUP_INFO, 0, vec![0, 0]), // version 0, all defaults
+    ];
+    for (ds, &addr) in datasets.iter().zip(addrs) {
+        // version 1, 1-byte name length, hard link
+        let mut link = vec![1, 0, ds.name.len() as u8];
+        link.extend_from_slice(ds.name.as_bytes());
+        put_u64(&mut link, addr);
+        messages.push((MSG_LINK, 0, link));
+    }
+    object_header(&messages, false)
+}
+
+fn dataset_header(ds: &Dataset, data_addr: u64) -> Vec<u8> {
+    let mut messages = vec![
+        (MSG_DATASPACE, 0, dataspace(&ds.dims)),
+        (MSG_DATATYPE, MSG_FLAG_CONSTANT, f64_datatype()),
+    ];
+
+    /* version 3; late allocation, fill value written if set by the user,
+     * and set (to NaN) */
+    let mut fill = vec![3, 0x2a];
+    put_u32(&mut fill, 8);
+    fill.extend_from_slice(&f64::NAN.to_le_bytes());
+    messages.push((MSG_FILL_VALUE, MSG_FLAG_CONSTANT, fill));
+
+    let mut layout = vec![3, 1]; // version 3, contiguous
+    put_u64(&mut layout, data_addr);
+    put_u64(&mut layout, ds.data.len() as u64 * 8);
+    messages.push((MSG_LAYOUT, 0, layout));
+
+    for (name, value) in &ds.attrs {
+        let datatype = value.datatype();
+        let dataspace = dataspace(&[]);
+        let mut attr = vec![3, 0]; // version 3, not shared
+        put_u16(&mut attr, name.len() as u16 + 1);
+        put_u16(&mut attr, datatype.len() as u16);
+        put_u16(&mut attr, dataspace.len() as u16);
+        attr.push(0); // ASCII name
+        attr.extend_from_slice(name.as_bytes());
+        attr.push(0);
+        attr.extend_from_slice(&datatype);
+        attr.extend_from_slice(&dataspace);
+        attr.extend_from_slice(&value.value());
+        messages.push((MSG_ATTRIBUTE, 0, attr));
+    }
+
+    object_header(&messages, true)
+}
+
+/* a version 2 object header, with a single chunk holding the given (type,
+ * flags, data) messages; when asked, we raise the limit on "compact"
+ * attributes, so that readers never expect ours to be stored densely */
+fn object_header(messages: &[(u8, u8, Vec<u8>)], many_attributes: bool)
+  -> Vec<u8> {
+    let mut h = b"OHDR".to_vec();
+    h.push(2); // version
+    if many_attributes {
+        // 4-byte chunk size, non-default attribute phase change
+        h.push(0x12);
+        let n = messages.iter().filter(|m| m.0 == MSG_ATTRIBUTE).count();
+        put_u16(&mut h, n.max(8) as u16);
+        put_u16(&mut h, 6);
+    } else {
+        h.push(0x02); // 4-byte chunk size
+    }
+    let size: usize = messages.iter().map(|m| 4 + m.2.len()).sum();
+    put_u32(&mut h, size as u32);
+    for (kind, flags, data) in messages {
+        h.push(*kind);
+        put_u16(&mut h, data.len() as u16);
+        h.push(*flags);
+        h.extend_from_slice(data);
+    }
+    let checksum = lookup3(&h);
+    put_u32(&mut h, checksum);
+    h
+}
+
+// a version 2 dataspace: simple with the given dimensions, or scalar
+fn dataspace(dims: &[u64]) -> Vec<u8> {
+    let kind = if dims.is_empty() { 0 } else { 1 };
+    let mut h = vec![2, dims.len() as u8, 0, kind];
+    for &d in dims {
+        put_u64(&mut h, d);
+    }
+    h
+}
+
+fn datatype(class_version: u8, bits: [u8; 3], size: u32, properties: &[u8])
+  -> Vec<u8> {
+    let mut h = vec![class_version];
+    h.extend_from_slice(&bits);
+    put_u32(&mut h, size);
+    h.extend_from_slice(properties);
+    h
+}
+
+// IEEE 754 double, little-endian
+fn f64_datatype() -> Vec<u8> {
+    /* implied leading mantissa bit, sign at bit 63; then bit offset and
+     * precision, exponent location and size, mantissa location and size,
+     * and exponent bias */
+    datatype(0x11, [0x20, 63, 0], 8,
+      &[0, 0, 64, 0, 52, 11, 0, 52, 0xff, 0x03, 0, 0])
+}
+
+fn put_u16(h: &mut Vec<u8>, v: u16) {
+    h.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u32(h: &mut Vec<u8>, v: u32) {
+    h.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u64(h: &mut Vec<u8>, v: u64) {
+    h.extend_from_slice(&v.to_le_bytes());
+}
+
+// Bob Jenkins' lookup3 hash ("hashlittle", seeded with 0), as HDF5 uses for
+// metadata checksums
+fn lookup3(key: &[u8]) -> u32 {
+    let word = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+
+    let mut a = 0xdeadbeef_u32.wrapping_add(key.len() as u32);
+    let mut b = a;
+    let mut c = a;
+    let mut k = key;
+    while k.len() > 12 {
+        a = a.wrapping_add(word(&k[0..]));
+        b = b.wrapping_add(word(&k[4..]));
+        c = c.wrapping_add(word(&k[8..]));
+        a = a.wrapping_sub(c); a ^= c.rotate_left(4); c = c.wrapping_add(b);
+        b = b.wrapping_sub(a); b ^= a.rotate_left(6); a = a.wrapping_add(c);
+        c = c.wrapping_sub(b); c ^= b.rotate_left(8); b = b.wrapping_add(a);
+        a = a.wrapping_sub(c); a ^= c.rotate_left(16); c = c.wrapping_add(b);
+        b = b.wrapping_sub(a); b ^= a.rotate_left(19); a = a.wrapping_add(c);
+        c = c.wrapping_sub(b); c ^= b.rotate_left(4); b = b.wrapping_add(a);
+        k = &k[12..];
+    }
+    if k.is_empty() {
+        return c;
+    }
+
+    // the last block is zero-padded
+    let mut tail = [0; 12];
+    tail[..k.len()].copy_from_slice(k);
+    a = a.wrapping_add(word(&tail[0..]));
+    b = b.wrapping_add(word(&tail[4..]));
+    c = c.wrapping_add(word(&tail[8..]));
+    c ^= b; c = c.wrapping_sub(b.rotate_left(14));
+    a ^= c; a = a.wrapping_sub(c.rotate_left(11));
+    b ^= a; b = b.wrapping_sub(a.rotate_left(25));
+    c ^= b; c = c.wrapping_sub(b.rotate_left(16));
+    a ^= c; a = a.wrapping_sub(c.rotate_left(4));
+    b ^= a; b = b.wrapping_sub(a.rotate_left(14));
+    c ^= b; c = c.wrapping_sub(b.rotate_left(24));
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    // the (type, data) messages of the object header at the given address
+    fn messages(buf: &[u8], addr: u64) -> Vec<(u8, &[u8])> {
+        let h = &buf[addr as usize..];
+        assert_eq!(&h[..5], b"OHDR\x02");
+        let flags = h[5];
+        let mut rest = if flags & 0x10 != 0 { &h[10..] } else { &h[6..] };
+        let size = rest.read_u32::<LittleEndian>().unwrap() as usize;
+        let header_len = h.len() - rest.len();
+        assert_eq!(lookup3(&h[..header_len + size]),
+          u32::from_le_bytes(h[header_len + size..][..4].try_into().unwrap()));
+
+        let mut messages = Vec::new();
+        let mut rest = &rest[..size];
+        while !rest.is_empty() {
+            let kind = rest.read_u8().unwrap();
+            let len = rest.read_u16::<LittleEndian>().unwrap() as usize;
+            rest.read_u8().unwrap();
+            messages.push((kind, &rest[..len]));
+            rest = &rest[len..];
+        }
+        messages
+    }
+
+    #[test]
+    fn superblock_and_z_dataset_read_back() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_hdf5(&grid, &mut buf).expect("write HDF5 file");
+
+        assert_eq!(&buf[..8], SIGNATURE);
+        assert_eq!(&buf[8..12], &[2, 8, 8, 0]);
+        let mut superblock = &buf[12..SUPERBLOCK_SIZE as usize];
+        assert_eq!(superblock.read_u64::<LittleEndian>().unwrap(), 0);
+        assert_eq!(superblock.read_u64::<LittleEndian>().unwrap(),
+          UNDEFINED_ADDRESS);
+        assert_eq!(superblock.read_u64::<LittleEndian>().unwrap(),
+          buf.len() as u64);
+        let root_addr = superblock.read_u64::<LittleEndian>().unwrap();
+        assert_eq!(superblock.read_u32::<LittleEndian>().unwrap(),
+          lookup3(&buf[..44]));
+
+        // version 1, 1-byte name length, hard link, then the name and address
+        let mut link = messages(&buf, root_addr).into_iter()
+          .find(|&(kind, link)| kind == MSG_LINK && &link[2..4] == b"\x01z")
+          .expect("a link to z").1;
+        link = &link[4..];
+        let z_addr = link.read_u64::<LittleEndian>().unwrap();
+        let z = messages(&buf, z_addr);
+        let space = z.iter().find(|m| m.0 == MSG_DATASPACE).unwrap().1;
+        assert_eq!(space, &dataspace(&[3, 4])[..]);
+        let mut layout = z.iter().find(|m| m.0 == MSG_LAYOUT).unwrap().1;
+        assert_eq!(&layout[..2], &[3, 1]);
+        layout = &layout[2..];
+        let data_addr = layout.read_u64::<LittleEndian>().unwrap() as usize;
+        assert_eq!(layout.read_u64::<LittleEndian>().unwrap(), 12 * 8);
+        let mut data = &buf[data_addr..];
+        assert_eq!(data.read_f64::<LittleEndian>().unwrap(), 1260.0);
+        assert_eq!(data.read_f64::<LittleEndian>().unwrap(), 1266.25);
+    }
+
+    #[test]
+    fn lookup3_matches_the_reference_implementation() {
+        /* the values printed by driver5() in Bob Jenkins' lookup3.c, which
+         * HDF5 uses (as H5_checksum_lookup3) with an initial value of 0 */
+        assert_eq!(lookup3(b""), 0xdeadbeef);
+        assert_eq!(lookup3(b"Four score and seven years ago"), 0x17770551);
+    }
+
+    /* libhdf5 itself is the real test of a file, but isn't a dependency;
+     * run with `cargo test --features hdf5 -- --ignored` where Python and
+     * h5py are installed */
+    #[test]
+    #[ignore = "needs Python with h5py"]
+    fn h5py_reads_the_file_back() {
+        use std::{fs, process::Command};
+
+        let path = std::env::temp_dir()
+          .join(format!("petra_grid-hdf5-{}.h5", std::process::id()));
+        let mut f = fs::File::create(&path).expect("create HDF5 file");
+        write_hdf5(&small_plane(), &mut f).expect("write HDF5 file");
+        drop(f);
+
+        let script = r#"
+import sys
+import h5py
+import numpy as np
+with h5py.File(sys.argv[1], "r") as f:
+    z = f["z"]
+    assert z.shape == (3, 4) and z.dtype == np.float64, (z.shape, z.dtype)
+    assert z[0, 0] == 1260.0 and z[2, 3] == 1328.75, z[...]
+    assert list(f["x"]) == [1000.0, 1025.0, 1050.0, 1075.0], f["x"][...]
+    assert list(f["y"]) == [2000.0, 2050.0, 2100.0], f["y"][...]
+    assert z.attrs["rows"] == 3 and z.attrs["columns"] == 4
+    assert z.attrs["xmax"] == 1075.0 and z.attrs["zmax"] == 1328.75
+    name = z.attrs["name"]
+    name = name.decode() if isinstance(name, bytes) else name
+    assert name == "synthetic plane", name
+"#;
+        let status = Command::new("python3").arg("-c").arg(script).arg(&path)
+          .status().expect("run python3");
+        fs::remove_file(&path).ok();
+        assert!(status.success(), "h5py couldn't read the file (see above)");
+    }
+}
@@ -0,0 +1,170 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Irap binary regular surfaces, as used by RMS and read by xtgeo
+//!
+//! an Irap binary file is a series of big-endian Fortran records: a header
+//! (the lattice's shape, origin, extents, spacing, and rotation, which is
+//! always zero here) followed by single-precision *z* values, row by row
+//! from south to north, each row from west to east. missing nodes are
+//! written as Irap's undefined value, 1e30. triangular grids are rasterized
+//! first (see [Grid::rasterize])
+//!
+//! note that the header's coordinates are single-precision, so very large
+//! coordinates (e.g. state plane feet) lose their fractional parts
+
+use std::io::{BufWriter, Write};
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+// the magic number opening the header
+const IRAP_MAGIC: i32 = -996;
+
+/// Irap's value for undefined (missing) nodes
+pub const IRAP_UNDEFINED: f32 = 1e30;
+
+/// write a grid as an Irap binary regular surface
+pub fn write_irap_binary<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    /* the numbers of rows and columns, and the length in bytes of each
+     * row's record, must all fit in Irap's 32-bit integers */
+    let shape = i32::try_from(cols).ok()
+      .zip(i32::try_from(rows).ok())
+      .and_then(|(nx, ny)| Some((nx, ny, nx.checked_mul(4)?)));
+    let (nx, ny, row_len) = match shape {
+        Some(shape) => shape,
+        None => return Err(Error::InvalidArgument(
+          "grid is too large for an Irap surface".to_string())),
+    };
+    let xmax = grid.xmin + cols.saturating_sub(1) as f64 * grid.xstep;
+    let ymax = grid.ymin + rows.saturating_sub(1) as f64 * grid.ystep;
+
+    let mut out = BufWriter::new(out);
+    /* the magic number and the number of rows, then the origin, the
+     * extents, and the spacing */
+    record(&mut out, 32, |out| {
+        out.write_i32::<BigEndian>(IRAP_MAGIC)?;
+        out.write_i32::<BigEndian>(ny)?;
+        for v in [grid.xmin, xmax, grid.ymin, ymax, grid.xstep, grid.ystep] {
+            out.write_f32::<BigEndian>(v as f32)?;
+        }
+        Ok(())
+    })?;
+    // the number of columns, then the rotation and its origin
+    record(&mut out, 16, |out| {
+        out.write_i32::<BigEndian>(nx)?;
+        for v in [0.0, grid.xmin, grid.ymin] {
+            out.write_f32::<BigEndian>(v as f32)?;
+        }
+        Ok(())
+    })?;
+    // seven unused integers
+    record(&mut out, 28, |out| {
+        for _ in 0..7 {
+            out.write_i32::<BigEndian>(0)?;
+        }
+        Ok(())
+    })?;
+
+    for row in arr.outer_iter() {
+        record(&mut out, row_len, |out| {
+            for &z in row {
+                out.write_f32::<BigEndian>(if z.is_nan() {
+                    IRAP_UNDEFINED
+                } else {
+                    z as f32
+                })?;
+            }
+            Ok(())
+        })?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/* a Fortran sequential record: its length in bytes, its contents, and its
+ * length again */
+fn record<W, F>(out: &mut W, len: i32, contents: F) -> Result<(), Error>
+  where W: Write,
+        F: FnOnce(&mut W) -> Result<(), Error>,
+{
+    out.write_i32::<BigEndian>(len)?;
+    contents(out)?;
+    out.write_i32::<BigEndian>(len)?;
+    Ok(())
+}
+
+/// Irap binary, as an [Exporter]; see [write_irap_binary]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct IrapBinary;
+
+impl Exporter for IrapBinary {
+    fn name(&self) -> &str {
+        "irap-binary"
+    }
+
+    fn extension(&self) -> &str {
+        "gri"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_irap_binary(grid, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_has_origin_extent_and_increment() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_irap_binary(&grid, &mut buf).expect("write Irap surface");
+
+        let mut header = &buf[..];
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), 32);
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), IRAP_MAGIC);
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), 3);
+        let mut floats = [0.0; 6];
+        header.read_f32_into::<BigEndian>(&mut floats).unwrap();
+        assert_eq!(floats, [1000.0, 1075.0, 2000.0, 2100.0, 25.0, 50.0]);
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), 32);
+        // the second record opens with the number of columns
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), 16);
+        assert_eq!(header.read_i32::<BigEndian>().unwrap(), 4);
+    }
+}
@@ -0,0 +1,416 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ASPRS LAS point clouds
+//!
+//! the nodes of rectangular grids, or the (distinct) vertices of triangular
+//! grids, are written as LAS 1.2 points (in point data format 0, as
+//! unclassified single returns); missing values are omitted. coordinates
+//! are stored as scaled integers, with the scale for each axis the finest
+//! power of ten (down to a thousandth of a unit) which spans its range, and
+//! the offset its minimum, rounded down.
+//!
+//! the coordinate system is recorded in a GeoTIFF key directory (as LAS
+//! specifies), with the EPSG code of the grid's projection if we know it
+//! (see [Grid::epsg]) and its horizontal and vertical units.
+//!
+//! with the `laz` feature, `write_laz` writes the same points compressed as
+//! LAZ (via the [`laz`](https://crates.io/crates/laz) crate's LASzip
+//! implementation), which most lidar tools read directly; the compressed
+//! points are assembled in memory before they're written
+
+use std::{
+    collections::HashSet,
+    io::{BufWriter, Write},
+};
+
+#[cfg(feature = "laz")]
+use std::io::{self, Cursor};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+#[cfg(feature = "laz")]
+use laz::{LasZipCompressor, LazItemRecordBuilder, LazVlr};
+
+use crate::{Error, Grid, GridData, UnitOfMeasure, export::Exporter};
+
+const HEADER_SIZE: u16 = 227;
+const VLR_HEADER_SIZE: u32 = 54;
+const POINT_FORMAT: u8 = 0;
+const POINT_RECORD_LEN: u16 = 20;
+
+// return 1 of 1
+const SINGLE_RETURN: u8 = 0b001_001;
+
+const FINEST_SCALE: f64 = 0.001;
+
+// GeoKeys and their values, as in GeoTIFF
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const GT_MODEL_TYPE: u16 = 1024;
+const PROJECTED_CS_TYPE: u16 = 3072;
+const PROJ_LINEAR_UNITS: u16 = 3076;
+const VERTICAL_UNITS: u16 = 4099;
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const USER_DEFINED: u16 = 32767;
+const LINEAR_METER: u16 = 9001;
+// see the note in export::geotiff
+const LINEAR_FOOT_US_SURVEY: u16 = 9003;
+
+// LASzip marks compressed point formats by setting the high bit
+#[cfg(feature = "laz")]
+const LAZ_POINT_FORMAT_FLAG: u8 = 0x80;
+
+/// write a grid's nodes (or a triangular grid's vertices) as a LAS 1.2
+/// point cloud
+///
+/// fails with [Error::InvalidArgument] if the grid has more points than LAS 1.2
+/// can count
+pub fn write_las<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let points = las_points(grid);
+    let axes = axes(&points);
+    let vlrs = [geo_key_vlr(grid)];
+
+    let mut out = BufWriter::new(out);
+    write_header(&mut out, grid, &points, &axes, POINT_FORMAT, &vlrs)?;
+    for p in &points {
+        out.write_all(&point_record(p, &axes))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// write a grid's nodes (or a triangular grid's vertices) as a LAZ
+/// (LASzip-compressed LAS 1.2) point cloud
+///
+/// fails with [Error::InvalidArgument] if the grid has more points than LAS 1.2
+/// can count
+#[cfg(feature = "laz")]
+pub fn write_laz<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let points = las_points(grid);
+    let axes = axes(&points);
+
+    let items = LazItemRecordBuilder::default_for_point_format_id(
+      POINT_FORMAT, 0).map_err(io::Error::other)?;
+    let laz_vlr = LazVlr::from_laz_items(items);
+    let mut laszip = Vec::new();
+    laz_vlr.write_to(&mut laszip)?;
+    let vlrs = [
+        geo_key_vlr(grid),
+        Vlr {
+            user_id: LazVlr::USER_ID,
+            record_id: LazVlr::RECORD_ID,
+            description: LazVlr::DESCRIPTION,
+            data: laszip,
+        },
+    ];
+
+    /* the compressed points begin with the absolute offset of their chunk
+     * table, so they're written after the header in one buffer */
+    let mut file = Cursor::new(Vec::new());
+    write_header(&mut file, grid, &points, &axes,
+      POINT_FORMAT | LAZ_POINT_FORMAT_FLAG, &vlrs)?;
+    let mut compressor = LasZipCompressor::new(file, laz_vlr)
+      .map_err(io::Error::other)?;
+    for p in &points {
+        compressor.compress_one(&point_record(p, &axes))?;
+    }
+    compressor.done()?;
+
+    out.write_all(&compressor.into_inner().into_inner())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// LAS, as an [Exporter]; see [write_las]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Las;
+
+impl Exporter for Las {
+    fn name(&self) -> &str {
+        "las"
+    }
+
+    fn extension(&self) -> &str {
+        "las"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_las(grid, out)
+    }
+}
+
+/// LAZ, as an [Exporter]; see [write_laz]
+#[cfg(feature = "laz")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Laz;
+
+#[cfg(feature = "laz")]
+impl Exporter for Laz {
+    fn name(&self) -> &str {
+        "laz"
+    }
+
+    fn extension(&self) -> &str {
+        "laz"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_laz(grid, out)
+    }
+}
+
+struct Axis {
+    min: f64,
+    max: f64,
+    scale: f64,
+    offset: f64,
+}
+
+// a variable-length record following the header
+struct Vlr {
+    user_id: &'static str,
+    record_id: u16,
+    description: &'static str,
+    data: Vec<u8>,
+}
+
+/* the range of each coordinate, with the scale for each the finest power of
+ * ten (down to FINEST_SCALE) which spans it from its offset */
+fn axes(points: &[[f64; 3]]) -> [Axis; 3] {
+    let axis = |c: usize| {
+        let (min, max) = points.iter().fold(
+          (f64::INFINITY, f64::NEG_INFINITY),
+          |(min, max), p| (min.min(p[c]), max.max(p[c])));
+        if points.is_empty() {
+            Axis { min: 0.0, max: 0.0, scale: FINEST_SCALE, offset: 0.0 }
+        } else {
+            let offset = min.floor();
+            let mut scale = FINEST_SCALE;
+            while (max - offset) / scale > i32::MAX as f64 {
+                scale *= 10.0;
+            }
+            Axis { min, max, scale, offset }
+        }
+    };
+    [axis(0), axis(1), axis(2)]
+}
+
+// the public header block, followed by the VLRs
+fn write_header<W: Write>(out: &mut W, grid: &Grid, points: &[[f64; 3]],
+  axes: &[Axis; 3], point_format: u8, vlrs: &[Vlr]) -> Result<(), Error> {
+    let count = u32::try_from(points.len()).map_err(|_| Error::InvalidArgument(
+      format!("{} points are too many for a LAS 1.2 file", points.len())))?;
+    let vlrs_len: u32 = vlrs.iter()
+      .map(|v| VLR_HEADER_SIZE + v.data.len() as u32)
+      .sum();
+
+    out.write_all(b"LASF")?;
+    out.write_u16::<LittleEndian>(0)?; // file source ID
+    out.write_u16::<LittleEndian>(0)?; // global encoding
+    out.write_all(&[0; 16])?; // project GUID
+    out.write_all(&[1, 2])?; // version
+    write_padded(out, "OTHER", 32)?;
+    write_padded(out,
+      &format!("petra_grid {}", env!("CARGO_PKG_VERSION")), 32)?;
+    out.write_u16::<LittleEndian>(grid.created_date.ordinal())?;
+    out.write_u16::<LittleEndian>(
+      grid.created_date.year().clamp(0, u16::MAX as i32) as u16)?;
+    out.write_u16::<LittleEndian>(HEADER_SIZE)?;
+    out.write_u32::<LittleEndian>(HEADER_SIZE as u32 + vlrs_len)?;
+    out.write_u32::<LittleEndian>(vlrs.len() as u32)?;
+    out.write_u8(point_format)?;
+    out.write_u16::<LittleEndian>(POINT_RECORD_LEN)?;
+    out.write_u32::<LittleEndian>(count)?;
+    for n in [count, 0, 0, 0, 0] {
+        out.write_u32::<LittleEndian>(n)?;
+    }
+    for a in axes {
+        out.write_f64::<LittleEndian>(a.scale)?;
+    }
+    for a in axes {
+        out.write_f64::<LittleEndian>(a.offset)?;
+    }
+    for a in axes {
+        out.write_f64::<LittleEndian>(a.max)?;
+        out.write_f64::<LittleEndian>(a.min)?;
+    }
+
+    for vlr in vlrs {
+        out.write_u16::<LittleEndian>(0)?; // reserved
+        write_padded(out, vlr.user_id, 16)?;
+        out.write_u16::<LittleEndian>(vlr.record_id)?;
+        out.write_u16::<LittleEndian>(vlr.data.len() as u16)?;
+        write_padded(out, vlr.description, 32)?;
+        out.write_all(&vlr.data)?;
+    }
+    Ok(())
+}
+
+// a point in format 0, with coordinates scaled and offset per axis
+fn point_record(p: &[f64; 3], axes: &[Axis; 3])
+  -> [u8; POINT_RECORD_LEN as usize] {
+    let mut record = [0; POINT_RECORD_LEN as usize];
+    for (c, a) in axes.iter().enumerate() {
+        let v = ((p[c] - a.offset) / a.scale).round() as i32;
+        record[4 * c..4 * c + 4].copy_from_slice(&v.to_le_bytes());
+    }
+    // intensity (bytes 12-13) is zero
+    record[14] = SINGLE_RETURN;
+    // classification (never classified), scan angle, user data, and point
+    // source ID are zero
+    record
+}
+
+/* the nodes of a rectangular grid, or the vertices of a triangular grid
+ * (each once, though they're shared between triangles), skipping missing
+ * values */
+fn las_points(grid: &Grid) -> Vec<[f64; 3]> {
+    let mut seen = HashSet::new();
+    grid.points()
+      .filter(|(_, _, z)| !z.is_nan())
+      .map(|(x, y, z)| [x, y, z])
+      .filter(|p| match grid.data {
+          GridData::Rectangular(_) => true,
+          GridData::Triangular(_) =>
+              seen.insert(p.map(|c| (c + 0.0).to_bits())),
+      })
+      .collect()
+}
+
+// the coordinate system, as a GeoTIFF key directory
+fn geo_key_vlr(grid: &Grid) -> Vlr {
+    Vlr {
+        user_id: "LASF_Projection",
+        record_id: GEO_KEY_DIRECTORY,
+        description: "GeoKeyDirectoryTag",
+        data: geo_key_directory(grid).iter()
+          .flat_map(|k| k.to_le_bytes())
+          .collect(),
+    }
+}
+
+fn geo_key_directory(grid: &Grid) -> Vec<u16> {
+    let linear_units = |uom| match uom {
+        UnitOfMeasure::Feet => LINEAR_FOOT_US_SURVEY,
+        UnitOfMeasure::Meters => LINEAR_METER,
+        UnitOfMeasure::Other(_) => USER_DEFINED,
+    };
+    let projected_cs_type = grid.epsg()
+      .and_then(|code| u16::try_from(code).ok())
+      .unwrap_or(USER_DEFINED);
+    let keys = [
+        [GT_MODEL_TYPE, 0, 1, MODEL_TYPE_PROJECTED],
+        [PROJECTED_CS_TYPE, 0, 1, projected_cs_type],
+        [PROJ_LINEAR_UNITS, 0, 1, linear_units(grid.xyunits)],
+        [VERTICAL_UNITS, 0, 1, linear_units(grid.zunits)],
+    ];
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    directory.extend(keys.iter().flatten());
+    directory
+}
+
+// write a NUL-padded (and if need be, truncated) fixed-length string
+fn write_padded<W: Write>(out: &mut W, s: &str, len: usize)
+  -> Result<(), Error> {
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.resize(len, 0);
+    out.write_all(&bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_has_counts_scales_and_bounds() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_las(&grid, &mut buf).expect("write LAS point cloud");
+
+        assert_eq!(&buf[..4], b"LASF");
+        assert_eq!(&buf[24..26], &[1, 2]);
+        let mut header = &buf[94..];
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(), HEADER_SIZE);
+        let point_offset = header.read_u32::<LittleEndian>().unwrap();
+        assert_eq!(header.read_u32::<LittleEndian>().unwrap(), 1);
+        assert_eq!(header.read_u8().unwrap(), POINT_FORMAT);
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(),
+          POINT_RECORD_LEN);
+        assert_eq!(header.read_u32::<LittleEndian>().unwrap(), 12);
+        assert_eq!(header.read_u32::<LittleEndian>().unwrap(), 12);
+        // skip the counts by return
+        let mut header = &header[16..];
+        let mut fields = [0.0; 12];
+        header.read_f64_into::<LittleEndian>(&mut fields).unwrap();
+        assert_eq!(fields, [
+            FINEST_SCALE, FINEST_SCALE, FINEST_SCALE,
+            1000.0, 2000.0, 1260.0,
+            1075.0, 1000.0, 2100.0, 2000.0, 1328.75, 1260.0,
+        ]);
+        assert_eq!(buf.len(),
+          point_offset as usize + 12 * POINT_RECORD_LEN as usize);
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn laz_points_decompress_to_the_las_points() {
+        use std::io::Cursor;
+
+        use laz::{LasZipDecompressor, LazVlr};
+
+        let grid = small_plane();
+        let mut las = Vec::new();
+        write_las(&grid, &mut las).expect("write LAS point cloud");
+        let mut laz = Vec::new();
+        write_laz(&grid, &mut laz).expect("write LAZ point cloud");
+
+        // the headers differ only in the point format, VLRs, and offset
+        assert_eq!(laz[..96], las[..96]);
+        assert_eq!(laz[100], 2);
+        assert_eq!(laz[104], POINT_FORMAT | LAZ_POINT_FORMAT_FLAG);
+        assert_eq!(laz[105..HEADER_SIZE as usize],
+          las[105..HEADER_SIZE as usize]);
+
+        let las_offset = (&las[96..]).read_u32::<LittleEndian>().unwrap();
+        let laz_offset = (&laz[96..]).read_u32::<LittleEndian>().unwrap();
+        // the LASzip VLR follows the GeoKey directory
+        let vlr = &laz[las_offset as usize..laz_offset as usize];
+        assert_eq!(&vlr[2..16], LazVlr::USER_ID.as_bytes());
+        assert_eq!((&vlr[18..]).read_u16::<LittleEndian>().unwrap(),
+          LazVlr::RECORD_ID);
+        let laz_vlr = LazVlr::from_buffer(&vlr[VLR_HEADER_SIZE as usize..])
+          .expect("parse LASzip VLR");
+
+        let mut source = Cursor::new(&laz[..]);
+        source.set_position(laz_offset as u64);
+        let mut decompressor = LasZipDecompressor::new(source, laz_vlr)
+          .expect("start decompressing");
+        let mut points = vec![0; 12 * POINT_RECORD_LEN as usize];
+        decompressor.decompress_many(&mut points).expect("decompress points");
+        assert_eq!(points, las[las_offset as usize..]);
+    }
+}
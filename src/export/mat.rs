@@ -0,0 +1,257 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! MATLAB Level 5 MAT-files, for scripts which `load` grids directly
+//!
+//! the file holds four variables: `z`, the grid's values as a rows ×
+//! columns matrix (rows south to north, columns west to east, so that
+//! `imagesc(x, y, z); axis xy` draws the grid north-up), with missing values
+//! as NaN; `x`, a row vector of the columns' *x* coordinates; `y`, a column
+//! vector of the rows' *y* coordinates; and `metadata`, a struct of the
+//! grid's header fields (name, source data, projection, datum, units,
+//! extents, and so on), its coordinate system, and its EPSG code (empty if
+//! we don't know it; see [Grid::epsg]). triangular grids are rasterized
+//! first (see [Grid::rasterize])
+//!
+//! data are uncompressed, which every version of MATLAB (and SciPy's
+//! `loadmat`, and GNU Octave) can read; the format limits each variable to
+//! 2 GiB
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, GridData, export::{Exporter, crs_description}};
+
+// data types
+const MI_INT8: u32 = 1;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+
+// array classes
+const MX_STRUCT_CLASS: u32 = 2;
+const MX_CHAR_CLASS: u32 = 4;
+const MX_DOUBLE_CLASS: u32 = 6;
+
+// the length of struct field names, including a null terminator
+const FIELD_NAME_LEN: usize = 32;
+
+/// write a grid as a MATLAB Level 5 MAT-file
+pub fn write_mat<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+
+    // MATLAB arrays are column-major
+    let z = Value::Double {
+        dims: (rows, cols),
+        data: arr.t().iter().copied().collect(),
+    };
+    let x = Value::Double {
+        dims: (1, cols),
+        data: (0..cols).map(|i| grid.xmin + i as f64 * grid.xstep).collect(),
+    };
+    let y = Value::Double {
+        dims: (rows, 1),
+        data: (0..rows).map(|j| grid.ymin + j as f64 * grid.ystep).collect(),
+    };
+
+    let epsg = match grid.epsg() {
+        Some(code) => Value::scalar(code as f64),
+        None => Value::Double { dims: (0, 0), data: Vec::new() },
+    };
+    let metadata = Value::Struct(vec![
+        ("name", Value::Text(grid.name.clone())),
+        ("source_data", Value::Text(grid.source_data.clone())),
+        ("projection", Value::Text(grid.projection.clone())),
+        ("datum", Value::Text(grid.datum.clone())),
+        ("crs", Value::Text(crs_description(grid))),
+        ("epsg", epsg),
+        ("created_date", Value::Text(grid.created_date.to_string())),
+        ("xy_units", Value::Text(format!("{:?}", grid.xyunits))),
+        ("z_units", Value::Text(format!("{:?}", grid.zunits))),
+        ("rows", Value::scalar(grid.rows as f64)),
+        ("columns", Value::scalar(grid.columns as f64)),
+        ("xmin", Value::scalar(grid.xmin)),
+        ("xmax", Value::scalar(grid.xmax)),
+        ("ymin", Value::scalar(grid.ymin)),
+        ("ymax", Value::scalar(grid.ymax)),
+        ("xstep", Value::scalar(grid.xstep)),
+        ("ystep", Value::scalar(grid.ystep)),
+        ("zmin", Value::scalar(grid.zmin)),
+        ("zmax", Value::scalar(grid.zmax)),
+    ]);
+
+    let mut out = BufWriter::new(out);
+    out.write_all(&header())?;
+    for (name, value) in [("z", z), ("x", x), ("y", y),
+      ("metadata", metadata)] {
+        out.write_all(&matrix(name, &value)?)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// MATLAB MAT-files, as an [Exporter]; see [write_mat]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Mat;
+
+impl Exporter for Mat {
+    fn name(&self) -> &str {
+        "mat"
+    }
+
+    fn extension(&self) -> &str {
+        "mat"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_mat(grid, out)
+    }
+}
+
+enum Value {
+    // column-major, with (rows, columns)
+    Double { dims: (usize, usize), data: Vec<f64> },
+    Text(String),
+    Struct(Vec<(&'static str, Value)>),
+}
+
+impl Value {
+    fn scalar(v: f64) -> Value {
+        Value::Double { dims: (1, 1), data: vec![v] }
+    }
+}
+
+// descriptive text, no subsystem data, version 0x0100, little-endian
+fn header() -> Vec<u8> {
+    let mut h = format!("MATLAB 5.0 MAT-file, written by petra_grid {}",
+      env!("CARGO_PKG_VERSION")).into_bytes();
+    h.resize(116, b' ');
+    h.extend_from_slice(&[0; 8]);
+    h.extend_from_slice(&0x0100u16.to_le_bytes());
+    h.extend_from_slice(b"IM");
+    h
+}
+
+// a tagged data element, padded to a multiple of 8 bytes
+fn element(out: &mut Vec<u8>, kind: u32, data: &[u8]) -> Result<(), Error> {
+    let len = u32::try_from(data.len()).ok()
+      .filter(|&len| len <= i32::MAX as u32)
+      .ok_or_else(|| Error::InvalidArgument(
+        "grid is too large for a MAT-file".to_string()))?;
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(data);
+    out.resize(out.len() + (8 - data.len() % 8) % 8, 0);
+    Ok(())
+}
+
+fn matrix(name: &str, value: &Value) -> Result<Vec<u8>, Error> {
+    let utf16 = match value {
+        Value::Text(s) => s.encode_utf16().collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let (class, dims) = match value {
+        Value::Double { dims, .. } => (MX_DOUBLE_CLASS, *dims),
+        Value::Text(_) if utf16.is_empty() => (MX_CHAR_CLASS, (0, 0)),
+        Value::Text(_) => (MX_CHAR_CLASS, (1, utf16.len())),
+        Value::Struct(_) => (MX_STRUCT_CLASS, (1, 1)),
+    };
+
+    let mut body = Vec::new();
+    element(&mut body, MI_UINT32, &[class.to_le_bytes(), [0; 4]].concat())?;
+    let dims = [dims.0, dims.1].map(|d| (d as i32).to_le_bytes()).concat();
+    element(&mut body, MI_INT32, &dims)?;
+    element(&mut body, MI_INT8, name.as_bytes())?;
+    match value {
+        Value::Double { data, .. } => {
+            let bytes = data.iter().flat_map(|v| v.to_le_bytes())
+              .collect::<Vec<_>>();
+            element(&mut body, MI_DOUBLE, &bytes)?;
+        },
+        Value::Text(_) => {
+            let bytes = utf16.iter().flat_map(|c| c.to_le_bytes())
+              .collect::<Vec<_>>();
+            element(&mut body, MI_UINT16, &bytes)?;
+        },
+        Value::Struct(fields) => {
+            // the field name length, as a "small" element
+            body.extend_from_slice(&(4 << 16 | MI_INT32).to_le_bytes());
+            body.extend_from_slice(&(FIELD_NAME_LEN as i32).to_le_bytes());
+            let mut names = Vec::with_capacity(fields.len() * FIELD_NAME_LEN);
+            for (field, _) in fields {
+                names.extend_from_slice(field.as_bytes());
+                names.resize(names.len() + FIELD_NAME_LEN - field.len(), 0);
+            }
+            element(&mut body, MI_INT8, &names)?;
+            for (_, field) in fields {
+                body.extend_from_slice(&matrix("", field)?);
+            }
+        },
+    }
+
+    let mut out = Vec::with_capacity(body.len() + 8);
+    element(&mut out, MI_MATRIX, &body)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_and_first_variable_are_z() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_mat(&grid, &mut buf).expect("write MAT-file");
+
+        assert!(buf.starts_with(b"MATLAB 5.0 MAT-file"));
+        assert_eq!(&buf[124..128], &[0x00, 0x01, b'I', b'M']);
+
+        let mut z = &buf[128..];
+        let mut tag = || (z.read_u32::<LittleEndian>().unwrap(),
+          z.read_u32::<LittleEndian>().unwrap());
+        assert_eq!(tag().0, MI_MATRIX);
+        assert_eq!(tag(), (MI_UINT32, 8));
+        assert_eq!(tag(), (MX_DOUBLE_CLASS, 0));
+        assert_eq!(tag(), (MI_INT32, 8));
+        assert_eq!(tag(), (3, 4));
+        assert_eq!(tag(), (MI_INT8, 1));
+        assert_eq!(tag(), (u32::from_le_bytes(*b"z\0\0\0"), 0));
+        assert_eq!(tag(), (MI_DOUBLE, 12 * 8));
+        let mut first_column = [0.0; 3];
+        z.read_f64_into::<LittleEndian>(&mut first_column).unwrap();
+        assert_eq!(first_column, [1260.0, 1285.0, 1310.0]);
+    }
+}
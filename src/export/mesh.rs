@@ -0,0 +1,92 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! indexed triangle meshes, for mesh export formats
+
+use std::collections::HashMap;
+
+use crate::{Grid, GridData};
+
+/* a triangle mesh with shared vertices; triangles with any missing vertex
+ * are omitted, since mesh formats have no way to represent them */
+pub(crate) struct Mesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub faces: Vec<[usize; 3]>,
+    // for each face, the index of the triangle it came from (counting those
+    // omitted, and for rectangular grids, two per cell in row-major order)
+    pub sources: Vec<usize>,
+}
+
+impl Mesh {
+    /* the mesh of a triangular grid, or of a rectangular grid with each
+     * cell split (southwest to northeast) into two counterclockwise
+     * triangles */
+    pub fn from_grid(grid: &Grid) -> Mesh {
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            sources: Vec::new(),
+        };
+        let mut index = HashMap::new();
+        match &grid.data {
+            GridData::Triangular(arr) => {
+                for (n, tri) in arr.outer_iter().enumerate() {
+                    let verts = [0, 1, 2].map(
+                      |v| [tri[(v, 0)], tri[(v, 1)], tri[(v, 2)]]);
+                    mesh.push(&mut index, n, verts);
+                }
+            },
+            GridData::Rectangular(arr) => {
+                let (rows, cols) = arr.dim();
+                let node = |j: usize, i: usize| {
+                    let (x, y) = grid.node_xy(j, i);
+                    [x, y, arr[(j, i)]]
+                };
+                for j in 0..rows.saturating_sub(1) {
+                    for i in 0..cols.saturating_sub(1) {
+                        let n = 2 * (j * (cols - 1) + i);
+                        mesh.push(&mut index, n,
+                          [node(j, i), node(j, i + 1), node(j + 1, i + 1)]);
+                        mesh.push(&mut index, n + 1,
+                          [node(j, i), node(j + 1, i + 1), node(j + 1, i)]);
+                    }
+                }
+            },
+        }
+        mesh
+    }
+
+    fn push(&mut self, index: &mut HashMap<[u64; 3], usize>, source: usize,
+      verts: [[f64; 3]; 3]) {
+        if verts.iter().flatten().any(|c| c.is_nan()) {
+            return;
+        }
+        let face = verts.map(|v| {
+            let key = v.map(|c| (c + 0.0).to_bits());
+            *index.entry(key).or_insert_with(|| {
+                self.vertices.push(v);
+                self.vertices.len() - 1
+            })
+        });
+        self.faces.push(face);
+        self.sources.push(source);
+    }
+}
@@ -0,0 +1,208 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! exporters writing grids in other formats
+//!
+//! each exporter writes to any [std::io::Write] implementor (output is
+//! buffered internally, so there's no need to wrap files in a
+//! [std::io::BufWriter])
+//!
+//! formats are also available as [Exporter]s, which can be looked up by name
+//! (see [exporter]) and combined into a [Pipeline] to write several formats
+//! at once
+//!
+//! raster formats are always written in the order of rows and columns
+//! their specifications require; only plain-text points, which carry their
+//! own coordinates, can be written in another order (see
+//! [xyz::TextOptions::axis_order])
+
+use std::io::Write;
+#[cfg(feature = "geotiff")]
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Grid};
+
+pub mod cps3;
+pub mod dxf;
+pub mod earthvision;
+pub mod esri_ascii;
+pub mod geojson;
+#[cfg(feature = "geopackage")]
+pub mod geopackage;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+#[cfg(feature = "geotiff")]
+pub mod geotiff;
+pub mod grd;
+pub mod gslib;
+#[cfg(feature = "hdf5")]
+pub mod hdf5;
+pub mod irap;
+pub mod las;
+pub mod mat;
+#[cfg(feature = "netcdf")]
+pub mod netcdf;
+pub(crate) mod mesh;
+pub mod obj;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod postgis;
+mod pipeline;
+pub use pipeline::Pipeline;
+#[cfg(feature = "shapefile")]
+pub mod shapefile;
+pub mod surfer;
+pub mod triangles;
+pub mod tsurf;
+pub mod vtk;
+pub mod xyz;
+#[cfg(feature = "zarr")]
+pub mod zarr;
+pub mod zmap;
+
+/// a format which grids can be exported to
+pub trait Exporter: Send + Sync {
+    /// the short lowercase name of the format, as accepted by [exporter]
+    fn name(&self) -> &str;
+
+    /// the usual file extension (without a leading `.`) for the format
+    fn extension(&self) -> &str;
+
+    /// write a grid in this format
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error>;
+}
+
+/// the names of all available export formats
+pub const FORMATS: &[&str] = &[
+    #[cfg(feature = "geotiff")]
+    "cog",
+    "cps3",
+    "csv",
+    "dxf",
+    "dxf-contours",
+    "earthvision",
+    "esri-ascii",
+    "geojson",
+    #[cfg(feature = "geopackage")]
+    "geopackage",
+    #[cfg(feature = "gltf")]
+    "glb",
+    #[cfg(feature = "geotiff")]
+    "geotiff",
+    "grd",
+    "gslib",
+    #[cfg(feature = "hdf5")]
+    "hdf5",
+    "irap-binary",
+    "las",
+    #[cfg(feature = "laz")]
+    "laz",
+    "mat",
+    #[cfg(feature = "netcdf")]
+    "netcdf",
+    "obj",
+    #[cfg(feature = "parquet")]
+    "parquet",
+    "postgis",
+    "surfer6",
+    "surfer7",
+    "triangles-csv",
+    "triangles-geojson",
+    "tsurf",
+    "vtk",
+    "xyz",
+    "zmap",
+];
+
+/// look up an exporter by its name (ignoring case); see [FORMATS]
+pub fn exporter(name: &str) -> Option<Box<dyn Exporter>> {
+    match name.to_ascii_lowercase().as_str() {
+        #[cfg(feature = "geotiff")]
+        "cog" => Some(Box::new(geotiff::Cog)),
+        "cps3" => Some(Box::new(cps3::Cps3)),
+        "csv" => Some(Box::new(xyz::Csv)),
+        "dxf" => Some(Box::new(dxf::Dxf)),
+        "dxf-contours" => Some(Box::new(dxf::DxfContours::default())),
+        "earthvision" => Some(Box::new(earthvision::EarthVision)),
+        "esri-ascii" => Some(Box::new(esri_ascii::EsriAscii)),
+        "geojson" => Some(Box::new(geojson::GeoJson)),
+        #[cfg(feature = "geopackage")]
+        "geopackage" => Some(Box::new(geopackage::GeoPackage)),
+        #[cfg(feature = "gltf")]
+        "glb" => Some(Box::new(gltf::Glb)),
+        #[cfg(feature = "geotiff")]
+        "geotiff" => Some(Box::new(geotiff::GeoTiff)),
+        "grd" => Some(Box::new(grd::Grd)),
+        "gslib" => Some(Box::new(gslib::Gslib)),
+        #[cfg(feature = "hdf5")]
+        "hdf5" => Some(Box::new(hdf5::Hdf5)),
+        "irap-binary" => Some(Box::new(irap::IrapBinary)),
+        "las" => Some(Box::new(las::Las)),
+        #[cfg(feature = "laz")]
+        "laz" => Some(Box::new(las::Laz)),
+        "mat" => Some(Box::new(mat::Mat)),
+        #[cfg(feature = "netcdf")]
+        "netcdf" => Some(Box::new(netcdf::NetCdf)),
+        "obj" => Some(Box::new(obj::Obj)),
+        #[cfg(feature = "parquet")]
+        "parquet" => Some(Box::new(parquet::Parquet)),
+        "postgis" => Some(Box::new(postgis::Postgis)),
+        "surfer6" => Some(Box::new(surfer::Surfer6)),
+        "surfer7" => Some(Box::new(surfer::Surfer7)),
+        "triangles-csv" => Some(Box::new(triangles::TriangleCsv)),
+        "triangles-geojson" => Some(Box::new(triangles::TriangleGeoJson)),
+        "tsurf" => Some(Box::new(tsurf::TSurf)),
+        "vtk" => Some(Box::new(vtk::Vtk)),
+        "xyz" => Some(Box::new(xyz::Xyz)),
+        "zmap" => Some(Box::new(zmap::Zmap)),
+        _ => None,
+    }
+}
+
+/// export a grid as both a mesh (in the given format, e.g. [obj::Obj]) and a
+/// GeoTIFF raster (rasterizing triangular grids), to `base` with each
+/// format's extension, since consumers differ in which they can load
+///
+/// both outputs describe the same coordinate system, taken from the grid's
+/// metadata; see [Pipeline::run] for the results
+#[cfg(feature = "geotiff")]
+pub fn write_mesh_and_raster<E, P>(grid: &Grid, base: P, mesh: E)
+  -> Vec<(PathBuf, Result<(), Error>)>
+  where E: Exporter + 'static,
+        P: AsRef<Path>,
+{
+    Pipeline::new()
+      .with(mesh)
+      .with(geotiff::GeoTiff)
+      .run(grid, base)
+}
+
+// a one-line description of a grid's coordinate system, for export metadata
+pub(crate) fn crs_description(grid: &Grid) -> String {
+    let name = |s: &str| if s.is_empty() {
+        "unknown".to_string()
+    } else {
+        s.to_string()
+    };
+    format!("projection {} (Petra code {}), datum {}, xy units {:?}, \
+      z units {:?}", name(&grid.projection), grid.projection_code,
+      name(&grid.datum), grid.xyunits, grid.zunits)
+}
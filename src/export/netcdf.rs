@@ -0,0 +1,334 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! netCDF files following the CF conventions, for xarray, Panoply, and
+//! friends
+//!
+//! files are written in the "classic" (64-bit offset) format, which needs no
+//! external library. the grid's *z* values become a 2D variable `z(y, x)`
+//! with 1D coordinate variables `x` and `y` (increasing from the grid's
+//! minimum bounds) and a `crs` container variable describing the grid's
+//! projection (and EPSG code, where known; see [Grid::epsg]). units come
+//! from the grid's units of measure (Petra's feet are taken to be US survey
+//! feet); missing values are written as [PETRA_NULL], which is declared as
+//! the `_FillValue`. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+//!
+//! derived grids which say what they measure (see [Grid::quantity]) have
+//! that quantity's name and units as the `long_name` and `units` of `z`
+//!
+//! grids can also be bundled with derived attributes (see
+//! [SurfaceAttribute]), each as another 2D variable
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    PETRA_NULL,
+    SurfaceAttribute,
+    export::{Exporter, crs_description},
+};
+
+// tags and types of the classic format
+const NC_DIMENSION: u32 = 0x0a;
+const NC_VARIABLE: u32 = 0x0b;
+const NC_ATTRIBUTE: u32 = 0x0c;
+const NC_CHAR: u32 = 2;
+const NC_INT: u32 = 4;
+const NC_DOUBLE: u32 = 6;
+
+/// write a grid as a CF-conventions netCDF file
+pub fn write_netcdf<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_netcdf_attribute_stack(grid, &[], out)
+}
+
+/// write a grid and the given derived attributes of its surface as a
+/// CF-conventions netCDF file, with each attribute in a variable named for
+/// it (see [SurfaceAttribute::name]) alongside `z`
+pub fn write_netcdf_attribute_stack<W: Write + ?Sized>(grid: &Grid,
+  attributes: &[SurfaceAttribute], out: &mut W) -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let xy_units = grid.xyunits.udunits();
+
+    let mut crs_attrs = vec![
+        ("long_name", Attr::Text(crs_description(grid))),
+    ];
+    if let Some(code) = grid.epsg() {
+        crs_attrs.push(("epsg_code", Attr::Text(format!("EPSG:{}", code))));
+    }
+
+    let coordinate = |name: &'static str, axis: &str, dim: u32, min: f64,
+      step: f64, n: usize| {
+        let mut attrs = vec![
+            ("standard_name",
+              Attr::Text(format!("projection_{}_coordinate", name))),
+            ("axis", Attr::Text(axis.to_string())),
+        ];
+        if let Some(units) = xy_units {
+            attrs.push(("units", Attr::Text(units.to_string())));
+        }
+        let values = (0..n).map(|k| min + k as f64 * step).collect();
+        Variable {
+            name,
+            dims: vec![dim],
+            attrs,
+            data: Values::Doubles(values),
+        }
+    };
+
+    let surface = |name: &'static str, long_name: String, units: Option<String>,
+      values: Vec<f64>| {
+        let mut attrs = vec![("long_name", Attr::Text(long_name))];
+        if let Some(units) = units {
+            attrs.push(("units", Attr::Text(units)));
+        }
+        attrs.push(("_FillValue", Attr::Double(PETRA_NULL)));
+        attrs.push(("grid_mapping", Attr::Text("crs".to_string())));
+        let values = values.into_iter()
+          .map(|v| if v.is_nan() { PETRA_NULL } else { v })
+          .collect();
+        Variable {
+            name,
+            dims: vec![0, 1],
+            attrs,
+            data: Values::Doubles(values),
+        }
+    };
+
+    let mut variables = vec![
+        coordinate("x", "X", 1, grid.xmin, grid.xstep, cols),
+        coordinate("y", "Y", 0, grid.ymin, grid.ystep, rows),
+        Variable {
+            name: "crs",
+            dims: vec![],
+            attrs: crs_attrs,
+            data: Values::Int(0),
+        },
+        match &grid.quantity {
+            Some(quantity) => surface("z", quantity.name.clone(),
+              Some(quantity.units.clone()), arr.iter().copied().collect()),
+            None => surface("z", grid.name.trim().to_string(),
+              grid.zunits.udunits().map(str::to_string),
+              arr.iter().copied().collect()),
+        },
+    ];
+    for &attribute in attributes {
+        let values = grid.attribute(attribute)?;
+        let units = match attribute {
+            SurfaceAttribute::Slope | SurfaceAttribute::Aspect =>
+                Some("degree".to_string()),
+            SurfaceAttribute::Hillshade { .. } => Some("1".to_string()),
+            SurfaceAttribute::Curvature => xy_units.map(|u| format!("{}-1", u)),
+        };
+        variables.push(surface(attribute.name(),
+          attribute.name().to_string(), units,
+          values.iter().copied().collect()));
+    }
+
+    let globals = [
+        ("Conventions", Attr::Text("CF-1.8".to_string())),
+        ("title", Attr::Text(grid.name.trim().to_string())),
+        ("source", Attr::Text(format!("Petra grid (source data: {})",
+          grid.source_data.trim()))),
+    ];
+    let dims = [("y", rows as u32), ("x", cols as u32)];
+
+    /* the header records where each variable's data begins, so we build it
+     * once to learn its length (which doesn't depend on the offsets), then
+     * again for real */
+    let mut begins = vec![0; variables.len()];
+    let mut offset = header(&dims, &globals, &variables, &begins).len() as u64;
+    for (begin, var) in begins.iter_mut().zip(&variables) {
+        *begin = offset;
+        offset += var.data.size() as u64;
+    }
+
+    let mut out = BufWriter::new(out);
+    out.write_all(&header(&dims, &globals, &variables, &begins))?;
+    for var in &variables {
+        match &var.data {
+            Values::Int(v) => out.write_all(&v.to_be_bytes())?,
+            Values::Doubles(vs) => for v in vs {
+                out.write_all(&v.to_be_bytes())?;
+            },
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// netCDF, as an [Exporter]; see [write_netcdf]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NetCdf;
+
+impl Exporter for NetCdf {
+    fn name(&self) -> &str {
+        "netcdf"
+    }
+
+    fn extension(&self) -> &str {
+        "nc"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_netcdf(grid, out)
+    }
+}
+
+enum Attr {
+    Text(String),
+    Double(f64),
+}
+
+enum Values {
+    Int(i32),
+    Doubles(Vec<f64>),
+}
+
+impl Values {
+    // the size of the data, in bytes (always a multiple of 4)
+    fn size(&self) -> usize {
+        match self {
+            Values::Int(_) => 4,
+            Values::Doubles(vs) => vs.len() * 8,
+        }
+    }
+}
+
+// a variable, with its dimensions as indices into the dimension list
+struct Variable {
+    name: &'static str,
+    dims: Vec<u32>,
+    attrs: Vec<(&'static str, Attr)>,
+    data: Values,
+}
+
+fn header(dims: &[(&str, u32)], globals: &[(&str, Attr)],
+  variables: &[Variable], begins: &[u64]) -> Vec<u8> {
+    let mut h = b"CDF\x02".to_vec();
+    put_u32(&mut h, 0); // no records
+
+    put_u32(&mut h, NC_DIMENSION);
+    put_u32(&mut h, dims.len() as u32);
+    for (name, len) in dims {
+        put_name(&mut h, name);
+        put_u32(&mut h, *len);
+    }
+
+    put_attrs(&mut h, globals);
+
+    put_u32(&mut h, NC_VARIABLE);
+    put_u32(&mut h, variables.len() as u32);
+    for (var, &begin) in variables.iter().zip(begins) {
+        put_name(&mut h, var.name);
+        put_u32(&mut h, var.dims.len() as u32);
+        for &dim in &var.dims {
+            put_u32(&mut h, dim);
+        }
+        put_attrs(&mut h, &var.attrs);
+        put_u32(&mut h, match var.data {
+            Values::Int(_) => NC_INT,
+            Values::Doubles(_) => NC_DOUBLE,
+        });
+        put_u32(&mut h, var.data.size().try_into().unwrap_or(u32::MAX));
+        h.extend_from_slice(&begin.to_be_bytes());
+    }
+    h
+}
+
+fn put_attrs(h: &mut Vec<u8>, attrs: &[(&str, Attr)]) {
+    if attrs.is_empty() {
+        // "absent"
+        put_u32(h, 0);
+        put_u32(h, 0);
+        return;
+    }
+    put_u32(h, NC_ATTRIBUTE);
+    put_u32(h, attrs.len() as u32);
+    for (name, value) in attrs {
+        put_name(h, name);
+        match value {
+            Attr::Text(text) => {
+                put_u32(h, NC_CHAR);
+                put_name(h, text);
+            },
+            Attr::Double(v) => {
+                put_u32(h, NC_DOUBLE);
+                put_u32(h, 1);
+                h.extend_from_slice(&v.to_be_bytes());
+            },
+        }
+    }
+}
+
+fn put_u32(h: &mut Vec<u8>, v: u32) {
+    h.extend_from_slice(&v.to_be_bytes());
+}
+
+// a count followed by bytes, zero-padded to a multiple of 4
+fn put_name(h: &mut Vec<u8>, s: &str) {
+    put_u32(h, s.len() as u32);
+    h.extend_from_slice(s.as_bytes());
+    h.resize(h.len() + (4 - s.len() % 4) % 4, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn header_declares_rows_and_columns() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_netcdf(&grid, &mut buf).expect("write netCDF file");
+
+        assert_eq!(&buf[..4], b"CDF\x02");
+        let mut header = &buf[4..];
+        let mut word = || header.read_u32::<BigEndian>().unwrap();
+        assert_eq!(word(), 0);
+        assert_eq!(word(), NC_DIMENSION);
+        assert_eq!(word(), 2);
+        assert_eq!((word(), word(), word()),
+          (1, u32::from_be_bytes(*b"y\0\0\0"), 3));
+        assert_eq!((word(), word(), word()),
+          (1, u32::from_be_bytes(*b"x\0\0\0"), 4));
+
+        // z is the last variable, south to north
+        let mut z = &buf[buf.len() - 12 * 8..];
+        assert_eq!(z.read_f64::<BigEndian>().unwrap(), 1260.0);
+        assert_eq!(z.read_f64::<BigEndian>().unwrap(), 1266.25);
+    }
+}
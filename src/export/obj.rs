@@ -0,0 +1,78 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Wavefront OBJ meshes
+//!
+//! triangular grids are written as-is (with shared vertices merged), and
+//! rectangular grids are split into two triangles per cell; triangles with
+//! missing vertices are omitted. OBJ has no notion of a coordinate system,
+//! so the grid's projection and units are recorded in a comment
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, export::{Exporter, crs_description, mesh::Mesh}};
+
+/// write a grid as a Wavefront OBJ triangle mesh
+pub fn write_obj<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mesh = Mesh::from_grid(grid);
+    let mut out = BufWriter::new(out);
+    writeln!(out, "# {}", grid.name)?;
+    writeln!(out, "# {}", crs_description(grid))?;
+    writeln!(out, "o {}", object_name(&grid.name))?;
+    for [x, y, z] in &mesh.vertices {
+        writeln!(out, "v {} {} {}", x, y, z)?;
+    }
+    // OBJ indices are 1-based
+    for [a, b, c] in &mesh.faces {
+        writeln!(out, "f {} {} {}", a + 1, b + 1, c + 1)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Wavefront OBJ, as an [Exporter]; see [write_obj]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Obj;
+
+impl Exporter for Obj {
+    fn name(&self) -> &str {
+        "obj"
+    }
+
+    fn extension(&self) -> &str {
+        "obj"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_obj(grid, out)
+    }
+}
+
+// OBJ object names can't contain whitespace
+fn object_name(name: &str) -> String {
+    let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+    if name.is_empty() {
+        "grid".to_string()
+    } else {
+        name
+    }
+}
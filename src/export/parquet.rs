@@ -0,0 +1,215 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Apache Parquet tables, for querying grids with DuckDB, Spark, and the like
+//!
+//! grids are written in "long" form, with one row per node of a rectangular
+//! grid (south to north, then west to east) or per vertex of each triangle
+//! of a triangular grid: `x`, `y`, and `z` (null where missing) columns,
+//! alongside columns repeating the grid's name, projection, datum, units,
+//! and EPSG code (if we know it; see [Grid::epsg]), so that tables from many
+//! grids can be queried together. these repeated columns are dictionary
+//! encoded, so they cost next to nothing. the grid's source data, creation
+//! date, and coordinate system are also recorded in the file's key-value
+//! metadata. columns are compressed with Snappy, in row groups of about a
+//! million rows
+
+use std::{io::{self, Write}, sync::Arc};
+
+use parquet::{
+    basic::Compression,
+    data_type::{ByteArray, ByteArrayType, DoubleType, Int32Type},
+    file::{
+        metadata::KeyValue,
+        properties::WriterProperties,
+        writer::SerializedFileWriter,
+    },
+    schema::parser::parse_message_type,
+};
+
+use crate::{Error, Grid, export::{Exporter, crs_description}};
+
+const SCHEMA: &str = "
+message grid {
+    required double x;
+    required double y;
+    optional double z;
+    required binary name (UTF8);
+    required binary projection (UTF8);
+    required binary datum (UTF8);
+    required binary xy_units (UTF8);
+    required binary z_units (UTF8);
+    optional int32 epsg;
+}
+";
+
+const ROW_GROUP_ROWS: usize = 1 << 20;
+
+/// write a grid's points as a Parquet table
+pub fn write_parquet<W: Write + Send + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_table(grid, out).map_err(io::Error::from)?;
+    Ok(())
+}
+
+/// Parquet, as an [Exporter]; see [write_parquet]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Parquet;
+
+impl Exporter for Parquet {
+    fn name(&self) -> &str {
+        "parquet"
+    }
+
+    fn extension(&self) -> &str {
+        "parquet"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        /* the Parquet writer needs a Send destination, which we can't
+         * promise for any old Write; the compressed table is small enough
+         * to assemble first */
+        let mut buf = Vec::new();
+        write_parquet(grid, &mut buf)?;
+        out.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+fn write_table<W: Write + Send + ?Sized>(grid: &Grid, out: &mut W)
+  -> parquet::errors::Result<()> {
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let metadata = vec![
+        KeyValue::new("petra_grid.version".to_string(),
+          env!("CARGO_PKG_VERSION").to_string()),
+        KeyValue::new("source_data".to_string(), grid.source_data.clone()),
+        KeyValue::new("created_date".to_string(),
+          grid.created_date.to_string()),
+        KeyValue::new("crs".to_string(), crs_description(grid)),
+    ];
+    let properties = WriterProperties::builder()
+      .set_compression(Compression::SNAPPY)
+      .set_key_value_metadata(Some(metadata))
+      .build();
+    let mut writer = SerializedFileWriter::new(out, schema,
+      Arc::new(properties))?;
+
+    let strings = [
+        grid.name.clone(),
+        grid.projection.clone(),
+        grid.datum.clone(),
+        format!("{:?}", grid.xyunits),
+        format!("{:?}", grid.zunits),
+    ].map(|s| ByteArray::from(s.into_bytes()));
+    let epsg = grid.epsg().and_then(|code| i32::try_from(code).ok());
+
+    let mut points = grid.points().peekable();
+    while points.peek().is_some() {
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        let mut zs = Vec::new();
+        let mut z_present = Vec::new();
+        for (x, y, z) in points.by_ref().take(ROW_GROUP_ROWS) {
+            xs.push(x);
+            ys.push(y);
+            if z.is_nan() {
+                z_present.push(0);
+            } else {
+                zs.push(z);
+                z_present.push(1);
+            }
+        }
+        let n = xs.len();
+
+        let mut row_group = writer.next_row_group()?;
+        let mut column = 0;
+        while let Some(mut col) = row_group.next_column()? {
+            match column {
+                0 | 1 => {
+                    let values = if column == 0 { &xs } else { &ys };
+                    col.typed::<DoubleType>().write_batch(values, None, None)?;
+                },
+                2 => {
+                    col.typed::<DoubleType>()
+                      .write_batch(&zs, Some(&z_present), None)?;
+                },
+                3..=7 => {
+                    let values = vec![strings[column - 3].clone(); n];
+                    col.typed::<ByteArrayType>()
+                      .write_batch(&values, None, None)?;
+                },
+                _ => {
+                    let (values, present) = match epsg {
+                        Some(code) => (vec![code; n], vec![1; n]),
+                        None => (Vec::new(), vec![0; n]),
+                    };
+                    col.typed::<Int32Type>()
+                      .write_batch(&values, Some(&present), None)?;
+                },
+            }
+            col.close()?;
+            column += 1;
+        }
+        row_group.close()?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn footer_has_schema_rows_and_metadata() {
+        let grid = small_plane();
+        let mut buf = Vec::new();
+        write_parquet(&grid, &mut buf).expect("write Parquet table");
+
+        assert_eq!(&buf[..4], b"PAR1");
+        assert_eq!(&buf[buf.len() - 4..], b"PAR1");
+
+        let path = std::env::temp_dir()
+          .join(format!("petra_grid-parquet-{}.parquet", std::process::id()));
+        fs::write(&path, &buf).expect("write scratch Parquet table");
+        let reader = SerializedFileReader::new(
+          File::open(&path).expect("open Parquet table"))
+          .expect("read Parquet footer");
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 12);
+        let columns = metadata.schema_descr().columns().iter()
+          .map(|c| c.name().to_string())
+          .collect::<Vec<_>>();
+        assert_eq!(columns, ["x", "y", "z", "name", "projection", "datum",
+          "xy_units", "z_units", "epsg"]);
+        let crs = metadata.key_value_metadata().unwrap().iter()
+          .find(|kv| kv.key == "crs")
+          .and_then(|kv| kv.value.clone());
+        assert_eq!(crs, Some(crs_description(&grid)));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,151 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! fanning one grid out to several export formats at once
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::{Error, Grid, backup_file, export::{Exporter, exporter}};
+
+/// a set of exporters, run in parallel over the same grid so that a grid
+/// read once can be delivered in several formats
+#[derive(Default)]
+pub struct Pipeline {
+    exporters: Vec<Box<dyn Exporter>>,
+    no_clobber: bool,
+    backup: bool,
+}
+
+impl Pipeline {
+    /// an empty pipeline
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// a pipeline from a comma-separated list of format names (e.g.
+    /// `"csv,xyz"`); unknown names produce [Error::InvalidArgument]
+    pub fn from_names(names: &str) -> Result<Pipeline, Error> {
+        let mut pipeline = Pipeline::new();
+        for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let exporter = exporter(name).ok_or_else(|| Error::InvalidArgument(
+              format!("unknown export format {} (available: {})",
+                name, super::FORMATS.join(", "))))?;
+            pipeline.push(exporter);
+        }
+        Ok(pipeline)
+    }
+
+    /// add an exporter to the pipeline
+    pub fn with<E: Exporter + 'static>(mut self, exporter: E) -> Pipeline {
+        self.push(Box::new(exporter));
+        self
+    }
+
+    /// refuse (or don't refuse) to overwrite existing files; refused
+    /// exports fail with an [std::io::ErrorKind::AlreadyExists] error,
+    /// leaving the existing file untouched
+    pub fn no_clobber(self, no_clobber: bool) -> Pipeline {
+        Pipeline { no_clobber, ..self }
+    }
+
+    /// copy (or don't copy) existing files to timestamped backups (see
+    /// [backup_file]) before overwriting them
+    pub fn backup(self, backup: bool) -> Pipeline {
+        Pipeline { backup, ..self }
+    }
+
+    /// add a boxed exporter to the pipeline
+    pub fn push(&mut self, exporter: Box<dyn Exporter>) {
+        self.exporters.push(exporter);
+    }
+
+    /// the exporters in the pipeline
+    pub fn exporters(&self) -> impl Iterator<Item = &dyn Exporter> {
+        self.exporters.iter().map(|e| e.as_ref())
+    }
+
+    /// the paths [Pipeline::run] would write for a given base path
+    pub fn output_paths<P: AsRef<Path>>(&self, base: P) -> Vec<PathBuf> {
+        self.exporters.iter()
+          .map(|e| base.as_ref().with_extension(e.extension()))
+          .collect()
+    }
+
+    /// export a grid with every exporter in parallel, writing each to `base`
+    /// with its extension replaced by the exporter's (so `out/top.grd` would
+    /// produce `out/top.csv`, `out/top.xyz`, ...)
+    ///
+    /// each output path is returned with the result of its export, in the
+    /// order the exporters were added; one failed export doesn't prevent the
+    /// others. existing files are overwritten, unless the pipeline was told
+    /// otherwise (see [Pipeline::no_clobber] and [Pipeline::backup]). an
+    /// export to the same path as an earlier one fails with
+    /// [Error::InvalidArgument], rather than racing it to write the file
+    pub fn run<P: AsRef<Path>>(&self, grid: &Grid, base: P)
+      -> Vec<(PathBuf, Result<(), Error>)> {
+        let base = base.as_ref();
+        let mut seen = HashSet::new();
+        thread::scope(|s| {
+            let handles = self.exporters.iter().map(|e| {
+                let path = base.with_extension(e.extension());
+                if !seen.insert(path.clone()) {
+                    return (path, None);
+                }
+                let handle = {
+                    let path = path.clone();
+                    let no_clobber = self.no_clobber;
+                    let backup = self.backup && !no_clobber;
+                    s.spawn(move || {
+                        if backup {
+                            backup_file(&path)?;
+                        }
+                        let mut f = File::options()
+                          .write(true)
+                          .create(true)
+                          .truncate(true)
+                          .create_new(no_clobber)
+                          .open(path)?;
+                        e.export(grid, &mut f)
+                    })
+                };
+                (path, Some(handle))
+            }).collect::<Vec<_>>();
+
+            handles.into_iter().map(|(path, handle)| {
+                /* an exporter panic is a bug, which we'll pass along rather
+                 * than report as an error */
+                let result = match handle {
+                    Some(handle) => handle.join()
+                      .unwrap_or_else(|p| std::panic::resume_unwind(p)),
+                    None => Err(Error::InvalidArgument(format!(
+                      "{} is already being written by another exporter",
+                      path.display()))),
+                };
+                (path, result)
+            }).collect()
+        })
+    }
+}
@@ -0,0 +1,345 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! SQL scripts loading grids into PostGIS rasters, as `raster2pgsql` writes
+//!
+//! the script creates a table with a `rid` serial key and a `rast` raster
+//! column, and inserts the grid as one or more rasters (in tiles, if asked,
+//! from the north-west corner row by row; tiles at the east and south edges
+//! are smaller, rather than padded) given as hex-encoded WKB, all in one
+//! transaction. each raster has a single band of 64-bit floats, with each
+//! node at the center of a pixel; missing nodes are written as the band's
+//! no-data value. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+//!
+//! rasters carry the grid's EPSG code as their SRID where the projection
+//! maps to one (see [Grid::epsg]), or else PostGIS's "unknown" SRID of 0
+//!
+//! the table name, tiling, no-data value, and whether the table is created
+//! and constrained can be adjusted with [PostgisOptions]; the rasters' WKB
+//! alone is available from [wkb_rasters]
+
+use std::io::{BufWriter, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use ndarray::{ArcArray2, s};
+
+use crate::{Error, Grid, GridData, export::{Exporter, crs_description}};
+
+// the largest raster width or height WKB allows
+const MAX_RASTER_SIZE: usize = u16::MAX as usize;
+
+// the band's pixel type (64-bit float) and flags
+const PIXTYPE_64BF: u8 = 11;
+const BAND_HAS_NODATA: u8 = 0x40;
+
+// PostGIS's SRID for an unknown coordinate system
+const UNKNOWN_SRID: i32 = 0;
+
+/// options for PostGIS SQL exports; see [write_postgis_with]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostgisOptions {
+    table: Option<String>,
+    tile_size: Option<(usize, usize)>,
+    nodata: f64,
+    create_table: bool,
+    constraints: bool,
+    hex_only: bool,
+}
+
+impl PostgisOptions {
+    /// the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the table to load into, optionally qualified by its schema (e.g.
+    /// `"grids.top_lansing"`); by default, it's named for the grid
+    pub fn table<S: Into<String>>(self, table: S) -> Self {
+        Self { table: Some(table.into()), ..self }
+    }
+
+    /// split the grid into rasters of at most `rows` × `columns` pixels (at
+    /// least one of each), as `raster2pgsql -t` does; by default, the grid
+    /// is loaded as a single raster, which can't be more than 65,535 pixels
+    /// wide or high
+    pub fn tiles(self, rows: usize, columns: usize) -> Self {
+        Self { tile_size: Some((rows.max(1), columns.max(1))), ..self }
+    }
+
+    /// the band's no-data value, written for missing nodes (by default,
+    /// `1e30`)
+    pub fn nodata(self, nodata: f64) -> Self {
+        Self { nodata, ..self }
+    }
+
+    /// create the table before loading it (by default, true); otherwise, it
+    /// must already exist, with a `rast` raster column
+    pub fn create_table(self, create_table: bool) -> Self {
+        Self { create_table, ..self }
+    }
+
+    /// add the standard raster constraints (`AddRasterConstraints`) once
+    /// the table is loaded, as `raster2pgsql -C` does, so that the rasters
+    /// are listed with their SRID, pixel size, and so on in the
+    /// `raster_columns` view (by default, false)
+    pub fn constraints(self, constraints: bool) -> Self {
+        Self { constraints, ..self }
+    }
+
+    /// write only the rasters' hex-encoded WKB, one per line (e.g. for
+    /// `COPY`), with no SQL at all (by default, false)
+    pub fn hex_only(self, hex_only: bool) -> Self {
+        Self { hex_only, ..self }
+    }
+}
+
+impl Default for PostgisOptions {
+    fn default() -> PostgisOptions {
+        PostgisOptions {
+            table: None,
+            tile_size: None,
+            nodata: 1e30,
+            create_table: true,
+            constraints: false,
+            hex_only: false,
+        }
+    }
+}
+
+/// write a grid as a PostGIS raster loading script, with the default
+/// options
+pub fn write_postgis<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_postgis_with(grid, out, &PostgisOptions::default())
+}
+
+/// write a grid as a PostGIS raster loading script (or just its rasters'
+/// hex-encoded WKB), according to `options`
+pub fn write_postgis_with<W: Write + ?Sized>(grid: &Grid, out: &mut W,
+  options: &PostgisOptions) -> Result<(), Error> {
+    let rasters = wkb_rasters(grid, options)?;
+    let mut out = BufWriter::new(out);
+    if options.hex_only {
+        for wkb in &rasters {
+            writeln!(out, "{}", hex(wkb))?;
+        }
+        out.flush()?;
+        return Ok(());
+    }
+
+    let (schema, table) = match &options.table {
+        Some(name) => match name.split_once('.') {
+            Some((schema, table)) =>
+                (Some(schema.to_string()), table.to_string()),
+            None => (None, name.clone()),
+        },
+        None => (None, table_name(&grid.name)),
+    };
+    let qualified = match &schema {
+        Some(schema) => format!("{}.{}", identifier(schema),
+          identifier(&table)),
+        None => identifier(&table),
+    };
+
+    // newlines would end the comment early
+    let comment = |s: &str| s.replace(['\r', '\n'], " ");
+    writeln!(out, "-- grid {}", comment(grid.name.trim()))?;
+    writeln!(out, "-- {}", comment(&crs_description(grid)))?;
+    writeln!(out, "BEGIN;")?;
+    if options.create_table {
+        writeln!(out, "CREATE TABLE {} (\"rid\" serial PRIMARY KEY, \
+          \"rast\" raster);", qualified)?;
+    }
+    for wkb in &rasters {
+        writeln!(out, "INSERT INTO {} (\"rast\") VALUES ('{}'::raster);",
+          qualified, hex(wkb))?;
+    }
+    if options.constraints {
+        match &schema {
+            Some(schema) => writeln!(out, "SELECT AddRasterConstraints({}, \
+              {}, 'rast'::name);", literal(schema), literal(&table))?,
+            None => writeln!(out, "SELECT AddRasterConstraints({}, \
+              'rast'::name);", literal(&table))?,
+        }
+    }
+    writeln!(out, "END;")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// the grid as PostGIS WKB rasters, one per tile (see
+/// [PostgisOptions::tiles]) from the north-west corner row by row; fails
+/// with [Error::InvalidArgument] if a raster would be too large for WKB
+pub fn wkb_rasters(grid: &Grid, options: &PostgisOptions)
+  -> Result<Vec<Vec<u8>>, Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let (tile_rows, tile_cols) = options.tile_size.unwrap_or((rows, cols));
+    if tile_rows.min(rows) > MAX_RASTER_SIZE
+      || tile_cols.min(cols) > MAX_RASTER_SIZE {
+        return Err(Error::InvalidArgument(format!(
+          "a {} x {} raster is too large for PostGIS (tile it instead)",
+          tile_cols.min(cols), tile_rows.min(rows))));
+    }
+    let srid = grid.epsg()
+      .and_then(|code| i32::try_from(code).ok())
+      .unwrap_or(UNKNOWN_SRID);
+
+    let mut rasters = Vec::new();
+    // rows counted from the north
+    let mut top = 0;
+    while top < rows {
+        let bottom = (top + tile_rows).min(rows);
+        let mut left = 0;
+        while left < cols {
+            let right = (left + tile_cols).min(cols);
+            rasters.push(wkb(grid, arr, (top, bottom), (left, right), srid,
+              options.nodata));
+            left = right;
+        }
+        top = bottom;
+    }
+    Ok(rasters)
+}
+
+/// the PostGIS exporter (with the default options)
+pub struct Postgis;
+
+impl Exporter for Postgis {
+    fn name(&self) -> &str {
+        "postgis"
+    }
+
+    fn extension(&self) -> &str {
+        "sql"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_postgis(grid, out)
+    }
+}
+
+/* one raster, covering the given rows (counted from the north) and
+ * columns of the grid, in little-endian WKB (version 0) */
+fn wkb(grid: &Grid, arr: &ArcArray2<f64>, (top, bottom): (usize, usize),
+  (left, right): (usize, usize), srid: i32, nodata: f64) -> Vec<u8> {
+    let rows = arr.nrows();
+    let (height, width) = (bottom - top, right - left);
+    // the upper-left corner of the upper-left pixel
+    let ulx = grid.xmin + (left as f64 - 0.5) * grid.xstep;
+    let uly = grid.ymin + ((rows - top) as f64 - 0.5) * grid.ystep;
+
+    let mut out = Vec::with_capacity(61 + 9 + height * width * 8);
+    /* safety: writes to a Vec can't fail */
+    out.write_u8(1).unwrap(); // little-endian
+    out.write_u16::<LittleEndian>(0).unwrap();
+    out.write_u16::<LittleEndian>(1).unwrap();
+    for v in [grid.xstep, -grid.ystep, ulx, uly, 0.0, 0.0] {
+        out.write_f64::<LittleEndian>(v).unwrap();
+    }
+    out.write_i32::<LittleEndian>(srid).unwrap();
+    out.write_u16::<LittleEndian>(width as u16).unwrap();
+    out.write_u16::<LittleEndian>(height as u16).unwrap();
+
+    out.write_u8(PIXTYPE_64BF | BAND_HAS_NODATA).unwrap();
+    out.write_f64::<LittleEndian>(nodata).unwrap();
+    let tile = arr.slice(s![rows - bottom..rows - top, left..right]);
+    for row in tile.outer_iter().rev() {
+        for &z in row {
+            out.write_f64::<LittleEndian>(if z.is_nan() { nodata } else { z })
+              .unwrap();
+        }
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+// a table name for the grid, from its name: lowercase letters, digits, and
+// underscores, starting with a letter
+fn table_name(name: &str) -> String {
+    let mut table: String = name.trim().chars()
+      .map(|c| if c.is_ascii_alphanumeric() {
+          c.to_ascii_lowercase()
+      } else {
+          '_'
+      })
+      .collect();
+    if !table.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        table.insert_str(0, "grid_");
+    }
+    table
+}
+
+// a quoted SQL identifier
+fn identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// a quoted SQL string literal, as a name
+fn literal(s: &str) -> String {
+    format!("'{}'::name", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn raster_header_has_georeferencing_and_size() {
+        let grid = small_plane();
+        let rasters = wkb_rasters(&grid, &PostgisOptions::new())
+          .expect("encode WKB raster");
+        assert_eq!(rasters.len(), 1);
+
+        let mut header = &rasters[0][..];
+        assert_eq!(header.read_u8().unwrap(), 1);
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(), 0);
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(), 1);
+        let mut georef = [0.0; 6];
+        header.read_f64_into::<LittleEndian>(&mut georef).unwrap();
+        assert_eq!(georef, [25.0, -50.0, 987.5, 2125.0, 0.0, 0.0]);
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(),
+          grid.epsg().map_or(UNKNOWN_SRID, |code| code as i32));
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(), 4);
+        assert_eq!(header.read_u16::<LittleEndian>().unwrap(), 3);
+        assert_eq!(header.read_u8().unwrap(), PIXTYPE_64BF | BAND_HAS_NODATA);
+        assert_eq!(header.read_f64::<LittleEndian>().unwrap(), 1e30);
+        // the first pixel is the north-west corner
+        assert_eq!(header.read_f64::<LittleEndian>().unwrap(), 1310.0);
+        assert_eq!(header.len(), 11 * 8);
+    }
+}
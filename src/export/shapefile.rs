@@ -0,0 +1,284 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ESRI Shapefiles of contour lines, for mapping deliverables
+//!
+//! contours (see [Grid::contours]) are written as a polyline layer: one
+//! single-part feature per contour line (closed contours repeat their first
+//! point), with its level in the `Z` column of the `.dbf` table. the `.prj`
+//! file describes the grid's state plane zone, when we can identify it (see
+//! [crate::epsg::state_plane_zone]), in the classic ESRI projection file
+//! format, which GDAL (and so QGIS) reads; we don't implement projections,
+//! so supply a WKT `.prj` of your own for other coordinate systems, or for
+//! software which expects one
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+
+use time::OffsetDateTime;
+
+use crate::{Contour, Error, Grid, UnitOfMeasure, epsg::state_plane_zone};
+
+// the shape type of polylines
+const POLYLINE: i32 = 3;
+
+// the length of .shp and .shx headers, in bytes
+const HEADER_LEN: usize = 100;
+
+// the width and precision of the `Z` column
+const Z_WIDTH: usize = 19;
+const Z_DECIMALS: usize = 6;
+
+/// write contour lines of a grid, at every multiple of `interval`, as a
+/// Shapefile at `path` (with its extension replaced by `.shp`, `.shx`,
+/// `.dbf`, and, if we can describe the grid's coordinate system, `.prj`),
+/// returning the paths written
+///
+/// fails with [Error::InvalidArgument] unless the interval is positive
+pub fn write_contour_shapefile<P: AsRef<Path>>(grid: &Grid, interval: f64,
+  path: P) -> Result<Vec<PathBuf>, Error> {
+    if !(interval > 0.0 && interval.is_finite()) {
+        return Err(Error::InvalidArgument(format!(
+          "invalid contour interval {}", interval)));
+    }
+
+    let path = path.as_ref();
+    let shp = path.with_extension("shp");
+    let shx = path.with_extension("shx");
+    let dbf = path.with_extension("dbf");
+    write_contours(grid, &grid.contour_levels(interval)?,
+      &mut File::create(&shp)?, &mut File::create(&shx)?,
+      &mut File::create(&dbf)?)?;
+    let mut written = vec![shp, shx, dbf];
+    if let Some(prj) = projection_file(grid) {
+        let prj_path = path.with_extension("prj");
+        fs::write(&prj_path, prj)?;
+        written.push(prj_path);
+    }
+    Ok(written)
+}
+
+/// write contour lines of a grid at the given levels as the `.shp`, `.shx`,
+/// and `.dbf` files of a Shapefile
+pub fn write_contours<S, X, D>(grid: &Grid, levels: &[f64], shp: &mut S,
+  shx: &mut X, dbf: &mut D) -> Result<(), Error>
+  where S: Write + ?Sized,
+        X: Write + ?Sized,
+        D: Write + ?Sized,
+{
+    let contours = grid.contours(levels);
+    let mut shp = BufWriter::new(shp);
+    let mut shx = BufWriter::new(shx);
+
+    // lengths, in 16-bit words, as the format counts them
+    let content_words = contours.iter()
+      .map(|c| record_len(c) / 2)
+      .collect::<Vec<_>>();
+    let shp_words = HEADER_LEN / 2
+      + content_words.iter().map(|w| w + 4).sum::<usize>();
+    let shx_words = HEADER_LEN / 2 + 4 * contours.len();
+    if shp_words > i32::MAX as usize {
+        return Err(Error::InvalidArgument(
+          "too many contours for a Shapefile".to_string()));
+    }
+
+    let extent = bounds(contours.iter().flat_map(|c| c.points.iter()));
+    write_header(&mut shp, shp_words, extent)?;
+    write_header(&mut shx, shx_words, extent)?;
+    let mut offset = HEADER_LEN / 2;
+    for (n, (contour, &words)) in contours.iter().zip(&content_words)
+      .enumerate() {
+        shx.write_i32::<BigEndian>(offset as i32)?;
+        shx.write_i32::<BigEndian>(words as i32)?;
+        offset += words + 4;
+
+        let points = ring(contour);
+        shp.write_i32::<BigEndian>(n as i32 + 1)?;
+        shp.write_i32::<BigEndian>(words as i32)?;
+        shp.write_i32::<LittleEndian>(POLYLINE)?;
+        let (min, max) = bounds(points.iter());
+        for v in [min.0, min.1, max.0, max.1] {
+            shp.write_f64::<LittleEndian>(v)?;
+        }
+        shp.write_i32::<LittleEndian>(1)?; // parts
+        shp.write_i32::<LittleEndian>(points.len() as i32)?;
+        shp.write_i32::<LittleEndian>(0)?; // the part's first point
+        for &(x, y) in &points {
+            shp.write_f64::<LittleEndian>(x)?;
+            shp.write_f64::<LittleEndian>(y)?;
+        }
+    }
+    shp.flush()?;
+    shx.flush()?;
+
+    write_dbf(&contours, dbf)
+}
+
+/// the contents of a `.prj` file describing the grid's coordinate system,
+/// if it's a state plane zone we know (in the classic ESRI format, naming
+/// the zone by its FIPS code)
+pub fn projection_file(grid: &Grid) -> Option<String> {
+    let (fips, year) = state_plane_zone(&grid.projection, &grid.datum)?;
+    let (datum, spheroid, units) = match (year, grid.xyunits) {
+        (27, UnitOfMeasure::Feet) => ("NAD27", "CLARKE1866", "FEET"),
+        (83, UnitOfMeasure::Feet) => ("NAD83", "GRS1980", "FEET"),
+        (83, UnitOfMeasure::Meters) => ("NAD83", "GRS1980", "METERS"),
+        _ => return None,
+    };
+    Some(format!("Projection    STATEPLANE\nFipszone      {}\n\
+      Datum         {}\nSpheroid      {}\nUnits         {}\nZunits        NO\n\
+      Xshift        0.0\nYshift        0.0\nParameters\n", fips, datum,
+      spheroid, units))
+}
+
+// a contour's points, closing rings explicitly
+fn ring(contour: &Contour) -> Vec<(f64, f64)> {
+    let mut points = contour.points.clone();
+    if contour.closed {
+        points.push(points[0]);
+    }
+    points
+}
+
+// the length of a polyline record's content, in bytes
+fn record_len(contour: &Contour) -> usize {
+    let points = contour.points.len() + usize::from(contour.closed);
+    4 + 32 + 4 + 4 + 4 + 16 * points
+}
+
+// the bounding box of some points (all zeros, if there are none)
+fn bounds<'a, I>(points: I) -> ((f64, f64), (f64, f64))
+  where I: Iterator<Item = &'a (f64, f64)>
+{
+    let mut min = (f64::INFINITY, f64::INFINITY);
+    let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for &(x, y) in points {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    if min.0 > max.0 {
+        ((0.0, 0.0), (0.0, 0.0))
+    } else {
+        (min, max)
+    }
+}
+
+fn write_header<W: Write>(out: &mut W, words: usize,
+  (min, max): ((f64, f64), (f64, f64))) -> Result<(), Error> {
+    out.write_i32::<BigEndian>(9994)?;
+    for _ in 0..5 {
+        out.write_i32::<BigEndian>(0)?;
+    }
+    out.write_i32::<BigEndian>(words as i32)?;
+    out.write_i32::<LittleEndian>(1000)?;
+    out.write_i32::<LittleEndian>(POLYLINE)?;
+    // x and y bounds, then (unused) z and m bounds
+    for v in [min.0, min.1, max.0, max.1, 0.0, 0.0, 0.0, 0.0] {
+        out.write_f64::<LittleEndian>(v)?;
+    }
+    Ok(())
+}
+
+// a dBASE III table with a single numeric `Z` column
+fn write_dbf<W: Write + ?Sized>(contours: &[Contour], out: &mut W)
+  -> Result<(), Error> {
+    let mut out = BufWriter::new(out);
+    let today = OffsetDateTime::now_utc().date();
+    out.write_u8(0x03)?;
+    out.write_all(&[(today.year() - 1900).clamp(0, 255) as u8,
+      today.month() as u8, today.day()])?;
+    out.write_u32::<LittleEndian>(contours.len() as u32)?;
+    out.write_u16::<LittleEndian>(32 + 32 + 1)?; // header, one field
+    out.write_u16::<LittleEndian>(1 + Z_WIDTH as u16)?;
+    out.write_all(&[0; 20])?;
+
+    let mut name = [0u8; 11];
+    name[0] = b'Z';
+    out.write_all(&name)?;
+    out.write_u8(b'N')?;
+    out.write_all(&[0; 4])?;
+    out.write_u8(Z_WIDTH as u8)?;
+    out.write_u8(Z_DECIMALS as u8)?;
+    out.write_all(&[0; 14])?;
+    out.write_u8(0x0d)?;
+
+    for contour in contours {
+        let mut z = format!("{:>width$.prec$}", contour.level,
+          width = Z_WIDTH, prec = Z_DECIMALS);
+        if z.len() > Z_WIDTH {
+            // too large to write with all its decimals
+            z = format!("{:>width$.0}", contour.level, width = Z_WIDTH);
+        }
+        out.write_u8(b' ')?; // not deleted
+        out.write_all(z.as_bytes())?;
+    }
+    out.write_u8(0x1a)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn headers_agree_with_the_contours_written() {
+        let grid = small_plane();
+        let (mut shp, mut shx, mut dbf) = (Vec::new(), Vec::new(), Vec::new());
+        write_contours(&grid, &[1300.0], &mut shp, &mut shx, &mut dbf)
+          .expect("write contour Shapefile");
+
+        for file in [&shp, &shx] {
+            let mut header = &file[..];
+            assert_eq!(header.read_i32::<BigEndian>().unwrap(), 9994);
+            header = &header[20..];
+            assert_eq!(header.read_i32::<BigEndian>().unwrap() as usize * 2,
+              file.len());
+            assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 1000);
+            assert_eq!(header.read_i32::<LittleEndian>().unwrap(), POLYLINE);
+            let mut bounds = [0.0; 4];
+            header.read_f64_into::<LittleEndian>(&mut bounds).unwrap();
+            assert!(bounds[0] >= 1000.0 && bounds[2] <= 1075.0);
+            assert!(bounds[1] >= 2000.0 && bounds[3] <= 2100.0);
+            assert!(bounds[0] < bounds[2] && bounds[1] < bounds[3]);
+        }
+
+        let n_records = (shx.len() - HEADER_LEN) / 8;
+        assert_eq!(n_records, 1);
+        let mut index = &shx[HEADER_LEN..];
+        assert_eq!(index.read_i32::<BigEndian>().unwrap() as usize * 2,
+          HEADER_LEN);
+        let words = index.read_i32::<BigEndian>().unwrap() as usize;
+        assert_eq!(shp.len(), HEADER_LEN + 8 + words * 2);
+
+        assert_eq!(dbf[0], 0x03);
+        assert_eq!((&dbf[4..]).read_u32::<LittleEndian>().unwrap() as usize,
+          n_records);
+    }
+}
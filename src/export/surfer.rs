@@ -0,0 +1,237 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Surfer 6 (`DSBB`) and Surfer 7 (`DSRB`) binary grids
+//!
+//! both hold the grid's shape, and its *x*, *y*, and *z* ranges, followed by
+//! its values row by row from south to north (as in Petra); missing values
+//! are written as Surfer's blanking value, [SURFER_BLANK]. triangular grids
+//! are rasterized first (see [Grid::rasterize])
+//!
+//! Surfer 6 grids store 32-bit floating-point values, and can't have more
+//! than 32,767 rows or columns; Surfer 7 grids are made of tagged sections,
+//! store full 64-bit values, and record their blanking value explicitly, so
+//! they're the better choice for newer versions of Surfer
+
+use std::io::{BufWriter, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+/// the value Surfer uses for blanked (missing) nodes
+pub const SURFER_BLANK: f32 = 1.70141e38;
+
+/// write a grid as a Surfer 6 binary grid
+pub fn write_surfer6<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mut raster = None;
+    let grid = rectangular(grid, &mut raster);
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let shape = (i16::try_from(rows), i16::try_from(cols));
+    let (rows_i16, cols_i16) = match shape {
+        (Ok(r), Ok(c)) => (r, c),
+        _ => return Err(Error::InvalidArgument(format!(
+          "{} rows x {} columns is too large for a Surfer 6 grid",
+          rows, cols))),
+    };
+
+    let (zlo, zhi) = z_range(grid);
+
+    let mut out = BufWriter::new(out);
+    out.write_all(b"DSBB")?;
+    out.write_i16::<LittleEndian>(cols_i16)?;
+    out.write_i16::<LittleEndian>(rows_i16)?;
+    out.write_f64::<LittleEndian>(grid.xmin)?;
+    out.write_f64::<LittleEndian>(grid.xmin
+      + cols.saturating_sub(1) as f64 * grid.xstep)?;
+    out.write_f64::<LittleEndian>(grid.ymin)?;
+    out.write_f64::<LittleEndian>(grid.ymin
+      + rows.saturating_sub(1) as f64 * grid.ystep)?;
+    out.write_f64::<LittleEndian>(zlo)?;
+    out.write_f64::<LittleEndian>(zhi)?;
+    for &z in arr.iter() {
+        let z = if z.is_nan() { SURFER_BLANK } else { z as f32 };
+        out.write_f32::<LittleEndian>(z)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// write a grid as a Surfer 7 binary grid
+pub fn write_surfer7<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mut raster = None;
+    let grid = rectangular(grid, &mut raster);
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let too_large = || Error::InvalidArgument(format!(
+      "{} rows x {} columns is too large for a Surfer 7 grid", rows, cols));
+    let rows_i32 = i32::try_from(rows).map_err(|_| too_large())?;
+    let cols_i32 = i32::try_from(cols).map_err(|_| too_large())?;
+    let data_len = rows.checked_mul(cols)
+      .and_then(|n| n.checked_mul(8))
+      .and_then(|n| i32::try_from(n).ok())
+      .ok_or_else(too_large)?;
+    let (zlo, zhi) = z_range(grid);
+    let blank = SURFER_BLANK as f64;
+
+    let mut out = BufWriter::new(out);
+    // the header section: just a version number
+    out.write_all(b"DSRB")?;
+    out.write_i32::<LittleEndian>(4)?;
+    out.write_i32::<LittleEndian>(1)?;
+
+    out.write_all(b"GRID")?;
+    out.write_i32::<LittleEndian>(72)?;
+    out.write_i32::<LittleEndian>(rows_i32)?;
+    out.write_i32::<LittleEndian>(cols_i32)?;
+    out.write_f64::<LittleEndian>(grid.xmin)?;
+    out.write_f64::<LittleEndian>(grid.ymin)?;
+    out.write_f64::<LittleEndian>(grid.xstep)?;
+    out.write_f64::<LittleEndian>(grid.ystep)?;
+    out.write_f64::<LittleEndian>(zlo)?;
+    out.write_f64::<LittleEndian>(zhi)?;
+    out.write_f64::<LittleEndian>(0.0)?; // rotation
+    out.write_f64::<LittleEndian>(blank)?;
+
+    out.write_all(b"DATA")?;
+    out.write_i32::<LittleEndian>(data_len)?;
+    for &z in arr.iter() {
+        out.write_f64::<LittleEndian>(if z.is_nan() { blank } else { z })?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// rasterize a triangular grid, if need be
+fn rectangular<'a>(grid: &'a Grid, raster: &'a mut Option<Grid>) -> &'a Grid {
+    match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => raster.insert(grid.rasterize()),
+    }
+}
+
+// the range of non-missing z values, or zeros if there are none
+fn z_range(grid: &Grid) -> (f64, f64) {
+    let stats = grid.data.stats();
+    if stats.count == 0 {
+        (0.0, 0.0)
+    } else {
+        (stats.min, stats.max)
+    }
+}
+
+/// Surfer 6 binary grid, as an [Exporter]; see [write_surfer6]
+///
+/// the extension is `surfer6.grd`, so that exports don't overwrite Petra
+/// grids of the same name
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Surfer6;
+
+impl Exporter for Surfer6 {
+    fn name(&self) -> &str {
+        "surfer6"
+    }
+
+    fn extension(&self) -> &str {
+        "surfer6.grd"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_surfer6(grid, out)
+    }
+}
+
+/// Surfer 7 binary grid, as an [Exporter]; see [write_surfer7]
+///
+/// the extension is `surfer7.grd`, so that exports don't overwrite Petra
+/// grids of the same name
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Surfer7;
+
+impl Exporter for Surfer7 {
+    fn name(&self) -> &str {
+        "surfer7"
+    }
+
+    fn extension(&self) -> &str {
+        "surfer7.grd"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_surfer7(grid, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::testing::small_plane;
+
+    #[test]
+    fn surfer6_header_has_shape_and_ranges() {
+        let mut buf = Vec::new();
+        write_surfer6(&small_plane(), &mut buf).expect("write Surfer 6 grid");
+        assert_eq!(buf.len(), 56 + 12 * 4);
+
+        let mut header = &buf[..];
+        let mut tag = [0; 4];
+        header.read_exact(&mut tag).unwrap();
+        assert_eq!(&tag, b"DSBB");
+        assert_eq!(header.read_i16::<LittleEndian>().unwrap(), 4);
+        assert_eq!(header.read_i16::<LittleEndian>().unwrap(), 3);
+        let mut ranges = [0.0; 6];
+        header.read_f64_into::<LittleEndian>(&mut ranges).unwrap();
+        assert_eq!(ranges, [1000.0, 1075.0, 2000.0, 2100.0, 1260.0, 1328.75]);
+    }
+
+    #[test]
+    fn surfer7_header_has_shape_and_lattice() {
+        let mut buf = Vec::new();
+        write_surfer7(&small_plane(), &mut buf).expect("write Surfer 7 grid");
+        assert_eq!(buf.len(), 12 + 80 + 8 + 12 * 8);
+
+        let mut header = &buf[..];
+        let mut tag = [0; 4];
+        header.read_exact(&mut tag).unwrap();
+        assert_eq!(&tag, b"DSRB");
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 4);
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 1);
+        header.read_exact(&mut tag).unwrap();
+        assert_eq!(&tag, b"GRID");
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 72);
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 3);
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 4);
+        let mut fields = [0.0; 8];
+        header.read_f64_into::<LittleEndian>(&mut fields).unwrap();
+        assert_eq!(fields, [1000.0, 2000.0, 25.0, 50.0, 1260.0, 1328.75, 0.0,
+          SURFER_BLANK as f64]);
+        header.read_exact(&mut tag).unwrap();
+        assert_eq!(&tag, b"DATA");
+        assert_eq!(header.read_i32::<LittleEndian>().unwrap(), 12 * 8);
+    }
+}
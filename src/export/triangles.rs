@@ -0,0 +1,152 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! per-triangle attribute tables for triangular grids: the area, dip, and
+//! dip azimuth of each triangle (see [Grid::triangle_attributes]), as CSV
+//! (one row per triangle, located by its centroid) or as GeoJSON (one
+//! polygon feature per triangle)
+//!
+//! triangles with missing vertices are omitted. GeoJSON coordinates are
+//! written in the grid's own coordinate system, which is recorded (along
+//! with the grid's name) in the feature collection's `name` and
+//! `description` members
+
+use std::io::{BufWriter, Write};
+
+use ndarray::Axis;
+
+use crate::{Error, Grid, GridData, export::{Exporter, crs_description}};
+
+/// write a triangular grid's triangle attributes as CSV, with columns
+/// `index`, `x`, `y`, `z` (the centroid), `area`, `dip`, and `dip_azimuth`;
+/// fails with [Error::NotTriangular] for rectangular grids
+pub fn write_triangle_csv<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let attributes = grid.triangle_attributes()?;
+    let mut out = BufWriter::new(out);
+    writeln!(out, "index,x,y,z,area,dip,dip_azimuth")?;
+    for t in attributes.iter().filter(|t| !t.area.is_nan()) {
+        let (x, y, z) = t.centroid;
+        writeln!(out, "{},{},{},{},{},{},{}", t.index, x, y, z, t.area,
+          csv_number(t.dip), csv_number(t.dip_azimuth))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// write a triangular grid's triangles as a GeoJSON feature collection of
+/// 3D polygons, with `index`, `area`, `dip`, and `dip_azimuth` properties;
+/// fails with [Error::NotTriangular] for rectangular grids
+pub fn write_triangle_geojson<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let attributes = grid.triangle_attributes()?;
+    let GridData::Triangular(arr) = &grid.data else {
+        return Err(Error::NotTriangular);
+    };
+
+    let mut out = BufWriter::new(out);
+    write!(out, "{{\"type\":\"FeatureCollection\",\"name\":{},\
+      \"description\":{},\"features\":[", json_string(&grid.name),
+      json_string(&crs_description(grid)))?;
+    let mut first = true;
+    for t in attributes.iter().filter(|t| !t.area.is_nan()) {
+        if !first {
+            write!(out, ",")?;
+        }
+        first = false;
+
+        let tri = arr.index_axis(Axis(0), t.index);
+        let point = |v: usize| format!("[{},{},{}]",
+          tri[(v, 0)], tri[(v, 1)], tri[(v, 2)]);
+        write!(out, "\n{{\"type\":\"Feature\",\"geometry\":{{\"type\":\
+          \"Polygon\",\"coordinates\":[[{},{},{},{}]]}},\"properties\":{{\
+          \"index\":{},\"area\":{},\"dip\":{},\"dip_azimuth\":{}}}}}",
+          point(0), point(1), point(2), point(0), t.index, t.area,
+          json_number(t.dip), json_number(t.dip_azimuth))?;
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// per-triangle attributes as CSV, as an [Exporter]; see
+/// [write_triangle_csv]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TriangleCsv;
+
+impl Exporter for TriangleCsv {
+    fn name(&self) -> &str {
+        "triangles-csv"
+    }
+
+    fn extension(&self) -> &str {
+        "triangles.csv"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_triangle_csv(grid, out)
+    }
+}
+
+/// per-triangle attributes as GeoJSON, as an [Exporter]; see
+/// [write_triangle_geojson]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TriangleGeoJson;
+
+impl Exporter for TriangleGeoJson {
+    fn name(&self) -> &str {
+        "triangles-geojson"
+    }
+
+    fn extension(&self) -> &str {
+        "triangles.geojson"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_triangle_geojson(grid, out)
+    }
+}
+
+// undefined angles are left empty in CSV...
+fn csv_number(v: f64) -> String {
+    if v.is_nan() { String::new() } else { v.to_string() }
+}
+
+// ...and null in JSON
+fn json_number(v: f64) -> String {
+    if v.is_nan() { "null".to_string() } else { v.to_string() }
+}
+
+pub(super) fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 =>
+                quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
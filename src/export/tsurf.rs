@@ -0,0 +1,126 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! GOCAD/SKUA TSurf ASCII surfaces, for delivering triangulated grids to
+//! geomodelers
+//!
+//! only triangular grids can be written; shared vertices are merged, and
+//! triangles with missing vertices are omitted. the grid's projection,
+//! datum, and units are recorded in the `GOCAD_ORIGINAL_COORDINATE_SYSTEM`
+//! block, with *z* taken as elevation (positive up), as Petra usually
+//! stores it
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    UnitOfMeasure,
+    export::{Exporter, mesh::Mesh},
+};
+
+/// write a triangular grid as a GOCAD TSurf ASCII file
+///
+/// fails with [Error::NotTriangular] for rectangular grids
+pub fn write_tsurf<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    if !matches!(grid.data, GridData::Triangular(_)) {
+        return Err(Error::NotTriangular);
+    }
+
+    let mesh = Mesh::from_grid(grid);
+    let mut out = BufWriter::new(out);
+    writeln!(out, "GOCAD TSurf 1")?;
+    writeln!(out, "HEADER {{")?;
+    writeln!(out, "name:{}", header_value(&grid.name))?;
+    writeln!(out, "}}")?;
+
+    writeln!(out, "GOCAD_ORIGINAL_COORDINATE_SYSTEM")?;
+    writeln!(out, "NAME Default")?;
+    if !grid.projection.is_empty() {
+        writeln!(out, "PROJECTION {}", quoted(&grid.projection))?;
+    }
+    if !grid.datum.is_empty() {
+        writeln!(out, "DATUM {}", quoted(&grid.datum))?;
+    }
+    writeln!(out, "AXIS_NAME \"X\" \"Y\" \"Z\"")?;
+    let xy = unit_name(grid.xyunits);
+    writeln!(out, "AXIS_UNIT \"{}\" \"{}\" \"{}\"", xy, xy,
+      unit_name(grid.zunits))?;
+    writeln!(out, "ZPOSITIVE Elevation")?;
+    writeln!(out, "END_ORIGINAL_COORDINATE_SYSTEM")?;
+
+    // TSurf vertex numbers are 1-based
+    writeln!(out, "TFACE")?;
+    for (n, [x, y, z]) in mesh.vertices.iter().enumerate() {
+        writeln!(out, "VRTX {} {} {} {}", n + 1, x, y, z)?;
+    }
+    for [a, b, c] in &mesh.faces {
+        writeln!(out, "TRGL {} {} {}", a + 1, b + 1, c + 1)?;
+    }
+    writeln!(out, "END")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// GOCAD TSurf, as an [Exporter]; see [write_tsurf]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TSurf;
+
+impl Exporter for TSurf {
+    fn name(&self) -> &str {
+        "tsurf"
+    }
+
+    fn extension(&self) -> &str {
+        "ts"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_tsurf(grid, out)
+    }
+}
+
+// GOCAD's abbreviation for a unit; "none" when we don't know
+fn unit_name(unit: UnitOfMeasure) -> &'static str {
+    match unit {
+        UnitOfMeasure::Feet => "ft",
+        UnitOfMeasure::Meters => "m",
+        UnitOfMeasure::Other(_) => "none",
+    }
+}
+
+// header values run to the end of the line, and can't be empty
+fn header_value(s: &str) -> String {
+    let s = s.replace(['\r', '\n'], " ");
+    if s.trim().is_empty() {
+        "unnamed".to_string()
+    } else {
+        s
+    }
+}
+
+// a double-quoted string on a single line (GOCAD has no escapes, so
+// embedded quotes are dropped)
+fn quoted(s: &str) -> String {
+    format!("\"{}\"", s.replace(['\r', '\n'], " ").replace('"', ""))
+}
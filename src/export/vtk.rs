@@ -0,0 +1,141 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! legacy VTK files, for inspecting surfaces in ParaView
+//!
+//! triangular grids are written as unstructured grids of triangles (with
+//! shared vertices merged, and triangles with missing vertices omitted), and
+//! rectangular grids as structured grids; either way, *z* is attached to
+//! each point as a scalar. missing nodes of rectangular grids are hidden
+//! (via a `vtkGhostType` array), and written with zero *z*, since ASCII VTK
+//! can't portably represent NaN
+//!
+//! VTK has no notion of a coordinate system, so the grid's name, projection,
+//! and units are recorded in the title line
+
+use std::io::{BufWriter, Write};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    export::{Exporter, crs_description, mesh::Mesh},
+};
+
+// VTK's cell type for triangles
+const VTK_TRIANGLE: u8 = 5;
+
+// vtkGhostType's flag for hidden points
+const HIDDEN_POINT: u8 = 2;
+
+/// write a grid as a legacy (ASCII) VTK file
+pub fn write_vtk<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    let mut out = BufWriter::new(out);
+    // the title is a single line of at most 256 characters
+    let title = format!("{} | {}", grid.name, crs_description(grid))
+      .replace(['\r', '\n'], " ")
+      .chars()
+      .take(255)
+      .collect::<String>();
+    writeln!(out, "# vtk DataFile Version 3.0")?;
+    writeln!(out, "{}", title)?;
+    writeln!(out, "ASCII")?;
+
+    match &grid.data {
+        GridData::Rectangular(arr) => {
+            let (rows, cols) = arr.dim();
+            let n = rows * cols;
+            writeln!(out, "DATASET STRUCTURED_GRID")?;
+            writeln!(out, "DIMENSIONS {} {} 1", cols, rows)?;
+            // VTK varies x fastest, as we do
+            writeln!(out, "POINTS {} double", n)?;
+            for ((j, i), &z) in arr.indexed_iter() {
+                let (x, y) = grid.node_xy(j, i);
+                writeln!(out, "{} {} {}", x, y, zero_if_missing(z))?;
+            }
+            writeln!(out, "POINT_DATA {}", n)?;
+            write_scalars(&mut out, arr.iter().map(|&z| zero_if_missing(z)))?;
+            if arr.iter().any(|z| z.is_nan()) {
+                writeln!(out, "FIELD FieldData 1")?;
+                writeln!(out, "vtkGhostType 1 {} unsigned_char", n)?;
+                for z in arr.iter() {
+                    writeln!(out, "{}",
+                      if z.is_nan() { HIDDEN_POINT } else { 0 })?;
+                }
+            }
+        },
+
+        GridData::Triangular(_) => {
+            let mesh = Mesh::from_grid(grid);
+            writeln!(out, "DATASET UNSTRUCTURED_GRID")?;
+            writeln!(out, "POINTS {} double", mesh.vertices.len())?;
+            for [x, y, z] in &mesh.vertices {
+                writeln!(out, "{} {} {}", x, y, z)?;
+            }
+            writeln!(out, "CELLS {} {}", mesh.faces.len(),
+              4 * mesh.faces.len())?;
+            for [a, b, c] in &mesh.faces {
+                writeln!(out, "3 {} {} {}", a, b, c)?;
+            }
+            writeln!(out, "CELL_TYPES {}", mesh.faces.len())?;
+            for _ in &mesh.faces {
+                writeln!(out, "{}", VTK_TRIANGLE)?;
+            }
+            writeln!(out, "POINT_DATA {}", mesh.vertices.len())?;
+            write_scalars(&mut out, mesh.vertices.iter().map(|v| v[2]))?;
+        },
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// legacy VTK, as an [Exporter]; see [write_vtk]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Vtk;
+
+impl Exporter for Vtk {
+    fn name(&self) -> &str {
+        "vtk"
+    }
+
+    fn extension(&self) -> &str {
+        "vtk"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_vtk(grid, out)
+    }
+}
+
+fn write_scalars<W: Write>(out: &mut W, values: impl Iterator<Item = f64>)
+  -> Result<(), Error> {
+    writeln!(out, "SCALARS z double 1")?;
+    writeln!(out, "LOOKUP_TABLE default")?;
+    for z in values {
+        writeln!(out, "{}", z)?;
+    }
+    Ok(())
+}
+
+fn zero_if_missing(z: f64) -> f64 {
+    if z.is_nan() { 0.0 } else { z }
+}
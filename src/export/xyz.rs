@@ -0,0 +1,280 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! plain-text point exports: whitespace-delimited XYZ and comma-separated CSV
+//!
+//! one point is written per line: every non-missing node of a rectangular
+//! grid (south to north, west to east), or every vertex of every triangle of
+//! a triangular grid (shared vertices are repeated); by default, values are
+//! written in their shortest exact decimal representation
+//!
+//! the delimiter, number formatting, treatment of missing values, header
+//! row, and order of nodes can all be adjusted with [TextOptions], for picky
+//! downstream loaders
+//!
+//! for exports kept under version control, [TextOptions::reproducible] pins
+//! the formatting of every value down exactly, so that diffs only show real
+//! changes
+
+use std::io::{BufWriter, Write};
+
+use crate::{AxisOrder, Error, Grid, RotatedGrid, export::Exporter};
+
+/// formatting options for plain-text point exports; see [write_text]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextOptions {
+    delimiter: String,
+    precision: Option<usize>,
+    scientific: bool,
+    null_token: Option<String>,
+    header: bool,
+    reproducible: bool,
+    axis_order: AxisOrder,
+}
+
+impl TextOptions {
+    /// the options used by [write_xyz]: space-delimited, with no header
+    pub fn xyz() -> TextOptions {
+        TextOptions {
+            delimiter: " ".to_string(),
+            precision: None,
+            scientific: false,
+            null_token: None,
+            header: false,
+            reproducible: false,
+            axis_order: AxisOrder::SOUTH_UP,
+        }
+    }
+
+    /// the options used by [write_csv]: comma-delimited, with an "x,y,z"
+    /// header row
+    pub fn csv() -> TextOptions {
+        TextOptions {
+            delimiter: ",".to_string(),
+            header: true,
+            ..TextOptions::xyz()
+        }
+    }
+
+    /// set the field delimiter (e.g. `"\t"`)
+    pub fn delimiter<S: Into<String>>(mut self, delimiter: S) -> TextOptions {
+        self.delimiter = delimiter.into();
+        self
+    }
+
+    /// write values with a fixed number of digits after the decimal point,
+    /// or (with `None`) in their shortest exact representation
+    pub fn precision(mut self, precision: Option<usize>) -> TextOptions {
+        self.precision = precision;
+        self
+    }
+
+    /// write values in scientific notation (e.g. `1.5e3`)
+    pub fn scientific(mut self, scientific: bool) -> TextOptions {
+        self.scientific = scientific;
+        self
+    }
+
+    /// write missing values as the given token (e.g. `"-999.25"` or
+    /// `"NaN"`), or (with `None`) omit points with missing *z* values
+    /// entirely
+    pub fn null_token(mut self, token: Option<String>) -> TextOptions {
+        self.null_token = token;
+        self
+    }
+
+    /// write (or don't write) a header row naming the columns
+    pub fn header(mut self, header: bool) -> TextOptions {
+        self.header = header;
+        self
+    }
+
+    /// write values in a canonical form, identical on every platform and
+    /// across releases, overriding the precision and scientific notation
+    /// settings: the shortest decimal digits which round-trip exactly (as
+    /// produced by the Ryū algorithm), in plain notation for magnitudes from
+    /// 1e-5 up to 1e16 and in scientific notation (e.g. `1.5e30`) otherwise,
+    /// with negative zero written as `0`
+    pub fn reproducible(mut self, reproducible: bool) -> TextOptions {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// write the nodes of rectangular grids in the given order (by default,
+    /// [AxisOrder::SOUTH_UP], Petra's own), for loaders which expect a
+    /// particular order rather than reading the coordinates
+    pub fn axis_order(mut self, axis_order: AxisOrder) -> TextOptions {
+        self.axis_order = axis_order;
+        self
+    }
+
+    fn format(&self, v: f64) -> String {
+        match (&self.null_token, self.precision, self.scientific) {
+            (Some(token), _, _) if v.is_nan() => token.clone(),
+            _ if self.reproducible => canonical(v),
+            (_, Some(p), true) => format!("{:.*e}", p, v),
+            (_, None, true) => format!("{:e}", v),
+            (_, Some(p), false) => format!("{:.*}", p, v),
+            (_, None, false) => v.to_string(),
+        }
+    }
+}
+
+/* Rust's float formatting is already shortest-round-trip and independent of
+ * the platform; we just fix the notation, which it otherwise chooses by
+ * whether a precision or exponent was requested */
+fn canonical(v: f64) -> String {
+    if v == 0.0 {
+        return "0".to_string();
+    }
+    if !v.is_finite() {
+        return v.to_string();
+    }
+    let scientific = format!("{:e}", v);
+    let exponent = scientific.split_once('e')
+      .and_then(|(_, e)| e.parse::<i32>().ok())
+      .unwrap_or(0);
+    if (-5..16).contains(&exponent) {
+        v.to_string()
+    } else {
+        scientific
+    }
+}
+
+impl Default for TextOptions {
+    fn default() -> TextOptions {
+        TextOptions::xyz()
+    }
+}
+
+/// write a grid as whitespace-delimited "x y z" lines
+pub fn write_xyz<W: Write + ?Sized>(grid: &Grid, out: &mut W) -> Result<(), Error> {
+    write_text(grid, out, &TextOptions::xyz())
+}
+
+/// write a grid as comma-separated "x,y,z" lines, with a header row
+pub fn write_csv<W: Write + ?Sized>(grid: &Grid, out: &mut W) -> Result<(), Error> {
+    write_text(grid, out, &TextOptions::csv())
+}
+
+/// write a grid as delimited "x y z" lines, formatted according to `options`
+pub fn write_text<W: Write + ?Sized>(grid: &Grid, out: &mut W,
+  options: &TextOptions) -> Result<(), Error> {
+    write_points(grid.points_in(options.axis_order), out, options)
+}
+
+/// write the nodes of a grid on a rotated lattice as text, in map
+/// coordinates, with the given formatting options (other than
+/// [TextOptions::axis_order], since rotated lattices have no north)
+pub fn write_rotated_text<W: Write + ?Sized>(rotated: &RotatedGrid,
+  out: &mut W, options: &TextOptions) -> Result<(), Error> {
+    write_points(rotated.points(), out, options)
+}
+
+fn write_points<W, I>(points: I, out: &mut W, options: &TextOptions)
+  -> Result<(), Error>
+  where W: Write + ?Sized,
+        I: Iterator<Item = (f64, f64, f64)>,
+{
+    let mut out = BufWriter::new(out);
+    let delim = &options.delimiter;
+    if options.header {
+        writeln!(out, "x{}y{}z", delim, delim)?;
+    }
+    for (x, y, z) in points {
+        if z.is_nan() && options.null_token.is_none() {
+            continue;
+        }
+        writeln!(out, "{}{}{}{}{}", options.format(x), delim,
+          options.format(y), delim, options.format(z))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// whitespace-delimited XYZ text, as an [Exporter]; see [write_xyz]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Xyz;
+
+impl Exporter for Xyz {
+    fn name(&self) -> &str {
+        "xyz"
+    }
+
+    fn extension(&self) -> &str {
+        "xyz"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_xyz(grid, out)
+    }
+}
+
+/// comma-separated XYZ text, as an [Exporter]; see [write_csv]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_csv(grid, out)
+    }
+}
+
+/// delimited text with custom formatting, as an [Exporter]; see [write_text]
+#[derive(Clone, Debug)]
+pub struct Text {
+    name: String,
+    extension: String,
+    options: TextOptions,
+}
+
+impl Text {
+    /// an exporter with the given format name and file extension (e.g.
+    /// `"csv"`) which writes text formatted according to `options`
+    pub fn new<N, E>(name: N, extension: E, options: TextOptions) -> Text
+      where N: Into<String>,
+            E: Into<String>,
+    {
+        Text { name: name.into(), extension: extension.into(), options }
+    }
+}
+
+impl Exporter for Text {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_text(grid, out, &self.options)
+    }
+}
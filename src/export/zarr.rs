@@ -0,0 +1,379 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Zarr stores, for serving grids from object storage to xarray and friends
+//!
+//! a Zarr store is a directory (or a prefix in an object store) rather than
+//! a single file, so there's no [Exporter](crate::export::Exporter) for it;
+//! [write_zarr] writes a store holding a group with a 2D array `z` (rows
+//! south to north, columns west to east), split into chunks of the
+//! requested size, alongside 1D coordinate arrays `x` and `y`, so that
+//! `xarray.open_zarr` finds a properly labeled surface. version 2 of the
+//! format (with consolidated metadata, for one-request opens from object
+//! storage) and version 3 are both supported
+//!
+//! chunks are uncompressed little-endian doubles; missing values are NaN,
+//! which is also the arrays' fill value. the grid's extents, units, and
+//! coordinate system (and EPSG code, where known; see [Grid::epsg]) are
+//! recorded as attributes of `z`. triangular grids are rasterized first
+//! (see [Grid::rasterize])
+
+use std::{fs, path::Path};
+
+use crate::{
+    Error,
+    Grid,
+    GridData,
+    export::{crs_description, triangles::json_string},
+};
+
+/// a version of the Zarr format
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ZarrVersion {
+    /// version 2, with consolidated metadata (`.zmetadata`)
+    #[default]
+    V2,
+    /// version 3
+    V3,
+}
+
+/// options for writing a Zarr store
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ZarrOptions {
+    version: ZarrVersion,
+    chunk_rows: usize,
+    chunk_columns: usize,
+}
+
+impl Default for ZarrOptions {
+    fn default() -> Self {
+        ZarrOptions {
+            version: ZarrVersion::default(),
+            chunk_rows: 256,
+            chunk_columns: 256,
+        }
+    }
+}
+
+impl ZarrOptions {
+    /// the default options: version 2, with 256 × 256 chunks
+    pub fn new() -> Self {
+        ZarrOptions::default()
+    }
+
+    /// set the version of the format to write
+    pub fn version(self, version: ZarrVersion) -> Self {
+        ZarrOptions { version, ..self }
+    }
+
+    /// set the number of rows and columns in each chunk of `z` (at least
+    /// one of each); chunks at the north and east edges are padded with NaN
+    pub fn chunks(self, rows: usize, columns: usize) -> Self {
+        ZarrOptions {
+            chunk_rows: rows.max(1),
+            chunk_columns: columns.max(1),
+            ..self
+        }
+    }
+}
+
+/// write a grid as a Zarr store in the given directory (which is created if
+/// need be; any existing metadata and chunks are overwritten)
+pub fn write_zarr<P: AsRef<Path>>(grid: &Grid, dir: P, options: &ZarrOptions)
+  -> Result<(), Error> {
+    let dir = dir.as_ref();
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+    let xy_units = grid.xyunits.udunits();
+
+    let coordinate = |name: &'static str, axis: &str, min: f64, step: f64,
+      n: usize| {
+        let mut attrs = vec![
+            ("standard_name",
+              json_string(&format!("projection_{}_coordinate", name))),
+            ("axis", json_string(axis)),
+        ];
+        if let Some(units) = xy_units {
+            attrs.push(("units", json_string(units)));
+        }
+        Array {
+            name,
+            dims: vec![name],
+            shape: vec![n],
+            chunks: vec![n.max(1)],
+            attrs,
+            data: (0..n).map(|k| min + k as f64 * step).collect(),
+        }
+    };
+
+    let mut z_attrs = vec![
+        ("long_name", json_string(grid.name.trim())),
+        ("source_data", json_string(grid.source_data.trim())),
+        ("created_date", json_string(&grid.created_date.to_string())),
+        ("projection", json_string(&grid.projection)),
+        ("datum", json_string(&grid.datum)),
+        ("crs", json_string(&crs_description(grid))),
+    ];
+    if let Some(code) = grid.epsg() {
+        z_attrs.push(("epsg_code", json_string(&format!("EPSG:{}", code))));
+    }
+    if let Some(units) = grid.zunits.udunits() {
+        z_attrs.push(("units", json_string(units)));
+    }
+    z_attrs.push(("xy_units", json_string(&format!("{:?}", grid.xyunits))));
+    z_attrs.push(("z_units", json_string(&format!("{:?}", grid.zunits))));
+    for (name, v) in [("xmin", grid.xmin), ("xmax", grid.xmax),
+      ("ymin", grid.ymin), ("ymax", grid.ymax), ("xstep", grid.xstep),
+      ("ystep", grid.ystep), ("zmin", grid.zmin), ("zmax", grid.zmax)] {
+        z_attrs.push((name, json_number(v)));
+    }
+
+    let arrays = [
+        coordinate("x", "X", grid.xmin, grid.xstep, cols),
+        coordinate("y", "Y", grid.ymin, grid.ystep, rows),
+        Array {
+            name: "z",
+            dims: vec!["y", "x"],
+            shape: vec![rows, cols],
+            chunks: vec![options.chunk_rows, options.chunk_columns],
+            attrs: z_attrs,
+            data: arr.iter().copied().collect(),
+        },
+    ];
+    let group_attrs = [
+        ("title", json_string(grid.name.trim())),
+        ("source", json_string(&format!("Petra grid (source data: {})",
+          grid.source_data.trim()))),
+        ("petra_grid_version", json_string(env!("CARGO_PKG_VERSION"))),
+    ];
+
+    fs::create_dir_all(dir)?;
+    match options.version {
+        ZarrVersion::V2 => write_v2(dir, &group_attrs, &arrays),
+        ZarrVersion::V3 => write_v3(dir, &group_attrs, &arrays),
+    }
+}
+
+// a 1D or 2D array, with its attributes as (name, JSON value)
+struct Array {
+    name: &'static str,
+    dims: Vec<&'static str>,
+    shape: Vec<usize>,
+    chunks: Vec<usize>,
+    attrs: Vec<(&'static str, String)>,
+    data: Vec<f64>,
+}
+
+impl Array {
+    // the index of each chunk along each dimension, in C order
+    fn chunk_indices(&self) -> Vec<Vec<usize>> {
+        let counts = self.shape.iter().zip(&self.chunks)
+          .map(|(&n, &c)| n.div_ceil(c))
+          .collect::<Vec<_>>();
+        let mut indices = vec![vec![]];
+        for &count in &counts {
+            indices = indices.into_iter()
+              .flat_map(|prefix| (0..count).map(move |k| {
+                  let mut index = prefix.clone();
+                  index.push(k);
+                  index
+              }))
+              .collect();
+        }
+        indices
+    }
+
+    // the bytes of a chunk, padded with NaN past the array's edges
+    fn chunk(&self, index: &[usize]) -> Vec<u8> {
+        let (rows, cols, chunk_rows, chunk_cols, i, j) = match index {
+            [i] => (1, self.shape[0], 1, self.chunks[0], 0, *i),
+            [i, j] => (self.shape[0], self.shape[1], self.chunks[0],
+              self.chunks[1], *i, *j),
+            _ => unreachable!("arrays are 1D or 2D"),
+        };
+        let mut bytes = Vec::with_capacity(chunk_rows * chunk_cols * 8);
+        for r in i * chunk_rows..(i + 1) * chunk_rows {
+            for c in j * chunk_cols..(j + 1) * chunk_cols {
+                let v = if r < rows && c < cols {
+                    self.data[r * cols + c]
+                } else {
+                    f64::NAN
+                };
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+fn write_v2(dir: &Path, group_attrs: &[(&str, String)], arrays: &[Array])
+  -> Result<(), Error> {
+    let mut metadata = vec![
+        (".zgroup".to_string(), "{\"zarr_format\":2}".to_string()),
+        (".zattrs".to_string(), json_object(group_attrs)),
+    ];
+    for array in arrays {
+        let zarray = format!("{{\"zarr_format\":2,\"shape\":{},\
+          \"chunks\":{},\"dtype\":\"<f8\",\"compressor\":null,\
+          \"fill_value\":\"NaN\",\"order\":\"C\",\"filters\":null,\
+          \"dimension_separator\":\".\"}}", json_sizes(&array.shape),
+          json_sizes(&array.chunks));
+        let mut attrs = array.attrs.clone();
+        attrs.push(("_ARRAY_DIMENSIONS", json_strings(&array.dims)));
+        metadata.push((format!("{}/.zarray", array.name), zarray));
+        metadata.push((format!("{}/.zattrs", array.name),
+          json_object(&attrs)));
+
+        let array_dir = dir.join(array.name);
+        fs::create_dir_all(&array_dir)?;
+        for index in array.chunk_indices() {
+            let key = index.iter().map(|k| k.to_string())
+              .collect::<Vec<_>>()
+              .join(".");
+            fs::write(array_dir.join(key), array.chunk(&index))?;
+        }
+    }
+
+    for (key, json) in &metadata {
+        fs::write(dir.join(key), json)?;
+    }
+    let consolidated = metadata.iter()
+      .map(|(key, json)| format!("{}:{}", json_string(key), json))
+      .collect::<Vec<_>>()
+      .join(",");
+    fs::write(dir.join(".zmetadata"), format!("{{\"metadata\":{{{}}},\
+      \"zarr_consolidated_format\":1}}", consolidated))?;
+    Ok(())
+}
+
+fn write_v3(dir: &Path, group_attrs: &[(&str, String)], arrays: &[Array])
+  -> Result<(), Error> {
+    fs::write(dir.join("zarr.json"), format!("{{\"zarr_format\":3,\
+      \"node_type\":\"group\",\"attributes\":{}}}",
+      json_object(group_attrs)))?;
+    for array in arrays {
+        let array_dir = dir.join(array.name);
+        fs::create_dir_all(&array_dir)?;
+        fs::write(array_dir.join("zarr.json"), format!("{{\
+          \"zarr_format\":3,\"node_type\":\"array\",\"shape\":{},\
+          \"data_type\":\"float64\",\"chunk_grid\":{{\"name\":\"regular\",\
+          \"configuration\":{{\"chunk_shape\":{}}}}},\
+          \"chunk_key_encoding\":{{\"name\":\"default\",\
+          \"configuration\":{{\"separator\":\"/\"}}}},\
+          \"fill_value\":\"NaN\",\"codecs\":[{{\"name\":\"bytes\",\
+          \"configuration\":{{\"endian\":\"little\"}}}}],\
+          \"attributes\":{},\"dimension_names\":{}}}",
+          json_sizes(&array.shape), json_sizes(&array.chunks),
+          json_object(&array.attrs), json_strings(&array.dims)))?;
+
+        for index in array.chunk_indices() {
+            let mut path = array_dir.join("c");
+            for k in &index[..index.len() - 1] {
+                path.push(k.to_string());
+            }
+            fs::create_dir_all(&path)?;
+            /* safety: arrays always have at least one dimension */
+            path.push(index.last().unwrap().to_string());
+            fs::write(path, array.chunk(&index))?;
+        }
+    }
+    Ok(())
+}
+
+fn json_object(attrs: &[(&str, String)]) -> String {
+    let members = attrs.iter()
+      .map(|(name, value)| format!("{}:{}", json_string(name), value))
+      .collect::<Vec<_>>();
+    format!("{{{}}}", members.join(","))
+}
+
+fn json_sizes(ns: &[usize]) -> String {
+    let ns = ns.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    format!("[{}]", ns.join(","))
+}
+
+fn json_strings(ss: &[&str]) -> String {
+    let ss = ss.iter().map(|s| json_string(s)).collect::<Vec<_>>();
+    format!("[{}]", ss.join(","))
+}
+
+// non-finite numbers aren't JSON
+fn json_number(v: f64) -> String {
+    if v.is_finite() { v.to_string() } else { "null".to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::small_plane;
+
+    fn doubles(bytes: &[u8]) -> Vec<f64> {
+        bytes.chunks(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+          .collect()
+    }
+
+    fn scratch(version: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("petra_grid-zarr-{}-{}", version,
+          std::process::id()))
+    }
+
+    #[test]
+    fn v2_metadata_and_chunks_read_back() {
+        let dir = scratch("v2");
+        write_zarr(&small_plane(), &dir, &ZarrOptions::new().chunks(2, 3))
+          .expect("write Zarr v2 store");
+
+        let zarray = fs::read_to_string(dir.join("z/.zarray")).unwrap();
+        assert!(zarray.contains("\"shape\":[3,4],\"chunks\":[2,3],\
+          \"dtype\":\"<f8\""));
+        let first = doubles(&fs::read(dir.join("z/0.0")).unwrap());
+        assert_eq!(first, [1260.0, 1266.25, 1272.5, 1285.0, 1291.25, 1297.5]);
+        // the last chunk is padded past the array's edges
+        let last = doubles(&fs::read(dir.join("z/1.1")).unwrap());
+        assert_eq!(last[0], 1328.75);
+        assert!(last[1..].iter().all(|z| z.is_nan()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn v3_metadata_and_chunks_read_back() {
+        let dir = scratch("v3");
+        write_zarr(&small_plane(), &dir,
+          &ZarrOptions::new().version(ZarrVersion::V3).chunks(2, 3))
+          .expect("write Zarr v3 store");
+
+        let zarr_json = fs::read_to_string(dir.join("z/zarr.json")).unwrap();
+        assert!(zarr_json.contains("\"shape\":[3,4],\
+          \"data_type\":\"float64\""));
+        assert!(zarr_json.contains("\"chunk_shape\":[2,3]"));
+        let first = doubles(&fs::read(dir.join("z/c/0/0")).unwrap());
+        assert_eq!(first, [1260.0, 1266.25, 1272.5, 1285.0, 1291.25, 1297.5]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
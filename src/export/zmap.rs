@@ -0,0 +1,156 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ZMAP+ ASCII grids
+//!
+//! a header of comma-separated records (the grid's name, field width, null
+//! value, and lattice) is followed by the nodes column by column from west
+//! to east, each column from north to south, a few fixed-width fields to a
+//! line. triangular grids are rasterized first (see [Grid::rasterize])
+//!
+//! the null value, field width, decimal places, and nodes per line can be
+//! adjusted with [ZmapOptions]
+
+use std::io::{BufWriter, Write};
+
+use crate::{Error, Grid, GridData, export::Exporter};
+
+/// formatting options for ZMAP+ exports; see [write_zmap_with]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ZmapOptions {
+    null_value: f64,
+    field_width: usize,
+    decimals: usize,
+    nodes_per_line: usize,
+}
+
+impl ZmapOptions {
+    /// the value written for missing nodes (by default, `1e30`)
+    pub fn null_value(self, null_value: f64) -> Self {
+        Self { null_value, ..self }
+    }
+
+    /// the width of each value's field (by default, 15 characters); values
+    /// too wide for their field are still separated by a space
+    pub fn field_width(self, field_width: usize) -> Self {
+        Self { field_width, ..self }
+    }
+
+    /// the number of digits after the decimal point (by default, 7)
+    pub fn decimals(self, decimals: usize) -> Self {
+        Self { decimals, ..self }
+    }
+
+    /// the number of nodes written to a line (by default, 5)
+    pub fn nodes_per_line(self, nodes_per_line: usize) -> Self {
+        Self { nodes_per_line: nodes_per_line.max(1), ..self }
+    }
+}
+
+impl Default for ZmapOptions {
+    fn default() -> ZmapOptions {
+        ZmapOptions {
+            null_value: 1e30,
+            field_width: 15,
+            decimals: 7,
+            nodes_per_line: 5,
+        }
+    }
+}
+
+/// write a grid as a ZMAP+ ASCII grid, with the default options
+pub fn write_zmap<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    write_zmap_with(grid, out, &ZmapOptions::default())
+}
+
+/// write a grid as a ZMAP+ ASCII grid, formatted according to `options`
+pub fn write_zmap_with<W: Write + ?Sized>(grid: &Grid, out: &mut W,
+  options: &ZmapOptions) -> Result<(), Error> {
+    let raster;
+    let grid = match grid.data {
+        GridData::Rectangular(_) => grid,
+        GridData::Triangular(_) => {
+            raster = grid.rasterize();
+            &raster
+        },
+    };
+    let arr = grid.rectangular()?;
+    let (rows, cols) = arr.dim();
+
+    // commas would break the header's fields
+    let name = match grid.name.replace(',', " ").trim() {
+        "" => "GRID".to_string(),
+        name => name.to_string(),
+    };
+    let xmax = grid.xmin + cols.saturating_sub(1) as f64 * grid.xstep;
+    let ymax = grid.ymin + rows.saturating_sub(1) as f64 * grid.ystep;
+
+    let mut out = BufWriter::new(out);
+    writeln!(out, "! exported from Petra grid {}", name)?;
+    writeln!(out, "@{}, GRID, {}", name, options.nodes_per_line)?;
+    writeln!(out, "{}, {:E}, , {}, 1", options.field_width,
+      options.null_value, options.decimals)?;
+    writeln!(out, "{}, {}, {}, {}, {}, {}", rows, cols, grid.xmin, xmax,
+      grid.ymin, ymax)?;
+    writeln!(out, "0.0, 0.0, 0.0")?;
+    writeln!(out, "@")?;
+
+    for i in 0..cols {
+        for (k, j) in (0..rows).rev().enumerate() {
+            let z = arr[(j, i)];
+            /* fixed-point notation makes a mess of the usual huge null
+             * values, so they're written in scientific notation */
+            let field = if z.is_nan() {
+                format!("{:E}", options.null_value)
+            } else {
+                format!("{:.*}", options.decimals, z)
+            };
+            if field.len() >= options.field_width {
+                write!(out, " ")?;
+            }
+            write!(out, "{:>w$}", field, w = options.field_width)?;
+            if (k + 1) % options.nodes_per_line == 0 || k + 1 == rows {
+                writeln!(out)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// ZMAP+ ASCII grid, as an [Exporter]; see [write_zmap]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Zmap;
+
+impl Exporter for Zmap {
+    fn name(&self) -> &str {
+        "zmap"
+    }
+
+    fn extension(&self) -> &str {
+        "zmap"
+    }
+
+    fn export(&self, grid: &Grid, out: &mut dyn Write) -> Result<(), Error> {
+        write_zmap(grid, out)
+    }
+}
@@ -0,0 +1,170 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! focal (moving-window) statistics over rectangular grids: the minimum,
+//! maximum, mean, range, or standard deviation of the values around each
+//! node, complementing [Grid::smooth] with non-linear statistics
+//!
+//! windows are measured in nodes, and are clipped to the edges of the grid;
+//! missing values in a window are ignored (see [FocalWindow] for how few
+//! values are too few). with the `rayon` feature enabled, rows are computed
+//! in parallel
+
+use crate::{Error, Grid, GridData, attributes::map_nodes};
+
+/// a statistic computed over each node's window; see [Grid::focal]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FocalStatistic {
+    /// the minimum value
+    Min,
+    /// the maximum value
+    Max,
+    /// the mean value
+    Mean,
+    /// the maximum less the minimum
+    Range,
+    /// the (population) standard deviation
+    StdDev,
+}
+
+/// the neighborhood around each node over which a focal statistic is
+/// computed, and how missing values are treated
+#[derive(Clone, Debug, PartialEq)]
+pub struct FocalWindow {
+    // (row, column) offsets from the center node
+    offsets: Vec<(isize, isize)>,
+    min_count: usize,
+    fill_nulls: bool,
+}
+
+impl FocalWindow {
+    /// a (2 × `radius` + 1)-node square window
+    pub fn square(radius: usize) -> FocalWindow {
+        FocalWindow::rectangle(radius, radius)
+    }
+
+    /// a (2 × `row_radius` + 1) rows by (2 × `column_radius` + 1) columns
+    /// window
+    pub fn rectangle(row_radius: usize, column_radius: usize) -> FocalWindow {
+        let (rr, cr) = (row_radius as isize, column_radius as isize);
+        FocalWindow::from_offsets((-rr..=rr)
+          .flat_map(|dj| (-cr..=cr).map(move |di| (dj, di)))
+          .collect())
+    }
+
+    /// a roughly circular window of the nodes within `radius` node spacings
+    /// of the center (in index space, so it's elliptical in map space if
+    /// the *x* and *y* steps differ)
+    pub fn circle(radius: f64) -> FocalWindow {
+        let r = radius.max(0.0).floor() as isize;
+        FocalWindow::from_offsets((-r..=r)
+          .flat_map(|dj| (-r..=r).map(move |di| (dj, di)))
+          .filter(|&(dj, di)| ((dj * dj + di * di) as f64).sqrt() <= radius)
+          .collect())
+    }
+
+    fn from_offsets(offsets: Vec<(isize, isize)>) -> FocalWindow {
+        FocalWindow { offsets, min_count: 1, fill_nulls: false }
+    }
+
+    /// require at least this many non-missing values in a window (by
+    /// default, one) for a result; nodes with fewer are missing
+    pub fn min_count(self, min_count: usize) -> Self {
+        Self { min_count: min_count.max(1), ..self }
+    }
+
+    /// compute results at missing nodes too, filling them from their
+    /// neighbors (by default, missing nodes stay missing)
+    pub fn fill_nulls(self, fill_nulls: bool) -> Self {
+        Self { fill_nulls, ..self }
+    }
+
+    /// the number of nodes in the window (before clipping to the grid)
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// is the window empty?
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+}
+
+impl Grid {
+    /// a grid of a focal statistic over each node's window, keeping this
+    /// grid's metadata; only rectangular grids are supported
+    pub fn focal(&self, statistic: FocalStatistic, window: &FocalWindow)
+      -> Result<Grid, Error> {
+        let arr = match &self.data {
+            GridData::Rectangular(arr) => arr,
+            _ => return Err(Error::NotRectangular),
+        };
+        let (rows, cols) = arr.dim();
+
+        let result = map_nodes(rows, cols, |j, i| {
+            if arr[(j, i)].is_nan() && !window.fill_nulls {
+                return f64::NAN;
+            }
+
+            let mut n = 0;
+            let mut min = f64::INFINITY;
+            let mut max = f64::NEG_INFINITY;
+            // Welford's algorithm, as for Stats
+            let mut mean = 0.0;
+            let mut m2 = 0.0;
+            for &(dj, di) in &window.offsets {
+                let (jj, ii) = (j as isize + dj, i as isize + di);
+                if jj < 0 || ii < 0 || jj >= rows as isize
+                  || ii >= cols as isize {
+                    continue;
+                }
+                let z = arr[(jj as usize, ii as usize)];
+                if z.is_nan() {
+                    continue;
+                }
+                n += 1;
+                min = min.min(z);
+                max = max.max(z);
+                let delta = z - mean;
+                mean += delta / n as f64;
+                m2 += delta * (z - mean);
+            }
+
+            if n < window.min_count {
+                return f64::NAN;
+            }
+            match statistic {
+                FocalStatistic::Min => min,
+                FocalStatistic::Max => max,
+                FocalStatistic::Mean => mean,
+                FocalStatistic::Range => max - min,
+                FocalStatistic::StdDev => (m2 / n as f64).sqrt(),
+            }
+        });
+
+        let mut header = self.header();
+        let data = GridData::Rectangular(result.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Ok(Grid::from_parts(header, data))
+    }
+}
@@ -0,0 +1,171 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! an in-memory cache of grids read from files
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufReader,
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{Error, Grid, GridData};
+
+/// a least-recently-used cache of grids read from files, bounded by an
+/// (approximate) memory budget
+///
+/// cached grids are keyed on their path, and are re-read whenever the file's
+/// modification time or size changes; this is aimed at long-running services
+/// which serve many requests from the same handful of grids
+///
+/// the cache isn't itself synchronized: to share one between threads, wrap
+/// it in a [std::sync::Mutex]; grids are handed out as [Arc]s, so the lock
+/// need only be held for the lookup
+#[derive(Debug)]
+pub struct GridCache {
+    budget: usize,
+    used: usize,
+    clock: u64,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    mtime: SystemTime,
+    len: u64,
+    grid: Arc<Grid>,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl GridCache {
+    /// an empty cache which will hold up to approximately `budget` bytes of
+    /// grids
+    pub fn new(budget: usize) -> GridCache {
+        GridCache {
+            budget,
+            used: 0,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// get the grid stored at a path, from the cache if it's present and
+    /// the file is unchanged, or by reading it otherwise
+    ///
+    /// grids larger than the whole budget are read and returned, but never
+    /// cached
+    pub fn get<P: AsRef<Path>>(&mut self, path: P) -> Result<Arc<Grid>, Error> {
+        let path = path.as_ref();
+        let meta = fs::metadata(path)?;
+        let mtime = meta.modified()?;
+        let len = meta.len();
+        self.clock += 1;
+
+        if let Some(entry) = self.entries.get_mut(path) {
+            if entry.mtime == mtime && entry.len == len {
+                entry.last_used = self.clock;
+                return Ok(Arc::clone(&entry.grid));
+            }
+        }
+        self.remove(path);
+
+        let grid = Arc::new(Grid::read(&mut BufReader::new(File::open(path)?))?);
+        let bytes = grid.memory_size();
+        if bytes <= self.budget {
+            while self.used + bytes > self.budget {
+                self.evict_lru();
+            }
+            self.used += bytes;
+            self.entries.insert(path.to_owned(), Entry {
+                mtime,
+                len,
+                grid: Arc::clone(&grid),
+                bytes,
+                last_used: self.clock,
+            });
+        }
+        Ok(grid)
+    }
+
+    /// drop the grid stored at a path from the cache, if present
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) {
+        if let Some(entry) = self.entries.remove(path.as_ref()) {
+            self.used -= entry.bytes;
+        }
+    }
+
+    /// drop all grids from the cache
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+
+    /// the number of grids in the cache
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// is the cache empty?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the approximate memory, in bytes, used by the cached grids
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    /// the memory budget, in bytes
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    fn evict_lru(&mut self) {
+        let lru = self.entries.iter()
+          .min_by_key(|(_, e)| e.last_used)
+          .map(|(p, _)| p.clone());
+        if let Some(path) = lru {
+            self.remove(path);
+        }
+    }
+}
+
+impl Grid {
+    /// the approximate memory, in bytes, used by the grid
+    pub fn memory_size(&self) -> usize {
+        let n_values = match &self.data {
+            GridData::Rectangular(arr) => arr.len(),
+            GridData::Triangular(arr) => arr.len(),
+        };
+        mem::size_of::<Grid>()
+          + n_values * mem::size_of::<f64>()
+          + self.name.len()
+          + self.source_data.len()
+          + self.unknown_metadata.len()
+          + self.projection.len()
+          + self.datum.len()
+    }
+}
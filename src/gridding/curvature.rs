@@ -0,0 +1,376 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! minimum-curvature gridding, with optional tension, after Smith & Wessel
+//! (1990), "Gridding with continuous curvature splines in tension"
+
+use ndarray::Array2;
+
+use crate::{Error, Grid};
+
+use super::{
+    Lattice,
+    multigrid::{Energy, Solver},
+};
+
+/// parameters for minimum-curvature gridding of scattered points, which
+/// yields the smoothest surface (in the sense of least total squared
+/// curvature) honoring the data; see [MinimumCurvature::grid]
+///
+/// the surface solves (1 − *T*)∇⁴*z* − *T*∇²*z* = 0 between data points,
+/// where *T* is the tension: 0 gives a pure minimum-curvature (biharmonic)
+/// spline, which may overshoot between widely-spaced data, while values
+/// approaching 1 pull the surface taut, suppressing spurious highs and lows
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MinimumCurvature {
+    /// the tension, from 0 (inclusive) to 1 (exclusive)
+    pub tension: f64,
+
+    /// the largest number of conjugate-gradient iterations to make at each
+    /// level of refinement
+    pub max_iterations: usize,
+
+    /// iteration stops when the residual of the equations falls to this
+    /// fraction of the data's pull on the surface (the residual of a
+    /// surface which is zero away from the data), and the data nodes'
+    /// corrections for their offsets from the data points settle to within
+    /// this fraction of the data's *z* range
+    pub tolerance: f64,
+}
+
+impl Default for MinimumCurvature {
+    /// tension 0.25 (a common choice for geological surfaces), at most 2000
+    /// iterations per level, and a tolerance of 10<sup>-6</sup>
+    fn default() -> MinimumCurvature {
+        MinimumCurvature {
+            tension: 0.25,
+            max_iterations: 2000,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// how well a minimum-curvature solution converged; see
+/// [MinimumCurvature::grid_with_convergence]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Convergence {
+    /// the number of conjugate-gradient iterations made, over all levels of
+    /// refinement
+    pub iterations: usize,
+
+    /// the residual on the finest lattice, relative to the data's pull on
+    /// the surface (see [MinimumCurvature::tolerance])
+    pub residual: f64,
+
+    /// whether the solution on the finest lattice met the tolerance within
+    /// the iteration limit
+    pub converged: bool,
+}
+
+// the most times to re-solve a level after correcting the data nodes for
+// their offsets from the data points
+const MAX_CORRECTIONS: usize = 100;
+
+impl MinimumCurvature {
+    /// grid scattered (*x*, *y*, *z*) points onto a lattice (points with
+    /// missing coordinates or values are ignored), failing with
+    /// [Error::NotConverged] if the solution doesn't meet the tolerance
+    /// within the iteration limit
+    ///
+    /// each point constrains its nearest node (where several points share a
+    /// node, the closest wins), with the node's value corrected for the
+    /// point's offset using the local slope of the surface; the lattice
+    /// spacing should be fine enough to separate the data, and points off
+    /// the lattice are ignored
+    ///
+    /// the surface minimizes a discrete form of the energy whose
+    /// Euler-Lagrange equation is the one above, with free edges, by
+    /// conjugate gradients; the solution is found on successively finer
+    /// lattices (halving the spacing each time), each level starting from
+    /// the last, so that even large grids converge in a modest number of
+    /// iterations
+    ///
+    /// metadata other than the geometry and *z* range takes default values;
+    /// see [Grid::new_rectangular]
+    pub fn grid(&self, points: &[(f64, f64, f64)], lattice: &Lattice)
+      -> Result<Grid, Error> {
+        let (grid, convergence) = self.grid_with_convergence(points, lattice);
+        if convergence.converged {
+            Ok(grid)
+        } else {
+            Err(Error::NotConverged(convergence.iterations,
+              convergence.residual))
+        }
+    }
+
+    /// grid scattered points as [MinimumCurvature::grid] does, returning
+    /// the surface even if it didn't converge, along with how well it did
+    pub fn grid_with_convergence(&self, points: &[(f64, f64, f64)],
+      lattice: &Lattice) -> (Grid, Convergence) {
+        let points = points.iter()
+          .copied()
+          .filter(|(x, y, z)| !(x.is_nan() || y.is_nan() || z.is_nan()))
+          .collect::<Vec<_>>();
+
+        let (zmin, zmax) = points.iter()
+          .fold((f64::INFINITY, f64::NEG_INFINITY),
+            |(lo, hi), p| (lo.min(p.2), hi.max(p.2)));
+        let range = (zmax - zmin).max(f64::MIN_POSITIVE);
+
+        /* as in GMT's surface, the least-squares plane through the data is
+         * removed before gridding and restored afterward, so that the
+         * tension (which resists any slope at the free edges) doesn't bend
+         * a regional dip, and planar data are reproduced exactly */
+        let trend = Trend::fit(&points);
+        let points = points.iter()
+          .map(|&(x, y, z)| (x, y, z - trend.z(x, y)))
+          .collect::<Vec<_>>();
+
+        let mut convergence = Convergence {
+            iterations: 0,
+            residual: 0.0,
+            converged: true,
+        };
+
+        if lattice.rows == 0 || lattice.columns == 0 {
+            let grid = Grid::new_rectangular(lattice.xmin, lattice.ymin,
+              lattice.xstep, lattice.ystep, Array2::zeros((0, 0)));
+            return (grid, convergence);
+        }
+
+        let span = lattice.rows.max(lattice.columns).saturating_sub(1);
+        let mut stride = 1;
+        while stride * 8 <= span {
+            stride *= 2;
+        }
+
+        let mut z: Option<(Lattice, Array2<f64>)> = None;
+        loop {
+            let level = Lattice {
+                xmin: lattice.xmin,
+                ymin: lattice.ymin,
+                xstep: lattice.xstep * stride as f64,
+                ystep: lattice.ystep * stride as f64,
+                rows: lattice.rows.saturating_sub(1) / stride + 1,
+                columns: lattice.columns.saturating_sub(1) / stride + 1,
+            };
+            let mut level_z = match &z {
+                Some((coarse, coarse_z)) =>
+                    Array2::from_shape_fn((level.rows, level.columns),
+                      |(j, i)| {
+                          let (x, y) = level.node_xy(j, i);
+                          bilinear(coarse, coarse_z, x, y)
+                      }),
+                None => Array2::zeros((level.rows, level.columns)),
+            };
+            let (iterations, residual, converged) =
+              self.solve(&points, &level, &mut level_z, range);
+            convergence = Convergence {
+                iterations: convergence.iterations + iterations,
+                residual,
+                converged,
+            };
+            z = Some((level, level_z));
+            if stride == 1 {
+                break;
+            }
+            stride /= 2;
+        }
+
+        let (_, mut data) = z.expect("at least one level");
+        for ((j, i), z) in data.indexed_iter_mut() {
+            let (x, y) = lattice.node_xy(j, i);
+            *z += trend.z(x, y);
+        }
+        let grid = Grid::new_rectangular(lattice.xmin, lattice.ymin,
+          lattice.xstep, lattice.ystep, data);
+        (grid, convergence)
+    }
+
+    // solve on one lattice, with the data (whose z range is given) held
+    // fixed at their nearest nodes, starting from (and updating) z; returns
+    // the number of iterations, the relative residual, and whether it
+    // converged
+    fn solve(&self, points: &[(f64, f64, f64)], lattice: &Lattice,
+      z: &mut Array2<f64>, range: f64) -> (usize, f64, bool) {
+        let settled = self.tolerance * range;
+        let (rows, cols) = (lattice.rows, lattice.columns);
+        if points.is_empty() || rows == 0 || cols == 0 {
+            return (0, 0.0, true);
+        }
+        /* data which (after removing the trend) are flat to within the
+         * tolerance leave nothing to solve for */
+        if points.iter().all(|p| p.2.abs() <= settled) {
+            z.fill(0.0);
+            return (0, 0.0, true);
+        }
+
+        /* the nearest data point to each node, if any, as its value and its
+         * offset from the node (in units of the node spacing) */
+        let mut fixed: Array2<Option<(f64, f64, f64)>> =
+          Array2::from_elem((rows, cols), None);
+        for &(x, y, pz) in points {
+            let fi = ((x - lattice.xmin) / lattice.xstep).round();
+            let fj = ((y - lattice.ymin) / lattice.ystep).round();
+            if fi < 0.0 || fj < 0.0 || fi >= cols as f64 || fj >= rows as f64 {
+                continue;
+            }
+            let (i, j) = (fi as usize, fj as usize);
+            let (nx, ny) = lattice.node_xy(j, i);
+            let (di, dj) = ((x - nx) / lattice.xstep, (y - ny) / lattice.ystep);
+            match fixed[(j, i)] {
+                Some((_, bi, bj)) if bi * bi + bj * bj <= di * di + dj * dj =>
+                    { },
+                _ => fixed[(j, i)] = Some((pz, di, dj)),
+            }
+        }
+
+        /* as in Smith & Wessel, distances are measured in units of the node
+         * spacing along x, so that the tension means the same thing at
+         * every level of refinement and for every lattice */
+        let energy = Energy {
+            curvature: 1.0 - self.tension,
+            tension: self.tension,
+            hx: 1.0,
+            hy: lattice.ystep / lattice.xstep,
+        };
+        let solver = Solver::new(energy, fixed.map(Option::is_none));
+
+        let mut iterations = 0;
+        let mut residual = 0.0;
+        for _ in 0..MAX_CORRECTIONS {
+            let mut correction: f64 = 0.0;
+            for ((j, i), node) in fixed.indexed_iter() {
+                if let Some(point) = *node {
+                    let value = corrected(z, (j, i), point);
+                    correction = correction.max((value - z[(j, i)]).abs());
+                    z[(j, i)] = value;
+                }
+            }
+
+            /* while the corrections are still large, there's no point
+             * solving to the full tolerance */
+            let rough = (0.01 * correction / range).max(self.tolerance);
+            let (used, relative) = solver.solve(z, rough,
+              self.max_iterations - iterations);
+            iterations += used;
+            residual = relative;
+
+            let resolved = relative <= self.tolerance;
+            if resolved && correction <= settled {
+                return (iterations, residual, true);
+            }
+            if iterations >= self.max_iterations {
+                break;
+            }
+        }
+        (iterations, residual, false)
+    }
+}
+
+// a plane z = z0 + dzdx (x − x0) + dzdy (y − y0), fit by least squares
+struct Trend {
+    x0: f64,
+    y0: f64,
+    z0: f64,
+    dzdx: f64,
+    dzdy: f64,
+}
+
+impl Trend {
+    // fit a plane to points, or a constant if they're collinear; with no
+    // points, the "plane" is missing everywhere
+    fn fit(points: &[(f64, f64, f64)]) -> Trend {
+        let n = points.len() as f64;
+        let (x0, y0, z0) = points.iter().fold((0.0, 0.0, 0.0),
+          |(x0, y0, z0), &(x, y, z)| (x0 + x / n, y0 + y / n, z0 + z / n));
+        let (mut sxx, mut sxy, mut syy, mut sxz, mut syz) =
+          (0.0, 0.0, 0.0, 0.0, 0.0);
+        for &(x, y, z) in points {
+            let (dx, dy, dz) = (x - x0, y - y0, z - z0);
+            sxx += dx * dx;
+            sxy += dx * dy;
+            syy += dy * dy;
+            sxz += dx * dz;
+            syz += dy * dz;
+        }
+        let det = sxx * syy - sxy * sxy;
+        if det > 1e-12 * sxx * syy {
+            Trend {
+                x0,
+                y0,
+                z0,
+                dzdx: (sxz * syy - syz * sxy) / det,
+                dzdy: (syz * sxx - sxz * sxy) / det,
+            }
+        } else {
+            Trend { x0, y0, z0, dzdx: 0.0, dzdy: 0.0 }
+        }
+    }
+
+    fn z(&self, x: f64, y: f64) -> f64 {
+        self.z0 + self.dzdx * (x - self.x0) + self.dzdy * (y - self.y0)
+    }
+}
+
+// the value at a data node which puts the surface through its data point,
+// offset (di, dj) node spacings away, given the slope there by central
+// differences; at the edges, the one-sided differences involve the node's
+// own value, which is solved for (with the divisor floored, so that a point
+// halfway between the nodes at a corner can't blow it up)
+fn corrected(z: &Array2<f64>, (j, i): (usize, usize),
+  (pz, di, dj): (f64, f64, f64)) -> f64 {
+    let (rows, cols) = z.dim();
+    /* the slope along one axis, split into the part not involving the node
+     * and the coefficient of the node's own value */
+    let axis = |at: usize, n: usize, z: &dyn Fn(usize) -> f64| {
+        let (lo, hi) = (at.saturating_sub(1), (at + 1).min(n - 1));
+        if lo == hi {
+            return (0.0, 0.0);
+        }
+        let span = (hi - lo) as f64;
+        let (mut rest, mut own) = (0.0, 0.0);
+        if hi == at { own += 1.0 / span } else { rest += z(hi) / span }
+        if lo == at { own -= 1.0 / span } else { rest -= z(lo) / span }
+        (rest, own)
+    };
+    let (rest_i, own_i) = axis(i, cols, &|k| z[(j, k)]);
+    let (rest_j, own_j) = axis(j, rows, &|k| z[(k, i)]);
+    (pz - di * rest_i - dj * rest_j)
+      / (1.0 + di * own_i + dj * own_j).max(0.25)
+}
+
+// bilinear interpolation on a lattice, clamped to its extent
+fn bilinear(lattice: &Lattice, z: &Array2<f64>, x: f64, y: f64) -> f64 {
+    let (rows, cols) = z.dim();
+    let fi = ((x - lattice.xmin) / lattice.xstep)
+      .clamp(0.0, cols.saturating_sub(1) as f64);
+    let fj = ((y - lattice.ymin) / lattice.ystep)
+      .clamp(0.0, rows.saturating_sub(1) as f64);
+    let i0 = (fi.floor() as usize).min(cols.saturating_sub(2));
+    let j0 = (fj.floor() as usize).min(rows.saturating_sub(2));
+    let i1 = (i0 + 1).min(cols - 1);
+    let j1 = (j0 + 1).min(rows - 1);
+    let (ti, tj) = (fi - i0 as f64, fj - j0 as f64);
+    let south = z[(j0, i0)] * (1.0 - ti) + z[(j0, i1)] * ti;
+    let north = z[(j1, i0)] * (1.0 - ti) + z[(j1, i1)] * ti;
+    south * (1.0 - tj) + north * tj
+}
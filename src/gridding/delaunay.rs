@@ -0,0 +1,364 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! incremental (Bowyer-Watson) Delaunay triangulation of scattered points,
+//! for natural-neighbor interpolation
+//!
+//! coordinates are translated and scaled to roughly the unit square before
+//! triangulating, to keep the (inexact) geometric predicates well-behaved
+
+use std::collections::HashMap;
+
+// how far, in multiples of the points' extent, the super-triangle's
+// vertices are placed from their center
+const SUPER_SCALE: f64 = 100.0;
+
+// give up walking toward a point after this many steps, and search instead
+const MAX_WALK: usize = 10_000;
+
+#[derive(Clone, Debug)]
+struct Triangle {
+    // vertex indices, counterclockwise
+    v: [usize; 3],
+    // the neighbor across the edge opposite each vertex
+    n: [Option<usize>; 3],
+    alive: bool,
+}
+
+/// a Delaunay triangulation of a set of (*x*, *y*, *z*) points
+#[derive(Clone, Debug)]
+pub(crate) struct Delaunay {
+    // normalized coordinates; the last three are the super-triangle's
+    xy: Vec<(f64, f64)>,
+    z: Vec<f64>,
+    tris: Vec<Triangle>,
+    center: (f64, f64),
+    scale: f64,
+}
+
+/// how a point relates to a triangulation
+pub(crate) enum Location {
+    /// the point coincides with a vertex
+    Vertex(usize),
+    /// the point lies within (or on an edge of) a triangle of real points
+    Inside(usize),
+    /// the point lies outside the convex hull of the real points
+    Outside,
+}
+
+impl Delaunay {
+    /// triangulate points, skipping any with missing coordinates or values;
+    /// points with the same (*x*, *y*) are merged, averaging their *z*
+    pub(crate) fn new(points: &[(f64, f64, f64)]) -> Delaunay {
+        let mut merged: HashMap<(u64, u64), (f64, f64, f64, usize)> =
+          HashMap::new();
+        let mut order = Vec::new();
+        for &(x, y, z) in points {
+            if x.is_nan() || y.is_nan() || z.is_nan() {
+                continue;
+            }
+            let key = ((x + 0.0).to_bits(), (y + 0.0).to_bits());
+            let entry = merged.entry(key).or_insert_with(|| {
+                order.push(key);
+                (x, y, 0.0, 0)
+            });
+            entry.2 += z;
+            entry.3 += 1;
+        }
+        let mut points = order.iter()
+          .map(|k| {
+              let (x, y, z, n) = merged[k];
+              (x, y, z / n as f64)
+          })
+          .collect::<Vec<_>>();
+
+        let (mut xmin, mut xmax) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut ymin, mut ymax) = (f64::INFINITY, f64::NEG_INFINITY);
+        for &(x, y, _) in &points {
+            xmin = xmin.min(x);
+            xmax = xmax.max(x);
+            ymin = ymin.min(y);
+            ymax = ymax.max(y);
+        }
+        let center = if points.is_empty() {
+            (0.0, 0.0)
+        } else {
+            ((xmin + xmax) / 2.0, (ymin + ymax) / 2.0)
+        };
+        let scale = (xmax - xmin).max(ymax - ymin);
+        let scale = if scale.is_finite() && scale > 0.0 { scale } else { 1.0 };
+
+        /* insert in a "snake" order through bands of the plane, so that each
+         * point is usually found by a short walk from the last one */
+        let bands = (points.len() as f64).sqrt().ceil().max(1.0);
+        let band = |y: f64| ((y - ymin) / scale * bands).floor() as i64;
+        points.sort_by(|a, b| {
+            let (ba, bb) = (band(a.1), band(b.1));
+            ba.cmp(&bb).then_with(|| {
+                let ord = a.0.total_cmp(&b.0);
+                if ba % 2 == 0 { ord } else { ord.reverse() }
+            })
+        });
+
+        let mut xy = points.iter()
+          .map(|&(x, y, _)| ((x - center.0) / scale, (y - center.1) / scale))
+          .collect::<Vec<_>>();
+        let z = points.iter().map(|p| p.2).collect::<Vec<_>>();
+
+        let n = xy.len();
+        xy.push((-3.0 * SUPER_SCALE, -3.0 * SUPER_SCALE));
+        xy.push((3.0 * SUPER_SCALE, -3.0 * SUPER_SCALE));
+        xy.push((0.0, 3.0 * SUPER_SCALE));
+
+        let mut tri = Delaunay {
+            xy,
+            z,
+            tris: vec![Triangle {
+                v: [n, n + 1, n + 2],
+                n: [None; 3],
+                alive: true,
+            }],
+            center,
+            scale,
+        };
+
+        let mut last = 0;
+        for p in 0..n {
+            last = tri.insert(p, last);
+        }
+        tri
+    }
+
+    /// the number of real (not super-triangle) points
+    pub(crate) fn len(&self) -> usize {
+        self.z.len()
+    }
+
+    /// the *z* value of a point
+    pub(crate) fn z(&self, v: usize) -> f64 {
+        self.z[v]
+    }
+
+    /// map a point into normalized coordinates
+    pub(crate) fn normalize(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.center.0) / self.scale, (y - self.center.1) / self.scale)
+    }
+
+    /// the normalized coordinates of a vertex
+    pub(crate) fn xy(&self, v: usize) -> (f64, f64) {
+        self.xy[v]
+    }
+
+    /// the vertices of a triangle, counterclockwise
+    pub(crate) fn vertices(&self, t: usize) -> [usize; 3] {
+        self.tris[t].v
+    }
+
+    /// is this vertex one of the super-triangle's?
+    pub(crate) fn is_super(&self, v: usize) -> bool {
+        v >= self.len()
+    }
+
+    /// locate a (normalized) point in the triangulation
+    pub(crate) fn locate(&self, p: (f64, f64)) -> Location {
+        let t = match self.find(p, self.any_triangle()) {
+            Some(t) => t,
+            None => return Location::Outside,
+        };
+        for &v in &self.tris[t].v {
+            if self.xy[v] == p {
+                return Location::Vertex(v);
+            }
+        }
+        if self.tris[t].v.iter().any(|&v| self.is_super(v)) {
+            Location::Outside
+        } else {
+            Location::Inside(t)
+        }
+    }
+
+    /// the triangles whose circumcircles strictly contain a (normalized)
+    /// point, found by searching outward from a triangle containing it
+    pub(crate) fn cavity(&self, p: (f64, f64), start: usize) -> Vec<usize> {
+        let mut bad = vec![start];
+        let mut seen = vec![start];
+        let mut i = 0;
+        while i < bad.len() {
+            for nb in self.tris[bad[i]].n.into_iter().flatten() {
+                if !seen.contains(&nb) {
+                    seen.push(nb);
+                    if self.in_circumcircle(nb, p) {
+                        bad.push(nb);
+                    }
+                }
+            }
+            i += 1;
+        }
+        bad
+    }
+
+    fn any_triangle(&self) -> usize {
+        self.tris.iter().rposition(|t| t.alive).unwrap_or(0)
+    }
+
+    // insert point p, starting the search at triangle `hint`; returns a
+    // triangle incident to the new point
+    fn insert(&mut self, p: usize, hint: usize) -> usize {
+        let pxy = self.xy[p];
+        let start = match self.find(pxy, hint) {
+            Some(t) => t,
+            None => return hint,
+        };
+
+        let mut bad = self.cavity(pxy, start);
+        /* rounding can make the cavity non-star-shaped around p, which would
+         * tangle the new triangles; shrink it until every boundary edge
+         * faces p */
+        loop {
+            let boundary = self.boundary(&bad);
+            let hidden = boundary.iter()
+              .find(|&&(a, b, _, _)| orient(self.xy[a], self.xy[b], pxy) <= 0.0)
+              .map(|&(_, _, _, inner)| inner);
+            match hidden {
+                Some(t) if t != start && bad.len() > 1 =>
+                    bad.retain(|&u| u != t),
+                _ => break,
+            }
+        }
+
+        let boundary = self.boundary(&bad);
+        for &t in &bad {
+            self.tris[t].alive = false;
+        }
+
+        let first_new = self.tris.len();
+        let mut by_first = HashMap::new();
+        let mut by_second = HashMap::new();
+        for &(a, b, outer, inner) in &boundary {
+            let t = self.tris.len();
+            self.tris.push(Triangle {
+                v: [a, b, p],
+                n: [None, None, outer],
+                alive: true,
+            });
+            if let Some(o) = outer {
+                for slot in self.tris[o].n.iter_mut() {
+                    if *slot == Some(inner) {
+                        *slot = Some(t);
+                    }
+                }
+            }
+            by_first.insert(a, t);
+            by_second.insert(b, t);
+        }
+        for t in first_new..self.tris.len() {
+            let [a, b, _] = self.tris[t].v;
+            // across (b, p): the new triangle starting at b
+            self.tris[t].n[0] = by_first.get(&b).copied();
+            // across (p, a): the new triangle ending at a
+            self.tris[t].n[1] = by_second.get(&a).copied();
+        }
+
+        first_new
+    }
+
+    // the boundary edges (a, b, outer neighbor, inner triangle) of a cavity,
+    // each counterclockwise with respect to the cavity
+    fn boundary(&self, bad: &[usize])
+      -> Vec<(usize, usize, Option<usize>, usize)> {
+        let mut edges = Vec::new();
+        for &t in bad {
+            let tri = &self.tris[t];
+            for i in 0..3 {
+                let outer = tri.n[i];
+                if outer.map(|o| !bad.contains(&o)).unwrap_or(true) {
+                    edges.push((tri.v[(i + 1) % 3], tri.v[(i + 2) % 3],
+                      outer, t));
+                }
+            }
+        }
+        edges
+    }
+
+    // find a live triangle containing p, walking from `start`
+    fn find(&self, p: (f64, f64), start: usize) -> Option<usize> {
+        let mut t = if self.tris.get(start).map(|t| t.alive).unwrap_or(false) {
+            start
+        } else {
+            self.any_triangle()
+        };
+
+        'walk: for _ in 0..MAX_WALK {
+            let tri = &self.tris[t];
+            for i in 0..3 {
+                let a = self.xy[tri.v[(i + 1) % 3]];
+                let b = self.xy[tri.v[(i + 2) % 3]];
+                if orient(a, b, p) < 0.0 {
+                    match tri.n[i] {
+                        Some(next) => {
+                            t = next;
+                            continue 'walk;
+                        },
+                        None => return None,
+                    }
+                }
+            }
+            return Some(t);
+        }
+
+        // the walk went astray; fall back to checking every triangle
+        self.tris.iter().position(|tri| tri.alive && (0..3).all(|i| {
+            let a = self.xy[tri.v[(i + 1) % 3]];
+            let b = self.xy[tri.v[(i + 2) % 3]];
+            orient(a, b, p) >= 0.0
+        }))
+    }
+
+    fn in_circumcircle(&self, t: usize, p: (f64, f64)) -> bool {
+        let [a, b, c] = self.tris[t].v;
+        let (a, b, c) = (self.xy[a], self.xy[b], self.xy[c]);
+        let (adx, ady) = (a.0 - p.0, a.1 - p.1);
+        let (bdx, bdy) = (b.0 - p.0, b.1 - p.1);
+        let (cdx, cdy) = (c.0 - p.0, c.1 - p.1);
+        let det = (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+          - (bdx * bdx + bdy * bdy) * (adx * cdy - cdx * ady)
+          + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady);
+        det > 0.0
+    }
+}
+
+/// twice the signed area of triangle (a, b, c): positive when
+/// counterclockwise
+pub(crate) fn orient(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// the center of the circle through three points (non-finite if they're
+/// collinear)
+pub(crate) fn circumcenter(a: (f64, f64), b: (f64, f64), c: (f64, f64))
+  -> (f64, f64) {
+    let (bx, by) = (b.0 - a.0, b.1 - a.1);
+    let (cx, cy) = (c.0 - a.0, c.1 - a.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    (a.0 + (cy * b2 - by * c2) / d, a.1 + (bx * c2 - cx * b2) / d)
+}
@@ -0,0 +1,140 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! distance-to-control and confidence grids, for data-quality maps; see
+//! [Grid::distance_to_points] and [Grid::confidence]
+
+use ndarray::Array2;
+
+use crate::{Error, Grid, GridData, UnitOfMeasure};
+
+impl Grid {
+    /// a grid, on the same lattice as this one, of the distance from each
+    /// node to the nearest of the given (*x*, *y*) points (such as well
+    /// locations), in *x*/*y* units
+    ///
+    /// points with missing coordinates are ignored; if there are no points,
+    /// every node is missing. descriptive metadata (name, projection, and so
+    /// on) is kept, and the *z* units become the *x*/*y* units
+    pub fn distance_to_points(&self, points: &[(f64, f64)]) -> Grid {
+        let control = Control::new(points.iter().map(|&(x, y)| (x, y, 0.0)));
+        let units = self.xyunits;
+        self.lattice_grid(units, |x, y| {
+            control.nearest(x, y).map_or(f64::NAN, |(d, _)| d)
+        })
+    }
+
+    /// a grid, on the same lattice as this one, of confidence in the surface
+    /// given (*x*, *y*, *z*) control points (such as well tops) it should
+    /// honor, from 1 (at a control point which the surface matches) toward 0
+    ///
+    /// confidence decays exponentially with the distance to the nearest
+    /// control point (falling by a factor of *e* every `range` *x*/*y*
+    /// units), and with the absolute residual (the control point's *z* less
+    /// the surface's, at that point) of that control point (by a factor of
+    /// *e* every `tolerance` *z* units). control points where the surface
+    /// is missing (or which lie outside it) count as matched; points with
+    /// missing coordinates are ignored, and if there are none, every node is
+    /// missing
+    pub fn confidence(&self, control: &[(f64, f64, f64)], range: f64,
+      tolerance: f64) -> Result<Grid, Error> {
+        if !(range > 0.0 && tolerance > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+              "confidence range ({}) and tolerance ({}) must be positive",
+              range, tolerance)));
+        }
+        let control = Control::new(control.iter().map(|&(x, y, z)| {
+            let residual = match self.sample(x, y) {
+                Some(surface) if !surface.is_nan() => (z - surface).abs(),
+                _ => 0.0,
+            };
+            (x, y, residual)
+        }));
+        Ok(self.lattice_grid(UnitOfMeasure::Other(0), |x, y| {
+            control.nearest(x, y).map_or(f64::NAN, |(d, residual)| {
+                (-d / range - residual / tolerance).exp()
+            })
+        }))
+    }
+
+    // a grid on this grid's lattice, with values from (x, y)
+    fn lattice_grid<F: Fn(f64, f64) -> f64>(&self, zunits: UnitOfMeasure,
+      f: F) -> Grid {
+        let (rows, cols) = (self.rows as usize, self.columns as usize);
+        let data = Array2::from_shape_fn((rows, cols), |(j, i)| {
+            f(self.xmin + i as f64 * self.xstep,
+              self.ymin + j as f64 * self.ystep)
+        });
+
+        let mut header = self.header();
+        header.n_triangles = 0;
+        header.size = (rows * cols) as u32;
+        header.zunits = zunits;
+        let data = GridData::Rectangular(data.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Grid::from_parts(header, data)
+    }
+}
+
+// control points, each with a value, sorted by x
+struct Control(Vec<(f64, f64, f64)>);
+
+impl Control {
+    fn new<I: Iterator<Item = (f64, f64, f64)>>(points: I) -> Control {
+        let mut points = points
+          .filter(|(x, y, _)| !(x.is_nan() || y.is_nan()))
+          .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Control(points)
+    }
+
+    /* the distance from (x, y) to the nearest point, and its value: we work
+     * outward from x in both directions, stopping once the x offset alone
+     * exceeds the best distance so far */
+    fn nearest(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let mut best: Option<(f64, f64)> = None;
+        let mut consider = |&(px, py, v): &(f64, f64, f64)| {
+            let dx = (px - x).abs();
+            if best.is_some_and(|(d, _)| dx >= d) {
+                return false;
+            }
+            let d = dx.hypot(py - y);
+            if best.is_none_or(|(b, _)| d < b) {
+                best = Some((d, v));
+            }
+            true
+        };
+        let start = self.0.partition_point(|p| p.0 < x);
+        for p in &self.0[start..] {
+            if !consider(p) {
+                break;
+            }
+        }
+        for p in self.0[..start].iter().rev() {
+            if !consider(p) {
+                break;
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,306 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! gridding: interpolation of scattered (*x*, *y*, *z*) points, such as
+//! well tops, onto a regular lattice, and resampling of existing grids onto
+//! new lattices
+//!
+//! pointwise methods ([Interpolation]) are used via [Grid::from_points] and
+//! [Grid::resample]; minimum-curvature gridding, which solves for the whole
+//! surface at once, via [MinimumCurvature::grid]
+//!
+//! distance-to-control and confidence grids, for judging how well data
+//! constrain a surface, are generated with [Grid::distance_to_points] and
+//! [Grid::confidence]
+//!
+//! existing grids are tied to control points, such as well tops, with
+//! [Grid::honor_points]
+
+use ndarray::Array2;
+
+use crate::Grid;
+
+mod curvature;
+pub use curvature::{Convergence, MinimumCurvature};
+mod delaunay;
+mod distance;
+mod multigrid;
+mod tie;
+pub use tie::{ControlTie, ResidualCorrection};
+use delaunay::{Delaunay, Location, circumcenter, orient};
+
+/// the regular lattice of nodes on which a grid is generated
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lattice {
+    /// the *x* coordinate of the westmost column of nodes
+    pub xmin: f64,
+
+    /// the *y* coordinate of the southmost row of nodes
+    pub ymin: f64,
+
+    /// the spacing of nodes along *x*
+    pub xstep: f64,
+
+    /// the spacing of nodes along *y*
+    pub ystep: f64,
+
+    /// the number of rows of nodes
+    pub rows: usize,
+
+    /// the number of columns of nodes
+    pub columns: usize,
+}
+
+impl Default for Lattice {
+    /// a small 20 × 30 lattice, spaced 100 units apart, with its lower-left
+    /// node at (1000, 2000)
+    fn default() -> Lattice {
+        Lattice {
+            xmin: 1000.0,
+            ymin: 2000.0,
+            xstep: 100.0,
+            ystep: 100.0,
+            rows: 20,
+            columns: 30,
+        }
+    }
+}
+
+impl Lattice {
+    /// the *x* and *y* coordinates of the node at (row, column)
+    pub fn node_xy(&self, row: usize, column: usize) -> (f64, f64) {
+        (self.xmin + column as f64 * self.xstep,
+         self.ymin + row as f64 * self.ystep)
+    }
+}
+
+/// methods for interpolating scattered points
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    /// inverse-distance weighting, with weights proportional to
+    /// 1 / distance<sup>`power`</sup> (2 is the usual choice); defined
+    /// everywhere, but prone to "bullseyes" around sparse control
+    InverseDistance { /** the exponent applied to distances */ power: f64 },
+
+    /// Sibson's natural-neighbor interpolation over the Delaunay
+    /// triangulation of the points, which honors the data exactly and
+    /// closely follows triangulation-based gridding; missing (NaN) outside
+    /// the convex hull of the points
+    ///
+    /// near the hull, where a point's natural neighbors would include the
+    /// unbounded Voronoi cells of hull vertices, we don't clip the cells;
+    /// there (and at points we can't place precisely enough in the
+    /// triangulation), values are interpolated linearly within the
+    /// enclosing Delaunay triangle instead, which agrees with Sibson's
+    /// interpolation on the hull itself
+    NaturalNeighbor,
+}
+
+/// an interpolant of a set of scattered points, which can be evaluated
+/// anywhere
+#[derive(Clone, Debug)]
+pub struct Interpolator {
+    method: Interpolation,
+    points: Vec<(f64, f64, f64)>,
+    delaunay: Option<Delaunay>,
+}
+
+impl Interpolator {
+    /// prepare to interpolate the given points (points with missing
+    /// coordinates or values are ignored)
+    pub fn new(points: &[(f64, f64, f64)], method: Interpolation)
+      -> Interpolator {
+        let points = points.iter()
+          .copied()
+          .filter(|(x, y, z)| !(x.is_nan() || y.is_nan() || z.is_nan()))
+          .collect::<Vec<_>>();
+        let delaunay = match method {
+            Interpolation::NaturalNeighbor => Some(Delaunay::new(&points)),
+            Interpolation::InverseDistance { .. } => None,
+        };
+        Interpolator { method, points, delaunay }
+    }
+
+    /// the interpolated *z* value at (*x*, *y*), or NaN where the method
+    /// gives no value
+    pub fn z(&self, x: f64, y: f64) -> f64 {
+        match (self.method, &self.delaunay) {
+            (Interpolation::InverseDistance { power }, _) =>
+                inverse_distance(&self.points, power, x, y),
+            (Interpolation::NaturalNeighbor, Some(delaunay)) =>
+                natural_neighbor(delaunay, x, y),
+            (Interpolation::NaturalNeighbor, None) =>
+                unreachable!("natural-neighbor interpolator without triangulation"),
+        }
+    }
+}
+
+impl Grid {
+    /// grid scattered (*x*, *y*, *z*) points onto a lattice, using the
+    /// given interpolation method; nodes where the method gives no value
+    /// are missing (NaN)
+    ///
+    /// metadata other than the geometry and *z* range takes default values;
+    /// see [Grid::new_rectangular]
+    pub fn from_points(points: &[(f64, f64, f64)], lattice: &Lattice,
+      method: Interpolation) -> Grid {
+        let interpolator = Interpolator::new(points, method);
+        let data = Array2::from_shape_fn((lattice.rows, lattice.columns),
+          |(j, i)| {
+              let (x, y) = lattice.node_xy(j, i);
+              interpolator.z(x, y)
+          });
+        Grid::new_rectangular(lattice.xmin, lattice.ymin, lattice.xstep,
+          lattice.ystep, data)
+    }
+
+    /// resample this grid onto a new lattice, treating its (non-missing)
+    /// nodes or triangle vertices as scattered points; see
+    /// [Grid::from_points]
+    ///
+    /// descriptive metadata (name, units, projection, and so on) is kept
+    pub fn resample(&self, lattice: &Lattice, method: Interpolation) -> Grid {
+        let points = self.points().collect::<Vec<_>>();
+        let (resampled, data) = Grid::from_points(&points, lattice, method)
+          .into_parts();
+        /* start from the whole original header, so that metadata added to
+         * it later are kept too, and take only the geometry from the new
+         * lattice */
+        let mut header = self.header();
+        header.size = resampled.size;
+        header.rows = resampled.rows;
+        header.columns = resampled.columns;
+        header.n_triangles = resampled.n_triangles;
+        header.xmin = resampled.xmin;
+        header.xmax = resampled.xmax;
+        header.ymin = resampled.ymin;
+        header.ymax = resampled.ymax;
+        header.xstep = resampled.xstep;
+        header.ystep = resampled.ystep;
+        header.zmin = resampled.zmin;
+        header.zmax = resampled.zmax;
+        Grid::from_parts(header, data)
+    }
+}
+
+fn inverse_distance(points: &[(f64, f64, f64)], power: f64, x: f64, y: f64)
+  -> f64 {
+    let mut num = 0.0;
+    let mut den = 0.0;
+    for &(px, py, pz) in points {
+        let d2 = (px - x).powi(2) + (py - y).powi(2);
+        if d2 == 0.0 {
+            return pz;
+        }
+        let w = d2.powf(-power / 2.0);
+        num += w * pz;
+        den += w;
+    }
+    if den > 0.0 { num / den } else { f64::NAN }
+}
+
+// a tiny nudge (in normalized coordinates) for query points lying on or very
+// near triangle edges, where the natural-neighbor weights involve collinear
+// (infinite, or hopelessly imprecise) circumcenters
+const NUDGE: (f64, f64) = (1e-7, 1.3e-7);
+
+// how close (relative to an edge's length) a query point must be to the
+// edge's line to count as lying on it
+const COLLINEAR: f64 = 1e-9;
+
+fn natural_neighbor(delaunay: &Delaunay, x: f64, y: f64) -> f64 {
+    let p = delaunay.normalize(x, y);
+    let t = match delaunay.locate(p) {
+        Location::Vertex(v) => return delaunay.z(v),
+        Location::Inside(t) => t,
+        Location::Outside => return f64::NAN,
+    };
+
+    sibson(delaunay, p, t)
+      .or_else(|| {
+          let nudged = (p.0 + NUDGE.0, p.1 + NUDGE.1);
+          match delaunay.locate(nudged) {
+              Location::Inside(t) => sibson(delaunay, nudged, t),
+              _ => None,
+          }
+      })
+      .unwrap_or_else(|| linear(delaunay, p, t))
+}
+
+/* Sibson's coordinates, by Watson's method: the area each natural neighbor
+ * would lose to the query point's Voronoi cell is accumulated over the
+ * triangles whose circumcircles contain the point, each contributing the
+ * signed area between its circumcenter and those of the new triangles the
+ * point would form with its edges
+ *
+ * points on (or nearly on) a cavity triangle's edge yield None, to be
+ * retried nearby; near the convex hull, the cavity can reach the
+ * super-triangle, whose vertices have unbounded cells; there, we return None
+ * and fall back to linear interpolation (see Interpolation::NaturalNeighbor) */
+fn sibson(delaunay: &Delaunay, p: (f64, f64), t: usize) -> Option<f64> {
+    let cavity = delaunay.cavity(p, t);
+    let mut weights: Vec<(usize, f64)> = Vec::new();
+    for &c in &cavity {
+        let v = delaunay.vertices(c);
+        if v.iter().any(|&u| delaunay.is_super(u)) {
+            return None;
+        }
+        let xy = v.map(|u| delaunay.xy(u));
+        for k in 0..3 {
+            let (a, b) = (xy[k], xy[(k + 1) % 3]);
+            let len2 = (b.0 - a.0).powi(2) + (b.1 - a.1).powi(2);
+            if orient(a, b, p).abs() <= COLLINEAR * len2 {
+                return None;
+            }
+        }
+        let g = circumcenter(xy[0], xy[1], xy[2]);
+        for k in 0..3 {
+            let (a, b, c) = (xy[k], xy[(k + 1) % 3], xy[(k + 2) % 3]);
+            let m_ab = circumcenter(p, a, b);
+            let m_ca = circumcenter(p, c, a);
+            let area = orient(m_ab, g, m_ca) / 2.0;
+            match weights.iter_mut().find(|(u, _)| *u == v[k]) {
+                Some((_, w)) => *w += area,
+                None => weights.push((v[k], area)),
+            }
+        }
+    }
+
+    let total = weights.iter().map(|(_, w)| w).sum::<f64>();
+    let z = weights.iter()
+      .map(|&(u, w)| w * delaunay.z(u))
+      .sum::<f64>() / total;
+    if z.is_finite() && total.abs() > 0.0 {
+        Some(z)
+    } else {
+        None
+    }
+}
+
+fn linear(delaunay: &Delaunay, p: (f64, f64), t: usize) -> f64 {
+    let [a, b, c] = delaunay.vertices(t);
+    let (pa, pb, pc) = (delaunay.xy(a), delaunay.xy(b), delaunay.xy(c));
+    let area = orient(pa, pb, pc);
+    let wa = orient(pb, pc, p) / area;
+    let wb = orient(pc, pa, p) / area;
+    let wc = 1.0 - wa - wb;
+    wa * delaunay.z(a) + wb * delaunay.z(b) + wc * delaunay.z(c)
+}
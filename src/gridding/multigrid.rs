@@ -0,0 +1,428 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! a multigrid-preconditioned conjugate-gradient solver for the discrete
+//! minimum-curvature energy, with some nodes held fixed
+//!
+//! the energy's Hessian is never assembled: it's applied term by term, and
+//! each coarser level of the multigrid hierarchy rediscretizes the energy
+//! on a lattice with (up to) twice the spacing, holding fixed the coarse
+//! nodes nearest the fine level's fixed nodes
+
+use ndarray::{Array2, Zip};
+
+// the discrete energy (1 − T)(z_xx² + 2z_xy² + z_yy²) + T(z_x² + z_y²),
+// integrated over a lattice with the given node spacing
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Energy {
+    pub curvature: f64,
+    pub tension: f64,
+    pub hx: f64,
+    pub hy: f64,
+}
+
+// the weights of the energy's terms: z_xx², z_yy², z_xy², z_x², and z_y²
+struct Weights {
+    xx: f64,
+    yy: f64,
+    xy: f64,
+    x: f64,
+    y: f64,
+}
+
+impl Energy {
+    fn weights(&self) -> Weights {
+        let (hx, hy, c, t) = (self.hx, self.hy, self.curvature, self.tension);
+        Weights {
+            xx: c * hy / (hx * hx * hx),
+            yy: c * hx / (hy * hy * hy),
+            xy: 2.0 * c / (hx * hy),
+            x: t * hy / hx,
+            y: t * hx / hy,
+        }
+    }
+
+    // visit each term of the energy on a rows × cols lattice, as its
+    // weight and the coefficients of the nodes in its finite difference
+    fn terms<F: FnMut(f64, &[((usize, usize), f64)])>(&self, rows: usize,
+      cols: usize, mut visit: F) {
+        let w = self.weights();
+        for j in 0..rows {
+            for i in 1..cols.saturating_sub(1) {
+                visit(w.xx,
+                  &[((j, i - 1), 1.0), ((j, i), -2.0), ((j, i + 1), 1.0)]);
+            }
+        }
+        for j in 1..rows.saturating_sub(1) {
+            for i in 0..cols {
+                visit(w.yy,
+                  &[((j - 1, i), 1.0), ((j, i), -2.0), ((j + 1, i), 1.0)]);
+            }
+        }
+        for j in 0..rows.saturating_sub(1) {
+            for i in 0..cols.saturating_sub(1) {
+                visit(w.xy,
+                  &[((j, i), 1.0), ((j, i + 1), -1.0), ((j + 1, i), -1.0),
+                    ((j + 1, i + 1), 1.0)]);
+            }
+        }
+        for j in 0..rows {
+            for i in 0..cols.saturating_sub(1) {
+                visit(w.x, &[((j, i), -1.0), ((j, i + 1), 1.0)]);
+            }
+        }
+        for j in 0..rows.saturating_sub(1) {
+            for i in 0..cols {
+                visit(w.y, &[((j, i), -1.0), ((j + 1, i), 1.0)]);
+            }
+        }
+    }
+
+    // the product of the energy's Hessian (up to a factor of 2) with v;
+    // this is where the solver spends its time, so it works on the flat
+    // (row-major) node values rather than visiting the terms
+    fn apply(&self, v: &Array2<f64>, out: &mut Array2<f64>) {
+        let (rows, cols) = v.dim();
+        let w = self.weights();
+        let v = v.as_slice().expect("row-major lattice");
+        let out = out.as_slice_mut().expect("row-major lattice");
+        out.fill(0.0);
+
+        for j in 0..rows {
+            let row = j * cols;
+            for k in row + 1..row + cols.saturating_sub(1) {
+                let d = w.xx * (v[k - 1] - 2.0 * v[k] + v[k + 1]);
+                out[k - 1] += d;
+                out[k] -= 2.0 * d;
+                out[k + 1] += d;
+            }
+            for k in row..row + cols.saturating_sub(1) {
+                let d = w.x * (v[k + 1] - v[k]);
+                out[k] -= d;
+                out[k + 1] += d;
+            }
+        }
+        for k in cols..(rows.saturating_sub(1)) * cols {
+            let d = w.yy * (v[k - cols] - 2.0 * v[k] + v[k + cols]);
+            out[k - cols] += d;
+            out[k] -= 2.0 * d;
+            out[k + cols] += d;
+        }
+        for k in 0..(rows.saturating_sub(1)) * cols {
+            let d = w.y * (v[k + cols] - v[k]);
+            out[k] -= d;
+            out[k + cols] += d;
+        }
+        for j in 0..rows.saturating_sub(1) {
+            let row = j * cols;
+            for k in row..row + cols.saturating_sub(1) {
+                let d = w.xy
+                  * (v[k] - v[k + 1] - v[k + cols] + v[k + cols + 1]);
+                out[k] += d;
+                out[k + 1] -= d;
+                out[k + cols] -= d;
+                out[k + cols + 1] += d;
+            }
+        }
+    }
+
+    // the diagonal of the Hessian (up to a factor of 2)
+    fn diagonal(&self, rows: usize, cols: usize) -> Array2<f64> {
+        let mut diag = Array2::zeros((rows, cols));
+        self.terms(rows, cols, |weight, nodes| {
+            for &(node, c) in nodes {
+                diag[node] += weight * c * c;
+            }
+        });
+        diag
+    }
+}
+
+// damping factor and number of sweeps for the Jacobi smoother
+const SMOOTHING_DAMPING: f64 = 0.5;
+const SMOOTHING_SWEEPS: usize = 3;
+
+// the most free nodes to solve directly on the coarsest level
+const MAX_COARSEST: usize = 256;
+
+struct Level {
+    energy: Energy,
+    free: Array2<bool>,
+    inverse_diagonal: Array2<f64>,
+    // whether the rows and columns are halved going to the next level
+    halved: (bool, bool),
+}
+
+// the Cholesky factor of the Hessian over the free nodes of the coarsest
+// level, in row-major lower-triangular form
+struct Coarsest {
+    nodes: Vec<(usize, usize)>,
+    factor: Vec<f64>,
+}
+
+pub(super) struct Solver {
+    levels: Vec<Level>,
+    coarsest: Coarsest,
+}
+
+impl Solver {
+    // a solver for the energy on a lattice whose free nodes are flagged
+    pub fn new(energy: Energy, free: Array2<bool>) -> Solver {
+        let mut levels = Vec::new();
+        let (mut energy, mut free) = (energy, free);
+        loop {
+            let (rows, cols) = free.dim();
+            let halved = (rows >= 5, cols >= 5);
+            let n_free = free.iter().filter(|&&f| f).count();
+            let inverse_diagonal = energy.diagonal(rows, cols)
+              .mapv(|d| if d > 0.0 { 1.0 / d } else { 0.0 });
+            if n_free <= MAX_COARSEST || halved == (false, false) {
+                let coarsest = Coarsest::new(&energy, &free);
+                levels.push(Level { energy, free, inverse_diagonal,
+                  halved: (false, false) });
+                return Solver { levels, coarsest };
+            }
+
+            let coarse_dim = (coarse_len(rows, halved.0),
+              coarse_len(cols, halved.1));
+            let mut coarse_free = Array2::from_elem(coarse_dim, true);
+            for ((j, i), &f) in free.indexed_iter() {
+                if !f {
+                    let (pj, pi) = (parents(j, halved.0)[0].0,
+                      parents(i, halved.1)[0].0);
+                    coarse_free[(pj, pi)] = false;
+                }
+            }
+            let coarse_energy = Energy {
+                hx: if halved.1 { 2.0 * energy.hx } else { energy.hx },
+                hy: if halved.0 { 2.0 * energy.hy } else { energy.hy },
+                ..energy
+            };
+            levels.push(Level { energy, free, inverse_diagonal, halved });
+            energy = coarse_energy;
+            free = coarse_free;
+        }
+    }
+
+    // minimize the energy over the free nodes of z, holding the rest fixed;
+    // stops when the residual falls to the given fraction of the fixed
+    // nodes' pull on the free ones (or after the given number of
+    // iterations), returning the number of iterations and the relative
+    // residual
+    pub fn solve(&self, z: &mut Array2<f64>, tolerance: f64,
+      max_iterations: usize) -> (usize, f64) {
+        let level = &self.levels[0];
+        let dot = |a: &Array2<f64>, b: &Array2<f64>| Zip::from(a).and(b)
+          .fold(0.0, |acc, x, y| acc + x * y);
+
+        let mut pull = z.clone();
+        Zip::from(&mut pull).and(&level.free)
+          .for_each(|v, &f| if f { *v = 0.0 });
+        let mut scratch = Array2::zeros(z.dim());
+        level.apply(&pull, &mut scratch);
+        let scale = dot(&scratch, &scratch).sqrt();
+        if scale == 0.0 {
+            return (0, 0.0);
+        }
+
+        level.apply(z, &mut scratch);
+        let mut r = -scratch.clone();
+        let mut s = self.precondition(0, &r);
+        let mut p = s.clone();
+        let mut rs = dot(&r, &s);
+
+        for iteration in 0..max_iterations {
+            let relative = dot(&r, &r).sqrt() / scale;
+            if relative <= tolerance {
+                return (iteration, relative);
+            }
+            level.apply(&p, &mut scratch);
+            let pap = dot(&p, &scratch);
+            if pap <= 0.0 || rs <= 0.0 {
+                return (iteration, relative);
+            }
+            let alpha = rs / pap;
+            z.scaled_add(alpha, &p);
+            r.scaled_add(-alpha, &scratch);
+            s = self.precondition(0, &r);
+            let rs_next = dot(&r, &s);
+            p = &s + &(p * (rs_next / rs));
+            rs = rs_next;
+        }
+        (max_iterations, dot(&r, &r).sqrt() / scale)
+    }
+
+    // approximately solve A e = r on a level by one multigrid V-cycle
+    fn precondition(&self, k: usize, r: &Array2<f64>) -> Array2<f64> {
+        let level = &self.levels[k];
+        if k + 1 == self.levels.len() {
+            return self.coarsest.solve(r);
+        }
+
+        let mut e = Array2::zeros(r.dim());
+        let mut scratch = Array2::zeros(r.dim());
+        level.smooth(&mut e, r, &mut scratch);
+
+        level.apply(&e, &mut scratch);
+        let residual = r - &scratch;
+        let next = &self.levels[k + 1];
+        let mut coarse = restrict(&residual, next.free.dim(), level.halved);
+        next.mask(&mut coarse);
+        let correction = self.precondition(k + 1, &coarse);
+        let mut fine = prolong(&correction, r.dim(), level.halved);
+        level.mask(&mut fine);
+        e += &fine;
+
+        level.smooth(&mut e, r, &mut scratch);
+        e
+    }
+}
+
+impl Level {
+    // the Hessian applied to v, over the free nodes
+    fn apply(&self, v: &Array2<f64>, out: &mut Array2<f64>) {
+        self.energy.apply(v, out);
+        self.mask(out);
+    }
+
+    fn mask(&self, a: &mut Array2<f64>) {
+        Zip::from(a).and(&self.free)
+          .for_each(|v, &f| if !f { *v = 0.0 });
+    }
+
+    // damped Jacobi sweeps toward the solution of A e = r
+    fn smooth(&self, e: &mut Array2<f64>, r: &Array2<f64>,
+      scratch: &mut Array2<f64>) {
+        for _ in 0..SMOOTHING_SWEEPS {
+            self.apply(e, scratch);
+            Zip::from(&mut *e).and(r).and(&*scratch).and(&self.inverse_diagonal)
+              .for_each(|e, &r, &ae, &d| *e += SMOOTHING_DAMPING * d * (r - ae));
+        }
+    }
+}
+
+impl Coarsest {
+    fn new(energy: &Energy, free: &Array2<bool>) -> Coarsest {
+        let nodes = free.indexed_iter()
+          .filter_map(|(node, &f)| f.then_some(node))
+          .collect::<Vec<_>>();
+        let n = nodes.len();
+
+        let mut matrix = vec![0.0; n * n];
+        let mut unit = Array2::zeros(free.dim());
+        let mut column = Array2::zeros(free.dim());
+        for (b, &node) in nodes.iter().enumerate() {
+            unit[node] = 1.0;
+            energy.apply(&unit, &mut column);
+            unit[node] = 0.0;
+            for (a, &other) in nodes.iter().enumerate() {
+                matrix[a * n + b] = column[other];
+            }
+        }
+
+        /* the Hessian is only semi-definite when too few nodes are fixed to
+         * pin down a plane, so tiny pivots are floored; the solution's
+         * component along the null space is then arbitrary (but finite),
+         * which conjugate gradients tolerate */
+        let largest = (0..n).map(|a| matrix[a * n + a]).fold(0.0, f64::max);
+        let floor = 1e-12 * largest.max(f64::MIN_POSITIVE);
+        for a in 0..n {
+            for b in 0..=a {
+                let sum = matrix[a * n + b] - (0..b)
+                  .map(|k| matrix[a * n + k] * matrix[b * n + k])
+                  .sum::<f64>();
+                matrix[a * n + b] = if a == b {
+                    sum.max(floor).sqrt()
+                } else {
+                    sum / matrix[b * n + b]
+                };
+            }
+        }
+        Coarsest { nodes, factor: matrix }
+    }
+
+    fn solve(&self, r: &Array2<f64>) -> Array2<f64> {
+        let (n, l) = (self.nodes.len(), &self.factor);
+        let mut x = self.nodes.iter().map(|&node| r[node]).collect::<Vec<_>>();
+        for a in 0..n {
+            let sum = (0..a).map(|k| l[a * n + k] * x[k]).sum::<f64>();
+            x[a] = (x[a] - sum) / l[a * n + a];
+        }
+        for a in (0..n).rev() {
+            let sum = (a + 1..n).map(|k| l[k * n + a] * x[k]).sum::<f64>();
+            x[a] = (x[a] - sum) / l[a * n + a];
+        }
+        let mut e = Array2::zeros(r.dim());
+        for (&node, &v) in self.nodes.iter().zip(&x) {
+            e[node] = v;
+        }
+        e
+    }
+}
+
+// the length of an axis of n nodes on the next coarser level
+fn coarse_len(n: usize, halved: bool) -> usize {
+    if halved { (n - 1).div_ceil(2) + 1 } else { n }
+}
+
+// the coarse nodes (and weights) from which a fine node along one axis is
+// linearly interpolated; the first has the larger weight
+fn parents(f: usize, halved: bool) -> [(usize, f64); 2] {
+    if !halved {
+        [(f, 1.0), (f, 0.0)]
+    } else if f.is_multiple_of(2) {
+        [(f / 2, 1.0), (f / 2, 0.0)]
+    } else {
+        [(f / 2, 0.5), (f / 2 + 1, 0.5)]
+    }
+}
+
+// bilinear interpolation from a coarse level to a fine one
+fn prolong(coarse: &Array2<f64>, dim: (usize, usize), halved: (bool, bool))
+  -> Array2<f64> {
+    Array2::from_shape_fn(dim, |(j, i)| {
+        let mut v = 0.0;
+        for (pj, wj) in parents(j, halved.0) {
+            for (pi, wi) in parents(i, halved.1) {
+                if wj * wi != 0.0 {
+                    v += wj * wi * coarse[(pj, pi)];
+                }
+            }
+        }
+        v
+    })
+}
+
+// the transpose of [prolong]
+fn restrict(fine: &Array2<f64>, dim: (usize, usize), halved: (bool, bool))
+  -> Array2<f64> {
+    let mut coarse = Array2::zeros(dim);
+    for ((j, i), &v) in fine.indexed_iter() {
+        for (pj, wj) in parents(j, halved.0) {
+            for (pi, wi) in parents(i, halved.1) {
+                if wj * wi != 0.0 {
+                    coarse[(pj, pi)] += wj * wi * v;
+                }
+            }
+        }
+    }
+    coarse
+}
@@ -0,0 +1,298 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! adjusting existing grids to honor control points, such as well tops; see
+//! [Grid::honor_points]
+
+use ndarray::ArcArray2;
+
+use crate::{Error, Grid, GridData};
+
+use super::{Lattice, MinimumCurvature};
+
+/// the surface used to spread the misfits at control points across a grid
+/// when tying it to them; see [Grid::honor_points]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResidualCorrection {
+    /// a sum of compactly supported radial basis functions (Wendland's
+    /// *φ*<sub>3,1</sub>) centered on the control points, which passes
+    /// exactly through their misfits and falls smoothly to zero `radius`
+    /// *x*/*y* units from the nearest of them, so that the grid is left
+    /// untouched away from control; the radius should span several nodes
+    RadialBasis {
+        /// the radius of influence of each control point
+        radius: f64,
+    },
+
+    /// a minimum-curvature grid of the misfits (see [MinimumCurvature]) on
+    /// the grid's own lattice, which spreads corrections across the whole
+    /// grid; rectangular grids only
+    MinimumCurvature(MinimumCurvature),
+}
+
+/// how well a grid honored a control point, before and after being tied to
+/// it by [Grid::honor_points]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ControlTie {
+    /// the control point, as (*x*, *y*, *z*)
+    pub point: (f64, f64, f64),
+
+    /// the control point's *z* less the original grid's, at the point; none
+    /// if the grid is missing there (or the point lies outside it)
+    pub before: Option<f64>,
+
+    /// the control point's *z* less the adjusted grid's, at the point
+    pub after: Option<f64>,
+}
+
+// give up on honoring the control points exactly after this many rounds
+const MAX_TIE_ROUNDS: usize = 50;
+
+// control points are honored once misfits are within this fraction of the
+// grid's z range
+const TIE_TOLERANCE: f64 = 1e-9;
+
+impl Grid {
+    /// adjust the grid as little as it takes to honor the given (*x*, *y*,
+    /// *z*) control points (the "tie the grid to the tops" workflow), by
+    /// adding a correction surface which passes through the misfit (control
+    /// *z* less grid *z*) at each of them
+    ///
+    /// since the grid is sampled between nodes (bilinearly, or linearly
+    /// within triangles; see [Grid::sample]), a single correction can't
+    /// quite honor points which fall between nodes; the correction is
+    /// refined in further rounds, each fitted to the misfits left by the
+    /// last, until every misfit is negligible (or no further progress can be
+    /// made, e.g. for conflicting points within one cell). the misfits
+    /// before and after are returned for each control point, in order
+    ///
+    /// control points with missing coordinates or values, outside the grid,
+    /// or where it's missing are left out of the fit (and missing nodes stay
+    /// missing); of several at the same location, only the first counts.
+    /// metadata is kept, with the *z* range updated
+    pub fn honor_points(&self, control: &[(f64, f64, f64)],
+      correction: &ResidualCorrection)
+      -> Result<(Grid, Vec<ControlTie>), Error> {
+        match *correction {
+            ResidualCorrection::RadialBasis { radius }
+              if radius.is_nan() || radius <= 0.0 =>
+                return Err(Error::InvalidArgument(format!(
+                  "radius of influence ({}) must be positive", radius))),
+            ResidualCorrection::MinimumCurvature(_) =>
+                { self.rectangular()?; },
+            _ => { },
+        }
+
+        let misfits = |grid: &Grid| control.iter().map(|&(x, y, z)| {
+            match grid.sample(x, y) {
+                Some(surface) if !(surface.is_nan() || z.is_nan()) =>
+                    Some(z - surface),
+                _ => None,
+            }
+        }).collect::<Vec<_>>();
+
+        let before = misfits(self);
+        let mut active = Vec::new();
+        for (k, (&(x, y, _), misfit)) in control.iter().zip(&before)
+          .enumerate() {
+            let duplicate = active.iter()
+              .any(|&j: &usize| control[j].0 == x && control[j].1 == y);
+            if misfit.is_some() && !duplicate {
+                active.push(k);
+            }
+        }
+
+        let tolerance = TIE_TOLERANCE
+          * (self.zmax - self.zmin).abs().max(1.0);
+        let worst = |misfits: &[Option<f64>]| active.iter()
+          .filter_map(|&k| misfits[k])
+          .fold(0.0, |worst: f64, m| worst.max(m.abs()));
+
+        let mut grid = self.clone();
+        let mut current = before.clone();
+        for _ in 0..MAX_TIE_ROUNDS {
+            let last_worst = worst(&current);
+            if last_worst <= tolerance {
+                break;
+            }
+            let residuals = active.iter()
+              .filter_map(|&k| {
+                  let (x, y, _) = control[k];
+                  current[k].map(|m| (x, y, m))
+              })
+              .collect::<Vec<_>>();
+            let mut adjusted = grid.clone();
+            adjusted.add_correction(&residuals, correction)?;
+            let next = misfits(&adjusted);
+            if worst(&next) >= last_worst {
+                break;
+            }
+            grid = adjusted;
+            current = next;
+        }
+
+        let ties = control.iter().zip(before).zip(current)
+          .map(|((&point, before), after)| ControlTie { point, before, after })
+          .collect();
+        Ok((grid, ties))
+    }
+
+    // add a surface through the given (x, y, residual) points to every
+    // non-missing node or vertex
+    fn add_correction(&mut self, residuals: &[(f64, f64, f64)],
+      correction: &ResidualCorrection) -> Result<(), Error> {
+        let surface = match *correction {
+            ResidualCorrection::RadialBasis { radius } =>
+                Surface::Radial(RadialBasis::fit(residuals, radius)?),
+            ResidualCorrection::MinimumCurvature(mc) => {
+                let (rows, columns) = self.rectangular()?.dim();
+                let lattice = Lattice {
+                    xmin: self.xmin,
+                    ymin: self.ymin,
+                    xstep: self.xstep,
+                    ystep: self.ystep,
+                    rows,
+                    columns,
+                };
+                let grid = mc.grid(residuals, &lattice)?;
+                Surface::Lattice(grid.rectangular()?.clone())
+            },
+        };
+
+        let (xmin, ymin, xstep, ystep) =
+          (self.xmin, self.ymin, self.xstep, self.ystep);
+        match &mut self.data {
+            GridData::Rectangular(arr) => {
+                for ((j, i), z) in arr.indexed_iter_mut() {
+                    let (x, y) = (xmin + i as f64 * xstep,
+                      ymin + j as f64 * ystep);
+                    *z += surface.z(x, y, (j, i));
+                }
+            },
+            GridData::Triangular(arr) => {
+                for mut tri in arr.outer_iter_mut() {
+                    for v in 0..3 {
+                        let (x, y) = (tri[(v, 0)], tri[(v, 1)]);
+                        tri[(v, 2)] += surface.z(x, y, (0, 0));
+                    }
+                }
+            },
+        }
+        let stats = self.data.stats();
+        self.zmin = stats.min;
+        self.zmax = stats.max;
+        Ok(())
+    }
+}
+
+// a correction surface, evaluated at a point or (for lattices) a node
+enum Surface {
+    Radial(RadialBasis),
+    Lattice(ArcArray2<f64>),
+}
+
+impl Surface {
+    fn z(&self, x: f64, y: f64, node: (usize, usize)) -> f64 {
+        match self {
+            Surface::Radial(rbf) => rbf.z(x, y),
+            Surface::Lattice(arr) => arr[node],
+        }
+    }
+}
+
+// an interpolant of scattered points by Wendland's compactly supported
+// radial basis function, which is positive definite in two (and three)
+// dimensions, so its weights always exist for distinct points
+struct RadialBasis {
+    centers: Vec<(f64, f64)>,
+    weights: Vec<f64>,
+    radius: f64,
+}
+
+impl RadialBasis {
+    fn fit(points: &[(f64, f64, f64)], radius: f64)
+      -> Result<RadialBasis, Error> {
+        let centers = points.iter().map(|&(x, y, _)| (x, y))
+          .collect::<Vec<_>>();
+        let n = centers.len();
+        let mut a = vec![vec![0.0; n + 1]; n];
+        for (j, row) in a.iter_mut().enumerate() {
+            for (k, &c) in centers.iter().enumerate() {
+                row[k] = wendland(distance(centers[j], c) / radius);
+            }
+            row[n] = points[j].2;
+        }
+        let weights = solve(a).ok_or_else(|| Error::InvalidArgument(
+          "control points are too close together to honor".to_string()))?;
+        Ok(RadialBasis { centers, weights, radius })
+    }
+
+    fn z(&self, x: f64, y: f64) -> f64 {
+        self.centers.iter().zip(&self.weights)
+          .map(|(&c, w)| w * wendland(distance((x, y), c) / self.radius))
+          .sum()
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+// Wendland's φ(3,1), by distance as a fraction of the radius of support
+fn wendland(r: f64) -> f64 {
+    if r >= 1.0 {
+        0.0
+    } else {
+        (1.0 - r).powi(4) * (4.0 * r + 1.0)
+    }
+}
+
+// solve a linear system, given as rows of its augmented matrix, by Gaussian
+// elimination with partial pivoting; none if it's (numerically) singular
+fn solve(mut a: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&j, &k| {
+            a[j][col].abs().total_cmp(&a[k][col].abs())
+        })?;
+        let p = a[pivot][col].abs();
+        if p.is_nan() || p <= 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower {
+            let factor = row[col] / pivot_row[col];
+            if factor != 0.0 {
+                for (v, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *v -= factor * p;
+                }
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).map(|k| a[row][k] * x[k]).sum::<f64>();
+        x[row] = (a[row][n] - sum) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,126 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+/* conversions between rectangular grids and `image` crate buffers
+ *
+ * images are "north-up" (row 0 at the top), so we flip the row order relative
+ * to Petra's south-to-north storage on the way in and out.
+ */
+
+use std::collections::HashMap;
+
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+use ndarray::Array2;
+
+use crate::{Colormap, Error, Grid, GridData};
+
+impl Grid {
+    /// render a rectangular grid as an 8-bit greyscale image, scaling *z*
+    /// linearly from `zmin` (black) to `zmax` (white)
+    ///
+    /// the image is north-up; missing (NaN) values are rendered as black,
+    /// so they can't be distinguished from the minimum - use
+    /// [Grid::to_rgba_image] if that matters
+    pub fn to_gray_image(&self) -> Result<GrayImage, Error> {
+        let arr = match &self.data {
+            GridData::Rectangular(arr) => arr,
+            _ => return Err(Error::NotRectangular),
+        };
+        let (rows, cols) = arr.dim();
+        let span = self.zmax - self.zmin;
+        Ok(GrayImage::from_fn(cols as u32, rows as u32, |x, y| {
+            let z = arr[(rows - 1 - y as usize, x as usize)];
+            if z.is_nan() || span == 0.0 {
+                Luma([0])
+            } else {
+                let frac = ((z - self.zmin) / span).clamp(0.0, 1.0);
+                Luma([(frac * 255.0).round() as u8])
+            }
+        }))
+    }
+
+    /// render a rectangular grid as an RGBA image using a [Colormap] spanning
+    /// `zmin` to `zmax`
+    ///
+    /// the image is north-up; missing (NaN) values are rendered as fully
+    /// transparent pixels
+    pub fn to_rgba_image(&self, colormap: &Colormap)
+      -> Result<RgbaImage, Error> {
+        let arr = match &self.data {
+            GridData::Rectangular(arr) => arr,
+            _ => return Err(Error::NotRectangular),
+        };
+        let (rows, cols) = arr.dim();
+        Ok(RgbaImage::from_fn(cols as u32, rows as u32, |x, y| {
+            let z = arr[(rows - 1 - y as usize, x as usize)];
+            if z.is_nan() {
+                Rgba([0, 0, 0, 0])
+            } else {
+                let [r, g, b] = colormap.color_for(z, self.zmin, self.zmax);
+                Rgba([r, g, b, 255])
+            }
+        }))
+    }
+}
+
+impl GridData {
+    /// recover rectangular grid data from a north-up greyscale image, scaling
+    /// black to `zmin` and white to `zmax`
+    ///
+    /// this is the inverse of [Grid::to_gray_image], up to quantization
+    /// error; no values are treated as missing
+    pub fn from_gray_image(img: &GrayImage, zmin: f64, zmax: f64) -> GridData {
+        let (cols, rows) = (img.width() as usize, img.height() as usize);
+        let span = zmax - zmin;
+        let arr = Array2::from_shape_fn((rows, cols), |(j, i)| {
+            let Luma([v]) = *img.get_pixel(i as u32, (rows - 1 - j) as u32);
+            zmin + v as f64 / 255.0 * span
+        });
+        GridData::Rectangular(arr.into_shared())
+    }
+
+    /// recover rectangular grid data from a north-up RGBA image rendered with
+    /// `colormap` over `zmin` to `zmax`
+    ///
+    /// this is the (approximate) inverse of [Grid::to_rgba_image]; fully
+    /// transparent pixels become missing (NaN) values, and other colors are
+    /// matched to the closest color in the colormap
+    pub fn from_rgba_image(img: &RgbaImage, colormap: &Colormap,
+      zmin: f64, zmax: f64) -> GridData {
+        let (cols, rows) = (img.width() as usize, img.height() as usize);
+        let span = zmax - zmin;
+        // inverting the colormap is slow, and images rarely have many colors
+        let mut inverted = HashMap::new();
+        let arr = Array2::from_shape_fn((rows, cols), |(j, i)| {
+            let Rgba([r, g, b, a]) =
+              *img.get_pixel(i as u32, (rows - 1 - j) as u32);
+            if a == 0 {
+                f64::NAN
+            } else {
+                let frac = *inverted.entry([r, g, b])
+                  .or_insert_with(|| colormap.invert([r, g, b]));
+                zmin + frac * span
+            }
+        });
+        GridData::Rectangular(arr.into_shared())
+    }
+}
@@ -0,0 +1,120 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ESRI ASCII grids (`.asc`)
+//!
+//! ESRI grids describe cells rather than nodes, so each cell's center is
+//! taken as a node; rows are stored from north to south, and are flipped to
+//! Petra's south-to-north order
+
+use std::io::{BufRead, BufReader, Read};
+
+use ndarray::Array2;
+
+use crate::{Error, Grid};
+
+/// read an ESRI ASCII grid
+pub fn read_esri_ascii<R: Read>(source: &mut R) -> Result<Grid, Error> {
+    let mut columns = None;
+    let mut rows = None;
+    let mut xll = None;
+    let mut yll = None;
+    let mut centered = false;
+    let mut dx = None;
+    let mut dy = None;
+    let mut nodata = None;
+    let mut values = Vec::new();
+
+    for line in BufReader::new(source).lines() {
+        let line = line?;
+        let mut fields = line.split_ascii_whitespace();
+        let key = match fields.next() {
+            Some(key) => key,
+            None => continue,
+        };
+
+        if !values.is_empty() || key.parse::<f64>().is_ok() {
+            for v in line.split_ascii_whitespace() {
+                values.push(v.parse::<f64>().map_err(|_| malformed())?);
+            }
+            continue;
+        }
+
+        let value = fields.next()
+          .and_then(|v| v.parse::<f64>().ok())
+          .ok_or_else(malformed)?;
+        match key.to_ascii_lowercase().as_str() {
+            "ncols" => columns = Some(value as usize),
+            "nrows" => rows = Some(value as usize),
+            "xllcorner" => xll = Some(value),
+            "yllcorner" => yll = Some(value),
+            "xllcenter" => {
+                xll = Some(value);
+                centered = true;
+            },
+            "yllcenter" => {
+                yll = Some(value);
+                centered = true;
+            },
+            "cellsize" => {
+                dx = Some(value);
+                dy = Some(value);
+            },
+            "dx" => dx = Some(value),
+            "dy" => dy = Some(value),
+            "nodata_value" => nodata = Some(value),
+            _ => return Err(Error::InvalidInput(
+              format!("unknown ESRI ASCII grid header {}", key))),
+        }
+    }
+
+    let (columns, rows, xll, yll, dx, dy) = match (columns, rows, xll, yll,
+      dx, dy) {
+        (Some(c), Some(r), Some(x), Some(y), Some(dx), Some(dy)) =>
+            (c, r, x, y, dx, dy),
+        _ => return Err(Error::InvalidInput(
+          "incomplete ESRI ASCII grid header".to_string())),
+    };
+    if rows.checked_mul(columns) != Some(values.len()) {
+        return Err(Error::InvalidInput(format!(
+          "ESRI ASCII grid has {} values, expected {} rows x {} columns",
+          values.len(), rows, columns)));
+    }
+
+    let data = Array2::from_shape_fn((rows, columns), |(j, i)| {
+        let z = values[(rows - 1 - j) * columns + i];
+        if Some(z) == nodata {
+            f64::NAN
+        } else {
+            z
+        }
+    });
+    let (xmin, ymin) = if centered {
+        (xll, yll)
+    } else {
+        (xll + dx / 2.0, yll + dy / 2.0)
+    };
+    Ok(Grid::new_rectangular(xmin, ymin, dx, dy, data))
+}
+
+fn malformed() -> Error {
+    Error::InvalidInput("malformed ESRI ASCII grid".to_string())
+}
@@ -0,0 +1,211 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! importers reading grids from other formats
+//!
+//! imported grids carry default values for any metadata the source format
+//! doesn't record; see [crate::Grid::new_rectangular]
+//!
+//! [detect] identifies the format of a file from its contents, and
+//! [read_file] reads a grid in any supported format (including Petra's own)
+//!
+//! each format specifies the order of its rows and columns (see
+//! [InputFormat::axis_order]), and formats which declare reversed extents
+//! are reordered to match; for files written in the wrong order anyway,
+//! [read_file_with] can override the format's order
+
+use std::{
+    fmt,
+    fs,
+    io::Cursor,
+    path::Path,
+};
+
+use crate::{
+    AxisOrder,
+    ColumnOrder,
+    Error,
+    Grid,
+    GridHeader,
+    RowOrder,
+    GRID_OFFSET,
+};
+
+pub mod esri;
+pub mod polygons;
+pub mod surfer;
+pub mod xyz;
+pub mod zmap;
+
+/// the grid formats which can be read
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Petra GRD
+    Petra,
+
+    /// Surfer ASCII, Surfer 6 binary, or Surfer 7 binary; see [surfer]
+    Surfer,
+
+    /// ZMAP+ ASCII; see [zmap]
+    Zmap,
+
+    /// ESRI ASCII grid; see [esri]
+    EsriAscii,
+
+    /// XYZ or CSV points on a regular lattice; see [xyz]
+    Xyz,
+}
+
+impl InputFormat {
+    /// the order in which the format stores rows and columns, or `None` if
+    /// each value is stored with its coordinates (as in XYZ files)
+    ///
+    /// ZMAP+ grids are stored column by column, but each column runs north
+    /// to south and the columns west to east
+    pub fn axis_order(&self) -> Option<AxisOrder> {
+        match self {
+            InputFormat::Petra | InputFormat::Surfer =>
+                Some(AxisOrder::SOUTH_UP),
+            InputFormat::Zmap | InputFormat::EsriAscii =>
+                Some(AxisOrder::NORTH_UP),
+            InputFormat::Xyz => None,
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            InputFormat::Petra => "Petra GRD",
+            InputFormat::Surfer => "Surfer",
+            InputFormat::Zmap => "ZMAP+",
+            InputFormat::EsriAscii => "ESRI ASCII",
+            InputFormat::Xyz => "XYZ",
+        })
+    }
+}
+
+/// identify the format of a grid file from its contents, if possible
+pub fn detect(bytes: &[u8]) -> Option<InputFormat> {
+    if [&b"DSAA"[..], b"DSBB", b"DSRB"].iter().any(|m| bytes.starts_with(m)) {
+        return Some(InputFormat::Surfer);
+    }
+
+    if bytes.len() as u64 >= GRID_OFFSET
+      && GridHeader::read(&mut Cursor::new(bytes)).is_ok() {
+        return Some(InputFormat::Petra);
+    }
+
+    // otherwise, go by the first meaningful line of text
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]);
+    let line = text.lines()
+      .map(str::trim)
+      .find(|l| !l.is_empty() && !l.starts_with('#'))?;
+    if line.starts_with('!') || line.starts_with('@') {
+        return Some(InputFormat::Zmap);
+    }
+    let first = line.split_ascii_whitespace().next()?.to_ascii_lowercase();
+    if ["ncols", "nrows", "xllcorner", "xllcenter", "yllcorner", "yllcenter"]
+      .contains(&first.as_str()) {
+        return Some(InputFormat::EsriAscii);
+    }
+    let numeric = line.split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|f| !f.is_empty())
+      .all(|f| f.parse::<f64>().is_ok());
+    if numeric || line.to_ascii_lowercase().starts_with('x') {
+        return Some(InputFormat::Xyz);
+    }
+
+    None
+}
+
+/// read a grid in any supported format, detecting the format from its
+/// contents
+pub fn read_bytes(bytes: &[u8]) -> Result<(Grid, InputFormat), Error> {
+    let format = detect(bytes).ok_or_else(|| Error::InvalidInput(
+      "unrecognized grid format".to_string()))?;
+    let mut source = Cursor::new(bytes);
+    let grid = match format {
+        InputFormat::Petra => Grid::read(&mut source)?,
+        InputFormat::Surfer => surfer::read_surfer(&mut source)?,
+        InputFormat::Zmap => zmap::read_zmap(&mut source)?,
+        InputFormat::EsriAscii => esri::read_esri_ascii(&mut source)?,
+        InputFormat::Xyz => xyz::read_xyz(&mut source)?,
+    };
+    Ok((grid, format))
+}
+
+/// read a grid file in any supported format; see [read_bytes]
+pub fn read_file<P: AsRef<Path>>(path: P)
+  -> Result<(Grid, InputFormat), Error> {
+    read_bytes(&fs::read(path)?)
+}
+
+/// read a grid in any supported format, as [read_bytes], but with its rows
+/// or columns (or both) stored in the given order rather than the format's
+/// own (see [InputFormat::axis_order]), for files written by software which
+/// got the order wrong
+///
+/// with `None` for both, or for formats which store coordinates with each
+/// value, this is the same as [read_bytes]
+pub fn read_bytes_with(bytes: &[u8], rows: Option<RowOrder>,
+  columns: Option<ColumnOrder>) -> Result<(Grid, InputFormat), Error> {
+    let (mut grid, format) = read_bytes(bytes)?;
+    if let Some(assumed) = format.axis_order() {
+        let actual = AxisOrder {
+            rows: rows.unwrap_or(assumed.rows),
+            columns: columns.unwrap_or(assumed.columns),
+        };
+        grid.reinterpret_axes(actual, assumed);
+    }
+    Ok((grid, format))
+}
+
+/// read a grid file in any supported format, with its rows or columns
+/// stored in the given order; see [read_bytes_with]
+pub fn read_file_with<P: AsRef<Path>>(path: P, rows: Option<RowOrder>,
+  columns: Option<ColumnOrder>) -> Result<(Grid, InputFormat), Error> {
+    read_bytes_with(&fs::read(path)?, rows, columns)
+}
+
+// node spacing from the extent and count of nodes along an axis
+pub(crate) fn node_step(lo: f64, hi: f64, count: usize) -> f64 {
+    if count > 1 {
+        (hi - lo) / (count - 1) as f64
+    } else {
+        1.0
+    }
+}
+
+// the number of nodes in a lattice of the given shape, failing unless there
+// are at least that many values of the given size (in bytes, or 1 for
+// tokens) available, so that a corrupt header can't provoke a huge
+// allocation
+pub(crate) fn checked_nodes(rows: usize, columns: usize, size: usize,
+  available: usize, format: &str) -> Result<usize, Error> {
+    let nodes = rows.checked_mul(columns);
+    match nodes.and_then(|n| n.checked_mul(size)) {
+        Some(needed) if needed <= available => Ok(nodes.unwrap_or(0)),
+        _ => Err(Error::InvalidInput(format!(
+          "{} grid declares {} rows x {} columns, more than the file holds",
+          format, rows, columns))),
+    }
+}
@@ -0,0 +1,566 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! polygon layers (e.g. land grids of sections or leases) from GeoJSON or
+//! ESRI Shapefiles, for summarizing grids by area; see
+//! [crate::Grid::polygon_volumes]
+//!
+//! each feature becomes one [Polygon], keeping its attributes (GeoJSON
+//! properties, or the Shapefile's `.dbf` columns) as text. holes and
+//! multi-part features are handled by the even-odd rule, so ring orientation
+//! doesn't matter. coordinates are used as-is, so the layer should be in the
+//! grid's coordinate system
+
+use std::{
+    fs,
+    io::Read,
+    path::Path,
+};
+
+use crate::Error;
+
+// a polygon's rings, each a list of (x, y) vertices
+type Rings = Vec<Vec<(f64, f64)>>;
+
+/// a polygon feature, with its attributes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    /// the feature's attributes, as (name, value) pairs in their original
+    /// order
+    pub attributes: Vec<(String, String)>,
+
+    /// the polygon's rings (outer boundaries and holes, of every part), each
+    /// a list of (*x*, *y*) vertices
+    pub rings: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    /// the value of the named attribute, if present
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+          .find(|(k, _)| k == name)
+          .map(|(_, v)| v.as_str())
+    }
+
+    /// does the polygon contain the point (*x*, *y*)?
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            let n = ring.len();
+            for k in 0..n {
+                let (x0, y0) = ring[k];
+                let (x1, y1) = ring[(k + 1) % n];
+                if (y0 > y) != (y1 > y)
+                  && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// the bounding box of the polygon, as ((*x* min, *y* min), (*x* max,
+    /// *y* max)); infinite and inverted if it has no vertices
+    pub fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let mut lo = (f64::INFINITY, f64::INFINITY);
+        let mut hi = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in self.rings.iter().flatten() {
+            lo = (lo.0.min(x), lo.1.min(y));
+            hi = (hi.0.max(x), hi.1.max(y));
+        }
+        (lo, hi)
+    }
+}
+
+/// read a polygon layer from a GeoJSON file (`.geojson` or `.json`) or an
+/// ESRI Shapefile (`.shp`, with attributes from the matching `.dbf`, if
+/// there is one), going by the file's extension
+pub fn read_polygons_file<P: AsRef<Path>>(path: P)
+  -> Result<Vec<Polygon>, Error> {
+    let path = path.as_ref();
+    let is_shp = path.extension()
+      .map(|ext| ext.eq_ignore_ascii_case("shp"))
+      .unwrap_or(false);
+    if !is_shp {
+        return read_geojson(&mut fs::File::open(path)?);
+    }
+
+    let shapes = read_shp(&fs::read(path)?)?;
+    let dbf_path = path.with_extension("dbf");
+    let attributes = if dbf_path.is_file() {
+        read_dbf(&fs::read(dbf_path)?)?
+    } else {
+        Vec::new()
+    };
+    Ok(shapes.into_iter().enumerate()
+      .filter_map(|(i, rings)| Some(Polygon {
+          attributes: attributes.get(i).cloned().unwrap_or_default(),
+          rings: rings?,
+      }))
+      .collect())
+}
+
+/// read the polygon and multi-polygon features of a GeoJSON feature
+/// collection, single feature, or bare geometry; other geometries are
+/// skipped
+pub fn read_geojson<R: Read + ?Sized>(source: &mut R)
+  -> Result<Vec<Polygon>, Error> {
+    let mut text = String::new();
+    source.read_to_string(&mut text)?;
+    let json = JsonParser { bytes: text.as_bytes(), pos: 0 }.document()?;
+
+    let mut polygons = Vec::new();
+    match json.get("type").and_then(Json::as_str) {
+        Some("FeatureCollection") => {
+            let features = json.get("features").and_then(Json::as_array)
+              .ok_or_else(|| malformed_geojson("missing features"))?;
+            for feature in features {
+                polygons.extend(feature_polygon(feature)?);
+            }
+        },
+        Some("Feature") => polygons.extend(feature_polygon(&json)?),
+        _ => polygons.extend(geometry_rings(&json)?
+          .map(|rings| Polygon { attributes: Vec::new(), rings })),
+    }
+    Ok(polygons)
+}
+
+fn feature_polygon(feature: &Json) -> Result<Option<Polygon>, Error> {
+    let geometry = match feature.get("geometry") {
+        Some(g) => g,
+        None => return Ok(None),
+    };
+    let rings = match geometry_rings(geometry)? {
+        Some(rings) => rings,
+        None => return Ok(None),
+    };
+    let attributes = match feature.get("properties") {
+        Some(Json::Object(props)) => props.iter()
+          .map(|(k, v)| (k.clone(), v.to_text()))
+          .collect(),
+        _ => Vec::new(),
+    };
+    Ok(Some(Polygon { attributes, rings }))
+}
+
+// the rings of a polygon or multi-polygon geometry
+fn geometry_rings(geometry: &Json)
+  -> Result<Option<Rings>, Error> {
+    let coords = geometry.get("coordinates");
+    let polygons = match (geometry.get("type").and_then(Json::as_str), coords) {
+        (Some("Polygon"), Some(c)) => vec![c],
+        (Some("MultiPolygon"), Some(Json::Array(parts))) =>
+            parts.iter().collect(),
+        _ => return Ok(None),
+    };
+
+    let mut rings = Vec::new();
+    for polygon in polygons {
+        let polygon = polygon.as_array()
+          .ok_or_else(|| malformed_geojson("bad polygon coordinates"))?;
+        for ring in polygon {
+            let ring = ring.as_array()
+              .ok_or_else(|| malformed_geojson("bad ring coordinates"))?;
+            let points = ring.iter().map(|p| match p.as_array() {
+                Some([Json::Number(x, _), Json::Number(y, _), ..]) =>
+                    Ok((*x, *y)),
+                _ => Err(malformed_geojson("bad point coordinates")),
+            }).collect::<Result<Vec<_>, _>>()?;
+            rings.push(points);
+        }
+    }
+    Ok(Some(rings))
+}
+
+fn malformed_geojson(what: &str) -> Error {
+    Error::InvalidInput(format!("malformed GeoJSON: {}", what))
+}
+
+/* just enough of a JSON parser for GeoJSON; numbers keep their original text
+ * too, so that attributes (like lease numbers) come through exactly */
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64, String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter()
+              .find(|(k, _)| k == key)
+              .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    // an attribute value as text; nested values are rendered as JSON
+    fn to_text(&self) -> String {
+        match self {
+            Json::Null => String::new(),
+            Json::Bool(b) => b.to_string(),
+            Json::Number(_, s) | Json::String(s) => s.clone(),
+            Json::Array(items) => format!("[{}]", items.iter()
+              .map(Json::to_json)
+              .collect::<Vec<_>>()
+              .join(",")),
+            Json::Object(_) => self.to_json(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Json::Null => "null".to_string(),
+            Json::String(s) => format!("{:?}", s),
+            Json::Object(members) => format!("{{{}}}", members.iter()
+              .map(|(k, v)| format!("{:?}:{}", k, v.to_json()))
+              .collect::<Vec<_>>()
+              .join(",")),
+            _ => self.to_text(),
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn document(mut self) -> Result<Json, Error> {
+        let value = self.value()?;
+        self.skip_ws();
+        if self.pos != self.bytes.len() {
+            return Err(self.error("trailing data"));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, what: &str) -> Error {
+        Error::InvalidInput(format!("malformed JSON at byte {}: {}",
+          self.pos, what))
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), Error> {
+        self.skip_ws();
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", b as char)))
+        }
+    }
+
+    fn literal(&mut self, text: &str, value: Json) -> Result<Json, Error> {
+        if self.bytes[self.pos..].starts_with(text.as_bytes()) {
+            self.pos += text.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected token"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, Error> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, Error> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value()?));
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                },
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, Error> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                },
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, Error> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+          = self.peek() {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos])
+          .map_err(|_| self.error("bad number"))?;
+        let value = text.parse::<f64>()
+          .map_err(|_| self.error("bad number"))?;
+        Ok(Json::Number(value, text.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                },
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                },
+                Some(b) => {
+                    out.push(b);
+                    self.pos += 1;
+                },
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    // a \uXXXX escape (or surrogate pair), leaving pos on its last digit
+    fn unicode_escape(&mut self) -> Result<char, Error> {
+        let hex = |p: &mut Self| -> Result<u32, Error> {
+            let digits = p.bytes.get(p.pos + 1..p.pos + 5)
+              .and_then(|d| std::str::from_utf8(d).ok())
+              .and_then(|d| u32::from_str_radix(d, 16).ok())
+              .ok_or_else(|| p.error("bad unicode escape"))?;
+            p.pos += 4;
+            Ok(digits)
+        };
+        let high = hex(self)?;
+        let code = if (0xd800..0xdc00).contains(&high)
+          && self.bytes[self.pos + 1..].starts_with(b"\\u") {
+            self.pos += 2;
+            let low = hex(self)?;
+            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00))
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("bad unicode escape"))
+    }
+}
+
+// the rings of each record of a Shapefile (None for null shapes)
+fn read_shp(bytes: &[u8]) -> Result<Vec<Option<Rings>>, Error> {
+    let malformed = || Error::InvalidInput("malformed Shapefile".to_string());
+    let be_i32 = |at: usize| bytes.get(at..at + 4)
+      .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+      .ok_or_else(malformed);
+    let le_i32 = |at: usize| bytes.get(at..at + 4)
+      .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+      .ok_or_else(malformed);
+    let le_f64 = |at: usize| bytes.get(at..at + 8)
+      .map(|b| f64::from_le_bytes(b.try_into().expect("8 bytes")))
+      .ok_or_else(malformed);
+
+    if be_i32(0)? != 9994 {
+        return Err(Error::InvalidInput("not a Shapefile".to_string()));
+    }
+
+    // counts and offsets are untrusted: they must be non-negative, and
+    // mustn't overflow
+    let count = |n: i32| usize::try_from(n).map_err(|_| malformed());
+
+    let mut shapes = Vec::new();
+    let mut pos = 100;
+    while pos + 8 <= bytes.len() {
+        // lengths are in 16-bit words
+        let len = u32::try_from(be_i32(pos + 4)?).map_err(|_| malformed())?;
+        let content = pos + 8;
+        let end = (len as usize).checked_mul(2)
+          .and_then(|len| content.checked_add(len))
+          .filter(|&end| end > content && end <= bytes.len())
+          .ok_or_else(malformed)?;
+        pos = end;
+
+        match le_i32(content)? {
+            0 => shapes.push(None),
+            // polygons, PolygonZs, and PolygonMs
+            5 | 15 | 25 => {
+                let n_parts = count(le_i32(content + 36)?)?;
+                let n_points = count(le_i32(content + 40)?)?;
+                let points_at = n_parts.checked_mul(4)
+                  .and_then(|len| (content + 44).checked_add(len))
+                  .ok_or_else(malformed)?;
+                let points_end = n_points.checked_mul(16)
+                  .and_then(|len| points_at.checked_add(len))
+                  .ok_or_else(malformed)?;
+                if points_end > end {
+                    return Err(malformed());
+                }
+                let parts = (0..n_parts)
+                  .map(|k| le_i32(content + 44 + 4 * k).and_then(count))
+                  .collect::<Result<Vec<_>, _>>()?;
+                let mut rings = Vec::with_capacity(n_parts);
+                for (k, &start) in parts.iter().enumerate() {
+                    let stop = parts.get(k + 1).copied().unwrap_or(n_points);
+                    if start > stop || stop > n_points {
+                        return Err(malformed());
+                    }
+                    rings.push((start..stop).map(|p| {
+                        let at = points_at + 16 * p;
+                        Ok((le_f64(at)?, le_f64(at + 8)?))
+                    }).collect::<Result<Vec<_>, Error>>()?);
+                }
+                shapes.push(Some(rings));
+            },
+            other => return Err(Error::InvalidInput(format!(
+              "Shapefile shape type {} is not a polygon", other))),
+        }
+    }
+    Ok(shapes)
+}
+
+// the attributes of each record of a dBASE table
+fn read_dbf(bytes: &[u8]) -> Result<Vec<Vec<(String, String)>>, Error> {
+    let malformed = || Error::InvalidInput("malformed dBASE table".to_string());
+    if bytes.len() < 32 {
+        return Err(malformed());
+    }
+    let n_records = u32::from_le_bytes([bytes[4], bytes[5], bytes[6],
+      bytes[7]]) as usize;
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let record_len = u16::from_le_bytes([bytes[10], bytes[11]]) as usize;
+
+    let text = |b: &[u8]| String::from_utf8_lossy(b)
+      .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+      .to_string();
+
+    let mut fields = Vec::new();
+    let mut at = 32;
+    while at + 32 <= header_len.min(bytes.len()) && bytes[at] != 0x0d {
+        let name = text(&bytes[at..at + 11]);
+        let len = bytes[at + 16] as usize;
+        fields.push((name, len));
+        at += 32;
+    }
+
+    // the header's record count is untrusted, so check it against the
+    // table's length before allocating
+    let records_len = n_records.checked_mul(record_len)
+      .and_then(|len| len.checked_add(header_len));
+    if record_len == 0 || records_len.is_none_or(|len| len > bytes.len()) {
+        return Err(malformed());
+    }
+
+    let mut records = Vec::with_capacity(n_records);
+    for r in 0..n_records {
+        let start = header_len + r * record_len;
+        let record = bytes.get(start..start + record_len)
+          .ok_or_else(malformed)?;
+        // skip the deletion flag
+        let mut offset = 1;
+        let mut attributes = Vec::with_capacity(fields.len());
+        for (name, len) in &fields {
+            let value = record.get(offset..offset + len)
+              .ok_or_else(malformed)?;
+            attributes.push((name.clone(), text(value)));
+            offset += len;
+        }
+        records.push(attributes);
+    }
+    Ok(records)
+}
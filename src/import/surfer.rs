@@ -0,0 +1,177 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Surfer grids: ASCII (`DSAA`), Surfer 6 binary (`DSBB`), and Surfer 7
+//! binary (`DSRB`)
+//!
+//! like Petra, Surfer stores rows from south to north; blanked nodes are
+//! recorded as missing values
+
+use std::{
+    io::{Cursor, Read},
+    str::SplitAsciiWhitespace,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use ndarray::Array2;
+
+use crate::{Error, Grid, import::{checked_nodes, node_step}};
+
+// Surfer's blanking value; values at or above it are blanked
+const SURFER_BLANK: f64 = 1.70141e38;
+
+/// read a Surfer grid in any of its ASCII or binary forms
+pub fn read_surfer<R: Read>(source: &mut R) -> Result<Grid, Error> {
+    let mut bytes = Vec::new();
+    source.read_to_end(&mut bytes)?;
+    match bytes.get(..4) {
+        Some(b"DSAA") => read_ascii(&bytes[4..]),
+        Some(b"DSBB") => read_surfer6(&mut Cursor::new(&bytes[4..])),
+        Some(b"DSRB") => read_surfer7(&mut Cursor::new(&bytes[4..])),
+        _ => Err(Error::InvalidInput("not a Surfer grid".to_string())),
+    }
+}
+
+fn read_ascii(text: &[u8]) -> Result<Grid, Error> {
+    let text = String::from_utf8_lossy(text);
+    let mut tokens = text.split_ascii_whitespace();
+
+    let columns = number(&mut tokens)? as usize;
+    let rows = number(&mut tokens)? as usize;
+    let (xlo, xhi) = (number(&mut tokens)?, number(&mut tokens)?);
+    let (ylo, yhi) = (number(&mut tokens)?, number(&mut tokens)?);
+    let _zrange = (number(&mut tokens)?, number(&mut tokens)?);
+
+    checked_nodes(rows, columns, 1, tokens.clone().count(), "Surfer ASCII")?;
+    let mut data = Array2::zeros((rows, columns));
+    for z in data.iter_mut() {
+        *z = blank_to_nan(number(&mut tokens)?, SURFER_BLANK);
+    }
+    Ok(Grid::new_rectangular(xlo, ylo, node_step(xlo, xhi, columns),
+      node_step(ylo, yhi, rows), data))
+}
+
+fn number(tokens: &mut SplitAsciiWhitespace) -> Result<f64, Error> {
+    tokens.next()
+      .and_then(|t| t.parse().ok())
+      .ok_or_else(|| Error::InvalidInput(
+        "truncated or malformed Surfer ASCII grid".to_string()))
+}
+
+fn read_surfer6(source: &mut Cursor<&[u8]>) -> Result<Grid, Error> {
+    let columns = source.read_i16::<LittleEndian>()?.max(0) as usize;
+    let rows = source.read_i16::<LittleEndian>()?.max(0) as usize;
+    let xlo = source.read_f64::<LittleEndian>()?;
+    let xhi = source.read_f64::<LittleEndian>()?;
+    let ylo = source.read_f64::<LittleEndian>()?;
+    let yhi = source.read_f64::<LittleEndian>()?;
+    let _zlo = source.read_f64::<LittleEndian>()?;
+    let _zhi = source.read_f64::<LittleEndian>()?;
+
+    let nodes = checked_nodes(rows, columns, 4, remaining(source),
+      "Surfer 6")?;
+    let mut buf = vec![0.0f32; nodes];
+    source.read_f32_into::<LittleEndian>(&mut buf)?;
+    let data = Array2::from_shape_fn((rows, columns),
+      |(j, i)| blank_to_nan(buf[j * columns + i] as f64, SURFER_BLANK));
+    Ok(Grid::new_rectangular(xlo, ylo, node_step(xlo, xhi, columns),
+      node_step(ylo, yhi, rows), data))
+}
+
+/* Surfer 7 files are a series of tagged sections; we need only the GRID
+ * section (geometry) and the DATA section which follows it */
+fn read_surfer7(source: &mut Cursor<&[u8]>) -> Result<Grid, Error> {
+    // the rest of the DSRB header section: its size, then a version
+    let header_size = source.read_i32::<LittleEndian>()?;
+    skip(source, header_size)?;
+
+    let mut geometry = None;
+    loop {
+        let mut tag = [0u8; 4];
+        source.read_exact(&mut tag)?;
+        let size = source.read_i32::<LittleEndian>()?;
+        match &tag {
+            b"GRID" => {
+                let rows = source.read_i32::<LittleEndian>()?.max(0) as usize;
+                let columns =
+                  source.read_i32::<LittleEndian>()?.max(0) as usize;
+                let xll = source.read_f64::<LittleEndian>()?;
+                let yll = source.read_f64::<LittleEndian>()?;
+                let xsize = source.read_f64::<LittleEndian>()?;
+                let ysize = source.read_f64::<LittleEndian>()?;
+                let _zmin = source.read_f64::<LittleEndian>()?;
+                let _zmax = source.read_f64::<LittleEndian>()?;
+                let rotation = source.read_f64::<LittleEndian>()?;
+                let blank = source.read_f64::<LittleEndian>()?;
+                if rotation != 0.0 {
+                    return Err(Error::InvalidInput(
+                      "rotated Surfer grids are not supported".to_string()));
+                }
+                geometry = Some((rows, columns, xll, yll, xsize, ysize, blank));
+            },
+
+            b"DATA" => {
+                let (rows, columns, xll, yll, xsize, ysize, blank) = geometry
+                  .ok_or_else(|| Error::InvalidInput(
+                    "Surfer 7 DATA section precedes GRID section".to_string()))?;
+                let nodes = checked_nodes(rows, columns, 8,
+                  remaining(source), "Surfer 7")?;
+                let mut buf = vec![0.0; nodes];
+                source.read_f64_into::<LittleEndian>(&mut buf)?;
+                for z in &mut buf {
+                    *z = blank_to_nan(*z, blank);
+                }
+                /* safety: the buffer was allocated at exactly rows x columns
+                 * values */
+                let data = Array2::from_shape_vec((rows, columns), buf)
+                  .unwrap();
+                return Ok(Grid::new_rectangular(xll, yll, xsize, ysize, data));
+            },
+
+            // e.g. fault traces, which we don't support
+            _ => skip(source, size)?,
+        }
+    }
+}
+
+fn skip(source: &mut Cursor<&[u8]>, size: i32) -> Result<(), Error> {
+    let pos = source.position() + size.max(0) as u64;
+    if pos > source.get_ref().len() as u64 {
+        return Err(Error::InvalidInput(
+          "truncated Surfer 7 grid".to_string()));
+    }
+    source.set_position(pos);
+    Ok(())
+}
+
+// the number of bytes left to read
+fn remaining(source: &Cursor<&[u8]>) -> usize {
+    source.get_ref().len().saturating_sub(source.position() as usize)
+}
+
+fn blank_to_nan(v: f64, blank: f64) -> f64 {
+    if v >= blank {
+        f64::NAN
+    } else {
+        v
+    }
+}
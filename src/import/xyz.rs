@@ -0,0 +1,112 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! plain-text point imports: whitespace- or comma-delimited XYZ/CSV
+//!
+//! the points must lie on a regular lattice (though not every node need be
+//! present; missing nodes are recorded as missing values), as produced by
+//! [crate::export::xyz]
+
+use std::io::{BufRead, BufReader, Read};
+
+use ndarray::Array2;
+
+use crate::{Error, Grid};
+
+// how far (as a fraction of the step) a point may be from its lattice node
+const LATTICE_TOLERANCE: f64 = 1e-6;
+
+/// read a rectangular grid from "x y z" or "x,y,z" lines
+///
+/// blank lines, lines beginning with `#`, and a non-numeric header line are
+/// skipped; the lattice spacing is inferred from the smallest gap between
+/// distinct *x* and *y* coordinates
+pub fn read_xyz<R: Read>(source: &mut R) -> Result<Grid, Error> {
+    let mut points = Vec::new();
+    for (i, line) in BufReader::new(source).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split(|c: char| c == ',' || c.is_whitespace())
+          .filter(|f| !f.is_empty())
+          .map(|f| f.parse::<f64>())
+          .collect::<Result<Vec<_>, _>>();
+        match fields.as_deref() {
+            Ok([x, y, z, ..]) => points.push((*x, *y, *z)),
+            Err(_) if points.is_empty() => continue, // a header
+            _ => return Err(Error::InvalidInput(
+              format!("line {}: expected x, y, and z values", i + 1))),
+        }
+    }
+
+    if points.is_empty() {
+        return Err(Error::InvalidInput("no points found".to_string()));
+    }
+
+    let (xmin, xstep, columns) = infer_axis(points.iter().map(|p| p.0))?;
+    let (ymin, ystep, rows) = infer_axis(points.iter().map(|p| p.1))?;
+
+    let mut data = Array2::from_elem((rows, columns), f64::NAN);
+    for (x, y, z) in points {
+        let i = lattice_index(x, xmin, xstep)?;
+        let j = lattice_index(y, ymin, ystep)?;
+        data[(j, i)] = z;
+    }
+
+    Ok(Grid::new_rectangular(xmin, ymin, xstep, ystep, data))
+}
+
+// infer (minimum, step, count) for a lattice axis from coordinate values
+fn infer_axis(coords: impl Iterator<Item = f64>)
+  -> Result<(f64, f64, usize), Error> {
+    let mut uniq = coords.collect::<Vec<_>>();
+    if uniq.iter().any(|c| !c.is_finite()) {
+        return Err(Error::InvalidInput(
+          "non-finite coordinate value".to_string()));
+    }
+    uniq.sort_by(|a, b| a.total_cmp(b));
+    uniq.dedup();
+
+    let min = uniq[0];
+    let max = uniq[uniq.len() - 1];
+    if uniq.len() == 1 {
+        return Ok((min, 1.0, 1));
+    }
+
+    let step = uniq.windows(2)
+      .map(|w| w[1] - w[0])
+      .fold(f64::INFINITY, f64::min);
+    let count = ((max - min) / step).round() as usize + 1;
+    Ok((min, step, count))
+}
+
+fn lattice_index(c: f64, min: f64, step: f64) -> Result<usize, Error> {
+    let f = (c - min) / step;
+    let i = f.round();
+    if (f - i).abs() > LATTICE_TOLERANCE {
+        return Err(Error::InvalidInput(
+          format!("coordinate {} does not lie on a regular lattice", c)));
+    }
+    Ok(i as usize)
+}
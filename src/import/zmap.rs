@@ -0,0 +1,125 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! ZMAP+ ASCII grids
+//!
+//! ZMAP+ stores nodes column by column from west to east, each column from
+//! north to south, in fixed-width fields; we rely on the fields being
+//! separated by whitespace, as they are in every file we've seen
+
+use std::io::{BufRead, BufReader, Read};
+
+use ndarray::Array2;
+
+use crate::{Error, Grid, import::node_step};
+
+/// read a ZMAP+ ASCII grid; the grid's name is taken from the header
+pub fn read_zmap<R: Read>(source: &mut R) -> Result<Grid, Error> {
+    let mut lines = BufReader::new(source).lines();
+
+    // the header opens with "@name, GRID, nodes per line"
+    let mut name = None;
+    for line in lines.by_ref() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('!') {
+            continue;
+        }
+        let fields = line.trim_start_matches('@').split(',')
+          .map(str::trim)
+          .collect::<Vec<_>>();
+        match fields.as_slice() {
+            [n, kind, ..] if line.starts_with('@')
+              && kind.eq_ignore_ascii_case("grid") =>
+                name = Some(n.to_string()),
+            _ => return Err(Error::InvalidInput(
+              "missing ZMAP+ grid header".to_string())),
+        }
+        break;
+    }
+    let name = name.ok_or_else(|| Error::InvalidInput(
+      "missing ZMAP+ grid header".to_string()))?;
+
+    // then comma-separated fields up to a closing "@"
+    let mut fields = Vec::new();
+    for line in lines.by_ref() {
+        let line = line?;
+        let line = line.trim();
+        if line.starts_with('!') {
+            continue;
+        }
+        if line.starts_with('@') {
+            break;
+        }
+        fields.extend(line.split(',').map(|f| f.trim().to_string()));
+    }
+    if fields.len() < 11 {
+        return Err(malformed());
+    }
+    let num = |i: usize| fields[i].parse::<f64>().map_err(|_| malformed());
+
+    // the null value may be given as a number or as a string
+    let null_value = num(1).ok();
+    let null_string = fields[2].clone();
+    let rows = num(5)? as usize;
+    let columns = num(6)? as usize;
+    let (xmin, xmax, ymin, ymax) = (num(7)?, num(8)?, num(9)?, num(10)?);
+
+    /* the values are collected as they're found, rather than allocated up
+     * front from the (untrusted) header */
+    let nodes = rows.checked_mul(columns).ok_or_else(|| Error::InvalidInput(
+      format!("ZMAP+ grid declares {} rows x {} columns, more than the file \
+        holds", rows, columns)))?;
+    let mut values = Vec::new();
+    for line in lines {
+        let line = line?;
+        if line.trim_start().starts_with('!') {
+            continue;
+        }
+        for v in line.split_ascii_whitespace() {
+            if !null_string.is_empty() && v == null_string {
+                values.push(f64::NAN);
+                continue;
+            }
+            let v = v.parse::<f64>().map_err(|_| malformed())?;
+            let is_null = null_value
+              .map(|n| (v - n).abs() <= n.abs() * 1e-6)
+              .unwrap_or(false);
+            values.push(if is_null { f64::NAN } else { v });
+        }
+    }
+    if values.len() != nodes {
+        return Err(Error::InvalidInput(format!(
+          "ZMAP+ grid has {} values, expected {} rows x {} columns",
+          values.len(), rows, columns)));
+    }
+
+    let data = Array2::from_shape_fn((rows, columns),
+      |(j, i)| values[i * rows + (rows - 1 - j)]);
+    let mut grid = Grid::new_rectangular(xmin, ymin,
+      node_step(xmin, xmax, columns), node_step(ymin, ymax, rows), data);
+    grid.name = name;
+    Ok(grid)
+}
+
+fn malformed() -> Error {
+    Error::InvalidInput("malformed ZMAP+ grid header".to_string())
+}
@@ -0,0 +1,132 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! the consistency rules relating the fields of a Petra grid's header, as
+//! pure functions
+//!
+//! these are the very predicates the readers apply ([GridHeader::read] and
+//! [Grid::read] reject headers which break them, or warn in lenient and
+//! salvage modes; see [crate::ReadOptions]), exposed so that fuzzers and
+//! property-based test suites can hold real and synthetic headers to
+//! exactly the same rules
+//!
+//! ```
+//! use petra_grid::invariants;
+//!
+//! // 11 columns from 0 to 1000 must be 100 apart
+//! assert!(invariants::spec_coherent(0.0, 1000.0, 100.0, 11));
+//! assert!(!invariants::spec_coherent(0.0, 1000.0, 90.0, 11));
+//! assert!(invariants::size_coherent(110, 10, 11));
+//! ```
+
+use crate::{Error, GridHeader, GRID_OFFSET};
+
+/// the greatest relative error tolerated between an axis' stated maximum
+/// and the one implied by its minimum, step, and count (see
+/// [spec_coherent])
+pub const SPEC_REL_TOLERANCE: f64 = 0.0001;
+
+/// the offset of the data block within a GRD file
+pub const DATA_OFFSET: u64 = GRID_OFFSET;
+
+/// the size, in bytes, of each node of a rectangular grid's data block
+pub const NODE_BYTES: u64 = 8;
+
+/// the size, in bytes, of each triangle of a triangular grid's data block
+pub const TRIANGLE_BYTES: u64 = 72;
+
+/// the strides (in values) of the triangle, vertex, and coordinate axes of
+/// a triangular grid's data block, which stores each triangle as *x*₁ *x*₂
+/// *x*₃ *y*₁ *y*₂ *y*₃ *z*₁ *z*₂ *z*₃; see [crate::GridData::Triangular]
+pub const TRIANGLE_STRIDES: (usize, usize, usize) = (9, 1, 3);
+
+/// the relative error (relative to the magnitude of `max`) between an
+/// axis' stated maximum and the one implied by its minimum, step, and
+/// number of nodes
+///
+/// when `max` is zero, this is infinite, unless the implied maximum is
+/// exactly zero too, when it's NaN (and so within any tolerance)
+pub fn spec_rel_error(min: f64, max: f64, step: f64, count: u32) -> f64 {
+    (min + (count as f64 - 1.0) * step - max).abs() / max.abs()
+}
+
+/// is an axis' specification (minimum, maximum, step, and number of nodes)
+/// coherent, to within [SPEC_REL_TOLERANCE]? see [Error::InvalidXSpec] and
+/// [Error::InvalidYSpec]
+pub fn spec_coherent(min: f64, max: f64, step: f64, count: u32) -> bool {
+    let err = spec_rel_error(min, max, step, count);
+    err.is_nan() || err <= SPEC_REL_TOLERANCE
+}
+
+/// is a grid's size the product of its row and column counts (without
+/// overflow)? see [Error::SizeMismatch]
+pub fn size_coherent(size: u32, rows: u32, columns: u32) -> bool {
+    rows.checked_mul(columns) == Some(size)
+}
+
+/// the length, in bytes, of the data block of a grid with the given size
+/// and number of triangles (a grid is triangular if it has any)
+pub fn data_block_len(size: u32, n_triangles: u32) -> u64 {
+    if n_triangles == 0 {
+        size as u64 * NODE_BYTES
+    } else {
+        n_triangles as u64 * TRIANGLE_BYTES
+    }
+}
+
+/// does a data block of `len` bytes hold exactly the nodes or triangles a
+/// header calls for? (any trailing partial node or triangle is ignored);
+/// see [Error::InvalidRectangularSize] and [Error::InvalidTriangleCount]
+pub fn data_block_coherent(size: u32, n_triangles: u32, len: u64) -> bool {
+    if n_triangles == 0 {
+        len / NODE_BYTES == size as u64
+    } else {
+        len / TRIANGLE_BYTES == n_triangles as u64
+    }
+}
+
+/// the index, within a triangular grid's data block (in values, not bytes),
+/// of the given coordinate (0, 1, or 2 for *x*, *y*, or *z*) of the given
+/// vertex (0, 1, or 2) of the given triangle
+pub fn triangle_value_index(triangle: usize, vertex: usize,
+  coordinate: usize) -> usize {
+    let (t, v, c) = TRIANGLE_STRIDES;
+    triangle * t + vertex * v + coordinate * c
+}
+
+/// check the rules relating a header's fields to one another, returning the
+/// error a strict read would fail with (size first, then the *x* and *y*
+/// specifications); use [crate::Grid::header] to check a grid
+pub fn check_header(header: &GridHeader) -> Result<(), Error> {
+    let GridHeader {
+        size, rows, columns, xmin, xmax, ymin, ymax, xstep, ystep, ..
+    } = *header;
+    if !size_coherent(size, rows, columns) {
+        return Err(Error::SizeMismatch(size, rows, columns));
+    }
+    if !spec_coherent(xmin, xmax, xstep, columns) {
+        return Err(Error::InvalidXSpec(xmin, xmax, xstep, columns));
+    }
+    if !spec_coherent(ymin, ymax, ystep, rows) {
+        return Err(Error::InvalidYSpec(ymin, ymax, ystep, rows));
+    }
+    Ok(())
+}
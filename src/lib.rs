@@ -38,9 +38,10 @@
 use byteorder::{LittleEndian, ReadBytesExt};
 
 use ndarray::{
+    ArcArray,
+    ArcArray2,
     Array,
-    Array2,
-    Array3,
+    Ix3,
     ShapeBuilder,
 };
 
@@ -54,23 +55,136 @@ use std::{
     error,
     fmt,
     io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    sync::Arc,
 };
 
+mod algebra;
+mod attributes;
+pub use attributes::{SurfaceAttribute, TriangleAttributes};
+mod axes;
+pub use axes::{AxisOrder, ColumnOrder, RowOrder};
+mod backup;
+pub use backup::backup_file;
+mod cache;
+mod categorical;
+pub use categorical::{CategoricalGrid, Category};
+mod chunks;
+pub use chunks::{ChunkReader, ChunkWriter, Chunks, GridChunk};
+#[cfg(feature = "checksum")]
+pub mod checksum;
+mod classify;
+pub use classify::{Classification, ClassificationMethod, ClassInterval};
+mod colormap;
+pub use colormap::Colormap;
+mod construct;
+mod contour;
+pub use contour::Contour;
+pub mod datum;
+mod edit;
+pub use edit::{EditRegion, RegionEdit};
+pub mod epsg;
+pub mod export;
+mod focal;
+pub use focal::{FocalStatistic, FocalWindow};
+mod grid_cache;
+pub use grid_cache::GridCache;
+pub mod gridding;
+pub mod import;
+pub mod invariants;
+mod method;
+pub use method::GridMethod;
+mod mosaic;
+mod nulls;
+pub use nulls::{NullArithmetic, NullPolicy};
+mod overviews;
+pub mod probe;
+pub mod qc;
+mod quantity;
+pub use quantity::Quantity;
+mod rasterize;
+pub use rasterize::RasterSampling;
+mod registration;
+mod rotation;
+pub use rotation::{RotatedGrid, RotatedLattice};
+pub use registration::GeometryComparison;
+mod sample;
+mod scan;
+pub use scan::{find_grd_files, read_header_file, scan_headers};
+mod set;
+pub use set::GridSet;
+mod stats;
+pub use stats::Stats;
+mod stochastic;
+pub use stochastic::{NodeCorrelation, StochasticVolume, VolumeDistribution};
+mod subdivide;
+mod template;
+pub use template::{MetadataTemplate, TemplateContext};
+pub mod testing;
+mod timeseries;
+mod topology;
+pub use topology::{DelaunayCheck, DelaunayViolation, MeshTopology};
+mod transform;
+mod uncertainty;
+pub use uncertainty::{GridWithUncertainty, VolumeEstimate};
+mod vertical;
+pub use vertical::VerticalDatum;
+mod write;
+pub mod zonal;
+
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "arrow")]
+pub use arrow::ArrowGrid;
+
+#[cfg(feature = "serde")]
+mod serialization;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "image")]
+mod imaging;
+
 /// units of measure for a given dimension
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnitOfMeasure {
     /// feet
     Feet,
     /// meters
     Meters,
+    /// some other unit (or none at all), by its raw code; seen for *z* in
+    /// the wild, where Petra probably means "none" or "other"
+    Other(u32),
 }
 
 impl UnitOfMeasure {
-    fn from_code(code: u32) -> Option<UnitOfMeasure> {
+    fn from_code(code: u32) -> UnitOfMeasure {
         match code {
-            0 => Some(UnitOfMeasure::Feet),
-            1 => Some(UnitOfMeasure::Meters),
-            _ => None,
+            0 => UnitOfMeasure::Feet,
+            1 => UnitOfMeasure::Meters,
+            other => UnitOfMeasure::Other(other),
+        }
+    }
+
+    /// the raw code Petra uses for this unit of measure
+    pub fn code(self) -> u32 {
+        match self {
+            UnitOfMeasure::Feet => 0,
+            UnitOfMeasure::Meters => 1,
+            UnitOfMeasure::Other(code) => code,
+        }
+    }
+
+    /// the UDUNITS name of this unit of measure (as written to netCDF and
+    /// Zarr `units` attributes, and used by [Quantity::from_units]), if
+    /// known; Petra's feet are the US survey feet of state plane systems
+    pub fn udunits(self) -> Option<&'static str> {
+        match self {
+            UnitOfMeasure::Feet => Some("US_survey_foot"),
+            UnitOfMeasure::Meters => Some("m"),
+            UnitOfMeasure::Other(_) => None,
         }
     }
 }
@@ -79,8 +193,14 @@ impl UnitOfMeasure {
 ///
 /// data layout is described for each variant, below
 ///
-/// Petra represents missing data in grids as `1e30` exactly; we recode this
-/// to [f64::NAN] in keeping with more modern convention
+/// Petra represents missing data in grids as `1e30` exactly ([PETRA_NULL]);
+/// we recode this to [f64::NAN] in keeping with more modern convention,
+/// unless asked not to (see [ReadOptions::nulls_to_nan])
+///
+/// the arrays are reference-counted and copy-on-write ([ndarray::ArcArray]),
+/// so cloning a grid (e.g. to hand it to several exports or analyses) is
+/// cheap; the data is only copied when one of the clones is modified. use
+/// [ndarray::ArcArray::into_owned] for an array of your own
 #[derive(Clone, Debug)]
 pub enum GridData {
     /// a rectangular (rows × columns) grid
@@ -99,7 +219,7 @@ pub enum GridData {
     /// you may wish to use e.g. [ndarray::ArrayBase::invert_axis] for these
     /// applications, or to use application-specific options (like passing
     /// `origin='lower'` to `matplotlib.pyplot.imshow`)
-    Rectangular(Array2<f64>),
+    Rectangular(ArcArray2<f64>),
 
     /// a triangular (n_triangles × 3 vertices × 3 dimensions) grid
     ///
@@ -107,11 +227,12 @@ pub enum GridData {
     /// (but haven't verified) that triangles are stored with their vertices in
     /// counterclockwise order, because they seem to work over in Python-land
     /// with `matplotlib.tri.Triangulation`
-    Triangular(Array3<f64>),
+    Triangular(ArcArray<f64, Ix3>),
 }
 
 /// a Petra grid
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Grid {
     /// we think this is the version number; always 2, as far as we can tell
     pub version: u32,
@@ -181,8 +302,8 @@ pub struct Grid {
     /// we think this string describes the map datum (e.g. "NAD27")
     pub datum: String,
 
-    /// we think this number is used to describe the gridding method, but
-    /// we're not sure how
+    /// we think this number describes the gridding method; see
+    /// [GridMethod] (and [Grid::method]) for our best guess at decoding it
     pub grid_method: u32,
 
     /// likewise, we think this stores values of an enumerated describing the
@@ -197,13 +318,114 @@ pub struct Grid {
     /// (observed values look like plausible latitudes)
     pub rlat: f64,
 
+    /// the vertical reference of the *z* values; GRD files don't record
+    /// this, so it's [VerticalDatum::Unknown] for grids read from them until
+    /// set otherwise
+    pub vertical_datum: VerticalDatum,
+
+    /// what the *z* values measure, for derived grids (e.g. thicknesses or
+    /// volumes); GRD files don't record this either, so it's `None` for
+    /// grids read from them until set otherwise (see [Grid::z_quantity])
+    pub quantity: Option<Quantity>,
+
     /// the actual grid data, according to its inferred format
     pub data: GridData,
 }
 
+/// the metadata of a Petra grid, without its data; see [Grid] for more
+/// detail on each field
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridHeader {
+    /// the (probable) format version number
+    pub version: u32,
+
+    /// the grid name
+    pub name: String,
+
+    /// the "size" (rows × columns)
+    pub size: u32,
+
+    /// the number of rows (in the *y* dimension)
+    pub rows: u32,
+
+    /// the number of columns (in the *x* dimension)
+    pub columns: u32,
+
+    /// the number of triangles; zero for rectangular grids
+    pub n_triangles: u32,
+
+    /// minimum bound in the *x* dimension
+    pub xmin: f64,
+
+    /// maximum bound in the *x* dimension
+    pub xmax: f64,
+
+    /// minimum bound in the *y* dimension
+    pub ymin: f64,
+
+    /// maximum bound in the *y* dimension
+    pub ymax: f64,
+
+    /// step in the *x* dimension
+    pub xstep: f64,
+
+    /// step in the *y* dimension
+    pub ystep: f64,
+
+    /// minimum value in the *z* dimension
+    pub zmin: f64,
+
+    /// maximum value in the *z* dimension
+    pub zmax: f64,
+
+    /// units of measure in the *x* and *y* dimensions
+    pub xyunits: UnitOfMeasure,
+
+    /// units of measure in the *z* dimension
+    pub zunits: UnitOfMeasure,
+
+    /// date of creation (or perhaps last modification)
+    pub created_date: PrimitiveDateTime,
+
+    /// the (probable) source of the data used in gridding
+    pub source_data: String,
+
+    /// unknown; usually "C66"
+    pub unknown_metadata: String,
+
+    /// the (probable) map projection
+    pub projection: String,
+
+    /// the (probable) map datum
+    pub datum: String,
+
+    /// the (probable) gridding method code (see [GridMethod])
+    pub grid_method: u32,
+
+    /// the (probable) map projection code
+    pub projection_code: u32,
+
+    /// the (probable) central meridian
+    pub cm: f64,
+
+    /// the (probable) reference latitude
+    pub rlat: f64,
+
+    /// the vertical reference of the *z* values (not recorded in GRD files)
+    pub vertical_datum: VerticalDatum,
+
+    /// what the *z* values measure (not recorded in GRD files)
+    pub quantity: Option<Quantity>,
+}
+
+const SIZE_OFFSET: u64 = 0x55;
+const XSPEC_OFFSET: u64 = 0x59;
+const YSPEC_OFFSET: u64 = 0x69;
 const CM_RLAT_OFFSET: u64 = 0xb9;
 const DATE_OFFSET: u64 = 0xe1;
 const ROWS_COLS_OFFSET: u64 = 0x3fd;
+const XYUNITS_OFFSET: u64 = 0x40d;
 const ZUNITS_OFFSET: u64 = 0x429;
 const N_TRIANGLES_OFFSET: u64 = 0x431;
 const SOURCE_OFFSET: u64 = 0x5b9;
@@ -219,104 +441,332 @@ const UNK_LEN: usize = 2009;
 const PROJ_LEN: usize = 65;
 const DATUM_LEN: usize = 195;
 
-const NAUGHTY_SPEC_REL_ERROR: f64 = 0.0001;
+/// options controlling how grids are read; see [Grid::read_with_options]
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    salvage: bool,
+    lenient: bool,
+    keep_nulls: bool,
+    nulls: NullPolicy,
+    observer: Option<WarningObserver>,
+}
 
-impl Grid { 
-    /// read a Petra [Grid] from a seekable source (including a file or buffer)
-    pub fn read<R: Read + Seek>(source: &mut R) -> Result<Grid, Error> {
-        source.rewind()?;
-        let version = source.read_u32::<LittleEndian>()?;
-        let name = read_petra_string::<_, NAME_LEN>(source)?;
-        let size = source.read_u32::<LittleEndian>()?;
-        let xmin = source.read_f64::<LittleEndian>()?;
-        let xmax = source.read_f64::<LittleEndian>()?;
-        let ymin = source.read_f64::<LittleEndian>()?;
-        let ymax = source.read_f64::<LittleEndian>()?;
-        let xstep = source.read_f64::<LittleEndian>()?;
-        let ystep = source.read_f64::<LittleEndian>()?;
-        let zmin = source.read_f64::<LittleEndian>()?;
-        let zmax = source.read_f64::<LittleEndian>()?;
+// a callback for warnings, wrapped so that ReadOptions can still be Debug
+#[derive(Clone)]
+struct WarningObserver(Arc<dyn Fn(&Warning) + Send + Sync>);
 
-        source.seek(SeekFrom::Start(CM_RLAT_OFFSET))?;
-        let cm = source.read_f64::<LittleEndian>()?;
-        let rlat = source.read_f64::<LittleEndian>()?;
+impl fmt::Debug for WarningObserver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("WarningObserver(..)")
+    }
+}
 
-        source.seek(SeekFrom::Start(DATE_OFFSET))?;
-        let created_date = petra_datetime(source.read_f64::<LittleEndian>()?);
+impl ReadOptions {
+    /// the default options, equivalent to [Grid::read]
+    pub fn new() -> ReadOptions {
+        ReadOptions::default()
+    }
 
-        source.seek(SeekFrom::Start(ROWS_COLS_OFFSET))?;
-        let rows = source.read_u32::<LittleEndian>()?;
-        let columns = source.read_u32::<LittleEndian>()?;
-        let grid_method = source.read_u32::<LittleEndian>()?;
-        let projection_code = source.read_u32::<LittleEndian>()?;
-        let xyunits = source.read_u32::<LittleEndian>()?;
-        let xyunits = UnitOfMeasure::from_code(xyunits)
-          .ok_or(Error::InvalidXYUnitOfMeasure(xyunits))?;
+    /// enable or disable "salvage" mode: when the data block is shorter than
+    /// the metadata indicates (as when a copy is interrupted), return the
+    /// data which is present along with a [Warning], rather than failing
+    ///
+    /// in salvage mode, values which are clearly garbage (non-finite, or far
+    /// outside the metadata-indicated bounds) are also marked missing, and
+    /// reported as [Warning::CorruptNodes] or [Warning::CorruptTriangles]
+    pub fn salvage(mut self, salvage: bool) -> ReadOptions {
+        self.salvage = salvage;
+        self
+    }
 
-        source.seek(SeekFrom::Start(ZUNITS_OFFSET))?;
-        let zunits = source.read_u32::<LittleEndian>()?;
-        let zunits = UnitOfMeasure::from_code(zunits)
-          .ok_or(Error::InvalidZUnitOfMeasure(zunits))?;
+    /// enable or disable "lenient" mode: when the header's size or *x*/*y*
+    /// dimension specs are inconsistent, but the grid can still be read,
+    /// return it along with a [Warning] (e.g. [Warning::InvalidXSpec]),
+    /// rather than failing
+    ///
+    /// the header fields are returned as stored, except that a size which
+    /// doesn't match the rows and columns is replaced with their product
+    pub fn lenient(mut self, lenient: bool) -> ReadOptions {
+        self.lenient = lenient;
+        self
+    }
 
-        source.seek(SeekFrom::Start(N_TRIANGLES_OFFSET))?;
-        let n_triangles = source.read_u32::<LittleEndian>()?;
+    /// enable (the default) or disable recoding of Petra's missing-value
+    /// sentinel ([PETRA_NULL]) to [f64::NAN] in the data block
+    ///
+    /// with recoding disabled, the sentinel values are returned as-is (see
+    /// [GridData::nulls_to_nan] to recode them later), though values which
+    /// salvage mode marks missing are NaN regardless
+    pub fn nulls_to_nan(mut self, convert: bool) -> ReadOptions {
+        self.keep_nulls = !convert;
+        self
+    }
 
-        if rows * columns != size {
-            return Err(Error::SizeMismatch(size, rows, columns));
+    /// recognize missing values in the data block by a [NullPolicy]'s
+    /// sentinels, rather than only Petra's own ([PETRA_NULL]), for files
+    /// written by software which uses another
+    ///
+    /// the sentinels are recoded to [f64::NAN] unless recoding is disabled
+    /// (see [ReadOptions::nulls_to_nan]), and in salvage mode they're never
+    /// marked corrupt
+    pub fn null_policy(mut self, policy: NullPolicy) -> ReadOptions {
+        self.nulls = policy;
+        self
+    }
+
+    /// register a callback to receive each [Warning] as it's encountered,
+    /// e.g. to stream data-quality issues into an application's own logs
+    /// during batch processing
+    ///
+    /// warnings are still returned from [Grid::read_with_options] as usual
+    pub fn on_warning<F>(mut self, observer: F) -> ReadOptions
+      where F: Fn(&Warning) + Send + Sync + 'static
+    {
+        self.observer = Some(WarningObserver(Arc::new(observer)));
+        self
+    }
+
+    // report a warning to the observer (and tracing), and collect it
+    fn warn(&self, warnings: &mut Vec<Warning>, warning: Warning) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(warning = %warning, "read warning");
+        if let Some(WarningObserver(observer)) = &self.observer {
+            observer(&warning);
         }
+        warnings.push(warning);
+    }
+}
 
-        let x_rel_err =
-          (xmin + (columns - 1) as f64 * xstep - xmax).abs() / xmax;
-        if x_rel_err > NAUGHTY_SPEC_REL_ERROR {
-            return Err(Error::InvalidXSpec(xmin, xmax, xstep, columns));
+/// non-fatal problems encountered while reading a grid with
+/// [Grid::read_with_options]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Warning {
+    /// the data block was shorter than the metadata-indicated grid size;
+    /// only the rows with data present were kept (with the header's rows,
+    /// size, and maximum *y* updated to match), and nodes past the end of
+    /// the data in the last of them were recorded as missing (NaN)
+    TruncatedRectangular(
+        /** number of nodes present */ u64,
+        /** metadata-indicated grid size */ u32
+    ),
+
+    /// the data block was shorter than the metadata-indicated triangle count;
+    /// only the complete triangles present were kept
+    TruncatedTriangular(
+        /** number of complete triangles present */ u64,
+        /** metadata-indicated triangle count */ u32
+    ),
+
+    /// some nodes of a rectangular grid held garbage values, and were
+    /// recorded as missing (NaN)
+    CorruptNodes(/** ranges of affected (row-major) node indices */ Vec<Range<u64>>),
+
+    /// some triangles of a triangular grid held garbage coordinates or
+    /// values, and all their coordinates were recorded as missing (NaN)
+    CorruptTriangles(/** ranges of affected triangle indices */ Vec<Range<u64>>),
+
+    /// in lenient mode, the metadata-indicated total grid size did not match
+    /// the product of the row and column counts (see [Error::SizeMismatch]),
+    /// and the product was used instead
+    SizeMismatch(/** grid size */ u32, /** rows */ u32, /** columns */ u32),
+
+    /// in lenient mode, the metadata-indicated *x* dimension spec was
+    /// incoherent (see [Error::InvalidXSpec]), but was kept as-is
+    InvalidXSpec(
+        /** minimum *x* */ f64,
+        /** maximum *x* */ f64,
+        /** *x* step */ f64,
+        /** number of columns */ u32
+    ),
+
+    /// in lenient mode, the metadata-indicated *y* dimension spec was
+    /// incoherent (see [Error::InvalidYSpec]), but was kept as-is
+    InvalidYSpec(
+        /** minimum *y* */ f64,
+        /** maximum *y* */ f64,
+        /** *y* step */ f64,
+        /** number of rows */ u32
+    ),
+}
+
+impl Warning {
+    /// a short, stable, machine-readable name for the kind of warning (e.g.
+    /// `"truncated_rectangular"`), suitable for scripts and logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Warning::TruncatedRectangular(..) => "truncated_rectangular",
+            Warning::TruncatedTriangular(..) => "truncated_triangular",
+            Warning::CorruptNodes(_) => "corrupt_nodes",
+            Warning::CorruptTriangles(_) => "corrupt_triangles",
+            Warning::SizeMismatch(..) => "size_mismatch",
+            Warning::InvalidXSpec(..) => "invalid_x_spec",
+            Warning::InvalidYSpec(..) => "invalid_y_spec",
         }
+    }
 
-        let y_rel_err =
-          (ymin + (rows - 1) as f64 * ystep - ymax).abs() / ymax;
-        if y_rel_err > NAUGHTY_SPEC_REL_ERROR {
-            return Err(Error::InvalidYSpec(ymin, ymax, ystep, rows));
+    /// the broad category of the problem: truncated or corrupt data is a
+    /// data-quality issue, while an incoherent header tolerated in lenient
+    /// mode is a format violation
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Warning::TruncatedRectangular(..)
+              | Warning::TruncatedTriangular(..)
+              | Warning::CorruptNodes(_)
+              | Warning::CorruptTriangles(_) => ErrorCategory::DataQuality,
+            Warning::SizeMismatch(..)
+              | Warning::InvalidXSpec(..)
+              | Warning::InvalidYSpec(..) => ErrorCategory::FormatViolation,
         }
+    }
 
-        let source_len = source.seek(SeekFrom::End(0))?;
-        let data_size = source_len - GRID_OFFSET;
+    /// the offset, within a GRD file, where the problem begins: the end of
+    /// the data present, the first corrupt node or triangle, or the
+    /// offending header field
+    pub fn offset(&self) -> u64 {
+        let first = |ranges: &[Range<u64>]| ranges.first()
+          .map(|r| r.start)
+          .unwrap_or(0);
+        match self {
+            Warning::TruncatedRectangular(present, _) =>
+                GRID_OFFSET + present * 8,
+            Warning::TruncatedTriangular(present, _) =>
+                GRID_OFFSET + present * 72,
+            Warning::CorruptNodes(ranges) =>
+                GRID_OFFSET + first(ranges) * 8,
+            Warning::CorruptTriangles(ranges) =>
+                GRID_OFFSET + first(ranges) * 72,
+            Warning::SizeMismatch(..) => SIZE_OFFSET,
+            Warning::InvalidXSpec(..) => XSPEC_OFFSET,
+            Warning::InvalidYSpec(..) => YSPEC_OFFSET,
+        }
+    }
+}
 
-        if n_triangles == 0 && data_size / 8 != size as u64 {
-            return Err(Error::InvalidRectangularSize(size, data_size));
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::TruncatedRectangular(present, size) =>
+                write!(f, "data truncated: only {} of {} nodes present",
+                  present, size),
+            Warning::TruncatedTriangular(present, count) =>
+                write!(f, "data truncated: only {} of {} triangles present",
+                  present, count),
+            Warning::CorruptNodes(ranges) =>
+                write!(f, "{} corrupt nodes marked missing (indices {})",
+                  ranges.iter().map(|r| r.end - r.start).sum::<u64>(),
+                  format_ranges(ranges)),
+            Warning::CorruptTriangles(ranges) =>
+                write!(f, "{} corrupt triangles marked missing (indices {})",
+                  ranges.iter().map(|r| r.end - r.start).sum::<u64>(),
+                  format_ranges(ranges)),
+            Warning::SizeMismatch(size, rows, columns) =>
+                write!(f, "total size {} != {} rows x {} columns; using {}",
+                  size, rows, columns, *rows as u64 * *columns as u64),
+            Warning::InvalidXSpec(min, max, step, columns) =>
+                write!(f, "invalid x spec: {} to {} by {} but {} columns \
+                  (kept as-is)", min, max, step, columns),
+            Warning::InvalidYSpec(min, max, step, rows) =>
+                write!(f, "invalid y spec: {} to {} by {} but {} rows \
+                  (kept as-is)", min, max, step, rows),
+        }
+    }
+}
+
+impl GridHeader {
+    /// read just the metadata of a Petra grid from a seekable source
+    /// (including a file or buffer), skipping the (possibly very large) data
+    /// block entirely
+    pub fn read<R: Read + Seek>(source: &mut R) -> Result<GridHeader, Error> {
+        let (header, _) = GridHeader::read_with_options(source,
+          &ReadOptions::new())?;
+        Ok(header)
+    }
+
+    /// read just the metadata of a Petra grid from a seekable source,
+    /// according to the provided [ReadOptions] (of which only lenient mode
+    /// matters here); see [Grid::read_with_options]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug",
+      skip_all, err))]
+    pub fn read_with_options<R: Read + Seek>(source: &mut R,
+      options: &ReadOptions) -> Result<(GridHeader, Vec<Warning>), Error> {
+        let mut warnings = Vec::new();
+        source.rewind()?;
+        let version = read_u32_field(source, "version")?;
+        let name = read_string_field::<_, NAME_LEN>(source, "name")?;
+        let size = read_u32_field(source, "size")?;
+        let xmin = read_f64_field(source, "xmin")?;
+        let xmax = read_f64_field(source, "xmax")?;
+        let ymin = read_f64_field(source, "ymin")?;
+        let ymax = read_f64_field(source, "ymax")?;
+        let xstep = read_f64_field(source, "xstep")?;
+        let ystep = read_f64_field(source, "ystep")?;
+        let zmin = read_f64_field(source, "zmin")?;
+        let zmax = read_f64_field(source, "zmax")?;
+
+        source.seek(SeekFrom::Start(CM_RLAT_OFFSET))?;
+        let cm = read_f64_field(source, "cm")?;
+        let rlat = read_f64_field(source, "rlat")?;
+
+        source.seek(SeekFrom::Start(DATE_OFFSET))?;
+        let created_date = read_field(source, "created_date",
+          |raw| petra_datetime(f64::from_le_bytes(raw)))?;
+
+        source.seek(SeekFrom::Start(ROWS_COLS_OFFSET))?;
+        let rows = read_u32_field(source, "rows")?;
+        let columns = read_u32_field(source, "columns")?;
+        let grid_method = read_u32_field(source, "grid_method")?;
+        let projection_code = read_u32_field(source, "projection_code")?;
+        let xyunits = read_u32_field(source, "xyunits")?;
+        let xyunits = UnitOfMeasure::from_code(xyunits);
+
+        source.seek(SeekFrom::Start(ZUNITS_OFFSET))?;
+        let zunits = read_u32_field(source, "zunits")?;
+        let zunits = UnitOfMeasure::from_code(zunits);
+
+        source.seek(SeekFrom::Start(N_TRIANGLES_OFFSET))?;
+        let n_triangles = read_u32_field(source, "n_triangles")?;
+
+        let mut size = size;
+        if !invariants::size_coherent(size, rows, columns) {
+            match rows.checked_mul(columns) {
+                Some(product) if options.lenient => {
+                    options.warn(&mut warnings,
+                      Warning::SizeMismatch(size, rows, columns));
+                    size = product;
+                },
+                _ => return Err(Error::SizeMismatch(size, rows, columns)),
+            }
         }
 
-        if n_triangles > 0 && data_size / 72 != n_triangles as u64 {
-            return Err(Error::InvalidTriangleCount(n_triangles, data_size));
+        if !invariants::spec_coherent(xmin, xmax, xstep, columns) {
+            if !options.lenient {
+                return Err(Error::InvalidXSpec(xmin, xmax, xstep, columns));
+            }
+            options.warn(&mut warnings,
+              Warning::InvalidXSpec(xmin, xmax, xstep, columns));
+        }
+
+        if !invariants::spec_coherent(ymin, ymax, ystep, rows) {
+            if !options.lenient {
+                return Err(Error::InvalidYSpec(ymin, ymax, ystep, rows));
+            }
+            options.warn(&mut warnings,
+              Warning::InvalidYSpec(ymin, ymax, ystep, rows));
         }
 
         source.seek(SeekFrom::Start(SOURCE_OFFSET))?;
-        let source_data = read_petra_string::<_, SOURCE_LEN>(source)?;
+        let source_data =
+          read_string_field::<_, SOURCE_LEN>(source, "source_data")?;
 
         source.seek(SeekFrom::Start(UNK_PROJ_DATUM_OFFSET))?;
-        let unknown_metadata = read_petra_string::<_, UNK_LEN>(source)?;
-        let projection = read_petra_string::<_, PROJ_LEN>(source)?;
-        let datum = read_petra_string::<_, DATUM_LEN>(source)?;
-
-        source.seek(SeekFrom::Start(GRID_OFFSET))?;
-        let data = if n_triangles == 0 {
-            let mut buf = vec![0.0; size as usize];
-            source.read_f64_into::<LittleEndian>(&mut buf[..])?;
-            petra_null_to_nan(&mut buf);
-            /* safety: we checked above that rows x columns == size and that the
-             * data size matched */
-            let arr = Array::from_shape_vec((rows as usize, columns as usize), buf)
-              .unwrap();
-            GridData::Rectangular(arr)
-        } else {
-            let mut buf = vec![0.0; n_triangles as usize * 9];
-            source.read_f64_into::<LittleEndian>(&mut buf[..])?;
-            petra_null_to_nan(&mut buf);
-            // safety: we checked above that n_triangles x 72 was the data size
-            let arr = Array::from_shape_vec(
-              (n_triangles as usize, 3, 3).strides((9, 1, 3)), buf).unwrap();
-            GridData::Triangular(arr)
-        };
+        let unknown_metadata =
+          read_string_field::<_, UNK_LEN>(source, "unknown_metadata")?;
+        let projection =
+          read_string_field::<_, PROJ_LEN>(source, "projection")?;
+        let datum = read_string_field::<_, DATUM_LEN>(source, "datum")?;
 
-        Ok(Grid {
+        Ok((GridHeader {
             version,
             name,
             size,
@@ -342,12 +792,273 @@ impl Grid {
             projection_code,
             cm,
             rlat,
+            vertical_datum: VerticalDatum::Unknown,
+            quantity: None,
+        }, warnings))
+    }
+}
+
+impl Grid { 
+    /// read a Petra [Grid] from a seekable source (including a file or buffer)
+    pub fn read<R: Read + Seek>(source: &mut R) -> Result<Grid, Error> {
+        let (grid, _) = Grid::read_with_options(source, &ReadOptions::new())?;
+        Ok(grid)
+    }
+
+    /// read just the metadata (name, extents, units, projection, date, and
+    /// so on) of a Petra grid from a seekable source, without reading or
+    /// allocating its data block; equivalent to [GridHeader::read], and
+    /// handy for cataloging many files (see also [scan_headers])
+    pub fn read_header<R: Read + Seek>(source: &mut R)
+      -> Result<GridHeader, Error> {
+        GridHeader::read(source)
+    }
+
+    /// read a Petra [Grid] from a seekable source (including a file or
+    /// buffer), according to the provided [ReadOptions]
+    ///
+    /// any non-fatal problems encountered are returned as [Warning]s
+    /// alongside the grid
+    pub fn read_with_options<R: Read + Seek>(source: &mut R,
+      options: &ReadOptions) -> Result<(Grid, Vec<Warning>), Error> {
+        let (mut header, mut warnings) =
+          GridHeader::read_with_options(source, options)?;
+        let data = read_data(source, &mut header, options, &mut warnings)?;
+        Ok((Grid::from_parts(header, data), warnings))
+    }
+
+    /// a copy of this grid's metadata
+    pub fn header(&self) -> GridHeader {
+        GridHeader {
+            version: self.version,
+            name: self.name.clone(),
+            size: self.size,
+            rows: self.rows,
+            columns: self.columns,
+            n_triangles: self.n_triangles,
+            xmin: self.xmin,
+            xmax: self.xmax,
+            ymin: self.ymin,
+            ymax: self.ymax,
+            xstep: self.xstep,
+            ystep: self.ystep,
+            zmin: self.zmin,
+            zmax: self.zmax,
+            xyunits: self.xyunits,
+            zunits: self.zunits,
+            created_date: self.created_date,
+            source_data: self.source_data.clone(),
+            unknown_metadata: self.unknown_metadata.clone(),
+            projection: self.projection.clone(),
+            datum: self.datum.clone(),
+            grid_method: self.grid_method,
+            projection_code: self.projection_code,
+            cm: self.cm,
+            rlat: self.rlat,
+            vertical_datum: self.vertical_datum,
+            quantity: self.quantity.clone(),
+        }
+    }
+
+    /// split the grid into its metadata and data, without copying either;
+    /// the data array can then be handed off on its own (e.g. to a
+    /// columnar buffer) while the metadata is kept separately
+    pub fn into_parts(self) -> (GridHeader, GridData) {
+        let header = GridHeader {
+            version: self.version,
+            name: self.name,
+            size: self.size,
+            rows: self.rows,
+            columns: self.columns,
+            n_triangles: self.n_triangles,
+            xmin: self.xmin,
+            xmax: self.xmax,
+            ymin: self.ymin,
+            ymax: self.ymax,
+            xstep: self.xstep,
+            ystep: self.ystep,
+            zmin: self.zmin,
+            zmax: self.zmax,
+            xyunits: self.xyunits,
+            zunits: self.zunits,
+            created_date: self.created_date,
+            source_data: self.source_data,
+            unknown_metadata: self.unknown_metadata,
+            projection: self.projection,
+            datum: self.datum,
+            grid_method: self.grid_method,
+            projection_code: self.projection_code,
+            cm: self.cm,
+            rlat: self.rlat,
+            vertical_datum: self.vertical_datum,
+            quantity: self.quantity,
+        };
+        (header, self.data)
+    }
+
+    /// reassemble a grid from its metadata and data (e.g. as split by
+    /// [Grid::into_parts]), without copying either
+    ///
+    /// no consistency checks are made: the data should match the
+    /// metadata's dimensions (`rows` × `columns` nodes, or `n_triangles`
+    /// triangles), or other operations may give nonsensical results
+    pub fn from_parts(header: GridHeader, data: GridData) -> Grid {
+        Grid {
+            version: header.version,
+            name: header.name,
+            size: header.size,
+            rows: header.rows,
+            columns: header.columns,
+            n_triangles: header.n_triangles,
+            xmin: header.xmin,
+            xmax: header.xmax,
+            ymin: header.ymin,
+            ymax: header.ymax,
+            xstep: header.xstep,
+            ystep: header.ystep,
+            zmin: header.zmin,
+            zmax: header.zmax,
+            xyunits: header.xyunits,
+            zunits: header.zunits,
+            created_date: header.created_date,
+            source_data: header.source_data,
+            unknown_metadata: header.unknown_metadata,
+            projection: header.projection,
+            datum: header.datum,
+            grid_method: header.grid_method,
+            projection_code: header.projection_code,
+            cm: header.cm,
+            rlat: header.rlat,
+            vertical_datum: header.vertical_datum,
+            quantity: header.quantity,
             data,
-        })
+        }
     }
 }
 
-/// errors which may occur while reading a grid
+/* read the data block described by a header, checking its size against the
+ * metadata; in salvage mode, a short data block is accepted, and the header's
+ * row or triangle count is updated to match what was actually read */
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug",
+  skip_all, err))]
+fn read_data<R: Read + Seek>(source: &mut R, header: &mut GridHeader,
+  options: &ReadOptions, warnings: &mut Vec<Warning>)
+  -> Result<GridData, Error> {
+    let GridHeader {
+        size, rows, columns, n_triangles, xmin, xmax, ymin, ymax, zmin, zmax, ..
+    } = *header;
+
+    let source_len = source.seek(SeekFrom::End(0))?;
+    let data_size = source_len.saturating_sub(GRID_OFFSET);
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(offset = format_args!("{:#x}", GRID_OFFSET),
+      bytes = data_size, size, n_triangles, "reading data block");
+
+    // the number of nodes or triangles we'll actually read
+    let mut n_present = if n_triangles == 0 {
+        size as u64
+    } else {
+        n_triangles as u64
+    };
+
+    let coherent = invariants::data_block_coherent(size, n_triangles,
+      data_size);
+    if n_triangles == 0 && !coherent {
+        let available = data_size / invariants::NODE_BYTES;
+        if options.salvage && available < size as u64 {
+            n_present = available;
+            options.warn(warnings,
+              Warning::TruncatedRectangular(n_present, size));
+        } else {
+            return Err(Error::InvalidRectangularSize(size, data_size));
+        }
+    }
+
+    if n_triangles > 0 && !coherent {
+        let available = data_size / invariants::TRIANGLE_BYTES;
+        if options.salvage && available < n_triangles as u64 {
+            n_present = available;
+            options.warn(warnings,
+              Warning::TruncatedTriangular(n_present, n_triangles));
+        } else {
+            return Err(Error::InvalidTriangleCount(n_triangles, data_size));
+        }
+    }
+
+    // salvage mode must see the raw values, so recodes nulls afterward
+    let nulls = &options.nulls;
+    let recode_nulls = !options.keep_nulls;
+    let recode_while_reading = (recode_nulls && !options.salvage)
+      .then_some(nulls);
+
+    source.seek(SeekFrom::Start(GRID_OFFSET))?;
+    if n_triangles == 0 {
+        /* a salvaged grid keeps only the rows with data present, so that a
+         * damaged header can't make us allocate far more than the file
+         * holds */
+        let rows = if n_present < size as u64 {
+            let kept = n_present.div_ceil(columns.max(1) as u64) as u32;
+            header.rows = kept;
+            header.size = kept * columns;
+            header.ymax = ymin + kept.saturating_sub(1) as f64 * header.ystep;
+            kept
+        } else {
+            rows
+        };
+        let len = (rows as usize).checked_mul(columns as usize)
+          .ok_or(Error::InvalidRectangularSize(size, data_size))?;
+
+        /* a zeroed buffer is much cheaper to allocate than one filled with
+         * NaN (the allocator can hand us fresh zero pages), and the bulk
+         * read overwrites it anyway; only a salvaged tail needs filling */
+        let mut buf = vec![0.0; len];
+        read_f64_blocks(source, &mut buf[..n_present as usize],
+          recode_while_reading)?;
+        buf[n_present as usize..].fill(f64::NAN);
+        if options.salvage {
+            let corrupt = mark_corrupt(&mut buf[..n_present as usize], 1,
+              |v| !plausible(v[0], zmin, zmax, nulls));
+            if !corrupt.is_empty() {
+                options.warn(warnings, Warning::CorruptNodes(corrupt));
+            }
+            if recode_nulls {
+                nulls.recode(&mut buf);
+            }
+        }
+        /* safety: buf holds rows x columns values (padded with NaN if
+         * salvaged), and a complete grid's rows x columns == size */
+        let arr = Array::from_shape_vec((rows as usize, columns as usize), buf)
+          .unwrap();
+        Ok(GridData::Rectangular(arr.into_shared()))
+    } else {
+        let mut buf = vec![0.0; n_present as usize * 9];
+        read_f64_blocks(source, &mut buf, recode_while_reading)?;
+        if options.salvage {
+            // triangles are stored as x1 x2 x3 y1 y2 y3 z1 z2 z3
+            let corrupt = mark_corrupt(&mut buf, 9, |t| {
+                t[0..3].iter().any(|&x| !plausible(x, xmin, xmax, nulls))
+                  || t[3..6].iter().any(|&y| !plausible(y, ymin, ymax, nulls))
+                  || t[6..9].iter().any(|&z| !plausible(z, zmin, zmax, nulls))
+            });
+            if !corrupt.is_empty() {
+                options.warn(warnings, Warning::CorruptTriangles(corrupt));
+            }
+            if recode_nulls {
+                nulls.recode(&mut buf);
+            }
+        }
+        /* safety: we checked above that n_triangles x 72 was the data size,
+         * or that n_present x 72 bytes were available */
+        let arr = Array::from_shape_vec(
+          (n_present as usize, 3, 3).strides(invariants::TRIANGLE_STRIDES),
+          buf).unwrap();
+        header.n_triangles = n_present as u32;
+        Ok(GridData::Triangular(arr.into_shared()))
+    }
+}
+
+/// errors which may occur while reading or working with a grid
 #[derive(Debug)]
 pub enum Error {
     /// an IO error
@@ -391,12 +1102,40 @@ pub enum Error {
      ),
 
      /// the *x* and *y* unit-of-measure code in the metadata did not match a
-     /// known value
+     /// known value (no longer produced when reading, since unknown codes
+     /// are kept as [UnitOfMeasure::Other])
      InvalidXYUnitOfMeasure(u32),
 
      /// the *z* unit-of-measure code in the metadata did not match a
-     /// known value
+     /// known value (no longer produced when reading, since unknown codes
+     /// are kept as [UnitOfMeasure::Other])
      InvalidZUnitOfMeasure(u32),
+
+     /// the operation is only supported for rectangular grids
+     NotRectangular,
+
+     /// the operation is only supported for triangular grids
+     NotTriangular,
+
+     /// an input file in some other (non-Petra) format was malformed
+     InvalidInput(/** description of the problem */ String),
+
+     /// the caller asked for something which can't be done: an argument was
+     /// out of range, grids which had to share a lattice didn't, or a grid
+     /// can't be represented in the requested format
+     InvalidArgument(/** description of the problem */ String),
+
+     /// the operation requires a known vertical datum, but the grid's (or
+     /// the requested) datum is [VerticalDatum::Unknown]
+     UnknownVerticalDatum,
+
+     /// an iterative solution (such as
+     /// [gridding::MinimumCurvature::grid]) didn't meet its tolerance within
+     /// its iteration limit
+     NotConverged(
+         /** number of iterations made */ usize,
+         /** final residual, relative to the tolerance's reference */ f64
+     ),
 }
 
 impl fmt::Display for Error {
@@ -424,12 +1163,174 @@ impl fmt::Display for Error {
                 write!(f, "unknown XY unit-of-measure code {}", code),
             Error::InvalidZUnitOfMeasure(code) =>
                 write!(f, "unknown Z unit-of-measure code {}", code),
+            Error::NotRectangular =>
+                write!(f, "operation requires a rectangular grid"),
+            Error::NotTriangular =>
+                write!(f, "operation requires a triangular grid"),
+            Error::InvalidInput(msg) =>
+                write!(f, "invalid input: {}", msg),
+            Error::InvalidArgument(msg) =>
+                write!(f, "invalid argument: {}", msg),
+            Error::UnknownVerticalDatum =>
+                write!(f, "operation requires a known vertical datum"),
+            Error::NotConverged(iterations, residual) =>
+                write!(f, "no convergence after {} iterations (residual {})",
+                  iterations, residual),
         }
     }
 }
 
 impl error::Error for Error { }
 
+impl Error {
+    /// a short, stable, machine-readable name for the kind of error (e.g.
+    /// `"io"` or `"invalid_x_spec"`), suitable for scripts and logs; I/O
+    /// errors from running out of data are reported as `"truncated"`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::IOError(e) if e.kind() == io::ErrorKind::UnexpectedEof =>
+                "truncated",
+            Error::IOError(_) => "io",
+            Error::SizeMismatch(..) => "size_mismatch",
+            Error::InvalidXSpec(..) => "invalid_x_spec",
+            Error::InvalidYSpec(..) => "invalid_y_spec",
+            Error::InvalidRectangularSize(..) => "invalid_rectangular_size",
+            Error::InvalidTriangleCount(..) => "invalid_triangle_count",
+            Error::InvalidXYUnitOfMeasure(_) => "invalid_xy_unit_of_measure",
+            Error::InvalidZUnitOfMeasure(_) => "invalid_z_unit_of_measure",
+            Error::NotRectangular => "not_rectangular",
+            Error::NotTriangular => "not_triangular",
+            Error::InvalidInput(_) => "invalid_input",
+            Error::InvalidArgument(_) => "invalid_argument",
+            Error::UnknownVerticalDatum => "unknown_vertical_datum",
+            Error::NotConverged(..) => "not_converged",
+        }
+    }
+
+    /// the broad category of the error, for deciding what to do about it
+    /// (see [ErrorCategory]); I/O errors are categorized by their
+    /// [io::ErrorKind], so that e.g. running out of data is a format
+    /// violation, and a missing file isn't worth retrying
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::IOError(e) => io_category(e.kind()),
+            Error::SizeMismatch(..)
+              | Error::InvalidXSpec(..)
+              | Error::InvalidYSpec(..)
+              | Error::InvalidRectangularSize(..)
+              | Error::InvalidTriangleCount(..)
+              | Error::InvalidXYUnitOfMeasure(_)
+              | Error::InvalidZUnitOfMeasure(_)
+              | Error::InvalidInput(_) => ErrorCategory::FormatViolation,
+            Error::InvalidArgument(_) => ErrorCategory::InvalidArgument,
+            Error::UnknownVerticalDatum
+              | Error::NotConverged(..) => ErrorCategory::DataQuality,
+            Error::NotRectangular
+              | Error::NotTriangular => ErrorCategory::Unsupported,
+        }
+    }
+
+    /// the offset, within a GRD file, of the header field or data block
+    /// which the error concerns, if any
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::SizeMismatch(..) => Some(SIZE_OFFSET),
+            Error::InvalidXSpec(..) => Some(XSPEC_OFFSET),
+            Error::InvalidYSpec(..) => Some(YSPEC_OFFSET),
+            Error::InvalidRectangularSize(..)
+              | Error::InvalidTriangleCount(..) => Some(GRID_OFFSET),
+            Error::InvalidXYUnitOfMeasure(_) => Some(XYUNITS_OFFSET),
+            Error::InvalidZUnitOfMeasure(_) => Some(ZUNITS_OFFSET),
+            _ => None,
+        }
+    }
+}
+
+/* the category of an I/O error: only failures which might clear up on
+ * their own (timeouts, interruptions, dropped connections) are worth
+ * retrying */
+fn io_category(kind: io::ErrorKind) -> ErrorCategory {
+    use io::ErrorKind::*;
+    match kind {
+        UnexpectedEof | InvalidData => ErrorCategory::FormatViolation,
+        InvalidInput | InvalidFilename => ErrorCategory::InvalidArgument,
+        Unsupported => ErrorCategory::Unsupported,
+        Interrupted | TimedOut | WouldBlock | ResourceBusy | Deadlock
+          | ConnectionRefused | ConnectionReset | ConnectionAborted
+          | NotConnected | BrokenPipe | AddrInUse | AddrNotAvailable
+          | NetworkDown | NetworkUnreachable | HostUnreachable
+          | StaleNetworkFileHandle | ExecutableFileBusy => ErrorCategory::Io,
+        NotFound | PermissionDenied | AlreadyExists | IsADirectory
+          | NotADirectory | DirectoryNotEmpty | ReadOnlyFilesystem
+          | StorageFull | QuotaExceeded | FileTooLarge | NotSeekable
+          | CrossesDevices | TooManyLinks | WriteZero | OutOfMemory =>
+            ErrorCategory::Environment,
+        _ => ErrorCategory::Other,
+    }
+}
+
+/// broad categories of [Error]s and [Warning]s, so that batch tools can
+/// decide what to do about a failure without knowing every kind
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorCategory {
+    /// reading or writing failed for a reason which may clear up on its own
+    /// (a timeout, an interrupted call, a network share going away); worth
+    /// retrying
+    Io,
+
+    /// reading or writing failed because of the environment: a missing
+    /// file, denied permissions, a full disk, or an output which already
+    /// exists; retrying won't help until someone fixes the problem
+    Environment,
+
+    /// the caller asked for something which can't be done (an out-of-range
+    /// option, grids on different lattices, a grid too large for the
+    /// requested format); retrying won't help
+    InvalidArgument,
+
+    /// the file (or other input) isn't valid in its format: it's truncated,
+    /// or its header is incoherent or doesn't match its data; retrying won't
+    /// help, so the file should be set aside for inspection
+    FormatViolation,
+
+    /// the file could be read, but some of its data or metadata is missing
+    /// or suspect (as reported by salvage mode, or an unknown vertical
+    /// datum); worth flagging, but the grid may still be usable
+    DataQuality,
+
+    /// the operation isn't supported for this grid (e.g. one which needs a
+    /// rectangular grid, given a triangular one)
+    Unsupported,
+
+    /// a failure which couldn't be classified, such as one reported by
+    /// another library while writing an export; don't assume retrying will
+    /// help
+    Other,
+}
+
+impl ErrorCategory {
+    /// a short, stable, machine-readable name for the category (e.g.
+    /// `"format_violation"`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorCategory::Io => "io",
+            ErrorCategory::Environment => "environment",
+            ErrorCategory::InvalidArgument => "invalid_argument",
+            ErrorCategory::FormatViolation => "format_violation",
+            ErrorCategory::DataQuality => "data_quality",
+            ErrorCategory::Unsupported => "unsupported",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(other: io::Error) -> Self {
         Self::IOError(other)
@@ -446,12 +1347,57 @@ fn petra_string(buf: &[u8]) -> String {
     String::from_utf8_lossy(&buf[0..len]).into_owned()
 }
 
-// read from a file or buffer, as above, given a fixed width
-fn read_petra_string<R: Read, const WIDTH: usize>(
-  source: &mut R) -> Result<String, io::Error> {
-    let mut buf = [0u8; WIDTH];
-    source.read_exact(&mut buf)?;
-    Ok(petra_string(&buf))
+/* read a fixed-width header field and decode it; with the `tracing` feature
+ * enabled, each field's offset, raw bytes, and decoded value are recorded as
+ * a trace-level event, so that odd files can be diagnosed from logs alone */
+fn read_field<R, T, F, const WIDTH: usize>(source: &mut R,
+  field: &'static str, decode: F) -> Result<T, io::Error>
+  where R: Read + Seek,
+        T: fmt::Debug,
+        F: FnOnce([u8; WIDTH]) -> T,
+{
+    #[cfg(feature = "tracing")]
+    let offset = source.stream_position()?;
+    let mut raw = [0u8; WIDTH];
+    source.read_exact(&mut raw)?;
+    let value = decode(raw);
+    #[cfg(feature = "tracing")]
+    tracing::trace!(field, offset = format_args!("{:#x}", offset),
+      raw = %trace_hex(&raw), value = ?value, "read header field");
+    #[cfg(not(feature = "tracing"))]
+    let _ = field;
+    Ok(value)
+}
+
+fn read_u32_field<R: Read + Seek>(source: &mut R, field: &'static str)
+  -> Result<u32, io::Error> {
+    read_field(source, field, u32::from_le_bytes)
+}
+
+fn read_f64_field<R: Read + Seek>(source: &mut R, field: &'static str)
+  -> Result<f64, io::Error> {
+    read_field(source, field, f64::from_le_bytes)
+}
+
+// read a fixed-width string field, as above
+fn read_string_field<R: Read + Seek, const WIDTH: usize>(source: &mut R,
+  field: &'static str) -> Result<String, io::Error> {
+    read_field(source, field, |raw: [u8; WIDTH]| petra_string(&raw))
+}
+
+// hex bytes for trace events, eliding the NUL padding of long string fields
+#[cfg(feature = "tracing")]
+fn trace_hex(raw: &[u8]) -> String {
+    const MAX_TRACE_BYTES: usize = 64;
+    let shown = raw.len().min(MAX_TRACE_BYTES);
+    let mut hex = raw[..shown].iter()
+      .map(|b| format!("{:02x}", b))
+      .collect::<Vec<_>>()
+      .join(" ");
+    if shown < raw.len() {
+        hex.push_str(&format!(" ... ({} bytes)", raw.len()));
+    }
+    hex
 }
 
 // Petra has a goofy date/time format (from Delphi)
@@ -461,14 +1407,227 @@ fn petra_datetime(days_since_origin: f64) -> PrimitiveDateTime {
     DELPHI_DATETIME_ORIGIN + Duration::seconds_f64(days_since_origin * 86_400.0)
 }
 
-/* Petra uses 1e30, exactly, as a floating-point null/no-data value; we'll
- * recode this to NaN following modern conventions */
-const PETRA_NULL: f64 = 1e30;
+/// the value (`1e30`, exactly) Petra uses for missing *z* values; see
+/// [ReadOptions::nulls_to_nan]
+pub const PETRA_NULL: f64 = 1e30;
+
+// the number of values read at once by read_f64_blocks (1 MiB worth)
+const READ_BLOCK: usize = 1 << 17;
+
+/* bulk-read little-endian f64s (byteorder reads straight into the buffer's
+ * bytes, converting in place only on big-endian targets), in blocks small
+ * enough to stay in cache, optionally recoding a policy's nulls to NaN as
+ * each block arrives; this makes one pass over a big grid's memory rather
+ * than two */
+fn read_f64_blocks<R: Read>(source: &mut R, buf: &mut [f64],
+  recode_nulls: Option<&NullPolicy>) -> Result<(), io::Error> {
+    for block in buf.chunks_mut(READ_BLOCK) {
+        source.read_f64_into::<LittleEndian>(block)?;
+        if let Some(nulls) = recode_nulls {
+            nulls.recode(block);
+        }
+    }
+    Ok(())
+}
+
+/* in salvage mode, we treat values as garbage if they're non-finite, or if
+ * they're further outside the metadata-indicated bounds than this fraction of
+ * the bounds' span; garbage bytes read as f64s tend to be wildly out of range,
+ * so this can be generous */
+const CORRUPT_MARGIN: f64 = 0.5;
 
-fn petra_null_to_nan(data: &mut [f64]) {
-    for x in data {
-        if *x == PETRA_NULL {
-            *x = f64::NAN;
+// is a raw value either a null sentinel or plausibly within [min, max]?
+fn plausible(v: f64, min: f64, max: f64, nulls: &NullPolicy) -> bool {
+    if nulls.is_sentinel(v) {
+        return true;
+    }
+    let margin = (max - min).abs() * CORRUPT_MARGIN;
+    v.is_finite() && v >= min - margin && v <= max + margin
+}
+
+/* replace each chunk of data which is_corrupt with NaN, and return the ranges
+ * of chunk indices affected */
+fn mark_corrupt<F: Fn(&[f64]) -> bool>(data: &mut [f64], chunk_size: usize,
+  is_corrupt: F) -> Vec<Range<u64>> {
+    let mut ranges: Vec<Range<u64>> = Vec::new();
+    for (i, chunk) in data.chunks_exact_mut(chunk_size).enumerate() {
+        if !is_corrupt(chunk) {
+            continue;
+        }
+        chunk.fill(f64::NAN);
+        let i = i as u64;
+        match ranges.last_mut() {
+            Some(r) if r.end == i => r.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+fn format_ranges(ranges: &[Range<u64>]) -> String {
+    ranges.iter().map(|r| {
+        if r.end - r.start == 1 {
+            r.start.to_string()
+        } else {
+            format!("{}-{}", r.start, r.end - 1)
         }
+    }).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::testing::{Lattice, Surface, to_grd_bytes};
+
+    fn plane() -> Grid {
+        Surface::Plane { z0: 100.0, dzdx: 0.01, dzdy: -0.02 }
+          .rectangular(&Lattice::default())
+    }
+
+    fn dome() -> Grid {
+        Surface::Dome { x0: 2500.0, y0: 3000.0, height: 500.0, radius: 2000.0 }
+          .triangular(&Lattice::default())
+    }
+
+    fn read(bytes: Vec<u8>, options: &ReadOptions)
+      -> Result<(Grid, Vec<Warning>), Error> {
+        Grid::read_with_options(&mut Cursor::new(bytes), options)
+    }
+
+    fn assert_same_header(read: &Grid, written: &Grid) {
+        assert_eq!(read.name, written.name);
+        assert_eq!((read.size, read.rows, read.columns, read.n_triangles),
+          (written.size, written.rows, written.columns, written.n_triangles));
+        assert_eq!([read.xmin, read.xmax, read.ymin, read.ymax],
+          [written.xmin, written.xmax, written.ymin, written.ymax]);
+        assert_eq!([read.xstep, read.ystep, read.zmin, read.zmax],
+          [written.xstep, written.ystep, written.zmin, written.zmax]);
+        assert_eq!((read.xyunits, read.zunits),
+          (written.xyunits, written.zunits));
+        assert_eq!(read.projection, written.projection);
+        assert_eq!(read.datum, written.datum);
+        assert_eq!(read.source_data, written.source_data);
+    }
+
+    #[test]
+    fn rectangular_grids_round_trip() {
+        let grid = plane();
+        let (read, warnings) = read(to_grd_bytes(&grid), &ReadOptions::new())
+          .expect("read back a rectangular grid");
+        assert!(warnings.is_empty());
+        assert_same_header(&read, &grid);
+        assert_eq!(read.rectangular().unwrap(), grid.rectangular().unwrap());
+    }
+
+    #[test]
+    fn triangular_grids_round_trip() {
+        let grid = dome();
+        let (read, warnings) = read(to_grd_bytes(&grid), &ReadOptions::new())
+          .expect("read back a triangular grid");
+        assert!(warnings.is_empty());
+        assert_same_header(&read, &grid);
+        match (&read.data, &grid.data) {
+            (GridData::Triangular(read), GridData::Triangular(written)) =>
+                assert_eq!(read, written),
+            _ => panic!("expected triangular grids"),
+        }
+    }
+
+    #[test]
+    fn incoherent_extents_are_not_written() {
+        let mut grid = plane();
+        grid.xmax += 1.0;
+        assert!(matches!(grid.write(&mut Vec::new()),
+          Err(Error::InvalidXSpec(..))));
+
+        let mut grid = plane();
+        grid.ystep *= 2.0;
+        assert!(matches!(grid.write(&mut Vec::new()),
+          Err(Error::InvalidYSpec(..))));
+    }
+
+    #[test]
+    fn truncated_grids_are_rejected_unless_salvaged() {
+        let mut bytes = to_grd_bytes(&plane());
+        bytes.truncate(GRID_OFFSET as usize + 45 * 8);
+        assert!(matches!(read(bytes.clone(), &ReadOptions::new()),
+          Err(Error::InvalidRectangularSize(600, 360))));
+
+        let mut bytes = to_grd_bytes(&dome());
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(read(bytes, &ReadOptions::new()),
+          Err(Error::InvalidTriangleCount(..))));
+    }
+
+    #[test]
+    fn salvage_keeps_only_the_complete_triangles_present() {
+        let grid = dome();
+        let mut bytes = to_grd_bytes(&grid);
+        // ten triangles and part of the eleventh
+        bytes.truncate(GRID_OFFSET as usize + (10 * 9 + 4) * 8);
+
+        let options = ReadOptions::new().salvage(true);
+        let (salvaged, warnings) = read(bytes, &options)
+          .expect("salvage a truncated grid");
+        assert!(matches!(warnings.as_slice(),
+          [Warning::TruncatedTriangular(10, n)] if *n == grid.n_triangles));
+        match (&salvaged.data, &grid.data) {
+            (GridData::Triangular(read), GridData::Triangular(written)) => {
+                assert_eq!(read.dim(), (10, 3, 3));
+                assert_eq!(read, &written.slice(ndarray::s![..10, .., ..]));
+            },
+            _ => panic!("expected triangular grids"),
+        }
+    }
+
+    #[test]
+    fn lenient_reads_tolerate_a_bad_size_in_a_truncated_grid() {
+        let grid = plane();
+        let mut bytes = to_grd_bytes(&grid);
+        let size = SIZE_OFFSET as usize;
+        bytes[size..size + 4].copy_from_slice(&599u32.to_le_bytes());
+        bytes.truncate(GRID_OFFSET as usize + 300 * 8);
+
+        assert!(matches!(read(bytes.clone(), &ReadOptions::new().salvage(true)),
+          Err(Error::SizeMismatch(599, 20, 30))));
+
+        let options = ReadOptions::new().salvage(true).lenient(true);
+        let (read, warnings) = read(bytes, &options)
+          .expect("read a truncated grid leniently");
+        assert!(matches!(warnings.as_slice(), [
+            Warning::SizeMismatch(599, 20, 30),
+            Warning::TruncatedRectangular(300, 600),
+        ]));
+        assert_eq!((read.rows, read.columns, read.size), (10, 30, 300));
+        assert_eq!(read.rectangular().unwrap(),
+          grid.rectangular().unwrap().slice(ndarray::s![..10, ..]));
+    }
+
+    #[test]
+    fn salvage_keeps_only_the_rows_present() {
+        let grid = plane();
+        let mut bytes = to_grd_bytes(&grid);
+        // a row and a half of the 20 x 30 lattice
+        bytes.truncate(GRID_OFFSET as usize + 45 * 8);
+
+        let options = ReadOptions::new().salvage(true);
+        let (salvaged, warnings) =
+          Grid::read_with_options(&mut Cursor::new(bytes), &options)
+            .expect("salvage a truncated grid");
+        assert!(matches!(warnings.as_slice(),
+          [Warning::TruncatedRectangular(45, 600)]));
+        assert_eq!((salvaged.rows, salvaged.columns, salvaged.size),
+          (2, 30, 60));
+        assert_eq!(salvaged.ymax, grid.ymin + grid.ystep);
+
+        let z = salvaged.rectangular().unwrap();
+        let original = grid.rectangular().unwrap();
+        assert_eq!(z.dim(), (2, 30));
+        assert_eq!(z.row(0), original.row(0));
+        assert_eq!(z.slice(ndarray::s![1, ..15]),
+          original.slice(ndarray::s![1, ..15]));
+        assert!(z.slice(ndarray::s![1, 15..]).iter().all(|z| z.is_nan()));
     }
 }
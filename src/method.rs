@@ -0,0 +1,107 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! the gridding algorithms Petra records in its grid files
+
+use std::fmt;
+
+use crate::{Grid, GridHeader};
+
+/// the algorithm Petra used to produce a grid, as decoded from
+/// [Grid::grid_method]
+///
+/// the mapping from codes to algorithms is our best guess, following the
+/// order in which Petra's gridding dialog lists them; it hasn't been
+/// confirmed for every code, so the raw code is always kept as well
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GridMethod {
+    /// least-squares fitting of local planes to nearby data
+    LeastSquares,
+    /// "highly connected features", which honors trends across sparse data
+    HighlyConnected,
+    /// triangulation (a TIN) of the data points
+    Triangulation,
+    /// kriging
+    Kriging,
+    /// minimum-curvature ("flex") gridding
+    MinimumCurvature,
+    /// inverse-distance weighting
+    InverseDistance,
+    /// a method we don't recognize, by its raw code
+    Unknown(u32),
+}
+
+impl GridMethod {
+    /// decode a Petra gridding method code
+    pub fn from_code(code: u32) -> GridMethod {
+        match code {
+            0 => GridMethod::LeastSquares,
+            1 => GridMethod::HighlyConnected,
+            2 => GridMethod::Triangulation,
+            3 => GridMethod::Kriging,
+            4 => GridMethod::MinimumCurvature,
+            5 => GridMethod::InverseDistance,
+            other => GridMethod::Unknown(other),
+        }
+    }
+
+    /// the raw code Petra uses for this method
+    pub fn code(self) -> u32 {
+        match self {
+            GridMethod::LeastSquares => 0,
+            GridMethod::HighlyConnected => 1,
+            GridMethod::Triangulation => 2,
+            GridMethod::Kriging => 3,
+            GridMethod::MinimumCurvature => 4,
+            GridMethod::InverseDistance => 5,
+            GridMethod::Unknown(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for GridMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridMethod::LeastSquares => write!(f, "least squares"),
+            GridMethod::HighlyConnected =>
+                write!(f, "highly connected features"),
+            GridMethod::Triangulation => write!(f, "triangulation"),
+            GridMethod::Kriging => write!(f, "kriging"),
+            GridMethod::MinimumCurvature => write!(f, "minimum curvature"),
+            GridMethod::InverseDistance => write!(f, "inverse distance"),
+            GridMethod::Unknown(code) => write!(f, "unknown ({})", code),
+        }
+    }
+}
+
+impl Grid {
+    /// the gridding algorithm, decoded from [Grid::grid_method]
+    pub fn method(&self) -> GridMethod {
+        GridMethod::from_code(self.grid_method)
+    }
+}
+
+impl GridHeader {
+    /// the gridding algorithm, decoded from [GridHeader::grid_method]
+    pub fn method(&self) -> GridMethod {
+        GridMethod::from_code(self.grid_method)
+    }
+}
@@ -0,0 +1,118 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! mosaics of several grids onto a single lattice
+
+use ndarray::Array2;
+
+use crate::{Error, Grid, GridData};
+
+impl Grid {
+    /// combine several grids (e.g. pieces of one horizon, mapped separately)
+    /// into one rectangular grid covering all of them
+    ///
+    /// the mosaic takes its lattice (spacing and alignment), name, and other
+    /// metadata from the first grid (rasterized, if it's triangular; see
+    /// [Grid::rasterize]), and is extended to cover every grid;
+    /// each node takes its value from the first grid, in order, with a
+    /// non-missing value there (see [Grid::sample]), and is otherwise
+    /// missing. so that earlier grids take precedence, list the most
+    /// trusted grids first
+    ///
+    /// the grids must share their units (or this fails with
+    /// [Error::InvalidArgument]); coordinate systems aren't checked, so
+    /// reproject grids (see [Grid::transform_xy]) before combining them
+    pub fn mosaic(grids: &[&Grid]) -> Result<Grid, Error> {
+        let Some(first) = grids.first() else {
+            return Err(Error::InvalidArgument(
+              "no grids to mosaic".to_string()));
+        };
+        if let Some(other) = grids.iter().find(|g| g.xyunits != first.xyunits
+          || g.zunits != first.zunits) {
+            return Err(Error::InvalidArgument(format!(
+              "can't mosaic grids with different units ({:?}/{:?} and \
+               {:?}/{:?})", first.xyunits, first.zunits, other.xyunits,
+               other.zunits)));
+        }
+
+        // sampling rasters is much quicker than searching triangles
+        let rasters = grids.iter()
+          .map(|g| match g.data {
+              GridData::Rectangular(_) => None,
+              GridData::Triangular(_) => Some(g.rasterize()),
+          })
+          .collect::<Vec<_>>();
+        let grids = grids.iter().zip(&rasters)
+          .map(|(&g, raster)| raster.as_ref().unwrap_or(g))
+          .collect::<Vec<_>>();
+
+        let base = grids[0];
+        let (xstep, ystep) = (base.xstep.abs(), base.ystep.abs());
+        if !(xstep > 0.0 && ystep > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+              "can't mosaic onto a lattice with spacing {} x {}",
+              base.xstep, base.ystep)));
+        }
+
+        // the union of the extents, snapped outward to the first lattice
+        let (mut xmin, mut xmax) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut ymin, mut ymax) = (f64::INFINITY, f64::NEG_INFINITY);
+        for g in &grids {
+            xmin = xmin.min(g.xmin);
+            xmax = xmax.max(g.xmax);
+            ymin = ymin.min(g.ymin);
+            ymax = ymax.max(g.ymax);
+        }
+        let snap = |v: f64, origin: f64, step: f64, round: fn(f64) -> f64|
+          origin + round((v - origin) / step) * step;
+        let xmin = snap(xmin, base.xmin, xstep, f64::floor);
+        let xmax = snap(xmax, base.xmin, xstep, f64::ceil);
+        let ymin = snap(ymin, base.ymin, ystep, f64::floor);
+        let ymax = snap(ymax, base.ymin, ystep, f64::ceil);
+        let cols = ((xmax - xmin) / xstep).round() as usize + 1;
+        let rows = ((ymax - ymin) / ystep).round() as usize + 1;
+
+        let data = Array2::from_shape_fn((rows, cols), |(j, i)| {
+            let (x, y) = (xmin + i as f64 * xstep, ymin + j as f64 * ystep);
+            grids.iter()
+              .filter_map(|g| g.sample(x, y))
+              .find(|z| !z.is_nan())
+              .unwrap_or(f64::NAN)
+        });
+
+        let mut header = base.header();
+        header.rows = rows as u32;
+        header.columns = cols as u32;
+        header.size = (rows * cols) as u32;
+        header.n_triangles = 0;
+        header.xmin = xmin;
+        header.xmax = xmax;
+        header.ymin = ymin;
+        header.ymax = ymax;
+        header.xstep = xstep;
+        header.ystep = ystep;
+        let data = GridData::Rectangular(data.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Ok(Grid::from_parts(header, data))
+    }
+}
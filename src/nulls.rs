@@ -0,0 +1,211 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! missing-value handling
+
+use ndarray::{Array2, Axis};
+
+use crate::{GridData, PETRA_NULL, Stats};
+
+/// how missing values are recognized, and how they're treated when values
+/// are combined or summarized
+///
+/// in memory, missing values are always NaN (and NaN is always missing);
+/// a policy's sentinels are the other values which mean "missing" in a
+/// file. the default policy recognizes Petra's own sentinel ([PETRA_NULL])
+/// and propagates missing values through arithmetic and statistics.
+/// policies are used when reading (see [ReadOptions::null_policy](
+/// crate::ReadOptions::null_policy)), to recode grids read some other way
+/// before they're exported (see [GridData::recode_nulls]), and in
+/// statistics and algebra (see [GridData::stats_with] and
+/// [GridChunk::zip_with_nulls](crate::GridChunk::zip_with_nulls))
+#[derive(Clone, Debug, PartialEq)]
+pub struct NullPolicy {
+    sentinels: Vec<f64>,
+    arithmetic: NullArithmetic,
+}
+
+/// how missing values are treated when values are combined or summarized
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum NullArithmetic {
+    /// any missing value makes the result missing (statistics other than
+    /// the counts are NaN, as is any combination including a missing value)
+    #[default]
+    Propagate,
+
+    /// missing values are skipped by statistics, which cover the values
+    /// present; there's no such meaning for combining a missing value with
+    /// a present one, so that's still missing
+    Ignore,
+
+    /// missing values stand for the given value (e.g. zero, for a thickness
+    /// where a zone pinches out), in statistics and when combined with a
+    /// present value; combining two missing values is still missing
+    Fill(f64),
+}
+
+impl Default for NullPolicy {
+    fn default() -> NullPolicy {
+        NullPolicy {
+            sentinels: vec![PETRA_NULL],
+            arithmetic: NullArithmetic::default(),
+        }
+    }
+}
+
+impl NullPolicy {
+    /// the default policy
+    pub fn new() -> NullPolicy {
+        NullPolicy::default()
+    }
+
+    /// replace the sentinel values (e.g. with none at all, so that only NaN
+    /// is missing)
+    pub fn sentinels(mut self, sentinels: &[f64]) -> NullPolicy {
+        self.sentinels = sentinels.to_vec();
+        self
+    }
+
+    /// add a sentinel value (e.g. `-9999`, written by some other software)
+    pub fn sentinel(mut self, sentinel: f64) -> NullPolicy {
+        self.sentinels.push(sentinel);
+        self
+    }
+
+    /// set the treatment of missing values in arithmetic and statistics
+    pub fn arithmetic(mut self, arithmetic: NullArithmetic) -> NullPolicy {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    /// is this value missing (NaN, or one of the sentinels)?
+    pub fn is_null(&self, v: f64) -> bool {
+        v.is_nan() || self.is_sentinel(v)
+    }
+
+    /// is this value one of the sentinels?
+    pub fn is_sentinel(&self, v: f64) -> bool {
+        self.sentinels.contains(&v)
+    }
+
+    /// combine two values with `f`; if either is missing, so is the result,
+    /// unless missing values are filled (see [NullArithmetic::Fill]), in
+    /// which case `f` sees the fill value in place of the missing one
+    pub fn combine<F: Fn(f64, f64) -> f64>(&self, a: f64, b: f64, f: F)
+      -> f64 {
+        let (a_null, b_null) = (self.is_null(a), self.is_null(b));
+        match self.arithmetic {
+            _ if !a_null && !b_null => f(a, b),
+            NullArithmetic::Fill(fill) if !(a_null && b_null) => f(
+              if a_null { fill } else { a },
+              if b_null { fill } else { b }),
+            _ => f64::NAN,
+        }
+    }
+
+    /// summary statistics of a sequence of values
+    pub fn stats(&self, values: impl IntoIterator<Item = f64>) -> Stats {
+        let stats = Stats::from_values(values.into_iter()
+          .map(|v| match self.arithmetic {
+              NullArithmetic::Fill(fill) if self.is_null(v) => fill,
+              _ if self.is_sentinel(v) => f64::NAN,
+              _ => v,
+          }));
+        match self.arithmetic {
+            NullArithmetic::Propagate if stats.null_count > 0 => Stats {
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+                ..stats
+            },
+            _ => stats,
+        }
+    }
+
+    // recode sentinels to NaN, in place
+    pub(crate) fn recode(&self, data: &mut [f64]) {
+        if self.sentinels.is_empty() {
+            return;
+        }
+        for x in data {
+            if self.is_sentinel(*x) {
+                *x = f64::NAN;
+            }
+        }
+    }
+}
+
+// is this value missing, either as NaN or as Petra's sentinel?
+fn is_null(v: f64) -> bool {
+    v.is_nan() || v == PETRA_NULL
+}
+
+impl GridData {
+    /// which values are missing (NaN, or [PETRA_NULL] if it wasn't recoded):
+    /// a (rows × columns) mask of the nodes of a rectangular grid, or a
+    /// (triangles × 3) mask of the *z* values of each triangle's vertices
+    pub fn null_mask(&self) -> Array2<bool> {
+        match self {
+            GridData::Rectangular(arr) => arr.mapv(is_null),
+            GridData::Triangular(arr) =>
+                arr.index_axis(Axis(2), 2).mapv(is_null),
+        }
+    }
+
+    /// the number of missing values (i.e. the number of `true`s in
+    /// [GridData::null_mask])
+    pub fn null_count(&self) -> usize {
+        self.z_values().filter(|&z| is_null(z)).count()
+    }
+
+    /// recode every [PETRA_NULL] (in any coordinate) to [f64::NAN], as
+    /// [Grid::read](crate::Grid::read) does by default
+    pub fn nulls_to_nan(&mut self) {
+        self.recode_nulls(&NullPolicy::default());
+    }
+
+    /// recode every sentinel value of a policy (in any coordinate) to
+    /// [f64::NAN]
+    pub fn recode_nulls(&mut self, policy: &NullPolicy) {
+        let recode = |v: f64| if policy.is_sentinel(v) { f64::NAN } else { v };
+        match self {
+            GridData::Rectangular(arr) => arr.mapv_inplace(recode),
+            GridData::Triangular(arr) => arr.mapv_inplace(recode),
+        }
+    }
+
+    /// which values are missing under a policy; see [GridData::null_mask]
+    pub fn null_mask_with(&self, policy: &NullPolicy) -> Array2<bool> {
+        let is_null = |v| policy.is_null(v);
+        match self {
+            GridData::Rectangular(arr) => arr.mapv(is_null),
+            GridData::Triangular(arr) =>
+                arr.index_axis(Axis(2), 2).mapv(is_null),
+        }
+    }
+
+    /// summary statistics of the grid's *z* values under a policy; see
+    /// [NullPolicy::stats]
+    pub fn stats_with(&self, policy: &NullPolicy) -> Stats {
+        policy.stats(self.z_values())
+    }
+}
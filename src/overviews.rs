@@ -0,0 +1,116 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! overviews (a pyramid of successively coarser copies) of grids, for fast
+//! display and tiling of large grids when zoomed out
+
+use ndarray::Array2;
+
+use crate::{Grid, GridData};
+
+// the weights of the (1, 2, 1) tent filter over a node and its neighbors
+const TENT: [(isize, f64); 3] = [(-1, 1.0), (0, 2.0), (1, 1.0)];
+
+impl Grid {
+    /// up to `levels` overviews of this grid, each with half the resolution
+    /// of the one before (so the *k*th, counting from 1, has 2<sup>*k*</sup>
+    /// times this grid's node spacing); triangular grids are rasterized
+    /// first (see [Grid::rasterize])
+    ///
+    /// each overview keeps every other node of the grid before it, smoothed
+    /// with its non-missing neighbors (by a 1-2-1 tent filter along each
+    /// axis) to avoid aliasing; nodes missing in the finer grid stay missing.
+    /// the lower-left node is unchanged, but a grid with an even number of
+    /// rows or columns loses its last one. fewer than `levels` overviews are
+    /// returned if the grid is coarsened to a single node first
+    pub fn build_overviews(&self, levels: usize) -> Vec<Grid> {
+        let raster;
+        let base = match self.data {
+            GridData::Rectangular(_) => self,
+            GridData::Triangular(_) => {
+                raster = self.rasterize();
+                &raster
+            },
+        };
+
+        let mut overviews: Vec<Grid> = Vec::with_capacity(levels);
+        while overviews.len() < levels {
+            let finer = overviews.last().unwrap_or(base);
+            if finer.rows <= 1 && finer.columns <= 1 {
+                break;
+            }
+            let coarser = finer.halve_resolution();
+            overviews.push(coarser);
+        }
+        overviews
+    }
+
+    // keep every other node, smoothed with its neighbors
+    fn halve_resolution(&self) -> Grid {
+        let GridData::Rectangular(arr) = &self.data else {
+            unreachable!("overviews of rectangular grids")
+        };
+        let (rows, cols) = arr.dim();
+        let (coarse_rows, coarse_cols) = (rows.div_ceil(2), cols.div_ceil(2));
+        let data = Array2::from_shape_fn((coarse_rows, coarse_cols),
+          |(cj, ci)| {
+            let (j, i) = (2 * cj, 2 * ci);
+            if arr[(j, i)].is_nan() {
+                return f64::NAN;
+            }
+            let mut sum = 0.0;
+            let mut weights = 0.0;
+            for (dj, wj) in TENT {
+                for (di, wi) in TENT {
+                    let (Some(jj), Some(ii)) = (j.checked_add_signed(dj),
+                      i.checked_add_signed(di)) else {
+                        continue;
+                    };
+                    match arr.get((jj, ii)) {
+                        Some(&z) if !z.is_nan() => {
+                            sum += wj * wi * z;
+                            weights += wj * wi;
+                        },
+                        _ => { },
+                    }
+                }
+            }
+            sum / weights
+          });
+
+        let mut header = self.header();
+        header.rows = coarse_rows as u32;
+        header.columns = coarse_cols as u32;
+        header.size = (coarse_rows * coarse_cols) as u32;
+        header.n_triangles = 0;
+        header.xstep = 2.0 * self.xstep;
+        header.ystep = 2.0 * self.ystep;
+        header.xmax = self.xmin
+          + coarse_cols.saturating_sub(1) as f64 * header.xstep;
+        header.ymax = self.ymin
+          + coarse_rows.saturating_sub(1) as f64 * header.ystep;
+        let data = GridData::Rectangular(data.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Grid::from_parts(header, data)
+    }
+}
@@ -0,0 +1,83 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! probing GRD headers for fields we don't yet understand
+//!
+//! Petra's own logs suggest grids record both a creation and a modification
+//! time, but we've only identified one timestamp field (see
+//! [crate::Grid::created_date], which may be either). until we've seen
+//! enough grids to tell, [find_header_dates] lists every plausible timestamp
+//! in a header, so that grids with known histories can be compared; please
+//! open an issue with your findings
+//!
+//! these are only candidates: any eight bytes which happen to decode to a
+//! date in range (an *x* coordinate of 40,000 feet, say) are listed too, so
+//! nothing found here is exposed on [crate::Grid]. once the field's offset
+//! is confirmed, we'll decode it there alone (and write it back with
+//! [crate::Grid::write])
+
+use time::{PrimitiveDateTime, macros::datetime};
+
+use crate::{DATE_OFFSET, GRID_OFFSET, petra_datetime};
+
+/// the length, in bytes, of a GRD file's header
+pub const HEADER_LEN: u64 = GRID_OFFSET;
+
+// the range of dates we consider plausible for a grid
+const EARLIEST_PLAUSIBLE: PrimitiveDateTime = datetime!(1985-01-01 00:00);
+const LATEST_PLAUSIBLE: PrimitiveDateTime = datetime!(2100-01-01 00:00);
+
+/// a value in a GRD header which looks like a Petra timestamp
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HeaderDate {
+    /// the offset of the value in the file
+    pub offset: u64,
+
+    /// the value, decoded as a timestamp
+    pub date: PrimitiveDateTime,
+
+    /// is this the field decoded as [crate::Grid::created_date]?
+    pub known: bool,
+}
+
+/// find every value in the header of a GRD file (given its leading bytes)
+/// which decodes as a plausible timestamp, at any byte offset
+pub fn find_header_dates(bytes: &[u8]) -> Vec<HeaderDate> {
+    let header = &bytes[..bytes.len().min(HEADER_LEN as usize)];
+    let earliest = days_since_origin(EARLIEST_PLAUSIBLE);
+    let latest = days_since_origin(LATEST_PLAUSIBLE);
+    header.windows(8).enumerate().filter_map(|(offset, w)| {
+        // safety: windows(8) always yields 8 bytes
+        let days = f64::from_le_bytes(w.try_into().unwrap());
+        if !(earliest..latest).contains(&days) {
+            return None;
+        }
+        Some(HeaderDate {
+            offset: offset as u64,
+            date: petra_datetime(days),
+            known: offset as u64 == DATE_OFFSET,
+        })
+    }).collect()
+}
+
+fn days_since_origin(dt: PrimitiveDateTime) -> f64 {
+    (dt - petra_datetime(0.0)).as_seconds_f64() / 86_400.0
+}
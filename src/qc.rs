@@ -0,0 +1,323 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! round-trip quality control for export formats
+//!
+//! [round_trip] exports a grid to some format, re-imports it, and reports how
+//! far the re-imported *z* values drift from the originals and which metadata
+//! didn't survive, so that users can check which formats preserve their data
+//! adequately
+//!
+//! what "adequately" means depends on the use: a [Tolerance] encodes how much
+//! drift is acceptable, with named profiles for common cases
+//! ([Tolerance::EXACT], [Tolerance::DISPLAY], and [Tolerance::VOLUMETRICS])
+
+use std::io::Cursor;
+
+use crate::{Error, Grid, GridData, export::Exporter, import};
+
+pub use crate::export::xyz::{Csv, Xyz};
+
+/// an export format which can be read back for round-trip checks
+pub trait Format: Exporter {
+    /// re-import a grid exported in this format
+    fn import(&self, bytes: &[u8]) -> Result<Grid, Error>;
+}
+
+impl Format for Xyz {
+    fn import(&self, bytes: &[u8]) -> Result<Grid, Error> {
+        import::xyz::read_xyz(&mut Cursor::new(bytes))
+    }
+}
+
+impl Format for Csv {
+    fn import(&self, bytes: &[u8]) -> Result<Grid, Error> {
+        import::xyz::read_xyz(&mut Cursor::new(bytes))
+    }
+}
+
+/// the results of a round-trip check
+#[derive(Clone, Debug, PartialEq)]
+pub struct QcReport {
+    /// the name of the format checked
+    pub format: String,
+
+    /// the size, in bytes, of the exported grid
+    pub exported_bytes: usize,
+
+    /// the number of nodes (or triangle vertices) with values in both the
+    /// original and the re-imported grid
+    pub nodes_compared: usize,
+
+    /// the number of nodes (or triangle vertices) missing in exactly one of
+    /// the original and the re-imported grid
+    pub null_mismatches: usize,
+
+    /// the maximum absolute difference in *z* between the original and the
+    /// re-imported grid
+    pub max_abs_drift: f64,
+
+    /// the mean absolute difference in *z*
+    pub mean_abs_drift: f64,
+
+    /// the root-mean-square difference in *z*
+    pub rms_drift: f64,
+
+    /// the range (maximum − minimum) of the original grid's *z* values
+    pub z_range: f64,
+
+    /// the larger of the original grid's *x* and *y* extents
+    pub xy_extent: f64,
+
+    /// the largest change in the lattice's origin, extent, or node spacing,
+    /// along *x* or *y*
+    pub max_xy_drift: f64,
+
+    /// the names of the header fields whose values were not preserved
+    pub lost_metadata: Vec<&'static str>,
+}
+
+impl QcReport {
+    /// were the data and metadata preserved exactly?
+    pub fn is_lossless(&self) -> bool {
+        self.null_mismatches == 0
+          && self.max_abs_drift == 0.0
+          && self.lost_metadata.is_empty()
+    }
+
+    /// was the grid preserved to within a tolerance?
+    pub fn is_within(&self, tolerance: &Tolerance) -> bool {
+        let compared = self.nodes_compared + self.null_mismatches;
+        let null_fraction = if compared == 0 {
+            0.0
+        } else {
+            self.null_mismatches as f64 / compared as f64
+        };
+        self.max_abs_drift <= tolerance.z_relative * self.z_range
+          && self.max_xy_drift <= tolerance.xy_relative * self.xy_extent
+          && null_fraction <= tolerance.null_fraction
+          && !self.lost_metadata.iter()
+            .any(|field| tolerance.metadata.contains(field))
+    }
+}
+
+/// how much numeric drift between a grid and a copy of it (e.g. after a
+/// round trip through some format) is acceptable for some downstream use;
+/// see [QcReport::is_within]
+///
+/// drift is measured relative to the original grid, so that the same
+/// tolerance applies regardless of units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tolerance {
+    /// the name of the tolerance, as accepted by [Tolerance::named]
+    pub name: &'static str,
+
+    /// the largest acceptable difference in *z*, as a fraction of the
+    /// original grid's *z* range
+    pub z_relative: f64,
+
+    /// the largest acceptable change in the lattice's origin, extent, or
+    /// node spacing, as a fraction of the original grid's larger extent
+    pub xy_relative: f64,
+
+    /// the largest acceptable fraction of nodes missing in exactly one of
+    /// the grids
+    pub null_fraction: f64,
+
+    /// the header fields (as named in [QcReport::lost_metadata]) which must
+    /// be preserved exactly
+    pub metadata: &'static [&'static str],
+}
+
+// every header field compared by round-trip checks
+const ALL_METADATA: &[&str] = &["version", "name", "data", "rows", "columns",
+  "xmin", "xmax", "ymin", "ymax", "xstep", "ystep", "xyunits", "zunits",
+  "created_date", "source_data", "projection", "datum", "grid_method",
+  "projection_code", "cm", "rlat", "vertical_datum"];
+
+impl Tolerance {
+    /// no drift at all, in data or metadata: for archival copies
+    pub const EXACT: Tolerance = Tolerance {
+        name: "exact",
+        z_relative: 0.0,
+        xy_relative: 0.0,
+        null_fraction: 0.0,
+        metadata: ALL_METADATA,
+    };
+
+    /// drift too small to see: *z* within half a step of an 8-bit color
+    /// ramp, the lattice within a pixel of a 1000-pixel render, and a
+    /// handful of nodes gained or lost; metadata doesn't matter
+    pub const DISPLAY: Tolerance = Tolerance {
+        name: "display",
+        z_relative: 1.0 / 512.0,
+        xy_relative: 1e-3,
+        null_fraction: 1e-3,
+        metadata: &[],
+    };
+
+    /// drift too small to move a volume: *z* within 10 ppm of its range,
+    /// the lattice essentially unchanged, no nodes gained or lost, and the
+    /// units preserved
+    pub const VOLUMETRICS: Tolerance = Tolerance {
+        name: "volumetrics",
+        z_relative: 1e-5,
+        xy_relative: 1e-7,
+        null_fraction: 0.0,
+        metadata: &["xyunits", "zunits"],
+    };
+
+    /// the named tolerance profiles
+    pub const PROFILES: &'static [Tolerance] = &[
+        Tolerance::EXACT,
+        Tolerance::DISPLAY,
+        Tolerance::VOLUMETRICS,
+    ];
+
+    /// look up a named tolerance profile (ignoring case); see
+    /// [Tolerance::PROFILES]
+    pub fn named(name: &str) -> Option<Tolerance> {
+        Tolerance::PROFILES.iter()
+          .find(|t| t.name.eq_ignore_ascii_case(name))
+          .copied()
+    }
+
+    /// the largest acceptable difference in *z* from a grid, in its *z*
+    /// units
+    pub fn z_tolerance(&self, grid: &Grid) -> f64 {
+        let stats = grid.data.stats();
+        self.z_relative * finite(stats.max - stats.min)
+    }
+
+    /// the largest acceptable change in a grid's lattice, in its *x*/*y*
+    /// units (e.g. for [Grid::is_coregistered])
+    pub fn xy_tolerance(&self, grid: &Grid) -> f64 {
+        self.xy_relative * xy_extent(grid)
+    }
+}
+
+/// export a grid in the given format, re-import it, and compare the result
+/// to the original
+///
+/// *z* values are compared at every node of a rectangular grid or every
+/// vertex of a triangular grid, by sampling the re-imported grid at the
+/// same location (see [Grid::sample])
+pub fn round_trip(grid: &Grid, format: &dyn Format) -> Result<QcReport, Error> {
+    let mut bytes = Vec::new();
+    format.export(grid, &mut bytes)?;
+    let reimported = format.import(&bytes)?;
+
+    let mut nodes_compared = 0;
+    let mut null_mismatches = 0;
+    let mut max_abs_drift: f64 = 0.0;
+    let mut sum_abs = 0.0;
+    let mut sum_sq = 0.0;
+    for (x, y, z) in grid.points() {
+        let z2 = reimported.sample(x, y).unwrap_or(f64::NAN);
+        match (z.is_nan(), z2.is_nan()) {
+            (true, true) => { },
+            (false, false) => {
+                let d = (z - z2).abs();
+                nodes_compared += 1;
+                max_abs_drift = max_abs_drift.max(d);
+                sum_abs += d;
+                sum_sq += d * d;
+            },
+            _ => null_mismatches += 1,
+        }
+    }
+
+    let (mean_abs_drift, rms_drift) = if nodes_compared == 0 {
+        (f64::NAN, f64::NAN)
+    } else {
+        let n = nodes_compared as f64;
+        (sum_abs / n, (sum_sq / n).sqrt())
+    };
+
+    let stats = grid.data.stats();
+    let geometry = grid.compare_geometry(&reimported);
+    let max_xy_drift = [geometry.origin_offset, geometry.step_mismatch,
+      geometry.extent_mismatch].iter()
+      .flat_map(|&(dx, dy)| [dx.abs(), dy.abs()])
+      .fold(0.0, f64::max);
+
+    Ok(QcReport {
+        format: format.name().to_string(),
+        exported_bytes: bytes.len(),
+        nodes_compared,
+        null_mismatches,
+        max_abs_drift,
+        mean_abs_drift,
+        rms_drift,
+        z_range: finite(stats.max - stats.min),
+        xy_extent: xy_extent(grid),
+        max_xy_drift,
+        lost_metadata: lost_metadata(grid, &reimported),
+    })
+}
+
+// the larger of a grid's x and y extents
+fn xy_extent(grid: &Grid) -> f64 {
+    (grid.xmax - grid.xmin).abs().max((grid.ymax - grid.ymin).abs())
+}
+
+// NaN (e.g. the range of an all-missing grid) as zero
+fn finite(v: f64) -> f64 {
+    if v.is_nan() {
+        0.0
+    } else {
+        v
+    }
+}
+
+fn lost_metadata(orig: &Grid, new: &Grid) -> Vec<&'static str> {
+    let mut lost = Vec::new();
+    let mut check = |name, same: bool| {
+        if !same {
+            lost.push(name);
+        }
+    };
+    check("version", orig.version == new.version);
+    check("name", orig.name == new.name);
+    check("data", matches!((&orig.data, &new.data),
+      (GridData::Rectangular(_), GridData::Rectangular(_))
+        | (GridData::Triangular(_), GridData::Triangular(_))));
+    check("rows", orig.rows == new.rows);
+    check("columns", orig.columns == new.columns);
+    check("xmin", orig.xmin == new.xmin);
+    check("xmax", orig.xmax == new.xmax);
+    check("ymin", orig.ymin == new.ymin);
+    check("ymax", orig.ymax == new.ymax);
+    check("xstep", orig.xstep == new.xstep);
+    check("ystep", orig.ystep == new.ystep);
+    check("xyunits", orig.xyunits == new.xyunits);
+    check("zunits", orig.zunits == new.zunits);
+    check("created_date", orig.created_date == new.created_date);
+    check("source_data", orig.source_data == new.source_data);
+    check("projection", orig.projection == new.projection);
+    check("datum", orig.datum == new.datum);
+    check("grid_method", orig.grid_method == new.grid_method);
+    check("projection_code", orig.projection_code == new.projection_code);
+    check("cm", orig.cm == new.cm);
+    check("rlat", orig.rlat == new.rlat);
+    check("vertical_datum", orig.vertical_datum == new.vertical_datum);
+    lost
+}
@@ -0,0 +1,236 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! descriptions of what grids' *z* values measure
+//!
+//! ```
+//! use petra_grid::{NullPolicy, Quantity, testing::{Lattice, Surface}};
+//!
+//! let lattice = Lattice::default();
+//! let flat = |z0| Surface::Plane { z0, dzdx: 0.0, dzdy: 0.0 }
+//!   .rectangular(&lattice);
+//! let (top, base) = (flat(120.0), flat(100.0));
+//! let thickness = top.subtract(&base, &NullPolicy::new())?
+//!   .with_quantity(Quantity::new("thickness", "m"));
+//! let porosity = flat(0.2)
+//!   .with_quantity(Quantity::dimensionless("porosity"));
+//! let pore_thickness = thickness.multiply(&porosity, &NullPolicy::new())?;
+//! assert_eq!(pore_thickness.z_quantity().unwrap().to_string(),
+//!   "thickness * porosity in m");
+//! # Ok::<(), petra_grid::Error>(())
+//! ```
+
+use std::fmt;
+
+use crate::{Error, Grid, UnitOfMeasure};
+
+/// what a grid's *z* values measure: a name (e.g. "thickness") and units
+/// (e.g. "m", or "US_survey_foot"), both free text
+///
+/// units are best written as UDUNITS expressions, since they're copied into
+/// netCDF `units` attributes; those of quantities derived by grid algebra
+/// (see [Grid::add] and friends) are built up that way, e.g. "ft.ft" for a
+/// product or "ft/m" for a quotient. dimensionless quantities have units
+/// "1"
+///
+/// GRD files have nowhere to record a quantity, so grids read from them
+/// have none, and [Grid::z_quantity] falls back on their *z* units
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quantity {
+    /// what's measured
+    pub name: String,
+
+    /// the units it's measured in
+    pub units: String,
+}
+
+impl Quantity {
+    /// a quantity with the given name and units
+    pub fn new<N: Into<String>, U: Into<String>>(name: N, units: U)
+      -> Quantity {
+        Quantity { name: name.into(), units: units.into() }
+    }
+
+    /// a dimensionless quantity (e.g. a ratio)
+    pub fn dimensionless<N: Into<String>>(name: N) -> Quantity {
+        Quantity::new(name, DIMENSIONLESS)
+    }
+
+    /// the quantity measured by a grid's *z* units alone (named "z", in
+    /// the units' UDUNITS name; see [UnitOfMeasure::udunits]), if they're
+    /// known
+    pub fn from_units(units: UnitOfMeasure) -> Option<Quantity> {
+        units.udunits().map(|units| Quantity::new("z", units))
+    }
+
+    /// is this quantity dimensionless?
+    pub fn is_dimensionless(&self) -> bool {
+        self.units == DIMENSIONLESS
+    }
+
+    /// the quantity measured by the sum of this quantity and another, which
+    /// must be in the same units; quantities of the same name keep it
+    pub fn sum(&self, other: &Quantity) -> Result<Quantity, Error> {
+        self.check_units("add", other)?;
+        let name = if self.name == other.name {
+            self.name.clone()
+        } else {
+            format!("{} + {}", self.name, other.name)
+        };
+        Ok(Quantity::new(name, self.units.clone()))
+    }
+
+    /// the quantity measured by the difference of this quantity and another
+    /// (this minus the other), which must be in the same units
+    pub fn difference(&self, other: &Quantity) -> Result<Quantity, Error> {
+        self.check_units("subtract", other)?;
+        let name = if self.name == other.name {
+            format!("{} difference", self.name)
+        } else {
+            format!("{} - {}", self.name, operand(&other.name))
+        };
+        Ok(Quantity::new(name, self.units.clone()))
+    }
+
+    /// the quantity measured by the product of this quantity and another
+    pub fn product(&self, other: &Quantity) -> Quantity {
+        let units = if self.is_dimensionless() {
+            other.units.clone()
+        } else if other.is_dimensionless() {
+            self.units.clone()
+        } else if self.units == other.units && is_bare(&self.units) {
+            format!("{}2", self.units)
+        } else {
+            format!("{}.{}", unit_factor(&self.units),
+              unit_factor(&other.units))
+        };
+        Quantity::new(format!("{} * {}", operand(&self.name),
+          operand(&other.name)), units)
+    }
+
+    /// the quantity measured by the quotient of this quantity and another
+    /// (this divided by the other)
+    pub fn quotient(&self, other: &Quantity) -> Quantity {
+        let units = if self.units == other.units {
+            DIMENSIONLESS.to_string()
+        } else if other.is_dimensionless() {
+            self.units.clone()
+        } else if self.is_dimensionless() {
+            format!("1/{}", unit_divisor(&other.units))
+        } else {
+            format!("{}/{}", unit_factor(&self.units),
+              unit_divisor(&other.units))
+        };
+        Quantity::new(format!("{} / {}", operand(&self.name),
+          operand(&other.name)), units)
+    }
+
+    /// the quantity measured by this one multiplied by a constant: the
+    /// same quantity, in units scaled by that constant (which can be
+    /// renamed with [Grid::with_quantity], e.g. after converting feet to
+    /// meters)
+    pub fn scaled(&self, factor: f64) -> Quantity {
+        if factor == 1.0 {
+            return self.clone();
+        }
+        let units = if self.is_dimensionless() {
+            format!("{}", factor)
+        } else {
+            format!("{} {}", factor, unit_factor(&self.units))
+        };
+        Quantity::new(self.name.clone(), units)
+    }
+
+    fn check_units(&self, verb: &str, other: &Quantity)
+      -> Result<(), Error> {
+        if self.units == other.units {
+            Ok(())
+        } else {
+            Err(Error::InvalidArgument(format!("can't {} {} and {}", verb, self,
+              other)))
+        }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dimensionless() {
+            write!(f, "{} (dimensionless)", self.name)
+        } else {
+            write!(f, "{} in {}", self.name, self.units)
+        }
+    }
+}
+
+impl Grid {
+    /// the quantity this grid's *z* values measure: the one it's been given
+    /// (see [Grid::quantity]), or else that of its *z* units, if known
+    pub fn z_quantity(&self) -> Option<Quantity> {
+        self.quantity.clone().or_else(|| Quantity::from_units(self.zunits))
+    }
+
+    /// this grid, describing its *z* values as measuring `quantity`
+    pub fn with_quantity(mut self, quantity: Quantity) -> Grid {
+        self.quantity = Some(quantity);
+        self
+    }
+}
+
+const DIMENSIONLESS: &str = "1";
+
+// a bare unit name, like "m" or "US_survey_foot", which can be squared by
+// appending a 2 (unlike "m2", which would become "m22")
+fn is_bare(units: &str) -> bool {
+    units.chars().all(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+// a bare unit name or power, like "ft" or "m2"
+fn is_simple(units: &str) -> bool {
+    units.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// units as one factor of a product (or the dividend of a quotient)
+fn unit_factor(units: &str) -> String {
+    if units.contains(['/', ' ']) {
+        format!("({})", units)
+    } else {
+        units.to_string()
+    }
+}
+
+// units as the divisor of a quotient
+fn unit_divisor(units: &str) -> String {
+    if is_simple(units) {
+        units.to_string()
+    } else {
+        format!("({})", units)
+    }
+}
+
+// a quantity name as an operand in the name of a derived quantity
+fn operand(name: &str) -> String {
+    if [" + ", " - ", " * ", " / "].iter().any(|op| name.contains(op)) {
+        format!("({})", name)
+    } else {
+        name.to_string()
+    }
+}
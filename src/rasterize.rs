@@ -0,0 +1,292 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! rasterization of triangular grids onto a rectangular lattice
+
+use ndarray::{ArcArray, Array2, ArrayView2, Ix3};
+
+use crate::{Grid, GridData, sample::triangle_z};
+
+/// how [Grid::rasterize_with] assigns a value to each node of the lattice,
+/// treating each node as the center of a cell one step wide and one step
+/// high
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RasterSampling {
+    /// the *z* of the nearest vertex of the triangle covering the node: the
+    /// fastest option, and it never invents values, but it's blocky
+    Vertex,
+
+    /// the surface interpolated at the node (i.e. the cell's center), as
+    /// [Grid::rasterize] does; fast and good for display, but small or thin
+    /// triangles can fall between nodes and be missed
+    #[default]
+    Centroid,
+
+    /// the mean of the surface over the part of the cell covered by
+    /// triangles, so that every triangle contributes in proportion to its
+    /// area; the best choice for volumetrics, though slower, and cells only
+    /// slightly covered at the edges of the surface still get values
+    AreaWeighted,
+
+    /// as [RasterSampling::AreaWeighted], but leaving cells less than half
+    /// covered missing, so that the edges of the surface follow the triangles
+    /// as closely as the lattice allows; pair with [Grid::raster_coverage]
+    /// for anti-aliased display
+    Coverage,
+}
+
+impl Grid {
+    /// produce a rectangular grid from this grid
+    ///
+    /// triangular grids are linearly interpolated onto the lattice described
+    /// by `xmin`, `ymin`, `xstep`, `ystep`, `rows`, and `columns` (which, we
+    /// think, describe the grid Petra triangulated from); nodes not covered
+    /// by any triangle are missing (NaN)
+    ///
+    /// rectangular grids are simply cloned
+    pub fn rasterize(&self) -> Grid {
+        self.rasterize_with(RasterSampling::Centroid)
+    }
+
+    /// produce a rectangular grid from this grid, as [Grid::rasterize] does,
+    /// but sampling triangular grids as specified
+    pub fn rasterize_with(&self, sampling: RasterSampling) -> Grid {
+        let tris = match &self.data {
+            GridData::Rectangular(_) => return self.clone(),
+            GridData::Triangular(tris) => tris,
+        };
+
+        let arr = match sampling {
+            RasterSampling::Vertex | RasterSampling::Centroid =>
+                self.point_sample(tris, sampling == RasterSampling::Vertex),
+            RasterSampling::AreaWeighted | RasterSampling::Coverage => {
+                let min_coverage = if sampling == RasterSampling::Coverage {
+                    0.5
+                } else {
+                    f64::MIN_POSITIVE
+                };
+                let (z_sum, area) = self.cell_areas(tris);
+                let cell_area = (self.xstep * self.ystep).abs();
+                let mut arr = z_sum / &area;
+                arr.zip_mut_with(&area, |z, &a| {
+                    if a / cell_area < min_coverage {
+                        *z = f64::NAN;
+                    }
+                });
+                arr
+            },
+        };
+
+        let mut header = self.header();
+        header.n_triangles = 0;
+        Grid::from_parts(header, GridData::Rectangular(arr.into_shared()))
+    }
+
+    /// the fraction (from 0 to 1) of each lattice cell (see
+    /// [RasterSampling]) covered by the triangles of a triangular grid, or
+    /// of each node which isn't missing in a rectangular grid; e.g. for use
+    /// as the alpha channel when displaying a rasterized grid
+    pub fn raster_coverage(&self) -> Array2<f64> {
+        match &self.data {
+            GridData::Rectangular(arr) =>
+                arr.mapv(|z| if z.is_nan() { 0.0 } else { 1.0 }),
+            GridData::Triangular(tris) => {
+                let cell_area = (self.xstep * self.ystep).abs();
+                let (_, area) = self.cell_areas(tris);
+                area.mapv(|a| (a / cell_area).min(1.0))
+            },
+        }
+    }
+
+    fn point_sample(&self, tris: &ArcArray<f64, Ix3>, nearest_vertex: bool)
+      -> Array2<f64> {
+        let (rows, cols) = (self.rows as usize, self.columns as usize);
+        let mut arr = Array2::from_elem((rows, cols), f64::NAN);
+
+        for tri in tris.outer_iter() {
+            let xs = tri.column(0);
+            let ys = tri.column(1);
+            let (x_lo, x_hi) = min_max(xs.iter());
+            let (y_lo, y_hi) = min_max(ys.iter());
+            let Some((i_lo, i_hi)) = index_range(
+              x_lo, x_hi, self.xmin, self.xstep, cols) else { continue };
+            let Some((j_lo, j_hi)) = index_range(
+              y_lo, y_hi, self.ymin, self.ystep, rows) else { continue };
+
+            for j in j_lo..=j_hi {
+                for i in i_lo..=i_hi {
+                    let (x, y) = self.node_xy(j, i);
+                    if let Some(z) = triangle_z(tri, x, y) {
+                        arr[(j, i)] = if nearest_vertex {
+                            nearest_vertex_z(tri, x, y)
+                        } else {
+                            z
+                        };
+                    }
+                }
+            }
+        }
+
+        arr
+    }
+
+    /* the integral of z over the part of each cell covered by triangles,
+     * and the area covered, found by clipping each triangle to each cell it
+     * overlaps */
+    fn cell_areas(&self, tris: &ArcArray<f64, Ix3>) -> (Array2<f64>, Array2<f64>) {
+        let (rows, cols) = (self.rows as usize, self.columns as usize);
+        let mut z_sum = Array2::zeros((rows, cols));
+        let mut area = Array2::zeros((rows, cols));
+        let (half_x, half_y) = (self.xstep.abs() / 2.0, self.ystep.abs() / 2.0);
+
+        for tri in tris.outer_iter() {
+            if tri.iter().any(|v| v.is_nan()) {
+                continue;
+            }
+            let (x_lo, x_hi) = min_max(tri.column(0).iter());
+            let (y_lo, y_hi) = min_max(tri.column(1).iter());
+            let Some((i_lo, i_hi)) = index_range(x_lo - half_x, x_hi + half_x,
+              self.xmin, self.xstep, cols) else { continue };
+            let Some((j_lo, j_hi)) = index_range(y_lo - half_y, y_hi + half_y,
+              self.ymin, self.ystep, rows) else { continue };
+
+            let polygon = [0, 1, 2].map(|v| (tri[(v, 0)], tri[(v, 1)]));
+            for j in j_lo..=j_hi {
+                for i in i_lo..=i_hi {
+                    let (x, y) = self.node_xy(j, i);
+                    let clipped = clip_to_box(&polygon,
+                      (x - half_x, x + half_x), (y - half_y, y + half_y));
+                    let Some((a, (cx, cy))) = area_centroid(&clipped)
+                      else { continue };
+                    if let Some(z) = plane_z(tri, cx, cy) {
+                        z_sum[(j, i)] += a * z;
+                        area[(j, i)] += a;
+                    }
+                }
+            }
+        }
+
+        (z_sum, area)
+    }
+}
+
+fn nearest_vertex_z(tri: ArrayView2<f64>, x: f64, y: f64) -> f64 {
+    let dist = |v: usize| (tri[(v, 0)] - x).hypot(tri[(v, 1)] - y);
+    let nearest = (0..3)
+      .min_by(|&v, &w| dist(v).total_cmp(&dist(w)))
+      .unwrap_or(0);
+    tri[(nearest, 2)]
+}
+
+/* z on the plane through a triangle's vertices, even outside the triangle
+ * (unlike triangle_z, so that points on its edges are never rejected by
+ * rounding error) */
+fn plane_z(tri: ArrayView2<f64>, x: f64, y: f64) -> Option<f64> {
+    let (x1, y1, z1) = (tri[(0, 0)], tri[(0, 1)], tri[(0, 2)]);
+    let (x2, y2, z2) = (tri[(1, 0)], tri[(1, 1)], tri[(1, 2)]);
+    let (x3, y3, z3) = (tri[(2, 0)], tri[(2, 1)], tri[(2, 2)]);
+
+    let det = (y2 - y3) * (x1 - x3) + (x3 - x2) * (y1 - y3);
+    if det == 0.0 {
+        return None;
+    }
+    let l1 = ((y2 - y3) * (x - x3) + (x3 - x2) * (y - y3)) / det;
+    let l2 = ((y3 - y1) * (x - x3) + (x1 - x3) * (y - y3)) / det;
+    Some(l1 * z1 + l2 * z2 + (1.0 - l1 - l2) * z3)
+}
+
+// Sutherland-Hodgman clipping of a convex polygon to an axis-aligned box
+fn clip_to_box(polygon: &[(f64, f64)], (x_lo, x_hi): (f64, f64),
+  (y_lo, y_hi): (f64, f64)) -> Vec<(f64, f64)> {
+    let mut poly = polygon.to_vec();
+    // (clip along x?, bound, which side is inside)
+    let edges = [
+        (true, x_lo, 1.0),
+        (true, x_hi, -1.0),
+        (false, y_lo, 1.0),
+        (false, y_hi, -1.0),
+    ];
+    for (along_x, bound, sign) in edges {
+        // how far inside this edge of the box a point is
+        let inside = |p: (f64, f64)|
+          sign * (if along_x { p.0 } else { p.1 } - bound);
+        let mut out = Vec::with_capacity(poly.len() + 1);
+        for (k, &p) in poly.iter().enumerate() {
+            let q = poly[(k + 1) % poly.len()];
+            let (dp, dq) = (inside(p), inside(q));
+            if dp >= 0.0 {
+                out.push(p);
+            }
+            if (dp >= 0.0) != (dq >= 0.0) {
+                let t = dp / (dp - dq);
+                out.push((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)));
+            }
+        }
+        poly = out;
+        if poly.is_empty() {
+            break;
+        }
+    }
+    poly
+}
+
+// the area and centroid of a simple polygon, if it has any area
+fn area_centroid(poly: &[(f64, f64)]) -> Option<(f64, (f64, f64))> {
+    if poly.len() < 3 {
+        return None;
+    }
+    // relative to the first vertex, for precision far from the origin
+    let (ox, oy) = poly[0];
+    let (mut a2, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for k in 0..poly.len() {
+        let (x0, y0) = (poly[k].0 - ox, poly[k].1 - oy);
+        let next = poly[(k + 1) % poly.len()];
+        let (x1, y1) = (next.0 - ox, next.1 - oy);
+        let cross = x0 * y1 - x1 * y0;
+        a2 += cross;
+        cx += (x0 + x1) * cross;
+        cy += (y0 + y1) * cross;
+    }
+    if a2 == 0.0 {
+        return None;
+    }
+    Some(((a2 / 2.0).abs(), (ox + cx / (3.0 * a2), oy + cy / (3.0 * a2))))
+}
+
+fn min_max<'a>(vals: impl Iterator<Item = &'a f64>) -> (f64, f64) {
+    vals.fold((f64::INFINITY, f64::NEG_INFINITY),
+      |(lo, hi), &v| (lo.min(v), hi.max(v)))
+}
+
+// the (inclusive) range of lattice indices covering [lo, hi], if any
+fn index_range(lo: f64, hi: f64, origin: f64, step: f64, n: usize)
+  -> Option<(usize, usize)> {
+    if n == 0 || !lo.is_finite() || !hi.is_finite() {
+        return None;
+    }
+    let first = ((lo - origin) / step).ceil().max(0.0);
+    let last = ((hi - origin) / step).floor().min((n - 1) as f64);
+    if first > last {
+        None
+    } else {
+        Some((first as usize, last as usize))
+    }
+}
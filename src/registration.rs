@@ -0,0 +1,110 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! comparison of grid geometry, e.g. to check that two grids share a lattice
+//! before combining them node by node
+
+use std::fmt;
+
+use crate::{Grid, GridData};
+
+/// how one grid's lattice differs from another's; see
+/// [Grid::compare_geometry]
+///
+/// differences are `other` − `self`, in *x*/*y* units (or nodes, for the
+/// shape)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GeometryComparison {
+    /// are both grids rectangular?
+    pub both_rectangular: bool,
+
+    /// do the grids use the same *x*/*y* units?
+    pub same_units: bool,
+
+    /// the offset of the lower-left node, along *x* and *y*
+    pub origin_offset: (f64, f64),
+
+    /// the difference in node spacing, along *x* and *y*
+    pub step_mismatch: (f64, f64),
+
+    /// the offset of the upper-right node, along *x* and *y*
+    pub extent_mismatch: (f64, f64),
+
+    /// the difference in the number of rows and columns
+    pub shape_mismatch: (i64, i64),
+}
+
+impl GeometryComparison {
+    /// do the lattices match, with every offset and mismatch no larger than
+    /// `tolerance` (in *x*/*y* units)?
+    pub fn is_coregistered(&self, tolerance: f64) -> bool {
+        let within = |(dx, dy): (f64, f64)|
+          dx.abs() <= tolerance && dy.abs() <= tolerance;
+        self.both_rectangular
+          && self.same_units
+          && self.shape_mismatch == (0, 0)
+          && within(self.origin_offset)
+          && within(self.step_mismatch)
+          && within(self.extent_mismatch)
+    }
+}
+
+impl fmt::Display for GeometryComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.both_rectangular {
+            write!(f, "not both rectangular; ")?;
+        }
+        if !self.same_units {
+            write!(f, "different x/y units; ")?;
+        }
+        write!(f,
+          "origin offset ({}, {}), step mismatch ({}, {}), \
+           extent mismatch ({}, {}), shape mismatch ({} rows, {} columns)",
+          self.origin_offset.0, self.origin_offset.1,
+          self.step_mismatch.0, self.step_mismatch.1,
+          self.extent_mismatch.0, self.extent_mismatch.1,
+          self.shape_mismatch.0, self.shape_mismatch.1)
+    }
+}
+
+impl Grid {
+    /// compare this grid's geometry (lattice origin, spacing, extent, and
+    /// shape) with another's
+    pub fn compare_geometry(&self, other: &Grid) -> GeometryComparison {
+        GeometryComparison {
+            both_rectangular: matches!(self.data, GridData::Rectangular(_))
+              && matches!(other.data, GridData::Rectangular(_)),
+            same_units: self.xyunits == other.xyunits,
+            origin_offset: (other.xmin - self.xmin, other.ymin - self.ymin),
+            step_mismatch: (other.xstep - self.xstep, other.ystep - self.ystep),
+            extent_mismatch: (other.xmax - self.xmax, other.ymax - self.ymax),
+            shape_mismatch: (other.rows as i64 - self.rows as i64,
+              other.columns as i64 - self.columns as i64),
+        }
+    }
+
+    /// do this grid and another share the same lattice (to within
+    /// `tolerance`, in *x*/*y* units), so that they can be combined node by
+    /// node? triangular grids are never coregistered
+    pub fn is_coregistered(&self, other: &Grid, tolerance: f64) -> bool {
+        self.compare_geometry(other).is_coregistered(tolerance)
+    }
+}
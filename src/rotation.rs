@@ -0,0 +1,142 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! grids on lattices rotated away from north, e.g. to line up with a
+//! development area's orientation
+//!
+//! Petra grids are always aligned with the map axes, so a rotated grid is
+//! represented as an ordinary [Grid] on the unrotated lattice, along with
+//! the rotation (about the lower-left node) which places it on the map
+
+use ndarray::Array2;
+
+use crate::{Grid, GridData, gridding::Lattice};
+
+/// a regular lattice of nodes rotated about its lower-left node
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RotatedLattice {
+    /// the lattice before rotation: `xmin` and `ymin` locate the lower-left
+    /// node (on the map), and the steps are along the rotated axes
+    pub lattice: Lattice,
+
+    /// the compass direction, in degrees clockwise from north, of the
+    /// lattice's columns (i.e. of increasing rows); 0 for no rotation
+    pub azimuth: f64,
+}
+
+impl RotatedLattice {
+    /// the map *x* and *y* coordinates of the node at (row, column)
+    pub fn node_xy(&self, row: usize, column: usize) -> (f64, f64) {
+        let u = column as f64 * self.lattice.xstep;
+        let v = row as f64 * self.lattice.ystep;
+        let (sin, cos) = self.azimuth.to_radians().sin_cos();
+        (self.lattice.xmin + u * cos + v * sin,
+         self.lattice.ymin - u * sin + v * cos)
+    }
+}
+
+/// a grid on a rotated lattice; see [Grid::resample_rotated]
+#[derive(Clone, Debug)]
+pub struct RotatedGrid {
+    /// the grid on the unrotated lattice (see [RotatedLattice::lattice])
+    pub grid: Grid,
+
+    /// the rotation about the lower-left node, in degrees clockwise
+    pub azimuth: f64,
+}
+
+impl RotatedGrid {
+    /// the rotated lattice of the grid's nodes
+    pub fn lattice(&self) -> RotatedLattice {
+        RotatedLattice {
+            lattice: Lattice {
+                xmin: self.grid.xmin,
+                ymin: self.grid.ymin,
+                xstep: self.grid.xstep,
+                ystep: self.grid.ystep,
+                rows: self.grid.rows as usize,
+                columns: self.grid.columns as usize,
+            },
+            azimuth: self.azimuth,
+        }
+    }
+
+    /// every (*x*, *y*, *z*) node of the grid, in map coordinates,
+    /// including missing values (south to north, then west to east, in the
+    /// rotated frame); none if the grid is triangular
+    pub fn points(&self) -> impl Iterator<Item = (f64, f64, f64)> + '_ {
+        let lattice = self.lattice();
+        let arr = match &self.grid.data {
+            GridData::Rectangular(arr) => Some(arr),
+            GridData::Triangular(_) => None,
+        };
+        arr.into_iter().flat_map(move |arr| {
+            arr.indexed_iter().map(move |((j, i), &z)| {
+                let (x, y) = lattice.node_xy(j, i);
+                (x, y, z)
+            })
+        })
+    }
+}
+
+impl Grid {
+    /// resample this grid onto a rotated lattice (by bilinear interpolation,
+    /// after rasterizing triangular grids; see [Grid::sample]), keeping its
+    /// descriptive metadata; nodes outside the grid are missing
+    pub fn resample_rotated(&self, lattice: &RotatedLattice) -> RotatedGrid {
+        let raster;
+        let source = match self.data {
+            GridData::Rectangular(_) => self,
+            GridData::Triangular(_) => {
+                raster = self.rasterize();
+                &raster
+            },
+        };
+
+        let RotatedLattice { lattice: l, .. } = lattice;
+        let arr = Array2::from_shape_fn((l.rows, l.columns),
+          |(j, i)| {
+              let (x, y) = lattice.node_xy(j, i);
+              source.sample(x, y).unwrap_or(f64::NAN)
+          });
+
+        let mut header = self.header();
+        header.rows = l.rows as u32;
+        header.columns = l.columns as u32;
+        header.size = header.rows * header.columns;
+        header.n_triangles = 0;
+        header.xmin = l.xmin;
+        header.ymin = l.ymin;
+        header.xstep = l.xstep;
+        header.ystep = l.ystep;
+        header.xmax = l.xmin + l.columns.saturating_sub(1) as f64 * l.xstep;
+        header.ymax = l.ymin + l.rows.saturating_sub(1) as f64 * l.ystep;
+        let data = GridData::Rectangular(arr.into_shared());
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+
+        RotatedGrid {
+            grid: Grid::from_parts(header, data),
+            azimuth: lattice.azimuth,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! point sampling of grid surfaces
+
+use ndarray::{ArcArray2, ArrayView2};
+
+use crate::{Error, Grid, GridData};
+
+// a small slop for points lying exactly on triangle edges
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+impl Grid {
+    /// the *z* value of the surface at a point (*x*, *y*)
+    ///
+    /// rectangular grids are bilinearly interpolated between the four
+    /// surrounding nodes; triangular grids are linearly interpolated within
+    /// the containing triangle
+    ///
+    /// returns `None` if the point lies outside the grid; points in areas of
+    /// missing data yield `Some(NaN)`
+    pub fn sample(&self, x: f64, y: f64) -> Option<f64> {
+        match &self.data {
+            GridData::Rectangular(arr) => {
+                let (rows, cols) = arr.dim();
+                if rows == 0 || cols == 0 {
+                    return None;
+                }
+
+                let fi = (x - self.xmin) / self.xstep;
+                let fj = (y - self.ymin) / self.ystep;
+                if !(0.0..=(cols - 1) as f64).contains(&fi)
+                  || !(0.0..=(rows - 1) as f64).contains(&fj) {
+                    return None;
+                }
+
+                let i0 = (fi.floor() as usize).min(cols.saturating_sub(2));
+                let j0 = (fj.floor() as usize).min(rows.saturating_sub(2));
+                let i1 = (i0 + 1).min(cols - 1);
+                let j1 = (j0 + 1).min(rows - 1);
+                let (ti, tj) = (fi - i0 as f64, fj - j0 as f64);
+
+                let south = lerp(arr[(j0, i0)], arr[(j0, i1)], ti);
+                let north = lerp(arr[(j1, i0)], arr[(j1, i1)], ti);
+                Some(lerp(south, north, tj))
+            },
+
+            GridData::Triangular(arr) => arr.outer_iter()
+              .find_map(|tri| triangle_z(tri, x, y)),
+        }
+    }
+
+    /// sample the surface at each of a series of points; see [Grid::sample]
+    pub fn sample_points(&self, points: &[(f64, f64)]) -> Vec<Option<f64>> {
+        points.iter().map(|&(x, y)| self.sample(x, y)).collect()
+    }
+
+    /// the *x* and *y* coordinates of the node at (row, column) in a
+    /// rectangular grid's lattice
+    pub fn node_xy(&self, row: usize, column: usize) -> (f64, f64) {
+        (self.xmin + column as f64 * self.xstep,
+         self.ymin + row as f64 * self.ystep)
+    }
+
+    /// every (*x*, *y*, *z*) point in the grid, including missing values:
+    /// each node of a rectangular grid (south to north, then west to east),
+    /// or each vertex of each triangle of a triangular grid
+    pub fn points(&self) -> Box<dyn Iterator<Item = (f64, f64, f64)> + '_> {
+        match &self.data {
+            GridData::Rectangular(arr) => Box::new(
+              arr.indexed_iter().map(|((j, i), &z)| {
+                  let (x, y) = self.node_xy(j, i);
+                  (x, y, z)
+              })),
+            GridData::Triangular(arr) => Box::new(
+              arr.outer_iter().flat_map(|tri| {
+                  (0..3).map(move |v| (tri[(v, 0)], tri[(v, 1)], tri[(v, 2)]))
+              })),
+        }
+    }
+
+    /// the rectangular grid data, or [Error::NotRectangular]
+    pub fn rectangular(&self) -> Result<&ArcArray2<f64>, Error> {
+        match &self.data {
+            GridData::Rectangular(arr) => Ok(arr),
+            _ => Err(Error::NotRectangular),
+        }
+    }
+}
+
+/* linear interpolation which ignores an endpoint with zero weight, so that
+ * sampling exactly on a node or edge isn't spoiled by missing neighbors */
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    if t == 0.0 {
+        a
+    } else if t == 1.0 {
+        b
+    } else {
+        a * (1.0 - t) + b * t
+    }
+}
+
+/* linearly interpolate z at (x, y) within a triangle (3 vertices × xyz),
+ * yielding None if the point lies outside the triangle */
+pub(crate) fn triangle_z(tri: ArrayView2<f64>, x: f64, y: f64) -> Option<f64> {
+    let (x1, y1, z1) = (tri[(0, 0)], tri[(0, 1)], tri[(0, 2)]);
+    let (x2, y2, z2) = (tri[(1, 0)], tri[(1, 1)], tri[(1, 2)]);
+    let (x3, y3, z3) = (tri[(2, 0)], tri[(2, 1)], tri[(2, 2)]);
+
+    let det = (y2 - y3) * (x1 - x3) + (x3 - x2) * (y1 - y3);
+    if det == 0.0 || det.is_nan() {
+        return None;
+    }
+
+    let l1 = ((y2 - y3) * (x - x3) + (x3 - x2) * (y - y3)) / det;
+    let l2 = ((y3 - y1) * (x - x3) + (x1 - x3) * (y - y3)) / det;
+    let l3 = 1.0 - l1 - l2;
+    if l1 < -EDGE_TOLERANCE || l2 < -EDGE_TOLERANCE || l3 < -EDGE_TOLERANCE {
+        return None;
+    }
+
+    Some(l1 * z1 + l2 * z2 + l3 * z3)
+}
@@ -0,0 +1,129 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! fast metadata scans over directory trees of grids
+//!
+//! paths are handled as [Path]s throughout, never as strings, so file and
+//! directory names which aren't valid Unicode are scanned like any others
+//! (and only displayed lossily); Windows UNC paths (`\\server\share\...`)
+//! and paths longer than the traditional 260-character limit work as well,
+//! since the standard library converts long paths to their extended-length
+//! (`\\?\`) forms as needed. symbolic links and junctions to directories
+//! aren't followed, so cyclic links can't trap a scan
+
+use std::{
+    fs::{self, File},
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{Error, GridHeader, GRID_OFFSET, set::is_grd_path};
+
+/// read the metadata of every `.grd` file (ignoring case) in a directory
+/// tree, yielding each path along with its header or the error encountered
+///
+/// only the fixed-size header of each file is read, so this is much faster
+/// than reading the grids in full; with the `rayon` feature enabled, files
+/// are read in parallel
+///
+/// directories which can't be listed are yielded first, as errors (with the
+/// directory's path), rather than aborting the scan; headers follow, in order
+/// of path
+pub fn scan_headers<P: AsRef<Path>>(dir: P)
+  -> impl Iterator<Item = (PathBuf, Result<GridHeader, Error>)> {
+    let (paths, errors) = find_grd_files(dir);
+    let errors = errors.into_iter().map(|(p, e)| (p, Err(e)));
+
+    #[cfg(feature = "rayon")]
+    let headers = paths.into_par_iter()
+      .map(|p| {
+          let header = read_header_file(&p);
+          (p, header)
+      })
+      .collect::<Vec<_>>();
+
+    #[cfg(not(feature = "rayon"))]
+    let headers = paths.into_iter()
+      .map(|p| {
+          let header = read_header_file(&p);
+          (p, header)
+      })
+      .collect::<Vec<_>>();
+
+    errors.chain(headers)
+}
+
+/// list every `.grd` file (ignoring case) in a directory tree, in order of
+/// path, along with any errors encountered listing its directories
+pub fn find_grd_files<P: AsRef<Path>>(dir: P)
+  -> (Vec<PathBuf>, Vec<(PathBuf, Error)>) {
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    walk(dir.as_ref(), &mut paths, &mut errors);
+    paths.sort();
+    (paths, errors)
+}
+
+/// read the header of a single GRD file, reading only the header bytes
+pub fn read_header_file<P: AsRef<Path>>(path: P) -> Result<GridHeader, Error> {
+    let f = File::open(path)?;
+    // one big read is much faster than the many small seeks and reads
+    let mut buf = Vec::with_capacity(GRID_OFFSET as usize);
+    f.take(GRID_OFFSET).read_to_end(&mut buf)?;
+    GridHeader::read(&mut Cursor::new(buf))
+}
+
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>,
+  errors: &mut Vec<(PathBuf, Error)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push((dir.to_owned(), e.into()));
+            return;
+        },
+    };
+
+    let mut subdirs = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push((dir.to_owned(), e.into()));
+                continue;
+            },
+        };
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => subdirs.push(path),
+            Ok(_) if is_grd_path(&path) => paths.push(path),
+            Ok(_) => { },
+            Err(e) => errors.push((path, e.into())),
+        }
+    }
+
+    subdirs.sort();
+    for subdir in subdirs {
+        walk(&subdir, paths, errors);
+    }
+}
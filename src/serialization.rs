@@ -0,0 +1,214 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! `serde` support for types where it can't simply be derived
+//!
+//! grid data is serialized as its shape and a flat (row-major) list of
+//! values, with missing values as `None`, so that it survives formats like
+//! JSON which have no NaN
+//!
+//! errors are serialized as a mirror enum in which I/O errors are reduced
+//! to their message and the name of their [io::ErrorKind] (so that
+//! [Error::category] survives the round trip); deserialized I/O errors are
+//! rebuilt from these
+
+use std::io;
+
+use ndarray::{Array2, Array3};
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::Error as _,
+};
+
+use crate::{Error, GridData};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "GridData")]
+enum GridDataRepr {
+    Rectangular { dim: (usize, usize), data: Vec<Option<f64>> },
+    Triangular { dim: (usize, usize, usize), data: Vec<Option<f64>> },
+}
+
+fn nan_to_none(v: &f64) -> Option<f64> {
+    if v.is_nan() { None } else { Some(*v) }
+}
+
+impl Serialize for GridData {
+    fn serialize<S: Serializer>(&self, serializer: S)
+      -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            GridData::Rectangular(arr) => GridDataRepr::Rectangular {
+                dim: arr.dim(),
+                data: arr.iter().map(nan_to_none).collect(),
+            },
+            GridData::Triangular(arr) => GridDataRepr::Triangular {
+                dim: arr.dim(),
+                data: arr.iter().map(nan_to_none).collect(),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GridData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+      -> Result<Self, D::Error> {
+        let values = |data: Vec<Option<f64>>| data.into_iter()
+          .map(|v| v.unwrap_or(f64::NAN))
+          .collect::<Vec<_>>();
+        match GridDataRepr::deserialize(deserializer)? {
+            GridDataRepr::Rectangular { dim, data } =>
+                Array2::from_shape_vec(dim, values(data))
+                  .map(|arr| GridData::Rectangular(arr.into_shared()))
+                  .map_err(D::Error::custom),
+            GridDataRepr::Triangular { dim, data } =>
+                Array3::from_shape_vec(dim, values(data))
+                  .map(|arr| GridData::Triangular(arr.into_shared()))
+                  .map_err(D::Error::custom),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Error")]
+enum ErrorRepr {
+    IOError { kind: String, message: String },
+    SizeMismatch(u32, u32, u32),
+    InvalidXSpec(f64, f64, f64, u32),
+    InvalidYSpec(f64, f64, f64, u32),
+    InvalidRectangularSize(u32, u64),
+    InvalidTriangleCount(u32, u64),
+    InvalidXYUnitOfMeasure(u32),
+    InvalidZUnitOfMeasure(u32),
+    NotRectangular,
+    NotTriangular,
+    InvalidInput(String),
+    InvalidArgument(String),
+    UnknownVerticalDatum,
+    NotConverged(usize, f64),
+}
+
+/* the I/O error kinds which are distinguished by [Error::category]; others
+ * round-trip as io::ErrorKind::Other */
+const IO_KINDS: &[io::ErrorKind] = {
+    use io::ErrorKind::*;
+    &[
+        UnexpectedEof, InvalidData, InvalidInput, InvalidFilename, Unsupported,
+        Interrupted, TimedOut, WouldBlock, ResourceBusy, Deadlock,
+        ConnectionRefused, ConnectionReset, ConnectionAborted, NotConnected,
+        BrokenPipe, AddrInUse, AddrNotAvailable, NetworkDown,
+        NetworkUnreachable, HostUnreachable, StaleNetworkFileHandle,
+        ExecutableFileBusy, NotFound, PermissionDenied, AlreadyExists,
+        IsADirectory, NotADirectory, DirectoryNotEmpty, ReadOnlyFilesystem,
+        StorageFull, QuotaExceeded, FileTooLarge, NotSeekable, CrossesDevices,
+        TooManyLinks, WriteZero, OutOfMemory,
+    ]
+};
+
+fn io_kind_name(kind: io::ErrorKind) -> String {
+    format!("{:?}", kind)
+}
+
+impl From<&Error> for ErrorRepr {
+    fn from(e: &Error) -> Self {
+        match *e {
+            Error::IOError(ref io) => ErrorRepr::IOError {
+                kind: io_kind_name(io.kind()),
+                message: io.to_string(),
+            },
+            Error::SizeMismatch(size, rows, cols) =>
+                ErrorRepr::SizeMismatch(size, rows, cols),
+            Error::InvalidXSpec(min, max, step, n) =>
+                ErrorRepr::InvalidXSpec(min, max, step, n),
+            Error::InvalidYSpec(min, max, step, n) =>
+                ErrorRepr::InvalidYSpec(min, max, step, n),
+            Error::InvalidRectangularSize(size, actual) =>
+                ErrorRepr::InvalidRectangularSize(size, actual),
+            Error::InvalidTriangleCount(count, actual) =>
+                ErrorRepr::InvalidTriangleCount(count, actual),
+            Error::InvalidXYUnitOfMeasure(code) =>
+                ErrorRepr::InvalidXYUnitOfMeasure(code),
+            Error::InvalidZUnitOfMeasure(code) =>
+                ErrorRepr::InvalidZUnitOfMeasure(code),
+            Error::NotRectangular => ErrorRepr::NotRectangular,
+            Error::NotTriangular => ErrorRepr::NotTriangular,
+            Error::InvalidInput(ref msg) => ErrorRepr::InvalidInput(msg.clone()),
+            Error::InvalidArgument(ref msg) =>
+                ErrorRepr::InvalidArgument(msg.clone()),
+            Error::UnknownVerticalDatum => ErrorRepr::UnknownVerticalDatum,
+            Error::NotConverged(iterations, residual) =>
+                ErrorRepr::NotConverged(iterations, residual),
+        }
+    }
+}
+
+impl From<ErrorRepr> for Error {
+    fn from(e: ErrorRepr) -> Self {
+        match e {
+            ErrorRepr::IOError { kind, message } => {
+                let kind = IO_KINDS.iter()
+                  .find(|k| io_kind_name(**k) == kind)
+                  .copied()
+                  .unwrap_or(io::ErrorKind::Other);
+                Error::IOError(io::Error::new(kind, message))
+            },
+            ErrorRepr::SizeMismatch(size, rows, cols) =>
+                Error::SizeMismatch(size, rows, cols),
+            ErrorRepr::InvalidXSpec(min, max, step, n) =>
+                Error::InvalidXSpec(min, max, step, n),
+            ErrorRepr::InvalidYSpec(min, max, step, n) =>
+                Error::InvalidYSpec(min, max, step, n),
+            ErrorRepr::InvalidRectangularSize(size, actual) =>
+                Error::InvalidRectangularSize(size, actual),
+            ErrorRepr::InvalidTriangleCount(count, actual) =>
+                Error::InvalidTriangleCount(count, actual),
+            ErrorRepr::InvalidXYUnitOfMeasure(code) =>
+                Error::InvalidXYUnitOfMeasure(code),
+            ErrorRepr::InvalidZUnitOfMeasure(code) =>
+                Error::InvalidZUnitOfMeasure(code),
+            ErrorRepr::NotRectangular => Error::NotRectangular,
+            ErrorRepr::NotTriangular => Error::NotTriangular,
+            ErrorRepr::InvalidInput(msg) => Error::InvalidInput(msg),
+            ErrorRepr::InvalidArgument(msg) => Error::InvalidArgument(msg),
+            ErrorRepr::UnknownVerticalDatum => Error::UnknownVerticalDatum,
+            ErrorRepr::NotConverged(iterations, residual) =>
+                Error::NotConverged(iterations, residual),
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S)
+      -> Result<S::Ok, S::Error> {
+        ErrorRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Error {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+      -> Result<Self, D::Error> {
+        ErrorRepr::deserialize(deserializer).map(Error::from)
+    }
+}
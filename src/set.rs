@@ -0,0 +1,268 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! collections of grids read from many files
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Grid, GridData};
+
+/// a collection of grids, each tagged with the path it was read from
+#[derive(Clone, Debug, Default)]
+pub struct GridSet {
+    entries: Vec<(PathBuf, Grid)>,
+}
+
+impl GridSet {
+    /// an empty set
+    pub fn new() -> GridSet {
+        GridSet::default()
+    }
+
+    /// read grids from each of a series of paths; grids which can't be read
+    /// are returned alongside their errors rather than aborting the process
+    pub fn read_paths<I, P>(paths: I) -> (GridSet, Vec<(PathBuf, Error)>)
+      where I: IntoIterator<Item = P>,
+            P: AsRef<Path>,
+    {
+        let mut set = GridSet::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            let result = File::open(path).map_err(Error::from)
+              .and_then(|mut f| Grid::read(&mut f));
+            match result {
+                Ok(grid) => set.push(path, grid),
+                Err(e) => errors.push((path.to_owned(), e)),
+            }
+        }
+        (set, errors)
+    }
+
+    /// read every `.grd` file (ignoring case) directly within a directory;
+    /// see [GridSet::read_paths]
+    ///
+    /// only failing to list the directory at all is an error; entries which
+    /// can't be examined (e.g. dangling links, or files we may not see) are
+    /// returned alongside the grids which can't be read, listing errors
+    /// first, rather than aborting the listing
+    pub fn read_dir<P: AsRef<Path>>(dir: P)
+      -> io::Result<(GridSet, Vec<(PathBuf, Error)>)> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        let mut errors = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    errors.push((dir.to_owned(), e.into()));
+                    continue;
+                },
+            };
+            if !is_grd_path(&path) {
+                continue;
+            }
+            // this follows links, so linked files are read too
+            match fs::metadata(&path) {
+                Ok(meta) if meta.is_file() => paths.push(path),
+                Ok(_) => { },
+                Err(e) => errors.push((path, e.into())),
+            }
+        }
+        paths.sort();
+        let (set, read_errors) = GridSet::read_paths(paths);
+        errors.extend(read_errors);
+        Ok((set, errors))
+    }
+
+    /// add a grid to the set
+    pub fn push<P: Into<PathBuf>>(&mut self, path: P, grid: Grid) {
+        self.entries.push((path.into(), grid));
+    }
+
+    /// the number of grids in the set
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// is the set empty?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// iterate over the paths and grids in the set
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &Grid)> {
+        self.entries.iter().map(|(p, g)| (p.as_path(), g))
+    }
+
+    /// find groups of grids with identical geometry and data, regardless of
+    /// their names, paths, or other metadata
+    ///
+    /// each group (in the order first encountered) lists the paths of two or
+    /// more duplicate grids; grids with no duplicates are omitted
+    pub fn find_duplicates(&self) -> Vec<Vec<&Path>> {
+        let mut by_fingerprint: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut order = Vec::new();
+        for (i, (_, grid)) in self.entries.iter().enumerate() {
+            let fp = grid.fingerprint();
+            by_fingerprint.entry(fp).or_insert_with(|| {
+                order.push(fp);
+                Vec::new()
+            }).push(i);
+        }
+
+        let mut groups = Vec::new();
+        for fp in order {
+            /* fingerprints can collide, so split each bucket into groups of
+             * grids which really are identical */
+            let mut pending = by_fingerprint.remove(&fp).unwrap_or_default();
+            while let Some(first) = pending.first().copied() {
+                let (same, rest): (Vec<usize>, Vec<usize>) = pending
+                  .into_iter()
+                  .partition(|&i| {
+                      self.entries[i].1.same_data(&self.entries[first].1)
+                  });
+                if same.len() > 1 {
+                    groups.push(same.iter()
+                      .map(|&i| self.entries[i].0.as_path())
+                      .collect());
+                }
+                pending = rest;
+            }
+        }
+        groups
+    }
+}
+
+impl FromIterator<(PathBuf, Grid)> for GridSet {
+    fn from_iter<I: IntoIterator<Item = (PathBuf, Grid)>>(iter: I) -> Self {
+        GridSet { entries: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for GridSet {
+    type Item = (PathBuf, Grid);
+    type IntoIter = std::vec::IntoIter<(PathBuf, Grid)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+// 64-bit FNV-1a, chosen because it's trivial and stable across releases
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_f64(&mut self, v: f64) {
+        // all NaNs are "missing", and should hash alike (as should ±0)
+        let v = if v.is_nan() {
+            f64::NAN
+        } else if v == 0.0 {
+            0.0
+        } else {
+            v
+        };
+        self.write(&v.to_le_bytes());
+    }
+}
+
+impl Grid {
+    /// a fingerprint of the grid's geometry and data, ignoring its name and
+    /// other descriptive metadata
+    ///
+    /// identical grids always have the same fingerprint, and the fingerprint
+    /// of a given grid is stable across releases of this library; different
+    /// grids *usually* have different fingerprints
+    pub fn fingerprint(&self) -> u64 {
+        let mut h = Fnv(FNV_OFFSET);
+        h.write(&self.rows.to_le_bytes());
+        h.write(&self.columns.to_le_bytes());
+        for v in [self.xmin, self.xmax, self.ymin, self.ymax,
+          self.xstep, self.ystep] {
+            h.write_f64(v);
+        }
+        match &self.data {
+            GridData::Rectangular(arr) => {
+                h.write(b"R");
+                for &z in arr.iter() {
+                    h.write_f64(z);
+                }
+            },
+            GridData::Triangular(arr) => {
+                h.write(b"T");
+                for &v in arr.iter() {
+                    h.write_f64(v);
+                }
+            },
+        }
+        h.0
+    }
+
+    /// do two grids have identical geometry and data (treating all missing
+    /// values as equal), regardless of their names and other descriptive
+    /// metadata?
+    pub fn same_data(&self, other: &Grid) -> bool {
+        let eq = |a: f64, b: f64| a == b || (a.is_nan() && b.is_nan());
+        let same_geometry = self.rows == other.rows
+          && self.columns == other.columns
+          && eq(self.xmin, other.xmin)
+          && eq(self.xmax, other.xmax)
+          && eq(self.ymin, other.ymin)
+          && eq(self.ymax, other.ymax)
+          && eq(self.xstep, other.xstep)
+          && eq(self.ystep, other.ystep);
+        if !same_geometry {
+            return false;
+        }
+
+        match (&self.data, &other.data) {
+            (GridData::Rectangular(a), GridData::Rectangular(b)) =>
+                a.dim() == b.dim()
+                  && a.iter().zip(b.iter()).all(|(&x, &y)| eq(x, y)),
+            (GridData::Triangular(a), GridData::Triangular(b)) =>
+                a.dim() == b.dim()
+                  && a.iter().zip(b.iter()).all(|(&x, &y)| eq(x, y)),
+            _ => false,
+        }
+    }
+}
+
+// does this path have a `.grd` extension (ignoring case)?
+pub(crate) fn is_grd_path(path: &Path) -> bool {
+    path.extension()
+      .map(|ext| ext.eq_ignore_ascii_case("grd"))
+      .unwrap_or(false)
+}
@@ -0,0 +1,144 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! summary statistics over grid *z* values
+
+use crate::GridData;
+
+/// summary statistics of a grid's *z* values; missing (NaN) values are
+/// counted but otherwise ignored
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// the number of non-missing values
+    pub count: usize,
+
+    /// the number of missing values
+    pub null_count: usize,
+
+    /// the minimum non-missing value (NaN if there are none)
+    pub min: f64,
+
+    /// the maximum non-missing value (NaN if there are none)
+    pub max: f64,
+
+    /// the mean of the non-missing values (NaN if there are none)
+    pub mean: f64,
+
+    /// the (population) standard deviation of the non-missing values (NaN if
+    /// there are none)
+    pub std_dev: f64,
+}
+
+impl Stats {
+    /// compute statistics over a sequence of values
+    pub fn from_values(values: impl IntoIterator<Item = f64>) -> Stats {
+        let mut count = 0;
+        let mut null_count = 0;
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        // Welford's algorithm, to stay stable over very large grids
+        let mut mean = 0.0;
+        let mut m2 = 0.0;
+
+        for z in values {
+            if z.is_nan() {
+                null_count += 1;
+                continue;
+            }
+            count += 1;
+            min = min.min(z);
+            max = max.max(z);
+            let delta = z - mean;
+            mean += delta / count as f64;
+            m2 += delta * (z - mean);
+        }
+
+        if count == 0 {
+            Stats {
+                count,
+                null_count,
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+            }
+        } else {
+            Stats {
+                count,
+                null_count,
+                min,
+                max,
+                mean,
+                std_dev: (m2 / count as f64).sqrt(),
+            }
+        }
+    }
+}
+
+impl Stats {
+    /// combine statistics of two disjoint sets of values (e.g. chunks of one
+    /// grid; see [GridChunk](crate::GridChunk)) into statistics of both
+    pub fn merge(&self, other: &Stats) -> Stats {
+        let null_count = self.null_count + other.null_count;
+        if other.count == 0 {
+            return Stats { null_count, ..*self };
+        }
+        if self.count == 0 {
+            return Stats { null_count, ..*other };
+        }
+
+        // Chan et al.'s pairwise combination of Welford's accumulators
+        let count = self.count + other.count;
+        let (n_a, n_b, n) =
+          (self.count as f64, other.count as f64, count as f64);
+        let delta = other.mean - self.mean;
+        let m2 = self.std_dev * self.std_dev * n_a
+          + other.std_dev * other.std_dev * n_b
+          + delta * delta * n_a * n_b / n;
+        Stats {
+            count,
+            null_count,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            mean: self.mean + delta * n_b / n,
+            std_dev: (m2 / n).sqrt(),
+        }
+    }
+}
+
+impl GridData {
+    /// all *z* values in the grid: every node of a rectangular grid, or every
+    /// vertex of every triangle of a triangular grid
+    pub fn z_values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        match self {
+            GridData::Rectangular(arr) => Box::new(arr.iter().copied()),
+            GridData::Triangular(arr) => Box::new(
+              arr.outer_iter().flat_map(|tri| {
+                  (0..3).map(move |v| tri[(v, 2)])
+              })),
+        }
+    }
+
+    /// summary statistics of the grid's *z* values
+    pub fn stats(&self) -> Stats {
+        Stats::from_values(self.z_values())
+    }
+}
@@ -0,0 +1,215 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Monte Carlo volumetrics: volumes over many realizations of a grid,
+//! perturbed within its uncertainty, and of the other inputs, varied within
+//! ranges
+
+use crate::{GridWithUncertainty, Stats};
+
+/// how node errors are related within a single realization
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NodeCorrelation {
+    /// every node moves by the same number of its own standard deviations
+    /// (the most conservative choice, and the usual one for surfaces whose
+    /// errors come from e.g. a velocity model)
+    #[default]
+    Full,
+
+    /// every node's error is drawn independently
+    Independent,
+}
+
+/// options for a Monte Carlo volume calculation; see
+/// [StochasticVolume::run]
+///
+/// in each realization, the grid is perturbed by normally-distributed
+/// errors with its variance (see [GridWithUncertainty]), the base is drawn
+/// uniformly from its range, and the gross volume above the base (see
+/// [GridWithUncertainty::volume_above]) is scaled by a factor drawn
+/// uniformly from its range (e.g. for net-to-gross and porosity)
+#[derive(Clone, Debug)]
+pub struct StochasticVolume {
+    realizations: usize,
+    seed: u64,
+    base: (f64, f64),
+    factor: (f64, f64),
+    correlation: NodeCorrelation,
+}
+
+impl StochasticVolume {
+    /// a calculation over the given number of realizations, with a base of
+    /// zero, a factor of one, fully-correlated node errors, and a fixed seed
+    pub fn new(realizations: usize) -> StochasticVolume {
+        StochasticVolume {
+            realizations,
+            seed: 0,
+            base: (0.0, 0.0),
+            factor: (1.0, 1.0),
+            correlation: NodeCorrelation::Full,
+        }
+    }
+
+    /// seed the random number generator; runs with the same seed and
+    /// options give the same volumes
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// a fixed base
+    pub fn base(self, base: f64) -> Self {
+        Self { base: (base, base), ..self }
+    }
+
+    /// a base which varies uniformly between `low` and `high`
+    pub fn base_range(self, low: f64, high: f64) -> Self {
+        Self { base: (low, high), ..self }
+    }
+
+    /// a volume factor which varies uniformly between `low` and `high`
+    pub fn factor_range(self, low: f64, high: f64) -> Self {
+        Self { factor: (low, high), ..self }
+    }
+
+    /// how node errors are related within a realization
+    pub fn correlation(self, correlation: NodeCorrelation) -> Self {
+        Self { correlation, ..self }
+    }
+
+    /// compute the volume of each realization
+    pub fn run(&self, input: &GridWithUncertainty) -> VolumeDistribution {
+        let mut rng = SplitMix64(self.seed);
+        let grid = input.grid();
+        let cell = grid.xstep * grid.ystep;
+        let estimates = grid.rectangular().expect("checked rectangular");
+        let std_dev = input.std_dev();
+
+        let mut volumes = Vec::with_capacity(self.realizations);
+        for _ in 0..self.realizations {
+            let base = rng.uniform(self.base.0, self.base.1);
+            let factor = rng.uniform(self.factor.0, self.factor.1);
+            let shared = rng.normal();
+            let mut volume = 0.0;
+            for (&z, &sd) in estimates.iter().zip(std_dev.iter()) {
+                if z.is_nan() {
+                    continue;
+                }
+                let error = match (sd.is_nan(), self.correlation) {
+                    (true, _) => 0.0,
+                    (false, NodeCorrelation::Full) => shared * sd,
+                    (false, NodeCorrelation::Independent) => rng.normal() * sd,
+                };
+                let thickness = z + error - base;
+                if thickness > 0.0 {
+                    volume += thickness * cell;
+                }
+            }
+            volumes.push(volume * factor);
+        }
+        VolumeDistribution::new(volumes)
+    }
+}
+
+/// the volumes from the realizations of a Monte Carlo volume calculation,
+/// in *x*/*y* units squared times *z* units
+///
+/// percentiles follow the petroleum convention, where P90 is the low case
+/// (exceeded in 90% of realizations) and P10 the high case
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeDistribution {
+    volumes: Vec<f64>,
+}
+
+impl VolumeDistribution {
+    fn new(mut volumes: Vec<f64>) -> VolumeDistribution {
+        volumes.sort_by(f64::total_cmp);
+        VolumeDistribution { volumes }
+    }
+
+    /// the volume of each realization, in increasing order
+    pub fn volumes(&self) -> &[f64] {
+        &self.volumes
+    }
+
+    /// summary statistics of the volumes
+    pub fn stats(&self) -> Stats {
+        Stats::from_values(self.volumes.iter().copied())
+    }
+
+    /// the volume exceeded in the given fraction (0 to 1) of realizations,
+    /// interpolating linearly between realizations; NaN if there are none
+    pub fn exceeded(&self, probability: f64) -> f64 {
+        let n = self.volumes.len();
+        if n == 0 {
+            return f64::NAN;
+        }
+        let pos = (1.0 - probability.clamp(0.0, 1.0)) * (n - 1) as f64;
+        let lo = pos.floor() as usize;
+        let hi = pos.ceil() as usize;
+        let frac = pos - lo as f64;
+        self.volumes[lo] + frac * (self.volumes[hi] - self.volumes[lo])
+    }
+
+    /// the high case, exceeded in 10% of realizations
+    pub fn p10(&self) -> f64 {
+        self.exceeded(0.1)
+    }
+
+    /// the median case
+    pub fn p50(&self) -> f64 {
+        self.exceeded(0.5)
+    }
+
+    /// the low case, exceeded in 90% of realizations
+    pub fn p90(&self) -> f64 {
+        self.exceeded(0.9)
+    }
+}
+
+/* SplitMix64 (Steele, Lea, & Flood 2014): tiny, fast, and good enough for
+ * Monte Carlo, without taking on a dependency */
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform on [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    // standard normal, by the Box-Muller transform
+    fn normal(&mut self) -> f64 {
+        let u = 1.0 - self.next_f64(); // (0, 1], so the log is finite
+        let v = self.next_f64();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
@@ -0,0 +1,127 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! refinement of triangular grids by midpoint subdivision
+
+use std::collections::HashMap;
+
+use ndarray::Array3;
+
+use crate::{Error, Grid, GridData};
+
+type Vertex = [f64; 3];
+
+// surface gradients (∂z/∂x, ∂z/∂y) at each distinct vertex
+type Gradients = HashMap<[u64; 3], (f64, f64)>;
+
+impl Grid {
+    /// densify a triangular grid by splitting each triangle into four at the
+    /// midpoints of its edges, `levels` times over (so multiplying the number
+    /// of triangles by 4<sup>`levels`</sup>), e.g. before rasterizing or
+    /// meshing a coarse TIN
+    ///
+    /// without `smooth`, the new vertices lie on the original facets; with
+    /// it, each midpoint's *z* follows a cubic along its edge which matches
+    /// the surface gradient at either end (the area-weighted mean gradient of
+    /// the triangles sharing each vertex), which hides the faceting. either
+    /// way the original vertices are kept as-is, and adjacent triangles
+    /// always agree on the midpoints of their shared edges
+    pub fn subdivide(&self, levels: u32, smooth: bool) -> Result<Grid, Error> {
+        let GridData::Triangular(arr) = &self.data else {
+            return Err(Error::NotTriangular);
+        };
+
+        let mut tris: Vec<[Vertex; 3]> = arr.outer_iter()
+          .map(|t| [0, 1, 2].map(|v| [t[(v, 0)], t[(v, 1)], t[(v, 2)]]))
+          .collect();
+        for _ in 0..levels {
+            let gradients = if smooth {
+                vertex_gradients(&tris)
+            } else {
+                Gradients::new()
+            };
+            let mid = |p, q| midpoint(p, q, &gradients);
+            tris = tris.iter().flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+                // preserving each triangle's winding
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            }).collect();
+        }
+
+        let n = tris.len();
+        let values = tris.into_iter().flatten().flatten().collect();
+        // safety: we have exactly 9 values per triangle
+        let data = GridData::Triangular(
+          Array3::from_shape_vec((n, 3, 3), values).unwrap().into_shared());
+
+        let mut header = self.header();
+        header.n_triangles = n as u32;
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Ok(Grid::from_parts(header, data))
+    }
+}
+
+fn key(v: Vertex) -> [u64; 3] {
+    // ±0 are the same vertex
+    v.map(|c| (c + 0.0).to_bits())
+}
+
+fn vertex_gradients(tris: &[[Vertex; 3]]) -> Gradients {
+    let mut sums: HashMap<[u64; 3], (f64, f64, f64)> = HashMap::new();
+    for &[a, b, c] in tris {
+        let (dx1, dy1, dz1) = (b[0] - a[0], b[1] - a[1], b[2] - a[2]);
+        let (dx2, dy2, dz2) = (c[0] - a[0], c[1] - a[1], c[2] - a[2]);
+        let det = dx1 * dy2 - dx2 * dy1;
+        let gx = (dz1 * dy2 - dz2 * dy1) / det;
+        let gy = (dx1 * dz2 - dx2 * dz1) / det;
+        // skips degenerate triangles, and those with missing values
+        if det == 0.0 || gx.is_nan() || gy.is_nan() {
+            continue;
+        }
+        let area = det.abs() / 2.0;
+        for v in [a, b, c] {
+            let sum = sums.entry(key(v)).or_insert((0.0, 0.0, 0.0));
+            sum.0 += area * gx;
+            sum.1 += area * gy;
+            sum.2 += area;
+        }
+    }
+    sums.into_iter()
+      .map(|(k, (gx, gy, w))| (k, (gx / w, gy / w)))
+      .collect()
+}
+
+/* the midpoint of the edge p → q, with z from the cubic Hermite interpolant
+ * through the ends' gradients if we have them; this is symmetric in p and q
+ * (bit-for-bit), so both triangles sharing an edge get the same midpoint */
+fn midpoint(p: Vertex, q: Vertex, gradients: &Gradients) -> Vertex {
+    let (dx, dy) = (q[0] - p[0], q[1] - p[1]);
+    let mut z = (p[2] + q[2]) / 2.0;
+    if let (Some(gp), Some(gq)) = (gradients.get(&key(p)),
+      gradients.get(&key(q))) {
+        let slope_p = gp.0 * dx + gp.1 * dy;
+        let slope_q = gq.0 * dx + gq.1 * dy;
+        z += (slope_p - slope_q) / 8.0;
+    }
+    [(p[0] + q[0]) / 2.0, (p[1] + q[1]) / 2.0, z]
+}
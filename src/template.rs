@@ -0,0 +1,251 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! metadata templates, so that grids converted or derived from other data
+//! carry consistent names, sources, and coordinate systems when written
+//!
+//! a template gives patterns for a grid's name and source data, and
+//! defaults for its projection and datum (used only where the grid has
+//! none). patterns may include these placeholders:
+//!
+//! - `{name}`: the grid's own name
+//! - `{source}`: the grid's own source data
+//! - `{file}` and `{stem}`: the name of the file the grid was read from,
+//!   with and without its extension
+//! - `{format}`: the format the grid was read from
+//! - `{date}`: today's date (UTC), as YYYY-MM-DD
+//! - `{version}`: the version of this library
+//!
+//! with `{{` and `}}` for literal braces. templates can be built in code, or
+//! read from simple configuration files of `key = value` lines (with keys
+//! `name`, `source_data`, `projection`, and `datum`, and `#` comments):
+//!
+//! ```text
+//! # house style for converted grids
+//! name = {stem}
+//! source_data = converted from {file} ({format}) on {date}
+//! projection = TX-27C
+//! datum = NAD27
+//! ```
+
+use std::{fs, path::Path};
+
+use time::OffsetDateTime;
+
+use crate::{DATUM_LEN, Error, Grid, NAME_LEN, PROJ_LEN, SOURCE_LEN};
+
+const PLACEHOLDERS: &[&str] = &[
+    "name", "source", "file", "stem", "format", "date", "version",
+];
+
+/// a template for grid metadata; see the [module docs](self)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetadataTemplate {
+    name: Option<String>,
+    source_data: Option<String>,
+    projection: Option<String>,
+    datum: Option<String>,
+}
+
+/// where a grid came from, for filling in a [MetadataTemplate]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TemplateContext<'a> {
+    /// the file the grid was read from
+    pub path: Option<&'a Path>,
+
+    /// the name of the format the grid was read from
+    pub format: Option<&'a str>,
+}
+
+impl MetadataTemplate {
+    /// an empty template, which changes nothing
+    pub fn new() -> MetadataTemplate {
+        MetadataTemplate::default()
+    }
+
+    /// set the pattern for grid names
+    pub fn name(mut self, pattern: &str) -> MetadataTemplate {
+        self.name = Some(pattern.to_string());
+        self
+    }
+
+    /// set the pattern for grids' source data
+    pub fn source_data(mut self, pattern: &str) -> MetadataTemplate {
+        self.source_data = Some(pattern.to_string());
+        self
+    }
+
+    /// set the projection given to grids without one
+    pub fn projection(mut self, projection: &str) -> MetadataTemplate {
+        self.projection = Some(projection.to_string());
+        self
+    }
+
+    /// set the datum given to grids without one
+    pub fn datum(mut self, datum: &str) -> MetadataTemplate {
+        self.datum = Some(datum.to_string());
+        self
+    }
+
+    /// parse a template from `key = value` lines; fails with
+    /// [Error::InvalidArgument] for unknown keys or placeholders
+    pub fn parse(text: &str) -> Result<MetadataTemplate, Error> {
+        let mut template = MetadataTemplate::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(Error::InvalidArgument(format!(
+                  "line {} of metadata template isn't key = value", n + 1)));
+            };
+            let value = value.trim();
+            template = match key.trim() {
+                "name" => template.name(value),
+                "source_data" => template.source_data(value),
+                "projection" => template.projection(value),
+                "datum" => template.datum(value),
+                key => return Err(Error::InvalidArgument(format!(
+                  "unknown key {} on line {} of metadata template", key,
+                  n + 1))),
+            };
+        }
+        template.check()?;
+        Ok(template)
+    }
+
+    /// read a template from a file; see [MetadataTemplate::parse]
+    pub fn read_file<P: AsRef<Path>>(path: P)
+      -> Result<MetadataTemplate, Error> {
+        MetadataTemplate::parse(&fs::read_to_string(path)?)
+    }
+
+    /// check that the template's patterns are well-formed, with only known
+    /// placeholders
+    pub fn check(&self) -> Result<(), Error> {
+        let context = TemplateContext::default();
+        for pattern in [&self.name, &self.source_data].into_iter().flatten() {
+            render(pattern, "", "", &context)?;
+        }
+        Ok(())
+    }
+
+    /// fill in a grid's metadata from the template
+    ///
+    /// fails with [Error::InvalidArgument] (leaving the grid unchanged) if a
+    /// pattern is malformed, or if any field would be too long for the
+    /// GRD format
+    pub fn apply(&self, grid: &mut Grid, context: &TemplateContext)
+      -> Result<(), Error> {
+        let name = match &self.name {
+            Some(pattern) =>
+                render(pattern, &grid.name, &grid.source_data, context)?,
+            None => grid.name.clone(),
+        };
+        let source_data = match &self.source_data {
+            Some(pattern) =>
+                render(pattern, &grid.name, &grid.source_data, context)?,
+            None => grid.source_data.clone(),
+        };
+        let default = |value: &str, default: &Option<String>|
+          match default {
+              Some(default) if value.trim().is_empty() => default.clone(),
+              _ => value.to_string(),
+          };
+        let projection = default(&grid.projection, &self.projection);
+        let datum = default(&grid.datum, &self.datum);
+
+        // GRD fields are NUL-terminated
+        for (field, value, len) in [("name", &name, NAME_LEN),
+          ("source data", &source_data, SOURCE_LEN),
+          ("projection", &projection, PROJ_LEN),
+          ("datum", &datum, DATUM_LEN)] {
+            if value.len() >= len {
+                return Err(Error::InvalidArgument(format!(
+                  "templated {} is longer than {} bytes: {}", field, len - 1,
+                  value)));
+            }
+        }
+
+        grid.name = name;
+        grid.source_data = source_data;
+        grid.projection = projection;
+        grid.datum = datum;
+        Ok(())
+    }
+}
+
+// fill in a pattern's placeholders
+fn render(pattern: &str, name: &str, source: &str,
+  context: &TemplateContext) -> Result<String, Error> {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            },
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(Error::InvalidArgument(format!(
+                          "unmatched {{ in metadata template: {}", pattern))),
+                    }
+                }
+                out.push_str(&placeholder_value(&placeholder, name,
+                  source, context).ok_or_else(|| Error::InvalidArgument(format!(
+                    "unknown placeholder {{{}}} in metadata template (known: \
+                     {})", placeholder, PLACEHOLDERS.join(", "))))?);
+            },
+            '}' => return Err(Error::InvalidArgument(format!(
+              "unmatched }} in metadata template: {}", pattern))),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+// a placeholder's value; empty where the context doesn't give it
+fn placeholder_value(placeholder: &str, name: &str, source: &str,
+  context: &TemplateContext) -> Option<String> {
+    let path_part = |part: Option<&std::ffi::OsStr>| part
+      .map(|s| s.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    Some(match placeholder {
+        "name" => name.to_string(),
+        "source" => source.to_string(),
+        "file" => path_part(context.path.and_then(Path::file_name)),
+        "stem" => path_part(context.path.and_then(Path::file_stem)),
+        "format" => context.format.unwrap_or_default().to_string(),
+        "date" => OffsetDateTime::now_utc().date().to_string(),
+        "version" => env!("CARGO_PKG_VERSION").to_string(),
+        _ => return None,
+    })
+}
@@ -0,0 +1,211 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! synthetic grids of analytic surfaces, with known answers, for use in test
+//! suites (this crate's or downstream crates')
+//!
+//! grids are generated by evaluating a [Surface] at each node of a
+//! [Lattice]; because the surfaces are known exactly, so are the right
+//! answers for sampling, gradients, and so on. planes in particular are
+//! reproduced exactly by both bilinear and triangular interpolation, so
+//! [crate::Grid::sample] on a synthetic plane should agree with
+//! [Surface::z] everywhere within the grid (up to rounding)
+//!
+//! ```
+//! use petra_grid::testing::{Lattice, Surface};
+//!
+//! let plane = Surface::Plane { z0: 100.0, dzdx: 0.01, dzdy: -0.02 };
+//! let grid = plane.rectangular(&Lattice::default());
+//! let z = grid.sample(1234.0, 2345.0).unwrap();
+//! assert!((z - plane.z(1234.0, 2345.0)).abs() < 1e-9);
+//! ```
+
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::Cursor,
+    path::Path,
+};
+
+use ndarray::{Array2, Array3};
+
+use time::macros::datetime;
+
+use crate::{Error, Grid, GridData};
+
+pub use crate::gridding::Lattice;
+
+/// an analytic surface *z* = *f*(*x*, *y*)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Surface {
+    /// the plane *z* = `z0` + `dzdx`·*x* + `dzdy`·*y*
+    Plane {
+        /** *z* at the origin */ z0: f64,
+        /** gradient along *x* */ dzdx: f64,
+        /** gradient along *y* */ dzdy: f64,
+    },
+
+    /// a paraboloid dome, `height` high at (`x0`, `y0`), falling to zero at
+    /// `radius` from its apex (and below zero beyond)
+    Dome {
+        /** *x* of the apex */ x0: f64,
+        /** *y* of the apex */ y0: f64,
+        /** *z* of the apex */ height: f64,
+        /** distance from the apex at which *z* = 0 */ radius: f64,
+    },
+
+    /// the "egg crate" *z* = `amplitude`·sin(2π*x*/`wavelength`)·
+    /// cos(2π*y*/`wavelength`)
+    Sinusoid {
+        /** the peak *z* value */ amplitude: f64,
+        /** the wavelength along both *x* and *y* */ wavelength: f64,
+    },
+}
+
+impl Surface {
+    /// the exact *z* value of the surface at (*x*, *y*)
+    pub fn z(&self, x: f64, y: f64) -> f64 {
+        match *self {
+            Surface::Plane { z0, dzdx, dzdy } => z0 + dzdx * x + dzdy * y,
+            Surface::Dome { x0, y0, height, radius } => {
+                let r2 = (x - x0).powi(2) + (y - y0).powi(2);
+                height * (1.0 - r2 / (radius * radius))
+            },
+            Surface::Sinusoid { amplitude, wavelength } => {
+                let k = 2.0 * PI / wavelength;
+                amplitude * (k * x).sin() * (k * y).cos()
+            },
+        }
+    }
+
+    /// the exact gradient (∂*z*/∂*x*, ∂*z*/∂*y*) of the surface at (*x*, *y*)
+    pub fn gradient(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            Surface::Plane { dzdx, dzdy, .. } => (dzdx, dzdy),
+            Surface::Dome { x0, y0, height, radius } => {
+                let c = -2.0 * height / (radius * radius);
+                (c * (x - x0), c * (y - y0))
+            },
+            Surface::Sinusoid { amplitude, wavelength } => {
+                let k = 2.0 * PI / wavelength;
+                (amplitude * k * (k * x).cos() * (k * y).cos(),
+                 -amplitude * k * (k * x).sin() * (k * y).sin())
+            },
+        }
+    }
+
+    /// a short name for the surface, used to name generated grids
+    pub fn name(&self) -> &'static str {
+        match self {
+            Surface::Plane { .. } => "plane",
+            Surface::Dome { .. } => "dome",
+            Surface::Sinusoid { .. } => "sinusoid",
+        }
+    }
+
+    /// a rectangular grid of the surface evaluated at each node of a lattice
+    pub fn rectangular(&self, lattice: &Lattice) -> Grid {
+        let data = Array2::from_shape_fn((lattice.rows, lattice.columns),
+          |(j, i)| {
+              let (x, y) = lattice_xy(lattice, j, i);
+              self.z(x, y)
+          });
+        let mut grid = Grid::new_rectangular(lattice.xmin, lattice.ymin,
+          lattice.xstep, lattice.ystep, data);
+        grid.name = format!("synthetic {}", self.name());
+        // a fixed date, so generated files are reproducible byte-for-byte
+        grid.created_date = datetime!(2000-01-01 00:00);
+        grid
+    }
+
+    /// a triangular grid of the surface evaluated at each node of a lattice,
+    /// with each lattice cell split (southwest to northeast) into two
+    /// counterclockwise triangles
+    pub fn triangular(&self, lattice: &Lattice) -> Grid {
+        let rect = self.rectangular(lattice);
+        let cells_x = lattice.columns.saturating_sub(1);
+        let cells_y = lattice.rows.saturating_sub(1);
+        let n = cells_x * cells_y * 2;
+
+        let mut data = Array3::zeros((n, 3, 3));
+        let mut t = 0;
+        for j in 0..cells_y {
+            for i in 0..cells_x {
+                for tri in [
+                    [(j, i), (j, i + 1), (j + 1, i + 1)],
+                    [(j, i), (j + 1, i + 1), (j + 1, i)],
+                ] {
+                    for (v, (jj, ii)) in tri.into_iter().enumerate() {
+                        let (x, y) = lattice_xy(lattice, jj, ii);
+                        data[(t, v, 0)] = x;
+                        data[(t, v, 1)] = y;
+                        data[(t, v, 2)] = self.z(x, y);
+                    }
+                    t += 1;
+                }
+            }
+        }
+
+        let mut header = rect.header();
+        header.n_triangles = n as u32;
+        Grid::from_parts(header, GridData::Triangular(data.into_shared()))
+    }
+}
+
+/// a small plane on a 3 × 4 lattice, spaced 25 × 50 units from (1000, 2000),
+/// for checking exported files field by field
+///
+/// its *z* values, from 1260 to 1328.75, are exact in both `f32` and `f64`,
+/// and its rows, columns, and steps all differ, so transposed axes show up
+pub fn small_plane() -> Grid {
+    let lattice = Lattice {
+        xmin: 1000.0,
+        ymin: 2000.0,
+        xstep: 25.0,
+        ystep: 50.0,
+        rows: 3,
+        columns: 4,
+    };
+    Surface::Plane { z0: 10.0, dzdx: 0.25, dzdy: 0.5 }.rectangular(&lattice)
+}
+
+/// encode a grid as the bytes of a GRD file, e.g. to feed to
+/// [Grid::read] through a [std::io::Cursor]
+///
+/// panics if the grid can't be written (see [Grid::write])
+pub fn to_grd_bytes(grid: &Grid) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    // writing to memory can only fail if the grid is inconsistent
+    grid.write(&mut buf).expect("grid can't be written");
+    buf.into_inner()
+}
+
+/// write a grid to a GRD file at the given path
+pub fn write_grd_file<P: AsRef<Path>>(grid: &Grid, path: P)
+  -> Result<(), Error> {
+    let mut f = File::create(path)?;
+    grid.write(&mut f)
+}
+
+fn lattice_xy(lattice: &Lattice, row: usize, column: usize) -> (f64, f64) {
+    (lattice.xmin + column as f64 * lattice.xstep,
+     lattice.ymin + row as f64 * lattice.ystep)
+}
@@ -0,0 +1,114 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! time series of grids: the same surface at several times (e.g. pressure
+//! or production-attribute grids by vintage), for scripting 4D comparisons
+
+use ndarray::{ArcArray2, Array2};
+
+use crate::{Error, Grid, GridData, GridSet};
+
+impl GridSet {
+    /// the *z* value at (*x*, *y*) in each grid of the set, in order (see
+    /// [Grid::sample]); `None` where a grid has no value there
+    pub fn series_at(&self, x: f64, y: f64) -> Vec<Option<f64>> {
+        self.iter().map(|(_, grid)| grid.sample(x, y)).collect()
+    }
+
+    /// the *z* value at node (`row`, `column`) of each grid of the set, in
+    /// order; every grid must be rectangular and coregistered with the first
+    /// (to within `tolerance`, in *x*/*y* units)
+    pub fn node_series(&self, row: usize, column: usize, tolerance: f64)
+      -> Result<Vec<f64>, Error> {
+        let arrays = self.coregistered(tolerance)?;
+        if let Some(arr) = arrays.first() {
+            let (rows, columns) = arr.dim();
+            if row >= rows || column >= columns {
+                return Err(Error::InvalidArgument(format!(
+                  "node ({}, {}) outside {} x {} grid",
+                  row, column, rows, columns)));
+            }
+        }
+        Ok(arrays.iter().map(|arr| arr[(row, column)]).collect())
+    }
+
+    /// grids of the rate of change of *z* between each pair of successive
+    /// grids in the set, given the time of each grid (in any units, e.g.
+    /// years); the result holds one fewer grid than the set
+    ///
+    /// every grid must be rectangular and coregistered with the first (to
+    /// within `tolerance`, in *x*/*y* units), and times must not repeat
+    /// between successive grids; each rate grid takes its metadata from the
+    /// later grid of its pair, and is missing wherever either grid is
+    pub fn rates_of_change(&self, times: &[f64], tolerance: f64)
+      -> Result<Vec<Grid>, Error> {
+        if times.len() != self.len() {
+            return Err(Error::InvalidArgument(format!(
+              "{} times for {} grids", times.len(), self.len())));
+        }
+        let arrays = self.coregistered(tolerance)?;
+
+        let grids = self.iter().map(|(_, grid)| grid).collect::<Vec<_>>();
+        let mut rates = Vec::with_capacity(grids.len().saturating_sub(1));
+        for i in 1..grids.len() {
+            let dt = times[i] - times[i - 1];
+            if dt == 0.0 || !dt.is_finite() {
+                return Err(Error::InvalidArgument(format!(
+                  "invalid time step from {} to {}", times[i - 1], times[i])));
+            }
+            let arr: Array2<f64> = (arrays[i] - arrays[i - 1]) / dt;
+
+            let mut header = grids[i].header();
+            let data = GridData::Rectangular(arr.into_shared());
+            let stats = data.stats();
+            header.zmin = stats.min;
+            header.zmax = stats.max;
+            rates.push(Grid::from_parts(header, data));
+        }
+        Ok(rates)
+    }
+
+    // the data of each grid, if all are rectangular and coregistered with
+    // the first
+    fn coregistered(&self, tolerance: f64) -> Result<Vec<&ArcArray2<f64>>, Error> {
+        let mut grids = self.iter();
+        let first = match grids.next() {
+            Some((_, grid)) => grid,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut arrays = Vec::with_capacity(self.len());
+        for (path, grid) in self.iter() {
+            let arr = match &grid.data {
+                GridData::Rectangular(arr) => arr,
+                GridData::Triangular(_) => return Err(Error::NotRectangular),
+            };
+            let comparison = first.compare_geometry(grid);
+            if !comparison.is_coregistered(tolerance) {
+                return Err(Error::InvalidArgument(format!(
+                  "{} not coregistered with first grid: {}",
+                  path.display(), comparison)));
+            }
+            arrays.push(arr);
+        }
+        Ok(arrays)
+    }
+}
@@ -0,0 +1,237 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! vertex and edge statistics for triangular grids, to help work out whether
+//! Petra stores a connected triangulated network or a "soup" of independent
+//! triangles, and how it goes about triangulating
+
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::{Grid, GridData, export::mesh::Mesh};
+
+/// how the triangles of a triangular grid fit together once coincident
+/// vertices are welded; see [Grid::mesh_topology]
+///
+/// triangles with any missing vertex are left out, as in mesh exports
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MeshTopology {
+    /// the number of triangles with no missing vertices
+    pub triangles: usize,
+
+    /// the number of triangles with two or more coincident vertices
+    pub degenerate_triangles: usize,
+
+    /// the number of vertices as stored, i.e. three per triangle
+    pub stored_vertices: usize,
+
+    /// the number of distinct vertices after welding
+    pub unique_vertices: usize,
+
+    /// the largest number of triangles sharing a single vertex
+    pub max_vertex_valence: usize,
+
+    /// the number of distinct edges after welding
+    pub edges: usize,
+
+    /// the number of edges shared by exactly two triangles
+    pub shared_edges: usize,
+
+    /// the number of edges belonging to a single triangle
+    pub boundary_edges: usize,
+
+    /// the number of edges shared by three or more triangles, which can't
+    /// happen in a proper triangulation
+    pub non_manifold_edges: usize,
+}
+
+impl MeshTopology {
+    /// the mean number of stored copies of each distinct vertex (1 for a
+    /// soup of disjoint triangles, typically near 6 for a large TIN)
+    pub fn vertex_reuse(&self) -> f64 {
+        self.stored_vertices as f64 / self.unique_vertices as f64
+    }
+
+    /// the fraction of edges which are shared by two triangles
+    pub fn shared_edge_fraction(&self) -> f64 {
+        self.shared_edges as f64 / self.edges as f64
+    }
+
+    /// do the triangles form a connected-looking network (sharing vertices
+    /// and edges, with no degenerate triangles or non-manifold edges) rather
+    /// than a soup of independent triangles?
+    pub fn is_network(&self) -> bool {
+        self.shared_edges > 0
+          && self.degenerate_triangles == 0
+          && self.non_manifold_edges == 0
+    }
+}
+
+impl Grid {
+    /// vertex reuse and edge sharing statistics for a triangular grid, after
+    /// welding bit-identical vertices (`None` for rectangular grids)
+    pub fn mesh_topology(&self) -> Option<MeshTopology> {
+        if let GridData::Rectangular(_) = self.data {
+            return None;
+        }
+
+        let mesh = Mesh::from_grid(self);
+        let mut valence = vec![0; mesh.vertices.len()];
+        let mut degenerate_triangles = 0;
+        for &[a, b, c] in &mesh.faces {
+            if is_degenerate([a, b, c]) {
+                degenerate_triangles += 1;
+                continue;
+            }
+            for v in [a, b, c] {
+                valence[v] += 1;
+            }
+        }
+        let edges = edge_faces(&mesh);
+
+        let mut topology = MeshTopology {
+            triangles: mesh.faces.len(),
+            degenerate_triangles,
+            stored_vertices: mesh.faces.len() * 3,
+            unique_vertices: mesh.vertices.len(),
+            max_vertex_valence: valence.into_iter().max().unwrap_or(0),
+            edges: edges.len(),
+            shared_edges: 0,
+            boundary_edges: 0,
+            non_manifold_edges: 0,
+        };
+        for faces in edges.into_values() {
+            match faces.len() {
+                1 => topology.boundary_edges += 1,
+                2 => topology.shared_edges += 1,
+                _ => topology.non_manifold_edges += 1,
+            }
+        }
+        Some(topology)
+    }
+}
+
+/// the results of checking a triangular grid's triangulation against the
+/// Delaunay criterion; see [Grid::check_delaunay]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelaunayCheck {
+    /// the number of edges shared by exactly two triangles, i.e. those which
+    /// were checked
+    pub interior_edges: usize,
+
+    /// the pairs of triangles which violate the criterion, worst first
+    pub violations: Vec<DelaunayViolation>,
+}
+
+impl DelaunayCheck {
+    /// does the triangulation satisfy the criterion (to within the
+    /// tolerance checked)?
+    pub fn is_delaunay(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// a pair of adjacent triangles which aren't locally Delaunay: each has its
+/// third vertex inside the other's circumcircle
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DelaunayViolation {
+    /// the indices of the two triangles, as stored in the grid's data
+    pub triangles: (usize, usize),
+
+    /// the amount (in radians) by which the two angles opposite the shared
+    /// edge sum to more than π; infinite if the triangles overlap
+    pub excess: f64,
+}
+
+impl Grid {
+    /// check whether a triangular grid's triangulation (in *x* and *y*,
+    /// after welding bit-identical vertices) satisfies the Delaunay
+    /// criterion, allowing the angles opposite each shared edge to sum to as
+    /// much as π + `tolerance` radians (`None` for rectangular grids)
+    ///
+    /// a positive tolerance forgives nearly-cocircular vertices (as in
+    /// lattice-like data), which may be triangulated either way
+    pub fn check_delaunay(&self, tolerance: f64) -> Option<DelaunayCheck> {
+        if let GridData::Rectangular(_) = self.data {
+            return None;
+        }
+
+        let mesh = Mesh::from_grid(self);
+        let xy = |v: usize| (mesh.vertices[v][0], mesh.vertices[v][1]);
+        let mut interior_edges = 0;
+        let mut violations = Vec::new();
+        for ((p, q), faces) in edge_faces(&mesh) {
+            let [f, g] = faces[..] else { continue };
+            interior_edges += 1;
+
+            let opposite = |face: usize| mesh.faces[face].into_iter()
+              .find(|&v| v != p && v != q)
+              .expect("non-degenerate face");
+            let (a, b) = (xy(opposite(f)), xy(opposite(g)));
+            let (p, q) = (xy(p), xy(q));
+
+            let excess = if side(p, q, a) * side(p, q, b) >= 0.0 {
+                f64::INFINITY
+            } else {
+                angle(a, p, q) + angle(b, p, q) - PI
+            };
+            if excess > tolerance {
+                let (s, t) = (mesh.sources[f], mesh.sources[g]);
+                violations.push(DelaunayViolation {
+                    triangles: (s.min(t), s.max(t)),
+                    excess,
+                });
+            }
+        }
+
+        violations.sort_by(|v, w| w.excess.total_cmp(&v.excess)
+          .then(v.triangles.cmp(&w.triangles)));
+        Some(DelaunayCheck { interior_edges, violations })
+    }
+}
+
+fn is_degenerate([a, b, c]: [usize; 3]) -> bool {
+    a == b || b == c || c == a
+}
+
+// the (non-degenerate) faces using each edge of a mesh
+fn edge_faces(mesh: &Mesh) -> HashMap<(usize, usize), Vec<usize>> {
+    let mut edges: HashMap<_, Vec<usize>> = HashMap::new();
+    for (f, &[a, b, c]) in mesh.faces.iter().enumerate() {
+        if is_degenerate([a, b, c]) {
+            continue;
+        }
+        for (p, q) in [(a, b), (b, c), (c, a)] {
+            edges.entry((p.min(q), p.max(q))).or_default().push(f);
+        }
+    }
+    edges
+}
+
+// which side of the line p → q is r on? (positive for left, zero for on)
+fn side(p: (f64, f64), q: (f64, f64), r: (f64, f64)) -> f64 {
+    (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+}
+
+// the angle p–a–q, in radians
+fn angle(a: (f64, f64), p: (f64, f64), q: (f64, f64)) -> f64 {
+    let (u, v) = ((p.0 - a.0, p.1 - a.1), (q.0 - a.0, q.1 - a.1));
+    (u.0 * v.1 - u.1 * v.0).abs().atan2(u.0 * v.0 + u.1 * v.1)
+}
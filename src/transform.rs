@@ -0,0 +1,212 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! general coordinate transformations of grids
+
+use std::convert::Infallible;
+
+use ndarray::{Array2, Array3};
+
+use crate::{Grid, GridData, GridHeader};
+
+// the most positions passed to a batched transformation at once
+const BATCH_SIZE: usize = 1 << 20;
+
+// mapped positions, or None for positions which can't be mapped
+type Mapped = Vec<Option<(f64, f64)>>;
+
+impl Grid {
+    /// transform a grid's (*x*, *y*) coordinates, e.g. to shift datums or
+    /// reproject, given functions mapping coordinates from this grid's
+    /// system to the new one (`forward`) and back (`inverse`); either may
+    /// return `None` for positions it can't map
+    ///
+    /// triangular grids have each vertex mapped with `forward` (triangles
+    /// with unmappable vertices become missing); rectangular grids keep the
+    /// same lattice, placed in the new system over the extents of its mapped
+    /// boundary nodes, with each node resampled from the original grid at
+    /// its `inverse`-mapped position, so that the lattice stays regular (if
+    /// no boundary node can be mapped, every node is missing)
+    pub fn transform_xy<F, I>(&self, forward: F, inverse: I) -> Grid
+      where F: Fn(f64, f64) -> Option<(f64, f64)>,
+            I: Fn(f64, f64) -> Option<(f64, f64)>,
+    {
+        let pointwise = |f: &dyn Fn(f64, f64) -> Option<(f64, f64)>,
+          points: &[(f64, f64)]| points.iter()
+            .map(|&(x, y)| f(x, y))
+            .collect::<Mapped>();
+        let result: Result<Grid, Infallible> = self.transform_xy_batched(
+          |points| Ok(pointwise(&forward, points)),
+          |points| Ok(pointwise(&inverse, points)));
+        match result {
+            Ok(grid) => grid,
+            Err(never) => match never { },
+        }
+    }
+
+    /// transform a grid's (*x*, *y*) coordinates as [Grid::transform_xy]
+    /// does, but mapping many positions at once, for transformations which
+    /// are costly to invoke (e.g. an external program like PROJ's `cs2cs`)
+    ///
+    /// each function is given a slice of positions, and returns their
+    /// mapped positions in the same order (with `None` for any it can't
+    /// map), or an error, which is returned as-is
+    pub fn transform_xy_batched<F, I, E>(&self, mut forward: F,
+      mut inverse: I) -> Result<Grid, E>
+      where F: FnMut(&[(f64, f64)]) -> Result<Mapped, E>,
+            I: FnMut(&[(f64, f64)]) -> Result<Mapped, E>,
+    {
+        let mut header = self.header();
+        let data = match &self.data {
+            GridData::Triangular(arr) => {
+                let vertices = arr.outer_iter()
+                  .flat_map(|tri| (0..3)
+                    .map(move |v| (tri[(v, 0)], tri[(v, 1)])))
+                  .collect::<Vec<_>>();
+                let mut mapped = Vec::with_capacity(vertices.len());
+                for batch in vertices.chunks(BATCH_SIZE) {
+                    mapped.extend(padded(forward(batch)?, batch.len()));
+                }
+
+                let mut out = Array3::from_elem(arr.dim(), f64::NAN);
+                for (t, tri) in arr.outer_iter().enumerate() {
+                    let vertices = &mapped[3 * t..3 * t + 3];
+                    if vertices.iter().all(Option::is_some) {
+                        for (v, &(x, y)) in vertices.iter().flatten()
+                          .enumerate() {
+                            out[(t, v, 0)] = x;
+                            out[(t, v, 1)] = y;
+                            out[(t, v, 2)] = tri[(v, 2)];
+                        }
+                    }
+                }
+                let xs = out.outer_iter().flat_map(|t| t.column(0).to_vec());
+                let (xmin, xmax) = min_max(xs);
+                let ys = out.outer_iter().flat_map(|t| t.column(1).to_vec());
+                let (ymin, ymax) = min_max(ys);
+                header.xmin = xmin;
+                header.xmax = xmax;
+                header.ymin = ymin;
+                header.ymax = ymax;
+                GridData::Triangular(out.into_shared())
+            },
+
+            GridData::Rectangular(arr) => {
+                let (rows, cols) = arr.dim();
+                /* a lattice's footprint may rotate or bend in the new
+                 * system, so its extents are those of its whole boundary,
+                 * not just two opposite corners */
+                let boundary = boundary_nodes(rows, cols).into_iter()
+                  .map(|(j, i)| (self.xmin + i as f64 * self.xstep,
+                    self.ymin + j as f64 * self.ystep))
+                  .collect::<Vec<_>>();
+                let mut mapped = Vec::with_capacity(boundary.len());
+                for batch in boundary.chunks(BATCH_SIZE) {
+                    mapped.extend(padded(forward(batch)?, batch.len()));
+                }
+                let (xmin, xmax) = min_max(mapped.iter().flatten()
+                  .map(|&(x, _)| x));
+                let (ymin, ymax) = min_max(mapped.iter().flatten()
+                  .map(|&(_, y)| y));
+
+                /* if no node on the boundary can be mapped, we can't place
+                 * the lattice in the new system at all, and resampling it
+                 * where it was would put the data in the wrong place, so
+                 * every node is left missing */
+                let mut out = Array2::from_elem((rows, cols), f64::NAN);
+                if !xmin.is_nan() && !ymin.is_nan() {
+                    header.xmin = xmin;
+                    header.ymin = ymin;
+                    header.xmax = xmax;
+                    header.ymax = ymax;
+                    if cols > 1 {
+                        header.xstep = (xmax - xmin) / (cols - 1) as f64;
+                    }
+                    if rows > 1 {
+                        header.ystep = (ymax - ymin) / (rows - 1) as f64;
+                    }
+                    resample(self, &header, &mut out, &mut inverse)?;
+                }
+                GridData::Rectangular(out.into_shared())
+            },
+        };
+
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        Ok(Grid::from_parts(header, data))
+    }
+}
+
+/* fill a lattice, laid out by a header, with a grid's values at its nodes'
+ * inverse-mapped positions */
+fn resample<I, E>(grid: &Grid, header: &GridHeader, out: &mut Array2<f64>,
+  inverse: &mut I) -> Result<(), E>
+  where I: FnMut(&[(f64, f64)]) -> Result<Mapped, E>,
+{
+    let (rows, cols) = out.dim();
+    let rows_per_batch = (BATCH_SIZE / cols.max(1)).max(1);
+    for first in (0..rows).step_by(rows_per_batch) {
+        let last = (first + rows_per_batch).min(rows);
+        let nodes = (first..last)
+          .flat_map(|j| (0..cols).map(move |i| (j, i)))
+          .map(|(j, i)| (header.xmin + i as f64 * header.xstep,
+            header.ymin + j as f64 * header.ystep))
+          .collect::<Vec<_>>();
+        let mapped = padded(inverse(&nodes)?, nodes.len());
+        for (k, position) in mapped.into_iter().enumerate() {
+            if let Some(z) = position.and_then(|(x, y)| grid.sample(x, y)) {
+                out[(first + k / cols, k % cols)] = z;
+            }
+        }
+    }
+    Ok(())
+}
+
+// a batch's mapped positions, with any it didn't return treated as
+// unmappable
+fn padded(mut mapped: Mapped, len: usize) -> Mapped {
+    mapped.resize(len, None);
+    mapped
+}
+
+// the (row, column) indices of the nodes around the edge of a lattice
+fn boundary_nodes(rows: usize, cols: usize) -> Vec<(usize, usize)> {
+    if rows == 0 || cols == 0 {
+        return Vec::new();
+    }
+    let mut nodes = (0..cols).map(|i| (0, i)).collect::<Vec<_>>();
+    if rows > 1 {
+        nodes.extend((0..cols).map(|i| (rows - 1, i)));
+    }
+    for j in 1..rows.saturating_sub(1) {
+        nodes.push((j, 0));
+        if cols > 1 {
+            nodes.push((j, cols - 1));
+        }
+    }
+    nodes
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.filter(|v| !v.is_nan())
+      .fold((f64::NAN, f64::NAN), |(lo, hi), v| (v.min(lo), v.max(hi)))
+}
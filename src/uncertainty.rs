@@ -0,0 +1,146 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! grids paired with their uncertainty, such as kriging estimates with their
+//! kriging variance, and the resulting ranges of volumetric estimates
+
+use ndarray::{ArcArray2, Array2};
+
+use crate::{Error, Grid, GridData};
+
+/* the standard normal quantile at 0.9; P10 and P90 lie this many standard
+ * deviations above and below the mean */
+const Z_90: f64 = 1.2815515655446004;
+
+/// a rectangular grid paired with a coregistered grid of the variance of
+/// each node's *z* value (in squared *z* units)
+#[derive(Clone, Debug)]
+pub struct GridWithUncertainty {
+    grid: Grid,
+    variance: Grid,
+}
+
+impl GridWithUncertainty {
+    /// pair a grid with its variance grid; both must be rectangular, and
+    /// coregistered to within `tolerance` (in *x*/*y* units)
+    pub fn new(grid: Grid, variance: Grid, tolerance: f64)
+      -> Result<GridWithUncertainty, Error> {
+        grid.rectangular()?;
+        variance.rectangular()?;
+        let comparison = grid.compare_geometry(&variance);
+        if !comparison.is_coregistered(tolerance) {
+            return Err(Error::InvalidArgument(format!(
+              "variance grid not coregistered with grid: {}", comparison)));
+        }
+        Ok(GridWithUncertainty { grid, variance })
+    }
+
+    /// the grid of estimates
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// the grid of variances
+    pub fn variance(&self) -> &Grid {
+        &self.variance
+    }
+
+    /// split back into the grid and its variance grid
+    pub fn into_parts(self) -> (Grid, Grid) {
+        (self.grid, self.variance)
+    }
+
+    /// the standard deviation of each node's *z* value (missing wherever the
+    /// variance is missing or negative)
+    pub fn std_dev(&self) -> Array2<f64> {
+        self.variances().mapv(|v| if v >= 0.0 { v.sqrt() } else { f64::NAN })
+    }
+
+    /// the gross volume between the grid's surface and a flat `base`, where
+    /// the surface lies above it, with its uncertainty; see [VolumeEstimate]
+    ///
+    /// each node stands for one cell (`xstep` × `ystep`) centered on it;
+    /// missing nodes contribute nothing, and nodes with missing variance
+    /// contribute no uncertainty
+    pub fn volume_above(&self, base: f64) -> VolumeEstimate {
+        let cell = self.grid.xstep * self.grid.ystep;
+        let mut mean = 0.0;
+        let mut std_dev = 0.0;
+        for (&z, &sd) in self.estimates().iter().zip(self.std_dev().iter()) {
+            if z.is_nan() || z <= base {
+                continue;
+            }
+            mean += (z - base) * cell;
+            if !sd.is_nan() {
+                std_dev += sd * cell;
+            }
+        }
+        VolumeEstimate { mean, std_dev }
+    }
+
+    fn estimates(&self) -> &ArcArray2<f64> {
+        match &self.grid.data {
+            GridData::Rectangular(arr) => arr,
+            GridData::Triangular(_) => unreachable!("checked rectangular"),
+        }
+    }
+
+    fn variances(&self) -> &ArcArray2<f64> {
+        match &self.variance.data {
+            GridData::Rectangular(arr) => arr,
+            GridData::Triangular(_) => unreachable!("checked rectangular"),
+        }
+    }
+}
+
+/// a volume, in *x*/*y* units squared times *z* units, with its
+/// uncertainty
+///
+/// node errors are treated as perfectly correlated (so that standard
+/// deviations, rather than variances, add), which gives the widest (most
+/// conservative) range; the volume is taken to be normally distributed.
+/// percentiles follow the petroleum convention, where P90 is the low case
+/// (exceeded with 90% probability) and P10 the high case
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VolumeEstimate {
+    /// the expected volume
+    pub mean: f64,
+
+    /// the standard deviation of the volume
+    pub std_dev: f64,
+}
+
+impl VolumeEstimate {
+    /// the high case, exceeded with 10% probability
+    pub fn p10(&self) -> f64 {
+        self.mean + Z_90 * self.std_dev
+    }
+
+    /// the median case
+    pub fn p50(&self) -> f64 {
+        self.mean
+    }
+
+    /// the low case, exceeded with 90% probability
+    pub fn p90(&self) -> f64 {
+        self.mean - Z_90 * self.std_dev
+    }
+}
@@ -0,0 +1,120 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! vertical reference datums for *z* values
+
+use std::fmt;
+
+use crate::{Error, Grid, GridData};
+
+/// the vertical reference of a grid's *z* values
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerticalDatum {
+    /// not known
+    #[default]
+    Unknown,
+
+    /// elevation above mean sea level (positive up)
+    MeanSeaLevel,
+
+    /// depth below mean sea level (positive down; i.e. negated elevation)
+    Subsea,
+
+    /// depth below a local datum, such as a kelly bushing, at the given
+    /// elevation above mean sea level (positive down), in the grid's *z*
+    /// units
+    Local(f64),
+}
+
+impl VerticalDatum {
+    /// convert a *z* value in this datum to elevation above mean sea level;
+    /// `None` for [VerticalDatum::Unknown]
+    pub fn to_elevation(self, z: f64) -> Option<f64> {
+        match self {
+            VerticalDatum::Unknown => None,
+            VerticalDatum::MeanSeaLevel => Some(z),
+            VerticalDatum::Subsea => Some(-z),
+            VerticalDatum::Local(datum) => Some(datum - z),
+        }
+    }
+
+    /// convert an elevation above mean sea level to a *z* value in this
+    /// datum; `None` for [VerticalDatum::Unknown]
+    pub fn from_elevation(self, elevation: f64) -> Option<f64> {
+        match self {
+            VerticalDatum::Unknown => None,
+            VerticalDatum::MeanSeaLevel => Some(elevation),
+            VerticalDatum::Subsea => Some(-elevation),
+            VerticalDatum::Local(datum) => Some(datum - elevation),
+        }
+    }
+}
+
+impl fmt::Display for VerticalDatum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerticalDatum::Unknown => write!(f, "unknown"),
+            VerticalDatum::MeanSeaLevel => write!(f, "mean sea level"),
+            VerticalDatum::Subsea => write!(f, "subsea"),
+            VerticalDatum::Local(datum) =>
+                write!(f, "local datum at {} above mean sea level", datum),
+        }
+    }
+}
+
+impl Grid {
+    /// convert the grid's *z* values from its current vertical datum to
+    /// another, e.g. to reconcile structure grids mapped subsea with grids
+    /// mapped as depths below a kelly bushing
+    ///
+    /// fails with [Error::UnknownVerticalDatum] if either datum is unknown;
+    /// set [Grid::vertical_datum] first for grids read from GRD files
+    pub fn shift_vertical_datum(&self, to: VerticalDatum)
+      -> Result<Grid, Error> {
+        let from = self.vertical_datum;
+        if from == VerticalDatum::Unknown || to == VerticalDatum::Unknown {
+            return Err(Error::UnknownVerticalDatum);
+        }
+        let convert = |z: f64| {
+            from.to_elevation(z)
+              .and_then(|e| to.from_elevation(e))
+              .unwrap_or(f64::NAN)
+        };
+
+        let mut data = self.data.clone();
+        match &mut data {
+            GridData::Rectangular(arr) => arr.mapv_inplace(convert),
+            GridData::Triangular(arr) => {
+                for mut tri in arr.outer_iter_mut() {
+                    tri.column_mut(2).mapv_inplace(convert);
+                }
+            },
+        }
+
+        let mut header = self.header();
+        let stats = data.stats();
+        header.zmin = stats.min;
+        header.zmax = stats.max;
+        header.vertical_datum = to;
+        Ok(Grid::from_parts(header, data))
+    }
+}
@@ -0,0 +1,207 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! JavaScript bindings, via `wasm-bindgen`, for reading and rendering grids
+//! in the browser
+//!
+//! see `examples/wasm-viewer` for a small drag-and-drop viewer built on these
+
+use std::io::Cursor;
+
+use ndarray::Array2;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Colormap, Grid, GridData};
+
+const HILLSHADE_AZIMUTH: f64 = 315.0;
+const HILLSHADE_ALTITUDE: f64 = 45.0;
+
+/// a grid read from an in-memory GRD file
+#[wasm_bindgen]
+pub struct WasmGrid {
+    grid: Grid,
+    raster: Array2<f64>,
+    shade: Option<Array2<f64>>,
+}
+
+#[wasm_bindgen]
+impl WasmGrid {
+    /// read a grid from the bytes of a GRD file
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmGrid, JsError> {
+        let grid = Grid::read(&mut Cursor::new(bytes))?;
+        let rasterized = grid.rasterize();
+        let shade = rasterized.hillshade(HILLSHADE_AZIMUTH, HILLSHADE_ALTITUDE)
+          .ok();
+        let raster = match rasterized.data {
+            GridData::Rectangular(arr) => arr.into_owned(),
+            GridData::Triangular(_) => unreachable!("rasterized grid"),
+        };
+        Ok(WasmGrid { grid, raster, shade })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.grid.name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn triangular(&self) -> bool {
+        matches!(self.grid.data, GridData::Triangular(_))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rows(&self) -> u32 {
+        self.grid.rows
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn columns(&self) -> u32 {
+        self.grid.columns
+    }
+
+    #[wasm_bindgen(getter, js_name = nTriangles)]
+    pub fn n_triangles(&self) -> u32 {
+        self.grid.n_triangles
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn xmin(&self) -> f64 {
+        self.grid.xmin
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn xmax(&self) -> f64 {
+        self.grid.xmax
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ymin(&self) -> f64 {
+        self.grid.ymin
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ymax(&self) -> f64 {
+        self.grid.ymax
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn xstep(&self) -> f64 {
+        self.grid.xstep
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ystep(&self) -> f64 {
+        self.grid.ystep
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn zmin(&self) -> f64 {
+        self.grid.zmin
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn zmax(&self) -> f64 {
+        self.grid.zmax
+    }
+
+    #[wasm_bindgen(getter, js_name = xyUnits)]
+    pub fn xyunits(&self) -> String {
+        format!("{:?}", self.grid.xyunits)
+    }
+
+    #[wasm_bindgen(getter, js_name = zUnits)]
+    pub fn zunits(&self) -> String {
+        format!("{:?}", self.grid.zunits)
+    }
+
+    #[wasm_bindgen(getter, js_name = createdDate)]
+    pub fn created_date(&self) -> String {
+        self.grid.created_date.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = sourceData)]
+    pub fn source_data(&self) -> String {
+        self.grid.source_data.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn projection(&self) -> String {
+        self.grid.projection.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn datum(&self) -> String {
+        self.grid.datum.clone()
+    }
+
+    /// the width, in pixels, of images from [WasmGrid::render]
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.raster.dim().1 as u32
+    }
+
+    /// the height, in pixels, of images from [WasmGrid::render]
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.raster.dim().0 as u32
+    }
+
+    /// render the grid as north-up RGBA pixels (suitable for an `ImageData`)
+    /// using the named colormap ("greyscale", "terrain", or "viridis"),
+    /// optionally hillshaded; missing values are transparent
+    pub fn render(&self, colormap: &str, hillshade: bool) -> Vec<u8> {
+        let colormap = match colormap {
+            "greyscale" => Colormap::greyscale(),
+            "terrain" => Colormap::terrain(),
+            _ => Colormap::viridis(),
+        };
+        let (rows, cols) = self.raster.dim();
+        let mut pixels = Vec::with_capacity(rows * cols * 4);
+        for j in (0..rows).rev() {
+            for i in 0..cols {
+                let z = self.raster[(j, i)];
+                if z.is_nan() {
+                    pixels.extend_from_slice(&[0, 0, 0, 0]);
+                    continue;
+                }
+                let mut rgb = colormap.color_for(z, self.grid.zmin,
+                  self.grid.zmax);
+                if let (true, Some(shade)) = (hillshade, &self.shade) {
+                    let s = shade[(j, i)];
+                    if !s.is_nan() {
+                        for c in &mut rgb {
+                            *c = (*c as f64 * (0.35 + 0.65 * s)) as u8;
+                        }
+                    }
+                }
+                pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        }
+        pixels
+    }
+
+    /// the *z* value at (*x*, *y*), or `undefined` outside the grid
+    pub fn sample(&self, x: f64, y: f64) -> Option<f64> {
+        self.grid.sample(x, y)
+    }
+}
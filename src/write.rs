@@ -0,0 +1,208 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! encoding grids in the Petra GRD format
+//!
+//! we only write the fields we know how to read; everything else in the
+//! header is left zeroed, which Petra itself may or may not appreciate
+
+use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use time::PrimitiveDateTime;
+
+use crate::{
+    CM_RLAT_OFFSET,
+    DATE_OFFSET,
+    DATUM_LEN,
+    DELPHI_DATETIME_ORIGIN,
+    Error,
+    GRID_OFFSET,
+    Grid,
+    GridData,
+    NAME_LEN,
+    N_TRIANGLES_OFFSET,
+    PETRA_NULL,
+    PROJ_LEN,
+    ROWS_COLS_OFFSET,
+    SOURCE_LEN,
+    SOURCE_OFFSET,
+    UNK_LEN,
+    UNK_PROJ_DATUM_OFFSET,
+    ZUNITS_OFFSET,
+    invariants,
+};
+
+impl Grid {
+    /// write this grid, in the Petra GRD format, to a destination (e.g. a
+    /// file), so that it can be read back with [Grid::read] or loaded into
+    /// Petra
+    ///
+    /// header fields we don't understand are written as zeros, apart from
+    /// [Grid::unknown_metadata]; the data must agree with the header's size
+    /// and triangle count, and the extents with the steps (fixing these up
+    /// is left to the caller), or the corresponding read error is returned
+    /// and nothing is written
+    pub fn write<W: Write + ?Sized>(&self, out: &mut W) -> Result<(), Error> {
+        write_grd(self, out)
+    }
+}
+
+// encode a grid as Petra GRD bytes
+pub(crate) fn write_grd<W: Write + ?Sized>(grid: &Grid, out: &mut W)
+  -> Result<(), Error> {
+    check_shape(grid)?;
+
+    let header = header_bytes(grid)?;
+
+    let mut out = BufWriter::new(out);
+    out.write_all(&header)?;
+    match &grid.data {
+        GridData::Rectangular(arr) => {
+            for &z in arr.iter() {
+                out.write_f64::<LittleEndian>(nan_to_petra_null(z))?;
+            }
+        },
+        GridData::Triangular(arr) => {
+            // triangles are stored as x1 x2 x3 y1 y2 y3 z1 z2 z3
+            for tri in arr.outer_iter() {
+                for component in 0..3 {
+                    for vertex in 0..3 {
+                        out.write_f64::<LittleEndian>(
+                          nan_to_petra_null(tri[(vertex, component)]))?;
+                    }
+                }
+            }
+        },
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// the header of a grid, as Petra GRD bytes (up to the start of the data)
+pub(crate) fn header_bytes(grid: &Grid) -> Result<Vec<u8>, Error> {
+    let mut header = Cursor::new(vec![0u8; GRID_OFFSET as usize]);
+    write_header(grid, &mut header)?;
+    Ok(header.into_inner())
+}
+
+/* refuse to write files which we (and presumably Petra) couldn't read back,
+ * reporting the same errors the reader would */
+fn check_shape(grid: &Grid) -> Result<(), Error> {
+    if grid.rows as u64 * grid.columns as u64 != grid.size as u64 {
+        return Err(Error::SizeMismatch(grid.size, grid.rows, grid.columns));
+    }
+
+    if !invariants::spec_coherent(grid.xmin, grid.xmax, grid.xstep,
+      grid.columns) {
+        return Err(Error::InvalidXSpec(grid.xmin, grid.xmax, grid.xstep,
+          grid.columns));
+    }
+
+    if !invariants::spec_coherent(grid.ymin, grid.ymax, grid.ystep,
+      grid.rows) {
+        return Err(Error::InvalidYSpec(grid.ymin, grid.ymax, grid.ystep,
+          grid.rows));
+    }
+
+    match &grid.data {
+        GridData::Rectangular(arr) => {
+            let shape = (grid.rows as usize, grid.columns as usize);
+            if grid.n_triangles != 0 || arr.dim() != shape {
+                return Err(Error::InvalidRectangularSize(grid.size,
+                  arr.len() as u64 * 8));
+            }
+        },
+        GridData::Triangular(arr) => {
+            let n = arr.dim().0;
+            if grid.n_triangles == 0 || n != grid.n_triangles as usize {
+                return Err(Error::InvalidTriangleCount(grid.n_triangles,
+                  n as u64 * 72));
+            }
+        },
+    }
+    Ok(())
+}
+
+fn write_header(grid: &Grid, dest: &mut Cursor<Vec<u8>>)
+  -> Result<(), Error> {
+    dest.write_u32::<LittleEndian>(grid.version)?;
+    write_petra_string::<NAME_LEN>(dest, &grid.name)?;
+    dest.write_u32::<LittleEndian>(grid.size)?;
+    for v in [grid.xmin, grid.xmax, grid.ymin, grid.ymax, grid.xstep,
+      grid.ystep, grid.zmin, grid.zmax] {
+        dest.write_f64::<LittleEndian>(nan_to_petra_null(v))?;
+    }
+
+    dest.seek(SeekFrom::Start(CM_RLAT_OFFSET))?;
+    dest.write_f64::<LittleEndian>(grid.cm)?;
+    dest.write_f64::<LittleEndian>(grid.rlat)?;
+
+    dest.seek(SeekFrom::Start(DATE_OFFSET))?;
+    dest.write_f64::<LittleEndian>(delphi_datetime(grid.created_date))?;
+
+    dest.seek(SeekFrom::Start(ROWS_COLS_OFFSET))?;
+    dest.write_u32::<LittleEndian>(grid.rows)?;
+    dest.write_u32::<LittleEndian>(grid.columns)?;
+    dest.write_u32::<LittleEndian>(grid.grid_method)?;
+    dest.write_u32::<LittleEndian>(grid.projection_code)?;
+    dest.write_u32::<LittleEndian>(grid.xyunits.code())?;
+
+    dest.seek(SeekFrom::Start(ZUNITS_OFFSET))?;
+    dest.write_u32::<LittleEndian>(grid.zunits.code())?;
+
+    dest.seek(SeekFrom::Start(N_TRIANGLES_OFFSET))?;
+    dest.write_u32::<LittleEndian>(grid.n_triangles)?;
+
+    dest.seek(SeekFrom::Start(SOURCE_OFFSET))?;
+    write_petra_string::<SOURCE_LEN>(dest, &grid.source_data)?;
+
+    dest.seek(SeekFrom::Start(UNK_PROJ_DATUM_OFFSET))?;
+    write_petra_string::<UNK_LEN>(dest, &grid.unknown_metadata)?;
+    write_petra_string::<PROJ_LEN>(dest, &grid.projection)?;
+    write_petra_string::<DATUM_LEN>(dest, &grid.datum)?;
+
+    Ok(())
+}
+
+/* write a "fixed width null-terminated" string, truncated if need be to leave
+ * room for at least one NUL */
+fn write_petra_string<const WIDTH: usize>(dest: &mut Cursor<Vec<u8>>,
+  s: &str) -> Result<(), io::Error> {
+    let mut buf = [0u8; WIDTH];
+    let bytes = s.as_bytes();
+    let len = bytes.len().min(WIDTH - 1);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    dest.write_all(&buf)
+}
+
+fn delphi_datetime(dt: PrimitiveDateTime) -> f64 {
+    (dt - DELPHI_DATETIME_ORIGIN).as_seconds_f64() / 86_400.0
+}
+
+pub(crate) fn nan_to_petra_null(v: f64) -> f64 {
+    if v.is_nan() {
+        PETRA_NULL
+    } else {
+        v
+    }
+}
@@ -0,0 +1,238 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! summaries of grids over polygons, such as the sections of a land grid;
+//! see [zonal_stats] and [Grid::polygon_volumes]
+//!
+//! each node stands for one cell (`xstep` × `ystep`) centered on it, and
+//! counts toward a polygon if the polygon contains the node (nodes on an
+//! edge shared by neighboring polygons count toward only one of them);
+//! missing nodes are ignored. triangular grids are rasterized first (see
+//! [Grid::rasterize])
+
+use std::{
+    borrow::Cow,
+    io::{BufWriter, Write},
+};
+
+use crate::{Error, Grid, GridData, import::polygons::Polygon};
+
+/// statistics of the grid nodes within one polygon, in the grid's units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ZonalStats {
+    /// the number of non-missing nodes within the polygon
+    pub node_count: usize,
+
+    /// the minimum *z* value (NaN if there are no nodes)
+    pub min: f64,
+
+    /// the maximum *z* value (NaN if there are no nodes)
+    pub max: f64,
+
+    /// the mean *z* value (NaN if there are no nodes)
+    pub mean: f64,
+
+    /// the sum of the *z* values
+    pub sum: f64,
+
+    /// the area of the cells of the nodes
+    pub area: f64,
+}
+
+/// a summary of the grid nodes within one polygon, with the volume above a
+/// base, in the grid's units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PolygonVolume {
+    /// the number of non-missing nodes within the polygon
+    pub node_count: usize,
+
+    /// the area of the cells of those nodes
+    pub area: f64,
+
+    /// the minimum *z* value (NaN if there are no nodes)
+    pub min: f64,
+
+    /// the maximum *z* value (NaN if there are no nodes)
+    pub max: f64,
+
+    /// the mean *z* value (NaN if there are no nodes)
+    pub mean: f64,
+
+    /// the volume between the grid's surface and the base, where the surface
+    /// lies above it
+    pub volume: f64,
+}
+
+/// compute statistics of the grid within each of a series of polygons (e.g.
+/// from [crate::import::polygons::read_polygons_file])
+pub fn zonal_stats(grid: &Grid, polygons: &[Polygon]) -> Vec<ZonalStats> {
+    let (grid, cell) = raster(grid);
+    polygons.iter().map(|polygon| {
+        let mut stats = ZonalStats {
+            node_count: 0,
+            min: f64::NAN,
+            max: f64::NAN,
+            mean: f64::NAN,
+            sum: 0.0,
+            area: 0.0,
+        };
+        for_each_node(&grid, polygon, |z| {
+            stats.node_count += 1;
+            stats.min = stats.min.min(z);
+            stats.max = stats.max.max(z);
+            stats.sum += z;
+        });
+        stats.area = stats.node_count as f64 * cell;
+        if stats.node_count > 0 {
+            stats.mean = stats.sum / stats.node_count as f64;
+        }
+        stats
+    }).collect()
+}
+
+impl Grid {
+    /// summarize the grid, and its gross volume above a flat `base`, within
+    /// each of a series of polygons; see [zonal_stats]
+    pub fn polygon_volumes(&self, polygons: &[Polygon], base: f64)
+      -> Vec<PolygonVolume> {
+        let (grid, cell) = raster(self);
+        zonal_stats(&grid, polygons).into_iter().zip(polygons)
+          .map(|(stats, polygon)| {
+              let mut volume = 0.0;
+              for_each_node(&grid, polygon, |z| if z > base {
+                  volume += (z - base) * cell;
+              });
+              PolygonVolume {
+                  node_count: stats.node_count,
+                  area: stats.area,
+                  min: stats.min,
+                  max: stats.max,
+                  mean: stats.mean,
+                  volume,
+              }
+          })
+          .collect()
+    }
+}
+
+/// write zonal statistics as a CSV attribute table: one row per polygon,
+/// with its attributes (every attribute name seen, in order of first
+/// appearance) followed by the statistics columns
+pub fn write_zonal_csv<W: Write + ?Sized>(polygons: &[Polygon],
+  stats: &[ZonalStats], out: &mut W) -> Result<(), Error> {
+    write_table(polygons, "node_count,min,max,mean,sum,area",
+      stats.iter().map(|s| format!("{},{},{},{},{},{}", s.node_count,
+        s.min, s.max, s.mean, s.sum, s.area)),
+      out)
+}
+
+/// write polygon summaries as a CSV attribute table, like
+/// [write_zonal_csv]
+pub fn write_volume_csv<W: Write + ?Sized>(polygons: &[Polygon],
+  volumes: &[PolygonVolume], out: &mut W) -> Result<(), Error> {
+    write_table(polygons, "node_count,area,min,max,mean,volume",
+      volumes.iter().map(|v| format!("{},{},{},{},{},{}", v.node_count,
+        v.area, v.min, v.max, v.mean, v.volume)),
+      out)
+}
+
+// the grid (rasterized if need be), and the area of one of its cells
+fn raster(grid: &Grid) -> (Cow<'_, Grid>, f64) {
+    let cell = grid.xstep * grid.ystep;
+    match grid.data {
+        GridData::Rectangular(_) => (Cow::Borrowed(grid), cell),
+        GridData::Triangular(_) => {
+            let raster = grid.rasterize();
+            let cell = raster.xstep * raster.ystep;
+            (Cow::Owned(raster), cell)
+        },
+    }
+}
+
+// visit the z value of each non-missing node of a rectangular grid within a
+// polygon
+fn for_each_node<F: FnMut(f64)>(grid: &Grid, polygon: &Polygon, mut f: F) {
+    let arr = grid.rectangular().expect("rasterized grid");
+    let (rows, cols) = arr.dim();
+
+    // the range of node indices along an axis within [lo, hi]
+    let span = |lo: f64, hi: f64, min: f64, step: f64, n: usize| {
+        let first = ((lo - min) / step).ceil().max(0.0) as usize;
+        let last = ((hi - min) / step).floor();
+        if last < 0.0 {
+            0..0
+        } else {
+            first..((last as usize) + 1).min(n)
+        }
+    };
+
+    let ((x_lo, y_lo), (x_hi, y_hi)) = polygon.bounds();
+    for j in span(y_lo, y_hi, grid.ymin, grid.ystep, rows) {
+        let y = grid.ymin + j as f64 * grid.ystep;
+        for i in span(x_lo, x_hi, grid.xmin, grid.xstep, cols) {
+            let z = arr[(j, i)];
+            let x = grid.xmin + i as f64 * grid.xstep;
+            if !z.is_nan() && polygon.contains(x, y) {
+                f(z);
+            }
+        }
+    }
+}
+
+// write an attribute table, with the given (pre-formatted) summary columns
+fn write_table<W, I>(polygons: &[Polygon], header: &str, rows: I,
+  out: &mut W) -> Result<(), Error>
+  where W: Write + ?Sized,
+        I: Iterator<Item = String>,
+{
+    let mut names: Vec<&str> = Vec::new();
+    for polygon in polygons {
+        for (name, _) in &polygon.attributes {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+
+    let mut out = BufWriter::new(out);
+    for name in &names {
+        write!(out, "{},", csv_field(name))?;
+    }
+    writeln!(out, "{}", header)?;
+    for (polygon, row) in polygons.iter().zip(rows) {
+        for name in &names {
+            write!(out, "{},",
+              csv_field(polygon.attribute(name).unwrap_or("")))?;
+        }
+        writeln!(out, "{}", row)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// quote a CSV field if it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,117 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! grid algebra, including grids with missing nodes, and the quantities
+//! derived grids measure
+
+use petra_grid::{
+    Grid,
+    GridData,
+    NullArithmetic,
+    NullPolicy,
+    Quantity,
+    testing::{Lattice, Surface},
+};
+
+fn flat(z0: f64) -> Grid {
+    Surface::Plane { z0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&Lattice::default())
+}
+
+// a flat grid, missing the given node
+fn flat_missing(z0: f64, node: (usize, usize)) -> Grid {
+    let mut grid = flat(z0);
+    if let GridData::Rectangular(arr) = &mut grid.data {
+        arr[node] = f64::NAN;
+    }
+    grid
+}
+
+fn z(grid: &Grid, node: (usize, usize)) -> f64 {
+    grid.rectangular().expect("rectangular grid")[node]
+}
+
+#[test]
+fn missing_nodes_stay_missing() {
+    let top = flat_missing(5000.0, (0, 0));
+    let base = flat_missing(4900.0, (1, 1));
+    let thickness = top.subtract(&base, &NullPolicy::new())
+      .expect("subtract coregistered grids");
+
+    assert!(z(&thickness, (0, 0)).is_nan());
+    assert!(z(&thickness, (1, 1)).is_nan());
+    assert_eq!(z(&thickness, (2, 2)), 100.0);
+    assert_eq!((thickness.zmin, thickness.zmax), (100.0, 100.0));
+
+    for combined in [
+        top.add(&base, &NullPolicy::new()),
+        top.multiply(&base, &NullPolicy::new()),
+        top.divide(&base, &NullPolicy::new()),
+    ] {
+        let combined = combined.expect("combine coregistered grids");
+        assert!(z(&combined, (0, 0)).is_nan());
+        assert!(z(&combined, (1, 1)).is_nan());
+    }
+}
+
+#[test]
+fn ignoring_missing_nodes_still_leaves_them_missing() {
+    let top = flat_missing(5000.0, (0, 0));
+    let base = flat_missing(4900.0, (1, 1));
+    let policy = NullPolicy::new().arithmetic(NullArithmetic::Ignore);
+    let thickness = top.subtract(&base, &policy)
+      .expect("subtract coregistered grids");
+    assert!(z(&thickness, (0, 0)).is_nan());
+    assert!(z(&thickness, (1, 1)).is_nan());
+    assert_eq!(thickness.zmax, 100.0);
+}
+
+#[test]
+fn missing_nodes_can_be_filled() {
+    let top = flat_missing(5000.0, (0, 0));
+    let base = flat_missing(4900.0, (1, 1));
+    let policy = NullPolicy::new().arithmetic(NullArithmetic::Fill(0.0));
+    let thickness = top.subtract(&base, &policy)
+      .expect("subtract coregistered grids");
+    assert_eq!(z(&thickness, (0, 0)), -4900.0);
+    assert_eq!(z(&thickness, (1, 1)), 5000.0);
+    assert_eq!(z(&thickness, (2, 2)), 100.0);
+}
+
+#[test]
+fn products_of_powers_are_not_mangled() {
+    let area = Quantity::new("area", "m2");
+    assert_eq!(area.product(&area).units, "m2.m2");
+    let length = Quantity::new("length", "m");
+    assert_eq!(length.product(&length).units, "m2");
+    assert_eq!(area.product(&length).units, "m2.m");
+}
+
+#[test]
+fn quantities_from_units_use_udunits_names() {
+    let grid = flat(1.0);
+    let quantity = grid.z_quantity().expect("known z units");
+    assert_eq!(Some(quantity.units.as_str()), grid.zunits.udunits());
+    let squared = grid.multiply(&grid, &NullPolicy::new())
+      .expect("multiply coregistered grids");
+    assert_eq!(squared.z_quantity().map(|q| q.units),
+      grid.zunits.udunits().map(|u| format!("{}2", u)));
+}
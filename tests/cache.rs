@@ -0,0 +1,105 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! malformed grid caches
+
+use petra_grid::{
+    Error,
+    Grid,
+    testing::{Lattice, Surface},
+};
+
+fn assert_rejected(bytes: &[u8]) {
+    match Grid::from_cache_bytes(bytes) {
+        Err(Error::InvalidInput(_)) | Err(Error::IOError(_)) => {},
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("malformed cache accepted"),
+    }
+}
+
+fn cache() -> Vec<u8> {
+    let lattice = Lattice { rows: 3, columns: 4, ..Lattice::default() };
+    Surface::Plane { z0: 1.0, dzdx: 0.5, dzdy: 0.25 }
+      .rectangular(&lattice)
+      .to_cache_bytes()
+}
+
+#[test]
+fn round_trips() {
+    let bytes = cache();
+    let grid = Grid::from_cache_bytes(&bytes).expect("read cache");
+    assert_eq!(grid.to_cache_bytes(), bytes);
+}
+
+#[test]
+fn truncated_caches_are_rejected() {
+    let bytes = cache();
+    for len in 0..bytes.len() {
+        assert_rejected(&bytes[..len]);
+    }
+}
+
+#[test]
+fn corrupted_caches_never_panic() {
+    let bytes = cache();
+    // a simple deterministic xorshift generator stands in for a fuzzer
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..2000 {
+        let mut corrupt = bytes.clone();
+        for _ in 0..1 + next() % 4 {
+            let at = next() as usize % corrupt.len();
+            corrupt[at] = next() as u8;
+        }
+        // the result doesn't matter, so long as there's no panic
+        let _ = Grid::from_cache_bytes(&corrupt);
+    }
+
+    for len in [0, 8, 12, 64, 512] {
+        let mut garbage = b"PGRDCACH\x04\0\0\0".to_vec();
+        garbage.extend((0..len).map(|_| next() as u8));
+        let _ = Grid::from_cache_bytes(&garbage);
+    }
+}
+
+#[test]
+fn out_of_range_lengths_and_dates_are_rejected() {
+    let mut bytes = cache();
+    // the name's length, just after the magic and two versions
+    bytes[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_rejected(&bytes);
+
+    let mut bytes = cache();
+    let name_len = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+    /* after the name: size, rows, columns, triangles, eight f64s, and two
+     * units, then the date's day and nanoseconds */
+    let nanos = 20 + name_len as usize + 16 + 64 + 8 + 4;
+    bytes[nanos..nanos + 8].copy_from_slice(&i64::MAX.to_le_bytes());
+    let day = nanos - 4;
+    bytes[day..nanos].copy_from_slice(&5_373_484i32.to_le_bytes());
+    assert_rejected(&bytes);
+}
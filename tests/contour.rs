@@ -0,0 +1,87 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! contour levels and tracing
+
+use petra_grid::{
+    Error,
+    export::exporter,
+    testing::{Lattice, Surface},
+};
+
+#[test]
+fn levels_are_multiples_of_the_interval() {
+    let plane = Surface::Plane { z0: 0.0, dzdx: 0.01, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    let levels = plane.contour_levels(5.0).expect("a few levels");
+    assert!(!levels.is_empty());
+    assert!(levels.iter().all(|l| l % 5.0 == 0.0));
+    assert!(levels.windows(2).all(|w| w[0] < w[1]));
+    assert!(levels[0] >= plane.zmin && levels[levels.len() - 1] <= plane.zmax);
+}
+
+#[test]
+fn bad_intervals_give_no_levels() {
+    let plane = Surface::Plane { z0: 0.0, dzdx: 0.01, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    for interval in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert_eq!(plane.contour_levels(interval).ok(), Some(Vec::new()));
+    }
+}
+
+#[test]
+fn tiny_intervals_are_rejected() {
+    let plane = Surface::Plane { z0: 0.0, dzdx: 0.01, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    assert!(matches!(plane.contour_levels(1e-12),
+      Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+fn default_intervals_are_round_and_give_about_ten_levels() {
+    let plane = Surface::Plane { z0: 0.0, dzdx: 0.01, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    assert_eq!(plane.contour_interval(), Some(2.0));
+    let flat = Surface::Plane { z0: 5.0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    assert_eq!(flat.contour_interval(), None);
+}
+
+#[test]
+fn dxf_contours_are_r12_polylines() {
+    let plane = Surface::Plane { z0: 0.0, dzdx: 0.01, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    let mut buf = Vec::new();
+    exporter("dxf-contours").expect("a known format")
+      .export(&plane, &mut buf).expect("write contours");
+    let dxf = String::from_utf8(buf).expect("ASCII DXF");
+    let lines = dxf.lines().map(str::trim).collect::<Vec<_>>();
+
+    assert!(lines.windows(4).any(|w| w == ["9", "$ACADVER", "1", "AC1009"]));
+    assert!(!lines.contains(&"LWPOLYLINE"));
+    let count = |entity| lines.iter().filter(|&&l| l == entity).count();
+    let contours = plane.contours(&plane.contour_levels(2.0).unwrap());
+    assert!(!contours.is_empty());
+    assert_eq!(count("POLYLINE"), contours.len());
+    assert_eq!(count("SEQEND"), contours.len());
+    assert_eq!(count("VERTEX"),
+      contours.iter().map(|c| c.points.len()).sum::<usize>());
+}
@@ -0,0 +1,76 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+
+//! NADCON grid-shift files
+
+use std::io::Cursor;
+
+use petra_grid::{Error, datum::GridShift};
+
+/* a NADCON file of uniform shifts, declaring the given shape but holding
+ * only `rows_present` rows */
+fn nadcon(columns: i32, rows: i32, rows_present: usize, shift: f32)
+  -> Vec<u8> {
+    let mut bytes = vec![b' '; 64];
+    for v in [columns, rows, 1] {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    for v in [-100.0f32, 0.25, 30.0, 0.25, 0.0] {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    let record_len = (columns.max(0) as usize + 1) * 4;
+    bytes.resize(record_len.max(bytes.len()), 0);
+    for _ in 0..rows_present {
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for _ in 0..columns {
+            bytes.extend_from_slice(&shift.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+#[test]
+fn uniform_shifts_apply_everywhere_inside() {
+    let shift = GridShift::read_nadcon(
+      &mut Cursor::new(nadcon(23, 3, 3, 3.6)),
+      &mut Cursor::new(nadcon(23, 3, 3, -7.2)))
+      .expect("read NADCON grids");
+    let (lon, lat) = shift.forward(-99.0, 30.25).expect("inside the grid");
+    assert!((lon - -98.998).abs() < 1e-9);
+    assert!((lat - 30.251).abs() < 1e-9);
+    let (lon, lat) = shift.inverse(lon, lat).expect("inside the grid");
+    assert!((lon - -99.0).abs() < 1e-9 && (lat - 30.25).abs() < 1e-9);
+    assert_eq!(shift.forward(-101.0, 30.25), None);
+}
+
+#[test]
+fn shapes_larger_than_the_file_are_rejected() {
+    for (columns, rows, present) in [(23, 3, 2), (i32::MAX, i32::MAX, 0),
+      (23, i32::MAX, 1)] {
+        let mut las = nadcon(columns.min(23), rows, present, 0.0);
+        las[64..68].copy_from_slice(&columns.to_le_bytes());
+        let result = GridShift::read_nadcon(&mut Cursor::new(las),
+          &mut Cursor::new(nadcon(23, 3, 3, 0.0)));
+        assert!(matches!(result, Err(Error::InvalidInput(_))),
+          "{} x {} accepted", rows, columns);
+    }
+}
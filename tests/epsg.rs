@@ -0,0 +1,58 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! resolving Petra projection names to EPSG codes
+
+use petra_grid::{UnitOfMeasure, epsg::{coordinate_system, lookup}};
+
+#[test]
+fn texas_central() {
+    assert_eq!(lookup("TX-27C", "", UnitOfMeasure::Feet), Some(32039));
+    assert_eq!(lookup("TX-83C", "", UnitOfMeasure::Meters), Some(32139));
+    assert_eq!(lookup("TX-83C", "", UnitOfMeasure::Feet), Some(2277));
+    assert_eq!(lookup("TX-C", "NAD 1983", UnitOfMeasure::Feet), Some(2277));
+}
+
+#[test]
+fn wyoming_nad83_meters() {
+    let codes = ["E", "EC", "WC", "W"].map(|zone| lookup(
+      &format!("WY-83{}", zone), "", UnitOfMeasure::Meters));
+    assert_eq!(codes, [Some(32155), Some(32156), Some(32157), Some(32158)]);
+}
+
+#[test]
+fn unknown_zones() {
+    assert_eq!(lookup("XX-27C", "", UnitOfMeasure::Feet), None);
+    assert_eq!(lookup("TX-C", "WGS84", UnitOfMeasure::Feet), None);
+    assert_eq!(lookup("KY-83N", "", UnitOfMeasure::Meters), None);
+}
+
+#[test]
+fn coordinate_systems_invert_lookup() {
+    for code in [32039, 32139, 2277, 26743, 32155] {
+        let (projection, datum, units) = coordinate_system(code)
+          .expect("known code");
+        assert_eq!(lookup(&projection, datum, units), Some(code));
+    }
+    assert_eq!(coordinate_system(32139),
+      Some(("TX-83C".to_string(), "NAD83", UnitOfMeasure::Meters)));
+    assert_eq!(coordinate_system(4326), None);
+}
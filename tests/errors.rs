@@ -0,0 +1,96 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! error kinds and categories
+
+use std::io::{self, Cursor};
+
+use petra_grid::{
+    Error,
+    ErrorCategory,
+    Grid,
+    NullPolicy,
+    testing::{Lattice, Surface},
+};
+
+fn io_error(kind: io::ErrorKind) -> Error {
+    Error::IOError(io::Error::new(kind, "test"))
+}
+
+#[test]
+fn io_errors_are_categorized_by_kind() {
+    assert_eq!(io_error(io::ErrorKind::TimedOut).category(),
+      ErrorCategory::Io);
+    assert_eq!(io_error(io::ErrorKind::Interrupted).category(),
+      ErrorCategory::Io);
+    assert_eq!(io_error(io::ErrorKind::NotFound).category(),
+      ErrorCategory::Environment);
+    assert_eq!(io_error(io::ErrorKind::PermissionDenied).category(),
+      ErrorCategory::Environment);
+    assert_eq!(io_error(io::ErrorKind::AlreadyExists).category(),
+      ErrorCategory::Environment);
+    assert_eq!(io_error(io::ErrorKind::UnexpectedEof).category(),
+      ErrorCategory::FormatViolation);
+    assert_eq!(io_error(io::ErrorKind::InvalidInput).category(),
+      ErrorCategory::InvalidArgument);
+    assert_eq!(Error::IOError(io::Error::other("from a library")).category(),
+      ErrorCategory::Other);
+}
+
+#[test]
+fn missing_files_are_not_worth_retrying() {
+    let e = Error::from(std::fs::File::open("/nonexistent/grid.grd")
+      .expect_err("no such file"));
+    assert_eq!(e.category(), ErrorCategory::Environment);
+    assert_eq!(e.kind(), "io");
+}
+
+#[test]
+fn truncated_files_are_format_violations() {
+    let e = Grid::read(&mut Cursor::new(vec![0u8; 16]))
+      .expect_err("too short for a header");
+    assert_eq!(e.kind(), "truncated");
+    assert_eq!(e.category(), ErrorCategory::FormatViolation);
+}
+
+#[test]
+fn caller_mistakes_are_invalid_arguments() {
+    let a = Surface::Plane { z0: 1.0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+    let b = Surface::Plane { z0: 1.0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&Lattice { columns: 7, ..Lattice::default() });
+    let e = a.add(&b, &NullPolicy::default())
+      .expect_err("different lattices");
+    assert!(matches!(e, Error::InvalidArgument(_)));
+    assert_eq!(e.kind(), "invalid_argument");
+    assert_eq!(e.category(), ErrorCategory::InvalidArgument);
+
+    let e = Grid::mosaic(&[]).expect_err("nothing to mosaic");
+    assert_eq!(e.category(), ErrorCategory::InvalidArgument);
+}
+
+#[test]
+fn malformed_foreign_files_are_format_violations() {
+    let e = petra_grid::import::zmap::read_zmap(&mut Cursor::new(b"junk\n"))
+      .expect_err("not a ZMAP+ grid");
+    assert!(matches!(e, Error::InvalidInput(_)));
+    assert_eq!(e.category(), ErrorCategory::FormatViolation);
+}
@@ -0,0 +1,54 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! GeoTIFF export
+
+#![cfg(feature = "geotiff")]
+
+use std::io;
+
+use petra_grid::{
+    Error,
+    export::geotiff::{write_geotiff, write_geotiff_with_layers},
+    testing::{Lattice, Surface},
+};
+
+#[test]
+fn writes_a_classic_tiff() {
+    let grid = Surface::Plane { z0: 1.0, dzdx: 0.5, dzdy: 0.25 }
+      .rectangular(&Lattice::default());
+    let mut tiff = Vec::new();
+    write_geotiff(&grid, &mut tiff).expect("write GeoTIFF");
+    assert_eq!(&tiff[..4], b"II*\0");
+    let pixels = grid.rows as usize * grid.columns as usize;
+    assert!(tiff.len() > 8 + 8 * pixels);
+}
+
+#[test]
+fn rasters_too_large_for_32_bit_offsets_are_rejected() {
+    let lattice = Lattice { columns: 1000, rows: 1000, ..Lattice::default() };
+    let grid = Surface::Plane { z0: 1.0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&lattice);
+    // 540 layers of a million doubles each: just over 4 GiB in all
+    let layers = vec![("copy", &grid); 540];
+    let result = write_geotiff_with_layers(&grid, &layers, &mut io::sink());
+    assert!(matches!(result, Err(Error::InvalidArgument(_))));
+}
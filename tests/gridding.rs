@@ -0,0 +1,54 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+
+//! gridding and resampling
+
+use petra_grid::{
+    gridding::Interpolation,
+    testing::{Lattice, Surface},
+};
+
+#[test]
+fn natural_neighbor_resampling_keeps_planes_and_metadata() {
+    let surface = Surface::Plane { z0: 100.0, dzdx: 0.01, dzdy: -0.02 };
+    let mut grid = surface.rectangular(&Lattice::default());
+    grid.projection = "TX-27C".to_string();
+    grid.datum = "NAD27".to_string();
+
+    // a finer lattice, reaching right up to the hull of the original nodes
+    let lattice = Lattice { xstep: 50.0, ystep: 50.0, rows: 39, columns: 59,
+      ..Lattice::default() };
+    let resampled = grid.resample(&lattice, Interpolation::NaturalNeighbor);
+    assert_eq!((resampled.rows, resampled.columns), (39, 59));
+    assert_eq!((resampled.xmax, resampled.ymax), (3900.0, 3900.0));
+    assert_eq!(resampled.name, grid.name);
+    assert_eq!((resampled.projection.as_str(), resampled.datum.as_str()),
+      ("TX-27C", "NAD27"));
+    assert_eq!(resampled.created_date, grid.created_date);
+
+    let z = resampled.rectangular().unwrap();
+    for ((j, i), &z) in z.indexed_iter() {
+        let (x, y) = lattice.node_xy(j, i);
+        assert!((z - surface.z(x, y)).abs() < 1e-4,
+          "{} at ({}, {}), expected {}", z, x, y, surface.z(x, y));
+    }
+}
@@ -0,0 +1,85 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! importers given headers which claim more data than the files hold
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use petra_grid::{
+    Error,
+    import::read_bytes,
+};
+
+fn assert_invalid_input(bytes: &[u8]) {
+    match read_bytes(bytes) {
+        Err(Error::InvalidInput(_)) => {},
+        Err(e) => panic!("expected invalid input, got {}", e),
+        Ok(_) => panic!("expected invalid input, got a grid"),
+    }
+}
+
+#[test]
+fn surfer_ascii_header_larger_than_file() {
+    assert_invalid_input(
+      b"DSAA\n4000000000 4000000000\n0 1\n0 1\n0 1\n1 2 3\n");
+    assert_invalid_input(b"DSAA\n3 2\n0 1\n0 1\n0 1\n1 2 3 4 5\n");
+}
+
+#[test]
+fn surfer6_header_larger_than_file() {
+    let mut bytes = b"DSBB".to_vec();
+    bytes.write_i16::<LittleEndian>(i16::MAX).unwrap();
+    bytes.write_i16::<LittleEndian>(i16::MAX).unwrap();
+    for _ in 0..6 {
+        bytes.write_f64::<LittleEndian>(1.0).unwrap();
+    }
+    bytes.write_f32::<LittleEndian>(1.0).unwrap();
+    assert_invalid_input(&bytes);
+}
+
+#[test]
+fn surfer7_header_larger_than_file() {
+    let mut bytes = b"DSRB".to_vec();
+    bytes.write_i32::<LittleEndian>(4).unwrap();
+    bytes.write_i32::<LittleEndian>(2).unwrap();
+    bytes.extend_from_slice(b"GRID");
+    bytes.write_i32::<LittleEndian>(72).unwrap();
+    bytes.write_i32::<LittleEndian>(i32::MAX).unwrap();
+    bytes.write_i32::<LittleEndian>(i32::MAX).unwrap();
+    for v in [0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.70141e38] {
+        bytes.write_f64::<LittleEndian>(v).unwrap();
+    }
+    bytes.extend_from_slice(b"DATA");
+    bytes.write_i32::<LittleEndian>(8).unwrap();
+    bytes.write_f64::<LittleEndian>(1.0).unwrap();
+    assert_invalid_input(&bytes);
+}
+
+#[test]
+fn zmap_header_larger_than_file() {
+    let text = "@grid, GRID, 5\n\
+      15, 1e30, , 7, 1\n\
+      18446744073709551615, 18446744073709551615, 0, 1, 0, 1\n\
+      0.0, 0.0, 0.0\n\
+      @\n\
+      1.0 2.0\n";
+    assert_invalid_input(text.as_bytes());
+}
@@ -0,0 +1,59 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! the header consistency predicates
+
+use petra_grid::invariants::{
+    SPEC_REL_TOLERANCE,
+    size_coherent,
+    spec_coherent,
+    spec_rel_error,
+};
+
+#[test]
+fn coherent_specs() {
+    assert!(spec_coherent(0.0, 1000.0, 100.0, 11));
+    assert!(spec_coherent(-2000.0, -1000.0, 100.0, 11));
+    assert!(spec_coherent(-500.0, 500.0, 100.0, 11));
+    assert!(spec_coherent(-1000.0, 0.0, 100.0, 11));
+}
+
+#[test]
+fn incoherent_specs() {
+    assert!(!spec_coherent(0.0, 1000.0, 90.0, 11));
+    assert!(!spec_coherent(-1000.0, -10.0, 1.0, 3));
+    assert!(!spec_coherent(-1000.0, 0.0, 90.0, 11));
+}
+
+#[test]
+fn relative_errors_are_never_negative() {
+    let err = spec_rel_error(-1000.0, -10.0, 1.0, 3);
+    assert!(err > SPEC_REL_TOLERANCE);
+    assert!(spec_rel_error(-1000.0, 0.0, 90.0, 11).is_infinite());
+    assert!(spec_rel_error(-1000.0, 0.0, 100.0, 11).is_nan());
+}
+
+#[test]
+fn sizes() {
+    assert!(size_coherent(110, 10, 11));
+    assert!(!size_coherent(100, 10, 11));
+    assert!(!size_coherent(0, u32::MAX, 2));
+}
@@ -0,0 +1,69 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! missing values in arithmetic and statistics
+
+use petra_grid::{NullArithmetic, NullPolicy, PETRA_NULL};
+
+const MISSING: f64 = f64::NAN;
+
+#[test]
+fn combining_propagates_missing_values_by_default() {
+    let policy = NullPolicy::new();
+    assert_eq!(policy.combine(5000.0, 4900.0, |a, b| a - b), 100.0);
+    assert!(policy.combine(5000.0, MISSING, |a, b| a - b).is_nan());
+    assert!(policy.combine(MISSING, 4900.0, |a, b| a - b).is_nan());
+    assert!(policy.combine(PETRA_NULL, 4900.0, |a, b| a - b).is_nan());
+}
+
+#[test]
+fn ignoring_missing_values_never_substitutes_an_operand() {
+    let policy = NullPolicy::new().arithmetic(NullArithmetic::Ignore);
+    assert!(policy.combine(5000.0, MISSING, |a, b| a - b).is_nan());
+    assert!(policy.combine(MISSING, 4900.0, |a, b| a - b).is_nan());
+}
+
+#[test]
+fn filling_missing_values_uses_the_fill_value() {
+    let policy = NullPolicy::new().arithmetic(NullArithmetic::Fill(0.0));
+    assert_eq!(policy.combine(5000.0, MISSING, |a, b| a - b), 5000.0);
+    assert_eq!(policy.combine(MISSING, 4900.0, |a, b| a - b), -4900.0);
+    assert!(policy.combine(MISSING, MISSING, |a, b| a - b).is_nan());
+}
+
+#[test]
+fn statistics_follow_the_policy() {
+    let values = [1.0, 2.0, MISSING, 3.0, PETRA_NULL];
+
+    let stats = NullPolicy::new().stats(values);
+    assert_eq!(stats.null_count, 2);
+    assert!(stats.mean.is_nan() && stats.min.is_nan());
+
+    let stats = NullPolicy::new().arithmetic(NullArithmetic::Ignore)
+      .stats(values);
+    assert_eq!(stats.null_count, 2);
+    assert_eq!((stats.min, stats.max, stats.mean), (1.0, 3.0, 2.0));
+
+    let stats = NullPolicy::new().arithmetic(NullArithmetic::Fill(0.0))
+      .stats(values);
+    assert_eq!(stats.null_count, 0);
+    assert_eq!((stats.min, stats.max, stats.mean), (0.0, 3.0, 1.2));
+}
@@ -0,0 +1,56 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! fanning grids out to several formats at once
+
+use std::{collections::HashSet, fs};
+
+use petra_grid::{
+    Error,
+    export::{FORMATS, Pipeline, exporter, xyz::Csv},
+    testing::{Lattice, Surface},
+};
+
+#[test]
+fn every_format_has_its_own_extension() {
+    let mut seen = HashSet::new();
+    for name in FORMATS {
+        let exporter = exporter(name).expect("a known format");
+        assert!(seen.insert(exporter.extension().to_ascii_lowercase()),
+          "{} shares the extension {}", name, exporter.extension());
+    }
+}
+
+#[test]
+fn duplicate_outputs_are_rejected() {
+    let dir = std::env::temp_dir()
+      .join(format!("petra_grid-pipeline-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create scratch directory");
+    let grid = Surface::Plane { z0: 1.0, dzdx: 0.0, dzdy: 0.0 }
+      .rectangular(&Lattice::default());
+
+    let results = Pipeline::new().with(Csv).with(Csv)
+      .run(&grid, dir.join("plane.grd"));
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, results[1].0);
+    assert!(results[0].1.is_ok());
+    assert!(matches!(results[1].1, Err(Error::InvalidArgument(_))));
+}
@@ -0,0 +1,145 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! reading polygon layers from Shapefiles, including malformed ones
+
+use std::{fs, path::PathBuf};
+
+use petra_grid::{Error, import::polygons::read_polygons_file};
+
+// a scratch file under the system temporary directory
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+      .join(format!("petra_grid-polygons-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create scratch directory");
+    dir.join(name)
+}
+
+// a Shapefile holding one polygon record with the given (possibly bogus)
+// record length in 16-bit words, part count, and point count, followed by
+// the given parts and points
+fn shapefile(record_words: i32, n_parts: i32, n_points: i32, parts: &[i32],
+  points: &[(f64, f64)]) -> Vec<u8> {
+    let mut content = Vec::new();
+    content.extend(5i32.to_le_bytes());
+    content.extend([0u8; 32]);
+    content.extend(n_parts.to_le_bytes());
+    content.extend(n_points.to_le_bytes());
+    for p in parts {
+        content.extend(p.to_le_bytes());
+    }
+    for (x, y) in points {
+        content.extend(x.to_le_bytes());
+        content.extend(y.to_le_bytes());
+    }
+
+    let mut bytes = vec![0u8; 100];
+    bytes[..4].copy_from_slice(&9994i32.to_be_bytes());
+    let file_words = ((100 + 8 + content.len()) / 2) as i32;
+    bytes[24..28].copy_from_slice(&file_words.to_be_bytes());
+    bytes[28..32].copy_from_slice(&1000i32.to_le_bytes());
+    bytes[32..36].copy_from_slice(&5i32.to_le_bytes());
+    bytes.extend(1i32.to_be_bytes());
+    bytes.extend(record_words.to_be_bytes());
+    bytes.extend(content);
+    bytes
+}
+
+fn square() -> Vec<(f64, f64)> {
+    vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)]
+}
+
+fn read_shp(name: &str, bytes: &[u8]) -> Result<usize, Error> {
+    let path = scratch(name);
+    fs::write(&path, bytes).expect("write scratch Shapefile");
+    read_polygons_file(&path).map(|polygons| polygons.len())
+}
+
+#[test]
+fn reads_a_well_formed_polygon() {
+    let points = square();
+    let words = (44 + 4 + 16 * points.len()) as i32 / 2;
+    let bytes = shapefile(words, 1, points.len() as i32, &[0], &points);
+    let path = scratch("square.shp");
+    fs::write(&path, bytes).expect("write scratch Shapefile");
+    let polygons = read_polygons_file(&path).expect("read Shapefile");
+    assert_eq!(polygons.len(), 1);
+    assert!(polygons[0].contains(5.0, 5.0));
+    assert!(!polygons[0].contains(15.0, 5.0));
+}
+
+#[test]
+fn rejects_negative_record_lengths() {
+    let points = square();
+    let bytes = shapefile(-4, 1, points.len() as i32, &[0], &points);
+    assert!(matches!(read_shp("negative-record.shp", &bytes),
+      Err(Error::InvalidInput(_))));
+}
+
+#[test]
+fn rejects_empty_records() {
+    let bytes = shapefile(0, 0, 0, &[], &[]);
+    assert!(matches!(read_shp("empty-record.shp", &bytes),
+      Err(Error::InvalidInput(_))));
+}
+
+#[test]
+fn rejects_bogus_counts() {
+    let points = square();
+    let words = (44 + 4 + 16 * points.len()) as i32 / 2;
+    for (name, n_parts, n_points) in [
+        ("negative-parts.shp", -1, points.len() as i32),
+        ("negative-points.shp", 1, -1),
+        ("huge-parts.shp", i32::MAX, points.len() as i32),
+        ("huge-points.shp", 1, i32::MAX),
+    ] {
+        let bytes = shapefile(words, n_parts, n_points, &[0], &points);
+        assert!(matches!(read_shp(name, &bytes), Err(Error::InvalidInput(_))),
+          "{}", name);
+    }
+}
+
+#[test]
+fn rejects_bogus_dbf_record_counts() {
+    let points = square();
+    let words = (44 + 4 + 16 * points.len()) as i32 / 2;
+    let shp = shapefile(words, 1, points.len() as i32, &[0], &points);
+    let path = scratch("huge-dbf.shp");
+    fs::write(&path, shp).expect("write scratch Shapefile");
+
+    // a one-field table claiming four billion records
+    let mut dbf = vec![0u8; 32];
+    dbf[0] = 3;
+    dbf[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    dbf[8..10].copy_from_slice(&65u16.to_le_bytes());
+    dbf[10..12].copy_from_slice(&11u16.to_le_bytes());
+    let mut field = [0u8; 32];
+    field[..4].copy_from_slice(b"NAME");
+    field[11] = b'C';
+    field[16] = 10;
+    dbf.extend(field);
+    dbf.push(0x0d);
+    dbf.extend(b" SECTION 12");
+    fs::write(path.with_extension("dbf"), dbf).expect("write scratch table");
+
+    assert!(matches!(read_polygons_file(&path),
+      Err(Error::InvalidInput(_))));
+}
@@ -0,0 +1,80 @@
+/* Copyright (c) 2023 Derrick W. Turk / terminus, LLC
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! coordinate transformations of grids
+
+use petra_grid::testing::{Lattice, Surface};
+
+fn shift(x: f64, y: f64) -> Option<(f64, f64)> {
+    Some((x * 0.3048 + 1000.0, y * 0.3048 - 500.0))
+}
+
+fn unshift(x: f64, y: f64) -> Option<(f64, f64)> {
+    Some(((x - 1000.0) / 0.3048, (y + 500.0) / 0.3048))
+}
+
+#[test]
+fn batched_transforms_match_pointwise_ones() {
+    let plane = Surface::Plane { z0: 10.0, dzdx: 0.5, dzdy: -0.25 };
+    for grid in [plane.rectangular(&Lattice::default()),
+      plane.triangular(&Lattice::default())] {
+        let pointwise = grid.transform_xy(shift, unshift);
+        let mut batches = 0;
+        let batched = grid.transform_xy_batched(
+          |points| {
+              batches += 1;
+              Ok::<_, ()>(points.iter().map(|&(x, y)| shift(x, y)).collect())
+          },
+          |points| Ok(points.iter().map(|&(x, y)| unshift(x, y)).collect()))
+          .expect("transform");
+        assert!(batches <= 2);
+        assert_eq!(batched.to_cache_bytes(), pointwise.to_cache_bytes());
+        assert!((batched.xmin - 1000.0 - grid.xmin * 0.3048).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn batched_transform_errors_are_returned() {
+    let grid = Surface::Plane { z0: 10.0, dzdx: 0.5, dzdy: -0.25 }
+      .rectangular(&Lattice::default());
+    let result = grid.transform_xy_batched(
+      |_| Err("no projection"),
+      |points| Ok(vec![None; points.len()]));
+    assert_eq!(result.err(), Some("no projection"));
+}
+
+#[test]
+fn rotated_footprints_keep_every_corner() {
+    let grid = Surface::Plane { z0: 10.0, dzdx: 0.5, dzdy: -0.25 }
+      .rectangular(&Lattice::default());
+    // a quarter turn about the origin
+    let rotated = grid.transform_xy(|x, y| Some((-y, x)), |x, y| Some((y, -x)));
+    assert_eq!((rotated.xmin, rotated.xmax), (-grid.ymax, -grid.ymin));
+    assert_eq!((rotated.ymin, rotated.ymax), (grid.xmin, grid.xmax));
+}
+
+#[test]
+fn unplaceable_grids_are_missing_everywhere() {
+    let grid = Surface::Plane { z0: 10.0, dzdx: 0.5, dzdy: -0.25 }
+      .rectangular(&Lattice::default());
+    let lost = grid.transform_xy(|_, _| None, unshift);
+    assert!(lost.rectangular().unwrap().iter().all(|z| z.is_nan()));
+}